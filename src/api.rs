@@ -0,0 +1,266 @@
+use std::time::SystemTime;
+
+use crate::{auth::AuthSession, cookies::PinterestCookie, PinterestCookies};
+
+/// The value of the `x-requested-with` header, the API refuses the requests without it
+const REQUESTED_WITH: &str = "XMLHttpRequest";
+
+/// Why the headers of the API requests can't be built
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ApiHeadersError {
+    /// There's no `csrftoken` cookie, the API refuses the requests without the `x-csrftoken` header.
+    /// The cookies probably aren't the ones of a complete login
+    #[error(
+        "The cookies have no `csrftoken` cookie, the API needs it in the `x-csrftoken` header"
+    )]
+    MissingCsrfToken,
+    /// A cookie or the user agent has a character that would break its header, like a line break
+    #[error("The `{header}` header can't hold {value}, it has an invalid character")]
+    InvalidHeaderValue {
+        /// The name of the header
+        header: &'static str,
+        /// What has the invalid character: `the cookie "name"`, or `the user agent`
+        value: String,
+    },
+    /// The url of [`cookie_header_for_url`] can't be parsed, or has no host
+    #[error("The url `{0}` isn't an absolute url with a host")]
+    InvalidUrl(String),
+}
+
+/// Builds the headers of the requests to the internal API of pinterest for a logged in session: `Cookie`,
+/// `x-csrftoken`, `x-requested-with`, and `User-Agent` when it's known, in this order.
+///
+/// The user agent should be the one of the browser that logged in, see
+/// [`Session::user_agent`](crate::session::Session::user_agent). The cookies are sent as they are, in the format of
+/// [`AuthSession::cookie_header`]: pinterest already encodes their values, so a cookie that would break the header is
+/// refused rather than encoded.
+///
+/// # Errors
+/// * [`ApiHeadersError::MissingCsrfToken`] - If there's no `csrftoken` cookie
+/// * [`ApiHeadersError::InvalidHeaderValue`] - If a cookie has a control character, a `;`, or a name with a `=` or a
+///   whitespace, or if the user agent has a control character
+///
+/// # Example
+/// ```
+/// use pinterest_login::api::build_api_headers;
+///
+/// let cookies = [("_pinterest_sess", "session"), ("csrftoken", "token")]
+///     .into_iter()
+///     .map(|(name, value)| (name.to_string(), value.to_string()))
+///     .collect();
+///
+/// let headers = build_api_headers(&cookies, Some("Mozilla/5.0"))?;
+/// assert_eq!(
+///     headers,
+///     [
+///         ("Cookie".to_string(), "_pinterest_sess=session; csrftoken=token".to_string()),
+///         ("x-csrftoken".to_string(), "token".to_string()),
+///         ("x-requested-with".to_string(), "XMLHttpRequest".to_string()),
+///         ("User-Agent".to_string(), "Mozilla/5.0".to_string()),
+///     ]
+/// );
+/// # Ok::<(), pinterest_login::api::ApiHeadersError>(())
+/// ```
+pub fn build_api_headers(
+    cookies: &PinterestCookies,
+    user_agent: Option<&str>,
+) -> Result<Vec<(String, String)>, ApiHeadersError> {
+    let cookie_header = cookie_header(cookies)?;
+    if user_agent.is_some_and(has_control_char) {
+        return Err(ApiHeadersError::InvalidHeaderValue {
+            header: "User-Agent",
+            value: "the user agent".to_string(),
+        });
+    }
+
+    let auth = AuthSession::from_cookies(cookies);
+    let csrf_token = auth.csrf_token.ok_or(ApiHeadersError::MissingCsrfToken)?;
+    let mut headers = vec![
+        ("Cookie".to_string(), cookie_header),
+        ("x-csrftoken".to_string(), csrf_token),
+        ("x-requested-with".to_string(), REQUESTED_WITH.to_string()),
+    ];
+    if let Some(user_agent) = user_agent {
+        headers.push(("User-Agent".to_string(), user_agent.to_string()));
+    }
+    Ok(headers)
+}
+
+/// Builds the value of the `Cookie` header of the cookies, for a raw HTTP client: the `name=value` pairs sorted by
+/// name and separated by `; `, like [`AuthSession::cookie_header`].
+///
+/// The values are sent as they are, since pinterest already encodes them and expects them back the same way:
+/// * the spaces, the `,`, the `"` and the non-ASCII characters are kept, the HTTP clients send the non-ASCII ones
+///   as UTF-8 bytes
+/// * a `;` would split the cookie in two, and a control character like a line break would end the header, so a value
+///   with one is refused rather than encoded
+///
+/// # Errors
+/// * [`ApiHeadersError::InvalidHeaderValue`] - If a cookie has a control character, a `;`, or a name with a `=` or a
+///   whitespace
+///
+/// # Example
+/// ```
+/// use pinterest_login::api::cookie_header;
+///
+/// let cookies = [("csrftoken", "3f2a%3D"), ("_b", "\"AXr0 k2w7\"")]
+///     .into_iter()
+///     .map(|(name, value)| (name.to_string(), value.to_string()))
+///     .collect();
+///
+/// assert_eq!(cookie_header(&cookies)?, r#"_b="AXr0 k2w7"; csrftoken=3f2a%3D"#);
+/// # Ok::<(), pinterest_login::api::ApiHeadersError>(())
+/// ```
+pub fn cookie_header(cookies: &PinterestCookies) -> Result<String, ApiHeadersError> {
+    check_cookies(cookies.iter())?;
+    Ok(AuthSession::from_cookies(cookies).cookie_header)
+}
+
+/// Same as [`cookie_header`], but only with the cookies that a browser would send to the url, of the ones of
+/// [`login_full`](crate::login_full):
+/// * their domain is the host of the url, or one of its parents when it starts with a `.`
+/// * their path is the one of the url, or one of its parents
+/// * they're not secure, or the url is `https`
+/// * they haven't expired
+///
+/// They're sorted by the longest path first, like the browsers do, then by name.
+///
+/// # Errors
+/// * [`ApiHeadersError::InvalidUrl`] - If the url can't be parsed or has no host
+/// * [`ApiHeadersError::InvalidHeaderValue`] - If one of the sent cookies can't be in the header, see
+///   [`cookie_header`]
+///
+/// # Example
+/// ```
+/// use pinterest_login::api::cookie_header_for_url;
+/// use pinterest_login::cookies::PinterestCookie;
+///
+/// let cookies = [
+///     PinterestCookie::new("csrftoken", "token", ".pinterest.com"),
+///     PinterestCookie::new("_routing_id", "id", "www.pinterest.com"),
+/// ];
+///
+/// let header = cookie_header_for_url(&cookies, "https://api.pinterest.com/v3/users/me/")?;
+/// assert_eq!(header, "csrftoken=token");
+/// # Ok::<(), pinterest_login::api::ApiHeadersError>(())
+/// ```
+pub fn cookie_header_for_url(
+    cookies: &[PinterestCookie],
+    url: &str,
+) -> Result<String, ApiHeadersError> {
+    let invalid_url = || ApiHeadersError::InvalidUrl(url.to_string());
+    let parsed = url::Url::parse(url).map_err(|_| invalid_url())?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(invalid_url)?
+        .to_ascii_lowercase();
+    let now = SystemTime::now();
+
+    let mut sent: Vec<_> = cookies
+        .iter()
+        .filter(|cookie| {
+            domain_matches(&cookie.domain, &host)
+                && path_matches(&cookie.path, parsed.path())
+                && (!cookie.secure || parsed.scheme() == "https")
+                && cookie.expires.map_or(true, |expires| expires > now)
+        })
+        .collect();
+    sent.sort_by(|a, b| b.path.len().cmp(&a.path.len()).then(a.name.cmp(&b.name)));
+    check_cookies(sent.iter().map(|cookie| (&cookie.name, &cookie.value)))?;
+
+    Ok(sent
+        .iter()
+        .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+        .collect::<Vec<_>>()
+        .join("; "))
+}
+
+/// The headers of [`build_api_headers`] as an [`http::HeaderMap`]
+///
+/// # Errors
+/// The ones of [`build_api_headers`]
+///
+/// # Example
+/// ```
+/// use pinterest_login::api::build_api_header_map;
+///
+/// let cookies = [("csrftoken".to_string(), "token".to_string())].into();
+///
+/// let headers = build_api_header_map(&cookies, None)?;
+/// assert_eq!(headers["x-csrftoken"], "token");
+/// assert_eq!(headers["x-requested-with"], "XMLHttpRequest");
+/// assert!(!headers.contains_key(http::header::USER_AGENT));
+/// # Ok::<(), pinterest_login::api::ApiHeadersError>(())
+/// ```
+#[cfg(feature = "http")]
+pub fn build_api_header_map(
+    cookies: &PinterestCookies,
+    user_agent: Option<&str>,
+) -> Result<http::HeaderMap, ApiHeadersError> {
+    let headers = build_api_headers(cookies, user_agent)?;
+    Ok(headers
+        .into_iter()
+        .map(|(name, value)| {
+            // The control characters were refused, the other bytes are valid in a header value
+            let value = http::HeaderValue::from_bytes(value.as_bytes())
+                .expect("the header values are checked");
+            let name =
+                http::HeaderName::from_bytes(name.as_bytes()).expect("the header names are valid");
+            (name, value)
+        })
+        .collect())
+}
+
+/// Refuses the first cookie that can't be sent in a `Cookie` header
+fn check_cookies<'a>(
+    mut cookies: impl Iterator<Item = (&'a String, &'a String)>,
+) -> Result<(), ApiHeadersError> {
+    match cookies.find(|(name, value)| !is_cookie_name(name) || !is_cookie_value(value)) {
+        Some((name, _)) => Err(ApiHeadersError::InvalidHeaderValue {
+            header: "Cookie",
+            value: format!("the cookie {name:?}"),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns `true` if a cookie of the domain is sent to the host, the domains with a leading dot include their
+/// subdomains
+fn domain_matches(domain: &str, host: &str) -> bool {
+    let domain = domain.to_ascii_lowercase();
+    match domain.strip_prefix('.') {
+        Some(parent) => {
+            host == parent
+                || host
+                    .strip_suffix(parent)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        }
+        None => host == domain,
+    }
+}
+
+/// Returns `true` if a cookie of the path is sent to the request path, the path-match of RFC 6265
+fn path_matches(cookie_path: &str, request_path: &str) -> bool {
+    request_path == cookie_path
+        || request_path
+            .strip_prefix(cookie_path)
+            .is_some_and(|rest| cookie_path.ends_with('/') || rest.starts_with('/'))
+}
+
+/// Returns `true` if the name can be sent in a `Cookie` header
+fn is_cookie_name(name: &str) -> bool {
+    !name.is_empty()
+        && !has_control_char(name)
+        && !name.contains(|c: char| c == '=' || c == ';' || c.is_whitespace())
+}
+
+/// Returns `true` if the value can be sent in a `Cookie` header
+fn is_cookie_value(value: &str) -> bool {
+    !has_control_char(value) && !value.contains(';')
+}
+
+/// Returns `true` if the text has a character that ends or corrupts a header line
+fn has_control_char(text: &str) -> bool {
+    text.chars().any(|c| c.is_control() && c != '\t')
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use crate::{session::Session, PinterestCookies};
+
+/// What an API client needs to send the requests of a logged in session: the `Cookie` header, the CSRF token and the
+/// user agent of the browser that logged in.
+///
+/// It's the handoff to the [pinterest-rs](https://github.com/0x61nas/pinterest-rs) client, so its fields and their
+/// formats are part of the public API: they only change with a major version. New fields may be added in a minor
+/// version, so it can't be built with a struct literal.
+///
+/// # Example
+/// ```
+/// use pinterest_login::auth::AuthSession;
+/// use pinterest_login::session::Session;
+///
+/// let cookies = [("_pinterest_sess", "session"), ("csrftoken", "token")]
+///     .into_iter()
+///     .map(|(name, value)| (name.to_string(), value.to_string()))
+///     .collect();
+/// let session = Session::new("me@example.com", cookies).with_user_agent("Mozilla/5.0");
+///
+/// let auth = AuthSession::from(&session);
+/// assert_eq!(auth.cookie_header, "_pinterest_sess=session; csrftoken=token");
+/// assert_eq!(auth.csrf_token.as_deref(), Some("token"));
+/// assert_eq!(auth.user_agent.as_deref(), Some("Mozilla/5.0"));
+/// assert_eq!(
+///     auth.headers(),
+///     [
+///         ("Cookie", "_pinterest_sess=session; csrftoken=token".to_string()),
+///         ("x-csrftoken", "token".to_string()),
+///         ("User-Agent", "Mozilla/5.0".to_string()),
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AuthSession {
+    /// The value of the `Cookie` header, `name=value` pairs sorted by name and separated by `; `
+    pub cookie_header: String,
+    /// The `csrftoken` cookie, to send in the `x-csrftoken` header of the requests that change something
+    pub csrf_token: Option<String>,
+    /// The user agent of the browser that logged in, the requests look less suspicious with it
+    pub user_agent: Option<String>,
+}
+
+impl AuthSession {
+    /// The credentials of the cookies, like the ones of [`login`](crate::login), without a user agent
+    pub fn from_cookies(cookies: &PinterestCookies) -> Self {
+        let cookie_header = cookies
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        Self {
+            cookie_header,
+            csrf_token: cookies.get_csrf().map(String::from),
+            user_agent: None,
+        }
+    }
+
+    /// Sets the user agent of the browser that logged in
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// The headers of the API requests: `Cookie`, then `x-csrftoken` and `User-Agent` when they're known
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![("Cookie", self.cookie_header.clone())];
+        if let Some(token) = &self.csrf_token {
+            headers.push(("x-csrftoken", token.clone()));
+        }
+        if let Some(user_agent) = &self.user_agent {
+            headers.push(("User-Agent", user_agent.clone()));
+        }
+        headers
+    }
+}
+
+impl From<&Session> for AuthSession {
+    fn from(session: &Session) -> Self {
+        let auth = Self::from_cookies(&session.cookies);
+        match &session.user_agent {
+            Some(user_agent) => auth.with_user_agent(user_agent),
+            None => auth,
+        }
+    }
+}
+
+impl From<Session> for AuthSession {
+    fn from(session: Session) -> Self {
+        Self::from(&session)
+    }
+}
+
+impl From<&PinterestCookies> for AuthSession {
+    fn from(cookies: &PinterestCookies) -> Self {
+        Self::from_cookies(cookies)
+    }
+}
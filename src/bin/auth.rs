@@ -0,0 +1,120 @@
+//! The password in the keyring of the OS: `auth store` saves it, `auth forget` deletes it, and the logins read it
+//! when it isn't on stdin or in PINTEREST_PASSWORD.
+//!
+//! The entries are under the `pinterest-login` service, with the email as the account, so every account has its
+//! own password.
+
+use keyring::{Entry, Error};
+
+/// The service of the keyring entries
+pub const SERVICE: &str = "pinterest-login";
+
+/// The keyring entry of an account
+pub struct Keyring {
+    email: String,
+    entry: Entry,
+}
+
+impl Keyring {
+    pub fn new(email: &str) -> Result<Self, String> {
+        let entry = Entry::new(SERVICE, email).map_err(|e| describe(e, email))?;
+        Ok(Self {
+            email: email.to_string(),
+            entry,
+        })
+    }
+
+    /// The saved password, `None` if there's none or if there's no keyring on the system, since the other
+    /// sources of the password can be used then
+    pub fn password(&self) -> Result<Option<String>, String> {
+        match self.entry.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(Error::NoEntry) => Ok(None),
+            Err(Error::NoStorageAccess(_) | Error::PlatformFailure(_)) => Ok(None),
+            Err(e) => Err(describe(e, &self.email)),
+        }
+    }
+
+    /// Saves the password, replacing the previous one
+    pub fn store(&self, password: &str) -> Result<(), String> {
+        self.entry
+            .set_password(password)
+            .map_err(|e| describe(e, &self.email))
+    }
+
+    /// Deletes the password, `false` if there was none
+    pub fn forget(&self) -> Result<bool, String> {
+        match self.entry.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(Error::NoEntry) => Ok(false),
+            Err(e) => Err(describe(e, &self.email)),
+        }
+    }
+}
+
+/// A readable error, that tells what to do when there's no keyring
+fn describe(error: Error, email: &str) -> String {
+    match error {
+        Error::NoStorageAccess(_) | Error::PlatformFailure(_) => format!(
+            "The keyring of the system isn't available ({error}), \
+             use PINTEREST_PASSWORD, `--password-stdin` or the config file instead"
+        ),
+        error => format!("The keyring entry of `{email}`: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyring::mock::{self, MockCredential};
+
+    fn mock_keyring(email: &str) -> Keyring {
+        keyring::set_default_credential_builder(mock::default_credential_builder());
+        Keyring::new(email).unwrap()
+    }
+
+    fn fail_next_call(keyring: &Keyring, error: Error) {
+        let mock: &MockCredential = keyring.entry.get_credential().downcast_ref().unwrap();
+        mock.set_error(error);
+    }
+
+    #[test]
+    fn the_password_is_stored_and_forgotten() {
+        let keyring = mock_keyring("email@example.com");
+        assert_eq!(keyring.password(), Ok(None));
+
+        keyring.store("hunter2").unwrap();
+        assert_eq!(keyring.password(), Ok(Some("hunter2".to_string())));
+
+        assert_eq!(keyring.forget(), Ok(true));
+        assert_eq!(keyring.password(), Ok(None));
+        assert_eq!(keyring.forget(), Ok(false));
+    }
+
+    #[test]
+    fn a_missing_keyring_is_a_clear_error() {
+        let keyring = mock_keyring("email@example.com");
+        let unavailable = || Error::NoStorageAccess("no secret service".into());
+
+        // The logins use the other sources of the password
+        fail_next_call(&keyring, unavailable());
+        assert_eq!(keyring.password(), Ok(None));
+
+        fail_next_call(&keyring, unavailable());
+        let err = keyring.store("hunter2").unwrap_err();
+        assert!(err.contains("isn't available"), "{err}");
+        assert!(err.contains("PINTEREST_PASSWORD"), "{err}");
+
+        fail_next_call(&keyring, unavailable());
+        assert!(keyring.forget().is_err());
+    }
+
+    #[test]
+    fn an_unreadable_password_is_an_error() {
+        let keyring = mock_keyring("email@example.com");
+        fail_next_call(&keyring, Error::BadEncoding(vec![0xff]));
+
+        let err = keyring.password().unwrap_err();
+        assert!(err.contains("`email@example.com`"), "{err}");
+    }
+}
@@ -0,0 +1,448 @@
+//! The `batch` command: it logs in a list of accounts, a few at a time, and writes the cookies of every account
+//! to its own file. A failed login doesn't stop the others, the summary at the end tells which ones failed.
+//!
+//! The accounts are a CSV file, or a TSV file (`.tsv`, or a header with tabs), with a header row that names the
+//! `email` column and either a `password` or a `password_command` column:
+//!
+//! ```text
+//! email,password_command
+//! me@example.com,pass show pinterest/me
+//! "other@example.com","pass show ""pinterest/other"""
+//! ```
+//!
+//! The commands are run without a shell, they're split in their program and arguments by
+//! [`split_command`](crate::config::split_command).
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use pinterest_login::config_builder::BrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::login_with_options;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::store::write_atomic;
+
+use crate::config::{run_password_command, split_command};
+use crate::{exit_code, to_json};
+
+/// The options of the `batch` command
+#[derive(Debug, Clone, PartialEq, Eq, clap::Args)]
+pub struct BatchArgs {
+    /// The accounts, a CSV or TSV file with the `email` and `password` or `password_command` columns
+    #[arg(long, value_name = "PATH")]
+    pub accounts: PathBuf,
+    /// How many accounts are logged in at the same time
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub concurrency: u16,
+    /// The directory of the cookie files, one `{email}.json` file per account, it's created if it's missing
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: PathBuf,
+}
+
+/// Where the password of an account comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Password {
+    Plain(String),
+    /// The program and the arguments of a command that prints it, run right before the login
+    Command(Vec<String>),
+}
+
+/// A line of the accounts file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub email: String,
+    pub password: Password,
+}
+
+/// How the login of an account went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountResult {
+    pub email: String,
+    /// The exit code the login would have alone, `0` if it succeeded
+    pub code: i32,
+    pub duration: Duration,
+}
+
+/// Parses the accounts file, the TSV files are told apart by their extension or the tabs of their header
+pub fn parse_accounts(contents: &str, tsv: bool) -> Result<Vec<Account>, String> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines.next().ok_or("the accounts file is empty")?;
+    let separator = if tsv || header.contains('\t') {
+        '\t'
+    } else {
+        ','
+    };
+    let header = split_fields(header, separator).map_err(|e| format!("line 1: {e}"))?;
+    let column = |name: &str| header.iter().position(|field| field.trim() == name);
+    let email_column = column("email").ok_or("the header has no `email` column")?;
+    let (password_column, is_command) = match (column("password"), column("password_command")) {
+        (Some(column), None) => (column, false),
+        (None, Some(column)) => (column, true),
+        (Some(_), Some(_)) => {
+            return Err("the header has both a `password` and a `password_command` column".into())
+        }
+        (None, None) => {
+            return Err("the header has no `password` or `password_command` column".into())
+        }
+    };
+
+    let mut accounts = Vec::new();
+    // The file names of the accounts, to catch two accounts that would overwrite each other's cookies
+    let mut files = HashMap::new();
+    for (line_number, line) in lines {
+        let fields =
+            split_fields(line, separator).map_err(|e| format!("line {line_number}: {e}"))?;
+        if fields.len() != header.len() {
+            return Err(format!(
+                "line {line_number}: expected {} fields, found {}",
+                header.len(),
+                fields.len()
+            ));
+        }
+        let email = fields[email_column].trim().to_string();
+        let password = fields[password_column].clone();
+        if email.is_empty() || password.is_empty() {
+            return Err(format!(
+                "line {line_number}: the email or the password is empty"
+            ));
+        }
+        if let Some(other) = files.insert(file_name(&email), email.clone()) {
+            return Err(format!(
+                "line {line_number}: `{email}` and `{other}` would have the same cookie file"
+            ));
+        }
+        let password = if is_command {
+            let command = split_command(&password)
+                .ok()
+                .filter(|command| !command.is_empty())
+                .ok_or_else(|| format!("line {line_number}: invalid `password_command`"))?;
+            Password::Command(command)
+        } else {
+            Password::Plain(password)
+        };
+        accounts.push(Account { email, password });
+    }
+    Ok(accounts)
+}
+
+/// Splits a line in its fields, a field between double quotes can have the separator, and `""` for a quote
+fn split_fields(line: &str, separator: char) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted field".to_string()),
+                }
+            }
+            match chars.next() {
+                None => {
+                    fields.push(field);
+                    return Ok(fields);
+                }
+                Some(c) if c == separator => fields.push(field),
+                Some(c) => return Err(format!("unexpected `{c}` after a quoted field")),
+            }
+        } else {
+            loop {
+                match chars.next() {
+                    None => {
+                        fields.push(field);
+                        return Ok(fields);
+                    }
+                    Some(c) if c == separator => break,
+                    Some(c) => field.push(c),
+                }
+            }
+            fields.push(field);
+        }
+    }
+}
+
+/// The name of the cookie file of an account: the lowercase email (they're case insensitive), with anything but
+/// the letters, the digits and `@._+-` replaced by `_`, so it can't leave the directory or be a hidden file
+pub fn file_name(email: &str) -> String {
+    let name: String = email
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "@._+-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = match name.strip_prefix('.') {
+        Some(rest) => format!("_{rest}"),
+        None => name,
+    };
+    format!("{name}.json")
+}
+
+/// The summary table, in the order of the accounts file
+pub fn summary(results: &[AccountResult]) -> String {
+    let width = results
+        .iter()
+        .map(|result| result.email.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("ACCOUNT".len());
+    let mut table = format!("{:<width$}  STATUS  CODE  DURATION\n", "ACCOUNT");
+    for result in results {
+        let status = if result.code == 0 { "ok" } else { "failed" };
+        let _ = writeln!(
+            table,
+            "{:<width$}  {status:<6}  {:<4}  {:.1}s",
+            result.email,
+            result.code,
+            result.duration.as_secs_f64()
+        );
+    }
+    table
+}
+
+/// Logs the accounts in, and returns the exit code of the batch
+pub async fn run<C>(
+    args: &BatchArgs,
+    full: bool,
+    config_builder: &C,
+    options: &LoginOptions,
+    login_host: Option<&Host>,
+    quiet: bool,
+) -> i32
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let contents = std::fs::read_to_string(&args.accounts).unwrap_or_else(|e| {
+        crate::fail(
+            exit_code::OTHER,
+            format!("Can't read `{}`: {e}", args.accounts.display()),
+        )
+    });
+    let tsv = args.accounts.extension().is_some_and(|ext| ext == "tsv");
+    let accounts = parse_accounts(&contents, tsv).unwrap_or_else(|e| {
+        crate::fail(
+            exit_code::OTHER,
+            format!("Invalid accounts file `{}`: {e}", args.accounts.display()),
+        )
+    });
+    std::fs::create_dir_all(&args.out_dir).unwrap_or_else(|e| {
+        crate::fail(
+            exit_code::OTHER,
+            format!("Can't create `{}`: {e}", args.out_dir.display()),
+        )
+    });
+
+    let mut results: Vec<(usize, AccountResult)> =
+        futures::stream::iter(accounts.iter().enumerate())
+            .map(|(i, account)| async move {
+                let started = Instant::now();
+                let code = match login(
+                    account,
+                    &args.out_dir,
+                    full,
+                    config_builder,
+                    options,
+                    login_host,
+                )
+                .await
+                {
+                    Ok(()) => 0,
+                    Err((code, e)) => {
+                        // Not quiet, like the errors of a single login
+                        eprintln!("{}: {e}", account.email);
+                        code
+                    }
+                };
+                let result = AccountResult {
+                    email: account.email.clone(),
+                    code,
+                    duration: started.elapsed(),
+                };
+                if !quiet && code == 0 {
+                    eprintln!("{}: logged in", account.email);
+                }
+                (i, result)
+            })
+            .buffer_unordered(args.concurrency.into())
+            .collect()
+            .await;
+    results.sort_by_key(|(i, _)| *i);
+    let results: Vec<_> = results.into_iter().map(|(_, result)| result).collect();
+
+    print!("{}", summary(&results));
+    if results.iter().any(|result| result.code != 0) {
+        exit_code::BATCH_FAILED
+    } else {
+        0
+    }
+}
+
+/// Logs an account in and writes its cookies, the error comes with its exit code
+async fn login<C>(
+    account: &Account,
+    out_dir: &Path,
+    full: bool,
+    config_builder: &C,
+    options: &LoginOptions,
+    login_host: Option<&Host>,
+) -> Result<(), (i32, String)>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let password = match &account.password {
+        Password::Plain(password) => password.clone(),
+        Password::Command(command) => {
+            run_password_command(command).map_err(|e| (exit_code::OTHER, e))?
+        }
+    };
+    let bot = DefaultBrowserLoginBot::new(&account.email, &password);
+    let bot = match login_host {
+        Some(host) => bot.with_login_host(host.clone()),
+        None => bot,
+    };
+    let outcome = login_with_options(&bot, config_builder, options)
+        .await
+        .map_err(|e| (exit_code::of(&e), e.to_string()))?;
+    let path = out_dir.join(file_name(&account.email));
+    let json = to_json(&outcome.cookies, &outcome.cookie_details, full);
+    write_atomic(&path, format!("{json}\n").as_bytes(), false).map_err(|e| {
+        (
+            exit_code::OTHER,
+            format!("Can't write the cookies to `{}`: {e}", path.display()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(email: &str, password: &str) -> Account {
+        Account {
+            email: email.to_string(),
+            password: Password::Plain(password.to_string()),
+        }
+    }
+
+    #[test]
+    fn the_accounts_are_read_from_csv_and_tsv() {
+        let csv =
+            "email,password\nme@example.com,hunter2\r\n\n\"other@example.com\",\"a,b \"\"c\"\"\"\n";
+        assert_eq!(
+            parse_accounts(csv, false),
+            Ok(vec![
+                plain("me@example.com", "hunter2"),
+                plain("other@example.com", "a,b \"c\""),
+            ])
+        );
+
+        // The columns can be in any order, the tabs of the header tell a TSV file
+        let tsv = "password_command\temail\npass show a,b\tme@example.com\n";
+        assert_eq!(
+            parse_accounts(tsv, false),
+            Ok(vec![Account {
+                email: "me@example.com".to_string(),
+                password: Password::Command(vec!["pass".into(), "show".into(), "a,b".into()]),
+            }])
+        );
+        // The passwords keep their spaces
+        assert_eq!(
+            parse_accounts("email\tpassword\nme@example.com\t pass \n", true),
+            Ok(vec![plain("me@example.com", " pass ")])
+        );
+    }
+
+    #[test]
+    fn invalid_accounts_files_are_rejected() {
+        let table = [
+            ("", "empty"),
+            ("mail,password\n", "no `email` column"),
+            ("email,pass\n", "no `password` or `password_command` column"),
+            ("email,password,password_command\n", "both"),
+            (
+                "email,password_command\nme@example.com,pass show 'a\n",
+                "line 2: invalid `password_command`",
+            ),
+            (
+                "email,password\nme@example.com\n",
+                "line 2: expected 2 fields, found 1",
+            ),
+            (
+                "email,password\nme@example.com,\n",
+                "line 2: the email or the password is empty",
+            ),
+            (
+                "email,password\n\"me@example.com,pass\n",
+                "line 2: unterminated quoted field",
+            ),
+            (
+                "email,password\n\"me\"x,pass\n",
+                "line 2: unexpected `x` after a quoted field",
+            ),
+            (
+                "email,password\nMe@Example.com,a\nme@example.com,b\n",
+                "line 3: `me@example.com` and `Me@Example.com` would have the same cookie file",
+            ),
+        ];
+        for (contents, expected) in table {
+            let err = parse_accounts(contents, false).unwrap_err();
+            assert!(err.contains(expected), "{contents:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn the_file_names_stay_in_the_directory() {
+        let table = [
+            ("me@example.com", "me@example.com.json"),
+            ("Me+Pins@Example.com", "me+pins@example.com.json"),
+            ("../../etc/passwd", "_._.._etc_passwd.json"),
+            (".hidden@example.com", "_hidden@example.com.json"),
+            ("a/b\\c d:e", "a_b_c_d_e.json"),
+            ("ünïcödé@example.com", "_n_c_d_@example.com.json"),
+        ];
+        for (email, name) in table {
+            assert_eq!(file_name(email), name, "{email}");
+        }
+    }
+
+    #[test]
+    fn the_summary_is_a_table() {
+        let results = [
+            AccountResult {
+                email: "me@example.com".to_string(),
+                code: 0,
+                duration: Duration::from_millis(12_345),
+            },
+            AccountResult {
+                email: "a@b.c".to_string(),
+                code: exit_code::AUTHENTICATION,
+                duration: Duration::from_millis(4_000),
+            },
+        ];
+
+        assert_eq!(
+            summary(&results),
+            "ACCOUNT         STATUS  CODE  DURATION\n\
+             me@example.com  ok      0     12.3s\n\
+             a@b.c           failed  10    4.0s\n"
+        );
+    }
+}
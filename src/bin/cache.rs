@@ -0,0 +1,188 @@
+//! The cookie cache of `--cache`: the cookies of the last successful login, with when they were saved and for
+//! which account, so the next logins can reuse them while they're still logged in.
+//!
+//! It's a JSON file like `{"saved_at": 1700000000, "email": "...", "login_url": "...", "cookies": [...]}`, the
+//! cookies are the ones of `--format json --full`.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{env, fs, io};
+
+use chromiumoxide::cdp::browser_protocol::network::CookieParam;
+use pinterest_login::store::write_atomic;
+use serde_json::{json, Value};
+
+use crate::parse_cookies;
+
+/// How long the cached cookies are reused by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// `$XDG_CACHE_HOME/pinterest-login/cookies.json`, or `~/.cache/pinterest-login/cookies.json`
+pub fn default_path() -> Option<PathBuf> {
+    let cache_home = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache_home.join("pinterest-login").join("cookies.json"))
+}
+
+/// Whose cookies they are, the cache of another account or login page is a miss
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key<'a> {
+    pub email: &'a str,
+    pub login_url: &'a str,
+}
+
+/// What the cache has for a login
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+    /// Cookies younger than the TTL, they still have to be verified
+    Fresh(Vec<CookieParam>),
+    /// Cookies older than the TTL
+    Expired,
+    /// No cache file, or the cookies of another account or login page
+    Missing,
+    /// A cache file that can't be read or parsed
+    Corrupted(String),
+}
+
+/// Looks up the cookies of the key that were saved less than `ttl` before `now`
+pub fn lookup(path: &Path, key: &Key, ttl: Duration, now: SystemTime) -> Lookup {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Lookup::Missing,
+        Err(e) => return Lookup::Corrupted(e.to_string()),
+    };
+    let cache: Value = match serde_json::from_str(&contents) {
+        Ok(cache) => cache,
+        Err(e) => return Lookup::Corrupted(e.to_string()),
+    };
+    let Some(saved_at) = cache["saved_at"].as_u64() else {
+        return Lookup::Corrupted("it has no `saved_at` time".to_string());
+    };
+    if cache["email"] != key.email || cache["login_url"] != key.login_url {
+        return Lookup::Missing;
+    }
+    let cookies = match parse_cookies(cache["cookies"].clone()) {
+        Ok(cookies) => cookies,
+        Err(e) => return Lookup::Corrupted(e),
+    };
+    let saved_at = SystemTime::UNIX_EPOCH + Duration::from_secs(saved_at);
+    // A time in the future is a clock that went back, the cookies can't be trusted to be young
+    match now.duration_since(saved_at) {
+        Ok(age) if age <= ttl => Lookup::Fresh(cookies),
+        _ => Lookup::Expired,
+    }
+}
+
+/// Saves the cookies of a login, `cookies` are the ones of `--format json --full`.
+/// The file is replaced atomically, and only its owner can read it
+pub fn save(path: &Path, key: &Key, cookies: Value, now: SystemTime) -> io::Result<()> {
+    let saved_at = now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let cache = json!({
+        "saved_at": saved_at,
+        "email": key.email,
+        "login_url": key.login_url,
+        "cookies": cookies,
+    });
+    write_atomic(path, format!("{cache}\n").as_bytes(), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: Key = Key {
+        email: "email@example.com",
+        login_url: "https://www.pinterest.com/login/",
+    };
+
+    fn cookies() -> Value {
+        json!([{
+            "name": "_pinterest_sess", "value": "session", "domain": ".pinterest.com", "path": "/",
+            "expires": 4102444800.0, "http_only": true, "secure": true, "same_site": "None",
+        }])
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn the_saved_cookies_are_a_hit_until_the_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pinterest-login").join("cookies.json");
+        save(&path, &KEY, cookies(), at(1_000)).unwrap();
+
+        let ttl = Duration::from_secs(60);
+        let Lookup::Fresh(cached) = lookup(&path, &KEY, ttl, at(1_060)) else {
+            panic!("expected a hit");
+        };
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].name, "_pinterest_sess");
+        assert_eq!(cached[0].domain.as_deref(), Some(".pinterest.com"));
+
+        assert_eq!(lookup(&path, &KEY, ttl, at(1_061)), Lookup::Expired);
+        // The clock went back
+        assert_eq!(lookup(&path, &KEY, ttl, at(999)), Lookup::Expired);
+    }
+
+    #[test]
+    fn the_cookies_of_another_account_are_a_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.json");
+        let ttl = Duration::from_secs(60);
+        assert_eq!(lookup(&path, &KEY, ttl, at(1_000)), Lookup::Missing);
+
+        save(&path, &KEY, cookies(), at(1_000)).unwrap();
+        let other_account = Key {
+            email: "other@example.com",
+            ..KEY
+        };
+        assert_eq!(
+            lookup(&path, &other_account, ttl, at(1_000)),
+            Lookup::Missing
+        );
+        let other_page = Key {
+            login_url: "http://127.0.0.1:8080/login",
+            ..KEY
+        };
+        assert_eq!(lookup(&path, &other_page, ttl, at(1_000)), Lookup::Missing);
+    }
+
+    #[test]
+    fn a_corrupted_cache_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.json");
+        let ttl = Duration::from_secs(60);
+
+        for contents in [
+            "{not json",
+            "[]",
+            r#"{"email": "email@example.com"}"#,
+            r#"{"saved_at": 1000, "email": "email@example.com",
+                "login_url": "https://www.pinterest.com/login/", "cookies": 42}"#,
+        ] {
+            fs::write(&path, contents).unwrap();
+            assert!(
+                matches!(lookup(&path, &KEY, ttl, at(1_000)), Lookup::Corrupted(_)),
+                "{contents}"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_cache_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.json");
+        save(&path, &KEY, cookies(), at(1_000)).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
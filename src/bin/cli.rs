@@ -0,0 +1,1335 @@
+//! The command line of the binary: the subcommands are parsed with clap, then flattened into [`Args`] for `main`.
+//!
+//! `login` is the default command, so `pintrest-login --format json` is `pintrest-login login --format json`.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use pinterest_login::retry::StepRetry;
+use pinterest_login::totp::TotpSecret;
+
+#[cfg(feature = "serve")]
+use crate::serve;
+use crate::{batch, cache, watch};
+
+/// The end of `--help`
+pub const AFTER_LONG_HELP: &str = "\
+The credentials are read from the PINTEREST_EMAIL and PINTEREST_PASSWORD environment variables,
+then from the keyring of the system for the password (saved by `auth store`, it needs the `auth` feature),
+then from the config file, and they're prompted for when they aren't set.
+With `--password-stdin` the password is the first line of stdin instead, e.g. from a CI secret
+(`printf '%s\\n' \"$SECRET\" | pintrest-login --password-stdin`), and the email must be set.
+With `--password-file` it's the first line of the file instead, like a mounted secret or
+`--password-file <(secret-tool lookup service pinterest)`.
+If pinterest asks for a two-factor authentication code, it's generated from `--totp-secret`, or prompted for
+on the terminal, and without a terminal the login fails with the exit code 11.
+
+Only the cookies are written to stdout, the logs and the errors go to stderr.
+Every retry is announced with a line on stderr, unless `--quiet` is passed.
+
+Exit codes:
+  0   Success
+  1   `verify`: the cookies are expired or invalid
+  2   Another error, like an unreadable input, an unwritable output or a missing `--cookie`
+  3   `batch`: some of the accounts failed to log in
+  4   `refresh`: the cookies weren't logged in anymore, and the login succeeded
+  10  The email or password is incorrect, retrying won't help
+  11  Pinterest asks for a captcha or a second factor
+  12  Pinterest is rate limiting the login attempts
+  13  Pinterest is down or in maintenance
+  20  The browser failed to launch, isn't supported, or crashed
+  21  A page or a request timed out, or the login went past `--fallback-deadline` or `--manual-timeout`
+  22  The browser couldn't reach the login page
+  64  Invalid arguments
+  78  Invalid config file
+  130 `--manual`: the login was interrupted with Ctrl-C";
+
+/// Logs into pinterest and prints the cookies
+#[derive(Debug, Parser)]
+#[command(
+    name = "pintrest-login",
+    version,
+    after_long_help = AFTER_LONG_HELP,
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+    /// The options of `login`, without a subcommand
+    #[command(flatten)]
+    login: LoginArgs,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Subcommand {
+    /// Log in and print the cookies (the default)
+    Login(LoginArgs),
+    /// Check if the cookies saved with `--format json` are still logged in
+    ///
+    /// The exit code is 0 if they are, and 1 if they're expired or invalid
+    Verify(VerifyArgs),
+    /// Refresh the saved cookies, and log in again only if they aren't logged in anymore
+    ///
+    /// The saved cookies are opened in the browser, so pinterest can rotate them, and the cookies of the browser
+    /// afterwards replace them (or go to `--output`), as `--format json`. The exit code is 0 if they were still
+    /// logged in, and 4 after a new login.
+    ///
+    /// It's quiet unless `-v` is passed, and the refreshes of the same file wait for each other, to run it from cron
+    Refresh(RefreshArgs),
+    /// Print the cookies saved with `--format json` in another format, without logging in
+    Export(ExportArgs),
+    /// Log in a list of accounts, and write the cookies of each one to its own file
+    ///
+    /// The accounts are a CSV or TSV file with a header row, that has an `email` column and a `password` or
+    /// `password_command` column, the commands are run without a shell. A failed login doesn't stop the others, a summary of the logins is printed at
+    /// the end, and the exit code is 3 if any of them failed
+    Batch(BatchCommandArgs),
+    /// Keep a cookie file logged in: verify its cookies periodically, and log in again when they aren't
+    ///
+    /// The cookies are written as `--format json`, the file is replaced atomically after every login. Every
+    /// cycle is reported with one line on stderr, the failed ones are retried sooner with a growing delay.
+    /// SIGTERM and SIGINT stop it once the current cycle is over, with the exit code 0
+    Watch(WatchCommandArgs),
+    /// Log in, then answer the cookies over HTTP, and keep them logged in
+    ///
+    /// `GET /cookies` answers the cookies as a JSON object, and `GET /healthz` answers `200` if the last login or
+    /// verification succeeded, `503` otherwise. The cookies are verified periodically, and it logs in again
+    /// when they aren't logged in anymore
+    #[cfg(feature = "serve")]
+    Serve(ServeCommandArgs),
+    /// Save the password in the keyring of the system, or delete it
+    ///
+    /// The logins read it when it isn't on stdin or in PINTEREST_PASSWORD, the entry is the one of the
+    /// email of PINTEREST_EMAIL or of the config file
+    #[cfg(feature = "auth")]
+    Auth(AuthArgs),
+    /// Print the completion script of a shell
+    ///
+    /// e.g. `pintrest-login completions bash > ~/.local/share/bash-completion/completions/pintrest-login`
+    Completions {
+        /// The shell of the script
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Only to tell that the binary was built without the `serve` feature
+    #[cfg(not(feature = "serve"))]
+    #[command(hide = true)]
+    Serve {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, hide = true)]
+        _args: Vec<String>,
+    },
+}
+
+/// The options of every command but `export`
+#[derive(Debug, clap::Args)]
+struct CommonArgs {
+    /// The config file [default: $XDG_CONFIG_HOME/pinterest-login/config.toml]
+    ///
+    /// A flat TOML file with the `email`, `password` or `password_command` (a command that prints the password
+    /// on its first line, like `["pass", "show", "pinterest"]`, run without a shell), `headless`, `timeout`,
+    /// `proxy` and `chrome_path` keys. The flags take precedence over it, and it's rejected if the other users can
+    /// read it
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Use the config file even if the other users can read it
+    #[arg(long)]
+    allow_insecure_config: bool,
+    /// Print the logs: `-v` for the info logs, `-vv` for the debug logs and `-vvv` for the trace logs
+    ///
+    /// It takes precedence over RUST_LOG (the logs need the `log` feature)
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// The format of the logs on stderr, `json` for one JSON object per line, with the `timestamp`, `level`,
+    /// `target` and `message` keys
+    ///
+    /// The errors follow it too, the other messages can be turned off with `--quiet`.
+    /// It doesn't change the output, see `--format`
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Only print the output and the errors
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+/// How the browser is launched and driven
+#[derive(Debug, clap::Args)]
+struct BrowserArgs {
+    /// Show the browser window
+    #[arg(long, conflicts_with = "headless")]
+    head: bool,
+    /// Hide the browser window, the default unless the config file says otherwise
+    #[arg(long)]
+    headless: bool,
+    /// The timeout of every request to the browser, like `3` (seconds), `1500ms` or `1m` [default: 3s]
+    #[arg(short, long, value_name = "TIME", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+    /// How long to wait for the browser to start, like `45` (seconds), `45s` or `1m` [default: 20s]
+    ///
+    /// It's separate from `--timeout` since Chrome can take a while to start on a busy machine, without
+    /// the requests being any slower
+    #[arg(long, value_name = "TIME", value_parser = parse_duration)]
+    launch_timeout: Option<Duration>,
+    /// Send the browser traffic through the proxy, like `socks5://127.0.0.1:1080`
+    ///
+    /// Without it, the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables are honored
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+    /// The Chrome/Chromium executable to use
+    ///
+    /// It takes precedence over the CHROME environment variable, then the `chrome_path` of the config file,
+    /// then the automatic detection
+    #[arg(long, value_name = "PATH")]
+    chrome_path: Option<PathBuf>,
+    /// Use the browser profile in the directory, instead of a throwaway one for every launch
+    ///
+    /// The directory is created if it's missing, and it implies `--no-incognito`, so the login stays in the
+    /// profile. A profile can only be used by one pinterest-login at a time, the others fail right away
+    #[arg(long, value_name = "DIR")]
+    user_data_dir: Option<PathBuf>,
+    /// Log in outside of an incognito window, so the cookies stay in the profile of `--user-data-dir`
+    ///
+    /// Pinterest recognizes the device of a profile it saw before, with fewer "new device" emails
+    #[arg(long)]
+    no_incognito: bool,
+    /// Log in on another page that mimics the pinterest login page, mostly for testing
+    #[arg(long, value_name = "URL")]
+    login_url: Option<String>,
+    /// How many times to retry the browser launch and the navigation to the login page [default: 2]
+    ///
+    /// Only the transient errors are retried, a wrong password never is
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+    /// The delay between the retries, like `500ms`, `2s` or `1m` [default: 500ms]
+    #[arg(long, value_name = "TIME", value_parser = parse_duration)]
+    retry_delay: Option<Duration>,
+}
+
+/// Where the credentials come from, besides the environment variables and the config file
+#[derive(Debug, clap::Args)]
+struct CredentialArgs {
+    /// Read the password from the first line of stdin, only its trailing newline is removed
+    ///
+    /// It can't be used with PINTEREST_PASSWORD, and the password is never prompted for
+    #[arg(long)]
+    password_stdin: bool,
+    /// Read the password from the first line of the file, only its trailing newline is removed
+    ///
+    /// Like a mounted secret, or a `/dev/fd/N` path on unix (`<(secret-tool lookup ...)`). It can't be used
+    /// with PINTEREST_PASSWORD, and the password is never prompted for. A warning is printed if everyone
+    /// can read the file
+    #[arg(long, value_name = "PATH", conflicts_with = "password_stdin")]
+    password_file: Option<PathBuf>,
+    /// The secret of the authenticator app, to enter the two-factor authentication codes unattended
+    ///
+    /// The PINTEREST_TOTP_SECRET environment variable is used without it
+    #[arg(long, value_name = "BASE32")]
+    totp_secret: Option<TotpSecret>,
+}
+
+/// How the cookies are printed
+#[derive(Debug, clap::Args)]
+struct CookieFormatArgs {
+    /// The output format
+    ///
+    /// `env` sets PINTEREST_SESSION and PINTEREST_CSRFTOKEN if the cookies are there, and
+    /// PINTEREST_COOKIE_HEADER to a `Cookie` header with all the cookies
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = Format::Debug)]
+    format: Format,
+    /// With `--format json`, print the full cookies (domain, path, expiry, flags...) instead of a name
+    /// to value object
+    #[arg(long)]
+    full: bool,
+    /// Print only the value of the cookie, without any decoration
+    ///
+    /// It can be repeated to print the values one per line in the same order. It fails if a cookie isn't there
+    #[arg(long = "cookie", value_name = "NAME", conflicts_with_all = ["format", "full"])]
+    cookies: Vec<String>,
+    /// With `--format curl`, the URL of the command [default: https://www.pinterest.com/]
+    #[arg(long, value_name = "URL")]
+    curl_url: Option<String>,
+}
+
+/// Where the output goes
+#[derive(Debug, clap::Args)]
+struct OutputArgs {
+    /// Write to the file instead of stdout (`-` is stdout)
+    ///
+    /// The file is replaced atomically and only readable by its owner
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Create the parent directories of the output file
+    #[arg(long)]
+    mkdir: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct LoginArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    browser: BrowserArgs,
+    #[command(flatten)]
+    credentials: CredentialArgs,
+    #[command(flatten)]
+    format: CookieFormatArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    /// If pinterest challenges the headless login (a captcha, a second factor...), open the browser window
+    /// to finish the login by hand
+    ///
+    /// The form is filled already. A wrong email or password never opens it
+    #[arg(long, conflicts_with = "head")]
+    headful_fallback: bool,
+    /// The overall deadline of the login with `--headful-fallback`, the window gets what's left of it
+    /// [default: 5m]
+    #[arg(long, value_name = "TIME", value_parser = parse_duration, requires = "headful_fallback")]
+    fallback_deadline: Option<Duration>,
+    /// Open the browser window on the login page, and let you log in by hand instead of filling the form
+    ///
+    /// The cookies are printed once you're logged in, like after the automated login. No credentials are
+    /// needed, and the window is closed on Ctrl-C. It's headful unless `--headless` is passed (for testing)
+    #[arg(long, conflicts_with_all = ["headful_fallback", "password_stdin", "password_file", "totp_secret", "cache"])]
+    manual: bool,
+    /// How long to wait for the login with `--manual` [default: 5m]
+    #[arg(long, value_name = "TIME", value_parser = parse_duration, requires = "manual")]
+    manual_timeout: Option<Duration>,
+    /// Save a screenshot of the page into the directory when the login fails on it
+    ///
+    /// It's saved as `login-failure-{unix time in ms}.png`, and its path is printed with the error (even
+    /// with `--quiet`). The directory is created if it's missing, and an unwritable one fails before the login
+    #[arg(long, value_name = "DIR")]
+    screenshot_on_failure: Option<PathBuf>,
+    /// Reuse the cookies of the last login while they're logged in, they're cached in the file
+    /// [default: $XDG_CACHE_HOME/pinterest-login/cookies.json]
+    ///
+    /// The cached cookies are verified before they're reused, and they're replaced after every login.
+    /// The file is only readable by its owner, and the cookies of another account or `--login-url` aren't reused
+    #[arg(long, value_name = "PATH", num_args = 0..=1)]
+    cache: Option<Option<PathBuf>>,
+    /// How long the cached cookies are reused, like `12h` or `30m` [default: 24h]
+    #[arg(long, value_name = "TIME", value_parser = parse_duration, requires = "cache")]
+    cache_ttl: Option<Duration>,
+    /// Log in even if the cached cookies are still logged in, the cache gets the new cookies
+    #[arg(long, requires = "cache")]
+    force_login: bool,
+    /// The saved cookies to print with `--cookie`, like `export`
+    #[arg(
+        short,
+        long,
+        value_name = "PATH",
+        hide = true,
+        requires = "cookies",
+        conflicts_with_all = ["password_stdin", "password_file", "cache"]
+    )]
+    input: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct VerifyArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    browser: BrowserArgs,
+    /// The cookies to verify, the JSON output of the login (with or without `--full`)
+    #[arg(short, long, value_name = "PATH")]
+    input: PathBuf,
+    /// The format of the report
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = ReportFormat::Debug)]
+    format: ReportFormat,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Debug, clap::Args)]
+struct RefreshArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    browser: BrowserArgs,
+    #[command(flatten)]
+    credentials: CredentialArgs,
+    /// The saved cookies, the JSON output of the login (with or without `--full`)
+    #[arg(short, long, value_name = "PATH")]
+    input: PathBuf,
+    /// Write the new cookies to the file instead of replacing the input (`-` is stdout)
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Create the parent directories of the output file
+    #[arg(long)]
+    mkdir: bool,
+    /// Write the full cookies (domain, path, expiry, flags...) instead of a name to value object
+    #[arg(long)]
+    full: bool,
+    /// Save a screenshot of the page into the directory when the login fails on it
+    #[arg(long, value_name = "DIR")]
+    screenshot_on_failure: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct ExportArgs {
+    /// The saved cookies, the JSON output of the login (with or without `--full`)
+    ///
+    /// The details that a name to value object doesn't have are the ones of a session cookie of
+    /// `.pinterest.com`
+    #[arg(short, long, value_name = "PATH")]
+    input: PathBuf,
+    #[command(flatten)]
+    format: CookieFormatArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Debug, clap::Args)]
+struct BatchCommandArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    browser: BrowserArgs,
+    #[command(flatten)]
+    batch: batch::BatchArgs,
+    /// Write the full cookies (domain, path, expiry, flags...) instead of a name to value object
+    #[arg(long)]
+    full: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct WatchCommandArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    browser: BrowserArgs,
+    #[command(flatten)]
+    credentials: CredentialArgs,
+    #[command(flatten)]
+    watch: watch::WatchArgs,
+    /// The file of the cookies, its cookies are verified and it gets the new ones
+    #[arg(short, long, value_name = "PATH")]
+    output: PathBuf,
+    /// Create the parent directories of the output file
+    #[arg(long)]
+    mkdir: bool,
+    /// Write the full cookies (domain, path, expiry, flags...) instead of a name to value object
+    #[arg(long)]
+    full: bool,
+    /// Save a screenshot of the page into the directory when a login fails on it
+    #[arg(long, value_name = "DIR")]
+    screenshot_on_failure: Option<PathBuf>,
+}
+
+#[cfg(feature = "auth")]
+#[derive(Debug, clap::Args)]
+struct AuthArgs {
+    #[command(subcommand)]
+    action: AuthAction,
+}
+
+#[cfg(feature = "auth")]
+#[derive(Debug, clap::Subcommand)]
+enum AuthAction {
+    /// Prompt for the password and save it in the keyring, it replaces the saved one
+    Store {
+        #[command(flatten)]
+        common: CommonArgs,
+        /// Read the password from the first line of stdin instead of prompting for it
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Delete the password from the keyring
+    Forget {
+        #[command(flatten)]
+        common: CommonArgs,
+    },
+}
+
+#[cfg(feature = "serve")]
+#[derive(Debug, clap::Args)]
+struct ServeCommandArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    browser: BrowserArgs,
+    #[command(flatten)]
+    credentials: CredentialArgs,
+    #[command(flatten)]
+    serve: serve::ServeArgs,
+    /// Save a screenshot of the page into the directory when a login fails on it
+    #[arg(long, value_name = "DIR")]
+    screenshot_on_failure: Option<PathBuf>,
+}
+
+/// The subcommands of the binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Logs in and prints the cookies
+    Login,
+    /// Checks if the saved cookies are still logged in
+    Verify,
+    /// Logs in again if the saved cookies aren't logged in anymore
+    Refresh,
+    /// Prints the saved cookies
+    Export,
+    /// Logs in a list of accounts
+    Batch,
+    /// Keeps a cookie file logged in
+    Watch,
+    /// Answers the cookies over HTTP, and keeps them logged in
+    #[cfg(feature = "serve")]
+    Serve,
+    /// Saves the password in the keyring
+    #[cfg(feature = "auth")]
+    AuthStore,
+    /// Deletes the password from the keyring
+    #[cfg(feature = "auth")]
+    AuthForget,
+    /// Prints the completion script of the shell
+    Completions(Shell),
+}
+
+/// How the cookies are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// The debug representation of the cookies map, for humans
+    Debug,
+    /// A JSON object of the cookie names and values, or with `--full` a JSON array of the full cookies,
+    /// recommended for scripts
+    Json,
+    /// A Netscape cookie file, that curl and wget can load (`curl -b`)
+    Netscape,
+    /// Shell `export` lines, for `eval "$(pintrest-login --format env)"`
+    Env,
+    /// A curl command with the cookies, the CSRF token and a user agent, to call the API from a shell
+    Curl,
+}
+
+/// How the logs are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// The colored lines of pretty_env_logger, the colors are disabled when stderr isn't a terminal
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// How the report of `verify` is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    /// The debug representation of the report, for humans
+    Debug,
+    /// A JSON object with the validity and the times as unix timestamps
+    Json,
+}
+
+/// The parsed command line, the options that a command doesn't have keep their default
+#[derive(Debug)]
+pub struct Args {
+    pub command: Command,
+    pub config: Option<PathBuf>,
+    pub allow_insecure_config: bool,
+    pub password_stdin: bool,
+    pub password_file: Option<PathBuf>,
+    pub headless: Option<bool>,
+    pub headful_fallback: bool,
+    pub fallback_deadline: Option<Duration>,
+    pub manual: bool,
+    pub manual_timeout: Option<Duration>,
+    pub timeout: Option<Duration>,
+    pub launch_timeout: Option<Duration>,
+    pub proxy: Option<String>,
+    pub totp_secret: Option<TotpSecret>,
+    pub format: Format,
+    pub full: bool,
+    /// The names of `--cookie`, in their order
+    pub cookies: Vec<String>,
+    pub curl_url: Option<String>,
+    pub login_url: Option<String>,
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub mkdir: bool,
+    pub screenshot_dir: Option<PathBuf>,
+    pub chrome_path: Option<PathBuf>,
+    pub user_data_dir: Option<PathBuf>,
+    pub no_incognito: bool,
+    pub retry: StepRetry,
+    /// The cookie cache of `--cache`
+    pub cache: Option<PathBuf>,
+    pub cache_ttl: Duration,
+    pub force_login: bool,
+    /// How many times `-v` was passed
+    pub verbose: u8,
+    pub log_format: LogFormat,
+    pub quiet: bool,
+    /// The options of `batch`, `None` for the other commands
+    pub batch: Option<batch::BatchArgs>,
+    /// The options of `watch`, `None` for the other commands
+    pub watch: Option<watch::WatchArgs>,
+    #[cfg(feature = "serve")]
+    pub serve: serve::ServeArgs,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            command: Command::Login,
+            config: None,
+            allow_insecure_config: false,
+            password_stdin: false,
+            password_file: None,
+            headless: None,
+            headful_fallback: false,
+            manual: false,
+            manual_timeout: None,
+            fallback_deadline: None,
+            timeout: None,
+            launch_timeout: None,
+            proxy: None,
+            totp_secret: None,
+            format: Format::Debug,
+            full: false,
+            cookies: Vec::new(),
+            curl_url: None,
+            login_url: None,
+            input: None,
+            output: None,
+            mkdir: false,
+            screenshot_dir: None,
+            chrome_path: None,
+            user_data_dir: None,
+            no_incognito: false,
+            retry: StepRetry::default(),
+            cache: None,
+            cache_ttl: cache::DEFAULT_TTL,
+            force_login: false,
+            verbose: 0,
+            log_format: LogFormat::Text,
+            quiet: false,
+            batch: None,
+            watch: None,
+            #[cfg(feature = "serve")]
+            serve: serve::ServeArgs::default(),
+        }
+    }
+}
+
+/// The long and the short flags of the commands, and whether they take a value
+#[derive(Default)]
+struct Flags {
+    long: HashMap<String, bool>,
+    short: HashMap<char, bool>,
+}
+
+impl Flags {
+    /// The flags of the command and of its subcommands
+    fn of(command: &clap::Command) -> Self {
+        let mut flags = Self::default();
+        flags.add(command);
+        flags
+    }
+
+    fn add(&mut self, command: &clap::Command) {
+        for arg in command.get_arguments() {
+            let takes_value = arg.get_action().takes_values();
+            for long in arg.get_long_and_visible_aliases().into_iter().flatten() {
+                self.long.insert(long.to_string(), takes_value);
+            }
+            for short in arg.get_short_and_visible_aliases().into_iter().flatten() {
+                self.short.insert(short, takes_value);
+            }
+        }
+        for subcommand in command.get_subcommands() {
+            self.add(subcommand);
+        }
+    }
+
+    /// Whether the next word is the value of the flag, `None` if it isn't a flag
+    fn takes_next(&self, flag: &str) -> Option<bool> {
+        if let Some(long) = flag.strip_prefix("--") {
+            return match long.split_once('=') {
+                Some((name, _)) => self.long.get(name).map(|_| false),
+                None => self.long.get(long).copied(),
+            };
+        }
+        // A cluster of short flags, the value of the first one that takes one is the rest of the word, or the next
+        // word if it's the last one
+        let mut shorts = flag.strip_prefix('-')?.chars();
+        while let Some(short) = shorts.next() {
+            if *self.short.get(&short)? {
+                return Some(shorts.as_str().is_empty());
+            }
+        }
+        Some(false)
+    }
+}
+
+/// Rewrites the long flags with a single dash into their `--` form, so the scripts written for the previous parser
+/// keep working: clap reads `-head` as `-h` (the help) followed by `ead`. The values of the flags, like the `-head`
+/// of `--output -head`, are left alone
+fn legacy_flags<I, T>(args: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut command = Cli::command();
+    command.build();
+    let flags = Flags::of(&command);
+    let mut args = args.into_iter().map(Into::into);
+    let mut rewritten: Vec<OsString> = args.next().into_iter().collect();
+    let mut value = false;
+    for arg in args.by_ref() {
+        let Some(word) = arg.to_str().filter(|_| !value) else {
+            value = false;
+            rewritten.push(arg);
+            continue;
+        };
+        if word == "--" {
+            rewritten.push(arg);
+            break;
+        }
+        let word = match word.strip_prefix('-') {
+            Some(name)
+                if !name.starts_with('-')
+                    && flags
+                        .long
+                        .contains_key(name.split_once('=').map_or(name, |(name, _)| name)) =>
+            {
+                format!("-{word}")
+            }
+            _ => word.to_string(),
+        };
+        value = word.starts_with('-') && flags.takes_next(&word).unwrap_or(false);
+        rewritten.push(word.into());
+    }
+    rewritten.extend(args);
+    rewritten
+}
+
+/// Parses the arguments, with the binary name. The help and the version are errors too, like clap has them
+pub fn parse_args<I, T>(args: I) -> Result<Args, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let cli = Cli::try_parse_from(legacy_flags(args))?;
+    let mut args = Args::default();
+    match cli.command.unwrap_or(Subcommand::Login(cli.login)) {
+        Subcommand::Login(login) => {
+            login.common.apply(&mut args);
+            login.browser.apply(&mut args);
+            login.credentials.apply(&mut args);
+            login.format.apply(&mut args)?;
+            login.output.apply(&mut args);
+            args.headful_fallback = login.headful_fallback;
+            args.fallback_deadline = login.fallback_deadline;
+            args.manual = login.manual;
+            args.manual_timeout = login.manual_timeout;
+            args.screenshot_dir = login.screenshot_on_failure;
+            args.cache = match login.cache {
+                Some(Some(path)) => Some(path),
+                Some(None) => Some(cache::default_path().ok_or_else(|| {
+                    Cli::command().error(
+                        ErrorKind::InvalidValue,
+                        "There's no cache directory, set XDG_CACHE_HOME or pass a path to `--cache`",
+                    )
+                })?),
+                None => None,
+            };
+            if let Some(ttl) = login.cache_ttl {
+                args.cache_ttl = ttl;
+            }
+            args.force_login = login.force_login;
+            // The saved cookies are printed like `export` does
+            if login.input.is_some() {
+                args.command = Command::Export;
+                args.input = login.input;
+            }
+        }
+        Subcommand::Verify(verify) => {
+            args.command = Command::Verify;
+            verify.common.apply(&mut args);
+            verify.browser.apply(&mut args);
+            verify.output.apply(&mut args);
+            args.input = Some(verify.input);
+            args.format = match verify.format {
+                ReportFormat::Debug => Format::Debug,
+                ReportFormat::Json => Format::Json,
+            };
+        }
+        Subcommand::Refresh(refresh) => {
+            args.command = Command::Refresh;
+            refresh.common.apply(&mut args);
+            // It's meant for cron, the exit code tells what happened
+            args.quiet = args.verbose == 0;
+            refresh.browser.apply(&mut args);
+            refresh.credentials.apply(&mut args);
+            args.format = Format::Json;
+            args.full = refresh.full;
+            args.output = match refresh.output {
+                Some(path) => stdout_or_file(path),
+                None => Some(refresh.input.clone()),
+            };
+            args.input = Some(refresh.input);
+            args.mkdir = refresh.mkdir;
+            args.screenshot_dir = refresh.screenshot_on_failure;
+        }
+        Subcommand::Export(export) => {
+            args.command = Command::Export;
+            export.format.apply(&mut args)?;
+            export.output.apply(&mut args);
+            args.input = Some(export.input);
+        }
+        Subcommand::Batch(batch) => {
+            args.command = Command::Batch;
+            batch.common.apply(&mut args);
+            batch.browser.apply(&mut args);
+            if args.user_data_dir.is_some() {
+                return Err(Cli::command().error(
+                    ErrorKind::ArgumentConflict,
+                    "`--user-data-dir` can't be used with `batch`, the accounts would share the profile",
+                ));
+            }
+            args.batch = Some(batch.batch);
+            args.full = batch.full;
+        }
+        Subcommand::Watch(watch) => {
+            args.command = Command::Watch;
+            watch.common.apply(&mut args);
+            watch.browser.apply(&mut args);
+            watch.credentials.apply(&mut args);
+            args.watch = Some(watch.watch);
+            args.format = Format::Json;
+            args.full = watch.full;
+            args.output = Some(watch.output);
+            args.mkdir = watch.mkdir;
+            args.screenshot_dir = watch.screenshot_on_failure;
+        }
+        #[cfg(feature = "serve")]
+        Subcommand::Serve(serve) => {
+            args.command = Command::Serve;
+            serve.common.apply(&mut args);
+            serve.browser.apply(&mut args);
+            serve.credentials.apply(&mut args);
+            args.serve = serve.serve;
+            args.screenshot_dir = serve.screenshot_on_failure;
+        }
+        #[cfg(feature = "auth")]
+        Subcommand::Auth(auth) => match auth.action {
+            AuthAction::Store {
+                common,
+                password_stdin,
+            } => {
+                args.command = Command::AuthStore;
+                common.apply(&mut args);
+                args.password_stdin = password_stdin;
+            }
+            AuthAction::Forget { common } => {
+                args.command = Command::AuthForget;
+                common.apply(&mut args);
+            }
+        },
+        Subcommand::Completions { shell } => args.command = Command::Completions(shell),
+        #[cfg(not(feature = "serve"))]
+        Subcommand::Serve { .. } => {
+            return Err(Cli::command().error(
+                ErrorKind::InvalidSubcommand,
+                "`serve` needs the binary to be built with the `serve` feature",
+            ))
+        }
+    }
+    Ok(args)
+}
+
+impl CommonArgs {
+    fn apply(self, args: &mut Args) {
+        args.config = self.config;
+        args.allow_insecure_config = self.allow_insecure_config;
+        args.verbose = self.verbose;
+        args.log_format = self.log_format;
+        args.quiet = self.quiet;
+    }
+}
+
+impl BrowserArgs {
+    fn apply(self, args: &mut Args) {
+        args.headless = match (self.head, self.headless) {
+            (true, _) => Some(false),
+            (_, true) => Some(true),
+            _ => None,
+        };
+        args.timeout = self.timeout;
+        args.launch_timeout = self.launch_timeout;
+        args.proxy = self.proxy;
+        args.chrome_path = self.chrome_path;
+        args.no_incognito = self.no_incognito || self.user_data_dir.is_some();
+        args.user_data_dir = self.user_data_dir;
+        args.login_url = self.login_url;
+        if let Some(retries) = self.retries {
+            args.retry.retries = retries;
+        }
+        if let Some(delay) = self.retry_delay {
+            args.retry.delay = delay;
+        }
+    }
+}
+
+impl CredentialArgs {
+    fn apply(self, args: &mut Args) {
+        args.password_stdin = self.password_stdin;
+        args.password_file = self.password_file;
+        args.totp_secret = self.totp_secret;
+    }
+}
+
+impl CookieFormatArgs {
+    fn apply(self, args: &mut Args) -> Result<(), clap::Error> {
+        if self.full && self.format != Format::Json {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                "`--full` only works with `--format json`",
+            ));
+        }
+        if self.curl_url.is_some() && self.format != Format::Curl {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                "`--curl-url` only works with `--format curl`",
+            ));
+        }
+        args.format = self.format;
+        args.full = self.full;
+        args.cookies = self.cookies;
+        args.curl_url = self.curl_url;
+        Ok(())
+    }
+}
+
+impl OutputArgs {
+    fn apply(self, args: &mut Args) {
+        args.output = self.output.and_then(stdout_or_file);
+        args.mkdir = self.mkdir;
+    }
+}
+
+/// Writes the completion script of the command line for the shell
+pub fn completions(shell: Shell, out: &mut dyn io::Write) {
+    clap_complete::generate(shell, &mut Cli::command(), env!("CARGO_BIN_NAME"), out);
+}
+
+/// `None` for `-`, the stdout
+fn stdout_or_file(path: PathBuf) -> Option<PathBuf> {
+    (path.as_os_str() != "-").then_some(path)
+}
+
+/// Parses a duration like `1500ms`, `45s`, `2m`, `1h` or `1h 30m` (the forms of humantime), a number without
+/// a unit is in seconds. The numbers can have decimals when there's a single unit, like `1.5s`
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = |reason: &dyn std::fmt::Display| {
+        format!(
+            "`{value}` isn't a duration ({reason}), expected seconds like `45` or a number with a unit \
+             like `1500ms`, `45s`, `2m`, `1h` or `1h 30m`"
+        )
+    };
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let duration =
+        if unit.is_empty() || (number.contains('.') && unit.chars().all(char::is_alphabetic)) {
+            let number: f64 = number.parse().map_err(|_| invalid(&"not a number"))?;
+            let unit = match unit {
+                "" => Duration::from_secs(1),
+                unit => humantime::parse_duration(&format!("1{unit}")).map_err(|e| invalid(&e))?,
+            };
+            unit.mul_f64(number)
+        } else {
+            humantime::parse_duration(value).map_err(|e| invalid(&e))?
+        };
+    Ok(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        parse_args(["pintrest-login"].iter().chain(args))
+    }
+
+    #[test]
+    fn the_command_line_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn the_single_dash_long_flags_still_work() {
+        let args = parse(&[
+            "-head",
+            "-timeout",
+            "5",
+            "-format=json",
+            "-config",
+            "c.toml",
+        ])
+        .unwrap();
+        assert_eq!(args.headless, Some(false));
+        assert_eq!(args.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(args.format, Format::Json);
+        assert_eq!(args.config, Some("c.toml".into()));
+
+        let args = parse(&["verify", "-input", "cookies.json", "-quiet"]).unwrap();
+        assert_eq!(args.input, Some("cookies.json".into()));
+        assert!(args.quiet);
+        let args = parse(&["-user-data-dir", "profile"]).unwrap();
+        assert_eq!(args.user_data_dir, Some("profile".into()));
+        // The short flags and their clusters are left alone
+        assert_eq!(parse(&["-vv", "-t", "2"]).unwrap().verbose, 2);
+        assert_eq!(
+            legacy_flags(["-q", "--", "-head"]),
+            ["-q", "--", "-head"].map(OsString::from)
+        );
+        // So are the values of the flags
+        assert_eq!(
+            legacy_flags([
+                "pintrest-login",
+                "--format",
+                "-format",
+                "-o",
+                "-head",
+                "-vt",
+                "-timeout"
+            ]),
+            [
+                "pintrest-login",
+                "--format",
+                "-format",
+                "-o",
+                "-head",
+                "-vt",
+                "-timeout"
+            ]
+            .map(OsString::from)
+        );
+        assert_eq!(
+            legacy_flags(["pintrest-login", "-output=-head", "-t5", "-head"]),
+            ["pintrest-login", "--output=-head", "-t5", "--head"].map(OsString::from)
+        );
+    }
+
+    #[test]
+    fn login_is_the_default_command() {
+        for args in [&[][..], &["login"], &["--format", "json"], &["login", "-q"]] {
+            assert_eq!(parse(args).unwrap().command, Command::Login, "{args:?}");
+        }
+        // The options of `login` go after it
+        assert!(parse(&["-q", "login"]).is_err());
+    }
+
+    #[test]
+    fn the_subcommands_map_to_their_command() {
+        let args = parse(&["verify", "-i", "cookies.json", "--format", "json"]).unwrap();
+        assert_eq!(args.command, Command::Verify);
+        assert_eq!(args.input, Some("cookies.json".into()));
+        assert_eq!(args.format, Format::Json);
+
+        let args = parse(&["export", "--input", "cookies.json", "--format", "netscape"]).unwrap();
+        assert_eq!(args.command, Command::Export);
+        assert_eq!(args.format, Format::Netscape);
+
+        // The saved cookies are printed by `login --cookie` too
+        let args = parse(&["-i", "cookies.json", "--cookie", "csrftoken"]).unwrap();
+        assert_eq!(args.command, Command::Export);
+        assert_eq!(args.cookies, ["csrftoken"]);
+    }
+
+    #[test]
+    fn refresh_replaces_the_input_by_default() {
+        let args = parse(&["refresh", "-i", "cookies.json"]).unwrap();
+        assert_eq!(args.command, Command::Refresh);
+        assert_eq!(args.format, Format::Json);
+        assert_eq!(args.output, Some("cookies.json".into()));
+
+        let args = parse(&["refresh", "-i", "cookies.json", "-o", "new.json", "--full"]).unwrap();
+        assert_eq!(args.output, Some("new.json".into()));
+        assert!(args.full);
+
+        let args = parse(&["refresh", "-i", "cookies.json", "-o", "-"]).unwrap();
+        assert_eq!(args.output, None);
+    }
+
+    #[test]
+    fn refresh_is_quiet_unless_verbose() {
+        assert!(parse(&["refresh", "-i", "cookies.json"]).unwrap().quiet);
+        assert!(
+            !parse(&["refresh", "-i", "cookies.json", "-v"])
+                .unwrap()
+                .quiet
+        );
+        assert!(!parse(&["verify", "-i", "cookies.json"]).unwrap().quiet);
+    }
+
+    #[test]
+    fn the_options_are_only_accepted_by_their_commands() {
+        for args in [
+            &["verify"][..],
+            &["verify", "-i", "cookies.json", "--format", "netscape"],
+            &["verify", "-i", "cookies.json", "--password-stdin"],
+            &["export", "-i", "cookies.json", "--head"],
+            &["export"],
+            &["refresh", "-i", "cookies.json", "--format", "env"],
+            &["refresh"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn the_log_format_is_independent_of_the_output_format() {
+        let args = parse(&["--log-format", "json", "--format", "netscape"]).unwrap();
+        assert_eq!(args.log_format, LogFormat::Json);
+        assert_eq!(args.format, Format::Netscape);
+
+        assert_eq!(parse(&[]).unwrap().log_format, LogFormat::Text);
+        let args = parse(&["verify", "-i", "cookies.json", "--log-format", "json"]).unwrap();
+        assert_eq!(args.log_format, LogFormat::Json);
+        assert!(parse(&["--log-format", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn the_completions_have_every_subcommand() {
+        let args = parse(&["completions", "powershell"]).unwrap();
+        assert_eq!(args.command, Command::Completions(Shell::PowerShell));
+        assert!(parse(&["completions", "tcsh"]).is_err());
+
+        let mut bash = Vec::new();
+        completions(Shell::Bash, &mut bash);
+        let bash = String::from_utf8(bash).unwrap();
+        for subcommand in Cli::command().get_subcommands() {
+            let name = subcommand.get_name();
+            assert!(bash.contains(&format!("pintrest__login,{name})")), "{name}");
+        }
+        for format in Format::value_variants() {
+            let name = format.to_possible_value().unwrap();
+            assert!(bash.contains(name.get_name()), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn the_watch_options() {
+        let args = parse(&["watch", "-o", "cookies.json"]).unwrap();
+        assert_eq!(args.command, Command::Watch);
+        assert_eq!(args.watch, Some(watch::WatchArgs::default()));
+        assert_eq!(args.output, Some("cookies.json".into()));
+        assert_eq!(args.format, Format::Json);
+
+        let args = parse(&[
+            "watch",
+            "--output",
+            "sessions/cookies.json",
+            "--mkdir",
+            "--interval",
+            "30m",
+            "--max-failures",
+            "5",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.watch,
+            Some(watch::WatchArgs {
+                interval: Duration::from_secs(30 * 60),
+                max_failures: Some(5),
+            })
+        );
+        assert!(args.mkdir);
+
+        assert!(parse(&["watch"]).is_err());
+        assert!(parse(&["watch", "-o", "c.json", "--max-failures", "0"]).is_err());
+        assert!(parse(&["watch", "-o", "c.json", "--interval", "soon"]).is_err());
+    }
+
+    #[test]
+    fn the_batch_options() {
+        let args = parse(&[
+            "batch",
+            "--accounts",
+            "accounts.csv",
+            "--out-dir",
+            "sessions",
+            "--concurrency",
+            "3",
+        ])
+        .unwrap();
+        assert_eq!(args.command, Command::Batch);
+        assert_eq!(
+            args.batch,
+            Some(batch::BatchArgs {
+                accounts: "accounts.csv".into(),
+                concurrency: 3,
+                out_dir: "sessions".into(),
+            })
+        );
+
+        let args = parse(&["batch", "--accounts", "a.csv", "--out-dir", "s"]).unwrap();
+        assert_eq!(args.batch.unwrap().concurrency, 1);
+        assert!(parse(&["batch", "--accounts", "a.csv"]).is_err());
+        assert!(parse(&[
+            "batch",
+            "--accounts",
+            "a.csv",
+            "--out-dir",
+            "s",
+            "--concurrency",
+            "0"
+        ])
+        .is_err());
+        assert!(parse(&[
+            "batch",
+            "--accounts",
+            "a.csv",
+            "--out-dir",
+            "s",
+            "--cookie",
+            "a"
+        ])
+        .is_err());
+        let err = parse(&[
+            "batch",
+            "--accounts",
+            "a.csv",
+            "--out-dir",
+            "s",
+            "--user-data-dir",
+            "profile",
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("share the profile"), "{err}");
+    }
+
+    #[test]
+    fn the_manual_options() {
+        let args = parse(&["--manual", "--format", "json", "-o", "session.json"]).unwrap();
+        assert!(args.manual);
+        assert_eq!(args.manual_timeout, None);
+        assert_eq!(args.output, Some("session.json".into()));
+
+        let args = parse(&["--manual", "--manual-timeout", "10m"]).unwrap();
+        assert_eq!(args.manual_timeout, Some(Duration::from_secs(600)));
+
+        for args in [
+            &["--manual-timeout", "10m"][..],
+            &["--manual", "--headful-fallback"],
+            &["--manual", "--password-stdin"],
+            &["--manual", "--cache", "cookies.json"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn the_cache_options() {
+        let args = parse(&["--cache", "cookies.json", "--cache-ttl", "12h"]).unwrap();
+        assert_eq!(args.cache, Some("cookies.json".into()));
+        assert_eq!(args.cache_ttl, Duration::from_secs(12 * 60 * 60));
+        assert!(!args.force_login);
+
+        let args = parse(&["--cache", "cookies.json", "--force-login"]).unwrap();
+        assert_eq!(args.cache_ttl, cache::DEFAULT_TTL);
+        assert!(args.force_login);
+
+        assert_eq!(parse(&[]).unwrap().cache, None);
+        assert!(parse(&["--cache-ttl", "1h"]).is_err());
+        assert!(parse(&["--force-login"]).is_err());
+        assert!(parse(&["--cache", "c.json", "-i", "cookies.json", "--cookie", "a"]).is_err());
+    }
+
+    #[test]
+    fn the_unknown_flags_get_a_suggestion() {
+        let err = parse(&["--fromat", "json"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnknownArgument);
+        assert!(err.to_string().contains("--format"), "{err}");
+
+        // The unknown long flags aren't accepted with a single dash either
+        assert!(parse(&["-fromat", "json"]).is_err());
+        assert!(parse(&["--v"]).is_err());
+    }
+
+    #[test]
+    fn the_help_and_the_version_are_errors_to_exit_with() {
+        assert_eq!(
+            parse(&["--help"]).unwrap_err().kind(),
+            ErrorKind::DisplayHelp
+        );
+        assert_eq!(
+            parse(&["verify", "-h"]).unwrap_err().kind(),
+            ErrorKind::DisplayHelp
+        );
+        assert_eq!(
+            parse(&["--version"]).unwrap_err().kind(),
+            ErrorKind::DisplayVersion
+        );
+    }
+
+    #[test]
+    fn durations() {
+        let table = [
+            ("500ms", Duration::from_millis(500)),
+            ("1500ms", Duration::from_millis(1500)),
+            ("2s", Duration::from_secs(2)),
+            ("2", Duration::from_secs(2)),
+            ("45sec", Duration::from_secs(45)),
+            ("1.5s", Duration::from_millis(1500)),
+            ("0.5", Duration::from_millis(500)),
+            ("1m", Duration::from_secs(60)),
+            ("2min", Duration::from_secs(120)),
+            ("12h", Duration::from_secs(12 * 60 * 60)),
+            ("1h 30m", Duration::from_secs(90 * 60)),
+            ("1h30m", Duration::from_secs(90 * 60)),
+            ("1.5h", Duration::from_secs(90 * 60)),
+            ("2days", Duration::from_secs(2 * 24 * 60 * 60)),
+            ("0", Duration::ZERO),
+        ];
+        for (value, duration) in table {
+            assert_eq!(parse_duration(value), Ok(duration), "{value}");
+        }
+        for value in ["", "ms", "soon", "5 parsecs", "1..5s", "-1", "1.5h 30m"] {
+            let err = parse_duration(value).unwrap_err();
+            assert!(
+                err.starts_with(&format!("`{value}` isn't a duration")),
+                "{err}"
+            );
+            assert!(err.contains("like `45` or a number with a unit"), "{err}");
+        }
+    }
+
+    #[test]
+    fn every_duration_flag_takes_a_duration() {
+        let args = parse(&[
+            "--timeout",
+            "1500ms",
+            "--launch-timeout",
+            "45s",
+            "--retry-delay",
+            "2",
+            "--cache",
+            "cache.json",
+            "--cache-ttl",
+            "1h 30m",
+        ])
+        .unwrap();
+        assert_eq!(args.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(args.launch_timeout, Some(Duration::from_secs(45)));
+        assert_eq!(args.retry.delay, Duration::from_secs(2));
+        assert_eq!(args.cache_ttl, Duration::from_secs(90 * 60));
+
+        let args = parse(&["--manual", "--manual-timeout", "2m"]).unwrap();
+        assert_eq!(args.manual_timeout, Some(Duration::from_secs(120)));
+        let args = parse(&["--headful-fallback", "--fallback-deadline", "90"]).unwrap();
+        assert_eq!(args.fallback_deadline, Some(Duration::from_secs(90)));
+
+        let err = parse(&["--timeout", "soon"]).unwrap_err().to_string();
+        assert!(err.contains("--timeout"), "{err}");
+        assert!(err.contains("`soon` isn't a duration"), "{err}");
+    }
+}
@@ -0,0 +1,401 @@
+//! The config file of the binary, it's a flat TOML file:
+//!
+//! ```toml
+//! email = "me@example.com"
+//! password_command = ["pass", "show", "pinterest"]
+//! headless = true
+//! timeout = 5
+//! proxy = "socks5://127.0.0.1:1080"
+//! chrome_path = "/opt/thorium/thorium"
+//! ```
+//!
+//! The unknown keys and the tables are rejected, the errors name the key.
+//!
+//! The `password_command` is run without a shell: it's the program and its arguments, as an array or as a string
+//! that [`split_command`] splits like a shell would, but without expanding anything. A pipe needs an explicit
+//! `["sh", "-c", "..."]`.
+
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use pinterest_login::credentials::{
+    CommandCredentialsProvider, Credentials, CredentialsError, CredentialsProvider,
+};
+use serde::Deserialize;
+
+/// The keys of the config file, every key is optional
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub email: Option<String>,
+    pub password: Option<String>,
+    /// A command that prints the password on its first line, e.g. `["pass", "show", "pinterest"]`, see
+    /// [`run_password_command`]
+    pub password_command: Option<Vec<String>>,
+    pub headless: Option<bool>,
+    pub timeout: Option<u64>,
+    pub proxy: Option<String>,
+    pub chrome_path: Option<PathBuf>,
+}
+
+/// The config file as it's written, before the checks of [`Config::parse`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    email: Option<String>,
+    password: Option<String>,
+    password_command: Option<Command>,
+    headless: Option<bool>,
+    timeout: Option<u64>,
+    proxy: Option<String>,
+    chrome_path: Option<PathBuf>,
+}
+
+/// A `password_command`, the program and its arguments, or a command line for [`split_command`]
+#[derive(Debug, Deserialize)]
+#[serde(untagged, expecting = "a string or an array of strings")]
+enum Command {
+    Args(Vec<String>),
+    Line(String),
+}
+
+/// `$XDG_CONFIG_HOME/pinterest-login/config.toml`, or `~/.config/pinterest-login/config.toml`
+pub fn default_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("pinterest-login").join("config.toml"))
+}
+
+impl Config {
+    /// Loads the config file, it's rejected if the others can read it (on unix) unless `allow_insecure` is `true`,
+    /// since it may have the password
+    pub fn load(path: &Path, allow_insecure: bool) -> Result<Self, String> {
+        let file = fs::File::open(path).map_err(|e| format!("Can't open the config file: {e}"))?;
+        #[cfg(unix)]
+        if !allow_insecure {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = file
+                .metadata()
+                .map_err(|e| format!("Can't read the config file: {e}"))?
+                .permissions()
+                .mode();
+            if mode & 0o044 != 0 {
+                return Err(format!(
+                    "The config file can be read by the other users (mode {:o}), run `chmod 600` on it \
+                     or pass `--allow-insecure-config`",
+                    mode & 0o777
+                ));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = allow_insecure;
+        let contents = std::io::read_to_string(file)
+            .map_err(|e| format!("Can't read the config file: {e}"))?;
+        Self::parse(&contents)
+    }
+
+    /// Parses the contents of a config file
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let file: File = toml::from_str(contents).map_err(|e| parse_error(contents, &e))?;
+        let password_command = match file.password_command {
+            Some(Command::Args(command)) => Some(command),
+            Some(Command::Line(line)) => {
+                Some(split_command(&line).map_err(|e| format!("`password_command`: {e}"))?)
+            }
+            None => None,
+        };
+        if password_command.as_ref().is_some_and(Vec::is_empty) {
+            return Err("`password_command` can't be empty".to_string());
+        }
+        if file.password.is_some() && password_command.is_some() {
+            return Err("`password` and `password_command` can't be both set".to_string());
+        }
+        Ok(Self {
+            email: file.email,
+            password: file.password,
+            password_command,
+            headless: file.headless,
+            timeout: file.timeout,
+            proxy: file.proxy,
+            chrome_path: file.chrome_path,
+        })
+    }
+
+    /// The password of the file, running the `password_command` if it's set
+    pub fn password(&self) -> Result<Option<String>, String> {
+        if let Some(password) = &self.password {
+            return Ok(Some(password.clone()));
+        }
+        match &self.password_command {
+            Some(command) => run_password_command(command).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Runs a `password_command`, the program and its arguments, without a shell. The password is the first line that
+/// it prints, like `pass` prints it
+pub fn run_password_command(command: &[String]) -> Result<String, String> {
+    let (program, args) = command
+        .split_first()
+        .ok_or("The `password_command` is empty")?;
+    let provider = CommandCredentialsProvider::new(program)
+        .args(args)
+        .parser(|output| {
+            // Only the line break isn't part of the password, even if it's empty
+            let password = output.lines().next().unwrap_or_default();
+            Ok(Credentials::new(password))
+        });
+    // Nothing else runs until the password is there
+    let credentials = futures::executor::block_on(provider.credentials()).map_err(|e| match e {
+        CredentialsError::Spawn { source, .. } => {
+            format!("Can't run the `password_command`: {source}")
+        }
+        CredentialsError::Failed { status, stderr, .. } => {
+            format!("The `password_command` failed ({status}): {stderr}")
+        }
+        CredentialsError::TimedOut { timeout, .. } => {
+            format!("The `password_command` didn't exit within {timeout:?}")
+        }
+        e => format!("The `password_command` failed: {e}"),
+    })?;
+    Ok(credentials.password)
+}
+
+/// Splits a command line in the program and its arguments, without a shell: the words are separated by the
+/// whitespace, the single quotes keep everything as it is, the double quotes keep everything but `\"` and `\\`,
+/// and a backslash outside of the quotes escapes the next character. Nothing is expanded, so a `$`, a `;` or a `|`
+/// is passed to the program as it is.
+pub fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars.next().ok_or("trailing backslash")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// The error of toml with the key of its line, the line is in the message otherwise
+fn parse_error(contents: &str, error: &toml::de::Error) -> String {
+    let message = match error.message().trim() {
+        "" => "missing value".to_string(),
+        message => message.replace('\n', ", "),
+    };
+    let Some(span) = error.span() else {
+        return message;
+    };
+    let start = contents[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_number = contents[..start].lines().count() + 1;
+    let line = contents[start..].lines().next().unwrap_or_default();
+    match line.split_once('=').map(|(key, _)| key.trim()) {
+        Some(key) if !key.is_empty() && !key.starts_with(['[', '#']) => {
+            if message.contains(&format!("`{key}`")) {
+                format!("{message} on line {line_number}")
+            } else {
+                format!("`{key}`: {message}")
+            }
+        }
+        _ => format!("line {line_number}: {message}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_config_file_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let contents = r#"
+# The account
+email = "me@example.com"
+password = "p\"ss w#rd é"  # with a comment
+headless = false
+timeout = 1_0
+proxy = 'socks5://127.0.0.1:1080'
+chrome_path = "/opt/thorium/thorium"
+"#;
+        pinterest_login::store::write_atomic(&path, contents.as_bytes(), false).unwrap();
+
+        let config = Config::load(&path, false).unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                email: Some("me@example.com".into()),
+                password: Some("p\"ss w#rd é".into()),
+                password_command: None,
+                headless: Some(false),
+                timeout: Some(10),
+                proxy: Some("socks5://127.0.0.1:1080".into()),
+                chrome_path: Some("/opt/thorium/thorium".into()),
+            }
+        );
+        assert_eq!(config.password().unwrap().as_deref(), Some("p\"ss w#rd é"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_readable_config_file_is_rejected_unless_allowed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "email = \"me@example.com\"\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let err = Config::load(&path, false).unwrap_err();
+        assert!(err.contains("--allow-insecure-config"), "{err}");
+
+        let config = Config::load(&path, true).unwrap();
+        assert_eq!(config.email.as_deref(), Some("me@example.com"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_password_command_prints_the_password() {
+        let config = Config::parse(r#"password_command = ["printf", 'hunter2\n']"#).unwrap();
+        assert_eq!(config.password().unwrap().as_deref(), Some("hunter2"));
+
+        // The next lines of `pass` are the metadata of the entry
+        let config =
+            Config::parse(r#"password_command = "printf 'hunter2\nurl: pinterest.com\n'""#)
+                .unwrap();
+        assert_eq!(config.password().unwrap().as_deref(), Some("hunter2"));
+
+        // A shell has to be asked for explicitly
+        let config = Config::parse(
+            r#"password_command = ["sh", "-c", "echo 'no entry' >&2; exit 3"]  # with a comment"#,
+        )
+        .unwrap();
+        let err = config.password().unwrap_err();
+        assert!(err.contains("no entry"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_password_command_has_no_shell() {
+        let config =
+            Config::parse(r#"password_command = "printf %s $HOME;echo|cat `id` *""#).unwrap();
+        assert_eq!(
+            config.password().unwrap().as_deref(),
+            Some("$HOME;echo|cat`id`*")
+        );
+    }
+
+    #[test]
+    fn the_commands_are_split_like_a_shell_does() {
+        let table = [
+            ("pass show pinterest", &["pass", "show", "pinterest"][..]),
+            ("  op  read\t'op://a b/c' ", &["op", "read", "op://a b/c"]),
+            (
+                r#"a "b \"c\" \$d" e\ f ''"#,
+                &["a", r#"b "c" \$d"#, "e f", ""],
+            ),
+            ("x;y|z $HOME", &["x;y|z", "$HOME"]),
+            ("", &[]),
+        ];
+        for (command, expected) in table {
+            assert_eq!(split_command(command).unwrap(), expected, "{command:?}");
+        }
+        for command in ["'unterminated", "\"unterminated", "trailing\\"] {
+            assert!(split_command(command).is_err(), "{command:?}");
+        }
+    }
+
+    #[test]
+    fn the_errors_name_the_key() {
+        let table = [
+            (
+                "timeout = \"5\"",
+                "`timeout`: invalid type: string \"5\", expected u64",
+            ),
+            ("timeout = -5", "`timeout`: invalid value: integer `-5`"),
+            (
+                "headless = 1",
+                "`headless`: invalid type: integer `1`, expected a boolean",
+            ),
+            (
+                "email = true",
+                "`email`: invalid type: boolean `true`, expected a string",
+            ),
+            ("proxy = \"unterminated", "`proxy`: invalid basic string"),
+            ("chrome_path = ", "`chrome_path`: missing value"),
+            (
+                "\nemial = \"me@example.com\"",
+                "unknown field `emial`, expected one of `email`",
+            ),
+            ("\nemial = \"me@example.com\"", "on line 2"),
+            ("email = \"a\" \"b\"", "`email`: expected newline"),
+            ("[account]", "line 1: unknown field `account`"),
+            (
+                "password = \"a\"\npassword_command = \"b\"",
+                "`password` and `password_command` can't be both set",
+            ),
+            ("password_command = []", "`password_command` can't be empty"),
+            (
+                "password_command = [\"a\" \"b\"]",
+                "`password_command`: invalid array, expected `]`",
+            ),
+            (
+                "password_command = [\"a\", 1]",
+                "`password_command`: a string or an array of strings",
+            ),
+            (
+                "password_command = [\"a\"",
+                "`password_command`: invalid array",
+            ),
+            (
+                "password_command = \"a 'b\"",
+                "`password_command`: unterminated single quote",
+            ),
+            (
+                "email = [\"a\"]",
+                "`email`: invalid type: sequence, expected a string",
+            ),
+        ];
+        for (contents, expected) in table {
+            let err = Config::parse(contents).unwrap_err();
+            assert!(err.contains(expected), "{contents:?}: {err}");
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! The locks of the binary: `refresh` locks the cookie file, so the refreshes of the same file (like two cron jobs
+//! that overlap) wait for each other instead of both logging in and overwriting each other's cookies, and
+//! `--user-data-dir` locks the profile, that a browser can't share with another one.
+//!
+//! They're OS locks on a `.lock` file, next to the cookies since they're replaced by a rename, or in the profile.
+//! The OS releases them when the process exits, even if it's killed, so there's no stale lock to clean up,
+//! and the `.lock` files are left in place.
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The lock file in the `--user-data-dir` profiles
+const PROFILE_LOCK: &str = "pinterest-login.lock";
+
+/// An exclusive lock of a file or a profile, it's released when it's dropped
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Waits until the lock of the file is free, and takes it.
+    /// With `mkdir` the parent directories of the file are created if they're missing
+    pub fn acquire(path: &Path, mkdir: bool) -> io::Result<Self> {
+        let path = lock_path(path);
+        if let Some(dir) = path
+            .parent()
+            .filter(|dir| mkdir && !dir.as_os_str().is_empty())
+        {
+            fs::create_dir_all(dir)?;
+        }
+        let file = lock(&path, true)?;
+        Ok(Self { _file: file })
+    }
+
+    /// Takes the lock of the browser profile, the directory is created if it's missing.
+    /// It fails with [`io::ErrorKind::WouldBlock`] if another process holds it
+    pub fn acquire_profile(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = lock(&dir.join(PROFILE_LOCK), false)?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// `cookies.json.lock` for `cookies.json`
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Locks the file, `wait` to wait for the process that holds it instead of failing with
+/// [`io::ErrorKind::WouldBlock`]
+#[cfg(unix)]
+fn lock(path: &Path, wait: bool) -> io::Result<File> {
+    use rustix::fs::{flock, FlockOperation};
+
+    let file = open(path)?;
+    let operation = if wait {
+        FlockOperation::LockExclusive
+    } else {
+        FlockOperation::NonBlockingLockExclusive
+    };
+    flock(&file, operation)?;
+    Ok(file)
+}
+
+/// Windows has no lock in std, a file opened without sharing can't be opened again until it's closed
+#[cfg(windows)]
+fn lock(path: &Path, wait: bool) -> io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::time::Duration;
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    loop {
+        match OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .share_mode(0)
+            .open(path)
+        {
+            Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => {
+                if !wait {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                std::thread::sleep(Duration::from_millis(100))
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock(path: &Path, _wait: bool) -> io::Result<File> {
+    open(path)
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn the_directory_is_created_with_mkdir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions").join("cookies.json");
+        assert!(FileLock::acquire(&path, false).is_err());
+
+        let _lock = FileLock::acquire(&path, true).unwrap();
+        assert!(dir.path().join("sessions/cookies.json.lock").is_file());
+    }
+
+    #[test]
+    fn the_lock_waits_for_the_previous_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.json");
+        let lock = FileLock::acquire(&path, false).unwrap();
+        assert!(dir.path().join("cookies.json.lock").is_file());
+
+        let (locked, waiter) = mpsc::channel();
+        let other = thread::spawn({
+            let path = path.clone();
+            move || {
+                let _lock = FileLock::acquire(&path, false).unwrap();
+                locked.send(()).unwrap();
+            }
+        });
+        assert!(waiter.recv_timeout(Duration::from_millis(200)).is_err());
+
+        drop(lock);
+        waiter.recv_timeout(Duration::from_secs(5)).unwrap();
+        other.join().unwrap();
+    }
+
+    #[test]
+    fn a_profile_in_use_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = dir.path().join("profiles").join("me");
+
+        let lock = FileLock::acquire_profile(&profile).unwrap();
+        assert!(profile.join(PROFILE_LOCK).is_file());
+        let err = FileLock::acquire_profile(&profile).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        drop(lock);
+        FileLock::acquire_profile(&profile).unwrap();
+    }
+}
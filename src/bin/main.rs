@@ -1,75 +1,1832 @@
-use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
-use pinterest_login::login;
-use pinterest_login::login_bot::DefaultBrowserLoginBot;
-use std::io::{self, Write};
-use std::time::Duration;
+use async_signal::{Signal, Signals};
+use chromiumoxide::cdp::browser_protocol::network::{
+    Cookie, CookieParam, CookiePriority, CookieSourceScheme, TimeSinceEpoch,
+};
+use clap::error::ErrorKind;
+use futures::future::{self, Either};
+use futures::{Future, StreamExt};
+use pinterest_login::auth::AuthSession;
+use pinterest_login::clock::SystemClock;
+use pinterest_login::config_builder::{BrowserConfigBuilder, DefaultBrowserConfigBuilder};
+use pinterest_login::cookies_txt::to_netscape;
+use pinterest_login::fallback::with_headful_fallback;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host, ManualLoginBot, TwoFactor};
+use pinterest_login::options::{LoginOptions, DEFAULT_KEEP_ALIVE_INTERVAL};
+use pinterest_login::retry;
+use pinterest_login::store::write_atomic;
+use pinterest_login::verify::{
+    refresh_session_with_options, verify_cookies, RefreshPath, VerifyReport,
+};
+use pinterest_login::{
+    login_with_options, LoginPhase, PinterestCookies, PinterestLoginError, PINTEREST_LOGIN_URL,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
 use std::{env, process};
 
 #[cfg(feature = "log")]
 extern crate log;
 
+#[cfg(feature = "auth")]
+mod auth;
+mod batch;
+mod cache;
+mod cli;
+mod config;
+mod lock;
+#[cfg(feature = "serve")]
+mod serve;
+mod watch;
+
+use cache::Lookup;
+use cli::{parse_args, Args, Command, Format, LogFormat};
+use config::Config;
+
+/// The exit codes of the binary, they're documented in the help so scripts can rely on them
+mod exit_code {
+    use pinterest_login::{ErrorKind, PinterestLoginError};
+
+    /// `verify`: the cookies are expired or invalid
+    pub const INVALID_COOKIES: i32 = 1;
+    /// Another error
+    pub const OTHER: i32 = 2;
+    /// `batch`: some of the accounts failed to log in
+    pub const BATCH_FAILED: i32 = 3;
+    /// `refresh`: the cookies weren't logged in anymore, and the login succeeded
+    pub const LOGGED_IN_AGAIN: i32 = 4;
+    pub const AUTHENTICATION: i32 = 10;
+    pub const CHALLENGE: i32 = 11;
+    pub const RATE_LIMITED: i32 = 12;
+    pub const SERVICE_UNAVAILABLE: i32 = 13;
+    pub const BROWSER: i32 = 20;
+    pub const TIMEOUT: i32 = 21;
+    pub const NETWORK: i32 = 22;
+    /// Like `EX_USAGE` of `sysexits.h`
+    pub const USAGE: i32 = 64;
+    /// Like `EX_CONFIG` of `sysexits.h`
+    pub const CONFIG: i32 = 78;
+    /// `--manual`: interrupted with Ctrl-C, like a shell reports a SIGINT
+    pub const INTERRUPTED: i32 = 130;
+
+    /// The exit code of a login or verification error
+    pub fn of(error: &PinterestLoginError) -> i32 {
+        match error.kind() {
+            ErrorKind::Authentication => AUTHENTICATION,
+            ErrorKind::Challenge => CHALLENGE,
+            ErrorKind::RateLimited => RATE_LIMITED,
+            ErrorKind::ServiceUnavailable => SERVICE_UNAVAILABLE,
+            ErrorKind::Browser => BROWSER,
+            ErrorKind::Timeout => TIMEOUT,
+            ErrorKind::Network => NETWORK,
+            _ => OTHER,
+        }
+    }
+}
+
+/// The default of `--fallback-deadline`
+const DEFAULT_FALLBACK_DEADLINE: Duration = Duration::from_secs(5 * 60);
+/// The default of `--manual-timeout`
+const DEFAULT_MANUAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+impl Args {
+    /// The login options of the arguments, with the host of the login url if it's set
+    fn login_options(&self) -> Result<(LoginOptions, Option<Host>), String> {
+        let mut options = LoginOptions::new()
+            .launch_retry(self.retry)
+            .navigation_retry(self.retry)
+            .incognito(!self.no_incognito);
+        if let Some(dir) = &self.screenshot_dir {
+            options = options.screenshot_on_failure(dir);
+        }
+        let Some(url) = &self.login_url else {
+            return Ok((options, None));
+        };
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| {
+                let host = url.host_str()?.to_owned();
+                Some(match url.port() {
+                    Some(port) => format!("{host}:{port}"),
+                    None => host,
+                })
+            })
+            .ok_or_else(|| format!("Invalid login url: `{url}`"))?;
+        Ok((options.login_url(url), Some(Host::new(host))))
+    }
+
+    /// The browser config of the arguments, merged with the config file
+    fn config_builder(&self, config: &Config) -> DefaultBrowserConfigBuilder {
+        self.browser_config_builder(config, self.headless.or(config.headless).unwrap_or(true))
+    }
+
+    /// The browser config of the `--manual` login, headful unless `--headless` is passed
+    fn manual_config_builder(&self, config: &Config) -> DefaultBrowserConfigBuilder {
+        self.browser_config_builder(config, self.headless.unwrap_or(false))
+    }
+
+    /// The browser config of the `--headful-fallback` login
+    fn headful_config_builder(&self, config: &Config) -> DefaultBrowserConfigBuilder {
+        self.browser_config_builder(config, false)
+    }
+
+    fn browser_config_builder(
+        &self,
+        config: &Config,
+        headless: bool,
+    ) -> DefaultBrowserConfigBuilder {
+        let config_builder = DefaultBrowserConfigBuilder::new(
+            headless,
+            self.timeout
+                .or(config.timeout.map(Duration::from_secs))
+                .unwrap_or(Duration::from_secs(3))
+                .into(),
+            self.launch_timeout,
+        );
+        let config_builder = config_builder.with_env_proxy(true);
+        let config_builder = match &self.user_data_dir {
+            Some(dir) => config_builder.with_user_data_dir(dir),
+            None => config_builder,
+        };
+        match self.proxy.clone().or(config.proxy.clone()) {
+            Some(proxy) => config_builder.with_proxy(proxy),
+            None => config_builder,
+        }
+    }
+
+    /// The log filter of the verbosity flags, `None` to use `RUST_LOG`
+    fn log_filter(&self) -> Option<&'static str> {
+        if self.quiet {
+            // The errors are printed by `fail` anyway
+            return Some("off");
+        }
+        match self.verbose {
+            0 => None,
+            1 => Some("info"),
+            2 => Some("debug"),
+            _ => Some("trace"),
+        }
+    }
+}
+
+/// Initializes the logger on stderr, to keep stdout for the output
+#[cfg(feature = "log")]
+fn init_logger(filter: Option<&str>, format: LogFormat) {
+    use pretty_env_logger::env_logger::{Target, WriteStyle};
+
+    let mut builder = pretty_env_logger::formatted_timed_builder();
+    builder.target(Target::Stderr);
+    // No colors in the files and the pipes
+    if !io::stderr().is_terminal() {
+        builder.write_style(WriteStyle::Never);
+    }
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let mut line = json_log_line(record.level().as_str(), &record.args().to_string());
+            line["target"] = record.target().into();
+            writeln!(buf, "{line}")
+        });
+    }
+    match filter
+        .map(str::to_owned)
+        .or_else(|| env::var("RUST_LOG").ok())
+    {
+        Some(filter) => builder.parse_filters(&filter),
+        // Like `pretty_env_logger::init_timed`, only the errors
+        None => &mut builder,
+    };
+    builder.init();
+}
+
+/// There's no logs without the `log` feature, the verbosity flags only print a warning
+#[cfg(not(feature = "log"))]
+fn init_logger(filter: Option<&str>, _format: LogFormat) {
+    if filter.is_some_and(|filter| filter != "off") {
+        eprintln!("`--verbose` has no effect, the binary was built without the `log` feature");
+    }
+}
+
 // #[tokio::main]
 #[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::main)]
 #[cfg_attr(feature = "tokio", tokio::main)]
 async fn main() {
-    let mut headless = true;
-    let mut timeout = 3;
-    let mut args = env::args().skip(1);
-    while let Some(arg) = &args.next() {
-        let arg = arg.trim_matches('-');
-        match arg {
-            "head" => headless = false,
-            "t" | "timeout" => {
-                timeout = args
-                    .next()
-                    .unwrap_or_else(|| fail(format!("expected value after `-{arg}`").leak()))
-                    .parse::<u64>()
-                    .unwrap_or_else(|e| fail(format!("Can't parse `-{arg}` value: {e}").leak()))
-                    .to_owned()
+    let args = parse_args(env::args_os()).unwrap_or_else(|e| match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => e.exit(),
+        _ => {
+            let _ = e.print();
+            process::exit(exit_code::USAGE)
+        }
+    });
+    if let Command::Completions(shell) = args.command {
+        cli::completions(shell, &mut io::stdout());
+        return;
+    }
+    let _ = LOG_FORMAT.set(args.log_format);
+    init_logger(args.log_filter(), args.log_format);
+    // Fail before the login rather than after it
+    if let Some(dir) = args.output.as_deref().and_then(|p| p.parent()) {
+        if !args.mkdir && !dir.as_os_str().is_empty() && !dir.is_dir() {
+            fail(
+                exit_code::USAGE,
+                format!(
+                    "The output directory `{}` doesn't exist, pass `--mkdir` to create it",
+                    dir.display()
+                ),
+            );
+        }
+    }
+
+    if let Some(dir) = &args.screenshot_dir {
+        check_screenshot_dir(dir).unwrap_or_else(|e| {
+            fail(
+                exit_code::OTHER,
+                format!("Can't save the screenshots into `{}`: {e}", dir.display()),
+            )
+        });
+    }
+    // Held until the browser is closed, when the process exits
+    let _profile_lock = args.user_data_dir.as_deref().map(|dir| {
+        lock::FileLock::acquire_profile(dir).unwrap_or_else(|e| {
+            let message = match e.kind() {
+                io::ErrorKind::WouldBlock => format!(
+                    "The profile `{}` is already in use by another pinterest-login",
+                    dir.display()
+                ),
+                _ => format!("Can't use the profile `{}`: {e}", dir.display()),
+            };
+            fail(exit_code::OTHER, message)
+        })
+    });
+    if args.no_incognito && args.user_data_dir.is_none() && !args.quiet {
+        eprintln!(
+            "`--no-incognito` without `--user-data-dir` keeps the cookies in a throwaway profile, \
+             that's deleted after the login anyway"
+        );
+    }
+
+    // The saved cookies are printed without logging in
+    if let (Command::Export, Some(input)) = (args.command, &args.input) {
+        let (cookies, details) = saved_cookies(load_cookies(input).unwrap_or_else(|e| {
+            fail(
+                exit_code::OTHER,
+                format!("Can't read the cookies from `{}`: {e}", input.display()),
+            )
+        }));
+        let contents =
+            format_cookies(&args, &cookies, &details).unwrap_or_else(|e| fail(exit_code::OTHER, e));
+        write_output(args.output.as_deref(), &contents, args.mkdir);
+        return;
+    }
+
+    let config = match (&args.config, config::default_path()) {
+        (Some(path), _) => Some(path.clone()),
+        (None, Some(path)) if path.exists() => Some(path),
+        _ => None,
+    };
+    let config = match config {
+        Some(path) => Config::load(&path, args.allow_insecure_config)
+            .unwrap_or_else(|e| fail(exit_code::CONFIG, format!("{}: {e}", path.display()))),
+        None => Config::default(),
+    };
+
+    // Checked before anything starts, the email can't be prompted for since stdin has the password
+    let stdin_password = args.password_stdin.then(|| {
+        if env::var_os("PINTEREST_PASSWORD").is_some() {
+            fail(
+                exit_code::USAGE,
+                "`--password-stdin` and PINTEREST_PASSWORD can't be used together",
+            );
+        }
+        if env::var_os("PINTEREST_EMAIL").is_none() && config.email.is_none() {
+            fail(
+                exit_code::USAGE,
+                "`--password-stdin` needs the email in PINTEREST_EMAIL or in the config file",
+            );
+        }
+        read_password(io::stdin().lock(), "`--password-stdin`")
+            .unwrap_or_else(|e| fail(exit_code::USAGE, e))
+    });
+    let flag_password = stdin_password.or_else(|| {
+        let path = args.password_file.as_deref()?;
+        if env::var_os("PINTEREST_PASSWORD").is_some() {
+            fail(
+                exit_code::USAGE,
+                "`--password-file` and PINTEREST_PASSWORD can't be used together",
+            );
+        }
+        let (password, warning) =
+            read_password_file(path).unwrap_or_else(|e| fail(exit_code::USAGE, e));
+        if let Some(warning) = warning.filter(|_| !args.quiet) {
+            eprintln!("Warning: {warning}");
+        }
+        Some(password)
+    });
+
+    #[cfg(feature = "auth")]
+    if let Command::AuthStore | Command::AuthForget = args.command {
+        manage_keyring(&args, &config, flag_password);
+        return;
+    }
+
+    let chrome_path = args
+        .chrome_path
+        .clone()
+        .map(|path| (path, "--chrome-path"))
+        .or_else(|| env::var_os("CHROME").map(|path| (PathBuf::from(path), "CHROME")))
+        .or_else(|| {
+            config
+                .chrome_path
+                .clone()
+                .map(|path| (path, "the config file"))
+        });
+    if let Some((path, source)) = &chrome_path {
+        if let Err(e) = check_executable(path) {
+            fail(
+                exit_code::BROWSER,
+                format!("The browser `{}` (from `{source}`) {e}", path.display()),
+            );
+        }
+    }
+    let executable = chrome_path.map(|(path, _)| path);
+    let with_executable = |config_builder: DefaultBrowserConfigBuilder| match &executable {
+        Some(path) => config_builder.with_executable(path),
+        None => config_builder,
+    };
+    let config_builder = with_executable(args.config_builder(&config));
+    let behind_proxy = config_builder.proxy().is_some();
+
+    let (options, login_host) = args
+        .login_options()
+        .unwrap_or_else(|e| fail(exit_code::USAGE, e));
+    // The last retry notice, to tell how many attempts were made if the login fails
+    let last_retry = Arc::new(Mutex::new(None));
+    let quiet = args.quiet;
+    let options = options.on_retry({
+        let last_retry = Arc::clone(&last_retry);
+        move |notice| {
+            if !quiet {
+                eprintln!(
+                    "The {} attempt {}/{} failed, retrying in {:?}: {}",
+                    notice.phase, notice.attempt, notice.max_attempts, notice.delay, notice.error
+                );
+            }
+            *last_retry.lock().unwrap() = Some((notice.phase, notice.attempt));
+        }
+    });
+    // The failure screenshot, to report it with the error
+    let screenshot = Arc::new(Mutex::new(None));
+    let options = options.on_failure_screenshot({
+        let screenshot = Arc::clone(&screenshot);
+        move |saved| {
+            *screenshot.lock().unwrap() = Some(match saved {
+                Ok(path) => format!("The screenshot of the page: {}", path.display()),
+                Err(e) => format!("Can't save the screenshot of the page: {e}"),
+            });
+        }
+    });
+    let attempts = |e: &PinterestLoginError| -> String {
+        let phase = if retry::is_transient_launch_error(e) {
+            LoginPhase::Launch
+        } else if retry::is_transient_navigation_error(e, behind_proxy) {
+            LoginPhase::Navigation
+        } else {
+            return "after 1 attempt".to_string();
+        };
+        match *last_retry.lock().unwrap() {
+            Some((retried, attempt)) if retried == phase => {
+                format!("after {} attempts", attempt + 1)
+            }
+            _ => "after 1 attempt".to_string(),
+        }
+    };
+
+    // The refreshes of the same file wait for each other, the later ones then verify the cookies of the first
+    let _lock = match (args.command, &args.output) {
+        (Command::Refresh, Some(output)) => Some(
+            lock::FileLock::acquire(output, args.mkdir).unwrap_or_else(|e| {
+                fail(
+                    exit_code::OTHER,
+                    format!("Can't lock `{}`: {e}", output.display()),
+                )
+            }),
+        ),
+        _ => None,
+    };
+    // Read after the lock, so a refresh that waited gets the cookies of the one before it
+    let saved = match (args.command, &args.input) {
+        (Command::Verify | Command::Refresh, Some(input)) => {
+            Some(load_cookies(input).unwrap_or_else(|e| {
+                fail(
+                    exit_code::OTHER,
+                    format!("Can't read the cookies from `{}`: {e}", input.display()),
+                )
+            }))
+        }
+        _ => None,
+    };
+    if let (Command::Verify, Some(cookies)) = (args.command, &saved) {
+        let report = verify_cookies(cookies.clone(), &config_builder, &options)
+            .await
+            .unwrap_or_else(|e| fail(exit_code::of(&e), format!("{e} ({})", attempts(&e))));
+        let contents = match args.format {
+            Format::Json => format!("{}\n", report_to_json(&report)),
+            _ => format!("{report:?}\n"),
+        };
+        write_output(args.output.as_deref(), &contents, args.mkdir);
+        if !report.valid {
+            process::exit(exit_code::INVALID_COOKIES);
+        }
+        return;
+    }
+
+    if let Some(batch) = &args.batch {
+        let code = batch::run(
+            batch,
+            args.full,
+            &config_builder,
+            &options,
+            login_host.as_ref(),
+            args.quiet,
+        )
+        .await;
+        process::exit(code);
+    }
+
+    if args.manual {
+        let bot = ManualLoginBot::new(args.manual_timeout.unwrap_or(DEFAULT_MANUAL_TIMEOUT));
+        let bot = match &login_host {
+            Some(host) => bot.with_login_host(host.clone()),
+            None => bot,
+        };
+        let manual_config_builder = with_executable(args.manual_config_builder(&config));
+        // The connection is idle while the human is on the page
+        let manual_options = options.clone().keep_alive(DEFAULT_KEEP_ALIVE_INTERVAL);
+        // Not quiet, someone has to act on it
+        eprintln!(
+            "Complete the login in the opened window within {}, Ctrl-C to cancel",
+            humantime::format_duration(args.manual_timeout.unwrap_or(DEFAULT_MANUAL_TIMEOUT))
+        );
+        let outcome = until_interrupted(login_with_options(
+            &bot,
+            &manual_config_builder,
+            &manual_options,
+        ))
+        .await
+        .unwrap_or_else(|e| fail(exit_code::of(&e), format!("{e} ({})", attempts(&e))));
+        let contents = format_cookies(&args, &outcome.cookies, &outcome.cookie_details)
+            .unwrap_or_else(|e| fail(exit_code::OTHER, e));
+        write_output(args.output.as_deref(), &contents, args.mkdir);
+        return;
+    }
+
+    let auth_error = |e| {
+        fail(
+            exit_code::OTHER,
+            format!("Can't get the authentication info: {e}"),
+        )
+    };
+    let email = get_email(&config).unwrap_or_else(auth_error);
+    let cache_key = cache::Key {
+        email: &email,
+        login_url: args.login_url.as_deref().unwrap_or(PINTEREST_LOGIN_URL),
+    };
+    if let Some(path) = args.cache.as_deref().filter(|_| !args.force_login) {
+        if let Some(contents) =
+            cached_output(&args, path, &cache_key, &config_builder, &options).await
+        {
+            write_output(args.output.as_deref(), &contents, args.mkdir);
+            return;
+        }
+    }
+    let password = get_password(&email, &config, flag_password).unwrap_or_else(auth_error);
+
+    let totp_secret = match args.totp_secret.clone() {
+        Some(secret) => Some(secret),
+        None => env::var("PINTEREST_TOTP_SECRET").ok().map(|secret| {
+            secret
+                .parse()
+                .unwrap_or_else(|e| fail(exit_code::USAGE, format!("PINTEREST_TOTP_SECRET: {e}")))
+        }),
+    };
+    let two_factor = match totp_secret {
+        Some(secret) => TwoFactor::Totp(secret),
+        None => TwoFactor::callback(prompt_two_factor_code),
+    };
+
+    let new_bot = || {
+        let bot =
+            DefaultBrowserLoginBot::new(&email, &password).with_two_factor(two_factor.clone());
+        match &login_host {
+            Some(host) => bot.with_login_host(host.clone()),
+            None => bot,
+        }
+    };
+    let bot = new_bot();
+
+    #[cfg(feature = "serve")]
+    if args.command == Command::Serve {
+        serve::serve(&args.serve, &bot, &config_builder, &options, args.quiet).await;
+    }
+
+    if let (Some(watch_args), Some(output)) = (&args.watch, &args.output) {
+        let backend = watch::BrowserBackend {
+            bot: &bot,
+            config_builder: &config_builder,
+            options: &options,
+            output,
+            full: args.full,
+            mkdir: args.mkdir,
+        };
+        // Without the handler, the signals kill the process as usual
+        let stop = match Signals::new([Signal::Term, Signal::Int]) {
+            Ok(signals) => signals.map(drop).boxed_local(),
+            Err(_) => futures::stream::pending().boxed_local(),
+        };
+        let result = watch::watch(watch_args, &backend, &SystemClock, stop, |report| {
+            if !args.quiet {
+                match args.log_format {
+                    LogFormat::Text => eprintln!("{}", report.to_text()),
+                    LogFormat::Json => eprintln!("{}", report.to_json()),
+                }
+            }
+        })
+        .await;
+        match result {
+            Ok(()) => return,
+            Err(failure) => fail(
+                failure.code,
+                format!("Giving up after `--max-failures` failed cycles in a row: {failure}"),
+            ),
+        }
+    }
+
+    if let (Command::Refresh, Some(cookies)) = (args.command, saved) {
+        // The saved cookies skip the login form while they're logged in, the bot only logs in when they aren't
+        match refresh_session_with_options(cookies, &bot, &config_builder, &options).await {
+            Ok(refreshed) => {
+                let outcome = &refreshed.outcome;
+                let contents = format_cookies(&args, &outcome.cookies, &outcome.cookie_details)
+                    .unwrap_or_else(|e| fail(exit_code::OTHER, e));
+                write_output(args.output.as_deref(), &contents, args.mkdir);
+                match refreshed.path {
+                    RefreshPath::Cookies => {
+                        if !args.quiet {
+                            eprintln!("The cookies are still logged in, they were refreshed");
+                        }
+                        return;
+                    }
+                    _ => {
+                        if !args.quiet {
+                            eprintln!("The cookies aren't logged in anymore, logged in again");
+                        }
+                        process::exit(exit_code::LOGGED_IN_AGAIN);
+                    }
+                }
+            }
+            Err(e) => {
+                if !args.quiet {
+                    eprintln!("Can't refresh the cookies ({e}), logging in again");
+                }
+            }
+        }
+    }
+
+    let login = login_with_options(&bot, &config_builder, &options);
+    let outcome = if args.headful_fallback {
+        let headful_config_builder = with_executable(args.headful_config_builder(&config));
+        // The connection may be idle for long while the human is on the page
+        let headful_options = options.clone().keep_alive(DEFAULT_KEEP_ALIVE_INTERVAL);
+        let deadline = args.fallback_deadline.unwrap_or(DEFAULT_FALLBACK_DEADLINE);
+        with_headful_fallback(deadline, &SystemClock, login, |time_left| {
+            // Not quiet, someone has to act on it
+            eprintln!(
+                "Pinterest challenged the login, finish it in the browser window within {} seconds",
+                time_left.as_secs()
+            );
+            let bot = new_bot().with_manual_assist(time_left);
+            async move { login_with_options(&bot, &headful_config_builder, &headful_options).await }
+        })
+        .await
+    } else {
+        login.await
+    };
+    let outcome = outcome.unwrap_or_else(|e| {
+        let mut report = format!("{e} ({})", attempts(&e));
+        if let Some(screenshot) = screenshot.lock().unwrap().take() {
+            report = format!("{report}\n{screenshot}");
+        }
+        fail(exit_code::of(&e), report)
+    });
+    if let Some(path) = &args.cache {
+        let cookies = to_json(&outcome.cookies, &outcome.cookie_details, true);
+        if let Err(e) = cache::save(path, &cache_key, cookies, SystemTime::now()) {
+            if !args.quiet {
+                eprintln!("Can't cache the cookies in `{}`: {e}", path.display());
             }
-            unknown => fail(format!("Unknown argument: `{unknown}`").leak()),
         }
     }
+    let contents = format_cookies(&args, &outcome.cookies, &outcome.cookie_details)
+        .unwrap_or_else(|e| fail(exit_code::OTHER, e));
+    write_output(args.output.as_deref(), &contents, args.mkdir);
+    if args.command == Command::Refresh {
+        process::exit(exit_code::LOGGED_IN_AGAIN);
+    }
+}
+
+/// Runs the login until it completes or Ctrl-C is pressed, which drops it (so the browser is closed and its
+/// profile removed) and exits with [`exit_code::INTERRUPTED`]
+async fn until_interrupted<T>(login: impl Future<Output = T>) -> T {
+    // Without the handler, Ctrl-C kills the process as usual
+    let Ok(mut signals) = Signals::new([Signal::Int]) else {
+        return login.await;
+    };
+    let login = Box::pin(login);
+    match future::select(login, signals.next()).await {
+        Either::Left((outcome, _)) => outcome,
+        Either::Right((_, login)) => {
+            drop(login);
+            fail(
+                exit_code::INTERRUPTED,
+                "Interrupted, the browser was closed",
+            )
+        }
+    }
+}
+
+/// Creates the screenshots directory if it's missing, and checks that it's writable
+fn check_screenshot_dir(dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    tempfile::tempfile_in(dir).map(drop)
+}
+
+/// Writes to the output file if there's one, otherwise to stdout
+fn write_output(output: Option<&Path>, contents: &str, mkdir: bool) {
+    match output {
+        Some(path) => write_atomic(path, contents.as_bytes(), mkdir).unwrap_or_else(|e| {
+            fail(
+                exit_code::OTHER,
+                format!("Can't write the output to `{}`: {e}", path.display()),
+            )
+        }),
+        None => print!("{contents}"),
+    }
+}
+
+/// The cookies in the format of the arguments, `details` are the full cookies
+fn format_cookies(
+    args: &Args,
+    cookies: &PinterestCookies,
+    details: &[Cookie],
+) -> Result<String, String> {
+    Ok(match args.format {
+        _ if !args.cookies.is_empty() => cookie_values(cookies, &args.cookies)?,
+        Format::Debug => format!("{:?}\n", **cookies),
+        Format::Json => format!("{}\n", to_json(cookies, details, args.full)),
+        Format::Netscape => to_netscape(details),
+        Format::Env => to_env(cookies),
+        Format::Curl => to_curl(cookies, args.curl_url.as_deref().unwrap_or(CURL_URL)),
+    })
+}
+
+/// The values of the `--cookie` names, one per line in their order
+fn cookie_values(cookies: &PinterestCookies, names: &[String]) -> Result<String, String> {
+    names.iter().try_fold(String::new(), |mut values, name| {
+        let value = cookies
+            .get(name)
+            .ok_or_else(|| format!("Cookie not found: `{name}`"))?;
+        values.push_str(value);
+        values.push('\n');
+        Ok(values)
+    })
+}
+
+/// The JSON output, its keys are sorted so it's stable from a run to another
+fn to_json(cookies: &PinterestCookies, details: &[Cookie], full: bool) -> Value {
+    if !full {
+        return json!(cookies.iter().collect::<BTreeMap<_, _>>());
+    }
+    sorted_cookies(details)
+        .iter()
+        .map(|cookie| {
+            json!({
+                "name": cookie.name,
+                "value": cookie.value,
+                "domain": cookie.domain,
+                "path": cookie.path,
+                // The session cookies have no expiry
+                "expires": (!cookie.session).then_some(cookie.expires),
+                "http_only": cookie.http_only,
+                "secure": cookie.secure,
+                "same_site": cookie.same_site.as_ref().map(|s| s.as_ref()),
+            })
+        })
+        .collect()
+}
+
+/// The cookies that get their own variable with `--format env`, the others are only in the header variable
+const ENV_COOKIES: &[(&str, &str)] = &[
+    ("PINTEREST_SESSION", "_pinterest_sess"),
+    ("PINTEREST_CSRFTOKEN", "csrftoken"),
+];
+
+/// The `export` lines of `--format env`
+fn to_env(cookies: &PinterestCookies) -> String {
+    let mut out = String::new();
+    for (variable, cookie) in ENV_COOKIES {
+        if let Some(value) = cookies.get(*cookie) {
+            out.push_str(&format!("export {variable}={}\n", shell_quote(value)));
+        }
+    }
+    out.push_str(&format!(
+        "export PINTEREST_COOKIE_HEADER={}\n",
+        shell_quote(&cookie_header(cookies))
+    ));
+    out
+}
+
+/// The URL of `--format curl` without `--curl-url`
+const CURL_URL: &str = "https://www.pinterest.com/";
+
+/// The user agent of `--format curl`, a desktop Chrome like the one that logged in, since the cookies
+/// are saved without the user agent of the browser
+const CURL_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                               Chrome/120.0.0.0 Safari/537.36";
+
+/// The curl command of `--format curl`, the CSRF token is sent in the `x-csrftoken` header like the
+/// pinterest API expects it
+fn to_curl(cookies: &PinterestCookies, url: &str) -> String {
+    let mut command = format!("curl {}", shell_quote(url));
+    let mut header = |header: String| {
+        command.push_str(" -H ");
+        command.push_str(&shell_quote(&header));
+    };
+    header(format!("Cookie: {}", cookie_header(cookies)));
+    if let Some(token) = cookies.get_csrf() {
+        header(format!("x-csrftoken: {token}"));
+    }
+    header(format!("User-Agent: {CURL_USER_AGENT}"));
+    command.push('\n');
+    command
+}
+
+/// A `Cookie` header with all the cookies, sorted by name
+fn cookie_header(cookies: &PinterestCookies) -> String {
+    AuthSession::from_cookies(cookies).cookie_header
+}
+
+/// Quotes the value for a POSIX shell: nothing is special between single quotes but the single quote itself,
+/// which is written as `'\''` (close the quotes, an escaped quote, reopen them)
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
 
-    let Ok((email, password)) = get_auth_info() else {
-        fail("Can't get the authentication info")
+/// The JSON report of `verify`, with the times as unix timestamps
+fn report_to_json(report: &VerifyReport) -> Value {
+    let timestamp = |time: SystemTime| {
+        time.duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
     };
+    json!({
+        "valid": report.valid,
+        "checked_at": timestamp(report.checked_at),
+        "earliest_expiry": report.earliest_expiry.map(timestamp),
+    })
+}
+
+/// Loads the cookies that were printed by `--format json`, with or without `--full`
+fn load_cookies(path: &Path) -> Result<Vec<CookieParam>, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_cookies(serde_json::from_str(&json).map_err(|e| e.to_string())?)
+}
 
-    let bot = DefaultBrowserLoginBot::new(email.as_str(), password.as_str());
+/// Parses the cookies of `--format json`, with or without `--full`
+fn parse_cookies(json: Value) -> Result<Vec<CookieParam>, String> {
+    let str_field = |cookie: &Value, field: &str| cookie[field].as_str().map(str::to_owned);
+    match json {
+        Value::Object(cookies) => cookies
+            .into_iter()
+            .map(|(name, value)| match value {
+                Value::String(value) => Ok(CookieParam::new(name, value)),
+                _ => Err(format!("the value of the `{name}` cookie isn't a string")),
+            })
+            .collect(),
+        Value::Array(cookies) => cookies
+            .iter()
+            .map(|cookie| {
+                let (Some(name), Some(value)) =
+                    (str_field(cookie, "name"), str_field(cookie, "value"))
+                else {
+                    return Err("a cookie has no name or value".to_string());
+                };
+                let mut param = CookieParam::new(name, value);
+                param.domain = str_field(cookie, "domain");
+                param.path = str_field(cookie, "path");
+                param.secure = cookie["secure"].as_bool();
+                param.http_only = cookie["http_only"].as_bool();
+                param.same_site = str_field(cookie, "same_site").and_then(|s| s.parse().ok());
+                param.expires = cookie["expires"].as_f64().map(TimeSinceEpoch::new);
+                Ok(param)
+            })
+            .collect(),
+        _ => Err("expected a JSON object or array of cookies".to_string()),
+    }
+}
+
+/// The full cookies, sorted so the output is stable from a run to another
+fn sorted_cookies(details: &[Cookie]) -> Vec<Cookie> {
+    let mut cookies = details.to_vec();
+    cookies.sort_by(|a, b| (&a.name, &a.domain, &a.path).cmp(&(&b.name, &b.domain, &b.path)));
+    cookies
+}
 
-    let config_builder =
-        DefaultBrowserConfigBuilder::new(headless, Duration::from_secs(timeout).into(), None);
+/// The saved cookies, as the login has them
+fn saved_cookies(saved: Vec<CookieParam>) -> (PinterestCookies, Vec<Cookie>) {
+    let details: Vec<Cookie> = saved.into_iter().map(saved_cookie).collect();
+    let cookies = details
+        .iter()
+        .map(|cookie| (cookie.name.clone(), cookie.value.clone()))
+        .collect();
+    (cookies, details)
+}
 
-    #[cfg(feature = "log")]
-    pretty_env_logger::init_timed();
-    match login(&bot, &config_builder).await {
-        Ok(cookies) => {
-            println!("{cookies:?}");
+/// The output of the cached cookies if they're still logged in, `None` to log in
+async fn cached_output(
+    args: &Args,
+    path: &Path,
+    key: &cache::Key<'_>,
+    config_builder: &DefaultBrowserConfigBuilder,
+    options: &LoginOptions,
+) -> Option<String> {
+    let note = |message: &str| {
+        if !args.quiet {
+            eprintln!("{message}");
         }
-        Err(e) => {
-            eprintln!("{e}");
+    };
+    let cached = match cache::lookup(path, key, args.cache_ttl, SystemTime::now()) {
+        Lookup::Fresh(cached) => cached,
+        Lookup::Missing => return None,
+        Lookup::Expired => {
+            note("The cached cookies are older than `--cache-ttl`, logging in");
+            return None;
+        }
+        Lookup::Corrupted(e) => {
+            note(&format!(
+                "Ignoring the corrupted cache `{}`, logging in: {e}",
+                path.display()
+            ));
+            return None;
         }
     };
+    match verify_cookies(cached.clone(), config_builder, options).await {
+        Ok(report) if report.valid => {}
+        Ok(_) => {
+            note("The cached cookies aren't logged in anymore, logging in");
+            return None;
+        }
+        Err(e) => {
+            note(&format!("Can't verify the cached cookies, logging in: {e}"));
+            return None;
+        }
+    }
+    note(&format!("Using the cached cookies of `{}`", path.display()));
+    let (cookies, details) = saved_cookies(cached);
+    Some(format_cookies(args, &cookies, &details).unwrap_or_else(|e| fail(exit_code::OTHER, e)))
 }
 
-fn get_auth_info() -> io::Result<(String, String)> {
-    if let Ok(email) = env::var("PINTEREST_EMAIL") {
-        let Ok(password) = env::var("PINTEREST_PASSWORD") else {
-            return Ok((email, rpassword::prompt_password("Account password: ")?));
-        };
-        Ok((email, password))
+/// The full cookie of a saved one, the details that the file doesn't have are the ones of a session cookie
+/// of `.pinterest.com`
+fn saved_cookie(cookie: CookieParam) -> Cookie {
+    Cookie {
+        size: (cookie.name.len() + cookie.value.len()) as i64,
+        domain: cookie
+            .domain
+            .unwrap_or_else(|| ".pinterest.com".to_string()),
+        path: cookie.path.unwrap_or_else(|| "/".to_string()),
+        // Like chrome has the session cookies
+        expires: cookie
+            .expires
+            .as_ref()
+            .map_or(-1.0, |expires| *expires.inner()),
+        session: cookie.expires.is_none(),
+        http_only: cookie.http_only.unwrap_or(false),
+        secure: cookie.secure.unwrap_or(false),
+        same_site: cookie.same_site,
+        priority: CookiePriority::Medium,
+        same_party: false,
+        source_scheme: CookieSourceScheme::Unset,
+        source_port: -1,
+        partition_key: None,
+        partition_key_opaque: None,
+        name: cookie.name,
+        value: cookie.value,
+    }
+}
+
+/// Checks that the browser can be launched, to fail with a readable error before the login
+fn check_executable(path: &Path) -> Result<(), &'static str> {
+    let metadata = path.metadata().map_err(|_| "doesn't exist")?;
+    if !metadata.is_file() {
+        return Err("isn't a file");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err("isn't executable");
+        }
+    }
+    Ok(())
+}
+
+/// The password of the account, from [`password_from_sources`], or prompted for if none has it
+fn get_password(
+    email: &str,
+    config: &Config,
+    flag_password: Option<String>,
+) -> Result<String, String> {
+    #[cfg(feature = "auth")]
+    let keyring = || auth::Keyring::new(email)?.password();
+    #[cfg(not(feature = "auth"))]
+    let _ = email;
+    #[cfg(not(feature = "auth"))]
+    let keyring = || Ok(None);
+    let env_password = env::var("PINTEREST_PASSWORD").ok();
+    match password_from_sources(flag_password, env_password, keyring, config)? {
+        Some(password) => Ok(password),
+        None => rpassword::prompt_password("Account password: ").map_err(|e| e.to_string()),
+    }
+}
+
+/// The email of the account, from PINTEREST_EMAIL, then from the config file, then prompted for
+fn get_email(config: &Config) -> Result<String, String> {
+    if let Some(email) = env::var("PINTEREST_EMAIL").ok().or(config.email.clone()) {
+        return Ok(email);
+    }
+    // The prompts go to stderr, to keep stdout for the cookies
+    eprint!("Pinterest email/username: ");
+    io::stderr().flush().map_err(|e| e.to_string())?;
+    let mut email = String::new();
+    io::stdin()
+        .read_line(&mut email)
+        .map_err(|e| e.to_string())?;
+    email.pop(); // rm `\n`
+    Ok(email)
+}
+
+/// The password of the first source that has it: `--password-stdin` or `--password-file`, PINTEREST_PASSWORD, the
+/// keyring (with the `auth` feature), then the config file. `None` if none has it, the later sources aren't read
+fn password_from_sources(
+    flag_password: Option<String>,
+    env_password: Option<String>,
+    keyring: impl FnOnce() -> Result<Option<String>, String>,
+    config: &Config,
+) -> Result<Option<String>, String> {
+    if let Some(password) = flag_password.or(env_password) {
+        return Ok(Some(password));
+    }
+    if let Some(password) = keyring()? {
+        return Ok(Some(password));
+    }
+    config.password()
+}
+
+/// `auth store` and `auth forget`
+#[cfg(feature = "auth")]
+fn manage_keyring(args: &Args, config: &Config, flag_password: Option<String>) {
+    let email = get_email(config)
+        .unwrap_or_else(|e| fail(exit_code::OTHER, format!("Can't get the email: {e}")));
+    let keyring = auth::Keyring::new(&email).unwrap_or_else(|e| fail(exit_code::OTHER, e));
+    let message = if args.command == Command::AuthStore {
+        let password = flag_password.unwrap_or_else(|| {
+            rpassword::prompt_password("Account password: ")
+                .unwrap_or_else(|e| fail(exit_code::OTHER, format!("Can't read the password: {e}")))
+        });
+        if password.is_empty() {
+            fail(exit_code::USAGE, "The password is empty");
+        }
+        keyring
+            .store(&password)
+            .unwrap_or_else(|e| fail(exit_code::OTHER, e));
+        format!("The password of `{email}` is saved in the keyring")
+    } else if keyring
+        .forget()
+        .unwrap_or_else(|e| fail(exit_code::OTHER, e))
+    {
+        format!("The password of `{email}` is deleted from the keyring")
     } else {
-        print!("Pinterest email/username: ");
-        io::stdout().flush()?;
-        let mut email = String::new();
-        io::stdin().read_line(&mut email)?;
-        email.pop(); // rm `\n`
-        Ok((email, rpassword::prompt_password("Account password: ")?))
+        format!("There's no password of `{email}` in the keyring")
+    };
+    if !args.quiet {
+        eprintln!("{message}");
+    }
+}
+
+/// The password of `--password-stdin` or `--password-file` (the `flag`): the first line, without its `\n` or
+/// `\r\n` but with any other whitespace
+fn read_password(mut input: impl io::BufRead, flag: &str) -> Result<String, String> {
+    let mut line = String::new();
+    input
+        .read_line(&mut line)
+        .map_err(|e| format!("Can't read the password of {flag}: {e}"))?;
+    let password = line.strip_suffix('\n').unwrap_or(&line);
+    let password = password.strip_suffix('\r').unwrap_or(password);
+    if password.is_empty() {
+        return Err(format!("{flag} got an empty password"));
+    }
+    Ok(password.to_string())
+}
+
+/// The password of `--password-file`, with a warning if the file is readable by everyone (on unix).
+///
+/// The `/dev/fd/N` paths of the shells' process substitutions are opened like any file, they're pipes that
+/// only this process can read, so they're never warned about
+fn read_password_file(path: &Path) -> Result<(String, Option<String>), String> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Can't open the password file {}: {e}", path.display()))?;
+    #[cfg(unix)]
+    let warning = {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = file
+            .metadata()
+            .map_err(|e| format!("Can't read the password file {}: {e}", path.display()))?;
+        let mode = metadata.permissions().mode();
+        (metadata.is_file() && mode & 0o004 != 0).then(|| {
+            format!(
+                "the password file {} can be read by everyone (mode {:o}), run `chmod 600` on it",
+                path.display(),
+                mode & 0o777
+            )
+        })
+    };
+    #[cfg(not(unix))]
+    let warning = None;
+    let password = read_password(io::BufReader::new(file), "`--password-file`")?;
+    Ok((password, warning))
+}
+
+/// Prompts for the two-factor authentication code on the terminal, `None` if there's no terminal to ask on
+fn prompt_two_factor_code() -> Option<String> {
+    // Without a terminal the prompt would wait forever
+    if !io::stdin().is_terminal() {
+        return None;
     }
+    rpassword::prompt_password("Enter the 6-digit code: ").ok()
 }
 
+/// The `--log-format` of the errors, set once the arguments are parsed
+static LOG_FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+/// Prints the error and exits with the code, see [`exit_code`].
+/// With `--log-format json` the error is a JSON line, like the logs
 #[cold]
-fn fail(msg: &'static str) -> ! {
-    eprintln!("{msg}");
-    process::exit(1)
+fn fail(code: i32, msg: impl std::fmt::Display) -> ! {
+    match LOG_FORMAT.get() {
+        Some(LogFormat::Json) => eprintln!("{}", json_log_line("ERROR", &msg.to_string())),
+        _ => eprintln!("{msg}"),
+    }
+    process::exit(code)
+}
+
+/// A line of `--log-format json`
+fn json_log_line(level: &str, message: &str) -> Value {
+    json!({
+        "timestamp": humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+        "level": level,
+        "target": module_path!(),
+        "message": message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinterest_login::retry::StepRetry;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(["pintrest-login"].iter().chain(args)).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn the_retries_default_to_the_library_policy() {
+        assert_eq!(parse(&[]).unwrap().retry, StepRetry::default());
+    }
+
+    #[test]
+    fn the_retry_flags_are_parsed() {
+        let args = parse(&["--retries", "5", "--retry-delay", "250ms"]).unwrap();
+        assert_eq!(
+            args.retry,
+            StepRetry {
+                retries: 5,
+                delay: Duration::from_millis(250)
+            }
+        );
+
+        // Each flag alone keeps the default of the other
+        let args = parse(&["--retries", "0"]).unwrap();
+        assert_eq!(args.retry.retries, 0);
+        assert_eq!(args.retry.delay, StepRetry::default().delay);
+        let args = parse(&["login", "--retry-delay", "3"]).unwrap();
+        assert_eq!(args.retry.retries, StepRetry::default().retries);
+        assert_eq!(args.retry.delay, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn invalid_retry_flags_are_rejected() {
+        for args in [
+            &["--retries"][..],
+            &["--retries", "-1"],
+            &["--retries", "many"],
+            &["--retry-delay", "soon"],
+            &["--retry-delay", "5x"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn no_incognito_is_passed_to_the_login() {
+        let (options, _) = parse(&[]).unwrap().login_options().unwrap();
+        assert!(format!("{options:?}").contains("incognito: true"));
+
+        let args = parse(&["--no-incognito", "--user-data-dir", "profile"]).unwrap();
+        assert!(args.no_incognito);
+        assert_eq!(args.user_data_dir, Some(PathBuf::from("profile")));
+        let (options, _) = args.login_options().unwrap();
+        assert!(format!("{options:?}").contains("incognito: false"));
+
+        // A profile is only useful outside of the incognito windows
+        assert!(parse(&["--user-data-dir", "profile"]).unwrap().no_incognito);
+    }
+
+    #[test]
+    fn the_retry_policy_is_passed_to_the_launch_and_the_navigation() {
+        let args = parse(&["--retries", "4", "--retry-delay", "1s"]).unwrap();
+
+        let (options, host) = args.login_options().unwrap();
+
+        let options = format!("{options:?}");
+        let retry = "StepRetry { retries: 4, delay: 1s }";
+        assert!(
+            options.contains(&format!("launch_retry: {retry}")),
+            "{options}"
+        );
+        assert!(
+            options.contains(&format!("navigation_retry: {retry}")),
+            "{options}"
+        );
+        assert_eq!(host, None);
+    }
+
+    #[test]
+    fn the_verbosity_flags_map_to_the_log_levels() {
+        let table = [
+            (&[][..], None),
+            (&["-v"], Some("info")),
+            (&["--verbose"], Some("info")),
+            (&["-vv"], Some("debug")),
+            (&["-v", "-v"], Some("debug")),
+            (&["-vvv"], Some("trace")),
+            (&["-vvvv"], Some("trace")),
+            (&["-v", "--verbose", "-vv"], Some("trace")),
+            (&["-q"], Some("off")),
+            (&["--quiet"], Some("off")),
+        ];
+        for (args, filter) in table {
+            assert_eq!(parse(args).unwrap().log_filter(), filter, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn the_errors_map_to_their_exit_code() {
+        use chromiumoxide::error::CdpError;
+
+        let table = [
+            (
+                PinterestLoginError::AuthenticationError,
+                exit_code::AUTHENTICATION,
+            ),
+            (PinterestLoginError::ChallengeRequired, exit_code::CHALLENGE),
+            (PinterestLoginError::RateLimited, exit_code::RATE_LIMITED),
+            (
+                PinterestLoginError::ServiceUnavailable { retry_hint: None },
+                exit_code::SERVICE_UNAVAILABLE,
+            ),
+            (
+                PinterestLoginError::BrowserDisconnected {
+                    during: LoginPhase::FillForm,
+                },
+                exit_code::BROWSER,
+            ),
+            (
+                PinterestLoginError::HandshakeTimeout {
+                    executable: "chrome".into(),
+                    timeout: Duration::from_secs(5),
+                },
+                exit_code::BROWSER,
+            ),
+            (
+                PinterestLoginError::CdpError(CdpError::Timeout),
+                exit_code::TIMEOUT,
+            ),
+            (
+                PinterestLoginError::CdpError(CdpError::ChromeMessage(
+                    "net::ERR_CONNECTION_REFUSED".into(),
+                )),
+                exit_code::NETWORK,
+            ),
+            (
+                PinterestLoginError::CdpError(CdpError::ChromeMessage("Target closed".into())),
+                exit_code::BROWSER,
+            ),
+        ];
+        for (error, code) in table {
+            assert_eq!(exit_code::of(&error), code, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn the_exit_codes_are_in_the_help() {
+        for code in [
+            exit_code::INVALID_COOKIES,
+            exit_code::OTHER,
+            exit_code::BATCH_FAILED,
+            exit_code::LOGGED_IN_AGAIN,
+            exit_code::AUTHENTICATION,
+            exit_code::CHALLENGE,
+            exit_code::RATE_LIMITED,
+            exit_code::SERVICE_UNAVAILABLE,
+            exit_code::BROWSER,
+            exit_code::TIMEOUT,
+            exit_code::NETWORK,
+            exit_code::USAGE,
+            exit_code::CONFIG,
+            exit_code::INTERRUPTED,
+        ] {
+            assert!(
+                cli::AFTER_LONG_HELP.contains(&format!("\n  {code:<3} ")),
+                "{code}"
+            );
+        }
+    }
+
+    #[test]
+    fn the_totp_secret_is_parsed() {
+        let args = parse(&["--totp-secret", "JBSWY3DPEHPK3PXP"]).unwrap();
+        assert_eq!(
+            args.totp_secret,
+            Some("jbsw y3dp ehpk 3pxp".parse().unwrap())
+        );
+
+        let err = parse(&["--totp-secret", "not base32!"]).unwrap_err();
+        assert!(err.contains("totp-secret"), "{err}");
+    }
+
+    #[test]
+    fn shell_quoting() {
+        let table = [
+            ("", "''"),
+            ("plain", "'plain'"),
+            ("it's", r"'it'\''s'"),
+            ("''", r"''\'''\'''"),
+            ("$HOME", "'$HOME'"),
+            ("`id`", "'`id`'"),
+            ("$(id)", "'$(id)'"),
+            ("a\nb", "'a\nb'"),
+            ("back\\slash", "'back\\slash'"),
+            ("\"double\"", "'\"double\"'"),
+        ];
+        for (value, quoted) in table {
+            assert_eq!(shell_quote(value), quoted, "{value:?}");
+        }
+    }
+
+    /// The quoted values come back as they were from a real shell, without running anything
+    #[cfg(unix)]
+    #[test]
+    fn shell_quoted_values_survive_eval() {
+        let values = [
+            "",
+            "it's",
+            "'",
+            "$HOME ${PATH} $(touch pwned) `touch pwned`",
+            "line\nbreak\r\n",
+            "back\\slash\\",
+            "\"; touch pwned; \"",
+            "tab\there",
+            "ünïcödé",
+        ];
+        for value in values {
+            // Like `eval "$(pintrest-login --format env)"`
+            let line = format!("export X={}", shell_quote(value));
+            let output = process::Command::new("sh")
+                .args(["-c", r#"eval "$1"; printf %s "$X""#, "sh", &line])
+                .current_dir(env::temp_dir())
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{value:?}");
+            assert_eq!(String::from_utf8_lossy(&output.stdout), value);
+        }
+    }
+
+    #[test]
+    fn the_env_format_exports_the_known_cookies_and_the_header() {
+        let mut cookies: PinterestCookies = [
+            ("_pinterest_sess", "se'ss"),
+            ("csrftoken", "tok$en"),
+            ("_auth", "1"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        assert_eq!(
+            to_env(&cookies),
+            "export PINTEREST_SESSION='se'\\''ss'\n\
+             export PINTEREST_CSRFTOKEN='tok$en'\n\
+             export PINTEREST_COOKIE_HEADER='_auth=1; _pinterest_sess=se'\\''ss; csrftoken=tok$en'\n"
+        );
+
+        // Only the header without the known cookies
+        cookies.retain(|name, _| name == "_auth");
+        assert_eq!(
+            to_env(&cookies),
+            "export PINTEREST_COOKIE_HEADER='_auth=1'\n"
+        );
+    }
+
+    #[test]
+    fn the_curl_format_is_a_command_with_the_cookies() {
+        let mut cookies: PinterestCookies = [("_pinterest_sess", "sess"), ("csrftoken", "token")]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        assert_eq!(
+            to_curl(&cookies, "https://www.pinterest.com/resource/"),
+            format!(
+                "curl 'https://www.pinterest.com/resource/' \
+                 -H 'Cookie: _pinterest_sess=sess; csrftoken=token' -H 'x-csrftoken: token' \
+                 -H 'User-Agent: {CURL_USER_AGENT}'\n"
+            )
+        );
+
+        // No token header without the cookie
+        cookies.retain(|name, _| name == "_pinterest_sess");
+        assert!(!to_curl(&cookies, CURL_URL).contains("x-csrftoken"));
+    }
+
+    #[test]
+    fn the_curl_command_survives_the_shell() {
+        let cookies: PinterestCookies = [
+            ("_pinterest_sess", "it's $(touch pwned)"),
+            ("csrftoken", "'; touch pwned; '"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let url = "https://www.pinterest.com/search/?q='`touch pwned`'&rs=typed";
+
+        // A `curl` function that prints its arguments, one per line
+        let output = process::Command::new("sh")
+            .args([
+                "-c",
+                r#"curl() { printf '%s\n' "$@"; }; eval "$1""#,
+                "sh",
+                &to_curl(&cookies, url),
+            ])
+            .current_dir(env::temp_dir())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!(
+                "{url}\n\
+                 -H\nCookie: _pinterest_sess=it's $(touch pwned); csrftoken='; touch pwned; '\n\
+                 -H\nx-csrftoken: '; touch pwned; '\n\
+                 -H\nUser-Agent: {CURL_USER_AGENT}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn curl_url_needs_the_curl_format() {
+        let args = parse(&[
+            "--format",
+            "curl",
+            "--curl-url",
+            "https://www.pinterest.com/me/",
+        ])
+        .unwrap();
+        assert_eq!(args.format, Format::Curl);
+        assert_eq!(
+            args.curl_url.as_deref(),
+            Some("https://www.pinterest.com/me/")
+        );
+
+        let err = parse(&["--format", "json", "--curl-url", "https://example.com"]).unwrap_err();
+        assert!(
+            err.contains("`--curl-url` only works with `--format curl`"),
+            "{err}"
+        );
+        assert!(parse(&[
+            "export",
+            "-i",
+            "c.json",
+            "--format",
+            "curl",
+            "--curl-url",
+            "u"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn the_launch_timeout_is_separate_from_the_request_timeout() {
+        let args = parse(&["--timeout", "4s", "--launch-timeout", "45s"]).unwrap();
+
+        let config_builder = args
+            .config_builder(&Config::default())
+            .with_executable("chrome");
+
+        assert_eq!(config_builder.handshake_timeout(), Duration::from_secs(45));
+        let config = format!("{:?}", config_builder.build_browser_config().unwrap());
+        assert!(config.contains("launch_timeout: 45s"), "{config}");
+        assert!(config.contains("request_timeout: 4s"), "{config}");
+    }
+
+    #[test]
+    fn the_launch_timeout_defaults_to_the_handshake_timeout() {
+        let config_builder = parse(&["--timeout", "4"])
+            .unwrap()
+            .config_builder(&Config::default());
+
+        assert_eq!(
+            config_builder.handshake_timeout(),
+            pinterest_login::config_builder::DEFAULT_HANDSHAKE_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn the_launch_timeout_accepts_seconds_and_durations() {
+        for (value, timeout) in [
+            ("45", Duration::from_secs(45)),
+            ("45s", Duration::from_secs(45)),
+            ("1m", Duration::from_secs(60)),
+            ("1500ms", Duration::from_millis(1500)),
+        ] {
+            let args = parse(&["--launch-timeout", value]).unwrap();
+            assert_eq!(args.launch_timeout, Some(timeout), "{value}");
+        }
+
+        let err = parse(&["--launch-timeout", "forever"]).unwrap_err();
+        assert!(err.contains("--launch-timeout"), "{err}");
+        assert!(err.contains("`forever` isn't a duration"), "{err}");
+        assert!(parse(&["--launch-timeout"]).is_err());
+    }
+
+    #[test]
+    fn the_stdin_password_is_the_first_line_without_its_newline() {
+        for (input, password) in [
+            ("hunter2\n", "hunter2"),
+            ("hunter2", "hunter2"),
+            ("hunter2\r\n", "hunter2"),
+            (" pass word \t\n", " pass word \t"),
+            ("first\nsecond\n", "first"),
+        ] {
+            assert_eq!(
+                read_password(input.as_bytes(), "`--password-stdin`").as_deref(),
+                Ok(password),
+                "{input:?}"
+            );
+        }
+
+        for input in ["", "\n", "\r\n"] {
+            assert_eq!(
+                read_password(input.as_bytes(), "`--password-stdin`"),
+                Err("`--password-stdin` got an empty password".to_string()),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn the_stdin_password_is_only_for_the_login() {
+        assert!(parse(&["--password-stdin"]).unwrap().password_stdin);
+        assert!(parse(&["verify", "-i", "cookies.json", "--password-stdin"]).is_err());
+    }
+
+    /// Writes the contents into a new file of the directory, with the mode on unix
+    fn password_file(dir: &Path, contents: &str, mode: u32) -> PathBuf {
+        let path = dir.join("password");
+        std::fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        path
+    }
+
+    #[test]
+    fn the_file_password_is_the_first_line_without_its_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        for (contents, password) in [
+            ("hunter2\n", "hunter2"),
+            ("hunter2", "hunter2"),
+            ("hunter2\r\n", "hunter2"),
+            (" pass word \t\n\n", " pass word \t"),
+            ("first\nsecond\n", "first"),
+        ] {
+            let path = password_file(dir.path(), contents, 0o600);
+            assert_eq!(
+                read_password_file(&path),
+                Ok((password.to_string(), None)),
+                "{contents:?}"
+            );
+        }
+
+        for contents in ["", "\n", "\r\n"] {
+            let path = password_file(dir.path(), contents, 0o600);
+            assert_eq!(
+                read_password_file(&path),
+                Err("`--password-file` got an empty password".to_string()),
+                "{contents:?}"
+            );
+        }
+
+        let err = read_password_file(&dir.path().join("missing")).unwrap_err();
+        assert!(err.contains("Can't open the password file"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_world_readable_password_file_is_warned_about() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for mode in [0o600, 0o640, 0o400] {
+            let path = password_file(dir.path(), "hunter2\n", mode);
+            assert_eq!(read_password_file(&path).unwrap().1, None, "{mode:o}");
+        }
+
+        let path = password_file(dir.path(), "hunter2\n", 0o644);
+        let (password, warning) = read_password_file(&path).unwrap();
+        assert_eq!(password, "hunter2");
+        let warning = warning.unwrap();
+        assert!(warning.contains("mode 644"), "{warning}");
+        assert!(warning.contains("chmod 600"), "{warning}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_password_file_can_be_a_file_descriptor() {
+        use std::os::fd::AsRawFd;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = std::fs::File::open(password_file(dir.path(), "hunter2\n", 0o600)).unwrap();
+
+        // Like the `<(...)` of the shells
+        let path = PathBuf::from(format!("/dev/fd/{}", file.as_raw_fd()));
+        assert_eq!(read_password_file(&path), Ok(("hunter2".to_string(), None)));
+    }
+
+    #[test]
+    fn the_password_file_conflicts_with_the_other_password_flags() {
+        let args = parse(&["--password-file", "/run/secrets/pinterest"]).unwrap();
+        assert_eq!(
+            args.password_file.as_deref(),
+            Some(Path::new("/run/secrets/pinterest"))
+        );
+        assert!(!args.password_stdin);
+
+        for flags in [
+            &["--password-file", "password", "--password-stdin"][..],
+            &["--password-file", "password", "--manual"],
+        ] {
+            let err = parse(flags).unwrap_err();
+            assert!(err.contains("cannot be used with"), "{flags:?}: {err}");
+        }
+        assert!(parse(&[
+            "verify",
+            "-i",
+            "cookies.json",
+            "--password-file",
+            "password"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn the_cookie_flags_print_the_values_in_their_order() {
+        let cookies = PinterestCookies::from([
+            ("_pinterest_sess".to_string(), "session".to_string()),
+            ("csrftoken".to_string(), "token".to_string()),
+        ]);
+
+        let args = parse(&["--cookie", "_pinterest_sess"]).unwrap();
+        assert_eq!(
+            cookie_values(&cookies, &args.cookies).as_deref(),
+            Ok("session\n")
+        );
+
+        let args = parse(&["--cookie", "csrftoken", "--cookie", "_pinterest_sess"]).unwrap();
+        assert_eq!(
+            cookie_values(&cookies, &args.cookies).as_deref(),
+            Ok("token\nsession\n")
+        );
+
+        let args = parse(&["--cookie", "csrftoken", "--cookie", "missing"]).unwrap();
+        assert_eq!(
+            cookie_values(&cookies, &args.cookies),
+            Err("Cookie not found: `missing`".to_string())
+        );
+    }
+
+    #[test]
+    fn the_cookie_flag_combinations() {
+        assert!(parse(&["--cookie", "a", "--input", "cookies.json", "-q"]).is_ok());
+        assert!(parse(&["--input", "cookies.json"]).is_err());
+        assert!(parse(&["--cookie", "a", "--format", "json"]).is_err());
+        assert!(parse(&["--cookie", "a", "--full"]).is_err());
+        assert!(parse(&["--cookie", "a", "-i", "cookies.json", "--password-stdin"]).is_err());
+        assert!(parse(&["verify", "-i", "cookies.json", "--cookie", "a"]).is_err());
+        assert!(parse(&["--cookie"]).is_err());
+    }
+
+    #[cfg(feature = "serve")]
+    #[test]
+    fn the_serve_options() {
+        let args = parse(&["serve"]).unwrap();
+        assert_eq!(args.command, Command::Serve);
+        assert_eq!(args.serve, serve::ServeArgs::default());
+        assert!(args.serve.listen.ip().is_loopback());
+
+        let args = parse(&[
+            "serve",
+            "--listen",
+            "0.0.0.0:8080",
+            "--auth-token",
+            "s3cret",
+            "--refresh-interval",
+            "1m",
+        ])
+        .unwrap();
+        assert_eq!(args.serve.listen, "0.0.0.0:8080".parse().unwrap());
+        assert_eq!(args.serve.auth_token.as_deref(), Some("s3cret"));
+        assert_eq!(args.serve.refresh_interval, Duration::from_secs(60));
+
+        assert!(parse(&["serve", "--listen", "localhost"]).is_err());
+        assert!(parse(&["--listen", "127.0.0.1:9123"]).is_err());
+        assert!(parse(&["serve", "--format", "json"]).is_err());
+        assert!(parse(&["serve", "--cookie", "csrftoken"]).is_err());
+    }
+
+    #[test]
+    fn the_screenshots_are_enabled_in_the_login_options() {
+        let args = parse(&["--screenshot-on-failure", "screenshots"]).unwrap();
+        let (options, _) = args.login_options().unwrap();
+        assert!(
+            format!("{options:?}").contains("screenshot_dir: Some(\"screenshots\")"),
+            "{options:?}"
+        );
+
+        assert!(parse(&[
+            "verify",
+            "-i",
+            "cookies.json",
+            "--screenshot-on-failure",
+            "dir"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn the_screenshots_dir_is_created() {
+        let dir = tempfile::tempdir().unwrap();
+        let screenshots = dir.path().join("nightly").join("screenshots");
+
+        check_screenshot_dir(&screenshots).unwrap();
+        assert!(screenshots.is_dir());
+        // Nothing is left in it
+        assert_eq!(std::fs::read_dir(&screenshots).unwrap().count(), 0);
+
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        assert!(check_screenshot_dir(&file.join("screenshots")).is_err());
+    }
+
+    #[test]
+    fn the_headful_fallback_options() {
+        let args = parse(&["--headful-fallback", "--fallback-deadline", "10m"]).unwrap();
+        assert!(args.headful_fallback);
+        assert_eq!(args.fallback_deadline, Some(Duration::from_secs(600)));
+        let config = format!(
+            "{:?}",
+            args.headful_config_builder(&Config::default())
+                .with_executable("chrome")
+                .build_browser_config()
+                .unwrap()
+        );
+        assert!(config.contains("headless: false"), "{config}");
+
+        assert!(parse(&["--headful-fallback", "--head"]).is_err());
+        assert!(parse(&["--fallback-deadline", "10m"]).is_err());
+        assert!(parse(&["verify", "-i", "cookies.json", "--headful-fallback"]).is_err());
+    }
+
+    #[test]
+    fn the_password_sources_precedence() {
+        let config = Config {
+            password: Some("from config".into()),
+            ..Config::default()
+        };
+        let some = |password: &str| Some(password.to_string());
+        let keyring = || Ok(some("from keyring"));
+        let unread = || -> Result<Option<String>, String> { panic!("the keyring was read") };
+
+        assert_eq!(
+            password_from_sources(some("from stdin"), some("from env"), unread, &config),
+            Ok(some("from stdin"))
+        );
+        assert_eq!(
+            password_from_sources(None, some("from env"), unread, &config),
+            Ok(some("from env"))
+        );
+        assert_eq!(
+            password_from_sources(None, None, keyring, &config),
+            Ok(some("from keyring"))
+        );
+        assert_eq!(
+            password_from_sources(None, None, || Ok(None), &config),
+            Ok(some("from config"))
+        );
+        assert_eq!(
+            password_from_sources(None, None, || Ok(None), &Config::default()),
+            Ok(None)
+        );
+        // A broken keyring isn't skipped silently
+        assert_eq!(
+            password_from_sources(None, None, || Err("bad entry".to_string()), &config),
+            Err("bad entry".to_string())
+        );
+    }
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn the_stored_password_comes_after_the_env_var() {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let keyring = auth::Keyring::new("email@example.com").unwrap();
+        keyring.store("from keyring").unwrap();
+        let config = Config {
+            password: Some("from config".into()),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            password_from_sources(None, None, || keyring.password(), &config),
+            Ok(Some("from keyring".to_string()))
+        );
+        assert_eq!(
+            password_from_sources(
+                None,
+                Some("from env".into()),
+                || keyring.password(),
+                &config
+            ),
+            Ok(Some("from env".to_string()))
+        );
+        keyring.forget().unwrap();
+        assert_eq!(
+            password_from_sources(None, None, || keyring.password(), &config),
+            Ok(Some("from config".to_string()))
+        );
+    }
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn the_auth_commands() {
+        let args = parse(&["auth", "store", "--password-stdin"]).unwrap();
+        assert_eq!(args.command, Command::AuthStore);
+        assert!(args.password_stdin);
+        assert_eq!(
+            parse(&["auth", "forget", "-q"]).unwrap().command,
+            Command::AuthForget
+        );
+
+        assert!(parse(&["auth"]).is_err());
+        assert!(parse(&["auth", "forget", "--password-stdin"]).is_err());
+    }
+
+    #[test]
+    fn quiet_and_verbose_are_exclusive() {
+        assert!(parse(&["-q", "-v"]).is_err());
+        assert!(parse(&["-vv", "--quiet"]).is_err());
+    }
 }
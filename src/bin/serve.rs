@@ -0,0 +1,459 @@
+//! The `serve` command: it logs in once, then answers the cookies over HTTP to the local services, and keeps them
+//! logged in by verifying them periodically and logging in again when they aren't anymore.
+//!
+//! The endpoints:
+//! - `GET /cookies`: the cookies as a JSON object of their names and values, like `--format json`,
+//!   it needs the `Authorization: Bearer TOKEN` header if there's an `--auth-token`
+//! - `GET /healthz`: `200 ok` if the last login or verification succeeded, `503` with the error otherwise
+//!
+//! The HTTP server is a minimal HTTP/1.1 one on the std sockets, a thread per connection, since it only has to
+//! answer a few local clients. Up to [`MAX_CONNECTIONS`] are answered at the same time, the next ones wait in the
+//! backlog of the socket, and the request line with the headers can't be longer than [`MAX_HEAD_SIZE`].
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use chromiumoxide::cdp::browser_protocol::network::CookieParam;
+use pinterest_login::clock::{Clock, SystemClock};
+use pinterest_login::config_builder::BrowserConfigBuilder;
+use pinterest_login::login_bot::BrowserLoginBot;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::verify::verify_cookies;
+use pinterest_login::{login_with_options, PinterestCookies};
+
+use crate::cli::parse_duration;
+use crate::{exit_code, fail};
+
+/// The default address, only the local services can reach it
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9123";
+/// How often the cookies are verified by default
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// How long a client has to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// How many connections are answered at the same time
+pub const MAX_CONNECTIONS: usize = 32;
+/// The most bytes of the request line and the headers of a request
+pub const MAX_HEAD_SIZE: u64 = 8 * 1024;
+
+/// The options of the `serve` command
+#[derive(Debug, Clone, PartialEq, Eq, clap::Args)]
+pub struct ServeArgs {
+    /// The address to listen on
+    #[arg(long, value_name = "ADDR", default_value = DEFAULT_LISTEN)]
+    pub listen: SocketAddr,
+    /// Answer `/cookies` only with the `Authorization: Bearer TOKEN` header
+    #[arg(long, value_name = "TOKEN")]
+    pub auth_token: Option<String>,
+    /// How often the cookies are verified, like `30m` or `90s`
+    #[arg(long, value_name = "TIME", default_value = "10m", value_parser = parse_duration)]
+    pub refresh_interval: Duration,
+}
+
+impl Default for ServeArgs {
+    fn default() -> Self {
+        Self {
+            listen: DEFAULT_LISTEN.parse().unwrap(),
+            auth_token: None,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+        }
+    }
+}
+
+/// What the endpoints answer
+#[derive(Debug, Default)]
+struct State {
+    /// `None` until the first login
+    cookies: Option<PinterestCookies>,
+    /// The error of the last login or verification, if it failed
+    error: Option<String>,
+}
+
+/// An HTTP response
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn text(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+
+    fn json(body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: body.into(),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            431 => "Request Header Fields Too Large",
+            _ => "Service Unavailable",
+        }
+    }
+}
+
+/// Serves the cookies until the process is killed, the first login failing exits like a normal login
+pub async fn serve<B, C>(
+    args: &ServeArgs,
+    bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+    quiet: bool,
+) -> !
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    // Bound before the login, a taken port shouldn't wait for it
+    let listener = TcpListener::bind(args.listen).unwrap_or_else(|e| {
+        fail(
+            exit_code::OTHER,
+            format!("Can't listen on `{}`: {e}", args.listen),
+        )
+    });
+    let addr = listener.local_addr().unwrap_or(args.listen);
+    if !quiet {
+        if !addr.ip().is_loopback() && args.auth_token.is_none() {
+            eprintln!("Warning: the cookies are served to the network without an `--auth-token`");
+        }
+        eprintln!("Serving the cookies on http://{addr}");
+    }
+    let state = Arc::new(Mutex::new(State::default()));
+    spawn_server(listener, Arc::clone(&state), args.auth_token.clone());
+
+    let outcome = login_with_options(bot, config_builder, options)
+        .await
+        .unwrap_or_else(|e| fail(exit_code::of(&e), e));
+    state.lock().unwrap().cookies = Some(outcome.cookies);
+    if !quiet {
+        eprintln!("Logged in");
+    }
+
+    loop {
+        SystemClock.sleep(args.refresh_interval).await;
+        let cookies: Vec<_> = state
+            .lock()
+            .unwrap()
+            .cookies
+            .iter()
+            .flatten()
+            .map(|(name, value)| CookieParam::new(name.clone(), value.clone()))
+            .collect();
+        match verify_cookies(cookies, config_builder, options).await {
+            Ok(report) if report.valid => {
+                state.lock().unwrap().error = None;
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if !quiet {
+                    eprintln!("Can't verify the cookies, logging in again: {e}");
+                }
+            }
+        }
+        // The old cookies are still served if the login fails, `/healthz` tells it
+        match login_with_options(bot, config_builder, options).await {
+            Ok(outcome) => {
+                let mut state = state.lock().unwrap();
+                state.cookies = Some(outcome.cookies);
+                state.error = None;
+                if !quiet {
+                    eprintln!("Logged in again");
+                }
+            }
+            Err(e) => {
+                if !quiet {
+                    eprintln!("Can't log in again: {e}");
+                }
+                state.lock().unwrap().error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+fn spawn_server(listener: TcpListener, state: Arc<Mutex<State>>, auth_token: Option<String>) {
+    let auth_token = Arc::new(auth_token);
+    let connections = Arc::new(Connections::default());
+    std::thread::spawn(move || loop {
+        // Nothing is accepted while every slot is taken
+        let slot = connections.slot();
+        let Ok((stream, _)) = listener.accept() else {
+            continue;
+        };
+        let state = Arc::clone(&state);
+        let auth_token = Arc::clone(&auth_token);
+        std::thread::spawn(move || {
+            let _slot = slot;
+            let _ = handle(stream, &state, auth_token.as_deref());
+        });
+    });
+}
+
+/// How many connections are answered, up to [`MAX_CONNECTIONS`]
+#[derive(Debug, Default)]
+struct Connections {
+    count: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl Connections {
+    /// Waits for a free slot
+    fn slot(self: &Arc<Self>) -> Slot {
+        let mut count = self.count.lock().unwrap();
+        while *count >= MAX_CONNECTIONS {
+            count = self.freed.wait(count).unwrap();
+        }
+        *count += 1;
+        Slot(Arc::clone(self))
+    }
+}
+
+/// A slot of the [`Connections`], it's given back when it's dropped
+struct Slot(Arc<Connections>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        *self.0.count.lock().unwrap() -= 1;
+        self.0.freed.notify_one();
+    }
+}
+
+fn handle(
+    stream: TcpStream,
+    state: &Mutex<State>,
+    auth_token: Option<&str>,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream).take(MAX_HEAD_SIZE);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Only the authorization matters in the headers
+    let mut authorization = None;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && !line.trim_end().is_empty() {
+        if let Some((name, value)) = line.trim_end().split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+        line.clear();
+    }
+    // The head ends with an empty line, it's only missing when the limit cut it
+    let truncated = reader.limit() == 0 && !line.ends_with('\n');
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        _ if truncated => Response::text(431, "The headers are too large\n"),
+        (Some(method), Some(target)) => {
+            let path = target.split('?').next().unwrap_or(target);
+            route(
+                method,
+                path,
+                authorization.as_deref(),
+                &state.lock().unwrap(),
+                auth_token,
+            )
+        }
+        _ => Response::text(400, "Bad request\n"),
+    };
+    respond(&stream, &response)
+}
+
+fn respond(mut stream: &TcpStream, response: &Response) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len()
+    );
+    if response.status == 401 {
+        head.push_str("WWW-Authenticate: Bearer\r\n");
+    }
+    stream.write_all(format!("{head}\r\n{}", response.body).as_bytes())
+}
+
+/// The response to a request, `authorization` is the value of its `Authorization` header
+fn route(
+    method: &str,
+    path: &str,
+    authorization: Option<&str>,
+    state: &State,
+    auth_token: Option<&str>,
+) -> Response {
+    if !matches!(path, "/cookies" | "/healthz") {
+        return Response::text(404, "Not found\n");
+    }
+    if method != "GET" {
+        return Response::text(405, "Only GET is allowed\n");
+    }
+    match path {
+        "/healthz" => match (&state.cookies, &state.error) {
+            (None, _) => Response::text(503, "Logging in\n"),
+            (Some(_), Some(error)) => Response::text(503, format!("{error}\n")),
+            (Some(_), None) => Response::text(200, "ok\n"),
+        },
+        _ => {
+            if let Some(token) = auth_token {
+                let bearer = authorization.and_then(|value| value.strip_prefix("Bearer "));
+                if !bearer.is_some_and(|bearer| constant_time_eq(bearer.trim(), token)) {
+                    return Response::text(401, "Unauthorized\n");
+                }
+            }
+            match &state.cookies {
+                Some(cookies) => Response::json(format!(
+                    "{}\n",
+                    serde_json::json!(cookies.iter().collect::<BTreeMap<_, _>>())
+                )),
+                None => Response::text(503, "Logging in\n"),
+            }
+        }
+    }
+}
+
+/// Compares the tokens without leaking where they differ through the timing
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logged_in() -> State {
+        State {
+            cookies: Some(PinterestCookies::from([
+                ("_pinterest_sess".to_string(), "session".to_string()),
+                ("csrftoken".to_string(), "token".to_string()),
+            ])),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn the_cookies_are_a_json_object() {
+        let response = route("GET", "/cookies", None, &logged_in(), None);
+
+        assert_eq!(
+            response,
+            Response::json("{\"_pinterest_sess\":\"session\",\"csrftoken\":\"token\"}\n")
+        );
+    }
+
+    #[test]
+    fn the_cookies_need_the_token() {
+        let state = logged_in();
+        let token = Some("s3cret");
+
+        for authorization in [
+            None,
+            Some("Bearer wrong"),
+            Some("s3cret"),
+            Some("Basic s3cret"),
+        ] {
+            let response = route("GET", "/cookies", authorization, &state, token);
+            assert_eq!(response.status, 401, "{authorization:?}");
+        }
+        let response = route("GET", "/cookies", Some("Bearer s3cret"), &state, token);
+        assert_eq!(response.status, 200);
+        // The health checks don't have the token
+        assert_eq!(route("GET", "/healthz", None, &state, token).status, 200);
+    }
+
+    #[test]
+    fn the_health_follows_the_logins() {
+        let mut state = State::default();
+        assert_eq!(route("GET", "/healthz", None, &state, None).status, 503);
+        assert_eq!(route("GET", "/cookies", None, &state, None).status, 503);
+
+        state = logged_in();
+        assert_eq!(
+            route("GET", "/healthz", None, &state, None),
+            Response::text(200, "ok\n")
+        );
+
+        state.error = Some("The login timed out".to_string());
+        assert_eq!(
+            route("GET", "/healthz", None, &state, None),
+            Response::text(503, "The login timed out\n")
+        );
+        // The last cookies are still served
+        assert_eq!(route("GET", "/cookies", None, &state, None).status, 200);
+    }
+
+    #[test]
+    fn the_other_requests_are_rejected() {
+        let state = logged_in();
+        assert_eq!(route("GET", "/", None, &state, None).status, 404);
+        assert_eq!(route("POST", "/cookies", None, &state, None).status, 405);
+        assert_eq!(route("DELETE", "/healthz", None, &state, None).status, 405);
+    }
+
+    /// Serves the state on a local port, returns its address
+    fn server(state: State) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        spawn_server(listener, Arc::new(Mutex::new(state)), None);
+        addr
+    }
+
+    /// Sends the request, returns the status of the response
+    fn status(addr: SocketAddr, request: &[u8]) -> u16 {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response.split_whitespace().nth(1).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn the_headers_are_capped() {
+        let addr = server(logged_in());
+        let request = b"GET /healthz HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        assert_eq!(status(addr, request), 200);
+
+        // The head never ends within the limit
+        let mut request = b"GET /healthz HTTP/1.1\r\nX-Padding: ".to_vec();
+        request.resize(MAX_HEAD_SIZE as usize, b'a');
+        assert_eq!(status(addr, &request), 431);
+    }
+
+    #[test]
+    fn the_connections_are_capped() {
+        let addr = server(logged_in());
+        let request = b"GET /healthz HTTP/1.1\r\n\r\n";
+        // They wait for their request meanwhile
+        let idle: Vec<_> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(addr).unwrap())
+            .collect();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || sender.send(status(addr, request)));
+        let waiting = receiver.recv_timeout(Duration::from_millis(200));
+        assert!(waiting.is_err(), "{waiting:?}");
+
+        drop(idle);
+        assert_eq!(receiver.recv_timeout(READ_TIMEOUT), Ok(200));
+    }
+}
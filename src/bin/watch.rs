@@ -0,0 +1,530 @@
+//! The `watch` command: it keeps a cookie file logged in, by verifying its cookies every `--interval` and logging
+//! in again when they aren't logged in anymore.
+//!
+//! Every cycle is reported with one line on stderr. The failed cycles are retried sooner, after a delay that
+//! doubles with every failure in a row (up to the interval) and is partly random, so a few watchers that failed
+//! together don't retry together. SIGTERM and SIGINT stop it once the current cycle is over.
+//!
+//! The loop is in [`watch`], the browser is behind the [`Backend`] trait so the loop can be tested without one.
+
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use futures::future::{self, Either, LocalBoxFuture};
+use futures::{FutureExt, Stream, StreamExt};
+use pinterest_login::clock::Clock;
+use pinterest_login::config_builder::BrowserConfigBuilder;
+use pinterest_login::jitter::random_fraction;
+use pinterest_login::login_bot::BrowserLoginBot;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::store::write_atomic;
+use pinterest_login::verify::verify_cookies;
+use pinterest_login::{login_with_options, PinterestLoginError};
+use serde_json::{json, Value};
+
+use crate::cli::parse_duration;
+use crate::lock::FileLock;
+use crate::{exit_code, load_cookies, to_json};
+
+/// How often the cookies are verified by default
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// The delay after a first failure, it doubles with every failure in a row
+const BACKOFF: Duration = Duration::from_secs(60);
+
+/// The options of the `watch` command
+#[derive(Debug, Clone, PartialEq, Eq, clap::Args)]
+pub struct WatchArgs {
+    /// How often the cookies are verified, like `6h` or `30m`
+    #[arg(long, value_name = "TIME", default_value = "6h", value_parser = parse_duration)]
+    pub interval: Duration,
+    /// Exit after this many failed cycles in a row, with the exit code of the last failure [default: never]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_failures: Option<u32>,
+}
+
+impl Default for WatchArgs {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_INTERVAL,
+            max_failures: None,
+        }
+    }
+}
+
+/// What a cycle does with the cookies, the binary's one uses the browser
+pub trait Backend {
+    /// Held for the whole cycle
+    type Lock;
+
+    /// Keeps the others from using the cookies during the cycle
+    fn lock(&self) -> Result<Self::Lock, Failure>;
+    /// Whether the saved cookies are still logged in, `false` if there are none
+    fn verify(&self) -> LocalBoxFuture<'_, Result<bool, Failure>>;
+    /// Logs in and saves the new cookies
+    fn login(&self) -> LocalBoxFuture<'_, Result<(), Failure>>;
+}
+
+/// Why a cycle failed, with the exit code of the error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub code: i32,
+    pub message: String,
+}
+
+impl From<PinterestLoginError> for Failure {
+    fn from(error: PinterestLoginError) -> Self {
+        Self {
+            code: exit_code::of(&error),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// What a cycle did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The cookies are still logged in
+    Valid,
+    /// They weren't, the login saved new ones
+    LoggedIn,
+    Failed(Failure),
+}
+
+/// The report of a cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// The number of the cycle, from 1
+    pub cycle: u64,
+    pub outcome: Outcome,
+    /// The failed cycles in a row, this one included
+    pub failures: u32,
+    /// When the next cycle starts, `None` if it's the last one
+    pub next: Option<Duration>,
+}
+
+impl Report {
+    /// A logfmt line, like `cycle=2 outcome=logged_in failures=0 next=6h`
+    pub fn to_text(&self) -> String {
+        let mut line = format!(
+            "cycle={} outcome={} failures={}",
+            self.cycle,
+            self.outcome_name(),
+            self.failures
+        );
+        if let Some(next) = self.next {
+            line.push_str(&format!(" next={}", humantime::format_duration(next)));
+        }
+        if let Outcome::Failed(failure) = &self.outcome {
+            line.push_str(&format!(" error={:?}", failure.message));
+        }
+        line
+    }
+
+    /// A line of `--log-format json`
+    pub fn to_json(&self) -> Value {
+        json!({
+            "timestamp": humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+            "cycle": self.cycle,
+            "outcome": self.outcome_name(),
+            "failures": self.failures,
+            "next_secs": self.next.map(|next| next.as_secs()),
+            "error": match &self.outcome {
+                Outcome::Failed(failure) => Some(&failure.message),
+                _ => None,
+            },
+        })
+    }
+
+    fn outcome_name(&self) -> &'static str {
+        match self.outcome {
+            Outcome::Valid => "valid",
+            Outcome::LoggedIn => "logged_in",
+            Outcome::Failed(_) => "failed",
+        }
+    }
+}
+
+/// Runs the cycles until `stop` yields, then returns once the current cycle is over.
+/// It fails with the last failure after `--max-failures` failed cycles in a row
+pub async fn watch<K>(
+    args: &WatchArgs,
+    backend: &K,
+    clock: &dyn Clock,
+    mut stop: impl Stream<Item = ()> + Unpin,
+    mut report: impl FnMut(&Report),
+) -> Result<(), Failure>
+where
+    K: Backend + ?Sized,
+{
+    let mut failures = 0;
+    let mut cycle = 0;
+    loop {
+        cycle += 1;
+        let (outcome, stopping) =
+            match future::select(Box::pin(run_cycle(backend)), stop.next()).await {
+                Either::Left((outcome, _)) => (outcome, false),
+                // The cycle is finished anyway, it may be writing the cookies
+                Either::Right((stopped, outcome)) => (outcome.await, stopped.is_some()),
+            };
+        failures = match outcome {
+            Ok(_) => 0,
+            Err(_) => failures + 1,
+        };
+        let last = stopping || args.max_failures.is_some_and(|max| failures >= max);
+        let delay = match failures {
+            0 => args.interval,
+            failures => backoff(failures, args.interval, random_fraction()),
+        };
+        report(&Report {
+            cycle,
+            outcome: match &outcome {
+                Ok(outcome) => outcome.clone(),
+                Err(failure) => Outcome::Failed(failure.clone()),
+            },
+            failures,
+            next: (!last).then_some(delay),
+        });
+        match outcome {
+            Err(failure) if !stopping && last => return Err(failure),
+            _ if last => return Ok(()),
+            _ => {}
+        }
+        match future::select(clock.sleep(delay), stop.next()).await {
+            Either::Left(_) => {}
+            Either::Right((Some(()), _)) => return Ok(()),
+            // Nothing can stop it anymore
+            Either::Right((None, sleep)) => sleep.await,
+        }
+    }
+}
+
+/// Verifies the cookies, and logs in if they aren't logged in anymore.
+/// A verification that fails logs in too, the login tells if something is wrong
+async fn run_cycle<K>(backend: &K) -> Result<Outcome, Failure>
+where
+    K: Backend + ?Sized,
+{
+    let _lock = backend.lock()?;
+    if let Ok(true) = backend.verify().await {
+        return Ok(Outcome::Valid);
+    }
+    backend.login().await?;
+    Ok(Outcome::LoggedIn)
+}
+
+/// The delay after `failures` failed cycles in a row: [`BACKOFF`] doubled for every failure after the first one,
+/// up to the interval, then up to a half of it is taken off by `jitter`, between 0 and 1
+fn backoff(failures: u32, interval: Duration, jitter: f64) -> Duration {
+    let doublings = failures.saturating_sub(1).min(31);
+    let delay = BACKOFF.saturating_mul(1 << doublings).min(interval);
+    delay.mul_f64(1.0 - jitter / 2.0)
+}
+
+/// The [`Backend`] of the binary: the cookies are in the output file, as `--format json`
+pub struct BrowserBackend<'a, B: ?Sized, C: ?Sized> {
+    pub bot: &'a B,
+    pub config_builder: &'a C,
+    pub options: &'a LoginOptions,
+    pub output: &'a Path,
+    pub full: bool,
+    pub mkdir: bool,
+}
+
+impl<B, C> Backend for BrowserBackend<'_, B, C>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    type Lock = FileLock;
+
+    fn lock(&self) -> Result<FileLock, Failure> {
+        FileLock::acquire(self.output, self.mkdir).map_err(|e| Failure {
+            code: exit_code::OTHER,
+            message: format!("Can't lock `{}`: {e}", self.output.display()),
+        })
+    }
+
+    fn verify(&self) -> LocalBoxFuture<'_, Result<bool, Failure>> {
+        async move {
+            if !self.output.exists() {
+                return Ok(false);
+            }
+            let cookies = load_cookies(self.output).map_err(|e| Failure {
+                code: exit_code::OTHER,
+                message: format!(
+                    "Can't read the cookies from `{}`: {e}",
+                    self.output.display()
+                ),
+            })?;
+            let report = verify_cookies(cookies, self.config_builder, self.options).await?;
+            Ok(report.valid)
+        }
+        .boxed_local()
+    }
+
+    fn login(&self) -> LocalBoxFuture<'_, Result<(), Failure>> {
+        async move {
+            let outcome = login_with_options(self.bot, self.config_builder, self.options).await?;
+            let cookies = to_json(&outcome.cookies, &outcome.cookie_details, self.full);
+            write_atomic(self.output, format!("{cookies}\n").as_bytes(), self.mkdir).map_err(|e| {
+                Failure {
+                    code: exit_code::OTHER,
+                    message: format!(
+                        "Can't write the cookies to `{}`: {e}",
+                        self.output.display()
+                    ),
+                }
+            })
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    use futures::channel::mpsc;
+    use futures::executor::block_on;
+    use pinterest_login::clock::ManualClock;
+    use pinterest_login::login_bot::PageLoginBot;
+    use pinterest_login::mock::MockPage;
+    use pinterest_login::scripted::{ScriptedLoginBot, Step};
+    use pinterest_login::LoginPhase;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    /// The verifications answer the scripted validities (then `true`), the logins are the `check_login` of the bot
+    struct ScriptedBackend {
+        bot: ScriptedLoginBot,
+        valid: RefCell<VecDeque<bool>>,
+    }
+
+    impl ScriptedBackend {
+        fn new(bot: ScriptedLoginBot, valid: impl IntoIterator<Item = bool>) -> Self {
+            Self {
+                bot,
+                valid: RefCell::new(valid.into_iter().collect()),
+            }
+        }
+    }
+
+    impl Backend for ScriptedBackend {
+        type Lock = ();
+
+        fn lock(&self) -> Result<(), Failure> {
+            Ok(())
+        }
+
+        fn verify(&self) -> LocalBoxFuture<'_, Result<bool, Failure>> {
+            let valid = self.valid.borrow_mut().pop_front().unwrap_or(true);
+            future::ready(Ok(valid)).boxed_local()
+        }
+
+        fn login(&self) -> LocalBoxFuture<'_, Result<(), Failure>> {
+            async move {
+                let page = MockPage::new("https://www.pinterest.com/login/");
+                PageLoginBot::check_login(&self.bot, &page).await?;
+                Ok(())
+            }
+            .boxed_local()
+        }
+    }
+
+    fn args(max_failures: Option<u32>) -> WatchArgs {
+        WatchArgs {
+            interval: 6 * HOUR,
+            max_failures,
+        }
+    }
+
+    /// Watches until `cycles` cycles were reported, or the watch ends by itself
+    fn run(
+        args: &WatchArgs,
+        backend: &ScriptedBackend,
+        clock: &ManualClock,
+        cycles: usize,
+    ) -> (Result<(), Failure>, Vec<Report>) {
+        let (stop, stopped) = mpsc::unbounded();
+        let mut reports = Vec::new();
+        let result = block_on(clock.drive(watch(args, backend, clock, stopped, |report| {
+            reports.push(report.clone());
+            if reports.len() == cycles {
+                stop.unbounded_send(()).unwrap();
+            }
+        })));
+        (result, reports)
+    }
+
+    fn outcomes(reports: &[Report]) -> Vec<&str> {
+        reports.iter().map(Report::outcome_name).collect()
+    }
+
+    fn authentication_error() -> Step {
+        Step::fail(|| PinterestLoginError::AuthenticationError)
+    }
+
+    #[test]
+    fn the_cookies_are_verified_every_interval_and_logged_in_when_needed() {
+        let clock = ManualClock::new();
+        let bot = ScriptedLoginBot::new();
+        let backend = ScriptedBackend::new(bot.clone(), [false, true, false, true]);
+
+        let (result, reports) = run(&args(None), &backend, &clock, 4);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            outcomes(&reports),
+            ["logged_in", "valid", "logged_in", "valid"]
+        );
+        assert_eq!(bot.calls(), [LoginPhase::CheckLogin; 2]);
+        assert_eq!(reports[0].next, Some(6 * HOUR));
+        // The last sleep is cut short by the stop
+        assert_eq!(clock.sleeps(), [6 * HOUR; 4]);
+    }
+
+    #[test]
+    fn the_failures_back_off_until_max_failures() {
+        let clock = ManualClock::new();
+        let bot = ScriptedLoginBot::new().script(LoginPhase::CheckLogin, [authentication_error()]);
+        let backend = ScriptedBackend::new(bot, [false; 3]);
+
+        let (result, reports) = run(&args(Some(3)), &backend, &clock, usize::MAX);
+
+        let failure = result.unwrap_err();
+        assert_eq!(failure.code, exit_code::AUTHENTICATION);
+        assert_eq!(outcomes(&reports), ["failed"; 3]);
+        assert_eq!(
+            reports.iter().map(|r| r.failures).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(reports[2].next, None);
+        // 1m then 2m, minus up to a half
+        let sleeps = clock.sleeps();
+        assert_eq!(sleeps.len(), 2);
+        assert!(
+            Duration::from_secs(30) <= sleeps[0] && sleeps[0] <= BACKOFF,
+            "{sleeps:?}"
+        );
+        assert!(
+            BACKOFF <= sleeps[1] && sleeps[1] <= 2 * BACKOFF,
+            "{sleeps:?}"
+        );
+    }
+
+    #[test]
+    fn a_success_resets_the_failures() {
+        let clock = ManualClock::new();
+        let bot = ScriptedLoginBot::new().script(
+            LoginPhase::CheckLogin,
+            [
+                authentication_error(),
+                authentication_error(),
+                Step::Succeed,
+            ],
+        );
+        let backend = ScriptedBackend::new(bot, [false, false, false, true]);
+
+        let (result, reports) = run(&args(Some(3)), &backend, &clock, 4);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            outcomes(&reports),
+            ["failed", "failed", "logged_in", "valid"]
+        );
+        assert_eq!(
+            reports.iter().map(|r| r.failures).collect::<Vec<_>>(),
+            [1, 2, 0, 0]
+        );
+        assert_eq!(clock.sleeps()[2..], [6 * HOUR; 2]);
+    }
+
+    #[test]
+    fn a_stop_waits_for_the_cycle_to_finish() {
+        let clock = ManualClock::new();
+        let bot = ScriptedLoginBot::new().with_clock(clock.clone()).script(
+            LoginPhase::CheckLogin,
+            [Step::Delay(Duration::from_secs(10))],
+        );
+        let backend = ScriptedBackend::new(bot.clone(), [false]);
+        let (stop, stopped) = mpsc::unbounded();
+        // Stopped before the login is over
+        stop.unbounded_send(()).unwrap();
+
+        let mut reports = Vec::new();
+        let result =
+            block_on(
+                clock.drive(watch(&args(None), &backend, &clock, stopped, |report| {
+                    reports.push(report.clone())
+                })),
+            );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(outcomes(&reports), ["logged_in"]);
+        assert_eq!(reports[0].next, None);
+        assert_eq!(clock.sleeps(), [Duration::from_secs(10)]);
+    }
+
+    #[test]
+    fn the_backoff_doubles_up_to_the_interval() {
+        let minute = Duration::from_secs(60);
+        for (failures, jitter, delay) in [
+            (1, 0.0, minute),
+            (2, 0.0, 2 * minute),
+            (3, 0.0, 4 * minute),
+            (3, 1.0, 2 * minute),
+            (3, 0.5, 3 * minute),
+            (10, 0.0, HOUR),
+            (u32::MAX, 0.0, HOUR),
+        ] {
+            assert_eq!(
+                backoff(failures, HOUR, jitter),
+                delay,
+                "{failures} {jitter}"
+            );
+        }
+        for _ in 0..100 {
+            assert!((0.0..1.0).contains(&random_fraction()));
+        }
+    }
+
+    #[test]
+    fn the_reports_are_one_line() {
+        let report = Report {
+            cycle: 3,
+            outcome: Outcome::Failed(Failure {
+                code: exit_code::NETWORK,
+                message: "Can't reach \"pinterest\"\nat all".to_string(),
+            }),
+            failures: 2,
+            next: Some(Duration::from_secs(90)),
+        };
+        assert_eq!(
+            report.to_text(),
+            r#"cycle=3 outcome=failed failures=2 next=1m 30s error="Can't reach \"pinterest\"\nat all""#
+        );
+        let json = report.to_json();
+        assert_eq!(json["outcome"], "failed");
+        assert_eq!(json["next_secs"], 90);
+        assert_eq!(json["error"], "Can't reach \"pinterest\"\nat all");
+        assert!(!json.to_string().contains('\n'));
+
+        let report = Report {
+            outcome: Outcome::Valid,
+            failures: 0,
+            next: None,
+            ..report
+        };
+        assert_eq!(report.to_text(), "cycle=3 outcome=valid failures=0");
+        assert_eq!(report.to_json()["error"], Value::Null);
+    }
+}
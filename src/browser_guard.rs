@@ -0,0 +1,282 @@
+use std::{
+    future::Future,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use chromiumoxide::{
+    cdp::js_protocol::runtime::EvaluateParams, error::CdpError, Browser, Handler, Page,
+};
+use futures::{
+    channel::oneshot,
+    future::{AbortHandle, Abortable, BoxFuture, Shared},
+    stream::FuturesUnordered,
+    FutureExt, StreamExt,
+};
+#[cfg(feature = "log")]
+use log::{debug, trace};
+use tempfile::TempDir;
+
+use crate::{clock::Clock, options::TaskMode};
+
+/// Owns the launched browser, its event handler task and any other background task of the login,
+/// and tears them all down when it gets dropped.
+///
+/// This is what makes [`login`](crate::login) cancellation safe, if the login future gets dropped at any await point
+/// the guard is dropped with it, which aborts the tasks and kills the browser process right away
+/// instead of leaving them running forever.
+pub(crate) struct BrowserGuard {
+    browser: Browser,
+    tasks: Vec<AbortHandle>,
+    connection: Connection,
+    disconnected: Disconnected,
+    profile: Option<TempDir>,
+    runner: TaskRunner,
+}
+
+impl BrowserGuard {
+    /// Takes the ownership of the browser, and runs its event handler with the runner
+    pub(crate) fn new(browser: Browser, mut handler: Handler, runner: TaskRunner) -> Self {
+        let (connection, disconnected) = Connection::new();
+        let mut guard = Self {
+            browser,
+            tasks: Vec::with_capacity(1),
+            connection: connection.clone(),
+            disconnected,
+            profile: None,
+            runner,
+        };
+        guard.spawn(async move {
+            // The stream only ends when the browser is closed gracefully, when the browser crashes or gets killed
+            // it yields a websocket error and then keeps pending forever
+            while let Some(res) = handler.next().await {
+                if let Err(CdpError::Ws(_e)) = res {
+                    #[cfg(feature = "log")]
+                    debug!("Lost the connection with the browser: {_e}");
+                    break;
+                }
+            }
+            connection.lost();
+        });
+        guard
+    }
+
+    /// Pings the browser through the page every `interval` until the guard is dropped, to keep the idle connection alive.
+    ///
+    /// A ping that gets no answer is considered a disconnection, every issued ping is counted in `pings`.
+    pub(crate) fn keep_alive(
+        &mut self,
+        page: Page,
+        interval: Duration,
+        clock: Arc<dyn Clock>,
+        pings: Arc<AtomicU32>,
+    ) {
+        let connection = self.connection.clone();
+        self.spawn(async move {
+            loop {
+                clock.sleep(interval).await;
+                pings.fetch_add(1, Ordering::Relaxed);
+                match page.execute(EvaluateParams::new("1")).await {
+                    Ok(_) => {}
+                    // The browser answered, even if with an error (e.g. the page is navigating), so it's still alive
+                    Err(CdpError::ChromeMessage(_) | CdpError::Chrome(_)) => {}
+                    Err(_e) => {
+                        #[cfg(feature = "log")]
+                        debug!("The keep-alive ping failed: {_e}");
+                        connection.lost();
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Takes the ownership of the temporary profile of the browser, to remove it once the browser is gone
+    pub(crate) fn own_profile(&mut self, profile: TempDir) {
+        self.profile = Some(profile);
+    }
+
+    /// A future that completes once the connection with the browser is lost
+    pub(crate) fn disconnected(&self) -> Disconnected {
+        self.disconnected.clone()
+    }
+
+    /// Spawns a background task that lives at most as long as the browser
+    pub(crate) fn spawn<F>(&mut self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (handle, registration) = AbortHandle::new_pair();
+        self.runner.run(async move {
+            let _ = Abortable::new(task, registration).await;
+        });
+        self.tasks.push(handle);
+    }
+}
+
+/// Runs the background tasks of a login as the [`TaskMode`] tells
+#[derive(Clone)]
+pub(crate) enum TaskRunner {
+    Spawn,
+    Local,
+    Inline(Arc<Mutex<InlineTasks>>),
+}
+
+/// The tasks that are waiting for [`TaskRunner::drive`] to pick them up
+#[derive(Default)]
+pub(crate) struct InlineTasks {
+    pending: Vec<BoxFuture<'static, ()>>,
+    /// Wakes the driver up when a task is added
+    waker: Option<Waker>,
+}
+
+impl TaskRunner {
+    pub(crate) fn new(mode: TaskMode) -> Self {
+        match mode {
+            TaskMode::Spawn => Self::Spawn,
+            TaskMode::Local => Self::Local,
+            TaskMode::Inline => Self::Inline(Arc::default()),
+        }
+    }
+
+    fn run<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        match self {
+            Self::Spawn => crate::spawn(task),
+            Self::Local => crate::spawn_local(task),
+            Self::Inline(inline) => {
+                let mut inline = inline.lock().unwrap();
+                inline.pending.push(task.boxed());
+                if let Some(waker) = inline.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+
+    /// Runs the future, and the inline tasks alongside it until it completes
+    pub(crate) async fn drive<F: Future>(&self, future: F) -> F::Output {
+        let Self::Inline(inline) = self else {
+            return future.await;
+        };
+        let mut future = std::pin::pin!(future);
+        let mut running = FuturesUnordered::new();
+        futures::future::poll_fn(|cx| {
+            if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                return Poll::Ready(output);
+            }
+            loop {
+                {
+                    let mut inline = inline.lock().unwrap();
+                    running.extend(inline.pending.drain(..));
+                    inline.waker = Some(cx.waker().clone());
+                }
+                // A finished task may have more work for the future, it gets polled again once it's woken
+                match running.poll_next_unpin(cx) {
+                    Poll::Ready(Some(())) => continue,
+                    Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+                }
+            }
+        })
+        .await
+    }
+}
+
+/// See [`BrowserGuard::disconnected`]
+pub(crate) type Disconnected = Shared<oneshot::Receiver<()>>;
+
+/// The state of the connection with the browser, shared by the tasks that can detect its loss
+#[derive(Clone)]
+struct Connection(Arc<Mutex<Option<oneshot::Sender<()>>>>);
+
+impl Connection {
+    fn new() -> (Self, Disconnected) {
+        // Nothing is ever sent, the receiver completes when the sender gets dropped
+        let (sender, receiver) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(sender)))), receiver.shared())
+    }
+
+    /// Marks the connection as lost, completing the [`Disconnected`] futures
+    fn lost(&self) {
+        if let Ok(mut sender) = self.0.lock() {
+            sender.take();
+        }
+    }
+}
+
+impl Deref for BrowserGuard {
+    type Target = Browser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.browser
+    }
+}
+
+impl DerefMut for BrowserGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.browser
+    }
+}
+
+impl Drop for BrowserGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "log")]
+        debug!("Canceling the event handler and the background tasks");
+        self.tasks.iter().for_each(AbortHandle::abort);
+
+        self.kill();
+        if let Some(profile) = self.profile.take() {
+            remove_profile(profile);
+        }
+    }
+}
+
+impl BrowserGuard {
+    fn kill(&mut self) {
+        let Some(child) = self.browser.get_mut_child() else {
+            return;
+        };
+        if let Ok(Some(_)) = child.try_wait() {
+            // Already exited, nothing to clean up
+            return;
+        }
+
+        #[cfg(feature = "log")]
+        debug!("Killing the browser process");
+        // chromiumoxide uses the async-std process implementation whenever its async-std runtime is enabled.
+        // The process is reaped in the background by the runtime after the browser gets dropped.
+        #[cfg(feature = "__async-std")]
+        let _res = child.as_mut_inner().kill();
+        #[cfg(all(feature = "tokio", not(feature = "__async-std")))]
+        let _res = child.as_mut_inner().start_kill();
+
+        #[cfg(feature = "log")]
+        if let Err(e) = _res {
+            trace!("Couldn't kill the browser process: {e}");
+        }
+    }
+}
+
+/// Removes the temporary profile in the background,
+/// the browser processes may still be writing to it for a moment after the kill
+fn remove_profile(profile: TempDir) {
+    const ATTEMPTS: usize = 50;
+    std::thread::spawn(move || {
+        for _ in 0..ATTEMPTS {
+            match std::fs::remove_dir_all(profile.path()) {
+                Ok(()) => return,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+                Err(_) => std::thread::sleep(Duration::from_millis(100)),
+            }
+        }
+        #[cfg(feature = "log")]
+        debug!("Couldn't remove the temporary profile {:?}", profile.path());
+    });
+}
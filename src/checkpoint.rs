@@ -0,0 +1,44 @@
+use crate::outage::{has_password_field, visible_text};
+
+/// The phrases of the "check your email" page, in its visible text.
+///
+/// They're matched in lowercase, with the typographic apostrophes replaced by plain ones.
+const EMAIL_CONFIRMATION_MARKERS: &[&str] = &[
+    "check your email",
+    "check your inbox",
+    "we sent you an email",
+    "we sent an email",
+    "we've sent an email",
+    "we emailed you a link",
+    "click the link in the email",
+    "confirm it's you",
+];
+
+/// Returns `true` if the HTML is the "check your email" page of pinterest: the login is parked until the link that
+/// pinterest sent by email is clicked, from any device.
+///
+/// It's not the two-factor authentication page, that asks for a code in the page. Only the visible text is searched,
+/// and a page with a password field is never this one, so the login form isn't taken for it.
+///
+/// # Example
+/// ```
+/// use pinterest_login::checkpoint::is_email_confirmation_page;
+///
+/// let checkpoint = "<html><body><h1>Confirm it’s you</h1>
+///     <p>We sent an email to m***@example.com, click the link in the email to log in.</p></body></html>";
+/// assert!(is_email_confirmation_page(checkpoint));
+///
+/// let login = r#"<html><body><form><input id="email"><input id="password" type="password">
+///     <p>Check your email for the reset link</p></form></body></html>"#;
+/// assert!(!is_email_confirmation_page(login));
+/// ```
+pub fn is_email_confirmation_page(html: &str) -> bool {
+    let html = html.to_lowercase();
+    if has_password_field(&html) {
+        return false;
+    }
+    let text = visible_text(&html).replace('\u{2019}', "'");
+    EMAIL_CONFIRMATION_MARKERS
+        .iter()
+        .any(|marker| text.contains(marker))
+}
@@ -0,0 +1,431 @@
+//! Chrome keeps its cookies in the `Cookies` SQLite database of the profile (`Network/Cookies` since chrome 96),
+//! with their values encrypted with a key of the OS:
+//!
+//! | OS      | Prefix | Encryption                                                                               |
+//! |---------|--------|------------------------------------------------------------------------------------------|
+//! | Linux   | `v10`  | AES-128-CBC, with a key derived from the password `peanuts`                              |
+//! | Linux   | `v11`  | AES-128-CBC, with a key derived from the password in the secret service (GNOME keyring)  |
+//! | macOS   | `v10`  | AES-128-CBC, with a key derived from the `Chrome Safe Storage` password of the Keychain  |
+//! | Windows | `v10`  | AES-256-GCM, with the key of the `Local State` file, itself encrypted with DPAPI          |
+//! | Windows | none   | DPAPI, for the cookies of the old versions                                               |
+//!
+//! The `v20` cookies of the app-bound encryption of the recent chrome versions on Windows can't be decrypted by
+//! another program, they're [`ChromeImportError::UnsupportedEncryption`] errors.
+
+#[cfg(not(windows))]
+use std::process::Command;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use cbc::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use rusqlite::{ErrorCode, OpenFlags};
+use sha1::Sha1;
+
+use crate::{session::Session, PinterestCookies};
+
+/// The salt of the keys that are derived from a password
+const SALT: &[u8] = b"saltysalt";
+/// The IV of the AES-128-CBC cookies, 16 spaces
+const IV: [u8; 16] = [b' '; 16];
+/// The password of the `v10` cookies on linux, when there's no keyring
+const LINUX_PASSWORD: &str = "peanuts";
+/// The seconds between the 1601-01-01 epoch of the chrome timestamps and the unix epoch
+const WINDOWS_EPOCH_OFFSET: u64 = 11_644_473_600;
+/// The databases from this version prefix the values with the SHA-256 of their domain
+const DOMAIN_HASH_VERSION: i64 = 24;
+
+/// Why a session can't be imported from a chrome profile
+#[derive(Debug, thiserror::Error)]
+pub enum ChromeImportError {
+    /// Chrome is running and holds the lock of its cookies
+    #[error(
+        "The cookies database {0} is locked by chrome, close it or pass a copy of the `Cookies` file"
+    )]
+    Locked(PathBuf),
+    /// The profile has no cookies database
+    #[error("There's no chrome cookies database in {0}")]
+    NotFound(PathBuf),
+    /// The database can't be read
+    #[error("Can't read the chrome cookies: {0}")]
+    Database(#[from] rusqlite::Error),
+    /// A file of the profile can't be read
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The key of the cookies can't be obtained from the OS
+    #[error("The key of the chrome cookies isn't available: {0}")]
+    KeyUnavailable(String),
+    /// A cookie is encrypted with a scheme that can't be decrypted here
+    #[error("The cookie `{name}` is encrypted with the unsupported `{scheme}` scheme")]
+    UnsupportedEncryption {
+        /// The name of the cookie
+        name: String,
+        /// The prefix of its value
+        scheme: String,
+    },
+    /// A cookie can't be decrypted with the key
+    #[error("The cookie `{0}` can't be decrypted, the key is wrong")]
+    DecryptionFailed(String),
+    /// The profile isn't logged in to pinterest
+    #[error("The chrome profile has no pinterest session, log in to pinterest with it first")]
+    NotLoggedIn,
+}
+
+/// The key of the cookies of a chrome profile, see the [module docs](self)
+#[derive(Clone)]
+pub struct ChromeKey(Scheme);
+
+#[derive(Clone)]
+enum Scheme {
+    Cbc {
+        v10: [u8; 16],
+        v11: Option<[u8; 16]>,
+    },
+    #[cfg(windows)]
+    Gcm([u8; 32]),
+}
+
+impl ChromeKey {
+    /// The key of the linux profiles, with the password of the secret service if there's one.
+    /// Without it only the `v10` cookies, of the profiles that don't use a keyring, can be decrypted.
+    pub fn linux(keyring_password: Option<&str>) -> Self {
+        Self(Scheme::Cbc {
+            v10: derive(LINUX_PASSWORD, 1),
+            v11: keyring_password.map(|password| derive(password, 1)),
+        })
+    }
+
+    /// The key of the macOS profiles, with the `Chrome Safe Storage` password of the Keychain
+    pub fn macos(keychain_password: &str) -> Self {
+        Self(Scheme::Cbc {
+            v10: derive(keychain_password, 1003),
+            v11: None,
+        })
+    }
+
+    /// The key of the profile, obtained from the OS: the secret service on linux (through `secret-tool`),
+    /// the Keychain on macOS (through `security`, which may ask for the permission), and the `Local State` file
+    /// of the profile decrypted with DPAPI on Windows.
+    ///
+    /// # Errors
+    /// [`ChromeImportError::KeyUnavailable`] if the OS doesn't give the key, on linux it's not an error since the
+    /// profiles without a keyring don't need it.
+    pub fn for_profile(profile_dir: &Path) -> Result<Self, ChromeImportError> {
+        #[cfg(target_os = "macos")]
+        {
+            let _ = profile_dir;
+            let password = ["Chrome Safe Storage", "Chromium Safe Storage"]
+                .into_iter()
+                .find_map(|service| {
+                    command_output(Command::new("security").args([
+                        "find-generic-password",
+                        "-w",
+                        "-s",
+                        service,
+                    ]))
+                })
+                .ok_or_else(|| {
+                    ChromeImportError::KeyUnavailable(
+                        "the Keychain has no `Chrome Safe Storage` password".to_string(),
+                    )
+                })?;
+            Ok(Self::macos(&password))
+        }
+        #[cfg(windows)]
+        {
+            windows::key_of_profile(profile_dir)
+        }
+        #[cfg(not(any(target_os = "macos", windows)))]
+        {
+            let _ = profile_dir;
+            let password = ["chrome", "chromium"].into_iter().find_map(|application| {
+                command_output(Command::new("secret-tool").args([
+                    "lookup",
+                    "application",
+                    application,
+                ]))
+            });
+            Ok(Self::linux(password.as_deref()))
+        }
+    }
+
+    fn decrypt(&self, name: &str, encrypted: &[u8]) -> Result<Vec<u8>, ChromeImportError> {
+        let (prefix, ciphertext) = encrypted.split_at(encrypted.len().min(3));
+        let unsupported = || ChromeImportError::UnsupportedEncryption {
+            name: name.to_string(),
+            scheme: String::from_utf8_lossy(prefix).into_owned(),
+        };
+        let failed = || ChromeImportError::DecryptionFailed(name.to_string());
+        match (&self.0, prefix) {
+            (Scheme::Cbc { v10, v11 }, b"v10" | b"v11") => {
+                let key = if prefix == b"v10" {
+                    Some(v10)
+                } else {
+                    v11.as_ref()
+                };
+                let key = key.ok_or_else(|| {
+                    ChromeImportError::KeyUnavailable(format!(
+                        "the cookie `{name}` needs the password of the keyring"
+                    ))
+                })?;
+                let mut value = ciphertext.to_vec();
+                let len = cbc::Decryptor::<aes::Aes128>::new(key.into(), (&IV).into())
+                    .decrypt_padded_mut::<Pkcs7>(&mut value)
+                    .map_err(|_| failed())?
+                    .len();
+                value.truncate(len);
+                Ok(value)
+            }
+            #[cfg(windows)]
+            (Scheme::Gcm(key), b"v10") => windows::decrypt_gcm(key, ciphertext).ok_or_else(failed),
+            #[cfg(windows)]
+            (Scheme::Gcm(_), b"v20") => Err(unsupported()),
+            #[cfg(windows)]
+            (Scheme::Gcm(_), _) => windows::unprotect(encrypted).map_err(|_| failed()),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+impl std::fmt::Debug for ChromeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ChromeKey(..)")
+    }
+}
+
+impl Session {
+    /// Imports the pinterest session of a chrome profile (e.g. `~/.config/google-chrome/Default`), decrypted with the
+    /// [key of the OS](ChromeKey::for_profile), so there's no need to log in again.
+    ///
+    /// The profile can't be read while chrome is running, close it or copy its `Cookies` file and use
+    /// [`Session::from_chrome_profile_with`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pinterest_login::session::Session;
+    ///
+    /// let home = std::env::var("HOME")?;
+    /// let session = Session::from_chrome_profile("me@example.com", format!("{home}/.config/google-chrome/Default"))?;
+    /// session.save("session.json")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    /// [`ChromeImportError::Locked`] if chrome is running, [`ChromeImportError::NotLoggedIn`] if the profile has no
+    /// pinterest session, and the errors of the key and the decryption.
+    pub fn from_chrome_profile(
+        account: impl Into<String>,
+        profile_dir: impl AsRef<Path>,
+    ) -> Result<Self, ChromeImportError> {
+        let profile_dir = profile_dir.as_ref();
+        Self::from_chrome_profile_with(account, profile_dir, &ChromeKey::for_profile(profile_dir)?)
+    }
+
+    /// Imports the pinterest session of a chrome profile, or of a `Cookies` database file, decrypted with the key.
+    ///
+    /// The cookies of every `pinterest.com` domain are kept, except the expired ones, and the session expires with
+    /// the earliest of them.
+    pub fn from_chrome_profile_with(
+        account: impl Into<String>,
+        path: impl AsRef<Path>,
+        key: &ChromeKey,
+    ) -> Result<Self, ChromeImportError> {
+        let path = cookies_file(path.as_ref())?;
+        let connection = rusqlite::Connection::open_with_flags(
+            &path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        // Chrome keeps the lock while it runs, there's no point in waiting for it
+        connection.busy_timeout(Duration::ZERO)?;
+        read_cookies(&connection, key)
+            .map_err(|e| match e {
+                ChromeImportError::Database(rusqlite::Error::SqliteFailure(error, _))
+                    if matches!(
+                        error.code,
+                        ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked
+                    ) =>
+                {
+                    ChromeImportError::Locked(path)
+                }
+                e => e,
+            })
+            .and_then(|(cookies, expires_at)| {
+                if !cookies.contains_key("_pinterest_sess") {
+                    return Err(ChromeImportError::NotLoggedIn);
+                }
+                let session = Session::new(account, cookies);
+                Ok(match expires_at {
+                    Some(expires_at) => session.with_expires_at(expires_at),
+                    None => session,
+                })
+            })
+    }
+}
+
+/// The database of the profile, or the path itself if it's a file
+fn cookies_file(path: &Path) -> Result<PathBuf, ChromeImportError> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    [path.join("Network").join("Cookies"), path.join("Cookies")]
+        .into_iter()
+        .find(|file| file.is_file())
+        .ok_or_else(|| ChromeImportError::NotFound(path.to_path_buf()))
+}
+
+/// The unexpired pinterest cookies, and the earliest expiry of the ones that aren't session cookies
+fn read_cookies(
+    connection: &rusqlite::Connection,
+    key: &ChromeKey,
+) -> Result<(PinterestCookies, Option<SystemTime>), ChromeImportError> {
+    let version: i64 = connection
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })?
+        .parse()
+        .unwrap_or(0);
+    let mut statement = connection.prepare(
+        "SELECT host_key, name, value, encrypted_value, expires_utc FROM cookies ORDER BY creation_utc",
+    )?;
+    let mut rows = statement.query([])?;
+    let now = SystemTime::now();
+    let mut cookies = PinterestCookies::new();
+    let mut expires_at: Option<SystemTime> = None;
+    while let Some(row) = rows.next()? {
+        let host: String = row.get(0)?;
+        let domain = host.trim_start_matches('.');
+        if domain != "pinterest.com" && !domain.ends_with(".pinterest.com") {
+            continue;
+        }
+        let name: String = row.get(1)?;
+        let expires = match row.get::<_, i64>(4)? {
+            // A session cookie
+            0 => None,
+            expires => Some(
+                SystemTime::UNIX_EPOCH
+                    + Duration::from_micros(expires as u64)
+                        .saturating_sub(Duration::from_secs(WINDOWS_EPOCH_OFFSET)),
+            ),
+        };
+        if expires.is_some_and(|expires| expires <= now) {
+            continue;
+        }
+        let encrypted: Vec<u8> = row.get(3)?;
+        let value = if encrypted.is_empty() {
+            row.get(2)?
+        } else {
+            let mut value = key.decrypt(&name, &encrypted)?;
+            if version >= DOMAIN_HASH_VERSION {
+                value.drain(..value.len().min(32));
+            }
+            String::from_utf8(value)
+                .map_err(|_| ChromeImportError::DecryptionFailed(name.clone()))?
+        };
+        if let Some(expires) = expires {
+            expires_at = Some(expires_at.map_or(expires, |earliest| earliest.min(expires)));
+        }
+        cookies.insert(name, value);
+    }
+    Ok((cookies, expires_at))
+}
+
+/// An AES-128 key derived from the password with PBKDF2-HMAC-SHA1
+fn derive(password: &str, rounds: u32) -> [u8; 16] {
+    let mut key = [0; 16];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), SALT, rounds, &mut key);
+    key
+}
+
+/// The first line of the output of the command, if it succeeds and prints something
+#[cfg(not(windows))]
+fn command_output(command: &mut Command) -> Option<String> {
+    let output = command
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let output = String::from_utf8(output.stdout).ok()?;
+    let line = output.lines().next()?.trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::{fs, path::Path, ptr};
+
+    use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+    use windows_sys::Win32::{
+        Foundation::LocalFree,
+        Security::Cryptography::{CryptUnprotectData, CRYPT_INTEGER_BLOB},
+    };
+
+    use super::{ChromeImportError, ChromeKey, Scheme};
+
+    /// The AES-256-GCM key of the `Local State` file of the user data directory, the parent of the profile
+    pub(super) fn key_of_profile(profile_dir: &Path) -> Result<ChromeKey, ChromeImportError> {
+        let unavailable = |reason: &str| ChromeImportError::KeyUnavailable(reason.to_string());
+        let local_state = profile_dir
+            .parent()
+            .map(|dir| dir.join("Local State"))
+            .ok_or_else(|| unavailable("the profile has no user data directory"))?;
+        let local_state: serde_json::Value = serde_json::from_slice(&fs::read(local_state)?)
+            .map_err(|_| unavailable("the `Local State` file isn't valid JSON"))?;
+        let encrypted = local_state["os_crypt"]["encrypted_key"]
+            .as_str()
+            .and_then(|key| data_encoding::BASE64.decode(key.as_bytes()).ok())
+            .ok_or_else(|| unavailable("the `Local State` file has no key"))?;
+        let encrypted = encrypted
+            .strip_prefix(b"DPAPI")
+            .ok_or_else(|| unavailable("the key of the `Local State` file isn't a DPAPI one"))?;
+        let key = unprotect(encrypted)?
+            .try_into()
+            .map_err(|_| unavailable("the key of the `Local State` file isn't 32 bytes long"))?;
+        Ok(ChromeKey(Scheme::Gcm(key)))
+    }
+
+    pub(super) fn decrypt_gcm(key: &[u8; 32], encrypted: &[u8]) -> Option<Vec<u8>> {
+        if encrypted.len() < 12 {
+            return None;
+        }
+        let (nonce, ciphertext) = encrypted.split_at(12);
+        Aes256Gcm::new(key.into())
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()
+    }
+
+    /// Decrypts the data with the DPAPI key of the current user
+    pub(super) fn unprotect(data: &[u8]) -> Result<Vec<u8>, ChromeImportError> {
+        let input = CRYPT_INTEGER_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8,
+        };
+        let mut output = CRYPT_INTEGER_BLOB {
+            cbData: 0,
+            pbData: ptr::null_mut(),
+        };
+        // SAFETY: the input is a valid buffer that's only read, and the output is allocated by the call
+        let succeeded = unsafe {
+            CryptUnprotectData(
+                &input,
+                ptr::null_mut(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                0,
+                &mut output,
+            )
+        };
+        if succeeded == 0 {
+            return Err(ChromeImportError::KeyUnavailable(format!(
+                "DPAPI can't decrypt it: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        // SAFETY: the call succeeded, so the output is a buffer of `cbData` bytes that has to be freed with `LocalFree`
+        let plaintext =
+            unsafe { std::slice::from_raw_parts(output.pbData, output.cbData as usize) }.to_vec();
+        unsafe { LocalFree(output.pbData.cast()) };
+        Ok(plaintext)
+    }
+}
@@ -0,0 +1,233 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::{future::BoxFuture, FutureExt};
+
+/// The source of time of the login: every wait, retry delay and timeout goes through it.
+///
+/// The default one is [`SystemClock`]. The `test-util` feature adds a `ManualClock` that only moves when it's told to,
+/// so the time based logic can be tested in a few milliseconds, pass it with [`LoginOptions::clock`](crate::options::LoginOptions::clock)
+/// and [`DefaultBrowserLoginBot::with_clock`](crate::login_bot::DefaultBrowserLoginBot::with_clock).
+///
+/// Like the [`BrowserLauncher`](crate::launcher::BrowserLauncher), this trait is object safe, so it returns a boxed future.
+pub trait Clock: Send + Sync {
+    /// The current time
+    fn now(&self) -> Instant;
+    /// The current wall time, to compare with the expiries of the sessions
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+    /// Waits for the duration
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The real clock, that sleeps on the selected runtime
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        crate::sleep(duration).boxed()
+    }
+}
+
+#[cfg(feature = "test-util")]
+pub use manual::ManualClock;
+
+#[cfg(feature = "test-util")]
+mod manual {
+    use std::{
+        future::Future,
+        pin::Pin,
+        sync::{Arc, Mutex, MutexGuard},
+        task::{Context, Poll, Waker},
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use futures::future::BoxFuture;
+
+    use super::Clock;
+
+    /// A [`Clock`] that only moves when it's advanced, to test the time based logic without waiting.
+    ///
+    /// Every requested sleep is recorded, see [`ManualClock::sleeps`], so the tests can assert the exact delays.
+    /// Its [`system_time`](Clock::system_time) moves along with it, from the time it was created at.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use pinterest_login::clock::{Clock, ManualClock};
+    ///
+    /// # async_std::task::block_on(async {
+    /// let clock = ManualClock::new();
+    /// let start = clock.now();
+    ///
+    /// // Resolves right away, the clock jumps to the end of the sleep
+    /// clock.drive(clock.sleep(Duration::from_secs(60))).await;
+    ///
+    /// assert_eq!(clock.now() - start, Duration::from_secs(60));
+    /// assert_eq!(clock.sleeps(), [Duration::from_secs(60)]);
+    /// # });
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct ManualClock {
+        state: Arc<Mutex<State>>,
+    }
+
+    #[derive(Debug)]
+    struct State {
+        start: (Instant, SystemTime),
+        now: Instant,
+        sleeps: Vec<Duration>,
+        sleepers: Vec<Sleeper>,
+        next_id: u64,
+    }
+
+    #[derive(Debug)]
+    struct Sleeper {
+        id: u64,
+        deadline: Instant,
+        waker: Option<Waker>,
+    }
+
+    impl ManualClock {
+        /// A clock that starts at the current time
+        pub fn new() -> Self {
+            let now = Instant::now();
+            Self {
+                state: Arc::new(Mutex::new(State {
+                    start: (now, SystemTime::now()),
+                    now,
+                    sleeps: Vec::new(),
+                    sleepers: Vec::new(),
+                    next_id: 0,
+                })),
+            }
+        }
+
+        /// Moves the clock forward, which wakes up the sleeps that are over
+        pub fn advance(&self, duration: Duration) {
+            let now = self.state().now + duration;
+            self.advance_to(now);
+        }
+
+        /// All the sleeps that were requested so far, in order
+        pub fn sleeps(&self) -> Vec<Duration> {
+            self.state().sleeps.clone()
+        }
+
+        /// How many sleeps are still waiting for the clock
+        pub fn pending(&self) -> usize {
+            self.state().sleepers.len()
+        }
+
+        /// Runs the future, and whenever it's stuck while something sleeps, advances the clock to the end of the
+        /// earliest sleep.
+        ///
+        /// Only meant for the futures that wait on nothing but the clock: a future that waits for anything else
+        /// (like a browser) while a timeout is pending would see the timeout expire right away.
+        pub async fn drive<F: Future>(&self, future: F) -> F::Output {
+            let mut future = std::pin::pin!(future);
+            futures::future::poll_fn(|cx| loop {
+                if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                    return Poll::Ready(output);
+                }
+                let next = self.state().sleepers.iter().map(|s| s.deadline).min();
+                match next {
+                    Some(deadline) => self.advance_to(deadline),
+                    None => return Poll::Pending,
+                }
+            })
+            .await
+        }
+
+        fn advance_to(&self, now: Instant) {
+            let wakers: Vec<_> = {
+                let mut state = self.state();
+                state.now = state.now.max(now);
+                let now = state.now;
+                state
+                    .sleepers
+                    .iter_mut()
+                    .filter(|s| s.deadline <= now)
+                    .filter_map(|s| s.waker.take())
+                    .collect()
+            };
+            wakers.into_iter().for_each(Waker::wake);
+        }
+
+        fn state(&self) -> MutexGuard<'_, State> {
+            // A panicking test can't poison anything that matters
+            self.state.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    impl Default for ManualClock {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.state().now
+        }
+
+        fn system_time(&self) -> SystemTime {
+            let state = self.state();
+            let (start, system_start) = state.start;
+            system_start + (state.now - start)
+        }
+
+        fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+            let mut state = self.state();
+            state.sleeps.push(duration);
+            let id = state.next_id;
+            state.next_id += 1;
+            let deadline = state.now + duration;
+            state.sleepers.push(Sleeper {
+                id,
+                deadline,
+                waker: None,
+            });
+            Box::pin(Sleep {
+                clock: self.clone(),
+                id,
+                deadline,
+            })
+        }
+    }
+
+    /// A sleep of the [`ManualClock`], it's forgotten by the clock once it's over or dropped
+    struct Sleep {
+        clock: ManualClock,
+        id: u64,
+        deadline: Instant,
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let mut state = self.clock.state();
+            if state.now >= self.deadline {
+                state.sleepers.retain(|s| s.id != self.id);
+                return Poll::Ready(());
+            }
+            if let Some(sleeper) = state.sleepers.iter_mut().find(|s| s.id == self.id) {
+                sleeper.waker = Some(cx.waker().clone());
+            }
+            Poll::Pending
+        }
+    }
+
+    impl Drop for Sleep {
+        fn drop(&mut self) {
+            self.clock.state().sleepers.retain(|s| s.id != self.id);
+        }
+    }
+}
@@ -1,5 +1,10 @@
-use crate::PinterestLoginError;
-use chromiumoxide::BrowserConfig;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{preset::Preset, PinterestLoginError};
+use chromiumoxide::{
+    detection::{default_executable, DetectionOptions},
+    BrowserConfig,
+};
 #[cfg(feature = "log")]
 use log::{debug, info, trace};
 
@@ -29,23 +34,56 @@ use log::{debug, info, trace};
 pub trait BrowserConfigBuilder {
     /// Builds a chromiumoxide browser config
     fn build_browser_config(&self) -> crate::Result<BrowserConfig>;
+
+    /// The path of the browser executable that the config will launch, if it's known.
+    ///
+    /// It's only used to make the errors more helpful, the default implementation returns `None`.
+    fn executable(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// The maximum time to wait for the browser to start and answer its first CDP request (the handshake).
+    ///
+    /// If the handshake doesn't complete in time, the login fails with [`PinterestLoginError::HandshakeTimeout`].
+    /// The default implementation returns [`DEFAULT_HANDSHAKE_TIMEOUT`].
+    fn handshake_timeout(&self) -> Duration {
+        DEFAULT_HANDSHAKE_TIMEOUT
+    }
+
+    /// The proxy server that the browser goes through, if any.
+    ///
+    /// It's used to classify the navigation errors, the default implementation returns `None`.
+    fn proxy(&self) -> Option<String> {
+        None
+    }
 }
 
+/// The default timeout for the browser handshake, see [`BrowserConfigBuilder::handshake_timeout`]
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(20);
+
 /// The default browser config builder, that provides a method to build a chromiumoxide browser config
 /// This builder enables you to set the headless mode, the request timeout and the launch timeout
 ///
 /// # Example
-/// ```
+/// ```no_run
 /// # use pinterest_login::config_builder::{BrowserConfigBuilder, DefaultBrowserConfigBuilder};
 /// use std::time::Duration;
 ///
 /// let browser_config_builder = DefaultBrowserConfigBuilder::new(true, Duration::from_secs(3).into(), None);
 /// let browser_config = browser_config_builder.build_browser_config().unwrap();
 /// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DefaultBrowserConfigBuilder {
     headless: bool,
     request_timeout: Option<std::time::Duration>,
     launch_timeout: Option<std::time::Duration>,
+    executable: Option<PathBuf>,
+    proxy: Option<String>,
+    env_proxy: bool,
+    user_data_dir: Option<PathBuf>,
+    images: bool,
+    webrtc_protection: bool,
+    window_size: Option<(u32, u32)>,
 }
 
 impl DefaultBrowserConfigBuilder {
@@ -64,8 +102,139 @@ impl DefaultBrowserConfigBuilder {
             headless,
             request_timeout,
             launch_timeout,
+            executable: None,
+            proxy: None,
+            env_proxy: false,
+            user_data_dir: None,
+            images: true,
+            webrtc_protection: false,
+            window_size: None,
         }
     }
+
+    /// Sets the defaults of the preset, see [`Preset`] for what it sets. Call it first, the setters called after it
+    /// override its values.
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+    /// use pinterest_login::preset::Preset;
+    ///
+    /// // The debug preset, but headless on a CI server
+    /// let config_builder = DefaultBrowserConfigBuilder::default()
+    ///     .preset(Preset::Debug)
+    ///     .with_headless(true);
+    /// ```
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.headless = preset != Preset::Debug;
+        self.request_timeout = Some(preset.request_timeout());
+        self.launch_timeout = Some(preset.launch_timeout());
+        self.images = preset != Preset::Fast;
+        self.webrtc_protection = preset == Preset::Stealthy;
+        self.window_size = match preset {
+            Preset::Stealthy => Some((1920, 1080)),
+            Preset::Fast | Preset::Debug => None,
+        };
+        self
+    }
+
+    /// Whether to launch the browser in headless mode, see [`DefaultBrowserConfigBuilder::new`]
+    pub fn with_headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    /// Sets the timeout for the requests, `None` for no timeout
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout for launching the browser, `None` for no timeout
+    pub fn with_launch_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.launch_timeout = timeout;
+        self
+    }
+
+    /// Whether the browser loads the images, the default is `true`. Without them the login page loads faster, and the
+    /// form doesn't need them.
+    pub fn with_images(mut self, images: bool) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Keeps WebRTC from revealing the real IP address behind the proxy, by only letting it go through the proxy, the
+    /// default is `false`
+    pub fn with_webrtc_protection(mut self, protection: bool) -> Self {
+        self.webrtc_protection = protection;
+        self
+    }
+
+    /// Sets the size of the browser window, instead of the default one of Chrome
+    pub fn with_window_size(mut self, width: u32, height: u32) -> Self {
+        self.window_size = Some((width, height));
+        self
+    }
+
+    /// Sets the path of the browser executable, instead of detecting it automatically
+    pub fn with_executable(mut self, path: impl Into<PathBuf>) -> Self {
+        self.executable = Some(path.into());
+        self
+    }
+
+    /// Sends the browser traffic through the proxy server, like `http://127.0.0.1:8080` or `socks5://127.0.0.1:1080`
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::config_builder::{BrowserConfigBuilder, DefaultBrowserConfigBuilder};
+    ///
+    /// let config_builder = DefaultBrowserConfigBuilder::default().with_proxy("socks5://127.0.0.1:1080");
+    /// assert_eq!(config_builder.proxy().as_deref(), Some("socks5://127.0.0.1:1080"));
+    /// ```
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Reads the proxy from the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables (or their lowercase
+    /// variants, that win over the uppercase ones) when no proxy is set with [`with_proxy`](Self::with_proxy).
+    ///
+    /// It's off by default, so the browser goes direct whatever the environment says. The variables are read when the
+    /// config is built, see [`EnvProxy`] for how they're translated into the flags of Chrome; a malformed one fails
+    /// the build with a [`PinterestLoginError::BrowserConfigBuildError`] that names it.
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::config_builder::{BrowserConfigBuilder, DefaultBrowserConfigBuilder};
+    ///
+    /// // The explicit proxy wins over the environment
+    /// let config_builder = DefaultBrowserConfigBuilder::default()
+    ///     .with_env_proxy(true)
+    ///     .with_proxy("socks5://127.0.0.1:1080");
+    /// assert_eq!(config_builder.proxy().as_deref(), Some("socks5://127.0.0.1:1080"));
+    /// ```
+    pub fn with_env_proxy(mut self, enabled: bool) -> Self {
+        self.env_proxy = enabled;
+        self
+    }
+
+    /// The proxy of the environment if it's read and no proxy is set explicitly
+    fn env_proxy(&self) -> crate::Result<Option<EnvProxy>> {
+        if self.proxy.is_some() || !self.env_proxy {
+            return Ok(None);
+        }
+        EnvProxy::from_env()
+    }
+
+    /// Uses the browser profile in the directory (it's created if it's missing), instead of a throwaway profile
+    /// for every launch.
+    ///
+    /// A profile can't be used by two browsers at the same time, so the concurrent logins need their own.
+    /// See [`LoginOptions::incognito`](crate::options::LoginOptions::incognito) to keep the cookies in it.
+    pub fn with_user_data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.user_data_dir = Some(dir.into());
+        self
+    }
 }
 
 impl BrowserConfigBuilder for DefaultBrowserConfigBuilder {
@@ -100,6 +269,48 @@ impl BrowserConfigBuilder for DefaultBrowserConfigBuilder {
             browser_config_builder = browser_config_builder.launch_timeout(timeout);
         }
 
+        if let Some(ref executable) = self.executable {
+            #[cfg(feature = "log")]
+            trace!("Setting the browser executable to {:?}", executable);
+            browser_config_builder = browser_config_builder.chrome_executable(executable);
+        }
+
+        if let Some(ref proxy) = self.proxy {
+            #[cfg(feature = "log")]
+            trace!("Setting the proxy server to {}", proxy);
+            browser_config_builder = browser_config_builder.arg(format!("--proxy-server={proxy}"));
+        } else if let Some(proxy) = self.env_proxy()? {
+            #[cfg(feature = "log")]
+            trace!("Setting the proxy of the environment: {:?}", proxy);
+            browser_config_builder = browser_config_builder.args(proxy.args());
+        }
+
+        if !self.images {
+            #[cfg(feature = "log")]
+            trace!("Disabling the images");
+            browser_config_builder =
+                browser_config_builder.arg("--blink-settings=imagesEnabled=false");
+        }
+
+        if self.webrtc_protection {
+            #[cfg(feature = "log")]
+            trace!("Disabling the non-proxied WebRTC traffic");
+            browser_config_builder = browser_config_builder
+                .arg("--force-webrtc-ip-handling-policy=disable_non_proxied_udp");
+        }
+
+        if let Some((width, height)) = self.window_size {
+            #[cfg(feature = "log")]
+            trace!("Setting the window size to {}x{}", width, height);
+            browser_config_builder = browser_config_builder.window_size(width, height);
+        }
+
+        if let Some(ref dir) = self.user_data_dir {
+            #[cfg(feature = "log")]
+            trace!("Setting the browser profile to {:?}", dir);
+            browser_config_builder = browser_config_builder.user_data_dir(dir);
+        }
+
         #[cfg(feature = "log")]
         {
             info!("Built browser config");
@@ -110,6 +321,26 @@ impl BrowserConfigBuilder for DefaultBrowserConfigBuilder {
             .build()
             .map_err(PinterestLoginError::BrowserConfigBuildError)
     }
+
+    fn executable(&self) -> Option<PathBuf> {
+        self.executable
+            .clone()
+            .or_else(|| default_executable(DetectionOptions::default()).ok())
+    }
+
+    /// The launch timeout if it's set, otherwise [`DEFAULT_HANDSHAKE_TIMEOUT`]
+    fn handshake_timeout(&self) -> Duration {
+        self.launch_timeout.unwrap_or(DEFAULT_HANDSHAKE_TIMEOUT)
+    }
+
+    fn proxy(&self) -> Option<String> {
+        self.proxy.clone().or_else(|| {
+            self.env_proxy()
+                .ok()
+                .flatten()
+                .map(|proxy| proxy.proxy_server().to_string())
+        })
+    }
 }
 
 impl Default for DefaultBrowserConfigBuilder {
@@ -121,3 +352,174 @@ impl Default for DefaultBrowserConfigBuilder {
         Self::new(true, Some(std::time::Duration::from_secs(5)), None)
     }
 }
+
+/// The proxy that the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables ask for, translated into the
+/// flags of Chrome, see [`DefaultBrowserConfigBuilder::with_env_proxy`].
+///
+/// * `HTTP_PROXY` and `HTTPS_PROXY` are the proxies of the `http` and `https` urls, the scheme of the proxy is `http`
+///   when it's missing. They're given to `--proxy-server` as one proxy when they're the same, otherwise per scheme
+///   (`http=...;https=...`).
+/// * `NO_PROXY` is a comma-separated list of hosts that go direct, given to `--proxy-bypass-list`. Like curl does,
+///   `example.com` and `.example.com` match the domain and its subdomains, `*` matches every host, and the IP addresses,
+///   CIDR ranges and ports are kept as they are.
+///
+/// The lowercase variables win over the uppercase ones, and the empty ones are ignored.
+///
+/// # Example
+/// ```
+/// use pinterest_login::config_builder::EnvProxy;
+///
+/// let proxy = EnvProxy::from_vars(|name| match name {
+///     "HTTPS_PROXY" => Some("proxy.internal:3128".to_string()),
+///     "no_proxy" => Some("localhost, .corp.example".to_string()),
+///     _ => None,
+/// })
+/// .unwrap()
+/// .unwrap();
+/// assert_eq!(proxy.proxy_server(), "https=http://proxy.internal:3128");
+/// assert_eq!(proxy.bypass_list(), Some("localhost;*.localhost;corp.example;*.corp.example"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvProxy {
+    proxy_server: String,
+    bypass_list: Option<String>,
+}
+
+impl EnvProxy {
+    /// Reads the variables from the environment of the process, `None` if they don't set a proxy
+    pub fn from_env() -> crate::Result<Option<Self>> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Reads the variables with `var`, that returns the value of a variable if it's set, `None` if they don't set a
+    /// proxy.
+    ///
+    /// # Errors
+    /// [`PinterestLoginError::BrowserConfigBuildError`] naming the variable if it's malformed
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> crate::Result<Option<Self>> {
+        let read = |name: &str| {
+            [name.to_lowercase(), name.to_string()]
+                .into_iter()
+                .find_map(|name| {
+                    var(&name)
+                        .filter(|value| !value.trim().is_empty())
+                        .map(|value| (name, value))
+                })
+        };
+        let http = read("HTTP_PROXY")
+            .map(|(name, value)| proxy_uri(&name, &value))
+            .transpose()?;
+        let https = read("HTTPS_PROXY")
+            .map(|(name, value)| proxy_uri(&name, &value))
+            .transpose()?;
+        let proxy_server = match (http, https) {
+            (None, None) => return Ok(None),
+            (Some(http), Some(https)) if http == https => http,
+            (http, https) => [("http", http), ("https", https)]
+                .into_iter()
+                .filter_map(|(scheme, proxy)| Some(format!("{scheme}={}", proxy?)))
+                .collect::<Vec<_>>()
+                .join(";"),
+        };
+        let bypass_list = read("NO_PROXY")
+            .map(|(name, value)| bypass_list(&name, &value))
+            .transpose()?
+            .filter(|list| !list.is_empty());
+        Ok(Some(Self {
+            proxy_server,
+            bypass_list,
+        }))
+    }
+
+    /// The value of the `--proxy-server` flag
+    pub fn proxy_server(&self) -> &str {
+        &self.proxy_server
+    }
+
+    /// The value of the `--proxy-bypass-list` flag, if `NO_PROXY` lists some hosts
+    pub fn bypass_list(&self) -> Option<&str> {
+        self.bypass_list.as_deref()
+    }
+
+    /// The flags of Chrome
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![format!("--proxy-server={}", self.proxy_server)];
+        if let Some(bypass_list) = &self.bypass_list {
+            args.push(format!("--proxy-bypass-list={bypass_list}"));
+        }
+        args
+    }
+}
+
+/// The error of a malformed proxy variable. The value isn't in it, it may have credentials.
+fn malformed(name: &str, reason: impl std::fmt::Display) -> PinterestLoginError {
+    PinterestLoginError::BrowserConfigBuildError(format!(
+        "The `{name}` environment variable is malformed: {reason}"
+    ))
+}
+
+/// Checks a proxy of the variable, and returns it in the form of `--proxy-server`
+fn proxy_uri(name: &str, value: &str) -> crate::Result<String> {
+    let value = value.trim();
+    let with_scheme = match value.contains("://") {
+        true => value.to_string(),
+        false => format!("http://{value}"),
+    };
+    let url = url::Url::parse(&with_scheme).map_err(|e| malformed(name, e))?;
+    if !matches!(url.scheme(), "http" | "https" | "socks4" | "socks5") {
+        return Err(malformed(
+            name,
+            format!("the `{}` proxies aren't supported", url.scheme()),
+        ));
+    }
+    let Some(host) = url.host_str() else {
+        return Err(malformed(name, "it has no host"));
+    };
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err(malformed(
+            name,
+            "Chrome doesn't take the credentials of the proxy from its url",
+        ));
+    }
+    Ok(match url.port() {
+        Some(port) => format!("{}://{host}:{port}", url.scheme()),
+        None => format!("{}://{host}", url.scheme()),
+    })
+}
+
+/// Translates the comma-separated hosts of `NO_PROXY` into the rules of `--proxy-bypass-list`
+fn bypass_list(name: &str, value: &str) -> crate::Result<String> {
+    let mut rules = Vec::new();
+    for entry in value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        if entry.contains(|c: char| c.is_whitespace() || c == ';') {
+            return Err(malformed(name, format!("`{entry}` isn't a host")));
+        }
+        let domain = entry
+            .strip_prefix("*.")
+            .or_else(|| entry.strip_prefix('.'))
+            .unwrap_or(entry);
+        if entry == "*" || domain.contains('/') || domain.starts_with('[') || is_ip(domain) {
+            // Every host, the CIDR ranges and the IP addresses have no subdomains
+            rules.push(entry.to_string());
+        } else if domain.is_empty() || domain.starts_with('.') || domain.contains('*') {
+            return Err(malformed(name, format!("`{entry}` isn't a host")));
+        } else {
+            rules.push(domain.to_string());
+            rules.push(format!("*.{domain}"));
+        }
+    }
+    Ok(rules.join(";"))
+}
+
+/// Whether the host, maybe with a port, is an IP address
+fn is_ip(host: &str) -> bool {
+    let is_ip = |host: &str| host.parse::<std::net::IpAddr>().is_ok();
+    is_ip(host)
+        || host
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| port.parse::<u16>().is_ok() && is_ip(host))
+}
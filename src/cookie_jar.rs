@@ -0,0 +1,125 @@
+use std::time::SystemTime;
+
+use cookie::{time::OffsetDateTime, Cookie, CookieJar, Expiration};
+
+use crate::cookies::{PinterestCookie, SameSite};
+
+/// Why a cookie of the `cookie` crate can't be a [`PinterestCookie`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum CookieConversionError {
+    /// The cookie has no domain, a [`PinterestCookie`] always has one since it comes from a browser
+    #[error("The cookie `{0}` has no domain")]
+    MissingDomain(String),
+}
+
+/// The cookie with all its attributes. A session cookie, without an expiry, has no `Expires` attribute, and a cookie
+/// without a `SameSite` attribute doesn't get one: the browsers treat it as `Lax`, not as `None`.
+///
+/// The `cookie` crate has no host-only cookies, its domain is always sent to the subdomains like a `Domain`
+/// attribute is, so the leading dot of the domain is dropped.
+///
+/// # Example
+/// ```
+/// use cookie::{Cookie, SameSite};
+/// use pinterest_login::cookies::{self, PinterestCookie};
+///
+/// let cookie = PinterestCookie {
+///     secure: true,
+///     same_site: Some(cookies::SameSite::None),
+///     ..PinterestCookie::new("_pinterest_sess", "sess", ".pinterest.com")
+/// };
+///
+/// let cookie = Cookie::from(&cookie);
+/// assert_eq!(cookie.domain(), Some("pinterest.com"));
+/// assert_eq!(cookie.same_site(), Some(SameSite::None));
+/// assert_eq!(cookie.expires(), None);
+/// ```
+impl From<&PinterestCookie> for Cookie<'static> {
+    fn from(cookie: &PinterestCookie) -> Self {
+        let mut builder = Cookie::build((cookie.name.clone(), cookie.value.clone()))
+            .domain(cookie.domain.trim_start_matches('.').to_string())
+            .path(cookie.path.clone())
+            .secure(cookie.secure)
+            .http_only(cookie.http_only);
+        if let Some(expires) = cookie.expires {
+            builder = builder.expires(OffsetDateTime::from(expires));
+        }
+        if let Some(same_site) = cookie.same_site {
+            builder = builder.same_site(match same_site {
+                SameSite::Strict => cookie::SameSite::Strict,
+                SameSite::Lax => cookie::SameSite::Lax,
+                SameSite::None => cookie::SameSite::None,
+            });
+        }
+        builder.build()
+    }
+}
+
+impl From<PinterestCookie> for Cookie<'static> {
+    fn from(cookie: PinterestCookie) -> Self {
+        Self::from(&cookie)
+    }
+}
+
+/// The cookie of a `Set-Cookie` header or of a jar, its domain is sent to the subdomains so it gets a leading dot,
+/// and its `Max-Age` is counted from now when it has no `Expires` attribute
+impl TryFrom<&Cookie<'_>> for PinterestCookie {
+    type Error = CookieConversionError;
+
+    fn try_from(cookie: &Cookie<'_>) -> Result<Self, Self::Error> {
+        let domain = cookie
+            .domain()
+            .filter(|domain| !domain.is_empty())
+            .ok_or_else(|| CookieConversionError::MissingDomain(cookie.name().to_string()))?;
+        let expires = match cookie.expires() {
+            Some(Expiration::DateTime(expires)) => Some(SystemTime::from(expires)),
+            _ => cookie
+                .max_age()
+                .map(|max_age| SystemTime::from(OffsetDateTime::now_utc().saturating_add(max_age))),
+        };
+        Ok(Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain: format!(".{domain}"),
+            path: cookie.path().unwrap_or("/").to_string(),
+            expires,
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            same_site: cookie.same_site().map(|same_site| match same_site {
+                cookie::SameSite::Strict => SameSite::Strict,
+                cookie::SameSite::Lax => SameSite::Lax,
+                cookie::SameSite::None => SameSite::None,
+            }),
+        })
+    }
+}
+
+/// A jar of the cookies, like the ones of [`login_full`](crate::login_full).
+///
+/// A jar keeps one cookie by name, so of the cookies with the same name on different domains or paths, the last one
+/// is kept. The cookies are added as the original ones, so they aren't part of the
+/// [`delta`](CookieJar::delta) of the jar.
+///
+/// # Example
+/// ```
+/// use pinterest_login::cookie_jar::to_cookie_jar;
+/// use pinterest_login::cookies::PinterestCookie;
+///
+/// let jar = to_cookie_jar(&[PinterestCookie::new("csrftoken", "token", ".pinterest.com")]);
+///
+/// assert_eq!(jar.get("csrftoken").map(|cookie| cookie.value()), Some("token"));
+/// assert_eq!(jar.delta().count(), 0);
+/// ```
+pub fn to_cookie_jar(cookies: &[PinterestCookie]) -> CookieJar {
+    let mut jar = CookieJar::new();
+    extend_cookie_jar(&mut jar, cookies);
+    jar
+}
+
+/// Adds the cookies to a jar as the original ones, like [`to_cookie_jar`] does
+pub fn extend_cookie_jar(jar: &mut CookieJar, cookies: &[PinterestCookie]) {
+    for cookie in cookies {
+        jar.add_original(Cookie::from(cookie));
+    }
+}
@@ -0,0 +1,507 @@
+use std::{
+    collections::{hash_map, HashMap},
+    fmt,
+    ops::{Deref, DerefMut},
+    time::{Duration, SystemTime},
+};
+
+use chromiumoxide::cdp::browser_protocol::network::{Cookie, CookieSameSite};
+
+use crate::session::{CSRF_COOKIE, SESSION_COOKIE};
+
+/// A cookie of a login with all its attributes, like the browser reported it, see [`login_full`](crate::login_full).
+///
+/// The [`PinterestCookies`] only keep the names and the values, a list of these
+/// converts to them with [`From`]:
+///
+/// ```
+/// use pinterest_login::cookies::PinterestCookie;
+/// use pinterest_login::PinterestCookies;
+///
+/// let cookies = vec![PinterestCookie::new("csrftoken", "token", ".pinterest.com")];
+///
+/// let map: PinterestCookies = cookies.into_iter().map(<(String, String)>::from).collect();
+/// assert_eq!(map["csrftoken"], "token");
+/// ```
+///
+/// With the `serde` feature, it's serialized with the names of its fields, and its expiry in seconds since the unix
+/// epoch, with a fraction since the browser reports one, or `null` for a session cookie:
+/// `{"name": "csrftoken", "value": "token", "domain": ".pinterest.com", "path": "/", "expires": 1767225600.5, "secure": true, "http_only": false, "same_site": "Lax"}`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PinterestCookie {
+    /// The name of the cookie
+    pub name: String,
+    /// The value of the cookie
+    pub value: String,
+    /// The domain of the cookie, with a leading dot when it's sent to the subdomains too, like `.pinterest.com`
+    pub domain: String,
+    /// The path of the cookie
+    pub path: String,
+    /// When the cookie expires, `None` for the session cookies that are gone with the browser
+    #[cfg_attr(feature = "serde", serde(with = "unix_seconds"))]
+    pub expires: Option<SystemTime>,
+    /// Whether the cookie is only sent over HTTPS
+    pub secure: bool,
+    /// Whether the cookie is hidden from the scripts of the page
+    pub http_only: bool,
+    /// The `SameSite` attribute of the cookie, `None` if it has none
+    pub same_site: Option<SameSite>,
+}
+
+/// The `SameSite` attribute of a cookie, which cross-site requests it's sent with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SameSite {
+    /// Only with the requests of the same site
+    Strict,
+    /// With the requests of the same site and the top level navigations to it
+    Lax,
+    /// With all the requests, the cookie has to be secure
+    None,
+}
+
+impl PinterestCookie {
+    /// A session cookie on the path `/`, that isn't secure nor http-only and has no `SameSite` attribute
+    pub fn new(
+        name: impl Into<String>,
+        value: impl Into<String>,
+        domain: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            domain: domain.into(),
+            path: "/".to_string(),
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Whether it's a session cookie, that has no expiry
+    pub fn is_session(&self) -> bool {
+        self.expires.is_none()
+    }
+}
+
+impl From<&Cookie> for PinterestCookie {
+    fn from(cookie: &Cookie) -> Self {
+        // The browser reports the session cookies with an expiry of -1, and the expiries that aren't times (like
+        // an infinite one) are read as session cookies too
+        let expires = Some(cookie.expires)
+            .filter(|expires| !cookie.session && *expires > 0.0)
+            .and_then(|expires| Duration::try_from_secs_f64(expires).ok())
+            .and_then(|since| SystemTime::UNIX_EPOCH.checked_add(since));
+        Self {
+            name: cookie.name.clone(),
+            value: cookie.value.clone(),
+            domain: cookie.domain.clone(),
+            path: cookie.path.clone(),
+            expires,
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            same_site: cookie.same_site.as_ref().map(|same_site| match same_site {
+                CookieSameSite::Strict => SameSite::Strict,
+                CookieSameSite::Lax => SameSite::Lax,
+                CookieSameSite::None => SameSite::None,
+            }),
+        }
+    }
+}
+
+impl From<Cookie> for PinterestCookie {
+    fn from(cookie: Cookie) -> Self {
+        Self::from(&cookie)
+    }
+}
+
+/// The name and the value of the cookie, as they're in the [`PinterestCookies`](PinterestCookies)
+impl From<PinterestCookie> for (String, String) {
+    fn from(cookie: PinterestCookie) -> Self {
+        (cookie.name, cookie.value)
+    }
+}
+
+/// The cookies of a login, their names and their values, see [`login`](crate::login).
+///
+/// It derefs to the map of the names and the values, so it has all its methods, and adds the ones of the pinterest
+/// cookies. Its [`Debug`](fmt::Debug) and [`Display`](fmt::Display) only show the names and the lengths of the values,
+/// so it can be logged without leaking the session.
+///
+/// Iterating it gives the cookies in a different order every time, [`sorted_pairs`] sorts them by name, and the
+/// exporters of the crate ([`store::save_json`](crate::store::save_json), [`cookies_txt`](crate::cookies_txt), the sessions,
+/// and its serialization with the `serde` feature) sort them too.
+///
+/// # Example
+/// ```
+/// use pinterest_login::PinterestCookies;
+///
+/// let cookies = PinterestCookies::from([
+///     ("_pinterest_sess".to_string(), "TWc9PSZs".to_string()),
+///     ("csrftoken".to_string(), "token".to_string()),
+/// ]);
+///
+/// assert_eq!(cookies.get_session(), Some("TWc9PSZs"));
+/// assert_eq!(cookies.get_csrf(), Some("token"));
+/// assert_eq!(cookies.to_string(), "_pinterest_sess=<8 bytes>; csrftoken=<5 bytes>");
+/// assert_eq!(
+///     format!("{cookies:?}"),
+///     "PinterestCookies { _pinterest_sess: <8 bytes>, csrftoken: <5 bytes> }"
+/// );
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct PinterestCookies(HashMap<String, String>);
+
+impl PinterestCookies {
+    /// No cookies
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// No cookies, with room for `capacity` of them
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(HashMap::with_capacity(capacity))
+    }
+
+    /// The value of the session cookie, `_pinterest_sess`
+    pub fn get_session(&self) -> Option<&str> {
+        self.0.get(SESSION_COOKIE).map(String::as_str)
+    }
+
+    /// The value of the CSRF token cookie, `csrftoken`
+    pub fn get_csrf(&self) -> Option<&str> {
+        self.0.get(CSRF_COOKIE).map(String::as_str)
+    }
+
+    /// The number of cookies
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no cookies
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether there's a cookie with the name
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// The map of the names and the values
+    pub fn into_inner(self) -> HashMap<String, String> {
+        self.0
+    }
+}
+
+impl Deref for PinterestCookies {
+    type Target = HashMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for PinterestCookies {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<HashMap<String, String>> for PinterestCookies {
+    fn from(cookies: HashMap<String, String>) -> Self {
+        Self(cookies)
+    }
+}
+
+impl<const N: usize> From<[(String, String); N]> for PinterestCookies {
+    fn from(cookies: [(String, String); N]) -> Self {
+        Self(HashMap::from(cookies))
+    }
+}
+
+impl From<PinterestCookies> for HashMap<String, String> {
+    fn from(cookies: PinterestCookies) -> Self {
+        cookies.0
+    }
+}
+
+impl FromIterator<(String, String)> for PinterestCookies {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self(HashMap::from_iter(iter))
+    }
+}
+
+impl Extend<(String, String)> for PinterestCookies {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for PinterestCookies {
+    type Item = (String, String);
+    type IntoIter = hash_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PinterestCookies {
+    type Item = (&'a String, &'a String);
+    type IntoIter = hash_map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut PinterestCookies {
+    type Item = (&'a String, &'a mut String);
+    type IntoIter = hash_map::IterMut<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+/// The length of a value, in place of the value
+struct Redacted(usize);
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} bytes>", self.0)
+    }
+}
+
+/// The names sorted, with the lengths of the values: `PinterestCookies { csrftoken: <5 bytes> }`
+impl fmt::Debug for PinterestCookies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("PinterestCookies");
+        for (name, value) in sorted_pairs(self) {
+            debug.field(name, &Redacted(value.len()));
+        }
+        debug.finish()
+    }
+}
+
+/// The names sorted, with the lengths of the values: `_pinterest_sess=<8 bytes>; csrftoken=<5 bytes>`, or
+/// `no cookies`
+impl fmt::Display for PinterestCookies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no cookies");
+        }
+        for (i, (name, value)) in sorted_pairs(self).into_iter().enumerate() {
+            let separator = if i == 0 { "" } else { "; " };
+            write!(f, "{separator}{name}={:?}", Redacted(value.len()))?;
+        }
+        Ok(())
+    }
+}
+
+/// A JSON object of the names and the values, sorted by name, like [`store::save_json`](crate::store::save_json)
+/// writes it
+#[cfg(feature = "serde")]
+impl serde::Serialize for PinterestCookies {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(sorted_pairs(self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PinterestCookies {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HashMap::deserialize(deserializer).map(Self)
+    }
+}
+
+/// The names and the values of the cookies sorted by name, to log, serialize or diff them in the same order from a run
+/// to another, which iterating the map doesn't do
+///
+/// # Example
+/// ```
+/// use pinterest_login::cookies::sorted_pairs;
+/// use pinterest_login::PinterestCookies;
+///
+/// let cookies: PinterestCookies = [
+///     ("csrftoken".to_string(), "token".to_string()),
+///     ("_auth".to_string(), "1".to_string()),
+/// ]
+/// .into();
+///
+/// assert_eq!(sorted_pairs(&cookies), [("_auth", "1"), ("csrftoken", "token")]);
+/// ```
+pub fn sorted_pairs(cookies: &PinterestCookies) -> Vec<(&str, &str)> {
+    let mut pairs: Vec<_> = cookies
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// What changed between two sets of cookies, see [`diff`].
+///
+/// It only has the names of the cookies, so it's safe to log: its [`Display`](fmt::Display) is like
+/// `added _b, changed _pinterest_sess, removed ar_debug`, with the values left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CookieDiff {
+    /// The cookies that are only in the new set, sorted by name
+    pub added: Vec<String>,
+    /// The cookies whose value changed, sorted by name
+    pub changed: Vec<String>,
+    /// The cookies that are only in the old set, sorted by name
+    pub removed: Vec<String>,
+}
+
+impl CookieDiff {
+    /// Whether the two sets have the same cookies with the same values
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for CookieDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no changes");
+        }
+        let mut separator = "";
+        for (change, names) in [
+            ("added", &self.added),
+            ("changed", &self.changed),
+            ("removed", &self.removed),
+        ] {
+            if !names.is_empty() {
+                write!(f, "{separator}{change} {}", names.join(" "))?;
+                separator = ", ";
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What [`merge`] does with the cookies that the update doesn't have
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MergePolicy {
+    /// Keeps them with their old value, since pinterest only sends back the cookies that it rotated
+    #[default]
+    KeepRemoved,
+    /// Drops them, the update is the whole set of cookies
+    DropRemoved,
+}
+
+/// The cookies that were added, changed or removed from `old` to `new`, like when pinterest rotates
+/// `_pinterest_sess` in the middle of a session
+///
+/// # Example
+/// ```
+/// use pinterest_login::cookies::diff;
+/// use pinterest_login::PinterestCookies;
+///
+/// let old: PinterestCookies = [("_pinterest_sess".to_string(), "old".to_string())].into();
+/// let new: PinterestCookies = [("_pinterest_sess".to_string(), "new".to_string())].into();
+///
+/// let diff = diff(&old, &new);
+/// assert_eq!(diff.changed, ["_pinterest_sess"]);
+/// assert_eq!(diff.to_string(), "changed _pinterest_sess");
+/// ```
+pub fn diff(old: &PinterestCookies, new: &PinterestCookies) -> CookieDiff {
+    let mut diff = CookieDiff::default();
+    for (name, value) in new {
+        match old.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(old) if old != value => diff.changed.push(name.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|name| !new.contains_key(*name))
+        .cloned()
+        .collect();
+    diff.added.sort_unstable();
+    diff.changed.sort_unstable();
+    diff.removed.sort_unstable();
+    diff
+}
+
+/// The cookies of `base` updated with the ones of `update`, its values win. The cookies that are only in `base` are
+/// kept or dropped as the policy says.
+///
+/// # Example
+/// ```
+/// use pinterest_login::cookies::{merge, MergePolicy};
+/// use pinterest_login::PinterestCookies;
+///
+/// let stored: PinterestCookies = [
+///     ("_pinterest_sess".to_string(), "old".to_string()),
+///     ("_auth".to_string(), "1".to_string()),
+/// ]
+/// .into();
+/// let rotated: PinterestCookies = [("_pinterest_sess".to_string(), "new".to_string())].into();
+///
+/// let merged = merge(&stored, &rotated, MergePolicy::KeepRemoved);
+/// assert_eq!(merged["_pinterest_sess"], "new");
+/// assert_eq!(merged["_auth"], "1");
+/// ```
+pub fn merge(
+    base: &PinterestCookies,
+    update: &PinterestCookies,
+    policy: MergePolicy,
+) -> PinterestCookies {
+    let mut merged = match policy {
+        MergePolicy::KeepRemoved => base.clone(),
+        MergePolicy::DropRemoved => PinterestCookies::with_capacity(update.len()),
+    };
+    merged.extend(
+        update
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+    merged
+}
+
+/// The expiry of a cookie as the seconds since the unix epoch, see [`PinterestCookie`]
+#[cfg(feature = "serde")]
+mod unix_seconds {
+    use std::time::{Duration, SystemTime};
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        expires: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match expires {
+            Some(expires) => serializer.serialize_f64(
+                expires
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0.0, |since| since.as_secs_f64()),
+            ),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        Option::<f64>::deserialize(deserializer)?
+            .map(|secs| {
+                Duration::try_from_secs_f64(secs)
+                    .ok()
+                    .and_then(|since| SystemTime::UNIX_EPOCH.checked_add(since))
+                    .ok_or_else(|| {
+                        D::Error::custom(format!(
+                            "invalid expiry {secs}, expected the seconds since the unix epoch"
+                        ))
+                    })
+            })
+            .transpose()
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt::Write;
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::cdp::browser_protocol::network::Cookie;
+
+use crate::cookies::PinterestCookie;
+
+/// The header of the cookie files, curl and wget only accept the files that start with its first line
+pub const NETSCAPE_HEADER: &str = "\
+# Netscape HTTP Cookie File
+# https://curl.se/docs/http-cookies.html
+# This file was generated by pinterest-login! Edit at your own risk.
+
+";
+
+/// The domain prefix of the http-only cookies, a comment for the clients that don't know it
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// A line of a cookie file that isn't a cookie, see [`from_netscape_str`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid cookie on line {line}: {reason}")]
+pub struct NetscapeError {
+    /// The number of the line, from 1
+    pub line: usize,
+    /// What's wrong with it
+    pub reason: &'static str,
+}
+
+/// Writes the cookies in the Netscape cookie file format (`cookies.txt`), that `curl -b`, wget and most of the
+/// HTTP clients can load.
+///
+/// Every cookie is a line of 7 tab separated fields: the domain, whether the subdomains are included, the path,
+/// whether it's secure, the expiry as a unix timestamp (`0` for the session cookies), the name and the value.
+/// The http-only cookies get the `#HttpOnly_` domain prefix that curl understands. The output always ends with a newline.
+///
+/// The cookies are sorted by name, domain and path, whatever order the browser reported them in, so the file only
+/// changes when the cookies do.
+///
+/// # Example
+/// ```
+/// use chromiumoxide::cdp::browser_protocol::network::Cookie;
+/// use pinterest_login::cookies_txt::{to_netscape, NETSCAPE_HEADER};
+///
+/// let cookie: Cookie = serde_json::from_value(serde_json::json!({
+///     "name": "csrftoken", "value": "token", "domain": ".pinterest.com", "path": "/",
+///     "expires": 1767225600.5, "size": 14, "httpOnly": false, "secure": true, "session": false,
+///     "priority": "Medium", "sameParty": false, "sourceScheme": "Secure", "sourcePort": 443
+/// }))?;
+///
+/// assert_eq!(
+///     to_netscape(&[cookie]),
+///     format!("{NETSCAPE_HEADER}.pinterest.com\tTRUE\t/\tTRUE\t1767225600\tcsrftoken\ttoken\n")
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub fn to_netscape(cookies: &[Cookie]) -> String {
+    let cookies: Vec<_> = cookies.iter().map(PinterestCookie::from).collect();
+    to_netscape_string(&cookies)
+}
+
+/// Same as [`to_netscape`], with the cookies of [`login_full`](crate::login_full). The `SameSite` attribute isn't
+/// part of the format, so it's lost.
+pub fn to_netscape_string(cookies: &[PinterestCookie]) -> String {
+    let mut cookies: Vec<_> = cookies.iter().collect();
+    cookies.sort_by(|a, b| (&a.name, &a.domain, &a.path).cmp(&(&b.name, &b.domain, &b.path)));
+
+    let mut out = String::from(NETSCAPE_HEADER);
+    for cookie in cookies {
+        let http_only = if cookie.http_only {
+            HTTP_ONLY_PREFIX
+        } else {
+            ""
+        };
+        let expires = cookie
+            .expires
+            .and_then(|expires| expires.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_secs());
+        // Writing to a string can't fail
+        let _ = writeln!(
+            out,
+            "{http_only}{}\t{}\t{}\t{}\t{expires}\t{}\t{}",
+            cookie.domain,
+            flag(cookie.domain.starts_with('.')),
+            cookie.path,
+            flag(cookie.secure),
+            cookie.name,
+            cookie.value,
+        );
+    }
+    out
+}
+
+/// Reads a cookie file in the Netscape format, like the ones that [`to_netscape`], curl, or the cookie exporting
+/// extensions of the browsers write.
+///
+/// It's lenient about the layout: the empty and the comment lines are skipped, except for the `#HttpOnly_` prefix that
+/// marks the http-only cookies, the fields can be separated by any mix of tabs and spaces, a missing value is an empty
+/// one, and the spaces inside the value are kept. The expiries of `0` or less are the session cookies.
+///
+/// The domain gets a leading dot when the subdomains are included, like the browsers write it, and nothing has a
+/// `SameSite` attribute since it isn't part of the format.
+///
+/// # Example
+/// ```
+/// use pinterest_login::cookies_txt::from_netscape_str;
+///
+/// let cookies = from_netscape_str(
+///     "# Netscape HTTP Cookie File\n\
+///      #HttpOnly_.pinterest.com\tTRUE\t/\tTRUE\t1798761600\t_pinterest_sess\tTWc9PSZs\n\
+///      pinterest.com TRUE / FALSE 0 csrftoken 3f2a9c1d7e\n",
+/// )?;
+///
+/// assert_eq!(cookies.len(), 2);
+/// assert!(cookies[0].http_only);
+/// assert_eq!(cookies[1].domain, ".pinterest.com");
+/// assert!(cookies[1].is_session());
+/// # Ok::<(), pinterest_login::cookies_txt::NetscapeError>(())
+/// ```
+pub fn from_netscape_str(contents: &str) -> Result<Vec<PinterestCookie>, NetscapeError> {
+    let mut cookies = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let error = |reason| NetscapeError {
+            line: index + 1,
+            reason,
+        };
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // The 6 first fields have no spaces, the value takes the rest of the line
+        let mut rest = line.trim_start();
+        let mut fields = [""; 6];
+        for field in &mut fields {
+            if rest.is_empty() {
+                return Err(error("expected 7 fields separated by tabs"));
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            *field = &rest[..end];
+            rest = rest[end..].trim_start();
+        }
+        let [domain, subdomains, path, secure, expires, name] = fields;
+
+        let subdomains =
+            parse_flag(subdomains).ok_or(error("the subdomains flag isn't TRUE or FALSE"))?;
+        let secure = parse_flag(secure).ok_or(error("the secure flag isn't TRUE or FALSE"))?;
+        let expires: f64 = expires
+            .parse()
+            .ok()
+            .filter(|expires: &f64| expires.is_finite())
+            .ok_or(error("the expiry isn't a unix timestamp"))?;
+        let expires = (expires > 0.0)
+            .then(|| Duration::try_from_secs_f64(expires).ok())
+            .flatten()
+            .and_then(|since| SystemTime::UNIX_EPOCH.checked_add(since));
+        let domain = match subdomains && !domain.starts_with('.') {
+            true => format!(".{domain}"),
+            false => domain.to_string(),
+        };
+
+        cookies.push(PinterestCookie {
+            name: name.to_string(),
+            value: rest.trim_end().to_string(),
+            domain,
+            path: path.to_string(),
+            expires,
+            secure,
+            http_only,
+            same_site: None,
+        });
+    }
+    Ok(cookies)
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+fn parse_flag(flag: &str) -> Option<bool> {
+    if flag.eq_ignore_ascii_case("TRUE") {
+        Some(true)
+    } else if flag.eq_ignore_ascii_case("FALSE") {
+        Some(false)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    ffi::OsString,
+    fmt,
+    io::Read,
+    process::{Command, ExitStatus, Stdio},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures::{
+    channel::oneshot,
+    future::{self, BoxFuture},
+};
+#[cfg(feature = "log")]
+use log::debug;
+
+/// The email and the password of an account
+#[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Credentials {
+    /// The email, if the provider knows it
+    pub email: Option<String>,
+    /// The password
+    pub password: String,
+}
+
+impl Credentials {
+    /// Creates the credentials without an email
+    pub fn new(password: impl Into<String>) -> Self {
+        Self {
+            email: None,
+            password: password.into(),
+        }
+    }
+
+    /// Sets the email
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("email", &self.email)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Why a [`CredentialsProvider`] couldn't give the credentials
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CredentialsError {
+    /// The command couldn't be started, e.g. it's not installed
+    #[error("Can't run `{command}`: {source}")]
+    Spawn {
+        /// The command
+        command: String,
+        /// The error of the start
+        source: std::io::Error,
+    },
+    /// The command didn't exit within its timeout, it was killed
+    #[error("`{command}` didn't exit within {timeout:?}")]
+    TimedOut {
+        /// The command
+        command: String,
+        /// The timeout that was reached
+        timeout: Duration,
+    },
+    /// The command failed
+    #[error("`{command}` failed ({status}): {stderr}")]
+    Failed {
+        /// The command
+        command: String,
+        /// The exit status of the command
+        status: ExitStatus,
+        /// What the command printed on its stderr, trimmed
+        stderr: String,
+    },
+    /// The output doesn't have the credentials, e.g. it's empty or not UTF-8
+    #[error("Invalid credentials output: {0}")]
+    InvalidOutput(String),
+}
+
+/// Where the login gets the credentials of an account from, instead of receiving the password directly, see
+/// [`SessionManager::account_with_credentials`](crate::manager::SessionManager::account_with_credentials) and
+/// [`SessionKeeper::spawn_with_credentials`](crate::keeper::SessionKeeper::spawn_with_credentials).
+///
+/// It's asked before every login, so a changed password is picked up. Like the
+/// [`BrowserLauncher`](crate::launcher::BrowserLauncher), this trait is object safe, so it returns a boxed future,
+/// and it must not block the executor while it waits.
+pub trait CredentialsProvider: Send + Sync {
+    /// The credentials of the account
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, CredentialsError>>;
+}
+
+/// The credentials are their own provider
+impl CredentialsProvider for Credentials {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, CredentialsError>> {
+        Box::pin(future::ready(Ok(self.clone())))
+    }
+}
+
+/// A parser of the output of a [`CommandCredentialsProvider`], see [`CommandCredentialsProvider::parser`]
+pub type CredentialsParser =
+    Arc<dyn Fn(&str) -> Result<Credentials, CredentialsError> + Send + Sync>;
+
+/// The default timeout of a [`CommandCredentialsProvider`], a password manager may ask for its passphrase first
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often the command is checked for its exit
+const COMMAND_POLL: Duration = Duration::from_millis(10);
+
+/// Reads the credentials from the output of a command, like the `pass show pinterest/me` of
+/// [pass](https://www.passwordstore.org/) or [gopass](https://www.gopass.pw/).
+///
+/// The command is executed directly, without a shell, so its arguments are passed as they are. Its stdin is
+/// closed, its stderr is kept for the error if it fails, and it's killed if it doesn't exit within the timeout
+/// ([`DEFAULT_COMMAND_TIMEOUT`] by default). It's waited for on a thread of its own, so the executor keeps running
+/// the other tasks meanwhile. The output is parsed with [`parse_pass_output`] unless another parser is set.
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::credentials::{CommandCredentialsProvider, CredentialsProvider};
+/// use pinterest_login::login_bot::DefaultBrowserLoginBot;
+///
+/// # async fn run() -> Result<(), pinterest_login::credentials::CredentialsError> {
+/// let provider = CommandCredentialsProvider::new("pass").args(["show", "pinterest/me"]);
+/// let credentials = provider.credentials().await?;
+/// let email = credentials.email.as_deref().unwrap_or("me@example.com");
+/// let bot = DefaultBrowserLoginBot::new(email, credentials.password);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CommandCredentialsProvider {
+    command: OsString,
+    args: Vec<OsString>,
+    parser: CredentialsParser,
+    timeout: Duration,
+}
+
+impl CommandCredentialsProvider {
+    /// Runs the command, looked up in the `PATH` unless it's a path
+    pub fn new(command: impl Into<OsString>) -> Self {
+        Self {
+            command: command.into(),
+            args: Vec::new(),
+            parser: Arc::new(parse_pass_output),
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+        }
+    }
+
+    /// Adds an argument
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Adds the arguments
+    pub fn args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Parses the output with the parser, instead of [`parse_pass_output`]
+    pub fn parser(
+        mut self,
+        parser: impl Fn(&str) -> Result<Credentials, CredentialsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.parser = Arc::new(parser);
+        self
+    }
+
+    /// Sets how long the command may run, the default is [`DEFAULT_COMMAND_TIMEOUT`]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The command line, for the errors
+    fn command_line(&self) -> String {
+        std::iter::once(&self.command)
+            .chain(&self.args)
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Runs the command, returns its stdout if it succeeds. It blocks until the command exits.
+    fn run(&self) -> Result<String, CredentialsError> {
+        let command = self.command_line();
+        #[cfg(feature = "log")]
+        debug!("Running `{command}` for the credentials");
+        let mut child = Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|source| CredentialsError::Spawn {
+                command: command.clone(),
+                source,
+            })?;
+        // Read in the background, a full pipe would block the command forever
+        let stdout = read_all(child.stdout.take());
+        let stderr = read_all(child.stderr.take());
+
+        let start = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if start.elapsed() >= self.timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(CredentialsError::TimedOut {
+                        command,
+                        timeout: self.timeout,
+                    });
+                }
+                Ok(None) => thread::sleep(COMMAND_POLL),
+                Err(source) => return Err(CredentialsError::Spawn { command, source }),
+            }
+        };
+
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            return Err(CredentialsError::Failed {
+                command,
+                status,
+                stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+            });
+        }
+        String::from_utf8(stdout.join().unwrap_or_default())
+            .map_err(|_| CredentialsError::InvalidOutput(format!("`{command}` didn't print UTF-8")))
+    }
+}
+
+impl CredentialsProvider for CommandCredentialsProvider {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, CredentialsError>> {
+        Box::pin(async move {
+            let (sender, receiver) = oneshot::channel();
+            let provider = self.clone();
+            thread::spawn(move || {
+                let _ = sender.send(provider.run());
+            });
+            let output = receiver
+                .await
+                .expect("the thread of the command doesn't panic")?;
+            (self.parser)(&output)
+        })
+    }
+}
+
+impl fmt::Debug for CommandCredentialsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandCredentialsProvider")
+            .field("command", &self.command)
+            .field("args", &self.args)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads the pipe to its end on a thread
+fn read_all(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        output
+    })
+}
+
+/// Parses the output of `pass show`: the password is the first line (without its line break), and the email is
+/// the value of an `email:` line among the next ones, if there's one.
+///
+/// # Errors
+/// [`CredentialsError::InvalidOutput`] if the first line is empty
+///
+/// # Example
+/// ```
+/// use pinterest_login::credentials::parse_pass_output;
+///
+/// let credentials = parse_pass_output("hunter2\nemail: me@example.com\nurl: pinterest.com\n")?;
+/// assert_eq!(credentials.password, "hunter2");
+/// assert_eq!(credentials.email.as_deref(), Some("me@example.com"));
+///
+/// // The spaces are part of the password
+/// assert_eq!(parse_pass_output(" hunter 2 \r\n")?.password, " hunter 2 ");
+/// assert!(parse_pass_output("\nemail: me@example.com").is_err());
+/// # Ok::<(), pinterest_login::credentials::CredentialsError>(())
+/// ```
+pub fn parse_pass_output(output: &str) -> Result<Credentials, CredentialsError> {
+    let mut lines = output.lines();
+    let password = lines.next().unwrap_or_default();
+    if password.is_empty() {
+        return Err(CredentialsError::InvalidOutput(
+            "the first line should be the password, it's empty".to_string(),
+        ));
+    }
+    let email = lines.find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("email")
+            .then(|| value.trim().to_string())
+            .filter(|email| !email.is_empty())
+    });
+    Ok(Credentials {
+        email,
+        password: password.to_string(),
+    })
+}
@@ -0,0 +1,153 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+#[cfg(feature = "log")]
+use log::{debug, info};
+
+use crate::{
+    config_builder::BrowserConfigBuilder,
+    login_bot::BrowserLoginBot,
+    options::{LoginOptions, Target},
+    report::LoginOutcome,
+};
+
+/// The cookies that the dry run fixture sets after the login form is submitted, as `(name, value)`
+pub const DRY_RUN_COOKIES: &[(&str, &str)] = &[
+    ("_pinterest_sess", "dry-run-session"),
+    ("csrftoken", "dry-run-csrf"),
+];
+
+const LOGIN_PAGE: &str = include_str!("fixtures/login.html");
+const HOME_PAGE: &str = include_str!("fixtures/home.html");
+
+/// Runs the whole login pipeline against a bundled fixture that mimics the pinterest login page,
+/// and returns the synthetic [`DRY_RUN_COOKIES`] that it sets.
+///
+/// The fixture is served from memory on a local port, so nothing ever reaches pinterest and no credentials are needed.
+/// It's meant to smoke test your [`BrowserLoginBot`] and [`BrowserConfigBuilder`] implementations (in CI for example),
+/// the [`Target`] of the options is replaced by the fixture, everything else is used as is.
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::dry_run::{dry_run, DRY_RUN_COOKIES};
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::options::LoginOptions;
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let bot = DefaultBrowserLoginBot::new("any email", "any password");
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+///
+/// let outcome = dry_run(&bot, &config_builder, &LoginOptions::default()).await?;
+/// for (name, value) in DRY_RUN_COOKIES {
+///     assert_eq!(outcome.cookies.get(*name).map(String::as_str), Some(*value));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn dry_run<B, C>(
+    login_bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> crate::Result<LoginOutcome>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let server = FixtureServer::start().map_err(chromiumoxide::error::CdpError::from)?;
+    #[cfg(feature = "log")]
+    info!("Serving the dry run fixture on {}", server.addr);
+
+    let options = options
+        .clone()
+        .target(Target::Url(format!("http://{}/login", server.addr)));
+    crate::login_with_options(login_bot, config_builder, &options).await
+}
+
+/// Serves the fixture pages from a background thread, until it gets dropped
+struct FixtureServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FixtureServer {
+    fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::Acquire) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        // The browser may open speculative connections that never send a request
+                        std::thread::spawn(move || respond(stream));
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        // Wake up the accept loop so it sees the stop flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        #[cfg(feature = "log")]
+        debug!("Stopped the dry run fixture server");
+    }
+}
+
+fn respond(stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or(path);
+    let response = match path {
+        "/login" => page(LOGIN_PAGE),
+        "/session" => {
+            let mut headers = String::from("Location: /home\r\n");
+            for (name, value) in DRY_RUN_COOKIES {
+                headers.push_str(&format!("Set-Cookie: {name}={value}; Path=/\r\n"));
+            }
+            format!("HTTP/1.1 302 Found\r\n{headers}Content-Length: 0\r\nConnection: close\r\n\r\n")
+        }
+        "/home" => page(HOME_PAGE),
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    (&stream).write_all(response.as_bytes())
+}
+
+fn page(html: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{html}",
+        html.len()
+    )
+}
@@ -0,0 +1,349 @@
+//! The sessions of the [`EncryptedFileSessionStore`] are encrypted with ChaCha20-Poly1305, so they can't be read or
+//! modified without the key.
+//!
+//! # File format
+//! Each account has its `<account>.enc` file, named like the ones of the [`FileSessionStore`], made of a 34 bytes
+//! header followed by the encrypted [`Session::to_json`] and its 16 bytes authentication tag:
+//!
+//! | Bytes    | Content                                                                        |
+//! |----------|--------------------------------------------------------------------------------|
+//! | `0..4`   | The magic `PLSE`                                                               |
+//! | `4`      | The version of the format, [`ENCRYPTED_SESSION_VERSION`]                       |
+//! | `5`      | How the key was obtained: `0` for a raw key, `1` for a passphrase              |
+//! | `6..22`  | The random salt of the passphrase, a new one for every write, zeros for a raw key |
+//! | `22..34` | The random nonce                                                               |
+//! | `34..`   | The ChaCha20-Poly1305 ciphertext and tag, with the whole header as associated data |
+//!
+//! The key of a passphrase is derived with Argon2id (version `0x13`, 19 MiB of memory, 2 iterations, 1 lane)
+//! and the salt of the header. Since the header is authenticated, changing any byte of the file makes it fail to decrypt.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use futures::{future::BoxFuture, FutureExt};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    session::{FileSessionStore, Session, SessionError, SessionStore},
+    store::write_atomic,
+};
+
+/// The version of the encrypted files that the [`EncryptedFileSessionStore`] writes
+pub const ENCRYPTED_SESSION_VERSION: u8 = 1;
+
+const MAGIC: &[u8; 4] = b"PLSE";
+const RAW_KEY: u8 = 0;
+const PASSPHRASE: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = 6 + SALT_LEN + NONCE_LEN;
+/// The memory of Argon2id in KiB, 19 MiB
+const ARGON2_MEMORY: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_LANES: u32 = 1;
+
+/// The key of the encrypted sessions, it's wiped from the memory when it's dropped
+#[derive(Clone)]
+pub struct SessionKey(Secret);
+
+#[derive(Clone)]
+enum Secret {
+    Raw([u8; 32]),
+    Passphrase(String),
+}
+
+impl SessionKey {
+    /// A random 32 bytes key, keep it somewhere safe
+    pub fn raw(key: [u8; 32]) -> Self {
+        Self(Secret::Raw(key))
+    }
+
+    /// A key derived from the passphrase with Argon2id, with a random salt for each file
+    pub fn passphrase(passphrase: impl Into<String>) -> Self {
+        Self(Secret::Passphrase(passphrase.into()))
+    }
+
+    fn kind(&self) -> u8 {
+        match self.0 {
+            Secret::Raw(_) => RAW_KEY,
+            Secret::Passphrase(_) => PASSPHRASE,
+        }
+    }
+}
+
+impl Drop for SessionKey {
+    fn drop(&mut self) {
+        match &mut self.0 {
+            Secret::Raw(key) => key.zeroize(),
+            Secret::Passphrase(passphrase) => passphrase.zeroize(),
+        }
+    }
+}
+
+impl std::fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self.0 {
+            Secret::Raw(_) => "SessionKey::Raw(..)",
+            Secret::Passphrase(_) => "SessionKey::Passphrase(..)",
+        })
+    }
+}
+
+/// A salt and the key derived from the passphrase with it
+type DerivedKey = ([u8; SALT_LEN], Zeroizing<[u8; 32]>);
+
+/// A [`FileSessionStore`] that encrypts the sessions, see the [module docs](self) for the file format.
+///
+/// The wrong keys and the modified files are [`SessionError::DecryptionFailed`] errors.
+///
+/// # Example
+/// ```
+/// use pinterest_login::encryption::{EncryptedFileSessionStore, SessionKey};
+/// use pinterest_login::session::{Session, SessionError, SessionStore};
+///
+/// # futures::executor::block_on(async {
+/// let dir = tempfile::tempdir()?;
+/// let store = EncryptedFileSessionStore::new(dir.path(), SessionKey::raw([7; 32]));
+/// store.put(&Session::new("me@example.com", Default::default())).await?;
+///
+/// let wrong = EncryptedFileSessionStore::new(dir.path(), SessionKey::raw([8; 32]));
+/// assert!(matches!(wrong.get("me@example.com").await, Err(SessionError::DecryptionFailed)));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # })?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct EncryptedFileSessionStore {
+    files: FileSessionStore,
+    key: SessionKey,
+    /// The salt and the key of the last derived passphrase, since deriving it is slow on purpose, so a file that was
+    /// just written or read isn't derived again. The key is zeroed when it's replaced and when the store is dropped
+    derived: Mutex<Option<DerivedKey>>,
+}
+
+impl EncryptedFileSessionStore {
+    /// A store of the sessions in the directory, encrypted with the key
+    pub fn new(dir: impl Into<PathBuf>, key: SessionKey) -> Self {
+        Self {
+            files: FileSessionStore::new(dir).with_extension("enc"),
+            key,
+            derived: Mutex::new(None),
+        }
+    }
+
+    /// The file of the session of the account
+    pub fn path(&self, account: &str) -> PathBuf {
+        self.files.path(account)
+    }
+
+    /// Encrypts every session of the directory with the new key, and returns the store with the new key.
+    ///
+    /// Every session is decrypted before any of them is written, so a wrong old key doesn't change anything.
+    /// The files that are already encrypted with the new key are kept as they are, so it can be run again
+    /// if it gets interrupted.
+    pub async fn re_encrypt(
+        dir: impl Into<PathBuf>,
+        old_key: SessionKey,
+        new_key: SessionKey,
+    ) -> Result<Self, SessionError> {
+        let dir = dir.into();
+        let old = Self::new(dir.clone(), old_key);
+        let new = Self::new(dir, new_key);
+        let mut sessions = Vec::new();
+        for account in old.list().await? {
+            let contents = fs::read(old.path(&account))?;
+            match old.open(&contents) {
+                Ok(session) => sessions.push(session),
+                Err(SessionError::DecryptionFailed) if new.open(&contents).is_ok() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        for session in &sessions {
+            new.save(session)?;
+        }
+        Ok(new)
+    }
+
+    fn derive(&self, salt: &[u8; SALT_LEN]) -> Key {
+        let passphrase = match &self.key.0 {
+            Secret::Raw(key) => return *Key::from_slice(key),
+            Secret::Passphrase(passphrase) => passphrase,
+        };
+        let mut derived = self.derived.lock().unwrap_or_else(|e| e.into_inner());
+        match &*derived {
+            Some((cached, key)) if cached == salt => *Key::from_slice(key.as_slice()),
+            _ => {
+                let mut key = Zeroizing::new([0; 32]);
+                argon2()
+                    .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+                    // It only fails on invalid parameters and lengths, which are fixed
+                    .expect("the argon2 parameters are valid");
+                let derived_key = *Key::from_slice(key.as_slice());
+                *derived = Some((*salt, key));
+                derived_key
+            }
+        }
+    }
+
+    /// The salt of a new file, a random one for each file with a passphrase
+    fn salt(&self) -> [u8; SALT_LEN] {
+        if let Secret::Raw(_) = self.key.0 {
+            return [0; SALT_LEN];
+        }
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    }
+
+    fn seal(&self, session: &Session) -> Vec<u8> {
+        let salt = self.salt();
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut file = Vec::with_capacity(HEADER_LEN);
+        file.extend_from_slice(MAGIC);
+        file.push(ENCRYPTED_SESSION_VERSION);
+        file.push(self.key.kind());
+        file.extend_from_slice(&salt);
+        file.extend_from_slice(&nonce);
+        let mut plaintext = session.to_json().to_string().into_bytes();
+        let ciphertext = ChaCha20Poly1305::new(&self.derive(&salt))
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: &file,
+                },
+            )
+            .expect("the sessions are smaller than the ChaCha20-Poly1305 limit");
+        plaintext.zeroize();
+        file.extend_from_slice(&ciphertext);
+        file
+    }
+
+    fn open(&self, file: &[u8]) -> Result<Session, SessionError> {
+        if file.len() < HEADER_LEN || &file[..4] != MAGIC {
+            return Err(SessionError::InvalidField {
+                field: "header",
+                reason: "it's not an encrypted session file",
+            });
+        }
+        if file[4] != ENCRYPTED_SESSION_VERSION {
+            return Err(SessionError::UnsupportedVersion {
+                found: Some(file[4].into()),
+                supported: ENCRYPTED_SESSION_VERSION.into()..=ENCRYPTED_SESSION_VERSION.into(),
+            });
+        }
+        if file[5] != self.key.kind() {
+            return Err(SessionError::DecryptionFailed);
+        }
+        let (header, ciphertext) = file.split_at(HEADER_LEN);
+        let salt = header[6..6 + SALT_LEN].try_into().expect("the salt length");
+        let nonce = Nonce::from_slice(&header[6 + SALT_LEN..]);
+        let mut plaintext = ChaCha20Poly1305::new(&self.derive(&salt))
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| SessionError::DecryptionFailed)?;
+        let session = serde_json::from_slice(&plaintext)
+            .map_err(SessionError::from)
+            .and_then(|json| Session::from_json(&json));
+        plaintext.zeroize();
+        session
+    }
+
+    fn load(&self, path: &Path) -> Result<Option<Session>, SessionError> {
+        match fs::read(path) {
+            Ok(contents) => self.open(&contents).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, session: &Session) -> Result<(), SessionError> {
+        write_atomic(self.path(&session.account), &self.seal(session), true)?;
+        Ok(())
+    }
+}
+
+/// The Argon2id of the file format, with its parameters pinned so the files stay readable whatever the defaults of
+/// the argon2 crate become
+fn argon2() -> Argon2<'static> {
+    let params = Params::new(ARGON2_MEMORY, ARGON2_ITERATIONS, ARGON2_LANES, Some(32))
+        .expect("the argon2 parameters are valid");
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+/// Saves the session encrypted with the passphrase to the file, in the format of the [module docs](self), atomically
+/// and only readable by its owner like [`write_atomic`] does. The parent directories are created if they're missing.
+///
+/// It's for a single session at a path of its own, the [`EncryptedFileSessionStore`] keeps the ones of several
+/// accounts. It's blocking, and deriving the key of the passphrase is slow on purpose, use it from a blocking task in
+/// async code.
+///
+/// # Example
+/// ```
+/// use pinterest_login::encryption::{load_encrypted, save_encrypted};
+/// use pinterest_login::session::{Session, SessionError};
+///
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("session.enc");
+/// let session = Session::new("me@example.com", Default::default());
+///
+/// save_encrypted(&path, "correct horse", &session)?;
+///
+/// assert_eq!(load_encrypted(&path, "correct horse")?.account, session.account);
+/// assert!(matches!(load_encrypted(&path, "wrong"), Err(SessionError::DecryptionFailed)));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn save_encrypted(
+    path: impl AsRef<Path>,
+    passphrase: &str,
+    session: &Session,
+) -> Result<(), SessionError> {
+    let sealer = EncryptedFileSessionStore::new(".", SessionKey::passphrase(passphrase));
+    write_atomic(path, &sealer.seal(session), true)?;
+    Ok(())
+}
+
+/// Loads the session that [`save_encrypted`] saved with the passphrase.
+///
+/// It's blocking, use it from a blocking task in async code.
+///
+/// # Errors
+/// * [`SessionError::DecryptionFailed`] - If the passphrase is wrong, or if the file was modified
+/// * [`SessionError::UnsupportedVersion`] - If the file is of another version of the format
+/// * [`SessionError::InvalidField`] - If the file isn't an encrypted session at all
+/// * [`SessionError::Io`] - If the file can't be read
+pub fn load_encrypted(path: impl AsRef<Path>, passphrase: &str) -> Result<Session, SessionError> {
+    let opener = EncryptedFileSessionStore::new(".", SessionKey::passphrase(passphrase));
+    opener.open(&fs::read(path)?)
+}
+
+impl SessionStore for EncryptedFileSessionStore {
+    fn get<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<Option<Session>, SessionError>> {
+        async move { self.load(&self.path(account)) }.boxed()
+    }
+
+    fn put<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, Result<(), SessionError>> {
+        async move { self.save(session) }.boxed()
+    }
+
+    fn delete<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), SessionError>> {
+        self.files.delete(account)
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, SessionError>> {
+        self.files.list()
+    }
+}
@@ -0,0 +1,85 @@
+use chromiumoxide::{
+    cdp::{
+        browser_protocol::{network, page as page_events},
+        js_protocol::runtime,
+        CdpEvent, IntoEventKind,
+    },
+    Page,
+};
+use futures::{channel::mpsc, stream::BoxStream, StreamExt};
+#[cfg(feature = "log")]
+use log::trace;
+
+use crate::{browser_guard::BrowserGuard, options::EventSink};
+
+/// How many events can wait for the sink before the new ones get dropped
+const EVENT_BUFFER: usize = 256;
+
+/// Subscribes to the interesting events of the page, and forwards them to the sink in the background
+pub(crate) async fn forward_events(
+    browser: &mut BrowserGuard,
+    page: &Page,
+    sink: EventSink,
+) -> crate::Result<()> {
+    async fn listen<T: IntoEventKind + Clone + Unpin + Send + Sync + 'static>(
+        page: &Page,
+        into_event: fn(T) -> CdpEvent,
+    ) -> crate::Result<BoxStream<'static, CdpEvent>> {
+        Ok(page
+            .event_listener::<T>()
+            .await?
+            .map(move |e| into_event((*e).clone()))
+            .boxed())
+    }
+
+    let mut events = futures::stream::select_all([
+        listen(page, |e: page_events::EventFrameStartedLoading| {
+            CdpEvent::PageFrameStartedLoading(e)
+        })
+        .await?,
+        listen(page, |e: page_events::EventFrameNavigated| {
+            CdpEvent::PageFrameNavigated(Box::new(e))
+        })
+        .await?,
+        listen(page, |e: page_events::EventDomContentEventFired| {
+            CdpEvent::PageDomContentEventFired(e)
+        })
+        .await?,
+        listen(page, CdpEvent::PageLoadEventFired).await?,
+        listen(page, |e: network::EventRequestWillBeSent| {
+            CdpEvent::NetworkRequestWillBeSent(Box::new(e))
+        })
+        .await?,
+        listen(page, |e: network::EventResponseReceived| {
+            CdpEvent::NetworkResponseReceived(Box::new(e))
+        })
+        .await?,
+        listen(page, CdpEvent::NetworkLoadingFinished).await?,
+        listen(page, CdpEvent::NetworkLoadingFailed).await?,
+        listen(page, CdpEvent::RuntimeConsoleApiCalled).await?,
+        listen(page, |e: runtime::EventExceptionThrown| {
+            CdpEvent::RuntimeExceptionThrown(Box::new(e))
+        })
+        .await?,
+    ]);
+
+    let (mut tx, mut rx) = mpsc::channel(EVENT_BUFFER);
+    browser.spawn(async move {
+        while let Some(event) = events.next().await {
+            if let Err(e) = tx.try_send(event) {
+                if e.is_disconnected() {
+                    break;
+                }
+                #[cfg(feature = "log")]
+                trace!("The event sink is too slow, dropping an event");
+            }
+        }
+    });
+    browser.spawn(async move {
+        while let Some(event) = rx.next().await {
+            sink(&event);
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::{future::Future, time::Duration};
+
+#[cfg(feature = "log")]
+use log::info;
+
+use crate::{clock::Clock, ErrorKind, PinterestLoginError};
+
+/// Whether a headless login that failed with the error may succeed headful, with a human's help.
+///
+/// Only the challenges are: a wrong email or password stays wrong, and the browser or network failures don't
+/// need a human.
+pub fn needs_human(error: &PinterestLoginError) -> bool {
+    error.kind() == ErrorKind::Challenge
+}
+
+/// Runs the `headless` login, and if pinterest challenges it (see [`needs_human`]), the `headful` one,
+/// so a human can finish the login in the browser window.
+///
+/// Both logins share the overall `deadline`: the `headful` one is given the time that's left, e.g. for
+/// [`DefaultBrowserLoginBot::with_manual_assist`](crate::login_bot::DefaultBrowserLoginBot::with_manual_assist),
+/// and the login fails with [`PinterestLoginError::DeadlineExceeded`] when it's over.
+///
+/// # Example
+/// ```no_run
+/// # use std::time::Duration;
+/// # use pinterest_login::clock::SystemClock;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::fallback::with_headful_fallback;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::login_with_options;
+/// # use pinterest_login::options::LoginOptions;
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let options = LoginOptions::new();
+/// let headless = DefaultBrowserConfigBuilder::new(true, None, None);
+/// let headful = DefaultBrowserConfigBuilder::new(false, None, None);
+/// let bot = DefaultBrowserLoginBot::new("email", "password");
+///
+/// let outcome = with_headful_fallback(
+///     Duration::from_secs(5 * 60),
+///     &SystemClock,
+///     login_with_options(&bot, &headless, &options),
+///     |time_left| {
+///         let bot = DefaultBrowserLoginBot::new("email", "password").with_manual_assist(time_left);
+///         let (headful, options) = (&headful, &options);
+///         async move { login_with_options(&bot, headful, options).await }
+///     },
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_headful_fallback<T, H, F, Fut>(
+    deadline: Duration,
+    clock: &dyn Clock,
+    headless: H,
+    headful: F,
+) -> crate::Result<T>
+where
+    H: Future<Output = crate::Result<T>>,
+    F: FnOnce(Duration) -> Fut,
+    Fut: Future<Output = crate::Result<T>>,
+{
+    let start = clock.now();
+    let error = match crate::timeout(clock, deadline, headless).await {
+        Some(Err(e)) if needs_human(&e) => e,
+        Some(res) => return res,
+        None => return Err(PinterestLoginError::DeadlineExceeded(deadline)),
+    };
+    let time_left = deadline.saturating_sub(clock.now().duration_since(start));
+    if time_left.is_zero() {
+        return Err(PinterestLoginError::DeadlineExceeded(deadline));
+    }
+    #[cfg(feature = "log")]
+    info!("The headless login was challenged ({error}), falling back to a headful one for {time_left:?}");
+    #[cfg(not(feature = "log"))]
+    let _ = error;
+    crate::timeout(clock, time_left, headful(time_left))
+        .await
+        .unwrap_or(Err(PinterestLoginError::DeadlineExceeded(deadline)))
+}
@@ -0,0 +1,179 @@
+use std::{fmt, time::Duration};
+
+use crate::jitter::Jitter;
+
+/// The Chrome major versions that the user agents are picked from, recent enough to not stand out
+const CHROME_VERSIONS: &[u32] = &[118, 119, 120, 121, 122, 123, 124];
+
+/// The common desktop viewport sizes, `(width, height)`
+const VIEWPORTS: &[(u32, u32)] = &[
+    (1280, 720),
+    (1366, 768),
+    (1440, 900),
+    (1536, 864),
+    (1600, 900),
+    (1920, 1080),
+];
+
+/// The range of the base delays between two keystrokes, in milliseconds
+const KEY_DELAY_MS: (u64, u64) = (60, 140);
+/// The range of the random variations around the base delay, in milliseconds
+const KEY_JITTER_MS: (u64, u64) = (10, 50);
+
+/// How the browser of a login attempt presents itself: its user agent, its viewport, and the typing pace of the login bot.
+///
+/// The fingerprints are made by [`Fingerprints`], and applied with
+/// [`LoginOptions::fingerprint`](crate::options::LoginOptions::fingerprint) and
+/// [`DefaultBrowserLoginBot::with_typing`](crate::login_bot::DefaultBrowserLoginBot::with_typing).
+///
+/// Its [`Display`](fmt::Display) is meant for the logs: it only tells the browser version, the viewport and the
+/// typing pace, not the whole user agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Fingerprint {
+    /// The user agent of the login page
+    pub user_agent: String,
+    /// The viewport of the login page
+    pub viewport: Viewport,
+    /// The pace of the typing into the login form
+    pub typing: TypingTimings,
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let browser = self
+            .user_agent
+            .split_whitespace()
+            .find(|product| product.starts_with("Chrome/"))
+            .and_then(|product| product.split('.').next())
+            .unwrap_or("unknown browser");
+        write!(f, "{browser}, {}, typing {}", self.viewport, self.typing)
+    }
+}
+
+/// The size of the viewport of the login page, in CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Viewport {
+    /// The width
+    pub width: u32,
+    /// The height
+    pub height: u32,
+}
+
+impl fmt::Display for Viewport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// The pace of the typing into the login form: a delay after every character, of `key_delay` give or take `jitter`.
+///
+/// The variations are random, but the same `seed` gives the same delays.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use pinterest_login::fingerprint::TypingTimings;
+///
+/// let typing = TypingTimings::new(Duration::from_millis(100), Duration::from_millis(30), 7);
+/// let delay = typing.delay(0);
+/// assert!(delay >= Duration::from_millis(70) && delay <= Duration::from_millis(130));
+/// assert_eq!(delay, typing.delay(0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypingTimings {
+    /// The average delay after a character
+    pub key_delay: Duration,
+    /// The maximum variation of the delay, either way
+    pub jitter: Duration,
+    /// The seed of the variations
+    pub seed: u64,
+}
+
+impl TypingTimings {
+    /// Creates the typing timings
+    pub fn new(key_delay: Duration, jitter: Duration, seed: u64) -> Self {
+        Self {
+            key_delay,
+            jitter,
+            seed,
+        }
+    }
+
+    /// The delay after the character at the index (counted in characters, across the typed texts)
+    pub fn delay(&self, index: usize) -> Duration {
+        let jitter = self.jitter.as_millis() as u64;
+        let offset = Jitter::new(self.seed ^ index as u64).next_u64() % (2 * jitter + 1);
+        (self.key_delay + Duration::from_millis(offset)).saturating_sub(self.jitter)
+    }
+}
+
+impl fmt::Display for TypingTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}±{:?}", self.key_delay, self.jitter)
+    }
+}
+
+/// Picks a new realistic [`Fingerprint`] for every login attempt, see
+/// [`LoginRetry::vary_fingerprint`](crate::retry::LoginRetry::vary_fingerprint).
+///
+/// The user agents are the ones of a desktop Chrome on Windows (the platform of the user agent of the stealth mode),
+/// only their version changes, and every fingerprint differs from the previous one. The same seed gives the same
+/// fingerprints, to reproduce a run.
+///
+/// # Example
+/// ```
+/// use pinterest_login::fingerprint::Fingerprints;
+///
+/// let mut fingerprints = Fingerprints::new(42);
+/// let (first, second) = (fingerprints.next_fingerprint(), fingerprints.next_fingerprint());
+/// assert_ne!(first, second);
+///
+/// // The same seed, the same fingerprints
+/// assert_eq!(Fingerprints::new(42).next_fingerprint(), first);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fingerprints {
+    rng: Jitter,
+    last: Option<Fingerprint>,
+}
+
+impl Fingerprints {
+    /// Creates the fingerprints of the seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Jitter::new(seed),
+            last: None,
+        }
+    }
+
+    /// Picks the next fingerprint, it differs from the previous one
+    pub fn next_fingerprint(&mut self) -> Fingerprint {
+        loop {
+            let fingerprint = self.pick();
+            if self.last.as_ref() != Some(&fingerprint) {
+                self.last = Some(fingerprint.clone());
+                return fingerprint;
+            }
+        }
+    }
+
+    fn pick(&mut self) -> Fingerprint {
+        let version = CHROME_VERSIONS[self.rng.below(CHROME_VERSIONS.len() as u64) as usize];
+        let (width, height) = VIEWPORTS[self.rng.below(VIEWPORTS.len() as u64) as usize];
+        let key_delay = self.rng.between(KEY_DELAY_MS);
+        let jitter = self.rng.between(KEY_JITTER_MS);
+        Fingerprint {
+            user_agent: format!(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{version}.0.0.0 Safari/537.36"
+            ),
+            viewport: Viewport { width, height },
+            typing: TypingTimings::new(
+                Duration::from_millis(key_delay),
+                Duration::from_millis(jitter),
+                self.rng.next_u64(),
+            ),
+        }
+    }
+}
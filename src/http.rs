@@ -0,0 +1,171 @@
+//! The HTTP responses of the login errors, for the services that log in on behalf of their clients.
+//!
+//! Every [`ErrorKind`] has a status and a stable code, see [`STATUS_CODES`]:
+//!
+//! | Kind                              | Status                      | Code                  |
+//! |-----------------------------------|-----------------------------|-----------------------|
+//! | [`ErrorKind::Authentication`]     | `401 Unauthorized`          | `authentication`      |
+//! | [`ErrorKind::Challenge`]          | `428 Precondition Required` | `challenge`           |
+//! | [`ErrorKind::RateLimited`]        | `429 Too Many Requests`     | `rate_limited`        |
+//! | [`ErrorKind::Browser`]            | `500 Internal Server Error` | `browser`             |
+//! | [`ErrorKind::Timeout`]            | `504 Gateway Timeout`       | `timeout`             |
+//! | [`ErrorKind::Network`]            | `502 Bad Gateway`           | `network`             |
+//! | [`ErrorKind::ServiceUnavailable`] | `503 Service Unavailable`   | `service_unavailable` |
+//! | [`ErrorKind::Store`]              | `500 Internal Server Error` | `store`               |
+//!
+//! The table only changes with a major version, the new kinds of a minor version get a new row.
+//!
+//! With the `axum` feature, [`PinterestLoginError`] and [`ErrorResponse`] are axum responses.
+//!
+//! # Example
+//! ```
+//! use http::StatusCode;
+//! use pinterest_login::http::ErrorResponse;
+//! use pinterest_login::PinterestLoginError;
+//!
+//! let response = ErrorResponse::from(&PinterestLoginError::AuthenticationError).to_response();
+//! assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+//! assert!(response.body().starts_with(r#"{"code":"authentication","message":"#));
+//! ```
+
+use std::time::Duration;
+
+use http::{header, HeaderValue, StatusCode};
+use serde_json::json;
+
+use crate::{ErrorKind, PinterestLoginError};
+
+/// The status and the stable code of every error kind, see the [module docs](self)
+pub const STATUS_CODES: &[(ErrorKind, StatusCode, &str)] = &[
+    (
+        ErrorKind::Authentication,
+        StatusCode::UNAUTHORIZED,
+        "authentication",
+    ),
+    (
+        ErrorKind::Challenge,
+        StatusCode::PRECONDITION_REQUIRED,
+        "challenge",
+    ),
+    (
+        ErrorKind::RateLimited,
+        StatusCode::TOO_MANY_REQUESTS,
+        "rate_limited",
+    ),
+    (
+        ErrorKind::Browser,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "browser",
+    ),
+    (ErrorKind::Timeout, StatusCode::GATEWAY_TIMEOUT, "timeout"),
+    (ErrorKind::Network, StatusCode::BAD_GATEWAY, "network"),
+    (
+        ErrorKind::ServiceUnavailable,
+        StatusCode::SERVICE_UNAVAILABLE,
+        "service_unavailable",
+    ),
+    (ErrorKind::Store, StatusCode::INTERNAL_SERVER_ERROR, "store"),
+];
+
+/// The status of the error kind, see [`STATUS_CODES`]
+// A match rather than a lookup in the table, so a new kind doesn't compile without its status
+pub fn status(kind: ErrorKind) -> StatusCode {
+    match kind {
+        ErrorKind::Authentication => StatusCode::UNAUTHORIZED,
+        ErrorKind::Challenge => StatusCode::PRECONDITION_REQUIRED,
+        ErrorKind::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+        ErrorKind::Browser => StatusCode::INTERNAL_SERVER_ERROR,
+        ErrorKind::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        ErrorKind::Network => StatusCode::BAD_GATEWAY,
+        ErrorKind::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        ErrorKind::Store => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// The stable code of the error kind, see [`STATUS_CODES`] and [`ErrorKind::code`]
+pub fn code(kind: ErrorKind) -> &'static str {
+    kind.code()
+}
+
+/// The HTTP response of a login error: its status, and a JSON body with its code and message
+/// (`{"code": "rate_limited", "message": "..."}`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorResponse {
+    /// The status of the kind of the error
+    pub status: StatusCode,
+    /// The stable code of the kind of the error
+    pub code: &'static str,
+    /// The message of the error
+    pub message: String,
+    /// When to try again, sent in the `Retry-After` header. It's the retry hint of a
+    /// [`PinterestLoginError::ServiceUnavailable`] error
+    pub retry_after: Option<Duration>,
+}
+
+impl ErrorResponse {
+    /// Sets when to try again, e.g. the backoff of the service after a [`ErrorKind::RateLimited`] error, pinterest
+    /// doesn't tell it
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+
+    /// The JSON body
+    pub fn body(&self) -> String {
+        json!({ "code": self.code, "message": self.message }).to_string()
+    }
+
+    /// The response, with the `Content-Type` and `Retry-After` headers
+    pub fn to_response(&self) -> http::Response<String> {
+        let mut response = http::Response::new(self.body());
+        *response.status_mut() = self.status;
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        if let Some(retry_after) = self.retry_after {
+            // Rounded up, so the client doesn't come back too early
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+impl From<&PinterestLoginError> for ErrorResponse {
+    fn from(error: &PinterestLoginError) -> Self {
+        let kind = error.kind();
+        Self {
+            status: status(kind),
+            code: kind.code(),
+            message: error.to_string(),
+            // Pinterest tells when to try again only for its outages
+            retry_after: match error {
+                PinterestLoginError::ServiceUnavailable { retry_hint } => *retry_hint,
+                _ => None,
+            },
+        }
+    }
+}
+
+impl From<PinterestLoginError> for ErrorResponse {
+    fn from(error: PinterestLoginError) -> Self {
+        Self::from(&error)
+    }
+}
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for ErrorResponse {
+    fn into_response(self) -> axum::response::Response {
+        self.to_response().map(axum::body::Body::from)
+    }
+}
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for PinterestLoginError {
+    fn into_response(self) -> axum::response::Response {
+        ErrorResponse::from(&self).into_response()
+    }
+}
@@ -0,0 +1,286 @@
+//! **Experimental.** The login without a browser: the credentials are posted to the login endpoint of the web app
+//! of pinterest, like its login form does.
+//!
+//! It's much lighter than launching Chrome, but it's **more likely to be blocked** than the browser login: the
+//! requests don't run the scripts of the login page, so pinterest can tell them from a browser, and it asks for a
+//! captcha ([`PinterestLoginError::ChallengeRequired`]) or refuses the logins sooner. The endpoint is internal too,
+//! it may change without notice. Fall back to [`login`](crate::login) when it fails with a challenge.
+//!
+//! The requests are made with [reqwest](https://docs.rs/reqwest) on tokio: in the tokio runtime of the caller if
+//! there's one, otherwise in a small runtime of the crate, so it works with async-std too.
+
+use std::time::Duration;
+
+#[cfg(feature = "log")]
+use log::{debug, info};
+use reqwest::{
+    header::{CONTENT_TYPE, ORIGIN, REFERER, RETRY_AFTER, SET_COOKIE},
+    redirect, Client, Response, StatusCode,
+};
+use serde_json::Value;
+
+use crate::{
+    api::build_api_headers, http_runtime::on_tokio, outage, PinterestCookies, PinterestLoginError,
+};
+
+/// The origin of pinterest
+pub const PINTEREST_ORIGIN: &str = "https://www.pinterest.com";
+/// The path of the login page, that gives the `csrftoken` cookie
+const LOGIN_PATH: &str = "/login/";
+/// The path of the endpoint that the login form posts the credentials to
+const SESSION_PATH: &str = "/resource/UserSessionResource/create/";
+/// The user agent of the requests, unless another one is set
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36";
+/// The default timeout of the whole login
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The login over HTTP, see the [module docs](self) for why it's experimental.
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::http_login::HttpLogin;
+///
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let cookies = HttpLogin::new().login("me@example.com", "hunter2").await?;
+/// println!("{:?}", cookies.get("_pinterest_sess"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpLogin {
+    base_url: String,
+    user_agent: String,
+    proxy: Option<String>,
+    timeout: Duration,
+}
+
+impl Default for HttpLogin {
+    fn default() -> Self {
+        Self {
+            base_url: PINTEREST_ORIGIN.to_string(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl HttpLogin {
+    /// Creates the login against pinterest
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Logs in against another origin than [`PINTEREST_ORIGIN`], e.g. a local stub of the endpoint in the tests
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the user agent of the requests, a recent desktop Chrome by default
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sends the requests through the proxy, e.g. `http://127.0.0.1:8080` or `socks5://127.0.0.1:1080`
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Sets the timeout of the whole login, 30 seconds by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The user agent of the requests, to keep with the cookies (see
+    /// [`Session::with_user_agent`](crate::session::Session::with_user_agent))
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Logs in with the credentials, and returns the cookies of the session like [`login`](crate::login) does.
+    ///
+    /// # Errors
+    /// * [`PinterestLoginError::AuthenticationError`] - If the email or the password is incorrect
+    /// * [`PinterestLoginError::ChallengeRequired`] - If pinterest asks for a captcha
+    /// * [`PinterestLoginError::RateLimited`] - If pinterest refuses the login attempts for a while
+    /// * [`PinterestLoginError::ServiceUnavailable`] - If pinterest answers with a server error
+    /// * [`PinterestLoginError::DeadlineExceeded`] - If the login didn't complete within its timeout
+    /// * [`PinterestLoginError::HttpLoginError`] - If the requests failed, e.g. pinterest can't be reached
+    pub async fn login(&self, email: &str, password: &str) -> crate::Result<PinterestCookies> {
+        let login = self.clone();
+        let (email, password) = (email.to_string(), password.to_string());
+        on_tokio(async move { login.run(&email, &password).await })
+            .await
+            .map_err(PinterestLoginError::HttpLoginError)?
+    }
+
+    async fn run(&self, email: &str, password: &str) -> crate::Result<PinterestCookies> {
+        let client = self.client()?;
+        let login_url = format!("{}{LOGIN_PATH}", self.base_url);
+
+        #[cfg(feature = "log")]
+        info!("Fetching {login_url} over HTTP");
+        let response = client
+            .get(&login_url)
+            .send()
+            .await
+            .map_err(|e| self.request_error(e))?;
+        let mut cookies = PinterestCookies::new();
+        collect_cookies(&response, &mut cookies);
+        check_status(&response)?;
+        if !cookies.contains_key("csrftoken") {
+            return Err(PinterestLoginError::HttpLoginError(
+                "the login page didn't set the `csrftoken` cookie".to_string(),
+            ));
+        }
+
+        let data = serde_json::json!({
+            "options": { "username_or_email": email, "password": password },
+            "context": {},
+        });
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("source_url", LOGIN_PATH)
+            .append_pair("data", &data.to_string())
+            .finish();
+        let headers = build_api_headers(&cookies, Some(&self.user_agent))
+            .map_err(|e| PinterestLoginError::HttpLoginError(e.to_string()))?;
+        let mut request = client
+            .post(format!("{}{SESSION_PATH}", self.base_url))
+            .header(
+                CONTENT_TYPE,
+                "application/x-www-form-urlencoded; charset=UTF-8",
+            )
+            .header(ORIGIN, &self.base_url)
+            .header(REFERER, &login_url)
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        #[cfg(feature = "log")]
+        debug!("Posting the credentials");
+        let response = request.send().await.map_err(|e| self.request_error(e))?;
+        collect_cookies(&response, &mut cookies);
+        check_status(&response)?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(|e| self.request_error(e))?;
+        let json: Value = serde_json::from_slice(&body).map_err(|e| {
+            PinterestLoginError::HttpLoginError(format!(
+                "the login endpoint didn't answer with JSON ({status}): {e}"
+            ))
+        })?;
+        check_resource_response(status, &json)?;
+        if !cookies.contains_key("_pinterest_sess") {
+            return Err(PinterestLoginError::AuthenticationError);
+        }
+
+        #[cfg(feature = "log")]
+        info!("Logged in over HTTP");
+        Ok(cookies)
+    }
+
+    fn client(&self) -> crate::Result<Client> {
+        let mut builder = Client::builder()
+            .user_agent(&self.user_agent)
+            .timeout(self.timeout)
+            // The cookies of the redirects would be lost
+            .redirect(redirect::Policy::none());
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy).map_err(|e| {
+                PinterestLoginError::HttpLoginError(format!("invalid proxy `{proxy}`: {e}"))
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        builder
+            .build()
+            .map_err(|e| PinterestLoginError::HttpLoginError(e.to_string()))
+    }
+
+    fn request_error(&self, error: reqwest::Error) -> PinterestLoginError {
+        if error.is_timeout() {
+            PinterestLoginError::DeadlineExceeded(self.timeout)
+        } else {
+            PinterestLoginError::HttpLoginError(error.to_string())
+        }
+    }
+}
+
+/// Adds the cookies that the response sets, and removes the ones that it expires
+fn collect_cookies(response: &Response, cookies: &mut PinterestCookies) {
+    for header in response.headers().get_all(SET_COOKIE) {
+        let Ok(header) = header.to_str() else {
+            continue;
+        };
+        let mut parts = header.split(';');
+        let Some((name, value)) = parts.next().and_then(|pair| pair.split_once('=')) else {
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        let expired = parts.any(|attribute| {
+            attribute.split_once('=').is_some_and(|(key, value)| {
+                key.trim().eq_ignore_ascii_case("max-age") && value.trim().starts_with('0')
+            })
+        });
+        if name.is_empty() {
+            continue;
+        }
+        if expired {
+            cookies.remove(name);
+        } else {
+            cookies.insert(name.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Fails on the statuses that mean that pinterest refuses the login or is down, whatever the body says
+fn check_status(response: &Response) -> crate::Result<()> {
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(PinterestLoginError::RateLimited);
+    }
+    if outage::is_outage_status(status.as_u16().into()) {
+        let retry_hint = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(outage::parse_retry_after);
+        return Err(PinterestLoginError::ServiceUnavailable { retry_hint });
+    }
+    Ok(())
+}
+
+/// Checks the `resource_response` of the login endpoint, its `error` tells why the login failed
+fn check_resource_response(status: StatusCode, json: &Value) -> crate::Result<()> {
+    let resource = &json["resource_response"];
+    if status.is_success() && resource["status"] == "success" {
+        return Ok(());
+    }
+    let error = &resource["error"];
+    let text = [&error["code"], &error["message"], &error["message_detail"]]
+        .iter()
+        .map(|value| match value {
+            Value::String(value) => value.to_lowercase(),
+            Value::Null => String::new(),
+            value => value.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    #[cfg(feature = "log")]
+    debug!("The login endpoint refused the login ({status}): {text}");
+    if ["captcha", "challenge"]
+        .iter()
+        .any(|marker| text.contains(marker))
+    {
+        Err(PinterestLoginError::ChallengeRequired)
+    } else if ["rate limit", "too many"]
+        .iter()
+        .any(|marker| text.contains(marker))
+    {
+        Err(PinterestLoginError::RateLimited)
+    } else {
+        Err(PinterestLoginError::AuthenticationError)
+    }
+}
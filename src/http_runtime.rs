@@ -0,0 +1,27 @@
+use std::{future::Future, sync::OnceLock};
+
+/// Runs the future in the tokio runtime of the caller, or in a runtime of the crate outside of one (e.g. with
+/// async-std), since the HTTP client needs tokio.
+///
+/// Fails with the reason if the runtime of the crate can't start, or if the future panicked in it.
+pub(crate) async fn on_tokio<F>(future: F) -> Result<F::Output, String>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Ok(future.await);
+    }
+    static RUNTIME: OnceLock<std::io::Result<tokio::runtime::Runtime>> = OnceLock::new();
+    let runtime = RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .thread_name("pinterest-login-http")
+                .enable_all()
+                .build()
+        })
+        .as_ref()
+        .map_err(|e| format!("can't start the tokio runtime: {e}"))?;
+    runtime.spawn(future).await.map_err(|e| e.to_string())
+}
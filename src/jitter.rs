@@ -0,0 +1,68 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A small seedable random number generator, for the random parts of the delays and of the
+/// [fingerprints](crate::fingerprint::Fingerprints). The variations don't need a cryptographic one.
+///
+/// # Example
+/// ```
+/// use pinterest_login::jitter::Jitter;
+///
+/// let fraction = Jitter::random().fraction();
+/// assert!((0.0..1.0).contains(&fraction));
+///
+/// // The same seed, the same numbers
+/// assert_eq!(Jitter::new(42).next_u64(), Jitter::new(42).next_u64());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Jitter(u64);
+
+impl Jitter {
+    /// Creates the generator of the seed
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Creates a generator from the random keys of std's hash maps and the current time
+    pub fn random() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64),
+        );
+        Self(hasher.finish())
+    }
+
+    /// The next number, SplitMix64
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// A number in the inclusive range
+    pub fn between(&mut self, (low, high): (u64, u64)) -> u64 {
+        low + self.below(high - low + 1)
+    }
+
+    /// A number between `0` and `1`, `1` excluded
+    pub fn fraction(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// A random number between `0` and `1`, `1` excluded, to take a random part off a delay
+pub fn random_fraction() -> f64 {
+    Jitter::random().fraction()
+}
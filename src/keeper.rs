@@ -0,0 +1,425 @@
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chromiumoxide::cdp::browser_protocol::network::CookieParam;
+use futures::{
+    channel::oneshot,
+    future::{self, Either},
+};
+#[cfg(feature = "log")]
+use log::{info, warn};
+
+use crate::{
+    config_builder::BrowserConfigBuilder,
+    credentials::{Credentials, CredentialsProvider},
+    jitter::random_fraction,
+    lifecycle::{emit, SessionEvents},
+    login_bot::BrowserLoginBot,
+    login_with_options,
+    manager::Bots,
+    options::LoginOptions,
+    session::{
+        Expiry, Session, SessionError, SessionStore, DEFAULT_EXPIRY_MARGIN, DEFAULT_MAX_AGE,
+    },
+    verify::verify_cookies,
+};
+
+/// The first delay after a failed cycle, it doubles with every failure in a row up to the interval
+const FIRST_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A callback that is told about the failed cycles of a [`SessionKeeper`], see [`KeeperPolicy::on_failure`]
+pub type FailureHook = Arc<dyn Fn(&KeeperFailure<'_>) + Send + Sync>;
+
+/// A failed cycle of a [`SessionKeeper`], it keeps the previous session and tries again after a delay
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct KeeperFailure<'a> {
+    /// Why the cycle failed
+    pub error: &'a SessionError,
+    /// How many cycles failed in a row, 1 for the first one
+    pub failures: u32,
+    /// The delay before the next cycle
+    pub retry_in: Duration,
+}
+
+/// When and how a [`SessionKeeper`] checks and renews its session
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use pinterest_login::keeper::KeeperPolicy;
+/// use pinterest_login::options::LoginOptions;
+///
+/// let policy = KeeperPolicy::new()
+///     .interval(Duration::from_secs(30 * 60))
+///     .verify(true)
+///     .login_options(LoginOptions::new())
+///     .on_failure(|failure| eprintln!("{} (retrying in {:?})", failure.error, failure.retry_in));
+/// ```
+#[derive(Clone)]
+pub struct KeeperPolicy {
+    interval: Duration,
+    margin: Duration,
+    max_age: Duration,
+    verify: bool,
+    jitter: f64,
+    options: LoginOptions,
+    on_failure: Option<FailureHook>,
+    events: Option<Arc<dyn SessionEvents>>,
+}
+
+impl KeeperPolicy {
+    /// Checks the session every hour, and logs in again when it expires within the [`DEFAULT_EXPIRY_MARGIN`],
+    /// without checking it with pinterest
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_secs(60 * 60),
+            margin: DEFAULT_EXPIRY_MARGIN,
+            max_age: DEFAULT_MAX_AGE,
+            verify: false,
+            jitter: 0.2,
+            options: LoginOptions::default(),
+            on_failure: None,
+            events: None,
+        }
+    }
+
+    /// How long to wait between the checks of the session
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Logs in again when the session expires within the margin, see [`Session::is_expired`]
+    pub fn margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// How long the sessions without any cookie expiry are valid, see [`Session::is_expired_with_max_age`]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Also checks that the session is still logged in with [`verify_cookies`] at every check, which launches a
+    /// browser, so a revoked session is noticed before it expires
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// The delays after a failure are shortened by a random part of up to this fraction (`0.2` by default),
+    /// so the keepers that failed together don't retry together. It's clamped between `0` and `1`.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The options of the logins and the verifications, their clock is also the one of the keeper
+    pub fn login_options(mut self, options: LoginOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Calls the callback after every failed cycle, the failures are only logged otherwise
+    pub fn on_failure(mut self, hook: impl Fn(&KeeperFailure<'_>) + Send + Sync + 'static) -> Self {
+        self.on_failure = Some(Arc::new(hook));
+        self
+    }
+
+    /// The hooks on the logins, the renewals, the expiries and the failed logins of the session
+    pub fn events(mut self, events: impl SessionEvents + 'static) -> Self {
+        self.events = Some(Arc::new(events));
+        self
+    }
+
+    /// The delay after the failures in a row: a minute, doubled with every failure, up to the interval
+    fn backoff(&self, failures: u32) -> Duration {
+        let backoff = FIRST_BACKOFF
+            .checked_mul(1 << failures.saturating_sub(1).min(31))
+            .unwrap_or(Duration::MAX)
+            .min(self.interval);
+        backoff.mul_f64(1.0 - self.jitter * random_fraction())
+    }
+}
+
+impl Default for KeeperPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for KeeperPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeeperPolicy")
+            .field("interval", &self.interval)
+            .field("margin", &self.margin)
+            .field("max_age", &self.max_age)
+            .field("verify", &self.verify)
+            .field("jitter", &self.jitter)
+            .field("options", &self.options)
+            .field("on_failure", &self.on_failure.is_some())
+            .field("events", &self.events.is_some())
+            .finish()
+    }
+}
+
+/// Keeps the session of an account logged in, in a task of the selected runtime, for the long-lived services.
+///
+/// At every [interval](KeeperPolicy::interval) it reloads the session from the store (another process may have
+/// renewed it), and logs in again when it expires soon or, with [`KeeperPolicy::verify`], when it's not logged in
+/// anymore. The new sessions are put in the store, and [`SessionKeeper::current`] returns them right away.
+///
+/// A failed cycle doesn't stop the task: the previous session is kept, the failure is told to
+/// [`KeeperPolicy::on_failure`], and the cycle is tried again after a delay that doubles with every failure in a row,
+/// from a minute up to the interval. The task stops with [`SessionKeeper::stop`], or when the keeper is dropped.
+///
+/// The logins, the renewals and the expiries are also told to the [`KeeperPolicy::events`], see [`SessionEvents`].
+///
+/// # Example
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::keeper::{KeeperPolicy, SessionKeeper};
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::session::FileSessionStore;
+/// # async fn run() -> Result<(), pinterest_login::session::SessionError> {
+/// let keeper = SessionKeeper::spawn(
+///     Arc::new(FileSessionStore::new("sessions")),
+///     "me@example.com",
+///     || DefaultBrowserLoginBot::new("me@example.com", std::env::var("PINTEREST_PASSWORD").unwrap()),
+///     DefaultBrowserConfigBuilder::default(),
+///     KeeperPolicy::new(),
+/// )
+/// .await?;
+///
+/// // Whenever a request needs the cookies
+/// let cookies = &keeper.current().cookies;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SessionKeeper {
+    current: Arc<RwLock<Arc<Session>>>,
+    stop: Option<oneshot::Sender<()>>,
+    stopped: oneshot::Receiver<()>,
+}
+
+impl SessionKeeper {
+    /// Loads the session of the account from the store, or logs in with a bot of the factory when there's none or
+    /// it expires soon, then spawns the task that keeps it logged in.
+    ///
+    /// The bot factory is called for every login, so the credentials can be read again each time.
+    ///
+    /// # Errors
+    /// If the first session can't be loaded, logged in or stored, the task isn't spawned then.
+    pub async fn spawn<F, B, C>(
+        store: Arc<dyn SessionStore>,
+        account: impl Into<String>,
+        bot_factory: F,
+        config_builder: C,
+        policy: KeeperPolicy,
+    ) -> Result<Self, SessionError>
+    where
+        F: Fn() -> B + Send + Sync + 'static,
+        B: BrowserLoginBot + Send + Sync + 'static,
+        C: BrowserConfigBuilder + Send + Sync + 'static,
+    {
+        let bots = Bots::Factory(Arc::new(bot_factory));
+        Self::start(store, account.into(), bots, config_builder, policy).await
+    }
+
+    /// Like [`spawn`](Self::spawn), but the credentials are asked to the provider before every login, and the
+    /// factory makes the login bot with them.
+    ///
+    /// A failed provider fails the login with [`SessionError::Credentials`].
+    ///
+    /// # Errors
+    /// If the first session can't be loaded, logged in or stored, the task isn't spawned then.
+    pub async fn spawn_with_credentials<P, F, B, C>(
+        store: Arc<dyn SessionStore>,
+        account: impl Into<String>,
+        provider: P,
+        bot_factory: F,
+        config_builder: C,
+        policy: KeeperPolicy,
+    ) -> Result<Self, SessionError>
+    where
+        P: CredentialsProvider + 'static,
+        F: Fn(Credentials) -> B + Send + Sync + 'static,
+        B: BrowserLoginBot + Send + Sync + 'static,
+        C: BrowserConfigBuilder + Send + Sync + 'static,
+    {
+        let bots = Bots::Credentials(Arc::new(provider), Arc::new(bot_factory));
+        Self::start(store, account.into(), bots, config_builder, policy).await
+    }
+
+    async fn start<B, C>(
+        store: Arc<dyn SessionStore>,
+        account: String,
+        bots: Bots<B>,
+        config_builder: C,
+        policy: KeeperPolicy,
+    ) -> Result<Self, SessionError>
+    where
+        B: BrowserLoginBot + Send + Sync + 'static,
+        C: BrowserConfigBuilder + Send + Sync + 'static,
+    {
+        let task = Task {
+            store,
+            account,
+            bots,
+            config_builder,
+            policy,
+        };
+        let session = match task.store.get(&task.account).await? {
+            Some(session) => match task.expiry(&session) {
+                Expiry::Fresh => session,
+                Expiry::ExpiringSoon => task.login().await?,
+                Expiry::Expired => {
+                    emit(task.events(), |events| events.on_expired(&task.account)).await;
+                    task.login().await?
+                }
+            },
+            None => task.login().await?,
+        };
+        let current = Arc::new(RwLock::new(Arc::new(session)));
+        let (stop, stop_requested) = oneshot::channel();
+        let (done, stopped) = oneshot::channel();
+        let run = task.run(Arc::clone(&current), stop_requested);
+        crate::spawn(async move {
+            run.await;
+            let _ = done.send(());
+        });
+        Ok(Self {
+            current,
+            stop: Some(stop),
+            stopped,
+        })
+    }
+
+    /// The current session, it's a cheap clone of the shared one
+    pub fn current(&self) -> Arc<Session> {
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Stops the task, and waits for it: a cycle that's in progress is finished, so its session is stored
+    pub async fn stop(mut self) {
+        self.stop.take();
+        let _ = (&mut self.stopped).await;
+    }
+}
+
+struct Task<B, C> {
+    store: Arc<dyn SessionStore>,
+    account: String,
+    bots: Bots<B>,
+    config_builder: C,
+    policy: KeeperPolicy,
+}
+
+impl<B, C> Task<B, C>
+where
+    B: BrowserLoginBot + Send + Sync,
+    C: BrowserConfigBuilder + Send + Sync,
+{
+    async fn run(self, current: Arc<RwLock<Arc<Session>>>, mut stop: oneshot::Receiver<()>) {
+        let clock = self.policy.options.get_clock();
+        let mut failures = 0;
+        let mut delay = self.policy.interval;
+        loop {
+            // Dropping the sender stops the task too
+            if let Either::Left(_) = future::select(&mut stop, clock.sleep(delay)).await {
+                return;
+            }
+            let session = Arc::clone(&current.read().unwrap_or_else(|e| e.into_inner()));
+            match self.cycle(&session).await {
+                Ok(renewed) => {
+                    failures = 0;
+                    delay = self.policy.interval;
+                    if let Some(renewed) = renewed {
+                        let renewed = Arc::new(renewed);
+                        *current.write().unwrap_or_else(|e| e.into_inner()) = Arc::clone(&renewed);
+                        emit(self.events(), |events| events.on_refreshed(&renewed)).await;
+                    }
+                }
+                Err(error) => {
+                    failures += 1;
+                    delay = self.policy.backoff(failures);
+                    #[cfg(feature = "log")]
+                    warn!("Failed to renew the session of {} ({failures} in a row), retrying in {delay:?}: {error}", self.account);
+                    if let Some(hook) = &self.policy.on_failure {
+                        hook(&KeeperFailure {
+                            error: &error,
+                            failures,
+                            retry_in: delay,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks the session and renews it if it has to, returns the new session if it changed
+    async fn cycle(&self, current: &Session) -> Result<Option<Session>, SessionError> {
+        let stored = self.store.get(&self.account).await?;
+        let session = stored.as_ref().unwrap_or(current);
+        let expiry = self.expiry(session);
+        let valid = match expiry {
+            Expiry::Fresh if self.policy.verify => {
+                let cookies = session
+                    .cookies
+                    .iter()
+                    .map(|(name, value)| CookieParam::new(name.clone(), value.clone()))
+                    .collect();
+                verify_cookies(cookies, &self.config_builder, &self.policy.options)
+                    .await?
+                    .valid
+            }
+            Expiry::Fresh => true,
+            Expiry::ExpiringSoon | Expiry::Expired => false,
+        };
+        if valid {
+            return Ok(stored.filter(|stored| stored != current));
+        }
+        if expiry != Expiry::ExpiringSoon {
+            emit(self.events(), |events| events.on_expired(&self.account)).await;
+        }
+        #[cfg(feature = "log")]
+        info!("The session of {} has to be renewed", self.account);
+        self.login().await.map(Some)
+    }
+
+    /// Logs in and stores the new session
+    async fn login(&self) -> Result<Session, SessionError> {
+        let bot = self.bots.bot().await?;
+        let options = &self.policy.options;
+        let outcome = match login_with_options(&bot, &self.config_builder, options).await {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                emit(self.events(), |events| {
+                    events.on_error(&self.account, &error)
+                })
+                .await;
+                return Err(error.into());
+            }
+        };
+        let session = Session::from_outcome(self.account.as_str(), &outcome, options);
+        self.store.put(&session).await?;
+        emit(self.events(), |events| events.on_login(&session)).await;
+        Ok(session)
+    }
+
+    fn events(&self) -> Option<&dyn SessionEvents> {
+        self.policy.events.as_deref()
+    }
+
+    fn expiry(&self, session: &Session) -> Expiry {
+        let now = self.policy.options.get_clock().system_time();
+        session.is_expired_with_max_age(now, self.policy.margin, self.policy.max_age)
+    }
+}
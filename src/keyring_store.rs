@@ -0,0 +1,396 @@
+//! The sessions of the [`KeyringSessionStore`] are kept in the keyring of the OS (the Keychain on macOS, the
+//! credential manager on Windows and the secret service on linux) instead of files.
+//! The [`save`], [`load`] and [`delete`] functions do the same for a single session, without a store. The module is
+//! also at [`store::keyring`](crate::store::keyring).
+//!
+//! # Entries
+//! Every entry is under the [service](KeyringSessionStore::with_service) of the store, with these users:
+//!
+//! | User         | Secret                                                                           |
+//! |--------------|----------------------------------------------------------------------------------|
+//! | `<account>`  | The number of chunks and a `\n`, then the first chunk of the [`Session::to_json`] |
+//! | `<account>#<n>` | The other chunks, from `1`                                                   |
+//! | `#accounts`  | The accounts of the store, as a JSON array, chunked the same way                 |
+//!
+//! The sessions are cut in chunks since some backends limit the size of the secrets, the credential manager of
+//! Windows to 2560 bytes.
+
+use std::{fmt, ops::RangeInclusive, sync::Mutex};
+
+use futures::{future::BoxFuture, FutureExt};
+use keyring::{credential::CredentialBuilder, Entry, Error};
+
+use crate::session::{Session, SessionError, SessionStore};
+
+/// The default service of the entries, see [`KeyringSessionStore::with_service`]
+pub const DEFAULT_SERVICE: &str = "pinterest-login-sessions";
+/// The default size of the chunks, under the limit of the Windows credential manager with room for the count
+pub const DEFAULT_CHUNK_SIZE: usize = 2000;
+
+/// The user of the index of the accounts
+const INDEX: &str = "#accounts";
+
+/// Why the keyring can't be used, it's the source of the [`SessionError::Store`] errors of the
+/// [`KeyringSessionStore`]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum KeyringStoreError {
+    /// There's no keyring, usually a headless linux system without a secret service
+    #[error(
+        "The keyring of the system isn't available ({0}), on linux it needs a running secret service \
+         like gnome-keyring, or use a FileSessionStore instead"
+    )]
+    Unavailable(Error),
+    /// The keyring is locked and the user didn't unlock it, or denied the access to it
+    #[error("The keyring of the system is locked ({0}), unlock it and try again")]
+    Locked(Error),
+    /// An entry can't be read or written
+    #[error("The keyring entry `{user}`: {error}")]
+    Entry {
+        /// The user of the entry
+        user: String,
+        /// Why it failed
+        error: Error,
+    },
+    /// An entry is incomplete, a chunk was removed or it was written by something else
+    #[error("The keyring entry `{0}` is incomplete or corrupted")]
+    Corrupted(String),
+    /// The session was saved by a newer version of the crate, or by a version too old to be migrated, see [`load`]
+    #[error(
+        "The session of `{account}` in the keyring has the unsupported version {found:?}, the supported versions \
+         are {supported:?}"
+    )]
+    UnsupportedVersion {
+        /// The account of the session
+        account: String,
+        /// The version of the session, `None` if it has none
+        found: Option<u64>,
+        /// The versions that can be read
+        supported: RangeInclusive<u64>,
+    },
+    /// There's no session of the account, see [`load`]
+    #[error("The keyring has no session of `{0}`")]
+    NotFound(String),
+}
+
+impl From<KeyringStoreError> for SessionError {
+    fn from(error: KeyringStoreError) -> Self {
+        SessionError::Store(Box::new(error))
+    }
+}
+
+/// A [`SessionStore`] in the keyring of the OS, see the [module docs](self) for its entries.
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::keyring_store::KeyringSessionStore;
+/// use pinterest_login::session::{Session, SessionStore};
+///
+/// # futures::executor::block_on(async {
+/// let store = KeyringSessionStore::new();
+/// store.put(&Session::new("me@example.com", Default::default())).await?;
+/// assert_eq!(store.list().await?, ["me@example.com"]);
+/// # Ok::<(), pinterest_login::session::SessionError>(())
+/// # })?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct KeyringSessionStore {
+    service: String,
+    chunk_size: usize,
+    builder: Option<Box<CredentialBuilder>>,
+    /// The index is read and written again by every `put` and `delete`
+    index: Mutex<()>,
+}
+
+impl KeyringSessionStore {
+    /// A store in the default keyring of the OS, under the [`DEFAULT_SERVICE`]
+    pub fn new() -> Self {
+        Self {
+            service: DEFAULT_SERVICE.to_string(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            builder: None,
+            index: Mutex::new(()),
+        }
+    }
+
+    /// The service of the entries, the stores with different services don't see the sessions of each other
+    pub fn with_service(mut self, service: impl Into<String>) -> Self {
+        self.service = service.into();
+        self
+    }
+
+    /// The size of the chunks of the secrets in bytes, at least 1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Keeps the entries in another keyring than the default one of the OS, a mock one in the tests for example
+    pub fn with_credential_builder(mut self, builder: Box<CredentialBuilder>) -> Self {
+        self.builder = Some(builder);
+        self
+    }
+
+    fn entry(&self, user: &str) -> Result<Entry, KeyringStoreError> {
+        match &self.builder {
+            Some(builder) => builder
+                .build(None, &self.service, user)
+                .map(Entry::new_with_credential),
+            None => Entry::new(&self.service, user),
+        }
+        .map_err(|error| describe(error, user))
+    }
+
+    /// Writes the data in as many chunks as it needs, and removes the chunks that it doesn't need anymore
+    fn write(&self, user: &str, data: &[u8]) -> Result<(), KeyringStoreError> {
+        let previous = match self.chunks(user) {
+            Ok(count) => count.unwrap_or(0),
+            // It's overwritten
+            Err(KeyringStoreError::Corrupted(_)) => 0,
+            Err(e) => return Err(e),
+        };
+        let mut chunks: Vec<&[u8]> = data.chunks(self.chunk_size).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        // The first chunk is written last, so the count is only updated when the others are there
+        for (n, chunk) in chunks.iter().enumerate().skip(1) {
+            self.set(&chunk_user(user, n), chunk)?;
+        }
+        let mut first = format!("{}\n", chunks.len()).into_bytes();
+        first.extend_from_slice(chunks[0]);
+        self.set(user, &first)?;
+        for n in chunks.len()..previous {
+            self.remove_entry(&chunk_user(user, n))?;
+        }
+        Ok(())
+    }
+
+    /// The data of the entry and its chunks, `None` if there's no entry
+    fn read(&self, user: &str) -> Result<Option<Vec<u8>>, KeyringStoreError> {
+        let Some(first) = self.get(user)? else {
+            return Ok(None);
+        };
+        let (count, mut data) = split_count(user, first)?;
+        for n in 1..count {
+            let chunk = self
+                .get(&chunk_user(user, n))?
+                .ok_or_else(|| KeyringStoreError::Corrupted(user.to_string()))?;
+            data.extend_from_slice(&chunk);
+        }
+        Ok(Some(data))
+    }
+
+    /// Removes the entry and its chunks, `false` if there was none
+    fn remove(&self, user: &str) -> Result<bool, KeyringStoreError> {
+        let Some(count) = self.chunks(user)? else {
+            return Ok(false);
+        };
+        for n in 1..count {
+            self.remove_entry(&chunk_user(user, n))?;
+        }
+        self.remove_entry(user)
+    }
+
+    /// The number of chunks of the entry, `None` if there's no entry
+    fn chunks(&self, user: &str) -> Result<Option<usize>, KeyringStoreError> {
+        match self.get(user)? {
+            Some(first) => split_count(user, first).map(|(count, _)| Some(count)),
+            None => Ok(None),
+        }
+    }
+
+    fn get(&self, user: &str) -> Result<Option<Vec<u8>>, KeyringStoreError> {
+        match self.entry(user)?.get_secret() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(Error::NoEntry) => Ok(None),
+            Err(error) => Err(describe(error, user)),
+        }
+    }
+
+    fn set(&self, user: &str, secret: &[u8]) -> Result<(), KeyringStoreError> {
+        self.entry(user)?
+            .set_secret(secret)
+            .map_err(|error| describe(error, user))
+    }
+
+    fn remove_entry(&self, user: &str) -> Result<bool, KeyringStoreError> {
+        match self.entry(user)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(Error::NoEntry) => Ok(false),
+            Err(error) => Err(describe(error, user)),
+        }
+    }
+
+    fn accounts(&self) -> Result<Vec<String>, KeyringStoreError> {
+        match self.read(INDEX)? {
+            Some(index) => serde_json::from_slice(&index)
+                .map_err(|_| KeyringStoreError::Corrupted(INDEX.to_string())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Adds or removes the account from the index
+    fn update_index(&self, account: &str, present: bool) -> Result<(), KeyringStoreError> {
+        let _lock = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        let mut accounts = self.accounts()?;
+        let position = accounts.binary_search_by(|a| a.as_str().cmp(account));
+        match (position, present) {
+            (Err(position), true) => accounts.insert(position, account.to_string()),
+            (Ok(position), false) => {
+                accounts.remove(position);
+            }
+            _ => return Ok(()),
+        }
+        let index = serde_json::to_string(&accounts).expect("a list of strings is valid JSON");
+        self.write(INDEX, index.as_bytes())
+    }
+}
+
+impl Default for KeyringSessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for KeyringSessionStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyringSessionStore")
+            .field("service", &self.service)
+            .field("chunk_size", &self.chunk_size)
+            .field("builder", &self.builder.is_some())
+            .finish()
+    }
+}
+
+impl SessionStore for KeyringSessionStore {
+    fn get<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<Option<Session>, SessionError>> {
+        async move {
+            match self.read(account)? {
+                Some(json) => Session::from_json(&serde_json::from_slice(&json)?).map(Some),
+                None => Ok(None),
+            }
+        }
+        .boxed()
+    }
+
+    fn put<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, Result<(), SessionError>> {
+        async move {
+            self.write(&session.account, session.to_json().to_string().as_bytes())?;
+            Ok(self.update_index(&session.account, true)?)
+        }
+        .boxed()
+    }
+
+    fn delete<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), SessionError>> {
+        async move {
+            self.remove(account)?;
+            Ok(self.update_index(account, false)?)
+        }
+        .boxed()
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, SessionError>> {
+        async move { Ok(self.accounts()?) }.boxed()
+    }
+}
+
+/// Saves the session in the default keyring of the OS, under the service and the account of the session, like a
+/// [`KeyringSessionStore`] with this service does.
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::session::Session;
+/// use pinterest_login::store::keyring::{self, KeyringStoreError, DEFAULT_SERVICE};
+///
+/// keyring::save(DEFAULT_SERVICE, &Session::new("me@example.com", Default::default()))?;
+///
+/// match keyring::load(DEFAULT_SERVICE, "me@example.com") {
+///     Ok(session) => println!("{} cookies", session.cookies.len()),
+///     // Log in again
+///     Err(
+///         KeyringStoreError::NotFound(_)
+///         | KeyringStoreError::Unavailable(_)
+///         | KeyringStoreError::Locked(_)
+///         | KeyringStoreError::UnsupportedVersion { .. },
+///     ) => {}
+///     Err(e) => return Err(e),
+/// }
+/// # Ok::<(), KeyringStoreError>(())
+/// ```
+pub fn save(service: &str, session: &Session) -> Result<(), KeyringStoreError> {
+    let store = KeyringSessionStore::new().with_service(service);
+    store.write(&session.account, session.to_json().to_string().as_bytes())?;
+    store.update_index(&session.account, true)
+}
+
+/// Loads the session of the account from the default keyring of the OS, see [`save`]
+///
+/// # Errors
+/// The cases to log in again:
+/// * [`KeyringStoreError::NotFound`] - If the account has no session
+/// * [`KeyringStoreError::Unavailable`] - If there's no keyring
+/// * [`KeyringStoreError::Locked`] - If the keyring is locked
+/// * [`KeyringStoreError::UnsupportedVersion`] - If the session was saved by another version of the crate
+/// * [`KeyringStoreError::Corrupted`] - If the session isn't one
+pub fn load(service: &str, account: &str) -> Result<Session, KeyringStoreError> {
+    let store = KeyringSessionStore::new().with_service(service);
+    let json = store
+        .read(account)?
+        .ok_or_else(|| KeyringStoreError::NotFound(account.to_string()))?;
+    let corrupted = || KeyringStoreError::Corrupted(account.to_string());
+    let json = serde_json::from_slice(&json).map_err(|_| corrupted())?;
+    Session::from_json(&json).map_err(|error| match error {
+        SessionError::UnsupportedVersion { found, supported } => {
+            KeyringStoreError::UnsupportedVersion {
+                account: account.to_string(),
+                found,
+                supported,
+            }
+        }
+        _ => corrupted(),
+    })
+}
+
+/// Deletes the session of the account from the default keyring of the OS, see [`save`]
+///
+/// # Errors
+/// [`KeyringStoreError::NotFound`] if the account has no session
+pub fn delete(service: &str, account: &str) -> Result<(), KeyringStoreError> {
+    let store = KeyringSessionStore::new().with_service(service);
+    if !store.remove(account)? {
+        return Err(KeyringStoreError::NotFound(account.to_string()));
+    }
+    store.update_index(account, false)
+}
+
+fn chunk_user(user: &str, n: usize) -> String {
+    format!("{user}#{n}")
+}
+
+/// Splits the first chunk in the number of chunks and its data
+fn split_count(user: &str, first: Vec<u8>) -> Result<(usize, Vec<u8>), KeyringStoreError> {
+    let corrupted = || KeyringStoreError::Corrupted(user.to_string());
+    let newline = first
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(corrupted)?;
+    let count = std::str::from_utf8(&first[..newline])
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .filter(|&count| count > 0)
+        .ok_or_else(corrupted)?;
+    Ok((count, first[newline + 1..].to_vec()))
+}
+
+/// A readable error, that tells what to do when there's no keyring or it's locked
+fn describe(error: Error, user: &str) -> KeyringStoreError {
+    match error {
+        Error::NoStorageAccess(_) => KeyringStoreError::Locked(error),
+        Error::PlatformFailure(_) => KeyringStoreError::Unavailable(error),
+        error => KeyringStoreError::Entry {
+            user: user.to_string(),
+            error,
+        },
+    }
+}
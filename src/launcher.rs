@@ -0,0 +1,74 @@
+use chromiumoxide::{Browser, BrowserConfig, Handler};
+use futures::{future::BoxFuture, FutureExt};
+
+/// Trait for browser launchers, which are used by the login to get a browser that speaks CDP.
+///
+/// The default one is [`ChromiumLauncher`], that launches a new Chrome/Chromium process.
+/// Implement this trait if you want to supply an already running browser (see [`RemoteLauncher`]),
+/// or a scripted fake in your tests, and pass it via [`LoginOptions::launcher`](crate::options::LoginOptions::launcher).
+///
+/// Unlike the [`BrowserLoginBot`](crate::login_bot::BrowserLoginBot) this trait needs to be object safe, so it returns a boxed future.
+///
+/// # Example
+/// ```
+/// use chromiumoxide::{Browser, BrowserConfig, Handler};
+/// use futures::{future::BoxFuture, FutureExt};
+/// use pinterest_login::launcher::BrowserLauncher;
+///
+/// struct MyLauncher;
+///
+/// impl BrowserLauncher for MyLauncher {
+///     fn launch(&self, config: BrowserConfig) -> BoxFuture<'_, pinterest_login::Result<(Browser, Handler)>> {
+///         async move {
+///             // Do whatever you want before launching the browser
+///             Ok(Browser::launch(config).await?)
+///         }
+///         .boxed()
+///     }
+/// }
+/// ```
+pub trait BrowserLauncher: Send + Sync {
+    /// Launches (or connects to) a browser with the given config, and returns it with its event handler
+    fn launch(&self, config: BrowserConfig) -> BoxFuture<'_, crate::Result<(Browser, Handler)>>;
+}
+
+/// The default browser launcher, that launches a new Chrome/Chromium process with the given config
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ChromiumLauncher;
+
+impl BrowserLauncher for ChromiumLauncher {
+    fn launch(&self, config: BrowserConfig) -> BoxFuture<'_, crate::Result<(Browser, Handler)>> {
+        async move { Ok(Browser::launch(config).await?) }.boxed()
+    }
+}
+
+/// A launcher that connects to an already running browser instead of launching a new one.
+///
+/// The browser config is ignored, since the browser is already running,
+/// and the browser isn't killed when the login is done.
+///
+/// # Example
+/// ```
+/// use pinterest_login::launcher::RemoteLauncher;
+/// use pinterest_login::options::LoginOptions;
+///
+/// // The http url of the remote debugging port, or the websocket url of the browser
+/// let options = LoginOptions::new().launcher(RemoteLauncher::new("http://127.0.0.1:9222"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RemoteLauncher {
+    url: String,
+}
+
+impl RemoteLauncher {
+    /// Creates a new remote launcher, that connects to the given url
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+impl BrowserLauncher for RemoteLauncher {
+    fn launch(&self, _config: BrowserConfig) -> BoxFuture<'_, crate::Result<(Browser, Handler)>> {
+        async move { Ok(Browser::connect(self.url.as_str()).await?) }.boxed()
+    }
+}
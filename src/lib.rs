@@ -23,7 +23,7 @@
 //!     let email = std::env::var("PINTEREST_EMAIL").unwrap();
 //!     let password = std::env::var("PINTEREST_PASSWORD").unwrap();
 //!
-//!     let bot = DefaultBrowserLoginBot::new(email.as_str(), password.as_str());
+//!     let bot = DefaultBrowserLoginBot::new(email, password);
 //!     let config_builder = DefaultBrowserConfigBuilder::default();
 //!
 //!     match login(&bot, &config_builder).await {
@@ -52,7 +52,7 @@
 //!     let email = std::env::var("PINTEREST_EMAIL").unwrap();
 //!     let password = std::env::var("PINTEREST_PASSWORD").unwrap();
 //!
-//!     let bot = DefaultBrowserLoginBot::new(email.as_str(), password.as_str());
+//!     let bot = DefaultBrowserLoginBot::new(email, password);
 //!
 //!    // Show the browser, and set the request timeout to 2 seconds
 //!     let config_builder = DefaultBrowserConfigBuilder::new(false, Duration::from_secs(2).into(), None);
@@ -81,7 +81,7 @@
 //!     let email = std::env::var("PINTEREST_EMAIL").unwrap();
 //!     let password = std::env::var("PINTEREST_PASSWORD").unwrap();
 //!
-//!    let bot = DefaultBrowserLoginBot::new(email.as_str(), password.as_str());
+//!    let bot = DefaultBrowserLoginBot::new(email, password);
 //!
 //!   // Show the browser, and set the request timeout to 2 seconds
 //!    let config_builder = DefaultBrowserConfigBuilder::new(false, Duration::from_secs(2).into(), None);
@@ -98,10 +98,63 @@
 //! }
 //! ```
 //!
+//! ## Dry run
+//! Runs the whole login against a bundled local imitation of the login page, without contacting pinterest or needing any credentials.
+//! It's a good smoke test for your own login bot or config builder, in CI for example.
+//! ```no_run
+//! use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+//! use pinterest_login::dry_run::dry_run;
+//! use pinterest_login::login_bot::DefaultBrowserLoginBot;
+//! use pinterest_login::options::LoginOptions;
+//!
+//! #[async_std::main]
+//! async fn main() {
+//!     let bot = DefaultBrowserLoginBot::new("any email", "any password");
+//!     let config_builder = DefaultBrowserConfigBuilder::default();
+//!
+//!     let outcome = dry_run(&bot, &config_builder, &LoginOptions::default())
+//!         .await
+//!         .expect("the dry run should succeed");
+//!     println!("{:?}", outcome.cookies);
+//! }
+//! ```
+//!
+//! ## With an API client
+//! `AuthSession` is the stable handoff to the [pinterest-rs](https://github.com/0x61nas/pinterest-rs) client, or any other HTTP client: the `Cookie` header, the CSRF token and the user agent of the login.
+//! ```no_run
+//! use pinterest_login::auth::AuthSession;
+//! use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+//! use pinterest_login::login;
+//! use pinterest_login::login_bot::DefaultBrowserLoginBot;
+//!
+//! #[async_std::main]
+//! async fn main() {
+//!     let bot = DefaultBrowserLoginBot::new("email", "password");
+//!     let cookies = login(&bot, &DefaultBrowserConfigBuilder::default()).await.unwrap();
+//!
+//!     let auth = AuthSession::from(&cookies);
+//!     for (name, value) in auth.headers() {
+//!         println!("{name}: {value}");
+//!     }
+//! }
+//! ```
+//!
+//! To call the internal API of pinterest directly, [`api::build_api_headers`] adds the `x-requested-with` header that it expects, and fails when the cookies have no CSRF token.
+//!
+//! # MSRV
+//! The minimum supported Rust version is **1.75**, since the [`BrowserLoginBot`](login_bot::BrowserLoginBot) trait uses native `async fn` in traits.
+//!
 //! # Features
 //! * `async-std-runtime`: Use the async-std runtime instead of tokio (enabled by default)
 //! * `tokio-runtime`: Use the tokio runtime instead of async-std
 //! * `debug`: Enable debug logging
+//! * `serde`: `Serialize` and `Deserialize` for the `PinterestCookie`s and the `Session`s, to persist a login and load it in another process
+//! * `cookie`: The `cookie_jar` module, to convert the cookies into the `Cookie`s and the `CookieJar` of the `cookie` crate
+//! * `reqwest-cookies`: The `reqwest_cookies` module, to send the cookies of a login with a reqwest client
+//! * `mock`: The `MockLogin` and `MockPage` test doubles, to test the code that logs in and the login bots without a browser
+//! * `test-util`: The `MockPage` and `ScriptedLoginBot` test doubles, the `ManualClock` and the CDP record and replay launchers, to test the login without a browser, an account or real waits
+//! * `webdriver`: The `webdriver` module and `login_with_engine`, to log in with the default bot in a browser of a WebDriver server on `http://`, like Firefox with geckodriver, instead of a Chrome/Chromium over CDP
+//! * `http-login`: **Experimental.** The `http_login` module, to log in with plain HTTP requests instead of a browser. It's lighter, but more likely to be blocked by pinterest
 //!
 //!
 //! # Contributing
@@ -119,23 +172,145 @@
 //! > This project is part of the [pinterest-rs](https://github.com/0x61nas/pinterest-rs) project
 //!
 #![deny(missing_docs, clippy::all)]
+// `CdpError` is big, but boxing it would break matching on `PinterestLoginError::CdpError`
+#![allow(clippy::result_large_err)]
 
+/// The source of time of the login
+pub mod clock;
 /// The chromiumoxide browser config builder
 pub mod config_builder;
 /// The pinterest login bot
 pub mod login_bot;
 
+/// The headers of the requests to the internal API of pinterest
+pub mod api;
+/// The session as the credentials of an API client
+pub mod auth;
+/// Telling the checkpoint pages that pinterest parks the login on, like the "check your email" one
+pub mod checkpoint;
+/// Importing the session of a local chrome profile
+#[cfg(feature = "chrome-import")]
+pub mod chrome_import;
+/// The conversions of the cookies into the ones of the `cookie` crate
+#[cfg(feature = "cookie")]
+pub mod cookie_jar;
+/// The cookies of a login with all their attributes
+pub mod cookies;
+/// Writing and reading the cookies in the Netscape cookie file format, the `cookies.txt` of curl and the downloaders
+pub mod cookies_txt;
+/// Getting the credentials from a password manager, instead of receiving the password directly
+pub mod credentials;
+/// Smoke testing the login bots and the config builders against a bundled fixture, without contacting pinterest
+pub mod dry_run;
+/// Encrypting the sessions at rest
+#[cfg(feature = "encryption")]
+pub mod encryption;
+/// Falling back to a headful login that a human finishes, when the headless one is challenged
+pub mod fallback;
+/// Varying how the browser presents itself between the login attempts
+pub mod fingerprint;
+/// The HTTP statuses and responses of the login errors
+#[cfg(feature = "http")]
+pub mod http;
+/// The experimental login over HTTP, without a browser
+#[cfg(feature = "http-login")]
+pub mod http_login;
+/// The random parts of the delays
+pub mod jitter;
+/// Keeping a session logged in from a background task
+pub mod keeper;
+/// Keeping the sessions in the keyring of the OS
+#[cfg(feature = "keyring")]
+pub mod keyring_store;
+/// The browser launchers
+pub mod launcher;
+/// The hooks on the changes of the sessions
+pub mod lifecycle;
+/// Managing the sessions of several accounts
+pub mod manager;
+/// Extra options for the login
+pub mod options;
+/// Telling the maintenance and the error pages of pinterest from its login page
+pub mod outage;
+/// The login page abstraction that the login bots work with
+pub mod page;
+/// The presets of the config builder and the options, for the fast, the stealthy and the debug logins
+pub mod preset;
+/// The logins as a trait, to test the code that logs in with a test double
+pub mod provider;
+/// The report of a successful login
+pub mod report;
+/// The cookies of the logins in a reqwest cookie store
+#[cfg(feature = "reqwest-cookies")]
+pub mod reqwest_cookies;
+/// The retry policies and the transient errors classification
+pub mod retry;
+/// The selectors of the login form fields, with their fallbacks
+pub mod selectors;
+/// The cookies of a login with their account and metadata, and the stores that keep them
+pub mod session;
+/// Exporting and importing the sessions in the `storage_state.json` format of Playwright
+pub mod storage_state;
+/// Storing the cookies on the disk
+pub mod store;
+/// Generating the two-factor authentication codes
+pub mod totp;
+/// Checking if saved cookies are still logged in
+pub mod verify;
+/// The login in the browsers of a WebDriver server, like Firefox with geckodriver
+#[cfg(feature = "webdriver")]
+pub mod webdriver;
+
+/// A fake login page to test the login bots, and a fake login to test the code that logs in, without a browser
+#[cfg(feature = "mock")]
+pub mod mock;
+/// Recording and replaying the CDP traffic, to test the whole login without a browser
+#[cfg(feature = "test-util")]
+pub mod replay;
+/// A login bot with a programmed outcome, to test the failure handling without an account
+#[cfg(feature = "test-util")]
+pub mod scripted;
+
+mod browser_guard;
+mod events;
+#[cfg(feature = "http-login")]
+mod http_runtime;
+mod metrics;
+mod ready;
+
 // #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
 // use async_std::prelude::StreamExt;
+use crate::browser_guard::{BrowserGuard, Disconnected, TaskRunner};
+use crate::clock::Clock;
 use crate::config_builder::BrowserConfigBuilder;
-use crate::login_bot::BrowserLoginBot;
-use chromiumoxide::{cdp::browser_protocol::target::CreateTargetParams, Browser};
-use futures::StreamExt;
+use crate::cookies::PinterestCookie;
+use crate::launcher::BrowserLauncher;
+use crate::login_bot::{is_login_url, BrowserLoginBot};
+use crate::options::{LoginOptions, Target};
+use crate::report::{LoginOutcome, LoginReport};
+use chromiumoxide::{
+    cdp::browser_protocol::{
+        emulation::SetDeviceMetricsOverrideParams,
+        network::{Cookie, CookieParam, GetCookiesParams, SetCookiesParams},
+        target::CreateTargetParams,
+    },
+    error::CdpError,
+    Page,
+};
 #[cfg(feature = "log")]
 use log::{debug, info, trace};
+use std::future::Future;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
 
 /// The pinterest login url
 pub const PINTEREST_LOGIN_URL: &str = "https://pinterest.com/login";
+/// The prefix of the temporary browser profiles, they are created in [`std::env::temp_dir`] and removed after the login
+pub const TEMP_PROFILE_PREFIX: &str = "pinterest-login-";
+/// The minimum supported Chrome/Chromium major version
+pub const MIN_CHROME_VERSION: u32 = 100;
 
 /// Pinterest login error type
 #[derive(Debug, thiserror::Error)]
@@ -150,14 +325,196 @@ pub enum PinterestLoginError {
     /// The login bot failed to fill or submit the login form, or the authentication is incorrect
     #[error("Authentication error: The email or password you entered is incorrect.")]
     AuthenticationError,
+    /// Pinterest asks for a captcha or a second factor to complete the login, and there's no way to answer it.
+    /// The default login bot returns it when it's asked for a two-factor code without a
+    /// [`TwoFactor`](login_bot::TwoFactor), it doesn't detect the captchas yet
+    #[error("Pinterest asks for a captcha or a second factor to complete the login")]
+    ChallengeRequired,
+    /// Pinterest asks to confirm the login with the link that it sent by email, and it wasn't clicked in time.
+    /// The default login bot waits for it as configured in
+    /// [`DefaultBrowserLoginBot::with_email_confirmation`](login_bot::DefaultBrowserLoginBot::with_email_confirmation)
+    #[error("Pinterest asks to confirm the login with the link that it sent by email")]
+    EmailConfirmationRequired,
+    /// Pinterest refuses the login attempts for a while because there were too many of them.
+    /// The default login bot doesn't detect it yet, it's for the custom login bots
+    #[error("Pinterest is rate limiting the login attempts, try again later")]
+    RateLimited,
+    /// The browser didn't start or didn't answer its first CDP request within the handshake timeout,
+    /// usually because the executable isn't actually a Chrome/Chromium browser
+    #[error("The browser `{executable}` didn't complete the CDP handshake within {timeout:?}, make sure it's a Chrome/Chromium executable")]
+    HandshakeTimeout {
+        /// The path of the browser executable, or `unknown`
+        executable: String,
+        /// The handshake timeout that was reached
+        timeout: std::time::Duration,
+    },
+    /// The browser speaks CDP, but it's not a supported Chrome/Chromium version
+    #[error("The browser `{executable}` ({version}) is not supported, the minimum supported Chrome version is {MIN_CHROME_VERSION}")]
+    IncompatibleBrowser {
+        /// The product name and version reported by the browser, e.g. `HeadlessChrome/90.0.4430.0`
+        version: String,
+        /// The path of the browser executable, or `unknown`
+        executable: String,
+    },
+    /// The connection with the browser was lost (e.g. it crashed or got killed) before the login completed
+    #[error("The browser disconnected during the {during} phase")]
+    BrowserDisconnected {
+        /// The login phase that was in progress
+        during: LoginPhase,
+    },
+    /// The login didn't complete within its overall deadline, see [`fallback::with_headful_fallback`]
+    #[error("The login didn't complete within {0:?}")]
+    DeadlineExceeded(std::time::Duration),
+    /// Pinterest serves a maintenance or an error page instead of the login page, see [`outage`].
+    /// It's transient, the navigation to the login page is retried as configured in
+    /// [`LoginOptions::navigation_retry`](options::LoginOptions::navigation_retry)
+    #[error("Pinterest is down or in maintenance, try again later")]
+    ServiceUnavailable {
+        /// When to try again, from the `Retry-After` header of the error page if it has one
+        retry_hint: Option<std::time::Duration>,
+    },
+    /// The requests of the login over HTTP of the `http-login` feature failed, e.g. pinterest can't be reached or its answer
+    /// isn't the expected one
+    #[error("The HTTP login failed: {0}")]
+    HttpLoginError(String),
+    /// The WebDriver server of the `webdriver` feature failed, e.g. it isn't running or it couldn't start the browser
+    #[error("WebDriver error: {0}")]
+    WebDriverError(String),
+    /// A required cookie of the [`CookieAllowlist`](options::CookieAllowlist) isn't in the browser after the login
+    #[error("The login didn't set the required cookie `{0}`")]
+    MissingCookie(String),
+    /// The [`CookieStore`](store::CookieStore) of [`refresh_stored_session`](verify::refresh_stored_session) failed
+    /// to load or save the cookies
+    #[error("The cookie store failed: {0}")]
+    StoreError(#[from] store::StoreError),
+}
+
+/// The class of a [`PinterestLoginError`], see [`PinterestLoginError::kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The email or password is incorrect, trying again won't help
+    Authentication,
+    /// Pinterest asks for a captcha or a second factor, a human has to step in
+    Challenge,
+    /// Pinterest refuses the login attempts for a while
+    RateLimited,
+    /// The browser failed to launch, isn't supported, or crashed
+    Browser,
+    /// A page or a CDP request didn't answer in time
+    Timeout,
+    /// The browser couldn't reach the login page (DNS, TLS, connection errors...)
+    Network,
+    /// Pinterest is down or in maintenance for a while
+    ServiceUnavailable,
+    /// The cookies couldn't be loaded or saved
+    Store,
+}
+
+impl ErrorKind {
+    /// The stable code of the kind, e.g. `rate_limited`, for the logs, the metrics and the APIs
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::ErrorKind;
+    ///
+    /// assert_eq!(ErrorKind::RateLimited.code(), "rate_limited");
+    /// ```
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Authentication => "authentication",
+            Self::Challenge => "challenge",
+            Self::RateLimited => "rate_limited",
+            Self::Browser => "browser",
+            Self::Timeout => "timeout",
+            Self::Network => "network",
+            Self::ServiceUnavailable => "service_unavailable",
+            Self::Store => "store",
+        }
+    }
+}
+
+impl PinterestLoginError {
+    /// The class of the error, to decide what to do about it without matching every variant.
+    ///
+    /// The browser launch timeouts are [`ErrorKind::Browser`] errors, since they usually mean that the executable
+    /// isn't a working Chrome/Chromium.
+    ///
+    /// # Example
+    /// ```
+    /// use chromiumoxide::error::CdpError;
+    /// use pinterest_login::{ErrorKind, PinterestLoginError};
+    ///
+    /// assert_eq!(PinterestLoginError::AuthenticationError.kind(), ErrorKind::Authentication);
+    /// assert_eq!(PinterestLoginError::CdpError(CdpError::Timeout).kind(), ErrorKind::Timeout);
+    ///
+    /// let dns = PinterestLoginError::CdpError(CdpError::ChromeMessage("net::ERR_NAME_NOT_RESOLVED".into()));
+    /// assert_eq!(dns.kind(), ErrorKind::Network);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::AuthenticationError | Self::MissingCookie(_) => ErrorKind::Authentication,
+            Self::ChallengeRequired | Self::EmailConfirmationRequired => ErrorKind::Challenge,
+            Self::RateLimited => ErrorKind::RateLimited,
+            Self::ServiceUnavailable { .. } => ErrorKind::ServiceUnavailable,
+            Self::CdpError(CdpError::Timeout) | Self::DeadlineExceeded(_) => ErrorKind::Timeout,
+            // The navigation errors are reported with the `errorText` of `Page.navigate`
+            Self::CdpError(CdpError::ChromeMessage(error_text))
+                if error_text.trim().starts_with("net::ERR_") =>
+            {
+                ErrorKind::Network
+            }
+            Self::HttpLoginError(_) => ErrorKind::Network,
+            Self::CdpError(_)
+            | Self::BrowserConfigBuildError(_)
+            | Self::HandshakeTimeout { .. }
+            | Self::IncompatibleBrowser { .. }
+            | Self::BrowserDisconnected { .. }
+            | Self::WebDriverError(_) => ErrorKind::Browser,
+            Self::StoreError(_) => ErrorKind::Store,
+        }
+    }
+}
+
+/// The phases of the login, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LoginPhase {
+    /// Launching the browser and doing the CDP handshake
+    Launch,
+    /// Opening and preparing the login page
+    Setup,
+    /// Navigating to the login page
+    Navigation,
+    /// Filling the login form, see [`BrowserLoginBot::fill_login_form`]
+    FillForm,
+    /// Submitting the login form, see [`BrowserLoginBot::submit_login_form`]
+    SubmitForm,
+    /// Checking if the login was successful, see [`BrowserLoginBot::check_login`]
+    CheckLogin,
+    /// Collecting the cookies
+    CollectCookies,
+}
+
+impl std::fmt::Display for LoginPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Launch => "launch",
+            Self::Setup => "setup",
+            Self::Navigation => "navigation",
+            Self::FillForm => "fill form",
+            Self::SubmitForm => "submit form",
+            Self::CheckLogin => "check login",
+            Self::CollectCookies => "collect cookies",
+        })
+    }
 }
 
 /// A type alias for `Result<T, PinterestLoginError>`
 pub type Result<T> = std::result::Result<T, PinterestLoginError>;
-/// Pinterest cookies map.
-pub type PinterestCookies = std::collections::HashMap<String, String>;
+pub use crate::cookies::PinterestCookies;
 
-/// Logs into Pinterest and returns the cookies as a HashMap
+/// Logs into Pinterest and returns the cookies, see [`PinterestCookies`]
 ///
 /// # Arguments
 /// * `login_bot` - The login bot to use to fill and submit the login form
@@ -165,12 +522,12 @@ pub type PinterestCookies = std::collections::HashMap<String, String>;
 ///
 /// # Example
 /// ```ignore
-/// # use std::collections::HashMap;
 /// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
 /// # use pinterest_login::login;
 /// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::PinterestCookies;
 ///
-/// async fn login_to_pinterest(email: &str, password: &str) -> pinterest_login::Result<HashMap<String, String>> {
+/// async fn login_to_pinterest(email: &str, password: &str) -> pinterest_login::Result<PinterestCookies> {
 ///     let browser_config_builder = DefaultBrowserConfigBuilder::default();
 ///     let bot = DefaultBrowserLoginBot::new(email, password);
 ///
@@ -183,81 +540,325 @@ pub type PinterestCookies = std::collections::HashMap<String, String>;
 /// * `CdpError` - If there is an error with chromiumoxide (like launching timeout, or request timeout, network error, etc.)  see [chromiumoxide::error::CdpError](https://docs.rs/chromiumoxide/latest/chromiumoxide/error/enum.CdpError.html) to see all the errors
 /// * `BrowserConfigBuildError` - If there is an error building the browser config
 /// * `AuthenticationError` - If the email or password is incorrect
+/// * `MissingCookie` - If a required cookie of the [`LoginOptions::cookie_allowlist`] isn't set after the login
+/// * `ChallengeRequired` and `RateLimited` - If the login bot detects a captcha or a rate limit
+/// * `EmailConfirmationRequired` - If pinterest waits for the link that it sent by email to be clicked
+/// * `ServiceUnavailable` - If pinterest serves a maintenance or an error page, even after the navigation retries
+/// * `HandshakeTimeout` - If the browser didn't start or didn't answer in time, see [`BrowserConfigBuilder::handshake_timeout`]
+/// * `IncompatibleBrowser` - If the browser is older than [`MIN_CHROME_VERSION`] or isn't Chrome/Chromium at all
+/// * `BrowserDisconnected` - If the browser crashed or got killed in the middle of the login
+///
+/// # Send
+/// The returned future is [`Send`] as long as the login bot and the config builder are [`Sync`],
+/// so you can spawn it on a multithreaded runtime. It borrows them though, so to spawn it you need to move
+/// owned values into the task (the [`DefaultBrowserLoginBot`](login_bot::DefaultBrowserLoginBot) owns its credentials for this reason).
+///
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// fn assert_send<T: Send>(t: T) -> T {
+///     t
+/// }
+///
+/// let bot = DefaultBrowserLoginBot::new("email", "password");
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+/// let _ = assert_send(login(&bot, &config_builder));
 ///
+/// // Spawning it on a multithreaded runtime
+/// let _ = assert_send(async move { login(&bot, &config_builder).await });
+/// ```
+///
+/// # Cancellation
+/// The future is cancellation safe, if it gets dropped before it completes (e.g. by a timeout)
+/// the event handler task is aborted and the browser process is killed right away.
 #[inline]
-pub async fn login(
-    login_bot: &dyn BrowserLoginBot,
-    config_builder: &dyn BrowserConfigBuilder,
-) -> Result<PinterestCookies> {
+pub async fn login<B, C>(login_bot: &B, config_builder: &C) -> Result<PinterestCookies>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    login_with_options(login_bot, config_builder, &LoginOptions::default())
+        .await
+        .map(|outcome| outcome.cookies)
+}
+
+/// Same as [`login`], but returns the cookies with all their attributes (domain, path, expiry, flags...) instead of
+/// just their names and values, e.g. to know when `_pinterest_sess` expires
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::login_full;
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let bot = DefaultBrowserLoginBot::new("email", "password");
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+///
+/// for cookie in login_full(&bot, &config_builder).await? {
+///     println!("{} on {} expires at {:?}", cookie.name, cookie.domain, cookie.expires);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// The same as [`login`]
+pub async fn login_full<B, C>(login_bot: &B, config_builder: &C) -> Result<Vec<PinterestCookie>>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    login_with_options(login_bot, config_builder, &LoginOptions::default())
+        .await
+        .map(|outcome| {
+            outcome
+                .cookie_details
+                .iter()
+                .map(PinterestCookie::from)
+                .collect()
+        })
+}
+
+/// Same as [`login`], but with extra [`LoginOptions`], and returns a [`LoginReport`] alongside the cookies
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::login_with_options;
+/// # use pinterest_login::options::LoginOptions;
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let bot = DefaultBrowserLoginBot::new("email", "password");
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+/// let options = LoginOptions::new().event_sink(|event| eprintln!("{event:?}"));
+///
+/// let outcome = login_with_options(&bot, &config_builder, &options).await?;
+/// println!("{} launch attempts", outcome.report.launch_attempts);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn login_with_options<B, C>(
+    login_bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> Result<LoginOutcome>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    #[cfg(feature = "webdriver")]
+    if let options::Engine::WebDriver(_) = options.engine {
+        return Err(PinterestLoginError::WebDriverError(
+            "the WebDriver engine needs a `PageLoginBot`, log in with `login_with_engine`"
+                .to_string(),
+        ));
+    }
+    let runner = TaskRunner::new(options.task_mode);
+    metrics::recorded(runner.drive(run_login(
+        login_bot,
+        config_builder,
+        options,
+        Vec::new(),
+        &runner,
+    )))
+    .await
+}
+
+/// Same as [`login_with_options`], but through the [`Engine`](options::Engine) of the options: a Chrome/Chromium
+/// over CDP by default, or the browser of a WebDriver server.
+///
+/// The WebDriver engine only works with the [`PageLoginBot`](login_bot::PageLoginBot)s, like the
+/// [`DefaultBrowserLoginBot`](login_bot::DefaultBrowserLoginBot), see [`webdriver`]. The config builder is only
+/// used by the CDP engine.
+#[cfg(feature = "webdriver")]
+pub async fn login_with_engine<B, C>(
+    login_bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> Result<LoginOutcome>
+where
+    B: login_bot::PageLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    match &options.engine {
+        options::Engine::WebDriver(config) => {
+            metrics::recorded(webdriver::login(login_bot, config, options)).await
+        }
+        options::Engine::Cdp => login_with_options(login_bot, config_builder, options).await,
+    }
+}
+
+/// Logs in, the `cookies` are set in the browser before the navigation to the login page, which skips the form if
+/// they're still logged in
+async fn run_login<B, C>(
+    login_bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+    cookies: Vec<CookieParam>,
+    runner: &TaskRunner,
+) -> Result<LoginOutcome>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
     #[cfg(feature = "log")]
     info!("Launching the browser");
 
-    let (mut browser, mut handler) =
-        Browser::launch(config_builder.build_browser_config()?).await?;
+    let (mut browser, launch_attempts) = metrics::timed(
+        LoginPhase::Launch,
+        launch_browser(config_builder, options, runner),
+    )
+    .await?;
+    let mut report = LoginReport {
+        launch_attempts,
+        ..Default::default()
+    };
 
-    #[cfg(feature = "log")]
-    info!(
-        "The browser has been launched\nBrowser version: {:?}",
-        browser.version().await?
-    );
+    let disconnected = browser.disconnected();
 
-    #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
-    let handle = async_std::task::spawn(async move {
-        loop {
-            let _event = handler.next().await;
+    let page = phase(&disconnected, LoginPhase::Setup, async {
+        if !options.no_incognito {
+            browser.start_incognito_context().await?;
+        }
+        let page = browser.new_page(CreateTargetParams::default()).await?;
+        page.disable_log().await?.disable_debugger().await?;
+        match &options.fingerprint {
+            Some(fingerprint) => {
+                #[cfg(feature = "log")]
+                debug!("Presenting the login page as: {fingerprint}");
+                page.enable_stealth_mode_with_agent(&fingerprint.user_agent)
+                    .await?;
+                let viewport = fingerprint.viewport;
+                page.execute(SetDeviceMetricsOverrideParams::new(
+                    viewport.width,
+                    viewport.height,
+                    1.0,
+                    false,
+                ))
+                .await?;
+            }
+            None => page.enable_stealth_mode().await?,
+        }
+        if !cookies.is_empty() {
+            #[cfg(feature = "log")]
+            debug!("Setting {} cookies before the navigation", cookies.len());
+            let cookies = verify::scoped_to(cookies, options.target.url());
+            page.execute(SetCookiesParams::new(cookies)).await?;
         }
-    });
 
-    #[cfg(feature = "tokio")]
-    let handle = tokio::spawn(async move {
-        loop {
-            let _event = handler.next().await;
+        if let Some(sink) = &options.event_sink {
+            #[cfg(feature = "log")]
+            debug!("Forwarding the page events to the event sink");
+            events::forward_events(&mut browser, &page, sink.clone()).await?;
         }
-    });
+        Ok(page)
+    })
+    .await?;
 
-    // TODO: make this configurable
-    browser.start_incognito_context().await?;
+    let keep_alive_pings = Arc::new(AtomicU32::new(0));
+    if let Some(interval) = options.keep_alive {
+        #[cfg(feature = "log")]
+        debug!("Pinging the browser every {interval:?} to keep the connection alive");
+        browser.keep_alive(
+            page.clone(),
+            interval,
+            options.get_clock(),
+            Arc::clone(&keep_alive_pings),
+        );
+    }
 
-    let page = browser.new_page(CreateTargetParams::default()).await?;
-    page.disable_log().await?.disable_debugger().await?;
-    page.enable_stealth_mode().await?;
+    // Everything that can fail on the login page, to screenshot it
+    let on_page = async {
+        let login_url = options.target.url();
+        (report.navigation_attempts, report.ready) =
+            phase(&disconnected, LoginPhase::Navigation, async {
+                let network = ready::NetworkWatch::start(&page, &options.ready_when).await?;
+                let attempts = navigate(&page, login_url, config_builder, options).await?;
+                let clock = options.get_clock();
+                let ready =
+                    ready::wait_until_ready(&page, &options.ready_when, network, &*clock).await?;
+                Ok((attempts, ready))
+            })
+            .await?;
 
-    #[cfg(feature = "log")]
-    info!("Navigating to the login page: {}", PINTEREST_LOGIN_URL);
-    page.goto(PINTEREST_LOGIN_URL)
-        .await?
-        .wait_for_navigation()
+        #[cfg(feature = "log")]
+        {
+            info!("The login page has been loaded");
+            trace!("The login page content: {}", page.content().await?);
+            debug!("The login page cookies: {:?}", page.get_cookies().await?);
+        }
+        let authenticated = guarded(
+            &disconnected,
+            LoginPhase::Navigation,
+            already_authenticated(&page, &options.target),
+        )
         .await?;
+        if let Some(url) = authenticated {
+            #[cfg(feature = "log")]
+            info!("The browser is already logged in ({url}), skipping the login form");
+            report.already_authenticated = true;
+            options.notify_authenticated(&url);
+        } else {
+            #[cfg(feature = "log")]
+            info!("Filling the login form");
+            // Fill the login form
+            phase(
+                &disconnected,
+                LoginPhase::FillForm,
+                login_bot.fill_login_form(&page),
+            )
+            .await?;
+            #[cfg(feature = "log")]
+            info!("Submitting the login form");
+            // Click the login button
+            phase(
+                &disconnected,
+                LoginPhase::SubmitForm,
+                login_bot.submit_login_form(&page),
+            )
+            .await?;
 
-    #[cfg(feature = "log")]
-    {
-        info!("The login page has been loaded");
-        trace!("The login page content: {}", page.content().await?);
-        debug!("The login page cookies: {:?}", page.get_cookies().await?);
-        info!("Filling the login form");
-    }
-    // Fill the login form
-    login_bot.fill_login_form(&page).await?;
-    #[cfg(feature = "log")]
-    info!("Submitting the login form");
-    // Click the login button
-    login_bot.submit_login_form(&page).await?;
+            #[cfg(feature = "log")]
+            info!("The login form has been submitted");
+            report.selectors = login_bot.matched_selectors();
+        }
 
-    #[cfg(feature = "log")]
-    {
-        info!("The login form has been submitted");
+        #[cfg(feature = "log")]
         info!("Waiting for the login to complete, and checking if the login was successful");
-    }
-    // Check if the login was successful
-    login_bot.check_login(&page).await?;
+        // Check if the login was successful
+        phase(
+            &disconnected,
+            LoginPhase::CheckLogin,
+            login_bot.check_login(&page),
+        )
+        .await?;
 
-    const MAP_CAPACITY: usize = 7;
-    let mut cookies = PinterestCookies::with_capacity(MAP_CAPACITY);
+        #[cfg(feature = "log")]
+        info!("The login was successful, getting the cookies");
+        // Get the cookies, and the storages if they're asked for
+        phase(&disconnected, LoginPhase::CollectCookies, async {
+            let storage = match options.capture_storage {
+                true => Some(capture_storage(&page).await?),
+                false => None,
+            };
+            Ok((collect_cookies(&page, options).await?, storage))
+        })
+        .await
+    };
+    let (c, storage) = match on_page.await {
+        Ok(collected) => collected,
+        Err(e) => {
+            if let Some(dir) = &options.screenshot_dir {
+                let screenshot = save_screenshot(&page, dir).await;
+                options.notify_screenshot(screenshot.as_deref());
+            }
+            return Err(e);
+        }
+    };
 
-    #[cfg(feature = "log")]
-    info!("The login was successful, getting the cookies");
-    // Get the cookies
-    let c = page.get_cookies().await?;
+    let c = options.filter_cookies(c)?;
+    let mut cookies = PinterestCookies::with_capacity(c.len());
 
     #[cfg(feature = "log")]
     {
@@ -267,36 +868,396 @@ pub async fn login(
     }
 
     #[cfg(feature = "log")]
-    info!("Collecting the cookies values and names into a HashMap");
-    for cookie in c {
+    info!("Collecting the cookies values and names");
+    for cookie in &c {
         #[cfg(feature = "log")]
         trace!("Inserting the cookie: {} : {}", cookie.name, cookie.value);
 
-        cookies.insert(cookie.name, cookie.value);
+        cookies.insert(cookie.name.clone(), cookie.value.clone());
     }
 
-    #[cfg(feature = "log")]
-    info!("Canceling the event handler");
-    #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
-    // Cancel the event handler
-    handle.cancel().await;
-    #[cfg(feature = "tokio")]
-    // Cancel the event handler
-    handle.abort();
-
-    // #[cfg(feature = "log")]
-    // info!("Closing the browser");
-    // Close the browser
-    // browser.close().await?;
+    // Cancel the event handler and kill the browser
+    drop(browser);
+    report.keep_alive_pings = keep_alive_pings.load(Ordering::Relaxed);
 
     #[cfg(feature = "log")]
     trace!("The cookies: {cookies:?}");
 
-    debug_assert_eq!(cookies.capacity(), MAP_CAPACITY);
+    Ok(LoginOutcome {
+        cookies,
+        cookie_details: c,
+        report,
+        storage,
+    })
+}
+
+/// The url that the browser landed on if it's already logged in after the navigation to the login page, see
+/// [`LoginOptions::on_already_authenticated`].
+///
+/// Pinterest sends the logged in browsers away from the login page, so it's logged in when it ended up on another page
+/// of the same host.
+async fn already_authenticated(page: &Page, target: &Target) -> Result<Option<String>> {
+    let Some(url) = page.url().await? else {
+        return Ok(None);
+    };
+    let host = verify::login_host(target);
+    let redirected = url::Url::parse(&url).is_ok_and(|landed| host.matches(&landed))
+        && !is_login_url(&url, &host);
+    Ok(redirected.then_some(url))
+}
 
+/// The cookies of the [`LoginOptions::cookie_urls`] and of the page, once each
+async fn collect_cookies(page: &Page, options: &LoginOptions) -> Result<Vec<Cookie>> {
+    let urls = options.get_cookie_urls(page.url().await?);
+    #[cfg(feature = "log")]
+    debug!("Collecting the cookies of: {urls:?}");
+    let mut cookies = page
+        .execute(GetCookiesParams::builder().urls(urls).build())
+        .await?
+        .result
+        .cookies;
+    // A cookie can be sent to several of the urls
+    let mut seen = std::collections::HashSet::new();
+    cookies.retain(|cookie| {
+        seen.insert((
+            cookie.name.clone(),
+            cookie.domain.clone(),
+            cookie.path.clone(),
+        ))
+    });
     Ok(cookies)
 }
 
+/// Reads the `localStorage` and the `sessionStorage` of the page, see [`LoginOptions::capture_storage`]
+async fn capture_storage(page: &Page) -> Result<report::WebStorage> {
+    // A page without an origin, like `about:blank`, throws on the access to its storages
+    const DUMP_STORAGE: &str = r#"(() => {
+        const dump = (name) => {
+            try {
+                const storage = window[name];
+                return Object.fromEntries(
+                    Array.from({ length: storage.length }, (_, i) => storage.key(i))
+                        .map((key) => [key, storage.getItem(key)])
+                );
+            } catch (e) {
+                return {};
+            }
+        };
+        return { origin: location.origin, local: dump("localStorage"), session: dump("sessionStorage") };
+    })()"#;
+
+    let dump: serde_json::Value = page
+        .evaluate(DUMP_STORAGE)
+        .await?
+        .into_value()
+        .map_err(CdpError::from)?;
+    let items = |name: &str| {
+        dump[name]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+            .collect()
+    };
+    let storage = report::WebStorage {
+        origin: dump["origin"].as_str().unwrap_or_default().to_string(),
+        local: items("local"),
+        session: items("session"),
+    };
+    #[cfg(feature = "log")]
+    debug!(
+        "The storages of {}: {} local and {} session items",
+        storage.origin,
+        storage.local.len(),
+        storage.session.len()
+    );
+    Ok(storage)
+}
+
+/// Saves a full page screenshot of the page into the directory, see [`LoginOptions::screenshot_on_failure`]
+async fn save_screenshot(page: &Page, dir: &std::path::Path) -> Result<std::path::PathBuf> {
+    use chromiumoxide::page::ScreenshotParams;
+
+    #[cfg(feature = "log")]
+    info!(
+        "Saving a screenshot of the failed login into {}",
+        dir.display()
+    );
+    let png = page
+        .screenshot(ScreenshotParams::builder().full_page(true).build())
+        .await?;
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = dir.join(format!("login-failure-{millis}.png"));
+    std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&path, png))
+        .map_err(CdpError::from)?;
+    Ok(path)
+}
+
+/// Navigates to the login page, retrying the transient network errors as configured in the options.
+///
+/// Returns the number of the navigation attempts.
+async fn navigate<C>(
+    page: &Page,
+    url: &str,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> Result<u32>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let behind_proxy = config_builder.proxy().is_some();
+    let mut attempt = 1;
+    loop {
+        #[cfg(feature = "log")]
+        info!("Navigating to the login page: {}", url);
+        // Navigating again reloads the page after a failed attempt
+        let res = match page.goto(url).await {
+            Ok(page) => page.wait_for_navigation_response().await,
+            Err(e) => Err(e),
+        };
+        let res = match res {
+            // The request of the frame is the last one that finished, it's the document if it has its url
+            Ok(request) => match page.url().await {
+                Ok(document) => outage::check_document(request.as_deref(), document.as_deref()),
+                Err(e) => Err(e.into()),
+            },
+            Err(e) => Err(e.into()),
+        };
+        match res {
+            Ok(_) => return Ok(attempt),
+            Err(e)
+                if attempt <= options.navigation_retry.retries
+                    && retry::is_transient_navigation_error(&e, behind_proxy) =>
+            {
+                #[cfg(feature = "log")]
+                info!(
+                    "The navigation attempt {attempt} failed with a transient error, retrying: {e}"
+                );
+                options.notify_retry(retry::RetryNotice {
+                    phase: LoginPhase::Navigation,
+                    attempt,
+                    max_attempts: options.navigation_retry.retries + 1,
+                    delay: options.navigation_retry.delay,
+                    error: &e,
+                });
+                options
+                    .get_clock()
+                    .sleep(options.navigation_retry.delay)
+                    .await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Launches the browser, retrying the transient launch failures as configured in the options.
+///
+/// Returns the browser with the number of the launch attempts.
+async fn launch_browser<C>(
+    config_builder: &C,
+    options: &LoginOptions,
+    runner: &TaskRunner,
+) -> Result<(BrowserGuard, u32)>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let config = config_builder.build_browser_config()?;
+    let launcher = options.get_launcher();
+    let mut attempt = 1;
+    loop {
+        match launch_browser_once(
+            config.clone(),
+            config_builder,
+            launcher,
+            &*options.get_clock(),
+            runner,
+        )
+        .await
+        {
+            Ok(browser) => return Ok((browser, attempt)),
+            Err(e)
+                if attempt <= options.launch_retry.retries
+                    && retry::is_transient_launch_error(&e) =>
+            {
+                #[cfg(feature = "log")]
+                info!("The browser launch attempt {attempt} failed with a transient error, retrying: {e}");
+                options.notify_retry(retry::RetryNotice {
+                    phase: LoginPhase::Launch,
+                    attempt,
+                    max_attempts: options.launch_retry.retries + 1,
+                    delay: options.launch_retry.delay,
+                    error: &e,
+                });
+                // chromiumoxide already killed the half started process, and its temporary profile is gone with it
+                options.get_clock().sleep(options.launch_retry.delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Launches the browser and checks that it speaks CDP and is supported, within the handshake timeout
+async fn launch_browser_once<C>(
+    mut config: chromiumoxide::BrowserConfig,
+    config_builder: &C,
+    launcher: &dyn BrowserLauncher,
+    clock: &dyn Clock,
+    runner: &TaskRunner,
+) -> Result<BrowserGuard>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let handshake_timeout = config_builder.handshake_timeout();
+    let executable = || {
+        config_builder
+            .executable()
+            .map_or_else(|| "unknown".to_string(), |e| e.display().to_string())
+    };
+    let timed_out = || PinterestLoginError::HandshakeTimeout {
+        executable: executable(),
+        timeout: handshake_timeout,
+    };
+
+    // Unless the config has its own profile, every launch gets a fresh one, so the concurrent logins don't share
+    // (and lock) the default chromiumoxide profile. The debugging port is already an ephemeral one unless configured.
+    let profile = match config.user_data_dir {
+        Some(_) => None,
+        None => {
+            let profile = tempfile::Builder::new()
+                .prefix(TEMP_PROFILE_PREFIX)
+                .tempdir()
+                .map_err(CdpError::from)?;
+            config.user_data_dir = Some(profile.path().to_path_buf());
+            Some(profile)
+        }
+    };
+
+    let handshake = async {
+        let (browser, handler) = launcher.launch(config).await?;
+        // From now on, the browser gets killed if this future is dropped before it completes
+        let mut browser = BrowserGuard::new(browser, handler, runner.clone());
+        if let Some(profile) = profile {
+            browser.own_profile(profile);
+        }
+        let version = guarded(&browser.disconnected(), LoginPhase::Launch, async {
+            Ok(browser.version().await?)
+        })
+        .await?;
+        Ok::<_, PinterestLoginError>((browser, version))
+    };
+    let (browser, version) = match timeout(clock, handshake_timeout, handshake).await {
+        Some(Ok(r)) => r,
+        Some(Err(PinterestLoginError::CdpError(CdpError::LaunchTimeout(_)))) | None => {
+            return Err(timed_out())
+        }
+        Some(Err(e)) => return Err(e),
+    };
+
+    #[cfg(feature = "log")]
+    info!(
+        "The browser has been launched\nBrowser version: {:?}",
+        version
+    );
+
+    match chrome_major_version(&version.product) {
+        Some(major) if major >= MIN_CHROME_VERSION => Ok(browser),
+        _ => Err(PinterestLoginError::IncompatibleBrowser {
+            version: version.product,
+            executable: executable(),
+        }),
+    }
+}
+
+/// Extracts the major version from a CDP product string like `HeadlessChrome/120.0.6099.109`
+fn chrome_major_version(product: &str) -> Option<u32> {
+    let (name, version) = product.split_once('/')?;
+    if !name.contains("Chrom") {
+        return None;
+    }
+    version.split('.').next()?.parse().ok()
+}
+
+/// Runs the login phase, failing fast with [`PinterestLoginError::BrowserDisconnected`] if the connection
+/// with the browser gets lost before it completes.
+///
+/// The in-flight CDP requests of a dead browser never get a response, they only fail on the request timeout (if any),
+/// so the phase is raced against the disconnection instead of checking it between the phases.
+async fn guarded<T, F>(disconnected: &Disconnected, during: LoginPhase, phase: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    use futures::future::{select, Either};
+    match select(disconnected.clone(), std::pin::pin!(phase)).await {
+        Either::Left(_) => Err(PinterestLoginError::BrowserDisconnected { during }),
+        // The requests may fail because of the disconnection, report it instead of their errors
+        Either::Right((Err(_), _)) if disconnected.peek().is_some() => {
+            Err(PinterestLoginError::BrowserDisconnected { during })
+        }
+        Either::Right((res, _)) => res,
+    }
+}
+
+/// Runs the login phase like [`guarded`], recording its duration in the metrics
+fn phase<'a, T, F>(
+    disconnected: &'a Disconnected,
+    during: LoginPhase,
+    phase: F,
+) -> impl Future<Output = Result<T>> + 'a
+where
+    T: 'a,
+    F: Future<Output = Result<T>> + 'a,
+{
+    metrics::timed(during, guarded(disconnected, during, phase))
+}
+
+/// Runs the future to completion, or returns `None` if it doesn't complete within the duration
+pub(crate) async fn timeout<F: Future>(
+    clock: &dyn Clock,
+    duration: std::time::Duration,
+    future: F,
+) -> Option<F::Output> {
+    use futures::future::{select, Either};
+    match select(std::pin::pin!(future), clock.sleep(duration)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+pub(crate) fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+    async_std::task::spawn(future);
+    #[cfg(feature = "tokio")]
+    tokio::spawn(future);
+}
+
+/// Spawns the future on the current thread, see [`TaskMode::Local`](options::TaskMode::Local)
+#[cfg(feature = "tokio")]
+pub(crate) fn spawn_local<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    tokio::task::spawn_local(future);
+}
+
+/// The executor of async-std is a global one, that doesn't need the caller to run on it, so the future is spawned
+/// on it like with [`spawn`]
+#[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+pub(crate) fn spawn_local<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    async_std::task::spawn(future);
+}
+
 pub(crate) async fn sleep(duration: std::time::Duration) {
     #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
     async_std::task::sleep(duration).await;
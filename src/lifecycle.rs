@@ -0,0 +1,216 @@
+use std::{panic::AssertUnwindSafe, sync::Arc};
+
+use futures::{
+    channel::mpsc,
+    future::{self, BoxFuture},
+    FutureExt,
+};
+#[cfg(feature = "log")]
+use log::warn;
+
+use crate::{session::Session, PinterestLoginError};
+
+/// The hooks that are called when the session of an account changes, given to
+/// [`KeeperPolicy::events`](crate::keeper::KeeperPolicy::events) and
+/// [`SessionManager::events`](crate::manager::SessionManager::events).
+///
+/// Every method does nothing by default. The hooks are awaited in the task of the keeper or the manager, so the slow
+/// ones should hand their work to another task. A hook that panics is ignored, it doesn't stop the keeper.
+///
+/// When an expired session is renewed, the hooks are called in this order: [`on_expired`](Self::on_expired),
+/// [`on_login`](Self::on_login) and [`on_refreshed`](Self::on_refreshed), or [`on_error`](Self::on_error) instead of
+/// the last two if the login fails.
+///
+/// # Example
+/// ```
+/// use futures::future::BoxFuture;
+/// use pinterest_login::lifecycle::SessionEvents;
+/// use pinterest_login::session::Session;
+///
+/// struct Audit;
+///
+/// impl SessionEvents for Audit {
+///     fn on_login<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, ()> {
+///         Box::pin(async move { eprintln!("{} logged in", session.account) })
+///     }
+/// }
+/// ```
+pub trait SessionEvents: Send + Sync {
+    /// A login succeeded, and its session was stored
+    fn on_login<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, ()> {
+        let _ = session;
+        future::ready(()).boxed()
+    }
+
+    /// The session of the account was replaced by a newer one, from a login or from the store
+    fn on_refreshed<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, ()> {
+        let _ = session;
+        future::ready(()).boxed()
+    }
+
+    /// The session of the account expired, or isn't logged in anymore, it's renewed right after
+    fn on_expired<'a>(&'a self, account: &'a str) -> BoxFuture<'a, ()> {
+        let _ = account;
+        future::ready(()).boxed()
+    }
+
+    /// A login of the account failed
+    fn on_error<'a>(
+        &'a self,
+        account: &'a str,
+        error: &'a PinterestLoginError,
+    ) -> BoxFuture<'a, ()> {
+        let _ = (account, error);
+        future::ready(()).boxed()
+    }
+}
+
+impl<T: SessionEvents + ?Sized> SessionEvents for Arc<T> {
+    fn on_login<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, ()> {
+        (**self).on_login(session)
+    }
+
+    fn on_refreshed<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, ()> {
+        (**self).on_refreshed(session)
+    }
+
+    fn on_expired<'a>(&'a self, account: &'a str) -> BoxFuture<'a, ()> {
+        (**self).on_expired(account)
+    }
+
+    fn on_error<'a>(
+        &'a self,
+        account: &'a str,
+        error: &'a PinterestLoginError,
+    ) -> BoxFuture<'a, ()> {
+        (**self).on_error(account, error)
+    }
+}
+
+/// A call of the [`SessionEvents`], as sent by the [`ChannelEvents`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SessionEvent {
+    /// See [`SessionEvents::on_login`]
+    Login {
+        /// The new session
+        session: Session,
+    },
+    /// See [`SessionEvents::on_refreshed`]
+    Refreshed {
+        /// The new session
+        session: Session,
+    },
+    /// See [`SessionEvents::on_expired`]
+    Expired {
+        /// The account
+        account: String,
+    },
+    /// See [`SessionEvents::on_error`]
+    Error {
+        /// The account
+        account: String,
+        /// The message of the error
+        message: String,
+    },
+}
+
+/// The [`SessionEvents`] as a stream: every call is sent as a [`SessionEvent`] to the receiver of
+/// [`ChannelEvents::new`]. The events are dropped once the receiver is dropped.
+///
+/// # Example
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use futures::StreamExt;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::keeper::{KeeperPolicy, SessionKeeper};
+/// # use pinterest_login::lifecycle::{ChannelEvents, SessionEvent};
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::session::FileSessionStore;
+/// # async fn run() -> Result<(), pinterest_login::session::SessionError> {
+/// let (events, mut receiver) = ChannelEvents::new();
+/// let keeper = SessionKeeper::spawn(
+///     Arc::new(FileSessionStore::new("sessions")),
+///     "me@example.com",
+///     || DefaultBrowserLoginBot::new("me@example.com", "password"),
+///     DefaultBrowserConfigBuilder::default(),
+///     KeeperPolicy::new().events(events),
+/// )
+/// .await?;
+///
+/// while let Some(event) = receiver.next().await {
+///     if let SessionEvent::Error { account, message, .. } = event {
+///         eprintln!("{account} can't log in: {message}");
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChannelEvents {
+    sender: mpsc::UnboundedSender<SessionEvent>,
+}
+
+impl ChannelEvents {
+    /// The events, and the receiver of what they're told
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<SessionEvent>) {
+        let (sender, receiver) = mpsc::unbounded();
+        (Self { sender }, receiver)
+    }
+
+    fn send(&self, event: SessionEvent) -> BoxFuture<'static, ()> {
+        // Nobody is listening anymore when it fails
+        let _ = self.sender.unbounded_send(event);
+        future::ready(()).boxed()
+    }
+}
+
+impl SessionEvents for ChannelEvents {
+    fn on_login<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, ()> {
+        self.send(SessionEvent::Login {
+            session: session.clone(),
+        })
+    }
+
+    fn on_refreshed<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, ()> {
+        self.send(SessionEvent::Refreshed {
+            session: session.clone(),
+        })
+    }
+
+    fn on_expired<'a>(&'a self, account: &'a str) -> BoxFuture<'a, ()> {
+        self.send(SessionEvent::Expired {
+            account: account.to_string(),
+        })
+    }
+
+    fn on_error<'a>(
+        &'a self,
+        account: &'a str,
+        error: &'a PinterestLoginError,
+    ) -> BoxFuture<'a, ()> {
+        self.send(SessionEvent::Error {
+            account: account.to_string(),
+            message: error.to_string(),
+        })
+    }
+}
+
+/// Calls a hook of the events if there are any, a panic of the hook is caught so it doesn't reach the caller
+pub(crate) async fn emit<'a>(
+    events: Option<&'a dyn SessionEvents>,
+    hook: impl FnOnce(&'a dyn SessionEvents) -> BoxFuture<'a, ()>,
+) {
+    let Some(events) = events else {
+        return;
+    };
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    let panicked = match std::panic::catch_unwind(AssertUnwindSafe(|| hook(events))) {
+        Ok(call) => AssertUnwindSafe(call).catch_unwind().await.is_err(),
+        Err(_) => true,
+    };
+    #[cfg(feature = "log")]
+    if panicked {
+        warn!("A session events hook panicked, it's ignored");
+    }
+}
@@ -1,44 +1,139 @@
-use std::time::Duration;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use chromiumoxide::{layout::BoundingBox, Element, Page};
+use chromiumoxide::{error::CdpError, layout::BoundingBox, Page};
 #[cfg(feature = "log")]
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 
-use crate::{sleep, PinterestLoginError};
+use crate::{
+    checkpoint::is_email_confirmation_page,
+    clock::{Clock, SystemClock},
+    fingerprint::TypingTimings,
+    outage,
+    page::{LoginElement, LoginPage},
+    selectors::{FormField, LoginSelectors, SelectorMatch},
+    totp::TotpSecret,
+    PinterestLoginError,
+};
 
 /// Trait for login bots, which are used to fill and submit the login form in the browser
 ///
+/// You don't need the `async_trait` macro to implement it, just write the methods as plain `async fn`s.
+/// The returned futures must be [`Send`], so the [`login`](crate::login) future can be spawned on a multithreaded runtime,
+/// which means that you can't hold non-`Send` values (like an `Rc`) across an `.await` in your implementation.
+///
+/// Note that this makes the trait not object safe, the [`login`](crate::login) function takes the bot as a generic instead.
+///
 /// # Example
-/// ```ignore
+/// ```no_run
 /// use chromiumoxide::Page;
 /// # use pinterest_login::login_bot::BrowserLoginBot;
 /// # use pinterest_login::Result;
 ///
 /// struct MyLoginBot;
 ///
-/// #[async_trait::async_trait]
 /// impl BrowserLoginBot for MyLoginBot {
 ///    async fn fill_login_form(&self, page: &Page) -> Result<()> {
 ///        // ...
+///        # Ok(())
 ///    }
 ///
 ///    async fn submit_login_form(&self, page: &Page) -> Result<()> {
 ///        // ...
+///        # Ok(())
 ///    }
 ///
 ///    async fn check_login(&self, page: &Page) -> Result<()> {
 ///        // ...
+///        # Ok(())
 ///    }
 /// }
 /// ```
-#[async_trait::async_trait]
 pub trait BrowserLoginBot {
     /// Fills the login form fields with the required data
-    async fn fill_login_form(&self, page: &Page) -> crate::Result<()>;
+    fn fill_login_form(&self, page: &Page) -> impl Future<Output = crate::Result<()>> + Send;
+    /// Submits the login form
+    fn submit_login_form(&self, page: &Page) -> impl Future<Output = crate::Result<()>> + Send;
+    /// Checks if the login was successful
+    fn check_login(&self, page: &Page) -> impl Future<Output = crate::Result<()>> + Send;
+
+    /// The selectors that found the fields of the login form during the last login, for the
+    /// [`LoginReport`](crate::report::LoginReport). The bots without selector chains don't report any.
+    fn matched_selectors(&self) -> Vec<SelectorMatch> {
+        Vec::new()
+    }
+}
+
+/// Same as [`BrowserLoginBot`], but written against the [`LoginPage`] abstraction instead of the chromiumoxide [`Page`],
+/// so the bot logic can be tested without a browser (see the `test-util` feature).
+///
+/// Every `PageLoginBot` is a [`BrowserLoginBot`], so it can be passed to [`login`](crate::login) as is.
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::login_bot::PageLoginBot;
+/// use pinterest_login::page::{LoginElement, LoginPage};
+/// # use pinterest_login::Result;
+///
+/// struct MyLoginBot;
+///
+/// impl PageLoginBot for MyLoginBot {
+///     async fn fill_login_form<P: LoginPage>(&self, page: &P) -> Result<()> {
+///         page.find_element("input#email").await?.type_str("email").await
+///     }
+///
+///     async fn submit_login_form<P: LoginPage>(&self, page: &P) -> Result<()> {
+///         // ...
+///         # Ok(())
+///     }
+///
+///     async fn check_login<P: LoginPage>(&self, page: &P) -> Result<()> {
+///         // ...
+///         # Ok(())
+///     }
+/// }
+/// ```
+pub trait PageLoginBot {
+    /// Fills the login form fields with the required data
+    fn fill_login_form<P: LoginPage>(
+        &self,
+        page: &P,
+    ) -> impl Future<Output = crate::Result<()>> + Send;
     /// Submits the login form
-    async fn submit_login_form(&self, page: &Page) -> crate::Result<()>;
+    fn submit_login_form<P: LoginPage>(
+        &self,
+        page: &P,
+    ) -> impl Future<Output = crate::Result<()>> + Send;
     /// Checks if the login was successful
-    async fn check_login(&self, page: &Page) -> crate::Result<()>;
+    fn check_login<P: LoginPage>(&self, page: &P)
+        -> impl Future<Output = crate::Result<()>> + Send;
+
+    /// The selectors that found the fields of the login form during the last login, see
+    /// [`BrowserLoginBot::matched_selectors`]
+    fn matched_selectors(&self) -> Vec<SelectorMatch> {
+        Vec::new()
+    }
+}
+
+impl<T: PageLoginBot + ?Sized> BrowserLoginBot for T {
+    fn fill_login_form(&self, page: &Page) -> impl Future<Output = crate::Result<()>> + Send {
+        PageLoginBot::fill_login_form(self, page)
+    }
+
+    fn submit_login_form(&self, page: &Page) -> impl Future<Output = crate::Result<()>> + Send {
+        PageLoginBot::submit_login_form(self, page)
+    }
+
+    fn check_login(&self, page: &Page) -> impl Future<Output = crate::Result<()>> + Send {
+        PageLoginBot::check_login(self, page)
+    }
+
+    fn matched_selectors(&self) -> Vec<SelectorMatch> {
+        PageLoginBot::matched_selectors(self)
+    }
 }
 
 /// The default login bot, that provides methods to fill and submit the login form in the browser
@@ -55,31 +150,545 @@ pub trait BrowserLoginBot {
 ///
 /// U don't need to use the login bot directly, it is used by the login function,
 /// you just send it to the login function and it will use it to fill and submit the login form
-pub struct DefaultBrowserLoginBot<'a> {
-    email: &'a str,
-    password: &'a str,
+///
+/// The bot owns its credentials, so it can be moved into a spawned task.
+pub struct DefaultBrowserLoginBot {
+    email: String,
+    password: String,
+    login_host: Host,
+    clock: Arc<dyn Clock>,
+    two_factor: Option<TwoFactor>,
+    manual_assist: Option<Duration>,
+    selectors: LoginSelectors,
+    typing: Option<TypingTimings>,
+    email_confirmation: Option<EmailConfirmationPolling>,
+    matched: Mutex<Vec<SelectorMatch>>,
+}
+
+/// How the bot waits for the link of the "check your email" page, see
+/// [`DefaultBrowserLoginBot::with_email_confirmation`]
+#[derive(Debug, Clone, Copy)]
+struct EmailConfirmationPolling {
+    interval: Duration,
+    timeout: Duration,
+}
+
+/// A callback that returns a two-factor authentication code, see [`TwoFactor::Callback`]
+pub type TwoFactorCallback = Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
+/// Where the [`DefaultBrowserLoginBot`] gets the two-factor authentication codes from,
+/// see [`DefaultBrowserLoginBot::with_two_factor`]
+///
+/// # Example
+/// ```
+/// use pinterest_login::login_bot::{DefaultBrowserLoginBot, TwoFactor};
+///
+/// // Unattended, with the secret of the authenticator app
+/// let bot = DefaultBrowserLoginBot::new("email", "password")
+///     .with_two_factor(TwoFactor::Totp("JBSWY3DPEHPK3PXP".parse()?));
+///
+/// // Or asking someone for the code
+/// let bot = DefaultBrowserLoginBot::new("email", "password").with_two_factor(TwoFactor::callback(|| {
+///     let mut code = String::new();
+///     std::io::stdin().read_line(&mut code).ok()?;
+///     Some(code.trim().to_string())
+/// }));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum TwoFactor {
+    /// Generates the codes from the secret of the authenticator app
+    Totp(TotpSecret),
+    /// Asks the callback for the code, e.g. to prompt the user.
+    ///
+    /// It's called from the login future, so it blocks it until it returns.
+    /// Return `None` if there's no way to get the code, the login then fails with
+    /// [`PinterestLoginError::ChallengeRequired`].
+    Callback(TwoFactorCallback),
+}
+
+impl TwoFactor {
+    /// A [`TwoFactor::Callback`]
+    pub fn callback(f: impl Fn() -> Option<String> + Send + Sync + 'static) -> Self {
+        Self::Callback(Arc::new(f))
+    }
+
+    fn code(&self) -> Option<String> {
+        match self {
+            Self::Totp(secret) => Some(secret.code()),
+            Self::Callback(f) => f(),
+        }
+    }
+}
+
+impl std::fmt::Debug for TwoFactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Totp(secret) => f.debug_tuple("Totp").field(secret).finish(),
+            Self::Callback(_) => f.write_str("Callback(..)"),
+        }
+    }
 }
 
-const EMAIL_INPUT_SELECTOR: &str = "input#email";
-const PASSWORD_INPUT_SELECTOR: &str = "input#password";
-const LOGIN_BUTTON_SELECTOR: &str = "//*[contains(text(), 'Log in')]";
 const WAIT_DELAY: u64 = 20;
+/// The code field of the two-factor authentication page, the standard attribute of the one-time codes fields
+const TWO_FACTOR_INPUT_SELECTOR: &str = "input[autocomplete='one-time-code']";
+const TWO_FACTOR_SUBMIT_SELECTOR: &str = "//button[@type='submit']";
+/// How many times the bot checks if the code was accepted, every [`WAIT_DELAY`] milliseconds
+const TWO_FACTOR_CHECKS: u32 = 100;
+/// How often the bot checks if the human finished the login, see [`DefaultBrowserLoginBot::with_manual_assist`]
+const MANUAL_ASSIST_POLL: Duration = Duration::from_millis(500);
 
-impl<'a> DefaultBrowserLoginBot<'a> {
+impl DefaultBrowserLoginBot {
     /// Creates a new default login bot
     ///
     /// # Arguments
     /// * `email` - The email to login with
     /// * `password` - The password to login with
-    pub fn new(email: &'a str, password: &'a str) -> Self {
-        Self { email, password }
+    pub fn new(email: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            email: email.into(),
+            password: password.into(),
+            login_host: Host::default(),
+            clock: Arc::new(SystemClock),
+            two_factor: None,
+            manual_assist: None,
+            selectors: LoginSelectors::default(),
+            typing: None,
+            email_confirmation: None,
+            matched: Mutex::default(),
+        }
+    }
+
+    /// Sets the host of the login page, the default is [`Host::pinterest`].
+    ///
+    /// The login is considered unsuccessful if the bot is still on the login page of this host after submitting the form.
+    pub fn with_login_host(mut self, host: Host) -> Self {
+        self.login_host = host;
+        self
+    }
+
+    /// Sets the clock of the waits for the login form, the default is [`SystemClock`]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Sets where the two-factor authentication codes come from.
+    ///
+    /// Without it, the login fails with [`PinterestLoginError::ChallengeRequired`] if pinterest asks for a code,
+    /// and a rejected code fails it with [`PinterestLoginError::AuthenticationError`].
+    pub fn with_two_factor(mut self, two_factor: TwoFactor) -> Self {
+        self.two_factor = Some(two_factor);
+        self
+    }
+
+    /// Lets a human finish the login in the browser window: after submitting the form, the bot waits up to `timeout`
+    /// for the page to leave the login and the two-factor authentication pages, instead of answering them itself.
+    ///
+    /// It's meant for a headful browser (see [`fallback`](crate::fallback)), to solve the captchas and
+    /// the challenges that the bot can't, or to fix the credentials by hand. If the login isn't finished in time,
+    /// it fails with [`PinterestLoginError::ChallengeRequired`].
+    pub fn with_manual_assist(mut self, timeout: Duration) -> Self {
+        self.manual_assist = Some(timeout);
+        self
+    }
+
+    /// Replaces the fallback chains of selectors of the login form fields, the default is [`LoginSelectors::default`]
+    pub fn with_selectors(mut self, selectors: LoginSelectors) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
+    /// Types the login form character by character, pausing as the timings tell after every one, instead of typing
+    /// every field at once. The pauses go through the clock of the bot, see [`DefaultBrowserLoginBot::with_clock`].
+    pub fn with_typing(mut self, typing: TypingTimings) -> Self {
+        self.typing = Some(typing);
+        self
+    }
+
+    /// Waits for the link of the "check your email" page (see [`checkpoint`](crate::checkpoint)) to be clicked, from
+    /// any device: the page is checked every `interval` for up to `timeout`, and the login goes on once it leaves the
+    /// login page.
+    ///
+    /// Without it, or if the link isn't clicked in time, the login fails with
+    /// [`PinterestLoginError::EmailConfirmationRequired`]. The waits go through the clock of the bot.
+    pub fn with_email_confirmation(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.email_confirmation = Some(EmailConfirmationPolling { interval, timeout });
+        self
+    }
+
+    /// Types the text into the element, at the pace of the typing timings if there are some
+    async fn type_text<E: LoginElement>(
+        &self,
+        element: &E,
+        text: &str,
+        typed: &mut usize,
+    ) -> crate::Result<()> {
+        let Some(typing) = &self.typing else {
+            return element.type_str(text).await;
+        };
+        for (i, c) in text.char_indices() {
+            element.type_str(&text[i..i + c.len_utf8()]).await?;
+            self.clock.sleep(typing.delay(*typed)).await;
+            *typed += 1;
+        }
+        Ok(())
+    }
+
+    /// Finds the field with the first selector of its chain that matches, and records it
+    async fn find_field<P: LoginPage>(
+        &self,
+        page: &P,
+        field: FormField,
+    ) -> crate::Result<Vec<P::Element>> {
+        let mut error = PinterestLoginError::from(CdpError::NotFound);
+        for (position, selector) in self.selectors.chain(field).iter().enumerate() {
+            #[cfg(feature = "log")]
+            trace!("Finding the {field} with the selector: {selector}");
+            match selector.find(page).await {
+                Ok(elements) => {
+                    #[cfg(feature = "log")]
+                    if position > 0 {
+                        warn!("The {field} was found with the fallback selector {selector}, pinterest may have changed its login form");
+                    } else {
+                        debug!("The {field} was found with its primary selector {selector}");
+                    }
+                    self.matched().push(SelectorMatch {
+                        field,
+                        selector: selector.clone(),
+                        position,
+                    });
+                    return Ok(elements);
+                }
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+
+    fn matched(&self) -> std::sync::MutexGuard<'_, Vec<SelectorMatch>> {
+        // The matches are only a report, a panicking login can't poison anything that matters
+        self.matched.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Enters the two-factor authentication code in its field, and submits it
+    async fn enter_two_factor_code<P: LoginPage>(
+        &self,
+        page: &P,
+        input: &P::Element,
+    ) -> crate::Result<()> {
+        #[cfg(feature = "log")]
+        info!("Pinterest asks for a two-factor authentication code");
+        let Some(code) = self.two_factor.as_ref().and_then(TwoFactor::code) else {
+            return Err(PinterestLoginError::ChallengeRequired);
+        };
+        input.focus().await?;
+        input.type_str(code.trim()).await?;
+        let button = page
+            .find_xpaths(TWO_FACTOR_SUBMIT_SELECTOR)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(CdpError::NotFound)?;
+        button.click().await?;
+        page.wait_for_navigation().await?;
+
+        // The code field stays if the code is rejected
+        for _ in 0..TWO_FACTOR_CHECKS {
+            if page.find_element(TWO_FACTOR_INPUT_SELECTOR).await.is_err() {
+                #[cfg(feature = "log")]
+                debug!("The two-factor authentication code was accepted");
+                return Ok(());
+            }
+            self.clock.sleep(Duration::from_millis(WAIT_DELAY)).await;
+        }
+        #[cfg(feature = "log")]
+        debug!("The two-factor authentication code was rejected");
+        Err(PinterestLoginError::AuthenticationError)
+    }
+
+    /// Waits for the page to leave the "check your email" page, see [`DefaultBrowserLoginBot::with_email_confirmation`]
+    async fn wait_for_email_confirmation<P: LoginPage>(&self, page: &P) -> crate::Result<()> {
+        #[cfg(feature = "log")]
+        info!("Pinterest asks to confirm the login with the link that it sent by email");
+        let Some(polling) = self.email_confirmation else {
+            return Err(PinterestLoginError::EmailConfirmationRequired);
+        };
+        let start = self.clock.now();
+        loop {
+            let elapsed = self.clock.now().duration_since(start);
+            if elapsed >= polling.timeout {
+                #[cfg(feature = "log")]
+                debug!("The link wasn't clicked within {:?}", polling.timeout);
+                return Err(PinterestLoginError::EmailConfirmationRequired);
+            }
+            self.clock
+                .sleep(polling.interval.min(polling.timeout - elapsed))
+                .await;
+            if let Some(url) = page.url().await? {
+                if !is_login_url(&url, &self.login_host) {
+                    #[cfg(feature = "log")]
+                    info!("The link was clicked, the login went on to {url}");
+                    return Ok(());
+                }
+            }
+            let html = page.content().await.unwrap_or_default();
+            if !is_email_confirmation_page(&html) {
+                // The page moved on without leaving the login, e.g. back to the form if the link expired
+                return Err(match outage::is_outage_page(&html) {
+                    true => PinterestLoginError::ServiceUnavailable { retry_hint: None },
+                    false => PinterestLoginError::AuthenticationError,
+                });
+            }
+        }
+    }
+}
+
+/// Waits up to `timeout` for a human to finish the login, i.e. for the page to leave the login and the two-factor
+/// authentication pages, returns `false` if it didn't in time
+async fn wait_for_human<P: LoginPage>(
+    page: &P,
+    login_host: &Host,
+    clock: &dyn Clock,
+    timeout: Duration,
+) -> crate::Result<bool> {
+    #[cfg(feature = "log")]
+    info!("Waiting up to {timeout:?} for the login to be finished by hand");
+    let start = clock.now();
+    loop {
+        let done = match page.url().await? {
+            Some(url) => {
+                !is_login_url(&url, login_host)
+                    && page.find_element(TWO_FACTOR_INPUT_SELECTOR).await.is_err()
+            }
+            None => false,
+        };
+        if done {
+            #[cfg(feature = "log")]
+            debug!("The login was finished by hand");
+            return Ok(true);
+        }
+        if clock.now().duration_since(start) >= timeout {
+            return Ok(false);
+        }
+        clock.sleep(MANUAL_ASSIST_POLL).await;
     }
 }
 
-#[async_trait::async_trait]
-impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
+/// A login bot that leaves the whole login to a human, in a headful browser: it opens the login page and waits for
+/// the human to log in, the cookies are then collected like after an automated login.
+///
+/// It's useful for the accounts that the [`DefaultBrowserLoginBot`] can't log in, like the ones that log in
+/// with Google, or that always get a captcha.
+/// If the login isn't finished within the timeout, it fails with [`PinterestLoginError::DeadlineExceeded`].
+///
+/// # Example
+/// ```no_run
+/// # use std::time::Duration;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::ManualLoginBot;
+/// # use pinterest_login::options::{LoginOptions, DEFAULT_KEEP_ALIVE_INTERVAL};
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let bot = ManualLoginBot::new(Duration::from_secs(5 * 60));
+/// let config_builder = DefaultBrowserConfigBuilder::new(false, None, None);
+/// // The connection is idle while the human is on the page
+/// let options = LoginOptions::new().keep_alive(DEFAULT_KEEP_ALIVE_INTERVAL);
+///
+/// let outcome = pinterest_login::login_with_options(&bot, &config_builder, &options).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ManualLoginBot {
+    timeout: Duration,
+    login_host: Host,
+    clock: Arc<dyn Clock>,
+}
+
+impl ManualLoginBot {
+    /// Creates a bot that waits up to `timeout` for the human to log in
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            login_host: Host::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the host of the login page, the default is [`Host::pinterest`].
+    ///
+    /// The login is finished once the page isn't the login page of this host anymore.
+    pub fn with_login_host(mut self, host: Host) -> Self {
+        self.login_host = host;
+        self
+    }
+
+    /// Sets the clock of the timeout, the default is [`SystemClock`]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+}
+
+impl PageLoginBot for ManualLoginBot {
+    /// The human fills the form
+    async fn fill_login_form<P: LoginPage>(&self, _page: &P) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// The human submits the form
+    async fn submit_login_form<P: LoginPage>(&self, _page: &P) -> crate::Result<()> {
+        Ok(())
+    }
+
+    async fn check_login<P: LoginPage>(&self, page: &P) -> crate::Result<()> {
+        if wait_for_human(page, &self.login_host, &*self.clock, self.timeout).await? {
+            Ok(())
+        } else {
+            Err(PinterestLoginError::DeadlineExceeded(self.timeout))
+        }
+    }
+}
+
+/// The pinterest domains, the login page can be served from any of them (or their subdomains) depending on the region
+pub const PINTEREST_DOMAINS: &[&str] = &[
+    "pinterest.com",
+    "pinterest.at",
+    "pinterest.ca",
+    "pinterest.ch",
+    "pinterest.cl",
+    "pinterest.co.kr",
+    "pinterest.co.uk",
+    "pinterest.com.au",
+    "pinterest.com.mx",
+    "pinterest.de",
+    "pinterest.dk",
+    "pinterest.es",
+    "pinterest.fr",
+    "pinterest.ie",
+    "pinterest.it",
+    "pinterest.jp",
+    "pinterest.nz",
+    "pinterest.ph",
+    "pinterest.pt",
+    "pinterest.ru",
+    "pinterest.se",
+];
+
+/// The domains that a login page can be served from, see [`is_login_url`]
+///
+/// A domain matches itself and its subdomains (e.g. `pinterest.com` matches `www.pinterest.com` and `br.pinterest.com`),
+/// and if it has a port it only matches that port (e.g. `127.0.0.1:8080`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    domains: Vec<String>,
+}
+
+impl Host {
+    /// A host with a single domain
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domains: vec![domain.into().to_ascii_lowercase()],
+        }
+    }
+
+    /// Adds another domain to the host
+    pub fn or(mut self, domain: impl Into<String>) -> Self {
+        self.domains.push(domain.into().to_ascii_lowercase());
+        self
+    }
+
+    /// All the [`PINTEREST_DOMAINS`]
+    pub fn pinterest() -> Self {
+        Self {
+            domains: PINTEREST_DOMAINS.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    pub(crate) fn matches(&self, url: &url::Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        self.domains.iter().any(|domain| {
+            let (domain, port) = match domain.rsplit_once(':') {
+                Some((domain, port)) => (domain, port.parse().ok()),
+                None => (domain.as_str(), None),
+            };
+            let host_matches = host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'));
+            host_matches && port.map_or(true, |port| url.port_or_known_default() == Some(port))
+        })
+    }
+}
+
+impl Default for Host {
+    /// [`Host::pinterest`]
+    fn default() -> Self {
+        Self::pinterest()
+    }
+}
+
+/// Returns `true` if the url is the login page of the host, i.e. its path starts with the `/login` segment.
+///
+/// # Example
+/// ```
+/// use pinterest_login::login_bot::{is_login_url, Host};
+///
+/// let pinterest = Host::pinterest();
+/// // (url, is the login page)
+/// let table = [
+///     ("https://pinterest.com/login", true),
+///     ("https://www.pinterest.com/login", true),
+///     ("http://www.pinterest.com/login", true),
+///     ("https://www.pinterest.com/login/", true),
+///     ("https://www.pinterest.com/login/?next=%2Fpin%2F123%2F", true),
+///     ("https://www.pinterest.com/login?referrer=home_page", true),
+///     ("https://www.pinterest.com/login#password", true),
+///     ("https://WWW.Pinterest.COM/login/", true),
+///     ("https://www.pinterest.com:443/login/", true),
+///     ("https://br.pinterest.com/login/", true),
+///     ("https://www.pinterest.co.uk/login/", true),
+///     ("https://www.pinterest.de/login/", true),
+///     ("https://www.pinterest.com/login-help/", false),
+///     ("https://www.pinterest.com/loginpage", false),
+///     ("https://www.pinterest.com/", false),
+///     ("https://www.pinterest.com/?login=1", false),
+///     ("https://www.pinterest.com/homefeed/#/login", false),
+///     ("https://www.pinterest.com/pin/login/", false),
+///     ("https://evilpinterest.com/login/", false),
+///     ("https://pinterest.com.evil.com/login/", false),
+///     ("https://pinterest.com@evil.com/login/", false),
+///     ("https://evil.com/pinterest.com/login/", false),
+///     ("about:blank", false),
+///     ("not a url", false),
+/// ];
+/// for (url, login) in table {
+///     assert_eq!(is_login_url(url, &pinterest), login, "{url}");
+/// }
+///
+/// // A configured host with a port, e.g. a local fixture server
+/// let local = Host::new("127.0.0.1:8080");
+/// assert!(is_login_url("http://127.0.0.1:8080/login", &local));
+/// assert!(!is_login_url("http://127.0.0.1:9090/login", &local));
+/// assert!(!is_login_url("https://www.pinterest.com/login", &local));
+/// ```
+pub fn is_login_url(url: &str, expected_host: &Host) -> bool {
+    let Ok(url) = url::Url::parse(url) else {
+        return false;
+    };
+    expected_host.matches(&url)
+        && url
+            .path_segments()
+            .and_then(|mut segments| segments.next())
+            .is_some_and(|segment| segment == "login")
+}
+
+impl PageLoginBot for DefaultBrowserLoginBot {
     #[inline]
-    async fn fill_login_form(&self, page: &Page) -> crate::Result<()> {
+    async fn fill_login_form<P: LoginPage>(&self, page: &P) -> crate::Result<()> {
         #[cfg(feature = "log")]
         {
             trace!(
@@ -88,38 +697,33 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
                 self.password
             );
             debug!("entering the email");
-            trace!(
-                "Finding the email input field with the selector: {}",
-                EMAIL_INPUT_SELECTOR
-            );
         }
+        self.matched().clear();
         // Wait for the page to load, and then find the email input field and fill it
         let e = loop {
-            let Ok(e) = page.find_element(EMAIL_INPUT_SELECTOR).await else {
-                sleep(Duration::from_millis(WAIT_DELAY)).await;
+            let Ok(mut elements) = self.find_field(page, FormField::Email).await else {
+                // A maintenance page never gets the form, unlike a slow login page
+                outage::check_page(page).await?;
+                self.clock.sleep(Duration::from_millis(WAIT_DELAY)).await;
                 continue;
             };
-            break e;
+            break elements.swap_remove(0);
         };
 
-        e.type_str(self.email).await?;
+        // The typing pace goes on from the email to the password
+        let mut typed = 0;
+        self.type_text(&e, &self.email, &mut typed).await?;
 
         #[cfg(feature = "log")]
-        {
-            debug!("Email entered successfully, entering the password");
-            trace!(
-                "Finding the password input field with the selector: {}",
-                PASSWORD_INPUT_SELECTOR
-            );
-        }
+        debug!("Email entered successfully, entering the password");
 
         // Find the password input field and fill it
-        page.find_element(PASSWORD_INPUT_SELECTOR)
-            .await?
-            .focus()
+        let e = self
+            .find_field(page, FormField::Password)
             .await?
-            .type_str(self.password)
-            .await?;
+            .swap_remove(0);
+        e.focus().await?;
+        self.type_text(&e, &self.password, &mut typed).await?;
 
         #[cfg(feature = "log")]
         debug!("Password entered successfully");
@@ -128,31 +732,40 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
     }
 
     #[inline]
-    async fn submit_login_form(&self, page: &Page) -> crate::Result<()> {
+    async fn submit_login_form<P: LoginPage>(&self, page: &P) -> crate::Result<()> {
         #[cfg(feature = "log")]
         {
             debug!("Submitting the login form");
             info!("Finding the submit button and clicking it");
-            trace!(
-                "Finding the submit button with the selector: {}",
-                LOGIN_BUTTON_SELECTOR
-            );
         }
+        // The form is still there while its email field is
+        let email = self
+            .matched()
+            .iter()
+            .find(|matched| matched.field == FormField::Email)
+            .map(|matched| matched.selector.clone())
+            .or_else(|| self.selectors.chain(FormField::Email).first().cloned());
         let mut buttons = Vec::with_capacity(2);
         let mut old_bounds = Vec::with_capacity(2);
         // Find the submit button and click it
-        for e in page.find_xpaths(LOGIN_BUTTON_SELECTOR).await? {
+        for e in self.find_field(page, FormField::Submit).await? {
             e.click().await?;
             old_bounds.push(e.bounding_box().await?);
             buttons.push(e);
         }
 
-        while page.find_element(EMAIL_INPUT_SELECTOR).await.is_ok() && {
+        while match &email {
+            Some(email) => email.find(page).await.is_ok(),
+            None => false,
+        } && {
             // We need this in case if user enters an invalid authentication data.
             // because in this case pinterest will not change the page and just show up a little tooltip
             // under the wrong box, and we don't have any way to handle that case besid this _wanky_ sloution
             // TODO: find a better way.
-            async fn cheack(buttons: &[Element], ob: &[BoundingBox]) -> crate::Result<bool> {
+            async fn cheack<E: LoginElement>(
+                buttons: &[E],
+                ob: &[BoundingBox],
+            ) -> crate::Result<bool> {
                 for (i, b) in ob.iter().enumerate() {
                     let nb = buttons[i].bounding_box().await?;
                     if nb.x != b.x || nb.y != b.y {
@@ -163,7 +776,7 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
             }
             cheack(&buttons, &old_bounds).await?
         } {
-            sleep(Duration::from_millis(WAIT_DELAY)).await;
+            self.clock.sleep(Duration::from_millis(WAIT_DELAY)).await;
         }
 
         #[cfg(feature = "log")]
@@ -173,12 +786,21 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
     }
 
     #[inline]
-    async fn check_login(&self, page: &Page) -> crate::Result<()> {
-        use lazy_regex::regex;
+    async fn check_login<P: LoginPage>(&self, page: &P) -> crate::Result<()> {
         #[cfg(feature = "log")]
         debug!("Checking if the login was successful");
         // Wait for the page to load, and then check if the login was successful
-        match page.wait_for_navigation().await?.url().await? {
+        page.wait_for_navigation().await?;
+        if let Some(timeout) = self.manual_assist {
+            return match wait_for_human(page, &self.login_host, &*self.clock, timeout).await? {
+                true => Ok(()),
+                false => Err(PinterestLoginError::ChallengeRequired),
+            };
+        }
+        if let Ok(input) = page.find_element(TWO_FACTOR_INPUT_SELECTOR).await {
+            self.enter_two_factor_code(page, &input).await?;
+        }
+        match page.url().await? {
             None => {
                 #[cfg(feature = "log")]
                 debug!("Couldn't get the url, the login was unsuccessful");
@@ -191,8 +813,16 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
                     debug!("Got the url: {}", url);
                     info!("Checking if the url is the same as the login url");
                 }
-                let regex = regex!(r#"^(https?:\/\/(?:www\.)?pinterest\.com\/login).*$"#);
-                if regex.is_match(&url) {
+                if is_login_url(&url, &self.login_host) {
+                    // Pinterest may have served its maintenance page after the submission, or parked the login
+                    // until the link that it sent by email is clicked
+                    let html = page.content().await.unwrap_or_default();
+                    if outage::is_outage_page(&html) {
+                        return Err(PinterestLoginError::ServiceUnavailable { retry_hint: None });
+                    }
+                    if is_email_confirmation_page(&html) {
+                        return self.wait_for_email_confirmation(page).await;
+                    }
                     #[cfg(feature = "log")]
                     debug!("The url is the same as the login url, the login was unsuccessful");
                     // If the url is the same as the login url, then the login was unsuccessful
@@ -205,4 +835,7 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
             }
         }
     }
+    fn matched_selectors(&self) -> Vec<SelectorMatch> {
+        self.matched().clone()
+    }
 }
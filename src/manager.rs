@@ -0,0 +1,253 @@
+use std::{collections::BTreeMap, fmt, sync::Arc, time::SystemTime};
+
+use futures::{lock::Mutex, StreamExt};
+#[cfg(feature = "log")]
+use log::info;
+
+use crate::{
+    config_builder::BrowserConfigBuilder,
+    credentials::{Credentials, CredentialsError, CredentialsProvider},
+    lifecycle::{emit, SessionEvents},
+    login_bot::BrowserLoginBot,
+    login_with_options,
+    options::LoginOptions,
+    session::{Expiry, Session, SessionError, SessionStore, DEFAULT_EXPIRY_MARGIN},
+};
+
+/// The login bots of an account, it's called right before every login so the credentials can be read each time
+pub type BotFactory<B> = Arc<dyn Fn() -> B + Send + Sync>;
+
+/// Where the login bots of an account come from, for the manager and the [keeper](crate::keeper::SessionKeeper)
+pub(crate) enum Bots<B> {
+    /// A factory that has the credentials
+    Factory(BotFactory<B>),
+    /// The credentials are asked to the provider before every login, then given to the factory
+    Credentials(
+        Arc<dyn CredentialsProvider>,
+        Arc<dyn Fn(Credentials) -> B + Send + Sync>,
+    ),
+}
+
+impl<B> Bots<B> {
+    /// The bot of the next login
+    pub(crate) async fn bot(&self) -> Result<B, CredentialsError> {
+        match self {
+            Self::Factory(bots) => Ok(bots()),
+            Self::Credentials(provider, bots) => Ok(bots(provider.credentials().await?)),
+        }
+    }
+}
+
+/// The sessions of several accounts, kept in a [`SessionStore`].
+///
+/// Every account has a lock, so the callers that ask for the same account at the same time wait for the same login
+/// instead of logging in once each, while the different accounts don't wait for each other.
+///
+/// # Example
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::lifecycle::ChannelEvents;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::manager::SessionManager;
+/// # use pinterest_login::session::FileSessionStore;
+/// # async fn run() -> Result<(), pinterest_login::session::SessionError> {
+/// // The logins and their failures are told to the receiver
+/// let (events, receiver) = ChannelEvents::new();
+/// let manager = SessionManager::new(Arc::new(FileSessionStore::new("sessions")), DefaultBrowserConfigBuilder::default())
+///     .account("me@example.com", || DefaultBrowserLoginBot::new("me@example.com", "password"))
+///     .account("other@example.com", || DefaultBrowserLoginBot::new("other@example.com", "password"))
+///     .events(events);
+///
+/// let session = manager.get_or_login("me@example.com").await?;
+///
+/// // Two accounts at a time
+/// for (account, result) in manager.refresh_all(2).await {
+///     if let Err(e) = result {
+///         eprintln!("{account}: {e}");
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SessionManager<B, C> {
+    store: Arc<dyn SessionStore>,
+    config_builder: C,
+    options: LoginOptions,
+    accounts: BTreeMap<String, Account<B>>,
+    events: Option<Arc<dyn SessionEvents>>,
+}
+
+struct Account<B> {
+    bots: Bots<B>,
+    lock: Mutex<()>,
+}
+
+impl<B, C> SessionManager<B, C>
+where
+    B: BrowserLoginBot,
+    C: BrowserConfigBuilder,
+{
+    /// A manager without any account, that keeps the sessions in the store and logs in with the config builder
+    pub fn new(store: Arc<dyn SessionStore>, config_builder: C) -> Self {
+        Self {
+            store,
+            config_builder,
+            options: LoginOptions::default(),
+            accounts: BTreeMap::new(),
+            events: None,
+        }
+    }
+
+    /// Adds an account, with the factory of its login bots, it replaces the factory if the account is already there
+    pub fn account(
+        self,
+        account: impl Into<String>,
+        bots: impl Fn() -> B + Send + Sync + 'static,
+    ) -> Self {
+        self.insert(account.into(), Bots::Factory(Arc::new(bots)))
+    }
+
+    /// Adds an account whose credentials are asked to the provider before every login, the factory makes the login
+    /// bot with them. It replaces the factory if the account is already there.
+    ///
+    /// A failed provider fails the login with [`SessionError::Credentials`].
+    pub fn account_with_credentials(
+        self,
+        account: impl Into<String>,
+        provider: impl CredentialsProvider + 'static,
+        bots: impl Fn(Credentials) -> B + Send + Sync + 'static,
+    ) -> Self {
+        self.insert(
+            account.into(),
+            Bots::Credentials(Arc::new(provider), Arc::new(bots)),
+        )
+    }
+
+    fn insert(mut self, account: String, bots: Bots<B>) -> Self {
+        self.accounts.insert(
+            account,
+            Account {
+                bots,
+                lock: Mutex::new(()),
+            },
+        );
+        self
+    }
+
+    /// The options of the logins
+    pub fn login_options(mut self, options: LoginOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The hooks on the logins, the refreshes, the expiries and the failed logins of every account.
+    ///
+    /// The sessions that replace a stored one, and the ones of [`refresh`](Self::refresh), are
+    /// [refreshed](SessionEvents::on_refreshed) after they're [logged in](SessionEvents::on_login).
+    pub fn events(mut self, events: impl SessionEvents + 'static) -> Self {
+        self.events = Some(Arc::new(events));
+        self
+    }
+
+    /// The accounts, sorted
+    pub fn accounts(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    /// The stored session of the account, or a new one from a login when it has none, or when it expires within
+    /// the [`DEFAULT_EXPIRY_MARGIN`], like [`login_or_load`](crate::session::login_or_load).
+    ///
+    /// # Errors
+    /// [`SessionError::UnknownAccount`] if the account wasn't added, and the errors of the store and the login.
+    pub async fn get_or_login(&self, account: &str) -> Result<Session, SessionError> {
+        self.session(account, false).await
+    }
+
+    /// Logs the account in again, even if its session is fresh, and stores the new session
+    pub async fn refresh(&self, account: &str) -> Result<Session, SessionError> {
+        self.session(account, true).await
+    }
+
+    /// Refreshes every account, up to `concurrency` of them at the same time (at least one), and returns their
+    /// results in the order of the accounts. A failed account doesn't stop the others.
+    pub async fn refresh_all(
+        &self,
+        concurrency: usize,
+    ) -> Vec<(String, Result<Session, SessionError>)> {
+        let mut results: Vec<_> = futures::stream::iter(self.accounts())
+            .map(|account| async move { (account.to_string(), self.refresh(account).await) })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        results.sort_by(|(a, _), (b, _)| a.cmp(b));
+        results
+    }
+
+    /// Removes the stored session of the account, so the next [`get_or_login`](Self::get_or_login) logs in
+    pub async fn invalidate(&self, account: &str) -> Result<(), SessionError> {
+        let entry = self.entry(account)?;
+        let _lock = entry.lock.lock().await;
+        self.store.delete(account).await?;
+        Ok(())
+    }
+
+    async fn session(&self, account: &str, refresh: bool) -> Result<Session, SessionError> {
+        let entry = self.entry(account)?;
+        // The callers of the same account wait for each other, the first one logs in and the others load its session
+        let _lock = entry.lock.lock().await;
+        let mut replaces = refresh;
+        if !refresh {
+            if let Some(session) = self.store.get(account).await? {
+                match session.is_expired(SystemTime::now(), DEFAULT_EXPIRY_MARGIN) {
+                    Expiry::Fresh => return Ok(session),
+                    Expiry::ExpiringSoon => {}
+                    Expiry::Expired => {
+                        emit(self.events.as_deref(), |events| events.on_expired(account)).await
+                    }
+                }
+                replaces = true;
+            }
+        }
+        #[cfg(feature = "log")]
+        info!("Logging {account} in");
+        let bot = entry.bots.bot().await?;
+        let outcome = match login_with_options(&bot, &self.config_builder, &self.options).await {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                emit(self.events.as_deref(), |events| {
+                    events.on_error(account, &error)
+                })
+                .await;
+                return Err(error.into());
+            }
+        };
+        let session = Session::from_outcome(account, &outcome, &self.options);
+        self.store.put(&session).await?;
+        emit(self.events.as_deref(), |events| events.on_login(&session)).await;
+        if replaces {
+            emit(self.events.as_deref(), |events| {
+                events.on_refreshed(&session)
+            })
+            .await;
+        }
+        Ok(session)
+    }
+
+    fn entry(&self, account: &str) -> Result<&Account<B>, SessionError> {
+        self.accounts
+            .get(account)
+            .ok_or_else(|| SessionError::UnknownAccount(account.to_string()))
+    }
+}
+
+impl<B, C: fmt::Debug> fmt::Debug for SessionManager<B, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionManager")
+            .field("config_builder", &self.config_builder)
+            .field("options", &self.options)
+            .field("accounts", &self.accounts.keys().collect::<Vec<_>>())
+            .field("events", &self.events.is_some())
+            .finish()
+    }
+}
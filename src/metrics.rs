@@ -0,0 +1,107 @@
+//! The OpenTelemetry metrics of the logins, with the `otel-metrics` feature.
+//!
+//! They're recorded with the global meter provider of the application, the crate doesn't install an exporter. Without
+//! the feature, the functions do nothing and get inlined away.
+//!
+//! | Instrument                   | Kind      | Attributes             |
+//! |------------------------------|-----------|------------------------|
+//! | `pinterest_login.attempts`   | counter   |                        |
+//! | `pinterest_login.successes`  | counter   |                        |
+//! | `pinterest_login.failures`   | counter   | `code`, see [`ErrorKind::code`](crate::ErrorKind::code) |
+//! | `pinterest_login.duration`   | histogram | `phase`, in seconds    |
+
+use std::future::Future;
+
+#[cfg(feature = "otel-metrics")]
+use futures::{future, FutureExt};
+
+use crate::{LoginPhase, Result};
+
+#[cfg(feature = "otel-metrics")]
+mod otel {
+    use std::time::Duration;
+
+    use opentelemetry::{global, metrics::Meter, KeyValue};
+
+    use crate::LoginPhase;
+
+    // The instruments aren't cached, so a meter provider installed after the first login is still used
+    fn meter() -> Meter {
+        global::meter("pinterest-login")
+    }
+
+    pub(super) fn attempt() {
+        meter()
+            .u64_counter("pinterest_login.attempts")
+            .with_description("The login attempts")
+            .build()
+            .add(1, &[]);
+    }
+
+    pub(super) fn success() {
+        meter()
+            .u64_counter("pinterest_login.successes")
+            .with_description("The successful logins")
+            .build()
+            .add(1, &[]);
+    }
+
+    pub(super) fn failure(code: &'static str) {
+        meter()
+            .u64_counter("pinterest_login.failures")
+            .with_description("The failed logins, by error code")
+            .build()
+            .add(1, &[KeyValue::new("code", code)]);
+    }
+
+    pub(super) fn duration(phase: LoginPhase, duration: Duration) {
+        meter()
+            .f64_histogram("pinterest_login.duration")
+            .with_description("The duration of the login phases")
+            .with_unit("s")
+            .build()
+            .record(
+                duration.as_secs_f64(),
+                &[KeyValue::new("phase", phase.to_string())],
+            );
+    }
+}
+
+// The wrappers are combinators rather than async fns, since an async fn that takes a future stores it twice, and the
+// login future is already big
+
+/// Runs the login, counting its attempt and its outcome
+#[cfg(feature = "otel-metrics")]
+pub(crate) fn recorded<T>(
+    login: impl Future<Output = Result<T>>,
+) -> impl Future<Output = Result<T>> {
+    future::lazy(|_| otel::attempt())
+        .then(move |()| login)
+        .inspect(|result| match result {
+            Ok(_) => otel::success(),
+            Err(error) => otel::failure(error.kind().code()),
+        })
+}
+
+/// Runs the login, counting its attempt and its outcome
+#[cfg(not(feature = "otel-metrics"))]
+#[inline]
+pub(crate) fn recorded<T>(
+    login: impl Future<Output = Result<T>>,
+) -> impl Future<Output = Result<T>> {
+    login
+}
+
+/// Runs the login phase, recording its duration whether it succeeds or not
+#[cfg(feature = "otel-metrics")]
+pub(crate) fn timed<F: Future>(phase: LoginPhase, future: F) -> impl Future<Output = F::Output> {
+    future::lazy(|_| std::time::Instant::now())
+        .then(move |start| future.inspect(move |_| otel::duration(phase, start.elapsed())))
+}
+
+/// Runs the login phase, recording its duration whether it succeeds or not
+#[cfg(not(feature = "otel-metrics"))]
+#[inline]
+pub(crate) fn timed<F: Future>(_phase: LoginPhase, future: F) -> impl Future<Output = F::Output> {
+    future
+}
@@ -0,0 +1,490 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use chromiumoxide::{
+    cdp::browser_protocol::network::{Cookie, CookiePriority, CookieSourceScheme},
+    error::CdpError,
+    layout::BoundingBox,
+};
+
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
+
+use crate::{
+    options::LoginOptions,
+    page::{LoginElement, LoginPage},
+    provider::LoginProvider,
+    report::{LoginOutcome, LoginReport},
+    PinterestCookies, PinterestLoginError,
+};
+
+/// A scripted fake [`LoginPage`], to unit test the [`PageLoginBot`](crate::login_bot::PageLoginBot)s without a browser.
+///
+/// The elements are matched by their exact selector (CSS or XPath), and every action that the bot does on them is
+/// recorded, see [`MockPage::actions`].
+///
+/// # Example
+/// ```
+/// use pinterest_login::login_bot::{DefaultBrowserLoginBot, PageLoginBot};
+/// use pinterest_login::mock::{MockAction, MockElement, MockPage};
+///
+/// # async_std::task::block_on(async {
+/// let page = MockPage::new("https://pinterest.com/login")
+///     .element("input#email", MockElement::new("email"))
+///     .element("input#password", MockElement::new("password"));
+///
+/// let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+/// bot.fill_login_form(&page).await.unwrap();
+///
+/// assert!(page.actions().contains(&MockAction::Typed {
+///     element: "email".to_string(),
+///     text: "me@example.com".to_string(),
+/// }));
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockPage {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    url: Option<String>,
+    content: String,
+    cookies: Vec<Cookie>,
+    elements: Vec<(String, MockElement)>,
+    actions: Vec<MockAction>,
+    lookups: HashMap<String, u32>,
+    /// The content reads left before the page navigates, with the url and the content that it navigates to
+    pending_navigation: Option<(u32, String, String)>,
+}
+
+/// An element of the [`MockPage`]
+#[derive(Debug, Clone)]
+pub struct MockElement {
+    name: String,
+    bounding_box: BoundingBox,
+    hidden_lookups: u32,
+    navigates_to: Option<String>,
+    moves_to: Option<(f64, f64)>,
+    pending_move: Option<((f64, f64), u32)>,
+    removed: bool,
+}
+
+/// An action that the bot did on the [`MockPage`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockAction {
+    /// Typed the text into the element
+    Typed {
+        /// The name of the element
+        element: String,
+        /// The typed text
+        text: String,
+    },
+    /// Clicked the element with this name
+    Clicked(String),
+    /// Focused the element with this name
+    Focused(String),
+}
+
+impl MockPage {
+    /// Creates an empty page with the given url
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                url: Some(url.into()),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Adds an element that's matched by the exact `selector`
+    pub fn element(self, selector: impl Into<String>, element: MockElement) -> Self {
+        self.state().elements.push((selector.into(), element));
+        self
+    }
+
+    /// Sets the HTML content of the page
+    pub fn content(self, html: impl Into<String>) -> Self {
+        self.state().content = html.into();
+        self
+    }
+
+    /// Navigates to the `url` with the `html` content once the content was read `reads` times, like a page that moves
+    /// on because of something out of the bot's reach, e.g. a link clicked on another device
+    pub fn navigates_after(
+        self,
+        reads: u32,
+        url: impl Into<String>,
+        html: impl Into<String>,
+    ) -> Self {
+        self.state().pending_navigation = Some((reads, url.into(), html.into()));
+        self
+    }
+
+    /// Adds a session cookie to the page
+    pub fn cookie(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let cookie = session_cookie(name.into(), value.into());
+        self.state().cookies.push(cookie);
+        self
+    }
+
+    /// The actions that the bot did on the page so far, in order
+    pub fn actions(&self) -> Vec<MockAction> {
+        self.state().actions.clone()
+    }
+
+    /// How many times the bot looked up the given selector
+    pub fn lookups(&self, selector: &str) -> u32 {
+        self.state().lookups.get(selector).copied().unwrap_or(0)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panicking test can't poison anything that matters
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn find(&self, selector: &str) -> Vec<MockElementHandle> {
+        let mut state = self.state();
+        *state.lookups.entry(selector.to_string()).or_default() += 1;
+        let mut found = Vec::new();
+        for (index, (s, element)) in state.elements.iter_mut().enumerate() {
+            if s != selector || element.removed {
+                continue;
+            }
+            if element.hidden_lookups > 0 {
+                element.hidden_lookups -= 1;
+                continue;
+            }
+            found.push(MockElementHandle {
+                page: self.clone(),
+                index,
+            });
+        }
+        found
+    }
+}
+
+/// A session cookie of pinterest, like the ones of a login
+fn session_cookie(name: String, value: String) -> Cookie {
+    Cookie {
+        size: (name.len() + value.len()) as i64,
+        name,
+        value,
+        domain: ".pinterest.com".to_string(),
+        path: "/".to_string(),
+        expires: -1.0,
+        http_only: false,
+        secure: true,
+        session: true,
+        same_site: None,
+        priority: CookiePriority::Medium,
+        same_party: false,
+        source_scheme: CookieSourceScheme::Secure,
+        source_port: 443,
+        partition_key: None,
+        partition_key_opaque: None,
+    }
+}
+
+impl MockElement {
+    /// Creates an element, the name is used to identify it in the [`MockAction`]s
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            bounding_box: BoundingBox {
+                x: 0.0,
+                y: 0.0,
+                width: 100.0,
+                height: 20.0,
+            },
+            hidden_lookups: 0,
+            navigates_to: None,
+            moves_to: None,
+            pending_move: None,
+            removed: false,
+        }
+    }
+
+    /// The element isn't found by the first `lookups` lookups, like an element that's rendered late
+    pub fn appears_after(mut self, lookups: u32) -> Self {
+        self.hidden_lookups = lookups;
+        self
+    }
+
+    /// Clicking the element navigates the page to the url, all the elements of the page are gone after that
+    pub fn navigates_to(mut self, url: impl Into<String>) -> Self {
+        self.navigates_to = Some(url.into());
+        self
+    }
+
+    /// Clicking the element moves it a moment later (after it's measured once), like when pinterest shows
+    /// a tooltip under a wrong field without leaving the page
+    pub fn moves_to(mut self, x: f64, y: f64) -> Self {
+        self.moves_to = Some((x, y));
+        self
+    }
+}
+
+/// The handle of a [`MockElement`] that was found on a [`MockPage`]
+#[derive(Debug)]
+pub struct MockElementHandle {
+    page: MockPage,
+    index: usize,
+}
+
+impl MockElementHandle {
+    fn with<T>(&self, f: impl FnOnce(&mut State, usize) -> T) -> T {
+        f(&mut self.page.state(), self.index)
+    }
+
+    fn name(state: &State, index: usize) -> String {
+        state.elements[index].1.name.clone()
+    }
+}
+
+impl LoginPage for MockPage {
+    type Element = MockElementHandle;
+
+    async fn find_element(&self, selector: &str) -> crate::Result<MockElementHandle> {
+        self.find(selector)
+            .into_iter()
+            .next()
+            .ok_or(CdpError::NotFound.into())
+    }
+
+    async fn find_xpaths(&self, xpath: &str) -> crate::Result<Vec<MockElementHandle>> {
+        Ok(self.find(xpath))
+    }
+
+    async fn wait_for_navigation(&self) -> crate::Result<()> {
+        // The navigations happen right away
+        Ok(())
+    }
+
+    async fn url(&self) -> crate::Result<Option<String>> {
+        Ok(self.state().url.clone())
+    }
+
+    async fn content(&self) -> crate::Result<String> {
+        let mut state = self.state();
+        let content = state.content.clone();
+        if let Some((reads, _, _)) = &mut state.pending_navigation {
+            *reads = reads.saturating_sub(1);
+            if *reads == 0 {
+                let (_, url, html) = state.pending_navigation.take().unwrap();
+                state.url = Some(url);
+                state.content = html;
+            }
+        }
+        Ok(content)
+    }
+
+    async fn get_cookies(&self) -> crate::Result<Vec<Cookie>> {
+        Ok(self.state().cookies.clone())
+    }
+}
+
+impl LoginElement for MockElementHandle {
+    async fn type_str(&self, text: &str) -> crate::Result<()> {
+        self.with(|state, index| {
+            let element = MockElementHandle::name(state, index);
+            state.actions.push(MockAction::Typed {
+                element,
+                text: text.to_string(),
+            });
+        });
+        Ok(())
+    }
+
+    async fn click(&self) -> crate::Result<()> {
+        self.with(|state, index| {
+            let name = MockElementHandle::name(state, index);
+            state.actions.push(MockAction::Clicked(name));
+            let element = &mut state.elements[index].1;
+            element.pending_move = element.moves_to.map(|to| (to, 1));
+            if let Some(url) = element.navigates_to.clone() {
+                state.url = Some(url);
+                state
+                    .elements
+                    .iter_mut()
+                    .for_each(|(_, element)| element.removed = true);
+            }
+        });
+        Ok(())
+    }
+
+    async fn focus(&self) -> crate::Result<()> {
+        self.with(|state, index| {
+            let name = MockElementHandle::name(state, index);
+            state.actions.push(MockAction::Focused(name));
+        });
+        Ok(())
+    }
+
+    async fn bounding_box(&self) -> crate::Result<BoundingBox> {
+        self.with(|state, index| {
+            let element = &mut state.elements[index].1;
+            match element.pending_move {
+                Some(((x, y), 0)) => {
+                    element.bounding_box.x = x;
+                    element.bounding_box.y = y;
+                    element.pending_move = None;
+                }
+                Some((to, measurements)) => element.pending_move = Some((to, measurements - 1)),
+                None => {}
+            }
+            Ok(element.bounding_box.clone())
+        })
+    }
+}
+
+/// A fake [`LoginProvider`] that returns canned cookies or errors, to test the code that logs in without a browser,
+/// an account or the network.
+///
+/// Every login plays the next outcome of its script, and the last one is repeated once the script is over, so
+/// `failing(..).then_returning(..)` fails once and then succeeds for good. The logins are recorded, see
+/// [`MockLogin::calls`]. The clones share the script and the calls.
+///
+/// # Example
+/// ```
+/// use pinterest_login::mock::MockLogin;
+/// use pinterest_login::options::LoginOptions;
+/// use pinterest_login::provider::LoginProvider;
+/// use pinterest_login::PinterestLoginError;
+///
+/// # async_std::task::block_on(async {
+/// let cookies = [("_pinterest_sess".to_string(), "session".to_string())].into();
+/// let login = MockLogin::failing(|| PinterestLoginError::RateLimited).then_returning(cookies);
+/// let options = LoginOptions::new().login_url("http://127.0.0.1:8080/login");
+///
+/// assert!(login.login(&options).await.is_err());
+/// let outcome = login.login(&options).await.unwrap();
+/// assert_eq!(outcome.cookies["_pinterest_sess"], "session");
+/// assert_eq!(login.calls().len(), 2);
+/// assert_eq!(login.calls()[0].login_url, "http://127.0.0.1:8080/login");
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct MockLogin {
+    state: Arc<Mutex<LoginState>>,
+}
+
+#[derive(Default)]
+struct LoginState {
+    script: VecDeque<Outcome>,
+    calls: Vec<MockCall>,
+}
+
+/// A canned outcome of the [`MockLogin`]
+#[derive(Clone)]
+enum Outcome {
+    Cookies(PinterestCookies),
+    // The errors aren't `Clone`, so they're made for every failure
+    Error(Arc<dyn Fn() -> PinterestLoginError + Send + Sync>),
+}
+
+/// A login of the [`MockLogin`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MockCall {
+    /// The url of the login page of the options
+    pub login_url: String,
+    /// The options of the login
+    pub options: LoginOptions,
+}
+
+impl MockLogin {
+    /// A login that returns the cookies
+    pub fn returning(cookies: PinterestCookies) -> Self {
+        Self::new(Outcome::Cookies(cookies))
+    }
+
+    /// A login that fails with the error that the function makes
+    pub fn failing(error: impl Fn() -> PinterestLoginError + Send + Sync + 'static) -> Self {
+        Self::new(Outcome::Error(Arc::new(error)))
+    }
+
+    /// Returns the cookies in the next login of the script
+    pub fn then_returning(self, cookies: PinterestCookies) -> Self {
+        self.state().script.push_back(Outcome::Cookies(cookies));
+        self
+    }
+
+    /// Fails with the error that the function makes in the next login of the script
+    pub fn then_failing(
+        self,
+        error: impl Fn() -> PinterestLoginError + Send + Sync + 'static,
+    ) -> Self {
+        self.state()
+            .script
+            .push_back(Outcome::Error(Arc::new(error)));
+        self
+    }
+
+    /// The logins so far, in order
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state().calls.clone()
+    }
+
+    fn new(outcome: Outcome) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LoginState {
+                script: [outcome].into(),
+                calls: Vec::new(),
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, LoginState> {
+        // A panicking test can't poison anything that matters
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for MockLogin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("MockLogin")
+            .field("script", &state.script.len())
+            .field("calls", &state.calls)
+            .finish()
+    }
+}
+
+impl LoginProvider for MockLogin {
+    fn login<'a>(
+        &'a self,
+        options: &'a LoginOptions,
+    ) -> BoxFuture<'a, crate::Result<LoginOutcome>> {
+        let outcome = {
+            let mut state = self.state();
+            state.calls.push(MockCall {
+                login_url: options.target.url().to_string(),
+                options: options.clone(),
+            });
+            match state.script.len() {
+                1 => state.script[0].clone(),
+                _ => state.script.pop_front().expect("the script isn't empty"),
+            }
+        };
+        let result = match outcome {
+            Outcome::Cookies(cookies) => Ok(LoginOutcome {
+                cookie_details: cookies
+                    .iter()
+                    .map(|(name, value)| session_cookie(name.clone(), value.clone()))
+                    .collect(),
+                cookies,
+                report: LoginReport::default(),
+                storage: None,
+            }),
+            Outcome::Error(error) => Err(error()),
+        };
+        future::ready(result).boxed()
+    }
+}
@@ -0,0 +1,555 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chromiumoxide::cdp::{browser_protocol::network::Cookie, CdpEvent};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    fingerprint::Fingerprint,
+    launcher::{BrowserLauncher, ChromiumLauncher},
+    preset::{self, Preset},
+    retry::{RetryNotice, StepRetry},
+};
+
+/// A callback that receives the raw CDP events of the login page, see [`LoginOptions::event_sink`]
+pub type EventSink = Arc<dyn Fn(&CdpEvent) + Send + Sync>;
+
+/// A callback that is told about the retries of the login steps, see [`LoginOptions::on_retry`]
+pub type RetryHook = Arc<dyn Fn(&RetryNotice<'_>) + Send + Sync>;
+
+/// A callback that is told where the failure screenshot was saved, or why it couldn't be,
+/// see [`LoginOptions::on_failure_screenshot`]
+pub type ScreenshotHook = Arc<dyn Fn(Result<&Path, &crate::PinterestLoginError>) + Send + Sync>;
+
+/// A callback that is told that the browser was already logged in, with the url it landed on,
+/// see [`LoginOptions::on_already_authenticated`]
+pub type AuthenticatedHook = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// The login page that the login navigates to, see [`LoginOptions::target`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Target {
+    /// The real pinterest login page, [`PINTEREST_LOGIN_URL`](crate::PINTEREST_LOGIN_URL)
+    #[default]
+    Pinterest,
+    /// Any other page that mimics the pinterest login page, like a local fixture (see [`dry_run`](crate::dry_run::dry_run))
+    Url(String),
+}
+
+impl Target {
+    /// The url of the login page
+    pub fn url(&self) -> &str {
+        match self {
+            Self::Pinterest => crate::PINTEREST_LOGIN_URL,
+            Self::Url(url) => url,
+        }
+    }
+}
+
+/// Where the background tasks of the login (the event handler of the browser, the keep-alive pings and the event
+/// sink) run, see [`LoginOptions::task_mode`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TaskMode {
+    /// Spawns them on the runtime, with `async_std::task::spawn` or `tokio::spawn`. Any tokio runtime works, the
+    /// current thread one too, as long as the login runs inside it
+    #[default]
+    Spawn,
+    /// Spawns them on the current thread, with `tokio::task::spawn_local`, so the login must run inside a
+    /// `tokio::task::LocalSet`. The executor of async-std is a global one that any thread can spawn on, so with
+    /// async-std it's the same as [`TaskMode::Spawn`]
+    Local,
+    /// Runs them inside the login future itself, nothing is spawned at all, so any executor can drive the login,
+    /// even `futures::executor::block_on`. The tasks only run while the login future is polled
+    Inline,
+}
+
+/// The browser automation that the login goes through, see [`LoginOptions::engine`]
+#[cfg(feature = "webdriver")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Engine {
+    /// A Chrome/Chromium over CDP, with chromiumoxide
+    #[default]
+    Cdp,
+    /// Any browser of a WebDriver server, like Firefox with geckodriver, see [`webdriver`](crate::webdriver)
+    WebDriver(crate::webdriver::WebDriverConfig),
+}
+
+/// When the login page is ready for the login bot after the navigation, see [`LoginOptions::ready_when`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ReadyWhen {
+    /// Right after the navigation, once the document is loaded
+    #[default]
+    DomContentLoaded,
+    /// Once no request of the page is in flight for `idle_for`, but no later than `max_wait` after the navigation.
+    ///
+    /// The requests are followed with the network events of CDP, so the scripts that render the form have been
+    /// fetched and the data that they fetch too. Over WebDriver, which has no network events, it's the same as
+    /// [`ReadyWhen::DomContentLoaded`].
+    NetworkIdle {
+        /// How long the network must be quiet
+        idle_for: Duration,
+        /// The maximum wait, the login goes on when it's over
+        max_wait: Duration,
+    },
+    /// Once an element matches the CSS selector, like `input#email`, but no later than [`READY_SELECTOR_TIMEOUT`]
+    /// after the navigation
+    Selector(String),
+}
+
+/// The maximum wait of [`ReadyWhen::Selector`], the login goes on when it's over
+pub const READY_SELECTOR_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The urls whose cookies the login returns by default, along with the url that the browser ended up on, see
+/// [`LoginOptions::cookie_urls`]
+pub const DEFAULT_COOKIE_URLS: [&str; 2] =
+    ["https://www.pinterest.com/", "https://api.pinterest.com/"];
+
+/// The default interval of the keep-alive pings, see [`LoginOptions::keep_alive`]
+pub const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The cookies that the login returns, see [`LoginOptions::cookie_allowlist`]
+///
+/// # Example
+/// ```
+/// use pinterest_login::options::CookieAllowlist;
+///
+/// let allowlist = CookieAllowlist::new()
+///     .require("_pinterest_sess")
+///     .allow("_routing_id");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieAllowlist {
+    required: Vec<String>,
+    allowed: Vec<String>,
+}
+
+impl CookieAllowlist {
+    /// An empty allowlist, the login returns no cookie with it
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cookies of a logged in session: `_pinterest_sess` and `csrftoken` are required, `_auth` and `_routing_id`
+    /// are kept when pinterest sets them
+    pub fn pinterest() -> Self {
+        Self::new()
+            .require(crate::session::SESSION_COOKIE)
+            .require(crate::session::CSRF_COOKIE)
+            .allow("_auth")
+            .allow("_routing_id")
+    }
+
+    /// Keeps the cookie, the login fails with [`MissingCookie`](crate::PinterestLoginError::MissingCookie) if the
+    /// browser doesn't have it
+    pub fn require(mut self, name: impl Into<String>) -> Self {
+        self.required.push(name.into());
+        self
+    }
+
+    /// Keeps the cookie if the browser has it
+    pub fn allow(mut self, name: impl Into<String>) -> Self {
+        self.allowed.push(name.into());
+        self
+    }
+
+    /// Whether the cookie is kept
+    pub fn contains(&self, name: &str) -> bool {
+        self.required.iter().chain(&self.allowed).any(|n| n == name)
+    }
+
+    /// Keeps the cookies of the allowlist, fails if a required one is missing
+    pub(crate) fn filter(&self, mut cookies: Vec<Cookie>) -> crate::Result<Vec<Cookie>> {
+        if let Some(missing) = self
+            .required
+            .iter()
+            .find(|name| !cookies.iter().any(|cookie| &cookie.name == *name))
+        {
+            return Err(crate::PinterestLoginError::MissingCookie(missing.clone()));
+        }
+        cookies.retain(|cookie| self.contains(&cookie.name));
+        Ok(cookies)
+    }
+}
+
+/// Extra options for [`login_with_options`](crate::login_with_options)
+///
+/// # Example
+/// ```
+/// use pinterest_login::options::LoginOptions;
+///
+/// let options = LoginOptions::new().event_sink(|event| println!("{event:?}"));
+/// ```
+#[derive(Clone, Default)]
+pub struct LoginOptions {
+    pub(crate) event_sink: Option<EventSink>,
+    pub(crate) target: Target,
+    pub(crate) launcher: Option<Arc<dyn BrowserLauncher>>,
+    pub(crate) launch_retry: StepRetry,
+    pub(crate) navigation_retry: StepRetry,
+    pub(crate) keep_alive: Option<Duration>,
+    pub(crate) clock: Option<Arc<dyn Clock>>,
+    pub(crate) on_retry: Option<RetryHook>,
+    pub(crate) screenshot_dir: Option<PathBuf>,
+    pub(crate) on_screenshot: Option<ScreenshotHook>,
+    pub(crate) on_authenticated: Option<AuthenticatedHook>,
+    /// `true` to use the default browser context instead of an incognito one, see [`LoginOptions::incognito`]
+    pub(crate) no_incognito: bool,
+    pub(crate) fingerprint: Option<Fingerprint>,
+    pub(crate) task_mode: TaskMode,
+    pub(crate) ready_when: ReadyWhen,
+    pub(crate) cookie_allowlist: Option<CookieAllowlist>,
+    pub(crate) cookie_urls: Option<Vec<String>>,
+    pub(crate) capture_storage: bool,
+    #[cfg(feature = "webdriver")]
+    pub(crate) engine: Engine,
+}
+
+impl LoginOptions {
+    /// Creates the default options, that makes [`login_with_options`](crate::login_with_options) behave exactly like [`login`](crate::login)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the defaults of the preset, see [`Preset`] for what it sets. Call it first, the setters called after it
+    /// override its values.
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::options::LoginOptions;
+    /// use pinterest_login::preset::Preset;
+    ///
+    /// // The debug preset, with the screenshots somewhere else
+    /// let options = LoginOptions::new()
+    ///     .preset(Preset::Debug)
+    ///     .screenshot_on_failure("screenshots");
+    /// ```
+    pub fn preset(mut self, preset: Preset) -> Self {
+        let retry = match preset {
+            Preset::Fast => StepRetry {
+                retries: 0,
+                delay: Duration::ZERO,
+            },
+            Preset::Stealthy | Preset::Debug => StepRetry::default(),
+        };
+        self.launch_retry = retry;
+        self.navigation_retry = retry;
+        self.fingerprint = (preset == Preset::Stealthy).then(preset::stealthy_fingerprint);
+        self.screenshot_dir = (preset == Preset::Debug).then(preset::debug_screenshot_dir);
+        self.keep_alive = (preset == Preset::Debug).then_some(DEFAULT_KEEP_ALIVE_INTERVAL);
+        self
+    }
+
+    /// Forwards the navigation, network and console CDP events of the login page to the given callback.
+    ///
+    /// The callback runs on a background task of the login, so it must be cheap, if it can't keep up with the events
+    /// they get dropped (there's a small buffer) rather than stalling the browser.
+    pub fn event_sink(mut self, sink: impl Fn(&CdpEvent) + Send + Sync + 'static) -> Self {
+        self.event_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sets the login page to navigate to, the default is [`Target::Pinterest`]
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Navigates to the given url instead of [`PINTEREST_LOGIN_URL`](crate::PINTEREST_LOGIN_URL), mostly useful for testing.
+    ///
+    /// A shorthand for `target(Target::Url(url))`.
+    pub fn login_url(self, url: impl Into<String>) -> Self {
+        self.target(Target::Url(url.into()))
+    }
+
+    /// Uses the given launcher to get the browser, instead of the default [`ChromiumLauncher`]
+    pub fn launcher(mut self, launcher: impl BrowserLauncher + 'static) -> Self {
+        self.launcher = Some(Arc::new(launcher));
+        self
+    }
+
+    /// Sets how many times to retry the transient browser launch failures, the default is [`StepRetry::default`]
+    pub fn launch_retry(mut self, retry: StepRetry) -> Self {
+        self.launch_retry = retry;
+        self
+    }
+
+    /// Sets how many times to retry the transient network errors while navigating to the login page,
+    /// the default is [`StepRetry::default`]
+    pub fn navigation_retry(mut self, retry: StepRetry) -> Self {
+        self.navigation_retry = retry;
+        self
+    }
+
+    /// Pings the browser every `interval` (e.g. [`DEFAULT_KEEP_ALIVE_INTERVAL`]) while the login is in progress,
+    /// so the idle websocket connection doesn't get dropped (by the browser or a proxy in between) during the long waits,
+    /// like when a human is solving a captcha.
+    ///
+    /// The flows that wait on a human enable it by default. A ping that doesn't get an answer fails the login with
+    /// [`PinterestLoginError::BrowserDisconnected`](crate::PinterestLoginError::BrowserDisconnected).
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Uses the given clock for the retry delays, the handshake timeout and the keep-alive pings,
+    /// instead of the default [`SystemClock`]. Mostly useful for testing.
+    ///
+    /// The login bot has its own clock, see [`DefaultBrowserLoginBot::with_clock`](crate::login_bot::DefaultBrowserLoginBot::with_clock).
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Calls the callback before every retry of the browser launch or the navigation, with the error of the failed attempt.
+    ///
+    /// Useful to tell the user why the login takes longer than usual, the retries are only logged otherwise.
+    pub fn on_retry(mut self, hook: impl Fn(&RetryNotice<'_>) + Send + Sync + 'static) -> Self {
+        self.on_retry = Some(Arc::new(hook));
+        self
+    }
+
+    /// Saves a screenshot of the login page into the directory when the login fails after the page was opened
+    /// (from the navigation to the cookies collection), to see what pinterest showed instead of the expected page.
+    ///
+    /// The screenshots are full page PNGs named `login-failure-{unix time in milliseconds}.png`, the directory is
+    /// created if it's missing. A failure to take or save the screenshot doesn't replace the login error,
+    /// use [`LoginOptions::on_failure_screenshot`] to know where it was saved, or why it wasn't.
+    pub fn screenshot_on_failure(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.screenshot_dir = Some(dir.into());
+        self
+    }
+
+    /// Calls the callback after every failure screenshot, with its path or the error that prevented it,
+    /// see [`LoginOptions::screenshot_on_failure`]
+    pub fn on_failure_screenshot(
+        mut self,
+        hook: impl Fn(Result<&Path, &crate::PinterestLoginError>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_screenshot = Some(Arc::new(hook));
+        self
+    }
+
+    /// Calls the callback when the browser is already logged in after the navigation to the login page, with the url
+    /// it landed on.
+    ///
+    /// With a persistent profile or seeded cookies, pinterest sends the browser away from the login page to the home
+    /// feed, so the login skips filling and submitting the form, and goes straight to
+    /// [`BrowserLoginBot::check_login`](crate::login_bot::BrowserLoginBot::check_login) and the cookies. The
+    /// [`LoginReport::already_authenticated`](crate::report::LoginReport::already_authenticated) tells it too.
+    pub fn on_already_authenticated(mut self, hook: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_authenticated = Some(Arc::new(hook));
+        self
+    }
+
+    /// Whether the login page is opened in an incognito browser context, the default is `true`.
+    ///
+    /// With `false`, the page uses the default context of the browser profile, so the cookies and the local storage
+    /// of the login stay in the profile and pinterest recognizes the device the next time (fewer "new device"
+    /// emails). It's only useful with a persistent profile, see
+    /// [`DefaultBrowserConfigBuilder::with_user_data_dir`](crate::config_builder::DefaultBrowserConfigBuilder::with_user_data_dir),
+    /// every launch gets a throwaway profile otherwise.
+    pub fn incognito(mut self, incognito: bool) -> Self {
+        self.no_incognito = !incognito;
+        self
+    }
+
+    /// Presents the login page with the user agent and the viewport of the fingerprint, instead of the ones of the
+    /// stealth mode and the browser window, see [`retry_login`](crate::retry::retry_login).
+    ///
+    /// The typing pace of the fingerprint is the one of the login bot, see
+    /// [`DefaultBrowserLoginBot::with_typing`](crate::login_bot::DefaultBrowserLoginBot::with_typing).
+    pub fn fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Sets when the login page is ready for the login bot after the navigation, the default is
+    /// [`ReadyWhen::DomContentLoaded`].
+    ///
+    /// The login page of pinterest is an app that renders the form once its scripts ran, so on a slow connection the
+    /// login bot may spend its own wait on an empty page. The strategy and how long it waited are in
+    /// [`LoginReport::ready`](crate::report::LoginReport::ready). The waits go through the clock of the options.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use pinterest_login::options::{LoginOptions, ReadyWhen};
+    ///
+    /// let options = LoginOptions::new().ready_when(ReadyWhen::NetworkIdle {
+    ///     idle_for: Duration::from_millis(500),
+    ///     max_wait: Duration::from_secs(15),
+    /// });
+    /// ```
+    pub fn ready_when(mut self, ready_when: ReadyWhen) -> Self {
+        self.ready_when = ready_when;
+        self
+    }
+
+    /// Sets where the background tasks of the login run, the default is [`TaskMode::Spawn`]
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+    /// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+    /// # use pinterest_login::login_with_options;
+    /// use pinterest_login::options::{LoginOptions, TaskMode};
+    ///
+    /// let bot = DefaultBrowserLoginBot::new("email", "password");
+    /// let config_builder = DefaultBrowserConfigBuilder::default();
+    /// let options = LoginOptions::new().task_mode(TaskMode::Inline);
+    ///
+    /// let outcome = futures::executor::block_on(login_with_options(&bot, &config_builder, &options));
+    /// ```
+    pub fn task_mode(mut self, mode: TaskMode) -> Self {
+        self.task_mode = mode;
+        self
+    }
+
+    /// A shorthand for `task_mode(TaskMode::Local)`, or `task_mode(TaskMode::Spawn)` with `false`
+    pub fn spawn_local(self, local: bool) -> Self {
+        self.task_mode(if local {
+            TaskMode::Local
+        } else {
+            TaskMode::Spawn
+        })
+    }
+
+    /// Sets the browser automation of the login, the default is [`Engine::Cdp`].
+    ///
+    /// The [`Engine::WebDriver`] only works with the [`PageLoginBot`](crate::login_bot::PageLoginBot)s, through
+    /// [`login_with_engine`](crate::login_with_engine).
+    #[cfg(feature = "webdriver")]
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Only returns the cookies of the allowlist, instead of all the cookies of the browser, like the ones of the
+    /// analytics. The login fails if one of its required cookies is missing, rather than returning a partial session.
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::options::{CookieAllowlist, LoginOptions};
+    ///
+    /// let options = LoginOptions::new().cookie_allowlist(CookieAllowlist::pinterest());
+    /// ```
+    pub fn cookie_allowlist(mut self, allowlist: CookieAllowlist) -> Self {
+        self.cookie_allowlist = Some(allowlist);
+        self
+    }
+
+    /// Sets the urls whose cookies the login returns, the default is [`DEFAULT_COOKIE_URLS`].
+    ///
+    /// The cookies that the browser would send to any of them are returned, along with the ones of the url that the
+    /// browser ended up on, like a regional host such as `www.pinterest.co.uk`, so the session has the cookies of the
+    /// `.com` API too. The WebDriver engine only returns the cookies of the page.
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::options::{LoginOptions, DEFAULT_COOKIE_URLS};
+    ///
+    /// let options = LoginOptions::new()
+    ///     .cookie_urls(DEFAULT_COOKIE_URLS.into_iter().chain(["https://www.pinterest.co.uk/"]));
+    /// ```
+    pub fn cookie_urls<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.cookie_urls = Some(urls.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// The urls of the cookies to return, with the one that the browser ended up on, see
+    /// [`LoginOptions::cookie_urls`]
+    pub(crate) fn get_cookie_urls(&self, page_url: Option<String>) -> Vec<String> {
+        let mut urls = match &self.cookie_urls {
+            Some(urls) => urls.clone(),
+            None => DEFAULT_COOKIE_URLS.map(String::from).to_vec(),
+        };
+        if let Some(url) = page_url.filter(|url| !urls.contains(url)) {
+            urls.push(url);
+        }
+        urls
+    }
+
+    /// Whether the login returns the `localStorage` and the `sessionStorage` of the page too, in
+    /// [`LoginOutcome::storage`](crate::report::LoginOutcome::storage), the default is `false`.
+    ///
+    /// Pinterest keeps some state in them, like its experiment flags, that a browser-like replay of the session may
+    /// need. They're read once the login is checked, which costs an extra CDP round trip. The WebDriver engine doesn't
+    /// read them.
+    pub fn capture_storage(mut self, capture: bool) -> Self {
+        self.capture_storage = capture;
+        self
+    }
+
+    /// Keeps the cookies of the [`LoginOptions::cookie_allowlist`], all of them if there's none
+    pub(crate) fn filter_cookies(&self, cookies: Vec<Cookie>) -> crate::Result<Vec<Cookie>> {
+        match &self.cookie_allowlist {
+            Some(allowlist) => allowlist.filter(cookies),
+            None => Ok(cookies),
+        }
+    }
+
+    pub(crate) fn notify_screenshot(&self, screenshot: Result<&Path, &crate::PinterestLoginError>) {
+        if let Some(hook) = &self.on_screenshot {
+            hook(screenshot);
+        }
+    }
+
+    pub(crate) fn notify_authenticated(&self, url: &str) {
+        if let Some(hook) = &self.on_authenticated {
+            hook(url);
+        }
+    }
+
+    pub(crate) fn notify_retry(&self, notice: RetryNotice<'_>) {
+        if let Some(hook) = &self.on_retry {
+            hook(&notice);
+        }
+    }
+
+    pub(crate) fn get_clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone().unwrap_or_else(|| Arc::new(SystemClock))
+    }
+
+    pub(crate) fn get_launcher(&self) -> &dyn BrowserLauncher {
+        self.launcher.as_deref().unwrap_or(&ChromiumLauncher)
+    }
+}
+
+impl fmt::Debug for LoginOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("LoginOptions");
+        f.field("event_sink", &self.event_sink.is_some())
+            .field("target", &self.target)
+            .field("launcher", &self.launcher.is_some())
+            .field("launch_retry", &self.launch_retry)
+            .field("navigation_retry", &self.navigation_retry)
+            .field("keep_alive", &self.keep_alive)
+            .field("clock", &self.clock.is_some())
+            .field("on_retry", &self.on_retry.is_some())
+            .field("screenshot_dir", &self.screenshot_dir)
+            .field("on_screenshot", &self.on_screenshot.is_some())
+            .field("on_authenticated", &self.on_authenticated.is_some())
+            .field("incognito", &!self.no_incognito)
+            .field("fingerprint", &self.fingerprint)
+            .field("task_mode", &self.task_mode)
+            .field("ready_when", &self.ready_when)
+            .field("cookie_allowlist", &self.cookie_allowlist)
+            .field("cookie_urls", &self.cookie_urls)
+            .field("capture_storage", &self.capture_storage);
+        #[cfg(feature = "webdriver")]
+        f.field("engine", &self.engine);
+        f.finish()
+    }
+}
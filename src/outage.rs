@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use chromiumoxide::handler::http::HttpRequest;
+
+use crate::{page::LoginPage, PinterestLoginError};
+
+/// The phrases of the maintenance and the error pages, in their visible text.
+///
+/// They're matched in lowercase, with the typographic apostrophes replaced by plain ones.
+const OUTAGE_MARKERS: &[&str] = &[
+    "down for maintenance",
+    "scheduled maintenance",
+    "undergoing maintenance",
+    "under maintenance",
+    "we'll be back soon",
+    "temporarily unavailable",
+    "service unavailable",
+    "internal server error",
+    "bad gateway",
+    "gateway timeout",
+];
+
+/// Returns `true` if the HTTP status of the login page means that pinterest is down, i.e. it's a server error.
+///
+/// # Example
+/// ```
+/// use pinterest_login::outage::is_outage_status;
+///
+/// for status in [500, 502, 503, 504] {
+///     assert!(is_outage_status(status), "{status}");
+/// }
+/// for status in [200, 301, 403, 404, 429] {
+///     assert!(!is_outage_status(status), "{status}");
+/// }
+/// ```
+pub fn is_outage_status(status: i64) -> bool {
+    (500..600).contains(&status)
+}
+
+/// Returns `true` if the HTML is a maintenance or an error page of pinterest rather than its login page.
+///
+/// Only the visible text is searched, the scripts of the login page carry the error messages of the app. A page with
+/// a password field is never an outage, so a login page that is still loading, or that shows an error under its form,
+/// isn't taken for one.
+///
+/// # Example
+/// ```
+/// use pinterest_login::outage::is_outage_page;
+///
+/// let maintenance = "<html><head><title>Pinterest</title></head>
+///     <body><h1>We’ll be back soon!</h1><p>Pinterest is down for maintenance.</p></body></html>";
+/// assert!(is_outage_page(maintenance));
+///
+/// // The messages of the app aren't visible
+/// let loading = r#"<html><body><div id="root"></div>
+///     <script>window.i18n = {"error": "Service unavailable"};</script></body></html>"#;
+/// assert!(!is_outage_page(loading));
+///
+/// let login = r#"<html><body><form><input id="email"><input id="password" type="password">
+///     <p>Something went wrong: the service is temporarily unavailable</p></form></body></html>"#;
+/// assert!(!is_outage_page(login));
+/// ```
+pub fn is_outage_page(html: &str) -> bool {
+    let html = html.to_lowercase();
+    if has_password_field(&html) {
+        return false;
+    }
+    let text = visible_text(&html).replace('\u{2019}', "'");
+    OUTAGE_MARKERS.iter().any(|marker| text.contains(marker))
+}
+
+/// Parses the `Retry-After` header of an error page, in seconds.
+///
+/// The HTTP dates aren't supported, pinterest sends the seconds.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use pinterest_login::outage::parse_retry_after;
+///
+/// assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+/// assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+/// ```
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Fails with [`PinterestLoginError::ServiceUnavailable`] if the document request of the login page got a server
+/// error, with the `Retry-After` of the response as the retry hint.
+///
+/// The responses of the other requests (like an image or an API call of the page) are ignored.
+pub(crate) fn check_document(
+    request: Option<&HttpRequest>,
+    document_url: Option<&str>,
+) -> crate::Result<()> {
+    let Some(response) = request.and_then(|request| request.response.as_ref()) else {
+        return Ok(());
+    };
+    if document_url != Some(response.url.as_str()) || !is_outage_status(response.status) {
+        return Ok(());
+    }
+    let retry_hint = response
+        .headers
+        .inner()
+        .as_object()
+        .and_then(|headers| {
+            headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+        })
+        .and_then(|(_, value)| value.as_str())
+        .and_then(parse_retry_after);
+    Err(PinterestLoginError::ServiceUnavailable { retry_hint })
+}
+
+/// Fails with [`PinterestLoginError::ServiceUnavailable`] if the page is a maintenance or an error page, see
+/// [`is_outage_page`].
+///
+/// The page may be navigating, so failing to get its content isn't an error.
+pub(crate) async fn check_page<P: LoginPage>(page: &P) -> crate::Result<()> {
+    match page.content().await {
+        Ok(html) if is_outage_page(&html) => {
+            Err(PinterestLoginError::ServiceUnavailable { retry_hint: None })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns `true` if the lowercased HTML has an `<input type="password">`
+pub(crate) fn has_password_field(html: &str) -> bool {
+    html.match_indices("<input").any(|(start, _)| {
+        let tag = &html[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        ["type=\"password\"", "type='password'", "type=password"]
+            .iter()
+            .any(|attribute| tag.contains(attribute))
+    })
+}
+
+/// The text of the HTML without its tags, its scripts and its styles
+pub(crate) fn visible_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        rest = &rest[start..];
+        if let Some(element) = ["<script", "<style"]
+            .iter()
+            .find(|element| rest.starts_with(*element))
+        {
+            // An unclosed script hides the rest of the page
+            let close = format!("</{}", &element[1..]);
+            rest = rest.find(&close).map_or("", |end| &rest[end..]);
+        }
+        rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+    }
+    text.push_str(rest);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
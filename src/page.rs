@@ -0,0 +1,165 @@
+use std::future::Future;
+
+use chromiumoxide::{
+    cdp::browser_protocol::network::Cookie, error::CdpError, keys, layout::BoundingBox, Element,
+    Page,
+};
+
+/// The operations that the login bots need from the login page.
+///
+/// It's implemented for the chromiumoxide [`Page`], and it lets the bots that are written against it
+/// (see [`PageLoginBot`](crate::login_bot::PageLoginBot)) be tested without a browser,
+/// with the `MockPage` of the `test-util` feature for example.
+pub trait LoginPage: Send + Sync {
+    /// The handle of an element of the page
+    type Element: LoginElement;
+
+    /// Finds the first element that matches the CSS selector
+    fn find_element(
+        &self,
+        selector: &str,
+    ) -> impl Future<Output = crate::Result<Self::Element>> + Send;
+    /// Finds all the elements that match the XPath
+    fn find_xpaths(
+        &self,
+        xpath: &str,
+    ) -> impl Future<Output = crate::Result<Vec<Self::Element>>> + Send;
+    /// Waits for the pending navigation of the page (if any) to finish
+    fn wait_for_navigation(&self) -> impl Future<Output = crate::Result<()>> + Send;
+    /// The current url of the page, if it has one
+    fn url(&self) -> impl Future<Output = crate::Result<Option<String>>> + Send;
+    /// The HTML content of the page
+    fn content(&self) -> impl Future<Output = crate::Result<String>> + Send;
+    /// The cookies of the page
+    fn get_cookies(&self) -> impl Future<Output = crate::Result<Vec<Cookie>>> + Send;
+}
+
+/// The operations that the login bots need from an element of the login page, see [`LoginPage`]
+pub trait LoginElement: Send + Sync {
+    /// Focuses the element and types the text into it
+    fn type_str(&self, text: &str) -> impl Future<Output = crate::Result<()>> + Send;
+    /// Clicks the element
+    fn click(&self) -> impl Future<Output = crate::Result<()>> + Send;
+    /// Focuses the element
+    fn focus(&self) -> impl Future<Output = crate::Result<()>> + Send;
+    /// The position and the size of the element
+    fn bounding_box(&self) -> impl Future<Output = crate::Result<BoundingBox>> + Send;
+}
+
+impl LoginPage for Page {
+    type Element = Element;
+
+    async fn find_element(&self, selector: &str) -> crate::Result<Element> {
+        Ok(Page::find_element(self, selector).await?)
+    }
+
+    async fn find_xpaths(&self, xpath: &str) -> crate::Result<Vec<Element>> {
+        Ok(Page::find_xpaths(self, xpath).await?)
+    }
+
+    async fn wait_for_navigation(&self) -> crate::Result<()> {
+        Page::wait_for_navigation(self).await?;
+        Ok(())
+    }
+
+    async fn url(&self) -> crate::Result<Option<String>> {
+        Ok(Page::url(self).await?)
+    }
+
+    async fn content(&self) -> crate::Result<String> {
+        Ok(Page::content(self).await?)
+    }
+
+    async fn get_cookies(&self) -> crate::Result<Vec<Cookie>> {
+        Ok(Page::get_cookies(self).await?)
+    }
+}
+
+impl LoginElement for Element {
+    async fn type_str(&self, text: &str) -> crate::Result<()> {
+        // The keyboard characters are typed key by key like a human would, the rest is inserted at once,
+        // since CDP can only synthesize the keys of the US keyboard layout
+        for (typeable, run) in typing_runs(text) {
+            if typeable {
+                Element::type_str(self, run).await?;
+            } else {
+                insert_text(self, run).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn click(&self) -> crate::Result<()> {
+        Element::click(self).await?;
+        Ok(())
+    }
+
+    async fn focus(&self) -> crate::Result<()> {
+        Element::focus(self).await?;
+        Ok(())
+    }
+
+    async fn bounding_box(&self) -> crate::Result<BoundingBox> {
+        Ok(Element::bounding_box(self).await?)
+    }
+}
+
+/// Returns `true` if the character can be typed with a synthesized keystroke, i.e. it's a printable character of
+/// the US keyboard layout. The others (like an emoji, a `§` or a tab, that would move the focus) are inserted as text.
+///
+/// # Example
+/// ```
+/// use pinterest_login::page::is_typeable;
+///
+/// for c in ['a', 'Z', '0', '@', '.', '!', '~', ' '] {
+///     assert!(is_typeable(c), "{c:?}");
+/// }
+/// for c in ['§', 'é', '\u{301}', '🔑', 'ß', '\t', '\n', '\r'] {
+///     assert!(!is_typeable(c), "{c:?}");
+/// }
+/// ```
+pub fn is_typeable(c: char) -> bool {
+    (c.is_ascii_graphic() || c == ' ')
+        && keys::get_key_definition(c.encode_utf8(&mut [0; 4])).is_some()
+}
+
+/// Splits the text into the runs of typeable and not typeable characters, see [`is_typeable`]
+fn typing_runs(text: &str) -> impl Iterator<Item = (bool, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let typeable = is_typeable(rest.chars().next()?);
+        let end = rest
+            .char_indices()
+            .find(|(_, c)| is_typeable(*c) != typeable)
+            .map_or(rest.len(), |(i, _)| i);
+        let (run, tail) = rest.split_at(end);
+        rest = tail;
+        Some((typeable, run))
+    })
+}
+
+/// Inserts the text at the cursor of the element, like a paste, firing the same input events as typing
+async fn insert_text(element: &Element, text: &str) -> crate::Result<()> {
+    // Every character is escaped, so the text can't break out of the string literal
+    let literal: String = text
+        .chars()
+        .map(|c| format!("\\u{{{:x}}}", c as u32))
+        .collect();
+    let function = format!(
+        r#"function() {{
+            this.focus();
+            const text = "{literal}";
+            if (!document.execCommand("insertText", false, text)) {{
+                const start = this.selectionStart ?? this.value.length;
+                const end = this.selectionEnd ?? start;
+                this.setRangeText(text, start, end, "end");
+                this.dispatchEvent(new InputEvent("input", {{ bubbles: true, inputType: "insertText", data: text }}));
+            }}
+        }}"#
+    );
+    let res = element.call_js_fn(function, false).await?;
+    match res.exception_details {
+        Some(exception) => Err(CdpError::ChromeMessage(exception.text).into()),
+        None => Ok(()),
+    }
+}
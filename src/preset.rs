@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use crate::fingerprint::{Fingerprint, TypingTimings, Viewport};
+
+/// A posture of the login, that sets coherent defaults for many knobs at once, with
+/// [`DefaultBrowserConfigBuilder::preset`](crate::config_builder::DefaultBrowserConfigBuilder::preset) and
+/// [`LoginOptions::preset`](crate::options::LoginOptions::preset).
+///
+/// A preset only sets the knobs listed below, and any setter called after it overrides its value. The knobs that are
+/// about the machine rather than the posture (the executable, the proxy, the profile directory, the launcher, ...) are
+/// left alone.
+///
+/// | Knob | [`Fast`](Preset::Fast) | [`Stealthy`](Preset::Stealthy) | [`Debug`](Preset::Debug) |
+/// |---|---|---|---|
+/// | headless | yes | yes | no |
+/// | request timeout | 3s | 10s | 30s |
+/// | launch timeout | 10s | 20s | 60s |
+/// | images | off | on | on |
+/// | WebRTC protection | off | on | off |
+/// | window size | Chrome's | 1920x1080 | Chrome's |
+/// | launch and navigation retries | none | 2, 500ms apart | 2, 500ms apart |
+/// | fingerprint | none | [`stealthy_fingerprint`] | none |
+/// | failure screenshots | none | none | into [`debug_screenshot_dir`] |
+/// | keep-alive pings | the flow's | the flow's | every 30s |
+///
+/// The typing pace lives on the login bot, pass the one of [`stealthy_fingerprint`] to
+/// [`DefaultBrowserLoginBot::with_typing`](crate::login_bot::DefaultBrowserLoginBot::with_typing) for a humanized
+/// input. The verbose logs of [`Preset::Debug`] come from the `log` feature, with `RUST_LOG=pinterest_login=trace`.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// use pinterest_login::options::LoginOptions;
+/// use pinterest_login::preset::Preset;
+///
+/// // Stealthy, but with a longer request timeout
+/// let config_builder = DefaultBrowserConfigBuilder::default()
+///     .preset(Preset::Stealthy)
+///     .with_request_timeout(Some(Duration::from_secs(20)));
+/// let options = LoginOptions::new().preset(Preset::Stealthy);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Preset {
+    /// As fast as possible: no images, short timeouts and no retries
+    Fast,
+    /// As hard to tell from a human as possible: a common desktop fingerprint and window, and no leak of the real IP
+    /// address through WebRTC
+    Stealthy,
+    /// As easy to see what went wrong as possible: a visible browser, long timeouts and the failure screenshots
+    Debug,
+}
+
+impl Preset {
+    /// The request timeout of the browser
+    pub(crate) fn request_timeout(self) -> Duration {
+        match self {
+            Self::Fast => Duration::from_secs(3),
+            Self::Stealthy => Duration::from_secs(10),
+            Self::Debug => Duration::from_secs(30),
+        }
+    }
+
+    /// The launch timeout of the browser
+    pub(crate) fn launch_timeout(self) -> Duration {
+        match self {
+            Self::Fast => Duration::from_secs(10),
+            Self::Stealthy => Duration::from_secs(20),
+            Self::Debug => Duration::from_secs(60),
+        }
+    }
+}
+
+/// The fingerprint of [`Preset::Stealthy`]: the most common desktop Chrome on Windows, in a full HD viewport, that
+/// types a character every 100ms give or take 40ms
+pub fn stealthy_fingerprint() -> Fingerprint {
+    Fingerprint {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36".to_string(),
+        viewport: Viewport {
+            width: 1920,
+            height: 1080,
+        },
+        typing: TypingTimings::new(Duration::from_millis(100), Duration::from_millis(40), 0),
+    }
+}
+
+/// The directory of the failure screenshots of [`Preset::Debug`], `pinterest-login` in the temporary directory
+pub fn debug_screenshot_dir() -> std::path::PathBuf {
+    std::env::temp_dir().join("pinterest-login")
+}
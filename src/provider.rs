@@ -0,0 +1,88 @@
+use futures::{future::BoxFuture, FutureExt};
+
+use crate::{
+    config_builder::BrowserConfigBuilder, login_bot::BrowserLoginBot, login_with_options,
+    options::LoginOptions, report::LoginOutcome,
+};
+
+/// Something that logs in: the [`BrowserLogin`] of the crate, or a test double like the
+/// [`MockLogin`](crate::mock::MockLogin) of the `mock` feature.
+///
+/// The code that logs in can be generic over it (or take a `&dyn LoginProvider`), so it can be tested without a
+/// browser or an account.
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// use pinterest_login::options::LoginOptions;
+/// use pinterest_login::provider::{BrowserLogin, LoginProvider};
+///
+/// async fn session_cookie(provider: &dyn LoginProvider) -> pinterest_login::Result<Option<String>> {
+///     let outcome = provider.login(&LoginOptions::new()).await?;
+///     Ok(outcome.cookies.get("_pinterest_sess").cloned())
+/// }
+///
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let provider = BrowserLogin::new(
+///     DefaultBrowserLoginBot::new("me@example.com", "hunter2"),
+///     DefaultBrowserConfigBuilder::default(),
+/// );
+/// println!("{:?}", session_cookie(&provider).await?);
+/// # Ok(())
+/// # }
+/// ```
+pub trait LoginProvider: Send + Sync {
+    /// Logs in with the options, like [`login_with_options`]
+    fn login<'a>(&'a self, options: &'a LoginOptions)
+        -> BoxFuture<'a, crate::Result<LoginOutcome>>;
+}
+
+impl<T: LoginProvider + ?Sized> LoginProvider for std::sync::Arc<T> {
+    fn login<'a>(
+        &'a self,
+        options: &'a LoginOptions,
+    ) -> BoxFuture<'a, crate::Result<LoginOutcome>> {
+        (**self).login(options)
+    }
+}
+
+/// The login in a browser, with the login bot and the config builder of [`login_with_options`]
+#[derive(Debug, Clone)]
+pub struct BrowserLogin<B, C> {
+    bot: B,
+    config_builder: C,
+}
+
+impl<B, C> BrowserLogin<B, C> {
+    /// Logs in with the bot, in the browsers of the config builder
+    pub fn new(bot: B, config_builder: C) -> Self {
+        Self {
+            bot,
+            config_builder,
+        }
+    }
+
+    /// The login bot
+    pub fn bot(&self) -> &B {
+        &self.bot
+    }
+
+    /// The config builder
+    pub fn config_builder(&self) -> &C {
+        &self.config_builder
+    }
+}
+
+impl<B, C> LoginProvider for BrowserLogin<B, C>
+where
+    B: BrowserLoginBot + Send + Sync,
+    C: BrowserConfigBuilder + Send + Sync,
+{
+    fn login<'a>(
+        &'a self,
+        options: &'a LoginOptions,
+    ) -> BoxFuture<'a, crate::Result<LoginOutcome>> {
+        login_with_options(&self.bot, &self.config_builder, options).boxed()
+    }
+}
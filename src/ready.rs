@@ -0,0 +1,145 @@
+use std::{collections::HashSet, time::Duration};
+
+use chromiumoxide::{
+    cdp::browser_protocol::network::{
+        EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent, RequestId,
+    },
+    Page,
+};
+use futures::{
+    future::{select, Either},
+    stream::BoxStream,
+    StreamExt,
+};
+#[cfg(feature = "log")]
+use log::debug;
+
+use crate::{
+    clock::Clock,
+    options::{ReadyWhen, READY_SELECTOR_TIMEOUT},
+    page::LoginPage,
+    report::ReadyReport,
+};
+
+/// The delay between two lookups of the selector of [`ReadyWhen::Selector`]
+const SELECTOR_POLL: Duration = Duration::from_millis(100);
+
+/// A request of the page that started or ended
+enum Activity {
+    Started(RequestId),
+    Ended(RequestId),
+}
+
+/// The network activity of the page, followed from before the navigation so its requests are all seen
+pub(crate) struct NetworkWatch(BoxStream<'static, Activity>);
+
+impl NetworkWatch {
+    /// Starts following the requests of the page if the strategy needs them, the other strategies send nothing to
+    /// the browser
+    pub(crate) async fn start(page: &Page, ready_when: &ReadyWhen) -> crate::Result<Option<Self>> {
+        if !matches!(ready_when, ReadyWhen::NetworkIdle { .. }) {
+            return Ok(None);
+        }
+        let started = page
+            .event_listener::<EventRequestWillBeSent>()
+            .await?
+            .map(|e| Activity::Started(e.request_id.clone()));
+        let finished = page
+            .event_listener::<EventLoadingFinished>()
+            .await?
+            .map(|e| Activity::Ended(e.request_id.clone()));
+        let failed = page
+            .event_listener::<EventLoadingFailed>()
+            .await?
+            .map(|e| Activity::Ended(e.request_id.clone()));
+        Ok(Some(Self(
+            futures::stream::select_all([started.boxed(), finished.boxed(), failed.boxed()])
+                .boxed(),
+        )))
+    }
+}
+
+/// Waits for the page to be ready as the strategy says, the `network` is the one of [`NetworkWatch::start`]
+pub(crate) async fn wait_until_ready<P: LoginPage>(
+    page: &P,
+    ready_when: &ReadyWhen,
+    network: Option<NetworkWatch>,
+    clock: &dyn Clock,
+) -> crate::Result<ReadyReport> {
+    let start = clock.now();
+    let timed_out = match (ready_when, network) {
+        (ReadyWhen::NetworkIdle { idle_for, max_wait }, Some(network)) => {
+            !network_idle(network, clock, *idle_for, *max_wait).await
+        }
+        (ReadyWhen::Selector(selector), _) => !selector_found(page, selector, clock).await,
+        _ => false,
+    };
+    let report = ReadyReport {
+        strategy: ready_when.clone(),
+        waited: clock.now().duration_since(start),
+        timed_out,
+    };
+    #[cfg(feature = "log")]
+    debug!(
+        "The login page was ready with {:?} after {:?} (timed out: {})",
+        report.strategy, report.waited, report.timed_out
+    );
+    Ok(report)
+}
+
+/// Waits for no request to be in flight for `idle_for`, returns `false` if it didn't happen within `max_wait`
+async fn network_idle(
+    mut network: NetworkWatch,
+    clock: &dyn Clock,
+    idle_for: Duration,
+    max_wait: Duration,
+) -> bool {
+    let start = clock.now();
+    let mut in_flight = HashSet::new();
+    let mut idle_since = start;
+    loop {
+        let now = clock.now();
+        let elapsed = now.duration_since(start);
+        if in_flight.is_empty() && now.duration_since(idle_since) >= idle_for {
+            return true;
+        }
+        if elapsed >= max_wait {
+            return false;
+        }
+        let wait = match in_flight.is_empty() {
+            true => idle_for - now.duration_since(idle_since),
+            false => max_wait - elapsed,
+        };
+        match select(network.0.next(), clock.sleep(wait.min(max_wait - elapsed))).await {
+            Either::Left((Some(Activity::Started(id)), _)) => {
+                in_flight.insert(id);
+            }
+            Either::Left((Some(Activity::Ended(id)), _)) => {
+                // The requests of a redirect end once, and the ones from before the watch never started
+                if in_flight.remove(&id) && in_flight.is_empty() {
+                    idle_since = clock.now();
+                }
+            }
+            // The page is gone, the next phase will tell why
+            Either::Left((None, _)) => return true,
+            Either::Right(_) => {}
+        }
+    }
+}
+
+/// Waits for an element to match the selector, returns `false` if none did within [`READY_SELECTOR_TIMEOUT`]
+async fn selector_found<P: LoginPage>(page: &P, selector: &str, clock: &dyn Clock) -> bool {
+    let start = clock.now();
+    loop {
+        if page.find_element(selector).await.is_ok() {
+            return true;
+        }
+        let elapsed = clock.now().duration_since(start);
+        if elapsed >= READY_SELECTOR_TIMEOUT {
+            return false;
+        }
+        clock
+            .sleep(SELECTOR_POLL.min(READY_SELECTOR_TIMEOUT - elapsed))
+            .await;
+    }
+}
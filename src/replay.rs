@@ -0,0 +1,512 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chromiumoxide::{error::CdpError, Browser, BrowserConfig, Handler};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+use crate::launcher::BrowserLauncher;
+
+/// The placeholder of the scrubbed values in a [`Recording`], it matches any value when replayed
+pub const SCRUBBED: &str = "<scrubbed>";
+
+/// How long the replay waits for the next expected message
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A message of the CDP exchange between chromiumoxide and the browser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "direction", content = "message")]
+pub enum Exchange {
+    /// A command that chromiumoxide sent to the browser
+    Sent(Value),
+    /// A response or an event that the browser sent to chromiumoxide
+    Received(Value),
+}
+
+/// A recorded CDP exchange, in order, see [`RecordingLauncher`] and [`ReplayLauncher`]
+///
+/// It's stored as a pretty JSON array, so the fixtures can be reviewed (and edited) by hand.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Recording {
+    /// The exchanged messages
+    pub exchanges: Vec<Exchange>,
+}
+
+impl Recording {
+    /// Reads a recording from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(std::io::Error::from)
+    }
+
+    /// Writes the recording to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        std::fs::write(path, json + "\n")
+    }
+}
+
+/// Records the CDP exchange of the browsers launched by another launcher, by sitting between chromiumoxide and the browser.
+///
+/// The typed text, the cookies and the given secrets are scrubbed from the recording.
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::dry_run::dry_run;
+/// # use pinterest_login::launcher::ChromiumLauncher;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::options::LoginOptions;
+/// use pinterest_login::replay::RecordingLauncher;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let launcher = RecordingLauncher::new(ChromiumLauncher).scrub("hunter2");
+/// let options = LoginOptions::new().launcher(launcher.clone());
+/// let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+///
+/// dry_run(&bot, &DefaultBrowserConfigBuilder::default(), &options).await?;
+/// launcher.recording().save("login-success.json")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RecordingLauncher {
+    inner: Arc<dyn BrowserLauncher>,
+    secrets: Vec<String>,
+    exchanges: Arc<Mutex<Vec<Exchange>>>,
+}
+
+impl RecordingLauncher {
+    /// Records the browsers of the given launcher
+    pub fn new(inner: impl BrowserLauncher + 'static) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            secrets: Vec::new(),
+            exchanges: Arc::default(),
+        }
+    }
+
+    /// Scrubs every string that contains the secret (e.g. a password in a form url) from the recording
+    pub fn scrub(mut self, secret: impl Into<String>) -> Self {
+        self.secrets.push(secret.into());
+        self
+    }
+
+    /// The scrubbed recording of everything that was exchanged so far
+    pub fn recording(&self) -> Recording {
+        let exchanges = self.exchanges.lock().unwrap_or_else(|e| e.into_inner());
+        Recording {
+            exchanges: exchanges
+                .iter()
+                .cloned()
+                .map(|exchange| match exchange {
+                    Exchange::Sent(mut msg) => {
+                        scrub(&mut msg, &self.secrets, None);
+                        Exchange::Sent(msg)
+                    }
+                    Exchange::Received(mut msg) => {
+                        scrub(&mut msg, &self.secrets, None);
+                        Exchange::Received(msg)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl BrowserLauncher for RecordingLauncher {
+    fn launch(&self, config: BrowserConfig) -> BoxFuture<'_, crate::Result<(Browser, Handler)>> {
+        Box::pin(async move {
+            let (browser, handler) = self.inner.launch(config).await?;
+            let target = browser.websocket_address().clone();
+            let listener = TcpListener::bind("127.0.0.1:0").map_err(CdpError::from)?;
+            let url = format!("ws://{}", listener.local_addr().map_err(CdpError::from)?);
+            let exchanges = Arc::clone(&self.exchanges);
+            std::thread::spawn(move || {
+                // The original connection is never used, but it owns the browser process,
+                // which gets killed when it's dropped at the end of the recording
+                let _browser = (browser, handler);
+                let _ = proxy(listener, &target, &exchanges);
+            });
+            Ok(Browser::connect(url).await?)
+        })
+    }
+}
+
+/// Forwards the messages between chromiumoxide and the browser, recording them, until one of them disconnects
+fn proxy(
+    listener: TcpListener,
+    target: &str,
+    exchanges: &Mutex<Vec<Exchange>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (stream, _) = listener.accept()?;
+    let mut client = tungstenite::accept(stream)?;
+    let (mut browser, _) = tungstenite::connect(target)?;
+    client.get_mut().set_nonblocking(true)?;
+    if let MaybeTlsStream::Plain(stream) = browser.get_mut() {
+        stream.set_nonblocking(true)?;
+    }
+
+    loop {
+        let sent = pump(&mut client, &mut browser, exchanges, Exchange::Sent)?;
+        let received = pump(&mut browser, &mut client, exchanges, Exchange::Received)?;
+        if !sent && !received {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+/// Forwards the next message (if any), returns `false` if there was nothing to forward
+fn pump(
+    from: &mut impl Socket,
+    to: &mut impl Socket,
+    exchanges: &Mutex<Vec<Exchange>>,
+    exchange: fn(Value) -> Exchange,
+) -> tungstenite::Result<bool> {
+    let Some(text) = from.read_text()? else {
+        to.flush()?;
+        return Ok(false);
+    };
+    if let Ok(msg) = serde_json::from_str(&text) {
+        exchanges
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(exchange(msg));
+    }
+    to.send_text(text)?;
+    Ok(true)
+}
+
+/// A non-blocking websocket, that ends the proxy with an error once it's closed
+trait Socket {
+    fn read_text(&mut self) -> tungstenite::Result<Option<String>>;
+    fn send_text(&mut self, text: String) -> tungstenite::Result<()>;
+    fn flush(&mut self) -> tungstenite::Result<()>;
+}
+
+impl<S: std::io::Read + std::io::Write> Socket for WebSocket<S> {
+    fn read_text(&mut self) -> tungstenite::Result<Option<String>> {
+        match self.read() {
+            Ok(Message::Text(text)) => Ok(Some(text)),
+            Ok(Message::Close(_)) => Err(tungstenite::Error::ConnectionClosed),
+            Ok(_) => Ok(None),
+            Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn send_text(&mut self, text: String) -> tungstenite::Result<()> {
+        match self.send(Message::Text(text)) {
+            // It's queued, and sent by the next flush
+            Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+            res => res,
+        }
+    }
+
+    fn flush(&mut self) -> tungstenite::Result<()> {
+        match WebSocket::flush(self) {
+            Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+            res => res,
+        }
+    }
+}
+
+/// Scrubs the typed text, the cookies and the strings that contain a secret
+fn scrub(value: &mut Value, secrets: &[String], key: Option<&str>) {
+    match value {
+        Value::String(s) => {
+            let sensitive = matches!(
+                key.map(str::to_ascii_lowercase).as_deref(),
+                Some("cookie" | "set-cookie" | "authorization")
+            );
+            if sensitive || secrets.iter().any(|secret| s.contains(secret.as_str())) {
+                *s = SCRUBBED.to_string();
+            }
+        }
+        Value::Array(values) => {
+            for v in values {
+                scrub(v, secrets, key);
+            }
+        }
+        Value::Object(object) => {
+            let method = object
+                .get("method")
+                .and_then(Value::as_str)
+                .map(str::to_owned);
+            let typed = matches!(
+                method.as_deref(),
+                Some("Input.dispatchKeyEvent" | "Input.insertText" | "Input.imeSetComposition")
+            );
+            let cookie = key == Some("cookies");
+            for (k, v) in object.iter_mut() {
+                if (cookie && k == "value") || (typed && k == "params") {
+                    scrub_all(v);
+                } else {
+                    scrub(v, secrets, Some(k));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Scrubs every string and number in the value (the key codes give away the typed text too),
+/// except the event types
+fn scrub_all(value: &mut Value) {
+    match value {
+        Value::String(_) | Value::Number(_) => *value = SCRUBBED.into(),
+        Value::Array(values) => values.iter_mut().for_each(scrub_all),
+        Value::Object(object) => object
+            .iter_mut()
+            .filter(|(k, _)| *k != "type")
+            .for_each(|(_, v)| scrub_all(v)),
+        _ => {}
+    }
+}
+
+/// Replays a [`Recording`] in place of a browser, to test the whole login without Chrome in a few milliseconds.
+///
+/// chromiumoxide must send exactly the recorded commands, the [`SCRUBBED`] values and the missing fields of the recorded
+/// commands match anything. The commands that chromiumoxide sends concurrently may come in any order, but a recorded
+/// response or event is only replayed once all the commands that were recorded before it were sent.
+/// Anything else fails the replay, which closes the connection
+/// (so the login fails with [`BrowserDisconnected`](crate::PinterestLoginError::BrowserDisconnected)),
+/// and [`ReplayLauncher::verify`] panics with the reason.
+///
+/// The browser config is ignored, but it still has to be built, so give the config builder an executable
+/// (any path will do) on the machines without Chrome.
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::login_with_options;
+/// # use pinterest_login::options::LoginOptions;
+/// use pinterest_login::replay::ReplayLauncher;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let launcher = ReplayLauncher::load("tests/fixtures/replay/login-success.json")?;
+/// let options = LoginOptions::new()
+///     .login_url("http://127.0.0.1:8080/login")
+///     .launcher(launcher.clone());
+/// let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+///
+/// let config_builder = DefaultBrowserConfigBuilder::default().with_executable("chrome");
+///
+/// let outcome = login_with_options(&bot, &config_builder, &options).await;
+/// launcher.verify();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplayLauncher {
+    recording: Arc<Recording>,
+    state: Arc<Mutex<ReplayState>>,
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    replayed: usize,
+    failure: Option<String>,
+}
+
+impl ReplayLauncher {
+    /// Replays the recording
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording: Arc::new(recording),
+            state: Arc::default(),
+        }
+    }
+
+    /// Replays the recording from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Recording::load(path).map(Self::new)
+    }
+
+    /// Panics if the replay failed, or if the recording wasn't replayed till the end
+    #[track_caller]
+    pub fn verify(&self) {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(failure) = &state.failure {
+            panic!("the replay failed: {failure}");
+        }
+        assert_eq!(
+            state.replayed,
+            self.recording.exchanges.len(),
+            "the recording wasn't replayed till the end, the next message is {:?}",
+            self.recording.exchanges.get(state.replayed)
+        );
+    }
+}
+
+impl BrowserLauncher for ReplayLauncher {
+    fn launch(&self, _config: BrowserConfig) -> BoxFuture<'_, crate::Result<(Browser, Handler)>> {
+        Box::pin(async move {
+            let listener = TcpListener::bind("127.0.0.1:0").map_err(CdpError::from)?;
+            let url = format!("ws://{}", listener.local_addr().map_err(CdpError::from)?);
+            let (recording, state) = (Arc::clone(&self.recording), Arc::clone(&self.state));
+            std::thread::spawn(move || {
+                let mut client = None;
+                if let Err(failure) = replay(listener, &mut client, &recording, &state) {
+                    state.lock().unwrap_or_else(|e| e.into_inner()).failure = Some(failure);
+                }
+                // The failure is stored before the connection is closed, so it's there when the login fails
+                drop(client);
+            });
+            Ok(Browser::connect(url).await?)
+        })
+    }
+}
+
+fn replay(
+    listener: TcpListener,
+    client: &mut Option<WebSocket<TcpStream>>,
+    recording: &Recording,
+    state: &Mutex<ReplayState>,
+) -> Result<(), String> {
+    let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+    let client = client.insert(tungstenite::accept(stream).map_err(|e| e.to_string())?);
+    client
+        .get_mut()
+        .set_read_timeout(Some(REPLAY_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    let exchanges = &recording.exchanges;
+    let mut matched = vec![false; exchanges.len()];
+    // The recorded command ids, mapped to the actual ones
+    let mut ids = HashMap::<u64, u64>::new();
+    // Everything before it is done
+    let mut next = 0;
+    loop {
+        // Replay the responses and the events once all the commands before them were sent
+        while let Some(exchange) = exchanges.get(next) {
+            match exchange {
+                Exchange::Sent(_) if matched[next] => {}
+                Exchange::Sent(_) => break,
+                Exchange::Received(msg) => {
+                    let mut msg = msg.clone();
+                    if let Some(id) = msg.get_mut("id") {
+                        let actual = id.as_u64().and_then(|id| ids.get(&id).copied());
+                        *id = actual
+                            .ok_or_else(|| {
+                                format!("#{next}: the response id {id} wasn't sent before")
+                            })?
+                            .into();
+                    }
+                    // Counted before it's sent, the client may be done as soon as it gets it
+                    state.lock().unwrap_or_else(|e| e.into_inner()).replayed = next + 1;
+                    client
+                        .send(Message::Text(msg.to_string()))
+                        .map_err(|e| format!("#{next}: couldn't send the message ({e})"))?;
+                }
+            }
+            next += 1;
+            state.lock().unwrap_or_else(|e| e.into_inner()).replayed = next;
+        }
+        // The loop above only stops at a command that wasn't sent yet
+        let Some(Exchange::Sent(expected)) = exchanges.get(next) else {
+            break;
+        };
+
+        let actual = match read_message(client) {
+            Ok(Some(actual)) => actual,
+            Ok(None) => {
+                return Err(format!(
+                    "#{next}: expected {expected}, but the client disconnected"
+                ))
+            }
+            Err(e) => {
+                return Err(format!(
+                    "#{next}: expected {expected}, but got nothing ({e})"
+                ))
+            }
+        };
+        // chromiumoxide sends some commands concurrently, so they may come in any order
+        let found = (next..exchanges.len()).find(|&i| match &exchanges[i] {
+            Exchange::Sent(recorded) if !matched[i] => {
+                let mut expected = recorded.clone();
+                if let Value::Object(object) = &mut expected {
+                    object.remove("id");
+                }
+                matches(&expected, &actual)
+            }
+            _ => false,
+        });
+        let Some(i) = found else {
+            return Err(format!(
+                "#{next}: expected {expected}, but the client sent {actual}"
+            ));
+        };
+        matched[i] = true;
+        if let (Some(Exchange::Sent(expected)), Some(actual_id)) =
+            (exchanges.get(i), actual.get("id").and_then(Value::as_u64))
+        {
+            if let Some(expected_id) = expected.get("id").and_then(Value::as_u64) {
+                ids.insert(expected_id, actual_id);
+            }
+        }
+    }
+
+    // The recording is over, the client shouldn't send anything else until it disconnects
+    loop {
+        match read_message(client) {
+            Ok(Some(msg)) => {
+                return Err(format!(
+                    "unexpected message after the end of the recording: {msg}"
+                ))
+            }
+            Ok(None) => return Ok(()),
+            // Nothing was sent, keep waiting for the disconnection
+            Err(_) => {}
+        }
+    }
+}
+
+/// Reads the next JSON message, or `None` if the client disconnected
+fn read_message(client: &mut WebSocket<TcpStream>) -> Result<Option<Value>, String> {
+    loop {
+        match client.read() {
+            Ok(Message::Text(text)) => {
+                return serde_json::from_str(&text)
+                    .map(Some)
+                    .map_err(|e| format!("invalid message {text}: {e}"))
+            }
+            Ok(Message::Close(_))
+            | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return Ok(None)
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if e.kind() != ErrorKind::WouldBlock && e.kind() != ErrorKind::TimedOut =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Returns `true` if every field of `expected` matches the same field of `actual`
+fn matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::String(s), _) if s == SCRUBBED => true,
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .all(|(k, v)| actual.get(k).is_some_and(|a| matches(v, a))),
+        (Value::Array(expected), Value::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual).all(|(e, a)| matches(e, a))
+        }
+        _ => expected == actual,
+    }
+}
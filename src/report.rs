@@ -0,0 +1,81 @@
+use chromiumoxide::cdp::browser_protocol::network::Cookie;
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::{options::ReadyWhen, selectors::SelectorMatch, PinterestCookies};
+
+/// What happened during a successful login, useful to spot the flakiness in the wild
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LoginReport {
+    /// How many times the browser was launched, 1 if the first attempt succeeded
+    pub launch_attempts: u32,
+    /// How many times the login page was navigated to, 1 if the first attempt succeeded
+    pub navigation_attempts: u32,
+    /// How many keep-alive pings were sent to the browser, see [`LoginOptions::keep_alive`](crate::options::LoginOptions::keep_alive)
+    pub keep_alive_pings: u32,
+    /// Whether the browser was already logged in when it got to the login page (a persistent profile or seeded
+    /// cookies), so the login form was skipped, see
+    /// [`LoginOptions::on_already_authenticated`](crate::options::LoginOptions::on_already_authenticated)
+    pub already_authenticated: bool,
+    /// The selectors that found the fields of the login form, see [`LoginSelectors`](crate::selectors::LoginSelectors).
+    /// It's empty if the form was skipped, or if the login bot doesn't report them
+    pub selectors: Vec<SelectorMatch>,
+    /// How the login waited for the login page to be ready, see
+    /// [`LoginOptions::ready_when`](crate::options::LoginOptions::ready_when)
+    pub ready: ReadyReport,
+}
+
+/// How the login waited for the login page to be ready after the navigation, see [`LoginReport::ready`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReadyReport {
+    /// The strategy of the wait
+    pub strategy: ReadyWhen,
+    /// How long the wait took, after the navigation
+    pub waited: Duration,
+    /// Whether the page wasn't ready at the end of the maximum wait, the login went on anyway
+    pub timed_out: bool,
+}
+
+impl Default for LoginReport {
+    fn default() -> Self {
+        Self {
+            launch_attempts: 1,
+            navigation_attempts: 1,
+            keep_alive_pings: 0,
+            already_authenticated: false,
+            selectors: Vec::new(),
+            ready: ReadyReport::default(),
+        }
+    }
+}
+
+/// The cookies of a successful login, with its report
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LoginOutcome {
+    /// The pinterest cookies
+    pub cookies: PinterestCookies,
+    /// The same cookies with all their metadata (domain, path, expiry, flags...), as reported by the browser
+    pub cookie_details: Vec<Cookie>,
+    /// What happened during the login
+    pub report: LoginReport,
+    /// The web storage of the page after the login, `None` unless
+    /// [`LoginOptions::capture_storage`](crate::options::LoginOptions::capture_storage) is set
+    pub storage: Option<WebStorage>,
+}
+
+/// The `localStorage` and the `sessionStorage` of the page after the login, see [`LoginOutcome::storage`]
+///
+/// The values are the strings that the page stored, they aren't parsed even when they're JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WebStorage {
+    /// The origin that the storages belong to, like `https://www.pinterest.com`
+    pub origin: String,
+    /// The items of the `localStorage`, by key
+    pub local: HashMap<String, String>,
+    /// The items of the `sessionStorage`, by key
+    pub session: HashMap<String, String>,
+}
@@ -0,0 +1,85 @@
+use std::time::SystemTime;
+
+use reqwest::{cookie::Jar, Url};
+
+use crate::{cookies::PinterestCookie, PinterestCookies};
+
+/// The hosts that the cookies of [`into_reqwest_jar`] are sent to: the website, and its internal API
+pub const PINTEREST_URLS: [&str; 2] = ["https://www.pinterest.com/", "https://api.pinterest.com/"];
+
+/// A reqwest cookie store with the cookies of a login, like the ones of [`login`](crate::login), for the requests to
+/// pinterest with `reqwest::ClientBuilder::cookie_provider`.
+///
+/// The map has no domains, so the cookies are put on `pinterest.com`, which sends them to all its subdomains like the
+/// ones of [`PINTEREST_URLS`], on the path `/`, and only over HTTPS. A cookie that can't be in a `Cookie` header,
+/// like one with a `;` in its value, is left out, see [`cookie_header`](crate::api::cookie_header).
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+///
+/// use reqwest::cookie::CookieStore;
+/// use pinterest_login::reqwest_cookies::into_reqwest_jar;
+///
+/// let cookies = [("_pinterest_sess".to_string(), "sess".to_string())].into();
+/// let jar = Arc::new(into_reqwest_jar(&cookies));
+///
+/// let api = "https://api.pinterest.com/v3/users/me/".parse()?;
+/// assert_eq!(jar.cookies(&api).unwrap(), "_pinterest_sess=sess");
+///
+/// let client = reqwest::Client::builder().cookie_provider(jar).build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn into_reqwest_jar(cookies: &PinterestCookies) -> Jar {
+    let jar = Jar::default();
+    let url: Url = PINTEREST_URLS[0]
+        .parse()
+        .expect("the pinterest url is valid");
+    for (name, value) in cookies {
+        jar.add_cookie_str(
+            &format!("{name}={value}; Domain=pinterest.com; Path=/; Secure"),
+            &url,
+        );
+    }
+    jar
+}
+
+/// Same as [`into_reqwest_jar`], with the cookies of [`login_full`](crate::login_full), that are each scoped to their
+/// own domain and path like a browser does:
+/// * the domains with a leading dot are sent to their subdomains, the other ones only to their host
+/// * the secure cookies are only sent over HTTPS
+/// * the session cookies never expire in the jar, and the expired ones aren't added
+pub fn reqwest_jar(cookies: &[PinterestCookie]) -> Jar {
+    let jar = Jar::default();
+    add_to_reqwest_jar(&jar, cookies);
+    jar
+}
+
+/// Adds the cookies to a reqwest cookie store, like [`reqwest_jar`] does
+pub fn add_to_reqwest_jar(jar: &Jar, cookies: &[PinterestCookie]) {
+    let now = SystemTime::now();
+    for cookie in cookies {
+        let host = cookie.domain.trim_start_matches('.');
+        let scheme = if cookie.secure { "https" } else { "http" };
+        let Ok(url) = Url::parse(&format!("{scheme}://{host}{}", cookie.path)) else {
+            continue;
+        };
+
+        let mut set_cookie = format!("{}={}; Path={}", cookie.name, cookie.value, cookie.path);
+        if cookie.domain.starts_with('.') {
+            set_cookie.push_str(&format!("; Domain={host}"));
+        }
+        if let Some(expires) = cookie.expires {
+            // A relative expiry, since a date would need its own formatting
+            let max_age = expires.duration_since(now).map_or(0, |left| left.as_secs());
+            set_cookie.push_str(&format!("; Max-Age={max_age}"));
+        }
+        if cookie.secure {
+            set_cookie.push_str("; Secure");
+        }
+        if cookie.http_only {
+            set_cookie.push_str("; HttpOnly");
+        }
+        jar.add_cookie_str(&set_cookie, &url);
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    future::Future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chromiumoxide::error::CdpError;
+#[cfg(feature = "log")]
+use log::info;
+
+use crate::{
+    clock::Clock,
+    fallback::needs_human,
+    fingerprint::{Fingerprint, Fingerprints},
+    LoginPhase, PinterestLoginError,
+};
+
+/// How many times to retry a failed step of the login (like the browser launch or the navigation to the login page),
+/// and how long to wait between the attempts
+///
+/// Only the errors that are known to be transient are retried,
+/// see [`is_transient_launch_error`] and [`is_transient_navigation_error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepRetry {
+    /// The maximum number of retries after the first attempt
+    pub retries: u32,
+    /// The delay between the attempts
+    pub delay: Duration,
+}
+
+impl Default for StepRetry {
+    /// 2 retries, with a 500ms delay between them
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A failed attempt of a login step that is about to be retried, see [`LoginOptions::on_retry`](crate::options::LoginOptions::on_retry)
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RetryNotice<'a> {
+    /// The retried phase, [`LoginPhase::Launch`] or [`LoginPhase::Navigation`]
+    pub phase: LoginPhase,
+    /// The failed attempt, 1 for the first one
+    pub attempt: u32,
+    /// The maximum number of attempts, the first one and the retries
+    pub max_attempts: u32,
+    /// The delay before the next attempt
+    pub delay: Duration,
+    /// The transient error of the failed attempt
+    pub error: &'a PinterestLoginError,
+}
+
+/// How many times to try the whole login again when pinterest challenges it, see [`retry_login`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LoginRetry {
+    /// The maximum number of retries after the first attempt
+    pub retries: u32,
+    /// The delay between the attempts
+    pub delay: Duration,
+    /// Gives every retry a slightly different [`Fingerprint`] (user agent, viewport and typing pace), since the exact
+    /// same browser tends to be challenged again right away
+    pub vary_fingerprint: bool,
+    /// The seed of the fingerprints, to reproduce a run, a random one is used if it's `None`
+    pub seed: Option<u64>,
+}
+
+impl LoginRetry {
+    /// Retries the challenged logins `retries` times, `delay` apart, with the same fingerprint
+    pub fn new(retries: u32, delay: Duration) -> Self {
+        Self {
+            retries,
+            delay,
+            vary_fingerprint: false,
+            seed: None,
+        }
+    }
+
+    /// Sets whether every retry gets a new fingerprint, see [`LoginRetry::vary_fingerprint`]
+    pub fn vary_fingerprint(mut self, vary: bool) -> Self {
+        self.vary_fingerprint = vary;
+        self
+    }
+
+    /// Sets the seed of the fingerprints, see [`LoginRetry::seed`]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl Default for LoginRetry {
+    /// 1 retry, 5 seconds later, with the same fingerprint
+    fn default() -> Self {
+        Self::new(1, Duration::from_secs(5))
+    }
+}
+
+/// An attempt of [`retry_login`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LoginAttempt {
+    /// The attempt, 1 for the first one
+    pub attempt: u32,
+    /// The maximum number of attempts, the first one and the retries
+    pub max_attempts: u32,
+    /// The fingerprint to log in with, `None` to keep the default one.
+    ///
+    /// It's always `None` for the first attempt, and for all of them without [`LoginRetry::vary_fingerprint`].
+    pub fingerprint: Option<Fingerprint>,
+}
+
+/// Runs the `login` again when pinterest challenges it (see [`needs_human`]), as configured in the policy.
+///
+/// The `login` builds every attempt from the [`LoginAttempt`]: it should keep the account and the proxy, and apply
+/// the fingerprint when there's one, with [`LoginOptions::fingerprint`](crate::options::LoginOptions::fingerprint)
+/// and [`DefaultBrowserLoginBot::with_typing`](crate::login_bot::DefaultBrowserLoginBot::with_typing).
+/// The other errors are returned right away, and so is the challenge of the last attempt.
+///
+/// # Example
+/// ```no_run
+/// # use std::time::Duration;
+/// # use pinterest_login::clock::SystemClock;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::login_with_options;
+/// # use pinterest_login::options::LoginOptions;
+/// # use pinterest_login::retry::{retry_login, LoginRetry};
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+/// let policy = LoginRetry::new(2, Duration::from_secs(30)).vary_fingerprint(true);
+///
+/// let outcome = retry_login(&policy, &SystemClock, |attempt| {
+///     let mut bot = DefaultBrowserLoginBot::new("email", "password");
+///     let mut options = LoginOptions::new();
+///     if let Some(fingerprint) = attempt.fingerprint {
+///         bot = bot.with_typing(fingerprint.typing);
+///         options = options.fingerprint(fingerprint);
+///     }
+///     let config_builder = &config_builder;
+///     async move { login_with_options(&bot, config_builder, &options).await }
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn retry_login<T, F, Fut>(
+    policy: &LoginRetry,
+    clock: &dyn Clock,
+    mut login: F,
+) -> crate::Result<T>
+where
+    F: FnMut(LoginAttempt) -> Fut,
+    Fut: Future<Output = crate::Result<T>>,
+{
+    let max_attempts = policy.retries + 1;
+    let mut fingerprints = policy.vary_fingerprint.then(|| {
+        Fingerprints::new(policy.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        }))
+    });
+    let mut attempt = 1;
+    loop {
+        let fingerprint = match &mut fingerprints {
+            Some(fingerprints) if attempt > 1 => Some(fingerprints.next_fingerprint()),
+            _ => None,
+        };
+        #[cfg(feature = "log")]
+        if let Some(fingerprint) = &fingerprint {
+            info!("The login attempt {attempt}/{max_attempts} presents itself as: {fingerprint}");
+        }
+        match login(LoginAttempt {
+            attempt,
+            max_attempts,
+            fingerprint,
+        })
+        .await
+        {
+            Err(e) if attempt < max_attempts && needs_human(&e) => {
+                #[cfg(feature = "log")]
+                info!("The login attempt {attempt}/{max_attempts} was challenged, retrying in {:?}: {e}", policy.delay);
+                #[cfg(not(feature = "log"))]
+                let _ = e;
+                clock.sleep(policy.delay).await;
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// The messages of the launch errors that are known to go away if we just try again.
+///
+/// These happen mostly when several browsers start at the same time and race on the debugging port or the profile.
+const TRANSIENT_LAUNCH_MESSAGES: &[&str] = &[
+    "DevToolsActivePort file doesn't exist",
+    "Address already in use",
+    "Cannot start http server for devtools",
+    "Failed to create a ProcessSingleton",
+    "The profile appears to be in use",
+];
+
+/// Returns `true` if the message of a failed browser launch is known to be transient.
+///
+/// # Example
+/// ```
+/// use pinterest_login::retry::is_transient_launch_message;
+///
+/// // Transient, retrying usually works
+/// assert!(is_transient_launch_message(r#"Browser process exited with status ExitStatus(unix_wait_status(256)) before websocket URL could be resolved, stderr: BrowserStderr("[0412/101010.123456:ERROR:devtools_http_handler.cc(766)] Cannot start http server for devtools.\n")"#));
+/// assert!(is_transient_launch_message("unknown error: DevToolsActivePort file doesn't exist"));
+/// assert!(is_transient_launch_message("[0412/101010.123456:ERROR:socket_posix.cc(93)] bind() failed: Address already in use (98)"));
+/// assert!(is_transient_launch_message("[0412/101010.123456:ERROR:process_singleton_posix.cc(353)] Failed to create a ProcessSingleton for your profile directory."));
+///
+/// // Permanent, retrying won't help
+/// assert!(!is_transient_launch_message("No such file or directory (os error 2)"));
+/// assert!(!is_transient_launch_message("Could not auto detect a chrome executable"));
+/// assert!(!is_transient_launch_message("chrome: error while loading shared libraries: libnss3.so: cannot open shared object file"));
+/// assert!(!is_transient_launch_message("Running as root without --no-sandbox is not supported."));
+/// ```
+pub fn is_transient_launch_message(message: &str) -> bool {
+    TRANSIENT_LAUNCH_MESSAGES
+        .iter()
+        .any(|transient| message.contains(transient))
+}
+
+/// Returns `true` if the browser launch failed with an error that is known to be transient,
+/// see [`is_transient_launch_message`].
+pub fn is_transient_launch_error(error: &PinterestLoginError) -> bool {
+    match error {
+        PinterestLoginError::CdpError(
+            e @ (CdpError::LaunchExit(..) | CdpError::LaunchIo(..) | CdpError::Io(_)),
+        ) => is_transient_launch_message(&e.to_string()),
+        _ => false,
+    }
+}
+
+/// The network errors that usually go away if we just try again
+const TRANSIENT_NET_ERRORS: &[&str] = &[
+    "net::ERR_CONNECTION_RESET",
+    "net::ERR_CONNECTION_CLOSED",
+    "net::ERR_CONNECTION_ABORTED",
+    "net::ERR_CONNECTION_REFUSED",
+    "net::ERR_CONNECTION_TIMED_OUT",
+    "net::ERR_TIMED_OUT",
+    "net::ERR_EMPTY_RESPONSE",
+    "net::ERR_NETWORK_CHANGED",
+    "net::ERR_NETWORK_IO_SUSPENDED",
+    "net::ERR_INTERNET_DISCONNECTED",
+    "net::ERR_ADDRESS_UNREACHABLE",
+    "net::ERR_SOCKET_NOT_CONNECTED",
+    "net::ERR_PROXY_CONNECTION_FAILED",
+    "net::ERR_TUNNEL_CONNECTION_FAILED",
+    "net::ERR_HTTP2_PROTOCOL_ERROR",
+    "net::ERR_QUIC_PROTOCOL_ERROR",
+];
+
+/// The DNS errors, they are permanent unless the browser goes through a proxy (that does the resolution itself)
+const DNS_NET_ERRORS: &[&str] = &[
+    "net::ERR_NAME_NOT_RESOLVED",
+    "net::ERR_NAME_RESOLUTION_FAILED",
+];
+
+/// Returns `true` if the error text of a failed navigation is known to be transient.
+///
+/// The certificate errors and the DNS errors without a proxy are permanent, and so is any unknown error.
+///
+/// # Example
+/// ```
+/// use pinterest_login::retry::is_transient_net_error;
+///
+/// // (error text, behind a proxy, transient)
+/// let table = [
+///     ("net::ERR_CONNECTION_RESET", false, true),
+///     ("net::ERR_CONNECTION_CLOSED", false, true),
+///     ("net::ERR_TIMED_OUT", false, true),
+///     ("net::ERR_PROXY_CONNECTION_FAILED", true, true),
+///     ("net::ERR_TUNNEL_CONNECTION_FAILED", true, true),
+///     ("net::ERR_NAME_NOT_RESOLVED", false, false),
+///     ("net::ERR_NAME_NOT_RESOLVED", true, true),
+///     ("net::ERR_CERT_AUTHORITY_INVALID", false, false),
+///     ("net::ERR_CERT_DATE_INVALID", true, false),
+///     ("net::ERR_SSL_PROTOCOL_ERROR", false, false),
+///     ("net::ERR_ABORTED", false, false),
+///     ("net::ERR_TOO_MANY_REDIRECTS", false, false),
+///     ("something else", false, false),
+/// ];
+/// for (error_text, behind_proxy, transient) in table {
+///     assert_eq!(is_transient_net_error(error_text, behind_proxy), transient, "{error_text} (proxy: {behind_proxy})");
+/// }
+/// ```
+pub fn is_transient_net_error(error_text: &str, behind_proxy: bool) -> bool {
+    let error_text = error_text.trim();
+    if DNS_NET_ERRORS.contains(&error_text) {
+        return behind_proxy;
+    }
+    TRANSIENT_NET_ERRORS.contains(&error_text)
+}
+
+/// Returns `true` if the navigation to the login page failed with an error that is known to be transient,
+/// see [`is_transient_net_error`].
+///
+/// # Example
+/// ```
+/// use chromiumoxide::error::CdpError;
+/// use pinterest_login::retry::is_transient_navigation_error;
+/// use pinterest_login::PinterestLoginError;
+///
+/// // The navigation errors are reported by chromiumoxide as chrome messages with the `errorText` of `Page.navigate`
+/// let reset = PinterestLoginError::CdpError(CdpError::ChromeMessage("net::ERR_CONNECTION_RESET".into()));
+/// assert!(is_transient_navigation_error(&reset, false));
+///
+/// let cert = PinterestLoginError::CdpError(CdpError::ChromeMessage("net::ERR_CERT_COMMON_NAME_INVALID".into()));
+/// assert!(!is_transient_navigation_error(&cert, false));
+///
+/// // A CDP request timeout is transient too, and so is a maintenance page
+/// assert!(is_transient_navigation_error(&PinterestLoginError::CdpError(CdpError::Timeout), false));
+/// assert!(is_transient_navigation_error(&PinterestLoginError::ServiceUnavailable { retry_hint: None }, false));
+/// assert!(!is_transient_navigation_error(&PinterestLoginError::AuthenticationError, false));
+/// ```
+pub fn is_transient_navigation_error(error: &PinterestLoginError, behind_proxy: bool) -> bool {
+    match error {
+        PinterestLoginError::CdpError(CdpError::ChromeMessage(error_text)) => {
+            is_transient_net_error(error_text, behind_proxy)
+        }
+        PinterestLoginError::CdpError(CdpError::Timeout)
+        | PinterestLoginError::ServiceUnavailable { .. } => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    login_bot::PageLoginBot,
+    page::LoginPage,
+    LoginPhase, PinterestLoginError,
+};
+
+/// What the [`ScriptedLoginBot`] does in one call of a phase
+#[derive(Clone)]
+pub enum Step {
+    /// Returns `Ok(())`
+    Succeed,
+    /// Returns the error that the function makes
+    Fail(Arc<dyn Fn() -> PinterestLoginError + Send + Sync>),
+    /// Waits for the duration (on the clock of the bot), then succeeds
+    Delay(Duration),
+}
+
+impl Step {
+    /// Fails with the error that the function makes, it's called for every failure since the errors aren't `Clone`
+    pub fn fail(error: impl Fn() -> PinterestLoginError + Send + Sync + 'static) -> Self {
+        Self::Fail(Arc::new(error))
+    }
+}
+
+impl fmt::Debug for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Succeed => f.write_str("Succeed"),
+            Self::Fail(error) => f.debug_tuple("Fail").field(&error().to_string()).finish(),
+            Self::Delay(duration) => f.debug_tuple("Delay").field(duration).finish(),
+        }
+    }
+}
+
+/// A login bot whose outcome is programmed per phase, to test how the code that uses the login handles every failure
+/// without a real account.
+///
+/// Every call of a phase plays the next [`Step`] of its script, and the last step is repeated once the script is over,
+/// so `[fail, fail, succeed]` fails twice and then succeeds for good. The phases without a script succeed.
+/// The bot never touches the page, and it's a [`BrowserLoginBot`](crate::login_bot::BrowserLoginBot),
+/// so it can be passed to [`login`](crate::login) as is.
+///
+/// The bot is shared by reference, so the same script carries over the successive logins (e.g. the attempts of a retry loop).
+///
+/// # Example
+/// ```
+/// use pinterest_login::login_bot::PageLoginBot;
+/// use pinterest_login::mock::MockPage;
+/// use pinterest_login::scripted::{ScriptedLoginBot, Step};
+/// use pinterest_login::{LoginPhase, PinterestLoginError};
+///
+/// # async_std::task::block_on(async {
+/// let bot = ScriptedLoginBot::new().script(
+///     LoginPhase::CheckLogin,
+///     [Step::fail(|| PinterestLoginError::AuthenticationError), Step::Succeed],
+/// );
+/// let page = MockPage::new("https://www.pinterest.com/login/");
+///
+/// assert!(bot.check_login(&page).await.is_err());
+/// assert!(bot.check_login(&page).await.is_ok());
+/// assert!(bot.check_login(&page).await.is_ok());
+/// assert_eq!(bot.calls(), [LoginPhase::CheckLogin; 3]);
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct ScriptedLoginBot {
+    state: Arc<Mutex<State>>,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    scripts: HashMap<LoginPhase, VecDeque<Step>>,
+    calls: Vec<LoginPhase>,
+}
+
+impl ScriptedLoginBot {
+    /// A bot that succeeds in every phase
+    pub fn new() -> Self {
+        Self {
+            state: Arc::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the script of a phase, it replaces the previous script of the phase.
+    ///
+    /// # Panics
+    /// If the phase isn't one of the bot phases: [`LoginPhase::FillForm`], [`LoginPhase::SubmitForm`]
+    /// and [`LoginPhase::CheckLogin`].
+    pub fn script(self, phase: LoginPhase, steps: impl IntoIterator<Item = Step>) -> Self {
+        assert!(
+            matches!(
+                phase,
+                LoginPhase::FillForm | LoginPhase::SubmitForm | LoginPhase::CheckLogin
+            ),
+            "the {phase} phase isn't run by the login bot"
+        );
+        self.state()
+            .scripts
+            .insert(phase, steps.into_iter().collect());
+        self
+    }
+
+    /// Sets the clock of the [`Step::Delay`]s, the default is [`SystemClock`]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// The phases that were called so far, in order
+    pub fn calls(&self) -> Vec<LoginPhase> {
+        self.state().calls.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panicking test can't poison anything that matters
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn play(&self, phase: LoginPhase) -> crate::Result<()> {
+        let step = {
+            let mut state = self.state();
+            state.calls.push(phase);
+            match state.scripts.get_mut(&phase) {
+                Some(steps) if steps.len() > 1 => steps.pop_front(),
+                Some(steps) => steps.front().cloned(),
+                None => None,
+            }
+        };
+        match step.unwrap_or(Step::Succeed) {
+            Step::Succeed => Ok(()),
+            Step::Fail(error) => Err(error()),
+            Step::Delay(duration) => {
+                self.clock.sleep(duration).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ScriptedLoginBot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("ScriptedLoginBot")
+            .field("scripts", &state.scripts)
+            .field("calls", &state.calls)
+            .finish()
+    }
+}
+
+impl Default for ScriptedLoginBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageLoginBot for ScriptedLoginBot {
+    async fn fill_login_form<P: LoginPage>(&self, _page: &P) -> crate::Result<()> {
+        self.play(LoginPhase::FillForm).await
+    }
+
+    async fn submit_login_form<P: LoginPage>(&self, _page: &P) -> crate::Result<()> {
+        self.play(LoginPhase::SubmitForm).await
+    }
+
+    async fn check_login<P: LoginPage>(&self, _page: &P) -> crate::Result<()> {
+        self.play(LoginPhase::CheckLogin).await
+    }
+}
@@ -0,0 +1,165 @@
+use chromiumoxide::error::CdpError;
+
+use crate::page::LoginPage;
+
+/// A field of the login form, see [`LoginSelectors`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FormField {
+    /// The email input
+    Email,
+    /// The password input
+    Password,
+    /// The submit button
+    Submit,
+}
+
+impl std::fmt::Display for FormField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Email => "email field",
+            Self::Password => "password field",
+            Self::Submit => "submit button",
+        })
+    }
+}
+
+/// A selector of a field of the login form
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Selector {
+    /// A CSS selector, it finds the first matching element
+    Css(String),
+    /// An XPath, it finds all the matching elements
+    XPath(String),
+}
+
+impl Selector {
+    /// A [`Selector::Css`]
+    pub fn css(selector: impl Into<String>) -> Self {
+        Self::Css(selector.into())
+    }
+
+    /// A [`Selector::XPath`]
+    pub fn xpath(xpath: impl Into<String>) -> Self {
+        Self::XPath(xpath.into())
+    }
+
+    /// Finds the elements of the page that match the selector, fails if there's none
+    pub(crate) async fn find<P: LoginPage>(&self, page: &P) -> crate::Result<Vec<P::Element>> {
+        match self {
+            Self::Css(selector) => Ok(vec![page.find_element(selector).await?]),
+            Self::XPath(xpath) => match page.find_xpaths(xpath).await? {
+                elements if elements.is_empty() => Err(CdpError::NotFound.into()),
+                elements => Ok(elements),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Css(selector) => f.write_str(selector),
+            Self::XPath(xpath) => f.write_str(xpath),
+        }
+    }
+}
+
+/// The ordered fallback chains of selectors of the login form fields, see
+/// [`DefaultBrowserLoginBot::with_selectors`](crate::login_bot::DefaultBrowserLoginBot::with_selectors).
+///
+/// The bot tries the selectors of a field in order, and the first one that matches wins. The default chains start with
+/// the selectors that pinterest uses today, and fall back to its `data-test-id`s, the `name`s of the fields, and their
+/// types or texts. The matched selectors are in the [`LoginReport`](crate::report::LoginReport), a fallback there
+/// means that pinterest changed its login form.
+///
+/// # Example
+/// ```
+/// use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// use pinterest_login::selectors::{LoginSelectors, Selector};
+///
+/// let selectors = LoginSelectors::default().with_submit([
+///     Selector::css("button#login"),
+///     Selector::xpath("//button[contains(text(), 'Log in')]"),
+/// ]);
+/// let bot = DefaultBrowserLoginBot::new("email", "password").with_selectors(selectors);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginSelectors {
+    email: Vec<Selector>,
+    password: Vec<Selector>,
+    submit: Vec<Selector>,
+}
+
+impl Default for LoginSelectors {
+    fn default() -> Self {
+        Self {
+            email: vec![
+                Selector::css("input#email"),
+                Selector::css("[data-test-id='emailInputField'] input"),
+                Selector::css("input[name='id']"),
+                Selector::css("input[type='email']"),
+            ],
+            password: vec![
+                Selector::css("input#password"),
+                Selector::css("[data-test-id='passwordInputField'] input"),
+                Selector::css("input[name='password']"),
+                Selector::css("input[type='password']"),
+            ],
+            submit: vec![
+                Selector::xpath("//*[contains(text(), 'Log in')]"),
+                Selector::css("[data-test-id='registerFormSubmitButton'] button"),
+                Selector::css("form button[type='submit']"),
+            ],
+        }
+    }
+}
+
+impl LoginSelectors {
+    /// Replaces the chain of the email field
+    pub fn with_email(mut self, chain: impl IntoIterator<Item = Selector>) -> Self {
+        self.email = chain.into_iter().collect();
+        self
+    }
+
+    /// Replaces the chain of the password field
+    pub fn with_password(mut self, chain: impl IntoIterator<Item = Selector>) -> Self {
+        self.password = chain.into_iter().collect();
+        self
+    }
+
+    /// Replaces the chain of the submit button
+    pub fn with_submit(mut self, chain: impl IntoIterator<Item = Selector>) -> Self {
+        self.submit = chain.into_iter().collect();
+        self
+    }
+
+    /// The chain of the field, in order
+    pub fn chain(&self, field: FormField) -> &[Selector] {
+        match field {
+            FormField::Email => &self.email,
+            FormField::Password => &self.password,
+            FormField::Submit => &self.submit,
+        }
+    }
+}
+
+/// The selector that found a field of the login form, see [`LoginSelectors`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SelectorMatch {
+    /// The field
+    pub field: FormField,
+    /// The selector that matched
+    pub selector: Selector,
+    /// The position of the selector in the chain of the field, 0 for the primary selector
+    pub position: usize,
+}
+
+impl SelectorMatch {
+    /// Returns `true` if the field was found with a fallback rather than its primary selector
+    pub fn is_fallback(&self) -> bool {
+        self.position > 0
+    }
+}
@@ -0,0 +1,998 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use futures::{
+    future::BoxFuture,
+    stream::{self, BoxStream},
+    FutureExt, StreamExt,
+};
+#[cfg(feature = "log")]
+use log::warn;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    auth::AuthSession, config_builder::BrowserConfigBuilder, cookies::PinterestCookie,
+    login_bot::BrowserLoginBot, login_with_options, options::LoginOptions, report::LoginOutcome,
+    store::write_atomic, PinterestCookies, PinterestLoginError,
+};
+
+/// The version of the session files that [`Session::save`] writes
+pub const SESSION_VERSION: u64 = 2;
+/// The oldest version of the session files that [`Session::load`] reads, they're migrated to the current one
+pub const MIN_SESSION_VERSION: u64 = 1;
+
+/// The cookies of a login, with what they belong to: the account, when they were obtained, and the browser that
+/// obtained them, since pinterest ties a session to its user agent.
+///
+/// It's saved as a versioned JSON file, like
+/// `{"format_version": 2, "account": "me@example.com", "created_at": 1700000000, "expires_at": null, "user_agent": null, "login_url": "...", "cookies": {...}}`,
+/// `created_at` and `expires_at` are in seconds since the unix epoch, so the sub-second part of the times isn't saved.
+///
+/// The files of the previous versions are migrated when they're loaded:
+/// * version 1 names its version `version`, and it may not have the `expires_at`
+///
+/// # Example
+/// ```
+/// use pinterest_login::session::Session;
+///
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("session.json");
+/// let cookies = [("_pinterest_sess".to_string(), "...".to_string())].into();
+///
+/// let session = Session::new("me@example.com", cookies);
+/// session.save(&path)?;
+///
+/// let loaded = Session::load(&path)?;
+/// assert_eq!(loaded.account, "me@example.com");
+/// assert_eq!(loaded.cookies, session.cookies);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// The account that logged in, usually its email
+    pub account: String,
+    /// The pinterest cookies
+    pub cookies: PinterestCookies,
+    /// When the cookies were obtained
+    pub created_at: SystemTime,
+    /// The user agent of the browser that obtained them, if it's known
+    pub user_agent: Option<String>,
+    /// The login page that they were obtained on
+    pub login_url: String,
+    /// The earliest expiry of the [`AUTH_COOKIES`], `None` if it's not known, see [`Session::expires_at()`]
+    pub expires_at: Option<SystemTime>,
+}
+
+/// The name of the session cookie of pinterest, the one that keeps the account logged in
+pub const SESSION_COOKIE: &str = "_pinterest_sess";
+/// The name of the CSRF token cookie of pinterest
+pub const CSRF_COOKIE: &str = "csrftoken";
+/// The header that the internal API expects the CSRF token in, see [`Session::csrf_header`]
+pub const CSRF_HEADER: &str = "X-CSRFToken";
+/// The cookies that keep the account logged in, a session expires with the earliest of them, see
+/// [`Session::expires_at`]
+pub const AUTH_COOKIES: [&str; 3] = [SESSION_COOKIE, CSRF_COOKIE, "_auth"];
+
+/// How long a [`login_or_load`] session has to stay valid to be reused, so it doesn't expire while it's used
+pub const DEFAULT_EXPIRY_MARGIN: Duration = Duration::from_secs(24 * 60 * 60);
+/// How long a session without any cookie expiry is considered valid, [`Session::is_expired`] uses it
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Whether a session can still be used, see [`Session::is_expired`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expiry {
+    /// It's valid for longer than the margin
+    Fresh,
+    /// It's still valid, but not for longer than the margin
+    ExpiringSoon,
+    /// It's not valid anymore
+    Expired,
+}
+
+/// Why a session file can't be loaded or saved
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    /// The file can't be read or written
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The file isn't JSON
+    #[error("The session file isn't valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The file was written by a newer version of the crate, or by a version too old to be migrated,
+    /// or it has no version
+    #[error(
+        "Unsupported session file version {found:?}, the supported versions are {supported:?}"
+    )]
+    UnsupportedVersion {
+        /// The version of the file, `None` if it has none
+        found: Option<u64>,
+        /// The versions that can be read
+        supported: RangeInclusive<u64>,
+    },
+    /// A field is missing or has the wrong type
+    #[error("The session file has an invalid `{field}`: {reason}")]
+    InvalidField {
+        /// The name of the field
+        field: &'static str,
+        /// What's wrong with it
+        reason: &'static str,
+    },
+    /// The encrypted file can't be decrypted with the key, or it was tampered with
+    #[error("The session file can't be decrypted, the key is wrong or the file was tampered with")]
+    DecryptionFailed,
+    /// The account isn't one of the [`SessionManager`](crate::manager::SessionManager)
+    #[error("Unknown account `{0}`")]
+    UnknownAccount(String),
+    /// A cookie that the session needs isn't there, like [`SESSION_COOKIE`] or [`CSRF_COOKIE`]. The cookies probably
+    /// aren't the ones of a complete login
+    #[error("The session has no `{0}` cookie")]
+    MissingCookie(&'static str),
+    /// The login of [`login_or_load`] failed
+    #[error("{0}")]
+    Login(#[from] PinterestLoginError),
+    /// The [`CredentialsProvider`](crate::credentials::CredentialsProvider) of the account couldn't give its
+    /// credentials
+    #[error("{0}")]
+    Credentials(#[from] crate::credentials::CredentialsError),
+    /// A custom [`SessionStore`] failed
+    #[error("{0}")]
+    Store(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Session {
+    /// The session of the cookies obtained now on the pinterest login page, with an unknown user agent
+    pub fn new(account: impl Into<String>, cookies: PinterestCookies) -> Self {
+        Self {
+            account: account.into(),
+            cookies,
+            created_at: SystemTime::now(),
+            user_agent: None,
+            login_url: crate::PINTEREST_LOGIN_URL.to_string(),
+            expires_at: None,
+        }
+    }
+
+    /// The session of a login that just succeeded with these options, obtained now on their login page,
+    /// that expires with the earliest of its [`AUTH_COOKIES`]
+    pub fn from_outcome(
+        account: impl Into<String>,
+        outcome: &LoginOutcome,
+        options: &LoginOptions,
+    ) -> Self {
+        let cookies: Vec<_> = outcome
+            .cookie_details
+            .iter()
+            .map(PinterestCookie::from)
+            .collect();
+        let mut session =
+            Self::new(account, outcome.cookies.clone()).with_login_url(options.target.url());
+        session.expires_at = auth_expiry(&cookies);
+        session
+    }
+
+    /// The session of the cookies of [`login_full`](crate::login_full), obtained now on the pinterest login page,
+    /// that expires with the earliest of its [`AUTH_COOKIES`]
+    pub fn from_full_cookies(account: impl Into<String>, cookies: &[PinterestCookie]) -> Self {
+        let map = cookies
+            .iter()
+            .cloned()
+            .map(<(String, String)>::from)
+            .collect();
+        let mut session = Self::new(account, map);
+        session.expires_at = auth_expiry(cookies);
+        session
+    }
+
+    /// Sets the user agent of the browser that obtained the cookies
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the login page that the cookies were obtained on
+    pub fn with_login_url(mut self, login_url: impl Into<String>) -> Self {
+        self.login_url = login_url.into();
+        self
+    }
+
+    /// Sets when the cookies were obtained
+    pub fn with_created_at(mut self, created_at: SystemTime) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// Sets when the earliest of the cookies expires
+    pub fn with_expires_at(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// The cookies of the session
+    pub fn cookies(&self) -> &PinterestCookies {
+        &self.cookies
+    }
+
+    /// The value of the session cookie, [`SESSION_COOKIE`]
+    ///
+    /// # Errors
+    /// [`SessionError::MissingCookie`] if there's none
+    pub fn session_cookie(&self) -> Result<&str, SessionError> {
+        self.cookie(SESSION_COOKIE)
+    }
+
+    /// The CSRF token of the session, the value of the [`CSRF_COOKIE`] cookie, that the requests to the internal API
+    /// send in the [`CSRF_HEADER`] header too
+    ///
+    /// # Errors
+    /// [`SessionError::MissingCookie`] if there's none
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::session::{Session, SessionError, CSRF_COOKIE};
+    ///
+    /// let cookies = [("csrftoken".to_string(), "token".to_string())].into();
+    /// let session = Session::new("me@example.com", cookies);
+    ///
+    /// assert_eq!(session.csrf_token()?, "token");
+    /// assert_eq!(session.csrf_header()?, ("X-CSRFToken", "token"));
+    /// assert!(matches!(session.session_cookie(), Err(SessionError::MissingCookie("_pinterest_sess"))));
+    /// # Ok::<(), SessionError>(())
+    /// ```
+    pub fn csrf_token(&self) -> Result<&str, SessionError> {
+        self.cookie(CSRF_COOKIE)
+    }
+
+    /// The name and the value of the CSRF header of the requests to the internal API, see [`Session::csrf_token`]
+    ///
+    /// # Errors
+    /// [`SessionError::MissingCookie`] if there's no CSRF token
+    pub fn csrf_header(&self) -> Result<(&'static str, &str), SessionError> {
+        Ok((CSRF_HEADER, self.csrf_token()?))
+    }
+
+    /// A curl command that sends a request to the url with the session, to paste in a POSIX shell: its cookies in the
+    /// `Cookie` header, its CSRF token in the [`CSRF_HEADER`] header, and its user agent when it's known.
+    ///
+    /// Every argument is single quoted, so the quotes, the semicolons and the `$` of the values don't reach the shell.
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_login::session::Session;
+    ///
+    /// let cookies = [("csrftoken".to_string(), "token".to_string())].into();
+    /// let session = Session::new("me@example.com", cookies).with_user_agent("Mozilla/5.0");
+    ///
+    /// assert_eq!(
+    ///     session.to_curl("https://www.pinterest.com/"),
+    ///     "curl 'https://www.pinterest.com/' -H 'Cookie: csrftoken=token' -H 'X-CSRFToken: token' \
+    ///      --user-agent 'Mozilla/5.0'"
+    /// );
+    /// ```
+    pub fn to_curl(&self, url: &str) -> String {
+        let auth = AuthSession::from(self);
+        let mut command = format!(
+            "curl {} -H {}",
+            shell_quote(url),
+            shell_quote(&format!("Cookie: {}", auth.cookie_header))
+        );
+        if let Some(token) = &auth.csrf_token {
+            command.push_str(" -H ");
+            command.push_str(&shell_quote(&format!("{CSRF_HEADER}: {token}")));
+        }
+        if let Some(user_agent) = &auth.user_agent {
+            command.push_str(" --user-agent ");
+            command.push_str(&shell_quote(user_agent));
+        }
+        command
+    }
+
+    /// The value of a cookie that the session needs
+    fn cookie(&self, name: &'static str) -> Result<&str, SessionError> {
+        self.cookies
+            .get(name)
+            .map(String::as_str)
+            .ok_or(SessionError::MissingCookie(name))
+    }
+
+    /// When the session expires: the earliest expiry of its [`AUTH_COOKIES`], when they were obtained with their
+    /// attributes, like with [`Session::from_outcome`].
+    ///
+    /// It's `None` when it's not known, and not when it never expires: if one of the auth cookies is a session cookie,
+    /// that pinterest drops on its own terms, or if the cookies came without their attributes.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    /// Whether the session should be renewed with a new login now, because it has expired or it expires within the
+    /// `margin`.
+    ///
+    /// A session without a known expiry isn't considered valid forever, it needs a refresh after [`DEFAULT_MAX_AGE`]
+    /// like [`Session::is_expired`] does.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use pinterest_login::session::Session;
+    ///
+    /// let session = Session::new("me@example.com", Default::default())
+    ///     .with_expires_at(SystemTime::now() + Duration::from_secs(60 * 60));
+    ///
+    /// assert!(!session.needs_refresh(Duration::from_secs(60)));
+    /// assert!(session.needs_refresh(Duration::from_secs(2 * 60 * 60)));
+    /// ```
+    pub fn needs_refresh(&self, margin: Duration) -> bool {
+        self.is_expired(SystemTime::now(), margin) != Expiry::Fresh
+    }
+
+    /// Whether the session is still valid at `now`, and for longer than the `margin`, without checking it with
+    /// pinterest. It expires with the earliest of its cookies, or after [`DEFAULT_MAX_AGE`] when they have no expiry.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use pinterest_login::session::{Expiry, Session, DEFAULT_EXPIRY_MARGIN};
+    ///
+    /// let now = SystemTime::now();
+    /// let session = Session::new("me@example.com", Default::default())
+    ///     .with_expires_at(now + Duration::from_secs(60 * 60));
+    ///
+    /// assert_eq!(session.is_expired(now, Duration::ZERO), Expiry::Fresh);
+    /// assert_eq!(session.is_expired(now, DEFAULT_EXPIRY_MARGIN), Expiry::ExpiringSoon);
+    /// ```
+    pub fn is_expired(&self, now: SystemTime, margin: Duration) -> Expiry {
+        self.is_expired_with_max_age(now, margin, DEFAULT_MAX_AGE)
+    }
+
+    /// Same as [`Session::is_expired`], with another max age for the sessions that have no cookie expiry
+    pub fn is_expired_with_max_age(
+        &self,
+        now: SystemTime,
+        margin: Duration,
+        max_age: Duration,
+    ) -> Expiry {
+        let deadline = self
+            .expires_at
+            .or_else(|| self.created_at.checked_add(max_age));
+        match deadline {
+            Some(deadline) if now >= deadline => Expiry::Expired,
+            Some(deadline) if now.checked_add(margin).map_or(true, |end| end >= deadline) => {
+                Expiry::ExpiringSoon
+            }
+            // Too far in the future to be represented
+            _ => Expiry::Fresh,
+        }
+    }
+
+    /// The JSON of the session file
+    pub fn to_json(&self) -> Value {
+        let secs = |time: SystemTime| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs())
+        };
+        json!({
+            "format_version": SESSION_VERSION,
+            "account": self.account,
+            "created_at": secs(self.created_at),
+            "expires_at": self.expires_at.map(secs),
+            "user_agent": self.user_agent,
+            "login_url": self.login_url,
+            "cookies": *self.cookies,
+        })
+    }
+
+    /// Parses the JSON of a session file, the files of the versions since [`MIN_SESSION_VERSION`] are migrated
+    pub fn from_json(json: &Value) -> Result<Self, SessionError> {
+        Self::parse(&migrate(json.clone())?.0)
+    }
+
+    /// Parses the JSON of the current version
+    fn parse(json: &Value) -> Result<Self, SessionError> {
+        let string = |field: &'static str| match &json[field] {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(SessionError::InvalidField {
+                field,
+                reason: "expected a string",
+            }),
+        };
+        let time = |field: &'static str| {
+            json[field]
+                .as_u64()
+                .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+                .ok_or(SessionError::InvalidField {
+                    field,
+                    reason: "expected the seconds since the unix epoch",
+                })
+        };
+        let expires_at = match &json["expires_at"] {
+            Value::Null if json.get("expires_at").is_some() => None,
+            _ => Some(time("expires_at")?),
+        };
+        let user_agent = match &json["user_agent"] {
+            Value::Null => None,
+            _ => Some(string("user_agent")?),
+        };
+        let cookies = json["cookies"]
+            .as_object()
+            .ok_or(SessionError::InvalidField {
+                field: "cookies",
+                reason: "expected an object of the cookie names and values",
+            })
+            .and_then(cookies)?;
+        Ok(Self {
+            account: string("account")?,
+            cookies,
+            created_at: time("created_at")?,
+            user_agent,
+            login_url: string("login_url")?,
+            expires_at,
+        })
+    }
+
+    /// Saves the session to the file, atomically and only readable by its owner like
+    /// [`write_atomic`](crate::store::write_atomic) does. The parent directories are created if they're missing.
+    ///
+    /// It's blocking, use it from a blocking task in async code.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        let contents = format!("{:#}\n", self.to_json());
+        write_atomic(path, contents.as_bytes(), true)?;
+        Ok(())
+    }
+
+    /// Loads the session that [`Session::save`] saved, the files of the previous versions are migrated in memory.
+    ///
+    /// It's blocking, use it from a blocking task in async code.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SessionError> {
+        let contents = fs::read_to_string(path)?;
+        Self::from_json(&serde_json::from_str(&contents)?)
+    }
+
+    /// Same as [`Session::load`], but the file of a previous version is rewritten in the current version, so the
+    /// next crate upgrades don't have to migrate it anymore. The current files are left as they are.
+    ///
+    /// It's blocking, use it from a blocking task in async code.
+    pub fn load_and_upgrade(path: impl AsRef<Path>) -> Result<Self, SessionError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        let (json, migrated) = migrate(serde_json::from_str(&contents)?)?;
+        let session = Self::parse(&json)?;
+        if migrated {
+            session.save(path)?;
+        }
+        Ok(session)
+    }
+}
+
+/// The session is serialized as the JSON of its file, see [`Session`], the sessions of the previous versions are
+/// migrated when they're deserialized
+#[cfg(feature = "serde")]
+impl serde::Serialize for Session {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Session {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = Value::deserialize(deserializer)?;
+        Self::from_json(&json).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The earliest expiry of the auth cookies, `None` if there are none or one of them is a session cookie
+fn auth_expiry(cookies: &[PinterestCookie]) -> Option<SystemTime> {
+    let mut auth = cookies
+        .iter()
+        .filter(|cookie| AUTH_COOKIES.contains(&cookie.name.as_str()))
+        .peekable();
+    auth.peek()?;
+    auth.map(|cookie| cookie.expires)
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min()
+}
+
+/// Quotes the value for a POSIX shell: nothing is special between single quotes but the single quote itself, which is
+/// written as `'\''` (close the quotes, an escaped quote, reopen them)
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Migrates the JSON of a session file to the current version, the flag is `true` if it was of a previous version
+fn migrate(mut json: Value) -> Result<(Value, bool), SessionError> {
+    let found = json
+        .get("format_version")
+        .or_else(|| json.get("version"))
+        .and_then(Value::as_u64);
+    let version = found
+        .filter(|version| (MIN_SESSION_VERSION..=SESSION_VERSION).contains(version))
+        .ok_or(SessionError::UnsupportedVersion {
+            found,
+            supported: MIN_SESSION_VERSION..=SESSION_VERSION,
+        })?;
+    if let Value::Object(object) = &mut json {
+        if version < 2 {
+            object.remove("version");
+            object.entry("expires_at").or_insert(Value::Null);
+            object.insert("format_version".to_string(), 2.into());
+        }
+    }
+    Ok((json, version < SESSION_VERSION))
+}
+
+/// The cookies of the JSON object of their names and values
+fn cookies(object: &Map<String, Value>) -> Result<PinterestCookies, SessionError> {
+    object
+        .iter()
+        .map(|(name, value)| match value {
+            Value::String(value) => Ok((name.clone(), value.clone())),
+            _ => Err(SessionError::InvalidField {
+                field: "cookies",
+                reason: "the cookie values must be strings",
+            }),
+        })
+        .collect()
+}
+
+/// Where the sessions are kept, by account.
+///
+/// The crate ships the [`FileSessionStore`] and the [`InMemorySessionStore`], implement it to keep them somewhere
+/// else, like a database, and report its errors as [`SessionError::Store`].
+/// It's object safe so it returns boxed futures, like the [`BrowserLauncher`](crate::launcher::BrowserLauncher).
+///
+/// # Example
+/// ```
+/// use futures::{future::BoxFuture, FutureExt};
+/// use pinterest_login::session::{Session, SessionError, SessionStore};
+///
+/// /// A store that forgets everything
+/// struct Forgetful;
+///
+/// impl SessionStore for Forgetful {
+///     fn get<'a>(&'a self, _account: &'a str) -> BoxFuture<'a, Result<Option<Session>, SessionError>> {
+///         async { Ok(None) }.boxed()
+///     }
+///
+///     fn put<'a>(&'a self, _session: &'a Session) -> BoxFuture<'a, Result<(), SessionError>> {
+///         async { Ok(()) }.boxed()
+///     }
+///
+///     fn delete<'a>(&'a self, _account: &'a str) -> BoxFuture<'a, Result<(), SessionError>> {
+///         async { Ok(()) }.boxed()
+///     }
+///
+///     fn list(&self) -> BoxFuture<'_, Result<Vec<String>, SessionError>> {
+///         async { Ok(Vec::new()) }.boxed()
+///     }
+/// }
+/// ```
+pub trait SessionStore: Send + Sync {
+    /// The session of the account, `None` if there's none
+    fn get<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<Option<Session>, SessionError>>;
+    /// Saves the session, replacing the previous one of its account
+    fn put<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, Result<(), SessionError>>;
+    /// Removes the session of the account, it's not an error if there's none
+    fn delete<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), SessionError>>;
+    /// The accounts that have a session, sorted
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, SessionError>>;
+}
+
+/// Keeps the sessions in a directory, one [`Session::save`]d file per account.
+///
+/// The files are named after their account, with the characters that aren't safe in a file name percent-encoded,
+/// e.g. `me@example.com.json`. They're written atomically, and the directory is created by the first [`put`](SessionStore::put).
+///
+/// The file operations are blocking, but they're short, so they're done in the futures. [`get`](SessionStore::get)
+/// reads the file every time, so it sees the changes of the other processes that share the directory, and
+/// [`watch`](Self::watch) tells when they happen.
+///
+/// # Example
+/// ```
+/// use pinterest_login::session::{FileSessionStore, Session, SessionStore};
+///
+/// # futures::executor::block_on(async {
+/// let dir = tempfile::tempdir()?;
+/// let store = FileSessionStore::new(dir.path().join("sessions"));
+///
+/// store.put(&Session::new("me@example.com", Default::default())).await?;
+/// assert_eq!(store.list().await?, ["me@example.com"]);
+/// assert!(store.path("me@example.com").exists());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # })?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileSessionStore {
+    dir: PathBuf,
+    extension: &'static str,
+    watch_mode: WatchMode,
+}
+
+/// The default interval of [`WatchMode::Poll`]
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How [`FileSessionStore::watch`] notices that a session file changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WatchMode {
+    /// Checks the modification time, the size and the inode of the file at every interval, the default with the
+    /// [`DEFAULT_POLL_INTERVAL`]
+    Poll(Duration),
+    /// Waits for the file system events of the OS, it polls with the [`DEFAULT_POLL_INTERVAL`] when they aren't
+    /// available, e.g. when the directory doesn't exist yet
+    #[cfg(feature = "notify")]
+    Notify,
+}
+
+impl Default for WatchMode {
+    fn default() -> Self {
+        WatchMode::Poll(DEFAULT_POLL_INTERVAL)
+    }
+}
+
+impl FileSessionStore {
+    /// A store of the sessions in the directory
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            extension: "json",
+            watch_mode: WatchMode::default(),
+        }
+    }
+
+    /// How [`watch`](Self::watch) notices the changes of the files
+    pub fn with_watch_mode(mut self, watch_mode: WatchMode) -> Self {
+        self.watch_mode = watch_mode;
+        self
+    }
+
+    /// A store of the files with another extension, for the stores that write their own files
+    #[cfg_attr(not(feature = "encryption"), allow(dead_code))]
+    pub(crate) fn with_extension(mut self, extension: &'static str) -> Self {
+        self.extension = extension;
+        self
+    }
+
+    /// The directory of the sessions
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file of the session of the account
+    pub fn path(&self, account: &str) -> PathBuf {
+        self.dir.join(file_name(account, self.extension))
+    }
+
+    /// The session of the account every time its file changes, starting with the current one, for the sessions that
+    /// are shared with other processes, e.g. a cron job that logs in again.
+    ///
+    /// The files are written atomically by [`put`](SessionStore::put), so a partially written file is never read.
+    /// The files that can't be loaded are skipped until they change again, a rewrite of the same session isn't an
+    /// item, and neither is a removed file. The stream never ends.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use futures::StreamExt;
+    /// use pinterest_login::session::FileSessionStore;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let store = FileSessionStore::new("sessions");
+    /// let mut sessions = store.watch("me@example.com");
+    /// while let Some(session) = sessions.next().await {
+    ///     println!("{} cookies", session.cookies.len());
+    /// }
+    /// # })
+    /// ```
+    pub fn watch(&self, account: &str) -> BoxStream<'static, Session> {
+        let path = self.path(account);
+        let watched = Watched {
+            changes: changes(&path, self.watch_mode),
+            path,
+            fingerprint: None,
+            session: None,
+        };
+        stream::unfold(watched, |mut watched| async move {
+            let session = watched.next().await?;
+            Some((session, watched))
+        })
+        .boxed()
+    }
+
+    /// The account of a session file name, `None` if it's not one
+    fn account_of(&self, file_name: &str) -> Option<String> {
+        let encoded = file_name.strip_suffix(self.extension)?.strip_suffix('.')?;
+        decode(encoded)
+    }
+}
+
+/// A session file of [`FileSessionStore::watch`]
+struct Watched {
+    path: PathBuf,
+    /// An item when the file may have changed
+    changes: BoxStream<'static, ()>,
+    fingerprint: Option<Fingerprint>,
+    /// The last session of the stream
+    session: Option<Session>,
+}
+
+impl Watched {
+    /// The next session that isn't the last one, `None` when the changes end
+    async fn next(&mut self) -> Option<Session> {
+        loop {
+            let fingerprint = fingerprint(&self.path);
+            if fingerprint.is_some() && fingerprint != self.fingerprint {
+                self.fingerprint = fingerprint;
+                match Session::load(&self.path) {
+                    Ok(session) if self.session.as_ref() != Some(&session) => {
+                        self.session = Some(session.clone());
+                        return Some(session);
+                    }
+                    Ok(_) => {}
+                    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+                    Err(error) => {
+                        #[cfg(feature = "log")]
+                        warn!(
+                            "Skipping the changed session file {}: {error}",
+                            self.path.display()
+                        );
+                    }
+                }
+            }
+            self.changes.next().await?;
+        }
+    }
+}
+
+/// What changes when a file is written: its modification time, its size, and its inode since the atomic writes
+/// replace it
+type Fingerprint = (Option<SystemTime>, u64, u64);
+
+/// The fingerprint of the file, `None` if it doesn't exist
+fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let metadata = fs::metadata(path).ok()?;
+    #[cfg(unix)]
+    let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+    #[cfg(not(unix))]
+    let inode = 0;
+    Some((metadata.modified().ok(), metadata.len(), inode))
+}
+
+/// An item every time the file may have changed
+#[cfg_attr(
+    not(feature = "notify"),
+    allow(unused_variables, clippy::infallible_destructuring_match)
+)]
+fn changes(path: &Path, watch_mode: WatchMode) -> BoxStream<'static, ()> {
+    let interval = match watch_mode {
+        WatchMode::Poll(interval) => interval,
+        #[cfg(feature = "notify")]
+        WatchMode::Notify => match file_events(path) {
+            Some(events) => return events,
+            None => DEFAULT_POLL_INTERVAL,
+        },
+    };
+    stream::repeat(())
+        .then(move |()| crate::sleep(interval))
+        .boxed()
+}
+
+/// The events of the file, from a watcher of its directory since the atomic writes replace the file
+#[cfg(feature = "notify")]
+fn file_events(path: &Path) -> Option<BoxStream<'static, ()>> {
+    use notify::Watcher;
+
+    let name = path.file_name()?.to_owned();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (sender, receiver) = futures::channel::mpsc::unbounded();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let changed = event.is_ok_and(|event| {
+            event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == Some(name.as_os_str()))
+        });
+        if changed {
+            let _ = sender.unbounded_send(());
+        }
+    })
+    .ok()?;
+    watcher
+        .watch(dir, notify::RecursiveMode::NonRecursive)
+        .ok()?;
+    // The watcher stops when it's dropped, so it lives as long as the stream
+    Some(
+        receiver
+            .map(move |()| {
+                let _watcher = &watcher;
+            })
+            .boxed(),
+    )
+}
+
+/// The file name of the account, with the characters that aren't safe in a file name percent-encoded
+pub(crate) fn file_name(account: &str, extension: &str) -> String {
+    let mut name = String::with_capacity(account.len() + extension.len() + 1);
+    for (i, byte) in account.bytes().enumerate() {
+        match byte {
+            // A leading dot would make a hidden file, or `..`
+            b'.' if i == 0 => name.push_str("%2E"),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'@' | b'.' | b'_' | b'-' | b'+' => {
+                name.push(byte as char)
+            }
+            _ => name.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    name.push('.');
+    name.push_str(extension);
+    name
+}
+
+/// Decodes the percent-encoded account of a file name
+fn decode(encoded: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes)
+        .ok()
+        .filter(|account| !account.is_empty())
+}
+
+impl SessionStore for FileSessionStore {
+    fn get<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<Option<Session>, SessionError>> {
+        async move {
+            match Session::load(self.path(account)) {
+                Ok(session) => Ok(Some(session)),
+                Err(SessionError::Io(e)) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        .boxed()
+    }
+
+    fn put<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, Result<(), SessionError>> {
+        async move { session.save(self.path(&session.account)) }.boxed()
+    }
+
+    fn delete<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), SessionError>> {
+        async move {
+            match fs::remove_file(self.path(account)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        }
+        .boxed()
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, SessionError>> {
+        async move {
+            let entries = match fs::read_dir(&self.dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => return Err(e.into()),
+            };
+            let mut accounts = Vec::new();
+            for entry in entries {
+                let entry = entry?;
+                if !entry.file_type()?.is_file() {
+                    continue;
+                }
+                if let Some(account) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| self.account_of(name))
+                {
+                    accounts.push(account);
+                }
+            }
+            accounts.sort();
+            Ok(accounts)
+        }
+        .boxed()
+    }
+}
+
+/// Keeps the sessions in memory, for the tests and the short-lived programs
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<BTreeMap<String, Session>>,
+}
+
+impl InMemorySessionStore {
+    /// An empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Session>> {
+        // A panic can't leave the map half updated
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn get<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<Option<Session>, SessionError>> {
+        async move { Ok(self.sessions().get(account).cloned()) }.boxed()
+    }
+
+    fn put<'a>(&'a self, session: &'a Session) -> BoxFuture<'a, Result<(), SessionError>> {
+        async move {
+            self.sessions()
+                .insert(session.account.clone(), session.clone());
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn delete<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), SessionError>> {
+        async move {
+            self.sessions().remove(account);
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, SessionError>> {
+        async move { Ok(self.sessions().keys().cloned().collect()) }.boxed()
+    }
+}
+
+/// The stored session of the account, or a new one from a login that's put in the store when there's none, or when
+/// it expires within the [`DEFAULT_EXPIRY_MARGIN`] (see [`Session::is_expired`]).
+///
+/// It doesn't check the stored session with pinterest, see [`verify_cookies`](crate::verify::verify_cookies) for that.
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::options::LoginOptions;
+/// # use pinterest_login::session::{login_or_load, FileSessionStore};
+/// # async fn run() -> Result<(), pinterest_login::session::SessionError> {
+/// let store = FileSessionStore::new("sessions");
+/// let bot = DefaultBrowserLoginBot::new("me@example.com", "password");
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+///
+/// let session = login_or_load(&store, "me@example.com", &bot, &config_builder, &LoginOptions::new()).await?;
+/// println!("{} cookies", session.cookies.len());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn login_or_load<B, C>(
+    store: &dyn SessionStore,
+    account: &str,
+    login_bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> Result<Session, SessionError>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    if let Some(session) = store.get(account).await? {
+        if session.is_expired(SystemTime::now(), DEFAULT_EXPIRY_MARGIN) == Expiry::Fresh {
+            return Ok(session);
+        }
+    }
+    let outcome = login_with_options(login_bot, config_builder, options).await?;
+    let session = Session::from_outcome(account, &outcome, options);
+    store.put(&session).await?;
+    Ok(session)
+}
@@ -0,0 +1,258 @@
+use std::time::{Duration, SystemTime};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    cookies::{sorted_pairs, PinterestCookie, SameSite},
+    report::WebStorage,
+    session::{Session, AUTH_COOKIES},
+};
+
+/// Why a `storage_state.json` can't be imported
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum StorageStateError {
+    /// The file isn't JSON
+    #[error("The storage state isn't valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The file has no `cookies` array, it's probably not a storage state
+    #[error("The storage state has no `cookies` array")]
+    MissingCookies,
+    /// A field of a cookie is missing or has the wrong type
+    #[error("The cookie {index} of the storage state has an invalid `{field}`: {reason}")]
+    InvalidCookie {
+        /// The index of the cookie in the `cookies` array, from 0
+        index: usize,
+        /// The name of the field
+        field: &'static str,
+        /// What's wrong with it
+        reason: &'static str,
+    },
+}
+
+/// Writes the session in the `storage_state.json` format of Playwright, that
+/// `browser.newContext({ storageState })` loads, so the tests of a Playwright suite start logged in.
+///
+/// The session only has the names and the values of the cookies, so they're put on `.pinterest.com` like
+/// [`into_reqwest_jar`](crate::reqwest_cookies::into_reqwest_jar) does, on the path `/`, secure and with a `Lax`
+/// `sameSite`. The [`AUTH_COOKIES`] expire with the session when its expiry is known, the other ones are session
+/// cookies, with the `-1` expiry of Playwright. See [`export_cookies`] for the cookies with all their attributes.
+///
+/// # Example
+/// ```
+/// use pinterest_login::session::Session;
+/// use pinterest_login::storage_state;
+///
+/// let cookies = [("_pinterest_sess".to_string(), "TWc9PSZs".to_string())].into();
+/// let session = Session::new("me@example.com", cookies);
+///
+/// let state: serde_json::Value = serde_json::from_str(&storage_state::export(&session))?;
+/// assert_eq!(state["cookies"][0]["domain"], ".pinterest.com");
+/// assert_eq!(state["cookies"][0]["expires"], -1);
+/// assert_eq!(state["origins"], serde_json::json!([]));
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub fn export(session: &Session) -> String {
+    let cookies: Vec<_> = sorted_pairs(&session.cookies)
+        .into_iter()
+        .map(|(name, value)| PinterestCookie {
+            expires: session.expires_at.filter(|_| AUTH_COOKIES.contains(&name)),
+            secure: true,
+            same_site: Some(SameSite::Lax),
+            ..PinterestCookie::new(name, value, ".pinterest.com")
+        })
+        .collect();
+    export_cookies(&cookies, None)
+}
+
+/// Same as [`export`], with the cookies of [`login_full`](crate::login_full) and all their attributes, and the
+/// `localStorage` of the page when it was captured, see
+/// [`LoginOptions::capture_storage`](crate::options::LoginOptions::capture_storage). Playwright has no
+/// `sessionStorage` in the format, so it's left out.
+///
+/// The cookies are sorted by name, domain and path like [`to_netscape_string`](crate::cookies_txt::to_netscape_string)
+/// does. A cookie without a `SameSite` attribute gets `Lax`, the browsers treat it like one.
+pub fn export_cookies(cookies: &[PinterestCookie], storage: Option<&WebStorage>) -> String {
+    let mut cookies: Vec<_> = cookies.iter().collect();
+    cookies.sort_by(|a, b| (&a.name, &a.domain, &a.path).cmp(&(&b.name, &b.domain, &b.path)));
+
+    let cookies: Vec<_> = cookies
+        .into_iter()
+        .map(|cookie| {
+            json!({
+                "name": cookie.name,
+                "value": cookie.value,
+                "domain": cookie.domain,
+                "path": cookie.path,
+                "expires": expires(cookie.expires),
+                "httpOnly": cookie.http_only,
+                "secure": cookie.secure,
+                "sameSite": match cookie.same_site {
+                    Some(SameSite::Strict) => "Strict",
+                    Some(SameSite::Lax) | None => "Lax",
+                    Some(SameSite::None) => "None",
+                },
+            })
+        })
+        .collect();
+    let origins: Vec<_> = storage
+        .into_iter()
+        .map(|storage| {
+            let mut local: Vec<_> = storage.local.iter().collect();
+            local.sort_unstable();
+            json!({
+                "origin": storage.origin,
+                "localStorage": local
+                    .into_iter()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    let mut out = serde_json::to_string_pretty(&json!({ "cookies": cookies, "origins": origins }))
+        .expect("the storage state is valid JSON");
+    out.push('\n');
+    out
+}
+
+/// Reads a `storage_state.json` that Playwright wrote with `context.storageState({ path })`, into a session of
+/// the account obtained now.
+///
+/// The cookies of every `pinterest.com` domain are kept, like
+/// [`Session::from_chrome_profile`](crate::session::Session::from_chrome_profile) does, and the session expires with
+/// the earliest of its [`AUTH_COOKIES`]. The `origins` aren't part of a session, so they're ignored.
+///
+/// # Example
+/// ```
+/// use pinterest_login::storage_state;
+///
+/// let session = storage_state::import(
+///     "me@example.com",
+///     r#"{"cookies": [
+///         {"name": "_pinterest_sess", "value": "TWc9PSZs", "domain": ".pinterest.com", "path": "/",
+///          "expires": -1, "httpOnly": true, "secure": true, "sameSite": "None"},
+///         {"name": "_ga", "value": "GA1.1", "domain": ".example.com", "path": "/",
+///          "expires": -1, "httpOnly": false, "secure": false, "sameSite": "Lax"}
+///     ], "origins": []}"#,
+/// )?;
+///
+/// assert_eq!(session.session_cookie()?, "TWc9PSZs");
+/// assert!(!session.cookies.contains("_ga"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+/// See [`import_cookies`]
+pub fn import(account: impl Into<String>, contents: &str) -> Result<Session, StorageStateError> {
+    let cookies: Vec<_> = import_cookies(contents)?
+        .into_iter()
+        .filter(|cookie| {
+            let domain = cookie.domain.trim_start_matches('.');
+            domain == "pinterest.com" || domain.ends_with(".pinterest.com")
+        })
+        .collect();
+    Ok(Session::from_full_cookies(account, &cookies))
+}
+
+/// Reads all the cookies of a `storage_state.json`, whatever their domain, with their attributes.
+///
+/// The `-1` expiry, or any expiry of `0` or less, is a session cookie. The `sameSite` is read whatever its case, and
+/// the `no_restriction` and `unspecified` spellings of the cookie exporting extensions of the browsers are
+/// understood too. The `path`, `httpOnly`, `secure`, `expires` and `sameSite` fields can be missing.
+///
+/// # Errors
+/// * [`StorageStateError::Json`] - If the file isn't JSON
+/// * [`StorageStateError::MissingCookies`] - If it has no `cookies` array
+/// * [`StorageStateError::InvalidCookie`] - If a cookie has no name, value or domain, or a field of the wrong type
+pub fn import_cookies(contents: &str) -> Result<Vec<PinterestCookie>, StorageStateError> {
+    let state: Value = serde_json::from_str(contents)?;
+    let cookies = state
+        .get("cookies")
+        .and_then(Value::as_array)
+        .ok_or(StorageStateError::MissingCookies)?;
+    cookies
+        .iter()
+        .enumerate()
+        .map(|(index, cookie)| {
+            let error = |field, reason| StorageStateError::InvalidCookie {
+                index,
+                field,
+                reason,
+            };
+            let cookie = cookie
+                .as_object()
+                .ok_or(error("cookies", "the cookies must be objects"))?;
+            let string = |field| {
+                cookie
+                    .get(field)
+                    .and_then(Value::as_str)
+                    .ok_or(error(field, "expected a string"))
+            };
+            let flag = |field| match cookie.get(field) {
+                None => Ok(false),
+                Some(value) => value.as_bool().ok_or(error(field, "expected a boolean")),
+            };
+            Ok(PinterestCookie {
+                name: string("name")?.to_string(),
+                value: string("value")?.to_string(),
+                domain: string("domain")?.to_string(),
+                path: match cookie.get("path") {
+                    None => "/".to_string(),
+                    Some(_) => string("path")?.to_string(),
+                },
+                expires: parse_expires(cookie).ok_or(error(
+                    "expires",
+                    "expected the seconds since the unix epoch, or -1",
+                ))?,
+                secure: flag("secure")?,
+                http_only: flag("httpOnly")?,
+                same_site: parse_same_site(cookie)
+                    .ok_or(error("sameSite", "expected Strict, Lax or None"))?,
+            })
+        })
+        .collect()
+}
+
+/// The expiry in seconds since the unix epoch, a whole number when it is one, or `-1` for a session cookie
+fn expires(expires: Option<SystemTime>) -> Value {
+    match expires.and_then(|expires| expires.duration_since(SystemTime::UNIX_EPOCH).ok()) {
+        Some(since) if since.subsec_nanos() == 0 => json!(since.as_secs()),
+        Some(since) => json!(since.as_secs_f64()),
+        None => json!(-1),
+    }
+}
+
+/// The expiry of the cookie, `None` if it's invalid
+fn parse_expires(cookie: &Map<String, Value>) -> Option<Option<SystemTime>> {
+    let Some(expires) = cookie.get("expires") else {
+        return Some(None);
+    };
+    let expires = expires.as_f64().filter(|expires| expires.is_finite())?;
+    if expires <= 0.0 {
+        return Some(None);
+    }
+    let since = Duration::try_from_secs_f64(expires).ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(since).map(Some)
+}
+
+/// The `SameSite` attribute of the cookie, `None` if it's invalid
+fn parse_same_site(cookie: &Map<String, Value>) -> Option<Option<SameSite>> {
+    let Some(same_site) = cookie.get("sameSite") else {
+        return Some(None);
+    };
+    let same_site = same_site.as_str()?;
+    if same_site.eq_ignore_ascii_case("Strict") {
+        Some(Some(SameSite::Strict))
+    } else if same_site.eq_ignore_ascii_case("Lax") {
+        Some(Some(SameSite::Lax))
+    } else if same_site.eq_ignore_ascii_case("None")
+        || same_site.eq_ignore_ascii_case("no_restriction")
+    {
+        Some(Some(SameSite::None))
+    } else if same_site.eq_ignore_ascii_case("unspecified") {
+        Some(None)
+    } else {
+        None
+    }
+}
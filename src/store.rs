@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use futures::{future::BoxFuture, FutureExt};
+
+use crate::{session::file_name, PinterestCookies};
+
+/// The sessions in the keyring of the OS, with [`save`](keyring::save), [`load`](keyring::load) and
+/// [`delete`](keyring::delete)
+#[cfg(feature = "keyring")]
+pub use crate::keyring_store as keyring;
+
+/// Why the cookies of [`load_json`] can't be loaded or the ones of [`save_json`] saved
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum StoreError {
+    /// The file can't be read or written
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The file isn't a JSON object of the cookie names and their string values, it was corrupted or only partly
+    /// written by something else than [`save_json`]
+    #[error("The cookies file is corrupted: {0}")]
+    Corrupted(serde_json::Error),
+    /// A custom [`CookieStore`] failed
+    #[error("{0}")]
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Writes the contents to the file atomically: they are written to a temporary file next to it, which then replaces it,
+/// so the file is never seen half written (e.g. by a job that reads the cookies while they're refreshed), and a failed
+/// write leaves the previous file as it was.
+///
+/// The file is only readable and writable by its owner (`0600`) on unix, since it's meant for the cookies.
+/// The parent directories are created when `create_dirs` is `true`, otherwise a missing parent is an error.
+///
+/// It's blocking, use it from a blocking task in async code.
+///
+/// # Example
+/// ```
+/// use pinterest_login::store::write_atomic;
+///
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("session").join("cookies.json");
+///
+/// write_atomic(&path, br#"{"csrftoken":"token"}"#, true)?;
+/// assert_eq!(std::fs::read_to_string(&path)?, r#"{"csrftoken":"token"}"#);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8], create_dirs: bool) -> io::Result<()> {
+    let path = path.as_ref();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if create_dirs {
+        fs::create_dir_all(dir)?;
+    }
+    // The temporary files are created with the 0600 permissions on unix
+    let mut file = tempfile::Builder::new()
+        .prefix(".pinterest-login-")
+        .tempfile_in(dir)?;
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Saves the cookies to the file as a JSON object of their names and values, sorted by name so the file only changes
+/// with the cookies. It's written with [`write_atomic`]: atomically, only readable by its owner on unix, and with its
+/// parent directories created.
+///
+/// It's blocking, use it from a blocking task in async code.
+///
+/// # Example
+/// ```
+/// use pinterest_login::store::{load_json, save_json};
+///
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("session").join("cookies.json");
+/// let cookies = [("csrftoken".to_string(), "token".to_string())].into();
+///
+/// save_json(&path, &cookies)?;
+/// assert_eq!(std::fs::read_to_string(&path)?, "{\n  \"csrftoken\": \"token\"\n}\n");
+/// assert_eq!(load_json(&path)?, cookies);
+/// # Ok::<(), pinterest_login::store::StoreError>(())
+/// ```
+pub fn save_json(path: impl AsRef<Path>, cookies: &PinterestCookies) -> Result<(), StoreError> {
+    let sorted: BTreeMap<_, _> = cookies.iter().collect();
+    let mut contents =
+        serde_json::to_string_pretty(&sorted).expect("a map of strings is valid JSON");
+    contents.push('\n');
+    write_atomic(path, contents.as_bytes(), true)?;
+    Ok(())
+}
+
+/// Loads the cookies that [`save_json`] saved, or any JSON object of the cookie names and their string values.
+///
+/// It's blocking, use it from a blocking task in async code.
+///
+/// # Errors
+/// * [`StoreError::Io`] - If the file can't be read, like when it doesn't exist
+/// * [`StoreError::Corrupted`] - If the file isn't a JSON object of strings
+pub fn load_json(path: impl AsRef<Path>) -> Result<PinterestCookies, StoreError> {
+    let contents = fs::read(path)?;
+    serde_json::from_slice::<HashMap<_, _>>(&contents)
+        .map(PinterestCookies::from)
+        .map_err(StoreError::Corrupted)
+}
+
+/// A place to keep the cookies of the accounts, like the [`SessionStore`](crate::session::SessionStore) does for the
+/// sessions, so the code that refreshes them can take a `&dyn CookieStore` instead of a path, like
+/// [`refresh_stored_session`](crate::verify::refresh_stored_session) does.
+///
+/// The crate ships the [`MemoryStore`] and the [`JsonFileStore`], implement it to keep them somewhere
+/// else and report its errors as [`StoreError::Backend`]. It's object safe so it returns boxed futures, and they
+/// don't depend on an async runtime.
+///
+/// # Example
+/// ```
+/// use futures::{future::BoxFuture, FutureExt};
+/// use pinterest_login::store::{CookieStore, StoreError};
+/// use pinterest_login::PinterestCookies;
+///
+/// /// A store that forgets everything
+/// struct Forgetful;
+///
+/// impl CookieStore for Forgetful {
+///     fn load<'a>(&'a self, _account: &'a str) -> BoxFuture<'a, Result<Option<PinterestCookies>, StoreError>> {
+///         async { Ok(None) }.boxed()
+///     }
+///
+///     fn save<'a>(
+///         &'a self,
+///         _account: &'a str,
+///         _cookies: &'a PinterestCookies,
+///     ) -> BoxFuture<'a, Result<(), StoreError>> {
+///         async { Ok(()) }.boxed()
+///     }
+///
+///     fn clear<'a>(&'a self, _account: &'a str) -> BoxFuture<'a, Result<(), StoreError>> {
+///         async { Ok(()) }.boxed()
+///     }
+/// }
+/// ```
+pub trait CookieStore: Send + Sync {
+    /// The cookies of the account, `None` if there are none
+    fn load<'a>(
+        &'a self,
+        account: &'a str,
+    ) -> BoxFuture<'a, Result<Option<PinterestCookies>, StoreError>>;
+    /// Saves the cookies of the account, replacing the previous ones
+    fn save<'a>(
+        &'a self,
+        account: &'a str,
+        cookies: &'a PinterestCookies,
+    ) -> BoxFuture<'a, Result<(), StoreError>>;
+    /// Removes the cookies of the account, it's not an error if there are none
+    fn clear<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), StoreError>>;
+}
+
+/// Keeps the cookies in memory, for the tests and the short-lived processes
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    cookies: Mutex<HashMap<String, PinterestCookies>>,
+}
+
+impl MemoryStore {
+    /// An empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn cookies(&self) -> std::sync::MutexGuard<'_, HashMap<String, PinterestCookies>> {
+        self.cookies.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CookieStore for MemoryStore {
+    fn load<'a>(
+        &'a self,
+        account: &'a str,
+    ) -> BoxFuture<'a, Result<Option<PinterestCookies>, StoreError>> {
+        async move { Ok(self.cookies().get(account).cloned()) }.boxed()
+    }
+
+    fn save<'a>(
+        &'a self,
+        account: &'a str,
+        cookies: &'a PinterestCookies,
+    ) -> BoxFuture<'a, Result<(), StoreError>> {
+        async move {
+            self.cookies().insert(account.to_string(), cookies.clone());
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn clear<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), StoreError>> {
+        async move {
+            self.cookies().remove(account);
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+/// Keeps the cookies in a directory, one [`save_json`] file per account.
+///
+/// The files are named after their account like the ones of the
+/// [`FileSessionStore`](crate::session::FileSessionStore), e.g. `me@example.com.json`, so the two stores need their own
+/// directories. The directory is created by the first [`save`](CookieStore::save).
+///
+/// The file operations are blocking, but they're short, so they're done in the futures.
+///
+/// # Example
+/// ```
+/// use pinterest_login::store::{CookieStore, JsonFileStore};
+///
+/// # futures::executor::block_on(async {
+/// let dir = tempfile::tempdir()?;
+/// let store = JsonFileStore::new(dir.path().join("cookies"));
+/// let cookies = [("csrftoken".to_string(), "token".to_string())].into();
+///
+/// store.save("me@example.com", &cookies).await?;
+/// assert!(store.path("me@example.com").exists());
+/// assert_eq!(store.load("me@example.com").await?, Some(cookies));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # })?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    dir: PathBuf,
+}
+
+impl JsonFileStore {
+    /// A store in the directory
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The directory of the cookies
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file of the cookies of the account
+    pub fn path(&self, account: &str) -> PathBuf {
+        self.dir.join(file_name(account, "json"))
+    }
+}
+
+impl CookieStore for JsonFileStore {
+    fn load<'a>(
+        &'a self,
+        account: &'a str,
+    ) -> BoxFuture<'a, Result<Option<PinterestCookies>, StoreError>> {
+        async move {
+            match load_json(self.path(account)) {
+                Ok(cookies) => Ok(Some(cookies)),
+                Err(StoreError::Io(e)) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+        .boxed()
+    }
+
+    fn save<'a>(
+        &'a self,
+        account: &'a str,
+        cookies: &'a PinterestCookies,
+    ) -> BoxFuture<'a, Result<(), StoreError>> {
+        async move { save_json(self.path(account), cookies) }.boxed()
+    }
+
+    fn clear<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), StoreError>> {
+        async move {
+            match fs::remove_file(self.path(account)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use sha1::{Digest, Sha1};
+
+/// The period of the codes, they change every 30 seconds
+pub const TOTP_PERIOD: Duration = Duration::from_secs(30);
+/// The number of digits of the codes
+pub const TOTP_DIGITS: u32 = 6;
+
+/// The base32 secret of an authenticator app, that generates the two-factor authentication codes (RFC 6238,
+/// with HMAC-SHA1, 6 digits and a 30 seconds period like pinterest and most of the sites use)
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use pinterest_login::totp::TotpSecret;
+///
+/// // The secret is case insensitive, and the spaces and the padding are ignored
+/// let secret: TotpSecret = "gezd gnbv gy3t qojq gezd gnbv gy3t qojq".parse()?;
+///
+/// // The test vectors of RFC 6238
+/// let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+/// assert_eq!(secret.code_at(at(59)), "287082");
+/// assert_eq!(secret.code_at(at(1_111_111_109)), "081804");
+/// assert_eq!(secret.code_at(at(2_000_000_000)), "279037");
+/// # Ok::<(), pinterest_login::totp::InvalidTotpSecret>(())
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct TotpSecret {
+    key: Vec<u8>,
+}
+
+/// The error of a [`TotpSecret`] that isn't valid base32
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("The TOTP secret isn't valid base32")]
+pub struct InvalidTotpSecret;
+
+impl TotpSecret {
+    /// The code at the given time
+    pub fn code_at(&self, time: SystemTime) -> String {
+        let counter = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            / TOTP_PERIOD.as_secs();
+        let mac = hmac_sha1(&self.key, &counter.to_be_bytes());
+        // The dynamic truncation of RFC 4226
+        let offset = (mac[19] & 0xf) as usize;
+        let binary = u32::from_be_bytes([
+            mac[offset],
+            mac[offset + 1],
+            mac[offset + 2],
+            mac[offset + 3],
+        ]) & 0x7fff_ffff;
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(TOTP_DIGITS),
+            width = TOTP_DIGITS as usize
+        )
+    }
+
+    /// The current code
+    pub fn code(&self) -> String {
+        self.code_at(SystemTime::now())
+    }
+}
+
+impl std::str::FromStr for TotpSecret {
+    type Err = InvalidTotpSecret;
+
+    fn from_str(secret: &str) -> Result<Self, Self::Err> {
+        let secret: String = secret
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        let key = data_encoding::BASE32_NOPAD
+            .decode(secret.as_bytes())
+            .map_err(|_| InvalidTotpSecret)?;
+        if key.is_empty() {
+            return Err(InvalidTotpSecret);
+        }
+        Ok(Self { key })
+    }
+}
+
+impl fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // It's as sensitive as a password
+        f.write_str("TotpSecret(..)")
+    }
+}
+
+/// HMAC (RFC 2104) with SHA-1
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..20].copy_from_slice(&Sha1::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha1::new()
+        .chain_update(block.map(|b| b ^ 0x36))
+        .chain_update(message)
+        .finalize();
+    Sha1::new()
+        .chain_update(block.map(|b| b ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
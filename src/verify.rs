@@ -0,0 +1,374 @@
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::cdp::browser_protocol::{
+    network::{Cookie, CookieParam, SetCookiesParams},
+    target::CreateTargetParams,
+};
+#[cfg(feature = "log")]
+use log::{debug, info};
+
+use crate::{
+    browser_guard::TaskRunner,
+    collect_cookies,
+    config_builder::BrowserConfigBuilder,
+    guarded, launch_browser,
+    login_bot::{is_login_url, BrowserLoginBot, Host},
+    metrics, navigate,
+    options::{LoginOptions, Target},
+    report::LoginOutcome,
+    run_login,
+    store::CookieStore,
+    LoginPhase, PinterestCookies, PinterestLoginError,
+};
+
+/// The result of [`verify_cookies`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct VerifyReport {
+    /// Whether the cookies are still logged in
+    pub valid: bool,
+    /// When the cookies were checked
+    pub checked_at: SystemTime,
+    /// The earliest expiry of the cookies, `None` if none of them has an expiry (e.g. they're all session cookies)
+    pub earliest_expiry: Option<SystemTime>,
+}
+
+/// Checks if previously saved cookies are still logged in, without the credentials.
+///
+/// The cookies are set in a fresh browser, which then opens the login page of the [`Target`] of the options:
+/// pinterest sends the logged in users away from it, so the cookies are valid if the browser doesn't end up on it.
+/// The cookies without a domain or an url are set for the login page url.
+///
+/// An expired or revoked session isn't an error, it's reported with [`VerifyReport::valid`] set to `false`,
+/// the errors are the browser failures, like with [`login_with_options`](crate::login_with_options).
+///
+/// # Example
+/// ```no_run
+/// # use chromiumoxide::cdp::browser_protocol::network::CookieParam;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::options::LoginOptions;
+/// # use pinterest_login::verify::verify_cookies;
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let cookies = vec![CookieParam::new("_pinterest_sess", "...")];
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+///
+/// let report = verify_cookies(cookies, &config_builder, &LoginOptions::default()).await?;
+/// if !report.valid {
+///     // Log in again
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn verify_cookies<C>(
+    cookies: Vec<CookieParam>,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> crate::Result<VerifyReport>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let earliest_expiry = earliest_expiry(&cookies);
+    let runner = TaskRunner::new(options.task_mode);
+    let (valid, _) = runner
+        .drive(verify(cookies, config_builder, options, false, &runner))
+        .await?;
+    Ok(VerifyReport {
+        valid,
+        checked_at: SystemTime::now(),
+        earliest_expiry,
+    })
+}
+
+/// Logs in with previously saved cookies instead of the credentials, and returns the cookies of the browser
+/// afterwards, that pinterest may have rotated, so they're the ones to save for the next time.
+///
+/// The cookies are checked like [`verify_cookies`] does, with the default [`LoginOptions`], see
+/// [`login_with_cookies_and_options`]. Reusing the cookies instead of filling the login form on every run makes the
+/// challenges less likely.
+///
+/// # Example
+/// ```no_run
+/// # use chromiumoxide::cdp::browser_protocol::network::CookieParam;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::verify::login_with_cookies;
+/// # async fn run(saved: pinterest_login::PinterestCookies) -> pinterest_login::Result<()> {
+/// let cookies = saved
+///     .into_iter()
+///     .map(|(name, value)| CookieParam::new(name, value))
+///     .collect();
+///
+/// let cookies = login_with_cookies(cookies, &DefaultBrowserConfigBuilder::default()).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// * `AuthenticationError` - If pinterest sent the browser back to the login page, the cookies are expired or revoked
+/// * The browser failures, like with [`login`](crate::login)
+pub async fn login_with_cookies<C>(
+    cookies: Vec<CookieParam>,
+    config_builder: &C,
+) -> crate::Result<PinterestCookies>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    login_with_cookies_and_options(cookies, config_builder, &LoginOptions::default()).await
+}
+
+/// Same as [`login_with_cookies`], but with extra [`LoginOptions`], like the [`Target`] that checks the cookies
+pub async fn login_with_cookies_and_options<C>(
+    cookies: Vec<CookieParam>,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> crate::Result<PinterestCookies>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let runner = TaskRunner::new(options.task_mode);
+    let (valid, cookies) = runner
+        .drive(verify(cookies, config_builder, options, true, &runner))
+        .await?;
+    if !valid {
+        return Err(PinterestLoginError::AuthenticationError);
+    }
+    Ok(options
+        .filter_cookies(cookies)?
+        .into_iter()
+        .map(|cookie| (cookie.name, cookie.value))
+        .collect())
+}
+
+/// How [`refresh_session`] logged in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RefreshPath {
+    /// The stored cookies were still logged in, the login form was skipped
+    Cookies,
+    /// The stored cookies were logged out, the login bot filled the login form
+    Credentials,
+}
+
+/// The result of [`refresh_session`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RefreshOutcome {
+    /// How the login went, with the stored cookies or with the credentials
+    pub path: RefreshPath,
+    /// The cookies of the login, with its report
+    pub outcome: LoginOutcome,
+}
+
+/// Logs in with the stored cookies when they're still logged in, and with the login bot only when they aren't.
+///
+/// A single browser is launched: the cookies are set in it before the navigation to the login page, and pinterest
+/// sends it away from the page if they're logged in, so the form is skipped like with a persistent profile, see
+/// [`LoginOptions::on_already_authenticated`]. Otherwise the login bot fills the form on that same page. Either way
+/// the returned cookies are the ones of the browser afterwards, to store for the next time.
+///
+/// # Example
+/// ```no_run
+/// # use chromiumoxide::cdp::browser_protocol::network::CookieParam;
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::verify::refresh_session;
+/// # async fn run(stored: Vec<CookieParam>) -> pinterest_login::Result<()> {
+/// let bot = DefaultBrowserLoginBot::new("email", "password");
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+///
+/// let refreshed = refresh_session(stored, &bot, &config_builder).await?;
+/// println!("Logged in with the {:?}", refreshed.path);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// The same as [`login`](crate::login), the `AuthenticationError` only comes from the login bot, since logged out
+/// cookies aren't an error
+pub async fn refresh_session<B, C>(
+    cookies: Vec<CookieParam>,
+    login_bot: &B,
+    config_builder: &C,
+) -> crate::Result<RefreshOutcome>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    refresh_session_with_options(cookies, login_bot, config_builder, &LoginOptions::default()).await
+}
+
+/// Same as [`refresh_session`], but with extra [`LoginOptions`]
+pub async fn refresh_session_with_options<B, C>(
+    cookies: Vec<CookieParam>,
+    login_bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> crate::Result<RefreshOutcome>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let runner = TaskRunner::new(options.task_mode);
+    let outcome = metrics::recorded(runner.drive(run_login(
+        login_bot,
+        config_builder,
+        options,
+        cookies,
+        &runner,
+    )))
+    .await?;
+    let path = match outcome.report.already_authenticated {
+        true => RefreshPath::Cookies,
+        false => RefreshPath::Credentials,
+    };
+    #[cfg(feature = "log")]
+    info!("The session was refreshed with the {path:?}");
+    Ok(RefreshOutcome { path, outcome })
+}
+
+/// Same as [`refresh_session_with_options`], with the cookies of the account in the store, that are replaced by the
+/// cookies of the refresh. The login bot logs in when the store has no cookies of the account.
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// # use pinterest_login::options::LoginOptions;
+/// # use pinterest_login::store::JsonFileStore;
+/// # use pinterest_login::verify::refresh_stored_session;
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let store = JsonFileStore::new("cookies");
+/// let bot = DefaultBrowserLoginBot::new("me@example.com", "password");
+/// let config_builder = DefaultBrowserConfigBuilder::default();
+///
+/// let refreshed =
+///     refresh_stored_session(&store, "me@example.com", &bot, &config_builder, &LoginOptions::new()).await?;
+/// println!("Logged in with the {:?}", refreshed.path);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// The errors of [`refresh_session`], and [`PinterestLoginError::StoreError`](crate::PinterestLoginError::StoreError)
+/// with the errors of the store. The stored cookies are left as they are when the login fails
+pub async fn refresh_stored_session<B, C>(
+    store: &dyn CookieStore,
+    account: &str,
+    login_bot: &B,
+    config_builder: &C,
+    options: &LoginOptions,
+) -> crate::Result<RefreshOutcome>
+where
+    B: BrowserLoginBot + ?Sized,
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let cookies = store
+        .load(account)
+        .await?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| CookieParam::new(name, value))
+        .collect();
+    let refreshed =
+        refresh_session_with_options(cookies, login_bot, config_builder, options).await?;
+    store.save(account, &refreshed.outcome.cookies).await?;
+    Ok(refreshed)
+}
+
+/// Opens the login page with the cookies, returns whether they're logged in, with the cookies of the browser
+/// afterwards if they are and `collect` is set
+async fn verify<C>(
+    cookies: Vec<CookieParam>,
+    config_builder: &C,
+    options: &LoginOptions,
+    collect: bool,
+    runner: &TaskRunner,
+) -> crate::Result<(bool, Vec<Cookie>)>
+where
+    C: BrowserConfigBuilder + ?Sized,
+{
+    let login_url = options.target.url();
+    let cookies = scoped_to(cookies, login_url);
+
+    #[cfg(feature = "log")]
+    info!("Launching the browser to verify the cookies");
+    let (mut browser, _) = launch_browser(config_builder, options, runner).await?;
+    let disconnected = browser.disconnected();
+
+    let page = guarded(&disconnected, LoginPhase::Setup, async {
+        if !options.no_incognito {
+            browser.start_incognito_context().await?;
+        }
+        let page = browser.new_page(CreateTargetParams::default()).await?;
+        page.enable_stealth_mode().await?;
+        // `Page::set_cookies` needs an http page, the cookies have their url already
+        page.execute(SetCookiesParams::new(cookies)).await?;
+        Ok(page)
+    })
+    .await?;
+
+    guarded(
+        &disconnected,
+        LoginPhase::Navigation,
+        navigate(&page, login_url, config_builder, options),
+    )
+    .await?;
+
+    let url = guarded(&disconnected, LoginPhase::CheckLogin, async {
+        page.wait_for_navigation().await?;
+        Ok(page.url().await?)
+    })
+    .await?;
+    #[cfg(feature = "log")]
+    debug!("The verification ended up on: {url:?}");
+    let valid = url.is_some_and(|url| !is_login_url(&url, &login_host(&options.target)));
+    if !(valid && collect) {
+        return Ok((valid, Vec::new()));
+    }
+
+    let cookies = guarded(
+        &disconnected,
+        LoginPhase::CollectCookies,
+        collect_cookies(&page, options),
+    )
+    .await?;
+    Ok((true, cookies))
+}
+
+/// Sets the url of the cookies that have no domain nor url, `Page::set_cookies` needs an http page for them
+pub(crate) fn scoped_to(mut cookies: Vec<CookieParam>, url: &str) -> Vec<CookieParam> {
+    for cookie in &mut cookies {
+        if cookie.url.is_none() && cookie.domain.is_none() {
+            cookie.url = Some(url.to_string());
+        }
+    }
+    cookies
+}
+
+/// The host of the login page of the target
+pub(crate) fn login_host(target: &Target) -> Host {
+    match target {
+        Target::Pinterest => Host::pinterest(),
+        Target::Url(url) => match url::Url::parse(url) {
+            Ok(url) => {
+                let host = url.host_str().unwrap_or_default();
+                match url.port() {
+                    Some(port) => Host::new(format!("{host}:{port}")),
+                    None => Host::new(host),
+                }
+            }
+            Err(_) => Host::new(url.as_str()),
+        },
+    }
+}
+
+fn earliest_expiry(cookies: &[CookieParam]) -> Option<SystemTime> {
+    cookies
+        .iter()
+        .filter_map(|cookie| cookie.expires.as_ref().map(|e| *e.inner()))
+        // The session cookies are saved with an expiry of -1 by some tools, and the expiries that aren't times
+        // (like an infinite one) are ignored
+        .filter(|expires| *expires > 0.0)
+        .filter_map(|expires| Duration::try_from_secs_f64(expires).ok())
+        .filter_map(|since| SystemTime::UNIX_EPOCH.checked_add(since))
+        .min()
+}
@@ -0,0 +1,657 @@
+//! The login in any browser that has a [WebDriver](https://www.w3.org/TR/webdriver2/) server, like Firefox with
+//! [geckodriver](https://github.com/mozilla/geckodriver), for the machines without a Chrome/Chromium to drive over
+//! CDP.
+//!
+//! The [`BrowserLoginBot`](crate::login_bot::BrowserLoginBot)s work with the chromiumoxide page, so only the
+//! [`PageLoginBot`](crate::login_bot::PageLoginBot)s can log in over WebDriver: the [`DefaultBrowserLoginBot`](crate::login_bot::DefaultBrowserLoginBot)
+//! and the bots written against the [`LoginPage`](crate::page::LoginPage) abstraction. The engine is selected with
+//! [`LoginOptions::engine`](crate::options::LoginOptions::engine), and the login goes through [`login_with_engine`](crate::login_with_engine).
+//!
+//! The WebDriver server isn't started by the login, it must be running already (`geckodriver --port 4444`). Only the
+//! [`target`](crate::options::LoginOptions::target) and the [`clock`](crate::options::LoginOptions::clock) of the options apply to this engine, the
+//! others are about the CDP browser. The WebDriver session is deleted when the login completes, and in the background
+//! on the runtime of the crate when the login future is dropped before, e.g. by a timeout.
+//!
+//! The commands are sent with a small WebDriver client of the crate, over HTTP/1.1 on the async runtime of the crate,
+//! rather than with `fantoccini`, which only runs on tokio: so only the `http://` servers are supported.
+//!
+//! # Example
+//! ```no_run
+//! use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+//! use pinterest_login::login_bot::DefaultBrowserLoginBot;
+//! use pinterest_login::login_with_engine;
+//! use pinterest_login::options::{Engine, LoginOptions};
+//! use pinterest_login::webdriver::WebDriverConfig;
+//!
+//! # async fn run() -> pinterest_login::Result<()> {
+//! let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+//! let options = LoginOptions::new().engine(Engine::WebDriver(WebDriverConfig::default()));
+//!
+//! let outcome = login_with_engine(&bot, &DefaultBrowserConfigBuilder::default(), &options).await?;
+//! println!("{:?}", outcome.cookies);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io,
+    pin::{pin, Pin},
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use chromiumoxide::{
+    cdp::browser_protocol::network::{Cookie, CookiePriority, CookieSourceScheme},
+    error::CdpError,
+    layout::BoundingBox,
+};
+use futures::future::{select, Either};
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Bytes, client::conn::http1, header, Method, Request, StatusCode};
+#[cfg(feature = "log")]
+use log::{debug, info};
+use serde_json::{json, Value};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    login_bot::PageLoginBot,
+    options::{LoginOptions, ReadyWhen},
+    page::{LoginElement, LoginPage},
+    report::{LoginOutcome, LoginReport},
+    LoginPhase, PinterestCookies, PinterestLoginError,
+};
+
+/// The default url of the WebDriver server, the default port of geckodriver
+pub const DEFAULT_WEBDRIVER_URL: &str = "http://localhost:4444";
+/// The key of the element references in the WebDriver responses
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f926f4c3e6e";
+/// How often the page is checked for the end of its navigation
+const NAVIGATION_POLL: Duration = Duration::from_millis(100);
+
+/// The browser that the WebDriver server drives, see [`WebDriverConfig::browser`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WebDriverBrowser {
+    /// Firefox, with geckodriver
+    #[default]
+    Firefox,
+    /// Chrome/Chromium, with chromedriver
+    Chrome,
+}
+
+/// Where the WebDriver server is and how it starts the browser, see [`Engine::WebDriver`](crate::options::Engine::WebDriver)
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use pinterest_login::webdriver::{WebDriverBrowser, WebDriverConfig};
+///
+/// let config = WebDriverConfig::new("http://localhost:9515")
+///     .browser(WebDriverBrowser::Chrome)
+///     .headless(false)
+///     .timeout(Duration::from_secs(10));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebDriverConfig {
+    url: String,
+    browser: WebDriverBrowser,
+    headless: bool,
+    timeout: Duration,
+}
+
+impl Default for WebDriverConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_WEBDRIVER_URL)
+    }
+}
+
+impl WebDriverConfig {
+    /// Drives a headless Firefox with the WebDriver server of the url
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            browser: WebDriverBrowser::default(),
+            headless: true,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// Sets the browser that the server drives, Firefox by default
+    pub fn browser(mut self, browser: WebDriverBrowser) -> Self {
+        self.browser = browser;
+        self
+    }
+
+    /// Shows the browser window if `false`, it's headless by default
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    /// Sets the timeout of every WebDriver command and of the navigations, 60 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The capabilities of the new session
+    fn capabilities(&self) -> Value {
+        let capabilities = match self.browser {
+            WebDriverBrowser::Firefox => json!({
+                "browserName": "firefox",
+                "moz:firefoxOptions": { "args": if self.headless { vec!["-headless"] } else { vec![] } },
+            }),
+            WebDriverBrowser::Chrome => json!({
+                "browserName": "chrome",
+                "goog:chromeOptions": { "args": if self.headless { vec!["--headless=new"] } else { vec![] } },
+            }),
+        };
+        json!({ "capabilities": { "alwaysMatch": capabilities } })
+    }
+}
+
+/// Logs in with the bot in a browser of the WebDriver server, see the [module docs](self)
+pub(crate) async fn login<B>(
+    login_bot: &B,
+    config: &WebDriverConfig,
+    options: &LoginOptions,
+) -> crate::Result<LoginOutcome>
+where
+    B: PageLoginBot + ?Sized,
+{
+    #[cfg(feature = "log")]
+    info!("Starting a {:?} session on {}", config.browser, config.url);
+    let page = WebDriverPage::start(config, options).await?;
+    let guard = SessionGuard(Some(Arc::clone(&page.session)));
+    let res = run_login(login_bot, &page, options).await;
+    guard.delete().await;
+    res
+}
+
+/// Deletes the WebDriver session in the background if the login future is dropped before it does, so the browser of
+/// the server doesn't outlive the login
+struct SessionGuard(Option<Arc<Session>>);
+
+impl SessionGuard {
+    /// Deletes the session, the guard takes over again if this future is dropped
+    async fn delete(mut self) {
+        if let Some(session) = &self.0 {
+            session.delete().await;
+        }
+        self.0 = None;
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let Some(session) = self.0.take() else {
+            return;
+        };
+        // Spawning outside of a tokio runtime panics, e.g. when the runtime is shutting down
+        #[cfg(feature = "tokio")]
+        if tokio::runtime::Handle::try_current().is_err() {
+            #[cfg(feature = "log")]
+            debug!("No runtime to delete the WebDriver session, it's left to the server");
+            return;
+        }
+        #[cfg(feature = "log")]
+        debug!("The login was dropped, deleting the WebDriver session in the background");
+        crate::spawn(async move { session.delete().await });
+    }
+}
+
+async fn run_login<B>(
+    login_bot: &B,
+    page: &WebDriverPage,
+    options: &LoginOptions,
+) -> crate::Result<LoginOutcome>
+where
+    B: PageLoginBot + ?Sized,
+{
+    let mut report = LoginReport {
+        launch_attempts: 1,
+        navigation_attempts: 1,
+        ..Default::default()
+    };
+    report.ready = crate::metrics::timed(LoginPhase::Navigation, async {
+        page.goto(options.target.url()).await?;
+        // The network events are CDP ones, WebDriver only waits for the page load
+        let ready_when = match &options.ready_when {
+            ReadyWhen::NetworkIdle { .. } => &ReadyWhen::DomContentLoaded,
+            ready_when => ready_when,
+        };
+        crate::ready::wait_until_ready(page, ready_when, None, &*options.get_clock()).await
+    })
+    .await?;
+    crate::metrics::timed(LoginPhase::FillForm, login_bot.fill_login_form(page)).await?;
+    crate::metrics::timed(LoginPhase::SubmitForm, login_bot.submit_login_form(page)).await?;
+    report.selectors = login_bot.matched_selectors();
+    crate::metrics::timed(LoginPhase::CheckLogin, login_bot.check_login(page)).await?;
+    let cookie_details =
+        crate::metrics::timed(LoginPhase::CollectCookies, page.get_cookies()).await?;
+    let cookie_details = options.filter_cookies(cookie_details)?;
+
+    let cookies: PinterestCookies = cookie_details
+        .iter()
+        .map(|cookie| (cookie.name.clone(), cookie.value.clone()))
+        .collect();
+    #[cfg(feature = "log")]
+    debug!("The cookies: {cookies:?}");
+    Ok(LoginOutcome {
+        cookies,
+        cookie_details,
+        report,
+        storage: None,
+    })
+}
+
+/// A WebDriver session, the commands are sent to its url
+#[derive(Debug)]
+struct Session {
+    url: String,
+    timeout: Duration,
+}
+
+impl Session {
+    /// Sends the command, returns the `value` of its response
+    async fn command(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> crate::Result<Value> {
+        send(method, &format!("{}{path}", self.url), body, self.timeout).await
+    }
+
+    /// Deletes the session, which closes the browser, the errors are only logged
+    async fn delete(&self) {
+        let res = self.command(Method::DELETE, "", None).await;
+        #[cfg(feature = "log")]
+        if let Err(e) = res {
+            debug!("Couldn't delete the WebDriver session: {e}");
+        }
+        #[cfg(not(feature = "log"))]
+        let _ = res;
+    }
+}
+
+/// Sends a WebDriver command, returns the `value` of its response
+async fn send(
+    method: Method,
+    url: &str,
+    body: Option<Value>,
+    timeout: Duration,
+) -> crate::Result<Value> {
+    // The timeout of the commands is a real one, even with the manual clock of the tests
+    let (status, body) = crate::timeout(&SystemClock, timeout, exchange(method, url, body))
+        .await
+        .ok_or_else(|| {
+            PinterestLoginError::WebDriverError(format!("no answer within {timeout:?}"))
+        })?
+        .map_err(PinterestLoginError::WebDriverError)?;
+    let mut json: Value = serde_json::from_slice(&body).map_err(|e| {
+        PinterestLoginError::WebDriverError(format!("invalid response ({status}): {e}"))
+    })?;
+    let value = json["value"].take();
+    if status.is_success() {
+        return Ok(value);
+    }
+    match value["error"].as_str() {
+        Some("no such element") => Err(CdpError::NotFound.into()),
+        Some(error) => Err(PinterestLoginError::WebDriverError(format!(
+            "{error}: {}",
+            value["message"].as_str().unwrap_or_default()
+        ))),
+        None => Err(PinterestLoginError::WebDriverError(format!(
+            "the server answered {status}"
+        ))),
+    }
+}
+
+/// Sends the request on a new connection to the server, returns the status and the body of the response
+async fn exchange(
+    method: Method,
+    url: &str,
+    body: Option<Value>,
+) -> Result<(StatusCode, Bytes), String> {
+    let url = url::Url::parse(url).map_err(|e| format!("invalid url `{url}`: {e}"))?;
+    if url.scheme() != "http" {
+        return Err(format!("only the http servers are supported, not `{url}`"));
+    }
+    let host = match url.host() {
+        Some(url::Host::Domain(domain)) => domain.to_string(),
+        Some(url::Host::Ipv4(ip)) => ip.to_string(),
+        Some(url::Host::Ipv6(ip)) => ip.to_string(),
+        None => return Err(format!("the url `{url}` has no host")),
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    let mut request = Request::builder()
+        .method(method)
+        .uri(&url[url::Position::BeforePath..])
+        .header(
+            header::HOST,
+            &url[url::Position::BeforeHost..url::Position::AfterPort],
+        );
+    if body.is_some() {
+        request = request.header(header::CONTENT_TYPE, "application/json");
+    }
+    let request = request
+        .body(Full::new(Bytes::from(
+            body.map(|body| body.to_string()).unwrap_or_default(),
+        )))
+        .map_err(|e| e.to_string())?;
+
+    let stream = Io::connect(&host, port)
+        .await
+        .map_err(|e| format!("can't connect to {host}:{port}: {e}"))?;
+    let (mut sender, connection) = http1::handshake(stream).await.map_err(|e| e.to_string())?;
+    let response = async move {
+        let response = sender.send_request(request).await?;
+        let status = response.status();
+        Ok((status, response.into_body().collect().await?.to_bytes()))
+    };
+    // The connection is driven until the response is read, and it's closed when it's dropped
+    match select(pin!(connection), pin!(response)).await {
+        Either::Left((Err(e), _)) => Err(e),
+        Either::Left((Ok(()), response)) => response.await,
+        Either::Right((response, _)) => response,
+    }
+    .map_err(|e: hyper::Error| e.to_string())
+}
+
+/// A TCP connection of the async runtime of the crate, for hyper
+struct Io {
+    #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+    stream: async_std::net::TcpStream,
+    #[cfg(feature = "tokio")]
+    stream: tokio::net::TcpStream,
+}
+
+impl Io {
+    async fn connect(host: &str, port: u16) -> io::Result<Self> {
+        #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+        let stream = async_std::net::TcpStream::connect((host, port)).await?;
+        #[cfg(feature = "tokio")]
+        let stream = tokio::net::TcpStream::connect((host, port)).await?;
+        Ok(Self { stream })
+    }
+}
+
+impl hyper::rt::Read for Io {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        mut buf: hyper::rt::ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        // The buffer of hyper can be uninitialized, the bytes are read in this one and copied
+        let mut read = [0; 8192];
+        let len = buf.remaining().min(read.len());
+        #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+        let res = futures::AsyncRead::poll_read(Pin::new(&mut self.stream), cx, &mut read[..len]);
+        #[cfg(feature = "tokio")]
+        let res = {
+            let mut read_buf = tokio::io::ReadBuf::new(&mut read[..len]);
+            tokio::io::AsyncRead::poll_read(Pin::new(&mut self.stream), cx, &mut read_buf)
+                .map_ok(|()| read_buf.filled().len())
+        };
+        res.map_ok(|n| buf.put_slice(&read[..n]))
+    }
+}
+
+impl hyper::rt::Write for Io {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+        return futures::AsyncWrite::poll_write(Pin::new(&mut self.stream), cx, buf);
+        #[cfg(feature = "tokio")]
+        return tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.stream), cx, buf);
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+        return futures::AsyncWrite::poll_flush(Pin::new(&mut self.stream), cx);
+        #[cfg(feature = "tokio")]
+        return tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.stream), cx);
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+        return futures::AsyncWrite::poll_close(Pin::new(&mut self.stream), cx);
+        #[cfg(feature = "tokio")]
+        return tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.stream), cx);
+    }
+}
+
+/// The page of a WebDriver session, as a [`LoginPage`] for the bots
+#[derive(Clone)]
+struct WebDriverPage {
+    session: Arc<Session>,
+    clock: Arc<dyn Clock>,
+}
+
+impl WebDriverPage {
+    /// Starts a new session
+    async fn start(config: &WebDriverConfig, options: &LoginOptions) -> crate::Result<Self> {
+        let value = crate::metrics::timed(
+            LoginPhase::Launch,
+            send(
+                Method::POST,
+                &format!("{}/session", config.url),
+                Some(config.capabilities()),
+                config.timeout,
+            ),
+        )
+        .await?;
+        let Some(id) = value["sessionId"].as_str() else {
+            return Err(PinterestLoginError::WebDriverError(
+                "the new session has no id".to_string(),
+            ));
+        };
+        #[cfg(feature = "log")]
+        debug!("The WebDriver session {id} has started");
+        Ok(Self {
+            session: Arc::new(Session {
+                url: format!("{}/session/{id}", config.url),
+                timeout: config.timeout,
+            }),
+            clock: options.get_clock(),
+        })
+    }
+
+    /// Navigates to the url, it returns when the page is loaded
+    async fn goto(&self, url: &str) -> crate::Result<()> {
+        #[cfg(feature = "log")]
+        info!("Navigating to the login page: {url}");
+        self.session
+            .command(Method::POST, "/url", Some(json!({ "url": url })))
+            .await?;
+        Ok(())
+    }
+
+    fn element(&self, value: &Value) -> crate::Result<WebDriverElement> {
+        match value[ELEMENT_KEY].as_str() {
+            Some(id) => Ok(WebDriverElement {
+                session: Arc::clone(&self.session),
+                id: id.to_string(),
+            }),
+            None => Err(PinterestLoginError::WebDriverError(format!(
+                "invalid element reference: {value}"
+            ))),
+        }
+    }
+}
+
+impl LoginPage for WebDriverPage {
+    type Element = WebDriverElement;
+
+    async fn find_element(&self, selector: &str) -> crate::Result<WebDriverElement> {
+        let value = self
+            .session
+            .command(
+                Method::POST,
+                "/element",
+                Some(json!({ "using": "css selector", "value": selector })),
+            )
+            .await?;
+        self.element(&value)
+    }
+
+    async fn find_xpaths(&self, xpath: &str) -> crate::Result<Vec<WebDriverElement>> {
+        let value = self
+            .session
+            .command(
+                Method::POST,
+                "/elements",
+                Some(json!({ "using": "xpath", "value": xpath })),
+            )
+            .await?;
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|element| self.element(element))
+            .collect()
+    }
+
+    async fn wait_for_navigation(&self) -> crate::Result<()> {
+        // The navigations of the clicks aren't awaited by the server, unlike the ones of the `url` command
+        let clock = &self.clock;
+        let start = clock.now();
+        loop {
+            let state = self
+                .session
+                .command(
+                    Method::POST,
+                    "/execute/sync",
+                    Some(json!({ "script": "return document.readyState", "args": [] })),
+                )
+                .await?;
+            if state == "complete" {
+                return Ok(());
+            }
+            if clock.now().duration_since(start) >= self.session.timeout {
+                return Err(CdpError::Timeout.into());
+            }
+            clock.sleep(NAVIGATION_POLL).await;
+        }
+    }
+
+    async fn url(&self) -> crate::Result<Option<String>> {
+        let value = self.session.command(Method::GET, "/url", None).await?;
+        Ok(value.as_str().map(str::to_string))
+    }
+
+    async fn content(&self) -> crate::Result<String> {
+        let value = self.session.command(Method::GET, "/source", None).await?;
+        Ok(value.as_str().unwrap_or_default().to_string())
+    }
+
+    async fn get_cookies(&self) -> crate::Result<Vec<Cookie>> {
+        let value = self.session.command(Method::GET, "/cookie", None).await?;
+        Ok(value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(cdp_cookie)
+            .collect())
+    }
+}
+
+/// Converts a WebDriver cookie to the CDP cookie of the [`LoginOutcome`]
+fn cdp_cookie(cookie: &Value) -> Option<Cookie> {
+    let name = cookie["name"].as_str()?.to_string();
+    let value = cookie["value"].as_str()?.to_string();
+    let secure = cookie["secure"].as_bool().unwrap_or(false);
+    let expiry = cookie["expiry"].as_f64();
+    Some(Cookie {
+        size: (name.len() + value.len()) as i64,
+        name,
+        value,
+        domain: cookie["domain"].as_str().unwrap_or_default().to_string(),
+        path: cookie["path"].as_str().unwrap_or("/").to_string(),
+        expires: expiry.unwrap_or(-1.0),
+        http_only: cookie["httpOnly"].as_bool().unwrap_or(false),
+        secure,
+        session: expiry.is_none(),
+        same_site: cookie["sameSite"]
+            .as_str()
+            .and_then(|same_site| same_site.parse().ok()),
+        priority: CookiePriority::Medium,
+        same_party: false,
+        source_scheme: match secure {
+            true => CookieSourceScheme::Secure,
+            false => CookieSourceScheme::Unset,
+        },
+        source_port: -1,
+        partition_key: None,
+        partition_key_opaque: None,
+    })
+}
+
+/// An element of a [`WebDriverPage`]
+#[derive(Debug, Clone)]
+struct WebDriverElement {
+    session: Arc<Session>,
+    id: String,
+}
+
+impl WebDriverElement {
+    /// The reference of the element in the arguments of a script
+    fn reference(&self) -> Value {
+        json!({ ELEMENT_KEY: self.id })
+    }
+}
+
+impl LoginElement for WebDriverElement {
+    async fn type_str(&self, text: &str) -> crate::Result<()> {
+        self.session
+            .command(
+                Method::POST,
+                &format!("/element/{}/value", self.id),
+                Some(json!({ "text": text })),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn click(&self) -> crate::Result<()> {
+        self.session
+            .command(
+                Method::POST,
+                &format!("/element/{}/click", self.id),
+                Some(json!({})),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn focus(&self) -> crate::Result<()> {
+        self.session
+            .command(
+                Method::POST,
+                "/execute/sync",
+                Some(json!({ "script": "arguments[0].focus()", "args": [self.reference()] })),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn bounding_box(&self) -> crate::Result<BoundingBox> {
+        let rect = self
+            .session
+            .command(Method::GET, &format!("/element/{}/rect", self.id), None)
+            .await?;
+        let coordinate = |name: &str| rect[name].as_f64().unwrap_or_default();
+        Ok(BoundingBox {
+            x: coordinate("x"),
+            y: coordinate("y"),
+            width: coordinate("width"),
+            height: coordinate("height"),
+        })
+    }
+}
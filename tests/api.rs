@@ -0,0 +1,217 @@
+use std::time::{Duration, SystemTime};
+
+use pinterest_login::api::{
+    build_api_headers, cookie_header, cookie_header_for_url, ApiHeadersError,
+};
+use pinterest_login::cookies::PinterestCookie;
+use pinterest_login::PinterestCookies;
+
+fn cookies(pairs: &[(&str, &str)]) -> PinterestCookies {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn the_headers_have_the_cookies_sorted_and_the_csrf_token() {
+    let cookies = cookies(&[
+        ("csrftoken", "token"),
+        ("_pinterest_sess", "TWc9PSZ2bXhW"),
+        ("_auth", "1"),
+    ]);
+
+    let headers = build_api_headers(&cookies, None).unwrap();
+
+    assert_eq!(
+        headers,
+        [
+            (
+                "Cookie".to_string(),
+                "_auth=1; _pinterest_sess=TWc9PSZ2bXhW; csrftoken=token".to_string()
+            ),
+            ("x-csrftoken".to_string(), "token".to_string()),
+            ("x-requested-with".to_string(), "XMLHttpRequest".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn a_missing_csrf_token_is_an_error() {
+    let cookies = cookies(&[("_pinterest_sess", "session")]);
+
+    let err = build_api_headers(&cookies, Some("Mozilla/5.0")).unwrap_err();
+
+    assert_eq!(err, ApiHeadersError::MissingCsrfToken);
+    assert!(err.to_string().contains("csrftoken"), "{err}");
+}
+
+#[test]
+fn the_values_are_sent_as_they_are() {
+    // Quotes, `=` and the encoded characters are valid in a cookie value
+    let cookies = cookies(&[("csrftoken", "a=b"), ("_pinterest_sess", "\"TWc9PSZ%2B\"")]);
+    let user_agent = "Mozilla/5.0 (X11; Linux x86_64) Chrome/120.0";
+
+    let headers = build_api_headers(&cookies, Some(user_agent)).unwrap();
+
+    assert_eq!(
+        headers[0].1,
+        "_pinterest_sess=\"TWc9PSZ%2B\"; csrftoken=a=b"
+    );
+    assert_eq!(headers[1].1, "a=b");
+    assert_eq!(
+        headers[3],
+        ("User-Agent".to_string(), user_agent.to_string())
+    );
+}
+
+#[test]
+fn the_values_that_would_break_a_header_are_refused() {
+    // (cookie name, cookie value)
+    let table = [
+        ("_pinterest_sess", "a\r\nX-Injected: 1"),
+        ("_pinterest_sess", "a; _auth=1"),
+        ("_pinterest sess", "a"),
+        ("_auth=1; a", "a"),
+        ("", "a"),
+    ];
+    for (name, value) in table {
+        let cookies = cookies(&[(name, value), ("csrftoken", "token")]);
+
+        let err = build_api_headers(&cookies, None).unwrap_err();
+
+        assert!(
+            matches!(
+                err,
+                ApiHeadersError::InvalidHeaderValue {
+                    header: "Cookie",
+                    ..
+                }
+            ),
+            "{name:?}={value:?}: {err}"
+        );
+    }
+
+    let cookies = cookies(&[("csrftoken", "token")]);
+    let err = build_api_headers(&cookies, Some("Mozilla/5.0\nX-Injected: 1")).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ApiHeadersError::InvalidHeaderValue {
+                header: "User-Agent",
+                ..
+            }
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn the_cookie_header_keeps_the_spaces_the_commas_and_the_unicode() {
+    let cookies = cookies(&[
+        ("csrftoken", "3f2a%3D%3D"),
+        ("_b", "\"a, b\""),
+        ("name", "ünïcödé ✓"),
+    ]);
+
+    assert_eq!(
+        cookie_header(&cookies).unwrap(),
+        "_b=\"a, b\"; csrftoken=3f2a%3D%3D; name=ünïcödé ✓"
+    );
+    assert_eq!(cookie_header(&PinterestCookies::new()).unwrap(), "");
+
+    let err = cookie_header(&self::cookies(&[("csrftoken", "a;b")])).unwrap_err();
+    assert!(
+        matches!(err, ApiHeadersError::InvalidHeaderValue { .. }),
+        "{err}"
+    );
+}
+
+fn login_cookies() -> Vec<PinterestCookie> {
+    let hour = Duration::from_secs(60 * 60);
+    vec![
+        PinterestCookie {
+            secure: true,
+            expires: Some(SystemTime::now() + hour),
+            ..PinterestCookie::new("_pinterest_sess", "sess", ".pinterest.com")
+        },
+        PinterestCookie::new("csrftoken", "token", ".pinterest.com"),
+        PinterestCookie::new("_routing_id", "id", "www.pinterest.com"),
+        PinterestCookie {
+            path: "/resource/".to_string(),
+            ..PinterestCookie::new("resource", "1", "www.pinterest.com")
+        },
+        PinterestCookie {
+            expires: Some(SystemTime::now() - hour),
+            ..PinterestCookie::new("expired", "1", ".pinterest.com")
+        },
+        PinterestCookie::new("other", "1", ".example.com"),
+    ]
+}
+
+#[test]
+fn only_the_cookies_of_the_url_are_in_its_header() {
+    // (url, header)
+    let table = [
+        (
+            "https://www.pinterest.com/resource/UserResource/get/",
+            "resource=1; _pinterest_sess=sess; _routing_id=id; csrftoken=token",
+        ),
+        (
+            "https://WWW.Pinterest.com/",
+            "_pinterest_sess=sess; _routing_id=id; csrftoken=token",
+        ),
+        (
+            "https://api.pinterest.com/v3/",
+            "_pinterest_sess=sess; csrftoken=token",
+        ),
+        (
+            "https://pinterest.com",
+            "_pinterest_sess=sess; csrftoken=token",
+        ),
+        // The secure cookies aren't sent over HTTP
+        ("http://api.pinterest.com/", "csrftoken=token"),
+        // Neither to a similar path nor a similar domain
+        (
+            "https://www.pinterest.com/resources",
+            "_pinterest_sess=sess; _routing_id=id; csrftoken=token",
+        ),
+        ("https://notpinterest.com/", ""),
+    ];
+    for (url, header) in table {
+        assert_eq!(
+            cookie_header_for_url(&login_cookies(), url).unwrap(),
+            header,
+            "{url}"
+        );
+    }
+}
+
+#[test]
+fn an_invalid_url_is_an_error() {
+    for url in ["www.pinterest.com/", "data:text/plain,hi"] {
+        assert_eq!(
+            cookie_header_for_url(&login_cookies(), url).unwrap_err(),
+            ApiHeadersError::InvalidUrl(url.to_string())
+        );
+    }
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn the_header_map_has_the_same_headers() {
+    use pinterest_login::api::build_api_header_map;
+
+    let cookies = cookies(&[("csrftoken", "token"), ("_auth", "1")]);
+
+    let map = build_api_header_map(&cookies, Some("Mozilla/5.0")).unwrap();
+
+    assert_eq!(map.len(), 4);
+    for (name, value) in build_api_headers(&cookies, Some("Mozilla/5.0")).unwrap() {
+        assert_eq!(map[name.as_str()], value.as_str(), "{name}");
+    }
+    assert_eq!(
+        build_api_header_map(&PinterestCookies::new(), None).unwrap_err(),
+        ApiHeadersError::MissingCsrfToken
+    );
+}
@@ -0,0 +1,76 @@
+use pinterest_login::auth::AuthSession;
+use pinterest_login::session::Session;
+use pinterest_login::PinterestCookies;
+
+fn cookies(pairs: &[(&str, &str)]) -> PinterestCookies {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+// The formats are what the API client relies on, changing them is a breaking change
+#[test]
+fn the_auth_session_of_a_session_has_the_stable_formats() {
+    let session = Session::new(
+        "me@example.com",
+        cookies(&[
+            ("csrftoken", "token"),
+            ("_pinterest_sess", "session"),
+            ("_auth", "1"),
+        ]),
+    )
+    .with_user_agent("Mozilla/5.0");
+
+    let auth = AuthSession::from(session.clone());
+
+    assert_eq!(
+        auth.cookie_header,
+        "_auth=1; _pinterest_sess=session; csrftoken=token"
+    );
+    assert_eq!(auth.csrf_token.as_deref(), Some("token"));
+    assert_eq!(auth.user_agent.as_deref(), Some("Mozilla/5.0"));
+    assert_eq!(AuthSession::from(&session), auth);
+    assert_eq!(
+        auth.headers(),
+        [
+            (
+                "Cookie",
+                "_auth=1; _pinterest_sess=session; csrftoken=token".to_string()
+            ),
+            ("x-csrftoken", "token".to_string()),
+            ("User-Agent", "Mozilla/5.0".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn the_cookies_of_a_login_have_no_user_agent() {
+    let cookies = cookies(&[("_pinterest_sess", "session")]);
+
+    let auth = AuthSession::from(&cookies);
+
+    assert_eq!(auth, AuthSession::from_cookies(&cookies));
+    assert_eq!(auth.cookie_header, "_pinterest_sess=session");
+    assert_eq!(auth.csrf_token, None);
+    assert_eq!(auth.user_agent, None);
+    assert_eq!(
+        auth.headers(),
+        [("Cookie", "_pinterest_sess=session".to_string())]
+    );
+    assert_eq!(
+        auth.with_user_agent("Mozilla/5.0").headers(),
+        [
+            ("Cookie", "_pinterest_sess=session".to_string()),
+            ("User-Agent", "Mozilla/5.0".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn no_cookies_is_an_empty_header() {
+    let auth = AuthSession::from_cookies(&PinterestCookies::new());
+
+    assert_eq!(auth.cookie_header, "");
+    assert_eq!(auth.csrf_token, None);
+}
@@ -0,0 +1,39 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::time::Duration;
+
+use futures::FutureExt;
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login;
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn dropping_the_login_future_kills_the_browser() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let before = common::chromiumoxide_processes();
+
+    let bot = DefaultBrowserLoginBot::new("email", "password");
+    let config_builder = DefaultBrowserConfigBuilder::default();
+    futures::select! {
+        _ = login(&bot, &config_builder).fuse() => panic!("the login shouldn't finish before the timeout"),
+        _ = common::sleep(Duration::from_secs(2)).fuse() => {},
+    }
+
+    // The kill is issued synchronously on drop, give the OS a moment to reap the processes
+    for _ in 0..50 {
+        let leftovers: Vec<_> = common::chromiumoxide_processes()
+            .into_iter()
+            .filter(|pid| !before.contains(pid))
+            .collect();
+        if leftovers.is_empty() {
+            return;
+        }
+        common::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("the browser is still running after the login future has been dropped");
+}
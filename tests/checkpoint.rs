@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use pinterest_login::checkpoint::is_email_confirmation_page;
+use pinterest_login::clock::ManualClock;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, PageLoginBot};
+use pinterest_login::mock::MockPage;
+use pinterest_login::{ErrorKind, PinterestLoginError};
+
+const LOGIN_URL: &str = "https://www.pinterest.com/login/";
+const HOME_URL: &str = "https://www.pinterest.com/";
+const INTERVAL: Duration = Duration::from_secs(5);
+const TIMEOUT: Duration = Duration::from_secs(12);
+
+fn fixture(path: &str) -> String {
+    std::fs::read_to_string(format!("tests/fixtures/{path}.html")).unwrap()
+}
+
+fn bot(clock: &ManualClock) -> DefaultBrowserLoginBot {
+    DefaultBrowserLoginBot::new("me@example.com", "hunter2").with_clock(clock.clone())
+}
+
+#[test]
+fn the_saved_pages_are_classified() {
+    // (fixture, is the "check your email" page)
+    let table = [
+        ("checkpoint/check-your-email", true),
+        ("checkpoint/confirm-its-you", true),
+        // The login form may mention the emails too
+        ("checkpoint/reset-link", false),
+        ("outage/login", false),
+        ("outage/loading", false),
+        ("outage/wrong-password", false),
+        ("outage/maintenance", false),
+    ];
+    for (name, checkpoint) in table {
+        assert_eq!(
+            is_email_confirmation_page(&fixture(name)),
+            checkpoint,
+            "{name}"
+        );
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_checkpoint_fails_right_away_without_polling() {
+    let clock = ManualClock::new();
+    let page = MockPage::new(LOGIN_URL).content(fixture("checkpoint/check-your-email"));
+
+    let err = clock
+        .drive(bot(&clock).check_login(&page))
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::EmailConfirmationRequired),
+        "{err}"
+    );
+    assert_eq!(err.kind(), ErrorKind::Challenge);
+    assert!(clock.sleeps().is_empty());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_login_goes_on_once_the_link_is_clicked() {
+    let clock = ManualClock::new();
+    // Read by the check and the first poll, the link is clicked during the second wait
+    let page = MockPage::new(LOGIN_URL)
+        .content(fixture("checkpoint/confirm-its-you"))
+        .navigates_after(2, HOME_URL, "<p>Welcome</p>");
+    let bot = bot(&clock).with_email_confirmation(INTERVAL, TIMEOUT);
+
+    clock.drive(bot.check_login(&page)).await.unwrap();
+
+    assert_eq!(clock.sleeps(), [INTERVAL; 2]);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_polling_gives_up_after_its_timeout() {
+    let clock = ManualClock::new();
+    let page = MockPage::new(LOGIN_URL).content(fixture("checkpoint/check-your-email"));
+    let bot = bot(&clock).with_email_confirmation(INTERVAL, TIMEOUT);
+
+    let err = clock.drive(bot.check_login(&page)).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::EmailConfirmationRequired),
+        "{err}"
+    );
+    // The last wait is cut to the end of the timeout
+    assert_eq!(clock.sleeps(), [INTERVAL, INTERVAL, Duration::from_secs(2)]);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn an_expired_link_back_to_the_login_form_fails() {
+    let clock = ManualClock::new();
+    let page = MockPage::new(LOGIN_URL)
+        .content(fixture("checkpoint/check-your-email"))
+        .navigates_after(1, LOGIN_URL, fixture("outage/wrong-password"));
+    let bot = bot(&clock).with_email_confirmation(INTERVAL, TIMEOUT);
+
+    let err = clock.drive(bot.check_login(&page)).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+    assert_eq!(clock.sleeps(), [INTERVAL]);
+}
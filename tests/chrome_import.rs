@@ -0,0 +1,131 @@
+#![cfg(feature = "chrome-import")]
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use pinterest_login::chrome_import::{ChromeImportError, ChromeKey};
+use pinterest_login::session::Session;
+
+// A profile with dummy cookies, encrypted like the ones of chrome on linux with the keyring password below
+const PROFILE: &str = "tests/fixtures/chrome/Default";
+const COOKIES: &str = "tests/fixtures/chrome/Default/Network/Cookies";
+const KEYRING_PASSWORD: &str = "keyring-password";
+
+fn key() -> ChromeKey {
+    ChromeKey::linux(Some(KEYRING_PASSWORD))
+}
+
+/// A copy of the fixture database, that the test can change or lock
+fn copy() -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Cookies");
+    fs::copy(COOKIES, &path).unwrap();
+    (dir, path)
+}
+
+#[test]
+fn the_pinterest_cookies_of_the_profile_are_imported() {
+    let session = Session::from_chrome_profile_with("me@example.com", PROFILE, &key()).unwrap();
+
+    assert_eq!(session.account, "me@example.com");
+    let mut cookies: Vec<_> = session
+        .cookies
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    cookies.sort();
+    // The expired `_b` and the cookie of another domain aren't there
+    assert_eq!(
+        cookies,
+        [
+            ("_auth", "1"),
+            ("_pinterest_sess", "dummy-session"),
+            ("csrftoken", "dummy-csrf")
+        ]
+    );
+    // The `csrftoken` expires first, `_auth` is a session cookie
+    assert_eq!(
+        session.expires_at,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(4_070_908_800))
+    );
+}
+
+#[test]
+fn the_database_file_can_be_passed_directly() {
+    let (_dir, path) = copy();
+
+    let from_file = Session::from_chrome_profile_with("me@example.com", &path, &key()).unwrap();
+    let from_profile =
+        Session::from_chrome_profile_with("me@example.com", PROFILE, &key()).unwrap();
+
+    assert_eq!(from_file.cookies, from_profile.cookies);
+}
+
+#[test]
+fn the_keyring_cookies_need_the_right_password() {
+    let err = Session::from_chrome_profile_with("me@example.com", PROFILE, &ChromeKey::linux(None))
+        .unwrap_err();
+    assert!(matches!(err, ChromeImportError::KeyUnavailable(_)), "{err}");
+
+    let wrong = ChromeKey::linux(Some("wrong"));
+    let err = Session::from_chrome_profile_with("me@example.com", PROFILE, &wrong).unwrap_err();
+    assert!(
+        matches!(&err, ChromeImportError::DecryptionFailed(name) if name == "csrftoken"),
+        "{err}"
+    );
+
+    // The macOS key doesn't decrypt the linux cookies either
+    let err = Session::from_chrome_profile_with("me@example.com", PROFILE, &ChromeKey::macos("x"))
+        .unwrap_err();
+    assert!(
+        matches!(&err, ChromeImportError::DecryptionFailed(name) if name == "_pinterest_sess"),
+        "{err}"
+    );
+}
+
+#[test]
+fn a_locked_database_is_a_clear_error() {
+    let (_dir, path) = copy();
+    // Like a running chrome, which keeps an exclusive lock
+    let chrome = rusqlite::Connection::open(&path).unwrap();
+    chrome
+        .execute_batch("PRAGMA locking_mode = EXCLUSIVE; BEGIN EXCLUSIVE;")
+        .unwrap();
+
+    let err = Session::from_chrome_profile_with("me@example.com", &path, &key()).unwrap_err();
+
+    assert!(
+        matches!(&err, ChromeImportError::Locked(locked) if locked == &path),
+        "{err}"
+    );
+    assert!(err.to_string().contains("close it or pass a copy"), "{err}");
+}
+
+#[test]
+fn a_profile_without_a_pinterest_session_isnt_logged_in() {
+    let (_dir, path) = copy();
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute(
+            "DELETE FROM cookies WHERE host_key = '.pinterest.com' AND name = '_pinterest_sess'",
+            [],
+        )
+        .unwrap();
+
+    let err = Session::from_chrome_profile_with("me@example.com", &path, &key()).unwrap_err();
+
+    assert!(matches!(err, ChromeImportError::NotLoggedIn), "{err}");
+}
+
+#[test]
+fn a_directory_without_cookies_isnt_a_profile() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let err = Session::from_chrome_profile_with("me@example.com", dir.path(), &key()).unwrap_err();
+
+    assert!(
+        matches!(&err, ChromeImportError::NotFound(path) if path == Path::new(dir.path())),
+        "{err}"
+    );
+}
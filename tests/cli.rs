@@ -0,0 +1,1766 @@
+//! Runs the `pintrest-login` binary against a local imitation of the pinterest login page
+#![cfg(feature = "__bin")]
+
+mod common;
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use common::{FixtureServer, Response};
+use predicates::prelude::*;
+use predicates::str::contains;
+use serde_json::Value;
+
+// The exit codes of the help
+const OTHER: i32 = 2;
+const BATCH_FAILED: i32 = 3;
+const LOGGED_IN_AGAIN: i32 = 4;
+const AUTHENTICATION: i32 = 10;
+const CHALLENGE: i32 = 11;
+const BROWSER: i32 = 20;
+const USAGE: i32 = 64;
+const CONFIG: i32 = 78;
+
+fn command(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pintrest-login"));
+    command
+        .args(args)
+        .env("PINTEREST_EMAIL", "email@example.com")
+        .env("PINTEREST_PASSWORD", "password")
+        .env_remove("RUST_LOG")
+        .env_remove("CHROME")
+        .env_remove("PINTEREST_TOTP_SECRET")
+        // Don't pick up the config file of the user
+        .env("XDG_CONFIG_HOME", "/nowhere/config");
+    command
+}
+
+fn run(args: &[&str]) -> Output {
+    command(args).output().expect("run the binary")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn successful_login_server() -> FixtureServer {
+    FixtureServer::start(vec![
+        ("/login", Response::html(common::login_page("/session"))),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/; HttpOnly")
+                .header("Set-Cookie", "csrftoken=token; Path=/"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ])
+}
+
+fn stdout_json(output: &Output) -> Value {
+    assert!(output.status.success(), "{}", stderr(output));
+    serde_json::from_slice(&output.stdout).expect("stdout should be nothing but JSON")
+}
+
+#[test]
+fn the_json_format_prints_the_cookies_as_an_object() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+
+    let output = run(&["--format", "json", "--login-url", &server.url("/login")]);
+
+    assert_eq!(
+        stdout_json(&output),
+        serde_json::json!({ "_pinterest_sess": "fixture", "csrftoken": "token" })
+    );
+}
+
+#[test]
+fn the_full_json_format_prints_the_cookie_metadata() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+
+    let output = run(&[
+        "--format",
+        "json",
+        "--full",
+        "--login-url",
+        &server.url("/login"),
+    ]);
+
+    let cookies = stdout_json(&output);
+    let cookies = cookies.as_array().expect("an array of cookies");
+    let names: Vec<_> = cookies
+        .iter()
+        .map(|c| c["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["_pinterest_sess", "csrftoken"]);
+    let session = &cookies[0];
+    assert_eq!(session["value"], "fixture");
+    assert_eq!(session["domain"], "127.0.0.1");
+    assert_eq!(session["path"], "/");
+    assert_eq!(session["expires"], Value::Null);
+    assert_eq!(session["http_only"], true);
+    assert_eq!(cookies[1]["http_only"], false);
+}
+
+#[test]
+fn the_netscape_format_prints_a_cookie_file() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+
+    let output = run(&["--format", "netscape", "--login-url", &server.url("/login")]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let file = String::from_utf8(output.stdout).unwrap();
+    assert!(file.starts_with("# Netscape HTTP Cookie File\n"), "{file}");
+    assert!(file.ends_with("\tcsrftoken\ttoken\n"), "{file}");
+    assert!(
+        file.contains("#HttpOnly_127.0.0.1\tFALSE\t/\tFALSE\t0\t_pinterest_sess\tfixture\n"),
+        "{file}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn the_env_format_can_be_evaluated() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+
+    let output = run(&["--format", "env", "--login-url", &server.url("/login")]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let script = r#"eval "$1"; printf '%s\n' "$PINTEREST_SESSION" "$PINTEREST_CSRFTOKEN" "$PINTEREST_COOKIE_HEADER""#;
+    let evaluated = Command::new("sh")
+        .args([
+            "-c",
+            script,
+            "sh",
+            &String::from_utf8(output.stdout).unwrap(),
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&evaluated.stdout),
+        "fixture\ntoken\n_pinterest_sess=fixture; csrftoken=token\n"
+    );
+}
+
+#[test]
+fn the_output_file_gets_the_cookies_instead_of_stdout() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cookies").join("cookies.json");
+
+    let output = run(&[
+        "--format",
+        "json",
+        "-o",
+        path.to_str().unwrap(),
+        "--mkdir",
+        "--login-url",
+        &server.url("/login"),
+    ]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    let cookies: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(cookies["csrftoken"], "token");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "{mode:o}");
+    }
+}
+
+#[test]
+fn quiet_json_prints_only_the_json() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+
+    let output = run(&[
+        "-q",
+        "--format",
+        "json",
+        "--login-url",
+        &server.url("/login"),
+    ]);
+
+    assert_eq!(
+        stdout_json(&output),
+        serde_json::json!({ "_pinterest_sess": "fixture", "csrftoken": "token" })
+    );
+    assert!(output.stderr.is_empty(), "{}", stderr(&output));
+}
+
+#[test]
+fn quiet_still_prints_the_errors() {
+    let output = run(&["-q", "--format", "json", "--chrome-path", "/nowhere/chrome"]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let message = stderr(&output);
+    assert!(message.contains("/nowhere/chrome"), "{message}");
+}
+
+#[test]
+fn a_missing_output_directory_fails_before_the_login() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing").join("cookies.json");
+
+    let output = run(&["--output", path.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("--mkdir"), "{stderr}");
+    assert!(!path.exists());
+}
+
+#[test]
+fn a_failed_login_exits_with_an_error_and_an_empty_stdout() {
+    // Fails with an authentication error, or without chrome, with a launch error
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+
+    let output = run(&["--format", "json", "--login-url", &server.url("/login")]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty(), "{:?}", output.stdout);
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn full_requires_the_json_format() {
+    let output = run(&["--full"]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(stderr(&output).contains("--format json"));
+}
+
+#[test]
+fn the_single_dash_long_flags_of_the_previous_parser_still_work() {
+    // `-head` isn't `-h` and the help, it gets to the launch like `--head`
+    let output = run(&["-head", "-timeout", "5", "-chrome-path", "/nowhere/chrome"]);
+
+    assert_eq!(output.status.code(), Some(BROWSER), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("`/nowhere/chrome` (from `--chrome-path`) doesn't exist"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("imeout"), "{stderr}");
+}
+
+// A file that exists but can't be launched
+const NOT_EXECUTABLE: &str = "tests/fixtures/netscape/cookies.txt";
+
+#[test]
+fn a_missing_chrome_path_fails_right_away() {
+    let output = run(&["--chrome-path", "/nowhere/chrome"]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("`/nowhere/chrome` (from `--chrome-path`) doesn't exist"),
+        "{stderr}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn a_non_executable_chrome_path_fails_right_away() {
+    let output = run(&["--chrome-path", NOT_EXECUTABLE]);
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("isn't executable"), "{stderr}");
+}
+
+#[test]
+fn the_chrome_env_var_is_used_without_the_flag() {
+    let output = command(&[])
+        .env("CHROME", "/nowhere/from-env")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("`/nowhere/from-env` (from `CHROME`) doesn't exist"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn the_chrome_path_flag_takes_precedence_over_the_env_var() {
+    let output = command(&["--chrome-path", "/nowhere/from-flag"])
+        .env("CHROME", "/nowhere/from-env")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("`/nowhere/from-flag` (from `--chrome-path`)"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("from-env"), "{stderr}");
+}
+
+#[test]
+fn the_help_describes_the_chrome_path_precedence() {
+    let output = run(&["--help"]);
+
+    assert!(output.status.success());
+    let help = String::from_utf8(output.stdout).unwrap();
+    assert!(help.contains("--chrome-path <PATH>"), "{help}");
+    assert!(
+        help.contains("precedence over the CHROME environment"),
+        "{help}"
+    );
+}
+
+/// Writes the cookies to verify in a temporary directory
+fn cookies_file(json: &str) -> (tempfile::TempDir, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cookies.json");
+    std::fs::write(&path, json).unwrap();
+    let path = path.to_str().unwrap().to_string();
+    (dir, path)
+}
+
+#[test]
+fn verify_exits_with_0_for_valid_cookies() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::verify_server();
+    let (_dir, input) = cookies_file(r#"{"_pinterest_sess": "fixture", "csrftoken": "token"}"#);
+
+    let output = run(&[
+        "verify",
+        "--input",
+        &input,
+        "--format",
+        "json",
+        "--login-url",
+        &server.url("/login"),
+    ]);
+
+    let report = stdout_json(&output);
+    assert_eq!(report["valid"], true);
+    assert!(report["checked_at"].as_u64().unwrap() > 0);
+    assert_eq!(report["earliest_expiry"], Value::Null);
+}
+
+#[test]
+fn verify_exits_with_1_for_invalid_cookies() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::verify_server();
+    let (_dir, input) = cookies_file(
+        r#"[{"name": "csrftoken", "value": "token", "domain": "127.0.0.1", "path": "/",
+             "expires": 4102444800, "http_only": false, "secure": false, "same_site": "Lax"}]"#,
+    );
+
+    let output = run(&[
+        "verify",
+        "-i",
+        &input,
+        "--format",
+        "json",
+        "--login-url",
+        &server.url("/login"),
+    ]);
+
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["earliest_expiry"], 4_102_444_800u64);
+}
+
+#[test]
+fn verify_exit_codes_when_it_cant_check() {
+    let (_dir, malformed) = cookies_file("{not json");
+    let (_dir2, valid) = cookies_file(r#"{"_pinterest_sess": "fixture"}"#);
+
+    for (args, code) in [
+        (vec!["verify"], USAGE),
+        (vec!["verify", "--input", "/nowhere/cookies.json"], OTHER),
+        (vec!["verify", "--input", &malformed], OTHER),
+        (
+            vec!["verify", "--input", &valid, "--format", "netscape"],
+            USAGE,
+        ),
+        (
+            vec![
+                "verify",
+                "--input",
+                &valid,
+                "--chrome-path",
+                "/nowhere/chrome",
+            ],
+            BROWSER,
+        ),
+    ] {
+        let output = run(&args);
+
+        assert_eq!(
+            output.status.code(),
+            Some(code),
+            "{args:?}: {}",
+            stderr(&output)
+        );
+        assert!(output.stdout.is_empty(), "{args:?}");
+    }
+}
+
+/// Writes a private config file in a temporary directory, returns the directory and the path of the file
+fn config_file(contents: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pinterest-login").join("config.toml");
+    pinterest_login::store::write_atomic(&path, contents.as_bytes(), true).unwrap();
+    (dir, path)
+}
+
+#[test]
+fn the_config_file_is_read_from_the_xdg_config_home() {
+    let (dir, _) = config_file("chrome_path = \"/nowhere/from-config\"\n");
+
+    let output = command(&[])
+        .env("XDG_CONFIG_HOME", dir.path())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("`/nowhere/from-config` (from `the config file`) doesn't exist"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn the_flags_take_precedence_over_the_config_file() {
+    let (_dir, path) = config_file("chrome_path = \"/nowhere/from-config\"\n");
+    let config = path.to_str().unwrap();
+
+    let output = run(&["--config", config, "--chrome-path", "/nowhere/from-flag"]);
+
+    let message = stderr(&output);
+    assert!(
+        message.contains("`/nowhere/from-flag` (from `--chrome-path`)"),
+        "{message}"
+    );
+    assert!(!message.contains("from-config"), "{message}");
+
+    // And so does the CHROME environment variable
+    let output = command(&["--config", config])
+        .env("CHROME", "/nowhere/from-env")
+        .output()
+        .unwrap();
+
+    let message = stderr(&output);
+    assert!(
+        message.contains("`/nowhere/from-env` (from `CHROME`)"),
+        "{message}"
+    );
+}
+
+#[test]
+fn a_config_error_names_the_key() {
+    let (_dir, path) = config_file("email = \"me@example.com\"\ntimeout = \"soon\"\n");
+
+    let output = run(&["--config", path.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    let message = stderr(&output);
+    assert!(
+        message.contains("`timeout`: invalid type: string \"soon\", expected u64"),
+        "{message}"
+    );
+}
+
+#[test]
+fn a_missing_config_file_is_an_error_when_it_is_given() {
+    let output = run(&["--config", "/nowhere/config.toml"]);
+
+    assert!(!output.status.success());
+    let message = stderr(&output);
+    assert!(message.contains("/nowhere/config.toml"), "{message}");
+}
+
+#[cfg(unix)]
+#[test]
+fn a_config_file_readable_by_the_others_is_rejected() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (_dir, path) = config_file("chrome_path = \"/nowhere/from-config\"\n");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let config = path.to_str().unwrap();
+
+    let output = run(&["--config", config]);
+
+    let message = stderr(&output);
+    assert!(!output.status.success());
+    assert!(message.contains("--allow-insecure-config"), "{message}");
+
+    let output = run(&["--config", config, "--allow-insecure-config"]);
+
+    let message = stderr(&output);
+    assert!(message.contains("from-config"), "{message}");
+}
+
+#[test]
+fn the_exit_codes_tell_the_error_class() {
+    let (_dir, config) = config_file("timeout = \"soon\"\n");
+    let wrong_password = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+    let login_url = wrong_password.url("/login");
+    // Without chrome the login can't even start
+    let wrong_password_code = if common::chrome_available() {
+        AUTHENTICATION
+    } else {
+        BROWSER
+    };
+
+    for (args, code) in [
+        (vec!["--unknown"], USAGE),
+        (vec!["--timeout", "soon"], USAGE),
+        (vec!["--login-url", "not a url"], USAGE),
+        (vec!["--config", config.to_str().unwrap()], CONFIG),
+        (vec!["--chrome-path", "/nowhere/chrome"], BROWSER),
+        (vec!["--chrome-path", NOT_EXECUTABLE], BROWSER),
+        (vec!["--login-url", &login_url], wrong_password_code),
+    ] {
+        let output = run(&args);
+
+        assert_eq!(
+            output.status.code(),
+            Some(code),
+            "{args:?}: {}",
+            stderr(&output)
+        );
+        assert!(output.stdout.is_empty(), "{args:?}");
+    }
+}
+
+#[test]
+fn the_totp_secret_completes_the_two_factor_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::two_factor_server();
+    let login_url = server.url("/login");
+
+    let output = run(&[
+        "--format",
+        "json",
+        "--login-url",
+        &login_url,
+        "--totp-secret",
+        common::TOTP_SECRET,
+    ]);
+    assert_eq!(stdout_json(&output)["_pinterest_sess"], "fixture");
+
+    let output = command(&["--format", "json", "--login-url", &login_url])
+        .env("PINTEREST_TOTP_SECRET", common::TOTP_SECRET)
+        .output()
+        .unwrap();
+    assert_eq!(stdout_json(&output)["_pinterest_sess"], "fixture");
+}
+
+#[test]
+fn the_two_factor_prompt_needs_a_terminal() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::two_factor_server();
+
+    // The stdin of the binary isn't a terminal
+    let output = run(&["--format", "json", "--login-url", &server.url("/login")]);
+
+    assert_eq!(output.status.code(), Some(CHALLENGE), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn an_invalid_totp_secret_is_a_usage_error() {
+    let output = run(&["--totp-secret", "not base32!"]);
+    assert_eq!(output.status.code(), Some(USAGE), "{}", stderr(&output));
+
+    let output = command(&[])
+        .env("PINTEREST_TOTP_SECRET", "not base32!")
+        .output()
+        .unwrap();
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(USAGE), "{message}");
+    assert!(message.contains("PINTEREST_TOTP_SECRET"), "{message}");
+}
+
+/// Runs the command with the input piped to its stdin
+fn run_with_stdin(mut command: Command, input: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run the binary");
+    // The binary may exit before reading it
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn the_password_can_be_piped_to_stdin() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(common::password_page(" s3cret pass", "/session")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/; HttpOnly"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ]);
+    let login_url = server.url("/login");
+    let login = || {
+        let mut command = command(&[
+            "--format",
+            "json",
+            "--login-url",
+            &login_url,
+            "--password-stdin",
+        ]);
+        command.env_remove("PINTEREST_PASSWORD");
+        command
+    };
+
+    // Only the newline is removed
+    let output = run_with_stdin(login(), " s3cret pass\n");
+    assert_eq!(stdout_json(&output)["_pinterest_sess"], "fixture");
+
+    let output = run_with_stdin(login(), " s3cret pass \n");
+    assert_eq!(
+        output.status.code(),
+        Some(AUTHENTICATION),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn the_stdin_password_usage_errors() {
+    let without_password = || {
+        let mut command = command(&["--format", "json", "--password-stdin"]);
+        command.env_remove("PINTEREST_PASSWORD");
+        command
+    };
+
+    for input in ["", "\n"] {
+        let output = run_with_stdin(without_password(), input);
+        let message = stderr(&output);
+        assert_eq!(output.status.code(), Some(USAGE), "{input:?}: {message}");
+        assert!(message.contains("empty password"), "{message}");
+    }
+
+    let output = run_with_stdin(command(&["--password-stdin"]), "hunter2\n");
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(USAGE), "{message}");
+    assert!(message.contains("PINTEREST_PASSWORD"), "{message}");
+
+    let mut without_email = without_password();
+    without_email.env_remove("PINTEREST_EMAIL");
+    let output = run_with_stdin(without_email, "hunter2\n");
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(USAGE), "{message}");
+    assert!(message.contains("PINTEREST_EMAIL"), "{message}");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn the_password_can_be_read_from_a_file() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(common::password_page(" s3cret pass", "/session")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/; HttpOnly"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ]);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("password");
+    // Only the newline is removed, and the second line isn't read
+    std::fs::write(&path, " s3cret pass\nnot the password\n").unwrap();
+
+    let output = command(&[
+        "--format",
+        "json",
+        "--login-url",
+        &server.url("/login"),
+        "--password-file",
+        path.to_str().unwrap(),
+    ])
+    .env_remove("PINTEREST_PASSWORD")
+    .output()
+    .unwrap();
+
+    assert_eq!(stdout_json(&output)["_pinterest_sess"], "fixture");
+}
+
+#[test]
+fn the_password_file_usage_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("password");
+    let password_file = path.to_str().unwrap();
+    let without_password = || {
+        let mut command = command(&["--format", "json", "--password-file", password_file]);
+        command.env_remove("PINTEREST_PASSWORD");
+        command
+    };
+
+    for contents in ["", "\n"] {
+        std::fs::write(&path, contents).unwrap();
+        let output = without_password().output().unwrap();
+        let message = stderr(&output);
+        assert_eq!(output.status.code(), Some(USAGE), "{contents:?}: {message}");
+        assert!(
+            message.contains("`--password-file` got an empty password"),
+            "{message}"
+        );
+    }
+
+    std::fs::write(&path, "hunter2\n").unwrap();
+    let output = run(&["--password-file", password_file]);
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(USAGE), "{message}");
+    assert!(message.contains("PINTEREST_PASSWORD"), "{message}");
+
+    let output = run(&["--password-file", password_file, "--password-stdin"]);
+    assert_eq!(output.status.code(), Some(USAGE), "{}", stderr(&output));
+
+    std::fs::remove_file(&path).unwrap();
+    let output = without_password().output().unwrap();
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(USAGE), "{message}");
+    assert!(
+        message.contains("Can't open the password file"),
+        "{message}"
+    );
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn the_cookie_flag_prints_the_saved_values() {
+    let (_dir, input) = cookies_file(r#"{"_pinterest_sess": "fixture", "csrftoken": "token"}"#);
+
+    let output = run(&[
+        "-q",
+        "--input",
+        &input,
+        "--cookie",
+        "csrftoken",
+        "--cookie",
+        "_pinterest_sess",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "token\nfixture\n");
+
+    let output = run(&["--input", &input, "--cookie", "missing"]);
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(OTHER), "{message}");
+    assert!(message.contains("Cookie not found: `missing`"), "{message}");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn the_cookie_flag_prints_the_value_after_the_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+
+    let output = run(&[
+        "--login-url",
+        &server.url("/login"),
+        "--cookie",
+        "_pinterest_sess",
+    ]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "fixture\n");
+}
+
+/// A `GET` request to the server, returns the status code and the body
+#[cfg(feature = "serve")]
+fn get(addr: &str, path: &str, token: Option<&str>) -> (u16, String) {
+    use std::io::Read;
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    let authorization = token
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: {addr}\r\n{authorization}\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+/// Runs `serve` against the server, returns it once it's logged in with its address
+#[cfg(feature = "serve")]
+fn serve(server: &FixtureServer, args: &[&str]) -> (std::process::Child, String) {
+    use std::io::{BufRead, BufReader};
+    use std::time::{Duration, Instant};
+
+    let login_url = server.url("/login");
+    let mut command = command(&[
+        "serve",
+        "--listen",
+        "127.0.0.1:0",
+        "--login-url",
+        &login_url,
+    ]);
+    let mut child = command.args(args).stderr(Stdio::piped()).spawn().unwrap();
+
+    // The port is printed once it's bound
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+    let addr = line
+        .trim()
+        .strip_prefix("Serving the cookies on http://")
+        .unwrap_or_else(|| panic!("{line}"))
+        .to_string();
+
+    let start = Instant::now();
+    while get(&addr, "/healthz", None).0 != 200 {
+        assert!(
+            start.elapsed() < Duration::from_secs(60),
+            "the login didn't finish"
+        );
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    (child, addr)
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_answers_the_cookies_of_the_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+    let (mut child, addr) = serve(&server, &["--auth-token", "s3cret"]);
+
+    let (status, body) = get(&addr, "/cookies", Some("s3cret"));
+    assert_eq!(status, 200);
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        serde_json::json!({ "_pinterest_sess": "fixture", "csrftoken": "token" })
+    );
+    assert_eq!(get(&addr, "/cookies", None).0, 401);
+    assert_eq!(get(&addr, "/cookies", Some("wrong")).0, 401);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_rejects_the_headers_that_never_end() {
+    use std::io::Read;
+
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+    let (mut child, addr) = serve(&server, &[]);
+
+    // 8 KiB of head without its empty line
+    let mut request = b"GET /cookies HTTP/1.1\r\nX-Padding: ".to_vec();
+    request.resize(8 * 1024, b'a');
+    let mut stream = std::net::TcpStream::connect(&addr).unwrap();
+    stream.write_all(&request).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"),
+        "{response}"
+    );
+    assert!(!response.contains("fixture"), "{response}");
+    // The server is still there
+    assert_eq!(get(&addr, "/cookies", None).0, 200);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_fails_right_away_without_the_browser() {
+    let output = run(&[
+        "serve",
+        "--listen",
+        "127.0.0.1:0",
+        "--chrome-path",
+        "/nowhere/chrome",
+    ]);
+
+    assert_eq!(output.status.code(), Some(BROWSER), "{}", stderr(&output));
+}
+
+#[test]
+fn a_failed_login_prints_the_screenshot_path_even_when_quiet() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+    let dir = tempfile::tempdir().unwrap();
+    let screenshots = dir.path().join("nightly").join("screenshots");
+
+    let output = run(&[
+        "-q",
+        "--login-url",
+        &server.url("/login"),
+        "--screenshot-on-failure",
+        screenshots.to_str().unwrap(),
+    ]);
+
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(AUTHENTICATION), "{message}");
+    let path = message
+        .lines()
+        .find_map(|line| line.strip_prefix("The screenshot of the page: "))
+        .unwrap_or_else(|| panic!("no screenshot path: {message}"));
+    assert!(
+        std::path::Path::new(path).starts_with(&screenshots),
+        "{path}"
+    );
+    assert!(std::fs::read(path).unwrap().starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn an_unwritable_screenshot_dir_fails_before_the_login() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("file");
+    std::fs::write(&file, "").unwrap();
+    let screenshots = file.join("screenshots");
+
+    // A chrome that doesn't exist would fail too, but later
+    let output = run(&["--screenshot-on-failure", screenshots.to_str().unwrap()]);
+
+    let message = stderr(&output);
+    assert_eq!(output.status.code(), Some(OTHER), "{message}");
+    assert!(
+        message.contains("Can't save the screenshots into"),
+        "{message}"
+    );
+}
+
+/// The binary with the environment of [`command`], for the assertions of `assert_cmd`
+fn cli(args: &[&str]) -> assert_cmd::Command {
+    assert_cmd::Command::from_std(command(args))
+}
+
+#[test]
+fn the_help_lists_the_subcommands_and_the_exit_codes() {
+    cli(&["--help"])
+        .assert()
+        .success()
+        .stdout(contains("Usage: pintrest-login [OPTIONS]"))
+        .stdout(contains("verify"))
+        .stdout(contains("refresh"))
+        .stdout(contains("export"))
+        .stdout(contains("Exit codes:"))
+        .stdout(contains("  64  Invalid arguments"));
+}
+
+#[test]
+fn the_subcommands_have_their_own_help() {
+    cli(&["export", "--help"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Usage: pintrest-login export [OPTIONS] --input <PATH>",
+        ))
+        .stdout(contains("--password-stdin").not());
+    cli(&["help", "refresh"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Refresh the saved cookies, and log in again only if they aren't logged in anymore",
+        ));
+}
+
+#[test]
+fn the_version_is_printed() {
+    cli(&["--version"])
+        .assert()
+        .success()
+        .stdout(format!("pintrest-login {}\n", env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn an_unknown_flag_is_rejected_with_a_suggestion() {
+    cli(&["--fromat", "json"])
+        .assert()
+        .code(USAGE)
+        .stdout("")
+        .stderr(contains("unexpected argument '--fromat'"))
+        .stderr(contains("a similar argument exists: '--format'"));
+    cli(&["verify", "--imput", "cookies.json"])
+        .assert()
+        .code(USAGE)
+        .stderr(contains("'--input'"));
+    cli(&["exprot"]).assert().code(USAGE);
+}
+
+#[test]
+fn only_the_long_flags_take_a_single_dash() {
+    // `-config` is `--config`, so it's the missing config file that fails
+    cli(&["-config", "/nowhere/config.toml"])
+        .assert()
+        .code(CONFIG)
+        .stdout("");
+    cli(&["-nowhere", "profile"])
+        .assert()
+        .code(USAGE)
+        .stdout("");
+    cli(&["--q"]).assert().code(USAGE).stdout("");
+    // The value of `--format` is left as it is, clap rejects it as a value that starts with a dash
+    let output = run(&["--format", "-format"]);
+    assert_eq!(output.status.code(), Some(USAGE));
+    assert!(stderr(&output).contains("'-f'"), "{}", stderr(&output));
+}
+
+#[test]
+fn export_prints_the_saved_cookies_in_another_format() {
+    let (_dir, input) = cookies_file(r#"{"_pinterest_sess": "fixture", "csrftoken": "token"}"#);
+
+    cli(&["export", "-i", &input, "--format", "netscape"])
+        .assert()
+        .success()
+        .stdout(contains(
+            ".pinterest.com\tTRUE\t/\tFALSE\t0\t_pinterest_sess\tfixture\n",
+        ));
+    cli(&["export", "-i", &input, "--format", "env"])
+        .assert()
+        .success()
+        .stdout(contains("export PINTEREST_SESSION='fixture'\n"));
+    cli(&["export", "-i", &input, "--cookie", "csrftoken"])
+        .assert()
+        .success()
+        .stdout("token\n");
+    cli(&[
+        "export",
+        "-i",
+        &input,
+        "--format",
+        "curl",
+        "--curl-url",
+        "https://www.pinterest.com/me/",
+    ])
+    .assert()
+    .success()
+    .stdout(contains(
+        "curl 'https://www.pinterest.com/me/' -H 'Cookie: _pinterest_sess=fixture; csrftoken=token' \
+         -H 'x-csrftoken: token' -H 'User-Agent: ",
+    ));
+
+    // The full cookies keep their details
+    let (_dir, input) = cookies_file(
+        r#"[{"name": "csrftoken", "value": "token", "domain": "127.0.0.1", "path": "/",
+             "expires": 4102444800, "http_only": false, "secure": true, "same_site": "Lax"}]"#,
+    );
+    let output = cli(&["export", "-i", &input, "--format", "json", "--full"])
+        .output()
+        .unwrap();
+    let cookies = stdout_json(&output);
+    assert_eq!(cookies[0]["domain"], "127.0.0.1");
+    assert_eq!(cookies[0]["expires"], 4_102_444_800.0);
+    assert_eq!(cookies[0]["secure"], true);
+    assert_eq!(cookies[0]["same_site"], "Lax");
+
+    cli(&["export", "-i", "/nowhere/cookies.json"])
+        .assert()
+        .code(OTHER)
+        .stdout("");
+}
+
+#[test]
+fn refresh_keeps_the_logged_in_cookies() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::verify_server();
+    let (_dir, input) = cookies_file(r#"{"_pinterest_sess": "fixture"}"#);
+
+    let login_url = server.url("/login");
+
+    // Quiet by default, for cron
+    cli(&["refresh", "-i", &input, "--login-url", &login_url])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+    // Written back with the cookies of the browser
+    let cookies: Value = serde_json::from_str(&std::fs::read_to_string(&input).unwrap()).unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "fixture");
+
+    cli(&["refresh", "-v", "-i", &input, "--login-url", &login_url])
+        .assert()
+        .success()
+        .stderr(contains("still logged in"));
+
+    // The cookies are copied to another output
+    let output = std::path::Path::new(&input).with_file_name("new.json");
+    cli(&[
+        "refresh",
+        "-i",
+        &input,
+        "-o",
+        output.to_str().unwrap(),
+        "--login-url",
+        &login_url,
+    ])
+    .assert()
+    .success();
+    let cookies: Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "fixture");
+}
+
+/// Sends the users with the `_pinterest_sess` cookie to `/home`, and logs the others in with a `fresh` session
+fn relogin_server() -> FixtureServer {
+    FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(common::login_page("/session"))
+                .when_cookie("_pinterest_sess", Response::redirect("/home")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fresh; Path=/; HttpOnly"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ])
+}
+
+#[test]
+fn refresh_logs_in_again_when_the_cookies_are_invalid() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = relogin_server();
+    let (_dir, input) = cookies_file(r#"{"csrftoken": "stale"}"#);
+
+    cli(&[
+        "refresh",
+        "-v",
+        "-i",
+        &input,
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .assert()
+    .code(LOGGED_IN_AGAIN)
+    .stdout("")
+    .stderr(contains("logged in again"));
+    let cookies: Value = serde_json::from_str(&std::fs::read_to_string(&input).unwrap()).unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "fresh");
+}
+
+#[test]
+fn concurrent_refreshes_log_in_once() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = relogin_server();
+    let (_dir, input) = cookies_file(r#"{"csrftoken": "stale"}"#);
+    let login_url = server.url("/login");
+
+    let refreshes: Vec<_> = (0..2)
+        .map(|_| {
+            command(&["refresh", "-i", &input, "--login-url", &login_url])
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    let mut codes: Vec<_> = refreshes
+        .into_iter()
+        .map(|refresh| refresh.wait_with_output().unwrap().status.code())
+        .collect();
+    codes.sort();
+
+    // The second one waits for the first, then finds its cookies logged in
+    assert_eq!(codes, [Some(0), Some(LOGGED_IN_AGAIN)]);
+    let cookies: Value = serde_json::from_str(&std::fs::read_to_string(&input).unwrap()).unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "fresh");
+}
+
+/// A cache of `--cache` with a `cached` session for the login page, saved at `saved_at`
+fn cache_file(login_url: &str, saved_at: u64) -> (tempfile::TempDir, String) {
+    cookies_file(
+        &serde_json::json!({
+            "saved_at": saved_at,
+            "email": "email@example.com",
+            "login_url": login_url,
+            "cookies": [{"name": "_pinterest_sess", "value": "cached", "domain": "127.0.0.1", "path": "/"}],
+        })
+        .to_string(),
+    )
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[test]
+fn the_cached_cookies_are_reused_while_they_are_logged_in() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = relogin_server();
+    let login_url = server.url("/login");
+    let (_dir, cache) = cache_file(&login_url, now());
+
+    let args = [
+        "--login-url",
+        &login_url,
+        "--cache",
+        &cache,
+        "--cookie",
+        "_pinterest_sess",
+    ];
+    cli(&args)
+        .assert()
+        .success()
+        .stdout("cached\n")
+        .stderr(contains("Using the cached cookies"));
+
+    // `--force-login` logs in anyway, and caches the new cookies
+    cli(&[&args[..], &["--force-login"]].concat())
+        .assert()
+        .success()
+        .stdout("fresh\n");
+    cli(&args).assert().success().stdout("fresh\n");
+}
+
+#[test]
+fn a_missing_cache_is_created_after_the_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = relogin_server();
+    let dir = tempfile::tempdir().unwrap();
+    let cache = dir.path().join("pinterest-login").join("cookies.json");
+
+    cli(&[
+        "--login-url",
+        &server.url("/login"),
+        "--cache",
+        cache.to_str().unwrap(),
+        "--cookie",
+        "_pinterest_sess",
+    ])
+    .assert()
+    .success()
+    .stdout("fresh\n");
+
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&cache).unwrap()).unwrap();
+    assert_eq!(saved["email"], "email@example.com");
+    assert_eq!(saved["cookies"][0]["value"], "fresh");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&cache).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn an_expired_or_corrupted_cache_logs_in_again() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = relogin_server();
+    let login_url = server.url("/login");
+    let (_dir, expired) = cache_file(&login_url, now() - 2 * 60 * 60);
+    let (_dir2, corrupted) = cookies_file("{not json");
+
+    for (cache, notice) in [
+        (&expired, "older than `--cache-ttl`"),
+        (&corrupted, "corrupted"),
+    ] {
+        cli(&[
+            "--login-url",
+            &login_url,
+            "--cache",
+            cache,
+            "--cache-ttl",
+            "1h",
+            "--cookie",
+            "_pinterest_sess",
+        ])
+        .assert()
+        .success()
+        .stdout("fresh\n")
+        .stderr(contains(notice));
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(cache).unwrap()).unwrap();
+        assert_eq!(saved["cookies"][0]["value"], "fresh");
+    }
+}
+
+#[test]
+fn the_cache_options_need_the_cache() {
+    cli(&["--cache-ttl", "1h"]).assert().code(USAGE).stdout("");
+    cli(&["--force-login"]).assert().code(USAGE).stdout("");
+}
+
+#[test]
+fn batch_logs_in_every_account_and_sums_them_up() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(common::password_page("right", "/session")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=session; Path=/; HttpOnly"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ]);
+    let dir = tempfile::tempdir().unwrap();
+    let accounts = dir.path().join("accounts.csv");
+    std::fs::write(
+        &accounts,
+        "email,password\nme@example.com,right\nOther@example.com,wrong\n",
+    )
+    .unwrap();
+    let out_dir = dir.path().join("sessions");
+
+    cli(&[
+        "batch",
+        "--accounts",
+        accounts.to_str().unwrap(),
+        "--out-dir",
+        out_dir.to_str().unwrap(),
+        "--concurrency",
+        "2",
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .assert()
+    .code(BATCH_FAILED)
+    .stdout(contains("me@example.com     ok      0"))
+    .stdout(contains(format!(
+        "Other@example.com  failed  {AUTHENTICATION}"
+    )));
+
+    let cookies: Value = serde_json::from_str(
+        &std::fs::read_to_string(out_dir.join("me@example.com.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "session");
+    assert!(!out_dir.join("other@example.com.json").exists());
+}
+
+#[test]
+fn batch_rejects_an_invalid_accounts_file() {
+    let (_dir, accounts) = cookies_file("email,password\nme@example.com\n");
+
+    cli(&["batch", "--accounts", &accounts, "--out-dir", "unused"])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(contains("line 2: expected 2 fields, found 1"));
+    assert!(!std::path::Path::new("unused").exists());
+}
+
+#[test]
+fn no_incognito_keeps_the_login_in_the_profile() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = relogin_server();
+    let profile = tempfile::tempdir().unwrap();
+
+    let assert = cli(&[
+        "--no-incognito",
+        "--user-data-dir",
+        profile.path().to_str().unwrap(),
+        "--format",
+        "json",
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .assert()
+    .success()
+    .stderr(contains("--no-incognito").not());
+    let cookies: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "fresh");
+    assert!(profile.path().join("Default").is_dir());
+}
+
+#[test]
+fn no_incognito_without_a_profile_is_a_warning() {
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+
+    // Fails with an authentication error, or without chrome, with a launch error
+    cli(&["--no-incognito", "--login-url", &server.url("/login")])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "`--no-incognito` without `--user-data-dir` keeps the cookies in a throwaway profile",
+        ));
+}
+
+#[test]
+fn the_json_logs_are_one_object_per_line() {
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+    let login_url = server.url("/login");
+    let mut args = vec!["--log-format", "json", "--login-url", &login_url];
+    // Without the `log` feature only the error is printed
+    if cfg!(feature = "log") {
+        args.push("-vv");
+    }
+
+    let output = run(&args);
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(!stderr.is_empty());
+    for line in stderr.lines() {
+        let line: Value = serde_json::from_str(line).unwrap_or_else(|e| panic!("{line}: {e}"));
+        for key in ["timestamp", "level", "target", "message"] {
+            assert!(line[key].is_string(), "{key}: {line}");
+        }
+    }
+    let last: Value = serde_json::from_str(stderr.lines().last().unwrap()).unwrap();
+    assert_eq!(last["level"], "ERROR");
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn the_logs_have_no_colors_in_a_pipe() {
+    let output = run(&["-vv", "--login-url", "http://127.0.0.1:1/login"]);
+
+    let stderr = stderr(&output);
+    assert!(stderr.contains("INFO"), "{stderr}");
+    assert!(!stderr.contains('\x1b'), "{stderr}");
+}
+
+#[test]
+fn the_profile_directory_is_created() {
+    let dir = tempfile::tempdir().unwrap();
+    let profile = dir.path().join("profiles").join("me");
+
+    // The login fails without chrome, or on the wrong password, but the profile is there already
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+    cli(&[
+        "--user-data-dir",
+        profile.to_str().unwrap(),
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .assert()
+    .failure()
+    .stderr(contains("--no-incognito").not());
+    assert!(profile.is_dir());
+}
+
+#[test]
+fn a_profile_in_use_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let profile = dir.path().to_str().unwrap();
+    // The first one holds the profile while it waits for its password on stdin
+    let mut first = command(&["--user-data-dir", profile, "--password-stdin"])
+        .env_remove("PINTEREST_PASSWORD")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    while !dir.path().join("pinterest-login.lock").exists() {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    cli(&["--user-data-dir", profile])
+        .assert()
+        .code(OTHER)
+        .stdout("")
+        .stderr(contains(format!(
+            "The profile `{profile}` is already in use by another pinterest-login"
+        )));
+
+    first.kill().unwrap();
+    first.wait().unwrap();
+}
+
+/// A login page that a "user" logs in on after a second, or never if `logs_in` is false
+fn manual_login_server(logs_in: bool) -> FixtureServer {
+    let script = if logs_in {
+        r#"<script>setTimeout(() => { location.href = "/session"; }, 1000);</script>"#
+    } else {
+        ""
+    };
+    FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(format!("<p>Log in by hand</p>{script}")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=by-hand; Path=/; HttpOnly"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ])
+}
+
+#[test]
+fn manual_writes_the_cookies_of_the_human_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = manual_login_server(true);
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("session.json");
+
+    cli(&[
+        "--manual",
+        // No one is watching
+        "--headless",
+        "--format",
+        "json",
+        "-o",
+        output.to_str().unwrap(),
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .env_remove("PINTEREST_EMAIL")
+    .env_remove("PINTEREST_PASSWORD")
+    .assert()
+    .success()
+    .stdout("")
+    .stderr(contains(
+        "Complete the login in the opened window within 5m",
+    ));
+    let cookies: Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "by-hand");
+}
+
+#[test]
+fn manual_gives_up_after_the_timeout() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = manual_login_server(false);
+
+    cli(&[
+        "--manual",
+        "--headless",
+        "--manual-timeout",
+        "1s",
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .assert()
+    .code(21)
+    .stdout("");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn ctrl_c_closes_the_manual_browser() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = manual_login_server(false);
+    let before = common::chromiumoxide_processes();
+
+    let mut manual = command(&[
+        "--manual",
+        "--headless",
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .spawn()
+    .unwrap();
+    // Until the browser is up
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(20);
+    while common::chromiumoxide_processes().len() <= before.len() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "the browser didn't start"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    std::process::Command::new("kill")
+        .args(["-INT", &manual.id().to_string()])
+        .status()
+        .unwrap();
+
+    assert_eq!(manual.wait().unwrap().code(), Some(130));
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let leftovers: Vec<_> = common::chromiumoxide_processes()
+        .into_iter()
+        .filter(|pid| !before.contains(pid))
+        .collect();
+    assert!(leftovers.is_empty(), "{leftovers:?}");
+}
+
+#[test]
+fn completions_are_printed_to_stdout() {
+    cli(&["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(contains("complete -F _pintrest-login").and(contains("pintrest__login,refresh)")))
+        .stderr("");
+    cli(&["completions", "fish"])
+        .assert()
+        .success()
+        .stdout(contains("complete -c pintrest-login"));
+    cli(&["completions", "tcsh"]).assert().code(USAGE);
+}
+
+#[test]
+fn watch_gives_up_after_max_failures() {
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("cookies.json");
+    // Without chrome the login can't even start
+    let code = if common::chrome_available() {
+        AUTHENTICATION
+    } else {
+        BROWSER
+    };
+
+    cli(&[
+        "watch",
+        "-o",
+        output.to_str().unwrap(),
+        "--max-failures",
+        "1",
+        "--retries",
+        "0",
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .assert()
+    .code(code)
+    .stdout("")
+    .stderr(
+        contains("cycle=1 outcome=failed failures=1 error=").and(contains(
+            "Giving up after `--max-failures` failed cycles in a row",
+        )),
+    );
+    assert!(!output.exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn sigterm_stops_watch_after_the_cycle() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = successful_login_server();
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("cookies.json");
+
+    let watch = command(&[
+        "watch",
+        "-o",
+        output.to_str().unwrap(),
+        "--login-url",
+        &server.url("/login"),
+    ])
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+    while !output.exists() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "no cookies were written"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    std::process::Command::new("kill")
+        .args(["-TERM", &watch.id().to_string()])
+        .status()
+        .unwrap();
+
+    let watched = watch.wait_with_output().unwrap();
+    assert!(watched.status.success(), "{}", stderr(&watched));
+    assert!(stderr(&watched).contains("cycle=1 outcome=logged_in failures=0 next=6h"));
+    let cookies: Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(cookies["_pinterest_sess"], "fixture");
+}
@@ -0,0 +1,185 @@
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chromiumoxide::error::CdpError;
+use chromiumoxide::{Browser, BrowserConfig, Handler};
+use futures::future::BoxFuture;
+use pinterest_login::clock::{Clock, ManualClock};
+use pinterest_login::config_builder::{DefaultBrowserConfigBuilder, DEFAULT_HANDSHAKE_TIMEOUT};
+use pinterest_login::launcher::BrowserLauncher;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, PageLoginBot};
+use pinterest_login::mock::{MockElement, MockPage};
+use pinterest_login::options::LoginOptions;
+use pinterest_login::retry::StepRetry;
+use pinterest_login::{login_with_options, LoginPhase, PinterestLoginError};
+
+/// Fails every launch with a transient error
+#[derive(Clone, Default)]
+struct BusyPortLauncher(Arc<AtomicUsize>);
+
+impl BrowserLauncher for BusyPortLauncher {
+    fn launch(
+        &self,
+        _config: BrowserConfig,
+    ) -> BoxFuture<'_, pinterest_login::Result<(Browser, Handler)>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {
+            let err = io::Error::new(io::ErrorKind::AddrInUse, "Address already in use");
+            Err(CdpError::Io(err).into())
+        })
+    }
+}
+
+/// Never completes the launch, like a browser that never speaks CDP
+struct SilentLauncher;
+
+impl BrowserLauncher for SilentLauncher {
+    fn launch(
+        &self,
+        _config: BrowserConfig,
+    ) -> BoxFuture<'_, pinterest_login::Result<(Browser, Handler)>> {
+        Box::pin(futures::future::pending())
+    }
+}
+
+fn config_builder() -> DefaultBrowserConfigBuilder {
+    // Nothing is launched, the executable just has to be there to build the config
+    DefaultBrowserConfigBuilder::default().with_executable("chrome")
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_launch_retries_wait_the_configured_delay() {
+    const DELAY: Duration = Duration::from_secs(3);
+    let clock = ManualClock::new();
+    let start = clock.now();
+    let launcher = BusyPortLauncher::default();
+    let options = LoginOptions::new()
+        .launcher(launcher.clone())
+        .launch_retry(StepRetry {
+            retries: 2,
+            delay: DELAY,
+        })
+        .clock(clock.clone());
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+
+    let err = clock
+        .drive(login_with_options(&bot, &config_builder(), &options))
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::CdpError(CdpError::Io(_))),
+        "{err}"
+    );
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 3);
+    // Every attempt is bounded by the handshake timeout, the failed ones are followed by the retry delay
+    assert_eq!(
+        clock.sleeps(),
+        [
+            DEFAULT_HANDSHAKE_TIMEOUT,
+            DELAY,
+            DEFAULT_HANDSHAKE_TIMEOUT,
+            DELAY,
+            DEFAULT_HANDSHAKE_TIMEOUT
+        ]
+    );
+    assert_eq!(clock.now() - start, 2 * DELAY);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn every_retry_is_notified_before_its_delay() {
+    const DELAY: Duration = Duration::from_secs(3);
+    let clock = ManualClock::new();
+    let notices = Arc::new(Mutex::new(Vec::new()));
+    let options = LoginOptions::new()
+        .launcher(BusyPortLauncher::default())
+        .launch_retry(StepRetry {
+            retries: 2,
+            delay: DELAY,
+        })
+        .on_retry({
+            let (notices, clock) = (Arc::clone(&notices), clock.clone());
+            move |notice| {
+                assert!(matches!(notice.error, PinterestLoginError::CdpError(_)));
+                notices.lock().unwrap().push((
+                    notice.phase,
+                    notice.attempt,
+                    notice.max_attempts,
+                    notice.delay,
+                    clock.sleeps().len(),
+                ));
+            }
+        })
+        .clock(clock.clone());
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+
+    let _ = clock
+        .drive(login_with_options(&bot, &config_builder(), &options))
+        .await
+        .unwrap_err();
+
+    // Only the handshake timeouts were requested when the retries were notified, not their delays
+    assert_eq!(
+        *notices.lock().unwrap(),
+        [
+            (LoginPhase::Launch, 1, 3, DELAY, 1),
+            (LoginPhase::Launch, 2, 3, DELAY, 3)
+        ]
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_silent_browser_times_out_after_the_handshake_timeout() {
+    const TIMEOUT: Duration = Duration::from_secs(7);
+    let clock = ManualClock::new();
+    let start = clock.now();
+    let options = LoginOptions::new()
+        .launcher(SilentLauncher)
+        .clock(clock.clone());
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+    let config_builder =
+        DefaultBrowserConfigBuilder::new(true, None, Some(TIMEOUT)).with_executable("chrome");
+
+    let err = clock
+        .drive(login_with_options(&bot, &config_builder, &options))
+        .await
+        .unwrap_err();
+
+    match err {
+        PinterestLoginError::HandshakeTimeout { timeout, .. } => assert_eq!(timeout, TIMEOUT),
+        e => panic!("expected a handshake timeout, got: {e}"),
+    }
+    assert_eq!(clock.sleeps(), [TIMEOUT]);
+    assert_eq!(clock.now() - start, TIMEOUT);
+    assert_eq!(clock.pending(), 0);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_bot_polls_for_a_late_email_field() {
+    let clock = ManualClock::new();
+    let page = MockPage::new("https://www.pinterest.com/login/")
+        .element("input#email", MockElement::new("email").appears_after(3))
+        .element("input#password", MockElement::new("password"));
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2").with_clock(clock.clone());
+
+    clock.drive(bot.fill_login_form(&page)).await.unwrap();
+
+    assert_eq!(clock.sleeps(), [Duration::from_millis(20); 3]);
+}
+
+#[test]
+fn the_wall_time_moves_with_the_manual_clock() {
+    let clock = ManualClock::new();
+    let start = clock.system_time();
+    clock.advance(Duration::from_secs(60 * 60));
+    assert_eq!(
+        clock.system_time().duration_since(start).unwrap(),
+        Duration::from_secs(60 * 60)
+    );
+}
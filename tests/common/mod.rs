@@ -0,0 +1,464 @@
+//! Shared helpers for the integration tests
+#![allow(dead_code)]
+
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::detection::{default_executable, DetectionOptions};
+use pinterest_login::session::{Session, SessionStore};
+use pinterest_login::store::CookieStore;
+use pinterest_login::PinterestCookies;
+
+/// Returns `true` if there's a chrome executable that the tests can launch.
+///
+/// The tests that need a real browser are skipped when this returns `false`.
+pub fn chrome_available() -> bool {
+    default_executable(DetectionOptions::default()).is_ok()
+}
+
+/// Prints why the test is skipped, to be used with an early `return`
+pub fn skip(reason: &str) {
+    eprintln!("skipping: {reason}");
+}
+
+pub async fn sleep(duration: Duration) {
+    #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+    async_std::task::sleep(duration).await;
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+}
+
+/// Returns the pids of the running chrome processes that were launched by chromiumoxide
+#[cfg(target_os = "linux")]
+pub fn chromiumoxide_processes() -> Vec<u32> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            std::fs::read(format!("/proc/{pid}/cmdline"))
+                .map(|cmdline| {
+                    let cmdline = String::from_utf8_lossy(&cmdline);
+                    cmdline.contains("--remote-debugging-port") && cmdline.contains("chromiumoxide")
+                })
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// A canned HTTP response of the [`FixtureServer`]
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// The response to send instead when the request has the cookie, see [`Response::when_cookie`]
+    pub when_cookie: Option<(String, Box<Response>)>,
+    /// How long the response is held back, see [`Response::delay`]
+    pub delay: Option<Duration>,
+}
+
+impl Response {
+    pub fn html(body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".into(), "text/html; charset=utf-8".into())],
+            body: body.into(),
+            when_cookie: None,
+            delay: None,
+        }
+    }
+
+    pub fn redirect(location: impl Into<String>) -> Self {
+        Self {
+            status: 302,
+            headers: vec![("Location".into(), location.into())],
+            body: String::new(),
+            when_cookie: None,
+            delay: None,
+        }
+    }
+
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Holds the response back for the duration, like a slow server
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Sends the other response when the request has the cookie, e.g. a redirect for the logged in users
+    pub fn when_cookie(mut self, name: impl Into<String>, response: Response) -> Self {
+        self.when_cookie = Some((name.into(), Box::new(response)));
+        self
+    }
+}
+
+/// A tiny HTTP server that serves canned responses on a local port, used instead of the real pinterest
+pub struct FixtureServer {
+    addr: std::net::SocketAddr,
+}
+
+impl FixtureServer {
+    /// Starts the server in a background thread, unknown paths get a 404
+    pub fn start(routes: Vec<(&str, Response)>) -> Self {
+        use std::io::{BufRead, BufReader, Write};
+        use std::sync::Arc;
+
+        let routes: Arc<std::collections::HashMap<String, Response>> = Arc::new(
+            routes
+                .into_iter()
+                .map(|(path, res)| (path.to_string(), res))
+                .collect(),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind the fixture server");
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let routes = Arc::clone(&routes);
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(&stream);
+                    let mut request_line = String::new();
+                    if reader.read_line(&mut request_line).is_err() {
+                        return;
+                    }
+                    // Only the cookies matter in the headers
+                    let mut cookies = Vec::new();
+                    let mut content_length = 0;
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).map(|n| n > 2).unwrap_or(false) {
+                        if let Some((name, value)) = line.trim_end().split_once(':') {
+                            if name.eq_ignore_ascii_case("cookie") {
+                                cookies.extend(value.split(';').filter_map(|cookie| {
+                                    Some(cookie.split_once('=')?.0.trim().to_string())
+                                }));
+                            } else if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap_or(0);
+                            }
+                        }
+                        line.clear();
+                    }
+                    // The body is ignored, but closing the connection before reading it would reset it
+                    let mut body = vec![0; content_length];
+                    let _ = std::io::Read::read_exact(&mut reader, &mut body);
+                    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                    let path = path.split('?').next().unwrap_or(path);
+                    let mut res = routes
+                        .get(path)
+                        .cloned()
+                        .unwrap_or_else(|| Response::html("not found").status(404));
+                    while let Some((cookie, other)) = res.when_cookie.take() {
+                        if !cookies.contains(&cookie) {
+                            break;
+                        }
+                        res = *other;
+                    }
+
+                    if let Some(delay) = res.delay {
+                        std::thread::sleep(delay);
+                    }
+                    let mut out = format!("HTTP/1.1 {} Fixture\r\n", res.status);
+                    for (name, value) in &res.headers {
+                        out.push_str(&format!("{name}: {value}\r\n"));
+                    }
+                    out.push_str(&format!(
+                        "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        res.body.len(),
+                        res.body
+                    ));
+                    let _ = (&stream).write_all(out.as_bytes());
+                });
+            }
+        });
+
+        Self { addr }
+    }
+
+    /// The `ip:port` of this server
+    pub fn host(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// The full url of the given path on this server
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+}
+
+/// A page that mimics the pinterest login form, submitting it navigates to `action`
+pub fn login_page(action: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+  <body>
+    <form id="login" action="{action}" method="get">
+      <input id="email" name="id" type="email">
+      <input id="password" name="password" type="password">
+      <button type="submit"><div>Log in</div></button>
+    </form>
+  </body>
+</html>"#
+    )
+}
+
+/// A login form that never leaves the page, it shows a tooltip above the button instead (which moves it),
+/// like pinterest does for a wrong password
+pub fn wrong_password_page() -> String {
+    r#"<!DOCTYPE html>
+<html>
+  <body>
+    <form id="login" onsubmit="event.preventDefault(); setTimeout(showTooltip, 300);">
+      <input id="email" name="id" type="email">
+      <input id="password" name="password" type="password">
+      <div id="tooltip"></div>
+      <button type="submit"><div>Log in</div></button>
+    </form>
+    <script>
+      function showTooltip() {
+        document.getElementById("tooltip").innerHTML =
+          "<p style='height: 40px'>The password you entered is incorrect.</p>";
+      }
+    </script>
+  </body>
+</html>"#
+        .to_string()
+}
+
+/// A login form that navigates to `action` only with the given password, and shows the tooltip of
+/// [`wrong_password_page`] otherwise
+pub fn password_page(password: &str, action: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+  <body>
+    <form id="login" onsubmit="event.preventDefault(); check();">
+      <input id="email" name="id" type="email">
+      <input id="password" name="password" type="password">
+      <div id="tooltip"></div>
+      <button type="submit"><div>Log in</div></button>
+    </form>
+    <script>
+      function check() {{
+        if (document.getElementById("password").value === {password:?}) {{
+          location.href = "{action}";
+        }} else {{
+          setTimeout(showTooltip, 300);
+        }}
+      }}
+      function showTooltip() {{
+        document.getElementById("tooltip").innerHTML =
+          "<p style='height: 40px'>The password you entered is incorrect.</p>";
+      }}
+    </script>
+  </body>
+</html>"#
+    )
+}
+
+/// A login form without the password field
+pub fn missing_password_page() -> String {
+    r#"<!DOCTYPE html>
+<html>
+  <body>
+    <form id="login">
+      <input id="email" name="id" type="email">
+      <button type="submit"><div>Log in</div></button>
+    </form>
+  </body>
+</html>"#
+        .to_string()
+}
+
+/// A login form whose inputs echo their value as a JSON string into `<pre id="echo-{input id}">`,
+/// to check what was actually typed into them
+pub fn echo_page() -> String {
+    r#"<!DOCTYPE html>
+<html>
+  <body>
+    <form id="login" onsubmit="event.preventDefault();">
+      <input id="email" name="id" type="email" oninput="echo(this)">
+      <input id="password" name="password" type="password" oninput="echo(this)">
+      <button type="submit"><div>Log in</div></button>
+    </form>
+    <pre id="echo-email"></pre>
+    <pre id="echo-password"></pre>
+    <script>
+      function echo(input) {
+        document.getElementById("echo-" + input.id).textContent = JSON.stringify(input.value);
+      }
+    </script>
+  </body>
+</html>"#
+        .to_string()
+}
+
+/// A login page that sends the users with the `_pinterest_sess` cookie to `/home`, like pinterest does for the logged in users
+pub fn verify_server() -> FixtureServer {
+    FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(login_page("/session"))
+                .when_cookie("_pinterest_sess", Response::redirect("/home")),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ])
+}
+
+/// A page that asks for a two-factor authentication code, the form navigates to `action` if the code is one of
+/// the `accepted` ones, and stays on the page otherwise
+pub fn two_factor_page(accepted: &[String], action: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+  <body>
+    <form id="two-factor" onsubmit="event.preventDefault(); verify();">
+      <input id="code" name="code" autocomplete="one-time-code" inputmode="numeric">
+      <button type="submit">Verify</button>
+    </form>
+    <script>
+      function verify() {{
+        if ({accepted:?}.includes(document.getElementById("code").value)) {{
+          location.href = "{action}";
+        }}
+      }}
+    </script>
+  </body>
+</html>"#
+    )
+}
+
+/// The TOTP secret of [`two_factor_server`]
+pub const TOTP_SECRET: &str = "JBSWY3DPEHPK3PXP";
+
+/// A login that asks for the code of [`TOTP_SECRET`] after the login form, around the current time
+/// (a code may expire in the middle of the login)
+pub fn two_factor_server() -> FixtureServer {
+    use std::time::SystemTime;
+
+    let secret: pinterest_login::totp::TotpSecret = TOTP_SECRET.parse().unwrap();
+    let now = SystemTime::now();
+    let accepted: Vec<String> = [
+        now - Duration::from_secs(30),
+        now,
+        now + Duration::from_secs(30),
+    ]
+    .into_iter()
+    .map(|time| secret.code_at(time))
+    .collect();
+    FixtureServer::start(vec![
+        ("/login", Response::html(login_page("/two_factor"))),
+        (
+            "/two_factor",
+            Response::html(two_factor_page(&accepted, "/session")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/; HttpOnly")
+                .header("Set-Cookie", "csrftoken=token; Path=/"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ])
+}
+
+/// What every store has to do
+pub async fn session_store_conformance(store: &dyn SessionStore) {
+    let accounts = [
+        "me@example.com",
+        "../../etc/passwd",
+        ".hidden",
+        "ünï cödé/50%",
+    ];
+    assert_eq!(store.list().await.unwrap(), Vec::<String>::new());
+    assert_eq!(store.get(accounts[0]).await.unwrap(), None);
+    store.delete(accounts[0]).await.unwrap();
+
+    let cookies: PinterestCookies = [
+        ("_pinterest_sess", "session"),
+        ("csrftoken", "it's \"quoted\""),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect();
+    let created_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let sessions =
+        accounts.map(|account| Session::new(account, cookies.clone()).with_created_at(created_at));
+    for session in &sessions {
+        store.put(session).await.unwrap();
+    }
+    let mut listed = accounts.map(String::from).to_vec();
+    listed.sort();
+    assert_eq!(store.list().await.unwrap(), listed);
+    for session in &sessions {
+        assert_eq!(
+            store.get(&session.account).await.unwrap().as_ref(),
+            Some(session)
+        );
+    }
+
+    let replaced = sessions[0].clone().with_user_agent("Mozilla/5.0");
+    store.put(&replaced).await.unwrap();
+    assert_eq!(store.get(accounts[0]).await.unwrap(), Some(replaced));
+    assert_eq!(store.list().await.unwrap().len(), accounts.len());
+
+    store.delete(accounts[0]).await.unwrap();
+    assert_eq!(store.get(accounts[0]).await.unwrap(), None);
+    assert!(!store
+        .list()
+        .await
+        .unwrap()
+        .contains(&accounts[0].to_string()));
+    store.delete(accounts[0]).await.unwrap();
+}
+
+/// Checks the behavior that every [`CookieStore`] must have, on an empty store
+pub async fn cookie_store_conformance(store: &dyn CookieStore) {
+    let accounts = [
+        "me@example.com",
+        "../../etc/passwd",
+        ".hidden",
+        "ünï cödé/50%",
+    ];
+    assert_eq!(store.load(accounts[0]).await.unwrap(), None);
+    store.clear(accounts[0]).await.unwrap();
+
+    let cookies = accounts.map(|account| -> PinterestCookies {
+        [
+            ("_pinterest_sess", account),
+            ("csrftoken", "it's \"quoted\""),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+    });
+    for (account, cookies) in accounts.iter().zip(&cookies) {
+        store.save(account, cookies).await.unwrap();
+    }
+    for (account, cookies) in accounts.iter().zip(&cookies) {
+        assert_eq!(store.load(account).await.unwrap().as_ref(), Some(cookies));
+    }
+
+    store.save(accounts[0], &cookies[1]).await.unwrap();
+    assert_eq!(
+        store.load(accounts[0]).await.unwrap().as_ref(),
+        Some(&cookies[1])
+    );
+
+    store.clear(accounts[0]).await.unwrap();
+    assert_eq!(store.load(accounts[0]).await.unwrap(), None);
+    assert_eq!(
+        store.load(accounts[1]).await.unwrap().as_ref(),
+        Some(&cookies[1])
+    );
+    store.clear(accounts[0]).await.unwrap();
+}
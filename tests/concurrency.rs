@@ -0,0 +1,73 @@
+mod common;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use common::{FixtureServer, Response};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::login_with_options;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::TEMP_PROFILE_PREFIX;
+
+const LOGINS: usize = 4;
+
+/// The temporary profiles that currently exist
+fn temp_profiles() -> HashSet<PathBuf> {
+    std::fs::read_dir(std::env::temp_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    e.file_name()
+                        .to_string_lossy()
+                        .starts_with(TEMP_PROFILE_PREFIX)
+                })
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn concurrent_logins_dont_interfere() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        ("/login", Response::html(common::login_page("/home"))),
+        (
+            "/home",
+            Response::html("<p>Welcome</p>")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/"),
+        ),
+    ]);
+    let before = temp_profiles();
+
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let bot = DefaultBrowserLoginBot::new("email@example.com", "password");
+    let config_builder = DefaultBrowserConfigBuilder::default();
+
+    let results = futures::future::join_all(
+        (0..LOGINS).map(|_| login_with_options(&bot, &config_builder, &options)),
+    )
+    .await;
+    for result in results {
+        let outcome = result.expect("every concurrent login should succeed");
+        assert_eq!(
+            outcome.cookies.get("_pinterest_sess").map(String::as_str),
+            Some("fixture")
+        );
+    }
+
+    // The profiles are removed in the background once the browsers are gone
+    for _ in 0..50 {
+        if temp_profiles().is_subset(&before) {
+            return;
+        }
+        common::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("the temporary profiles weren't removed after the logins");
+}
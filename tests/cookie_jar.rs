@@ -0,0 +1,115 @@
+#![cfg(feature = "cookie")]
+
+use std::time::{Duration, SystemTime};
+
+use cookie::{time::OffsetDateTime, Cookie, CookieJar};
+use pinterest_login::cookie_jar::{extend_cookie_jar, to_cookie_jar, CookieConversionError};
+use pinterest_login::cookies::{PinterestCookie, SameSite};
+
+fn session_cookie() -> PinterestCookie {
+    PinterestCookie {
+        expires: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1798761600)),
+        secure: true,
+        http_only: true,
+        same_site: Some(SameSite::None),
+        ..PinterestCookie::new("_pinterest_sess", "TWc9PSZs%3D", ".pinterest.com")
+    }
+}
+
+#[test]
+fn all_the_attributes_are_converted() {
+    let cookie = Cookie::from(session_cookie());
+
+    assert_eq!(cookie.name_value(), ("_pinterest_sess", "TWc9PSZs%3D"));
+    assert_eq!(cookie.domain(), Some("pinterest.com"));
+    assert_eq!(cookie.path(), Some("/"));
+    assert_eq!(cookie.secure(), Some(true));
+    assert_eq!(cookie.http_only(), Some(true));
+    assert_eq!(cookie.same_site(), Some(cookie::SameSite::None));
+    assert_eq!(
+        cookie.expires_datetime(),
+        Some(OffsetDateTime::from_unix_timestamp(1798761600).unwrap())
+    );
+    assert_eq!(
+        cookie.to_string(),
+        "_pinterest_sess=TWc9PSZs%3D; HttpOnly; SameSite=None; Secure; Path=/; \
+         Domain=pinterest.com; Expires=Fri, 01 Jan 2027 00:00:00 GMT"
+    );
+}
+
+#[test]
+fn a_session_cookie_has_no_expiry_nor_same_site() {
+    let cookie = Cookie::from(PinterestCookie::new(
+        "_routing_id",
+        "id",
+        "www.pinterest.com",
+    ));
+
+    assert_eq!(cookie.expires(), None);
+    assert_eq!(cookie.same_site(), None);
+    assert_eq!(cookie.secure(), Some(false));
+    assert_eq!(
+        cookie.to_string(),
+        "_routing_id=id; Path=/; Domain=www.pinterest.com"
+    );
+}
+
+#[test]
+fn the_cookies_convert_back() {
+    // The dot is added back, since the domain of the `cookie` crate includes the subdomains
+    let table = [
+        session_cookie(),
+        PinterestCookie {
+            path: "/resource/".to_string(),
+            same_site: Some(SameSite::Lax),
+            ..PinterestCookie::new("csrftoken", "token", ".pinterest.com")
+        },
+    ];
+    for expected in table {
+        let cookie = Cookie::from(&expected);
+
+        assert_eq!(PinterestCookie::try_from(&cookie).unwrap(), expected);
+    }
+}
+
+#[test]
+fn a_set_cookie_header_converts() {
+    let cookie =
+        Cookie::parse("csrftoken=token; Domain=.pinterest.com; Max-Age=3600; SameSite=Strict")
+            .unwrap();
+
+    let converted = PinterestCookie::try_from(&cookie).unwrap();
+
+    assert_eq!(converted.domain, ".pinterest.com");
+    assert_eq!(converted.same_site, Some(SameSite::Strict));
+    let expires = converted.expires.unwrap();
+    let left = expires.duration_since(SystemTime::now()).unwrap();
+    assert!(left > Duration::from_secs(3590) && left <= Duration::from_secs(3600));
+
+    let err = PinterestCookie::try_from(&Cookie::new("csrftoken", "token")).unwrap_err();
+    assert_eq!(
+        err,
+        CookieConversionError::MissingDomain("csrftoken".to_string())
+    );
+}
+
+#[test]
+fn the_jar_has_the_cookies_as_originals() {
+    let mut jar = CookieJar::new();
+    jar.add(Cookie::new("mine", "1"));
+
+    extend_cookie_jar(&mut jar, &[session_cookie()]);
+
+    assert_eq!(jar.iter().count(), 2);
+    assert_eq!(jar.delta().count(), 1);
+    assert_eq!(
+        jar.get("_pinterest_sess").and_then(Cookie::domain),
+        Some("pinterest.com")
+    );
+
+    let jar = to_cookie_jar(&[
+        PinterestCookie::new("csrftoken", "first", ".pinterest.com"),
+        PinterestCookie::new("csrftoken", "last", "www.pinterest.com"),
+    ]);
+    assert_eq!(jar.get("csrftoken").map(Cookie::value), Some("last"));
+}
@@ -0,0 +1,189 @@
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::cdp::browser_protocol::network::Cookie;
+use pinterest_login::cookies::{self, merge, MergePolicy, PinterestCookie, SameSite};
+use pinterest_login::PinterestCookies;
+
+fn fixture_cookies() -> Vec<PinterestCookie> {
+    let json = std::fs::read_to_string("tests/fixtures/netscape/cookies.json").unwrap();
+    let cookies: Vec<Cookie> = serde_json::from_str(&json).unwrap();
+    cookies.iter().map(PinterestCookie::from).collect()
+}
+
+fn at(secs: u64) -> Option<SystemTime> {
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[test]
+fn the_expiries_that_arent_times_are_session_cookies() {
+    let json = std::fs::read_to_string("tests/fixtures/netscape/cookies.json").unwrap();
+    let mut cookie = serde_json::from_str::<Vec<Cookie>>(&json)
+        .unwrap()
+        .remove(1);
+    assert!(PinterestCookie::from(&cookie).expires.is_some());
+
+    for expires in [f64::INFINITY, f64::NAN, 1e300, f64::MAX] {
+        cookie.expires = expires;
+        assert_eq!(PinterestCookie::from(&cookie).expires, None, "{expires}");
+    }
+}
+
+#[test]
+fn the_attributes_of_the_browser_are_kept() {
+    let cookies = fixture_cookies();
+
+    assert_eq!(
+        cookies[1],
+        PinterestCookie {
+            name: "_pinterest_sess".to_string(),
+            value: "TWc9PSZs1XZ3Y2dm==".to_string(),
+            domain: ".pinterest.com".to_string(),
+            path: "/".to_string(),
+            expires: at(1798761600),
+            secure: true,
+            http_only: true,
+            same_site: Some(SameSite::None),
+        }
+    );
+    assert_eq!(cookies[2].same_site, Some(SameSite::Lax));
+    assert!(!cookies[2].http_only);
+}
+
+#[test]
+fn the_session_cookies_have_no_expiry() {
+    let cookies = fixture_cookies();
+    let routing_id = &cookies[3];
+
+    assert_eq!(routing_id.name, "_routing_id");
+    assert_eq!(routing_id.expires, None);
+    assert!(routing_id.is_session());
+    assert_eq!(routing_id.same_site, None);
+    assert_eq!(routing_id.path, "/resource/");
+    // The fraction of a second is kept
+    assert_eq!(
+        cookies[0].expires,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs_f64(1798761600.25))
+    );
+}
+
+#[test]
+fn the_cookies_collapse_into_the_map() {
+    let map: PinterestCookies = fixture_cookies()
+        .into_iter()
+        .map(<(String, String)>::from)
+        .collect();
+
+    assert_eq!(map.len(), 4);
+    assert_eq!(map["csrftoken"], "3f2a9c1d7e");
+    assert_eq!(map["_routing_id"], "\"b6c4a1e0\"");
+}
+
+fn cookies(pairs: &[(&str, &str)]) -> PinterestCookies {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn the_values_are_redacted_when_formatted() {
+    let map = cookies(&[
+        ("csrftoken", "3f2a9c1d7e"),
+        ("_pinterest_sess", "TWc9PSZs%3D"),
+        ("_auth", ""),
+    ]);
+
+    for formatted in [map.to_string(), format!("{map:?}"), format!("{map:#?}")] {
+        for secret in ["3f2a9c1d7e", "TWc9PSZs"] {
+            assert!(!formatted.contains(secret), "{formatted}");
+        }
+    }
+    assert_eq!(
+        map.to_string(),
+        "_auth=<0 bytes>; _pinterest_sess=<11 bytes>; csrftoken=<10 bytes>"
+    );
+    assert_eq!(
+        format!("{map:?}"),
+        "PinterestCookies { _auth: <0 bytes>, _pinterest_sess: <11 bytes>, csrftoken: <10 bytes> }"
+    );
+    assert_eq!(PinterestCookies::new().to_string(), "no cookies");
+    assert_eq!(format!("{:?}", PinterestCookies::new()), "PinterestCookies");
+}
+
+#[test]
+fn the_pinterest_cookies_have_getters() {
+    let mut map = cookies(&[("csrftoken", "token"), ("_auth", "1")]);
+
+    assert_eq!(map.get_csrf(), Some("token"));
+    assert_eq!(map.get_session(), None);
+    assert!(map.contains("_auth"));
+    assert!(!map.contains("_pinterest_sess"));
+    assert_eq!(map.len(), 2);
+
+    // It's still the map underneath
+    map.insert("_pinterest_sess".to_string(), "sess".to_string());
+    assert_eq!(map.get_session(), Some("sess"));
+    let mut names: Vec<_> = (&map).into_iter().map(|(name, _)| name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["_auth", "_pinterest_sess", "csrftoken"]);
+    assert_eq!(map.clone().into_inner().len(), 3);
+}
+
+#[test]
+fn the_diff_has_the_added_changed_and_removed_cookies() {
+    let old = cookies(&[
+        ("_pinterest_sess", "old"),
+        ("csrftoken", "token"),
+        ("ar_debug", "1"),
+    ]);
+    let new = cookies(&[
+        ("_pinterest_sess", "new"),
+        ("csrftoken", "token"),
+        ("_b", "b"),
+    ]);
+
+    let diff = cookies::diff(&old, &new);
+
+    assert_eq!(diff.added, ["_b"]);
+    assert_eq!(diff.changed, ["_pinterest_sess"]);
+    assert_eq!(diff.removed, ["ar_debug"]);
+    // The values are left out of the logs
+    assert_eq!(
+        diff.to_string(),
+        "added _b, changed _pinterest_sess, removed ar_debug"
+    );
+    assert!(cookies::diff(&new, &new).is_empty());
+    assert_eq!(cookies::diff(&new, &new).to_string(), "no changes");
+}
+
+#[test]
+fn a_subset_only_removes_cookies() {
+    let old = cookies(&[
+        ("_pinterest_sess", "sess"),
+        ("csrftoken", "token"),
+        ("_auth", "1"),
+    ]);
+    let new = cookies(&[("_pinterest_sess", "sess")]);
+
+    let diff = cookies::diff(&old, &new);
+
+    assert!(diff.added.is_empty() && diff.changed.is_empty());
+    assert_eq!(diff.removed, ["_auth", "csrftoken"]);
+    assert_eq!(merge(&old, &new, MergePolicy::KeepRemoved), old);
+    assert_eq!(merge(&old, &new, MergePolicy::DropRemoved), new);
+}
+
+#[test]
+fn the_merge_takes_the_new_values() {
+    let stored = cookies(&[("_pinterest_sess", "old"), ("_auth", "1")]);
+    let rotated = cookies(&[("_pinterest_sess", "new"), ("_b", "b")]);
+
+    assert_eq!(
+        merge(&stored, &rotated, MergePolicy::KeepRemoved),
+        cookies(&[("_pinterest_sess", "new"), ("_auth", "1"), ("_b", "b")])
+    );
+    assert_eq!(
+        merge(&stored, &rotated, MergePolicy::DropRemoved),
+        cookies(&[("_pinterest_sess", "new"), ("_b", "b")])
+    );
+}
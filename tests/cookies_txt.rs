@@ -0,0 +1,132 @@
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::cdp::browser_protocol::network::Cookie;
+use pinterest_login::cookies::PinterestCookie;
+use pinterest_login::cookies_txt::{
+    from_netscape_str, to_netscape, to_netscape_string, NetscapeError, NETSCAPE_HEADER,
+};
+
+fn fixture_cookies() -> Vec<Cookie> {
+    let json = std::fs::read_to_string("tests/fixtures/netscape/cookies.json").unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn the_cookie_file_matches_the_golden_file() {
+    let golden = std::fs::read_to_string("tests/fixtures/netscape/cookies.txt").unwrap();
+
+    assert_eq!(to_netscape(&fixture_cookies()), golden);
+}
+
+#[test]
+fn every_cookie_line_has_seven_fields() {
+    let file = to_netscape(&fixture_cookies());
+
+    assert!(file.ends_with('\n'));
+    let lines: Vec<_> = file
+        .lines()
+        .filter(|line| {
+            !line.is_empty() && (!line.starts_with('#') || line.starts_with("#HttpOnly_"))
+        })
+        .collect();
+    assert_eq!(lines.len(), 4);
+    for line in lines {
+        assert_eq!(line.split('\t').count(), 7, "{line:?}");
+    }
+}
+
+#[test]
+fn no_cookies_is_just_the_header() {
+    assert_eq!(to_netscape(&[]), NETSCAPE_HEADER);
+    assert!(NETSCAPE_HEADER.ends_with('\n'));
+}
+
+#[test]
+fn the_golden_file_reads_back_into_the_same_cookies() {
+    let golden = std::fs::read_to_string("tests/fixtures/netscape/cookies.txt").unwrap();
+
+    let cookies = from_netscape_str(&golden).unwrap();
+
+    let mut expected: Vec<_> = fixture_cookies()
+        .iter()
+        .map(|cookie| PinterestCookie {
+            // Neither the fraction of the expiry nor the SameSite attribute are in the file
+            expires: PinterestCookie::from(cookie).expires.map(|expires| {
+                let secs = expires
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+            }),
+            same_site: None,
+            ..cookie.into()
+        })
+        .collect();
+    // The file is sorted by name
+    expected.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(cookies, expected);
+    assert_eq!(to_netscape_string(&cookies), golden);
+}
+
+#[test]
+fn a_chrome_export_is_read() {
+    let export = std::fs::read_to_string("tests/fixtures/netscape/chrome-export.txt").unwrap();
+
+    let cookies = from_netscape_str(&export).unwrap();
+
+    let names: Vec<_> = cookies.iter().map(|cookie| cookie.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "_auth",
+            "_pinterest_sess",
+            "csrftoken",
+            "_b",
+            "_routing_id",
+            "sessionFunnelEventLogged",
+            "ar_debug"
+        ]
+    );
+    let http_only: Vec<_> = cookies.iter().filter(|cookie| cookie.http_only).collect();
+    assert_eq!(http_only.len(), 2);
+    assert_eq!(http_only[0].value, "TWc9PSZs1XZ3Y2dmRE%3D");
+    assert_eq!(http_only[1].domain, "www.pinterest.com");
+    assert!(cookies[4].is_session());
+    assert!(!cookies[4].secure);
+    assert_eq!(
+        cookies[2].expires,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1767225600))
+    );
+}
+
+#[test]
+fn the_layout_is_read_leniently() {
+    let contents = "\
+        # a comment\r\n\
+        \r\n\
+        pinterest.com  TRUE\t/ \t true\t-1\tcsrftoken\ta value with spaces \r\n\
+        www.pinterest.com\tFALSE\t/\tFALSE\t0\tempty\n";
+
+    let cookies = from_netscape_str(contents).unwrap();
+
+    assert_eq!(cookies.len(), 2);
+    assert_eq!(cookies[0].domain, ".pinterest.com");
+    assert!(cookies[0].secure);
+    assert!(cookies[0].is_session());
+    assert_eq!(cookies[0].value, "a value with spaces");
+    assert_eq!(cookies[1].value, "");
+}
+
+#[test]
+fn an_invalid_line_is_named_in_the_error() {
+    // (contents, the invalid line)
+    let table = [
+        ("# header\n.pinterest.com\tTRUE\t/\tTRUE\n", 2),
+        (".pinterest.com\tYES\t/\tTRUE\t0\tname\tvalue", 1),
+        ("\n\n.pinterest.com\tTRUE\t/\tTRUE\tsoon\tname\tvalue", 3),
+    ];
+    for (contents, line) in table {
+        let err: NetscapeError = from_netscape_str(contents).unwrap_err();
+        assert_eq!(err.line, line, "{contents:?}: {err}");
+    }
+}
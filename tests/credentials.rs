@@ -0,0 +1,173 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Either};
+use pinterest_login::credentials::{
+    CommandCredentialsProvider, Credentials, CredentialsError, CredentialsProvider,
+};
+
+/// Runs the provider to its end
+fn credentials(provider: CommandCredentialsProvider) -> Result<Credentials, CredentialsError> {
+    futures::executor::block_on(provider.credentials())
+}
+
+/// Writes an executable shell script into the directory
+fn stub(dir: &Path, script: &str) -> PathBuf {
+    let path = dir.join("stub");
+    std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700)).unwrap();
+    path
+}
+
+#[test]
+fn the_output_of_pass_has_the_credentials() {
+    let dir = tempfile::tempdir().unwrap();
+    let stub = stub(
+        dir.path(),
+        r#"[ "$1 $2" = "show pinterest/me" ] || exit 1
+printf 'hunter2\nemail: me@example.com\nurl: pinterest.com\n'"#,
+    );
+
+    let credentials =
+        credentials(CommandCredentialsProvider::new(stub).args(["show", "pinterest/me"])).unwrap();
+
+    assert_eq!(
+        credentials,
+        Credentials::new("hunter2").with_email("me@example.com")
+    );
+}
+
+#[test]
+fn the_arguments_are_not_interpreted_by_a_shell() {
+    let dir = tempfile::tempdir().unwrap();
+    // Prints its first argument as the password
+    let stub = stub(dir.path(), r#"printf '%s\n' "$1""#);
+
+    let credentials =
+        credentials(CommandCredentialsProvider::new(stub).arg("$HOME; echo injected `id`"))
+            .unwrap();
+
+    assert_eq!(credentials.password, "$HOME; echo injected `id`");
+    assert_eq!(credentials.email, None);
+}
+
+#[test]
+fn a_failed_command_has_its_stderr_in_the_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let stub = stub(
+        dir.path(),
+        "echo 'Error: pinterest/me is not in the password store.' >&2; exit 1",
+    );
+
+    let err = credentials(CommandCredentialsProvider::new(stub)).unwrap_err();
+
+    match &err {
+        CredentialsError::Failed { status, stderr, .. } => {
+            assert_eq!(status.code(), Some(1));
+            assert_eq!(stderr, "Error: pinterest/me is not in the password store.");
+        }
+        other => panic!("{other:?}"),
+    }
+    assert!(
+        err.to_string().contains("not in the password store"),
+        "{err}"
+    );
+}
+
+#[test]
+fn a_hanging_command_is_killed_after_the_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let stub = stub(dir.path(), "exec sleep 30");
+    let start = Instant::now();
+
+    let err =
+        credentials(CommandCredentialsProvider::new(stub).timeout(Duration::from_millis(200)))
+            .unwrap_err();
+
+    assert!(
+        matches!(err, CredentialsError::TimedOut { timeout, .. } if timeout == Duration::from_millis(200)),
+        "{err:?}"
+    );
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn a_missing_command_is_a_spawn_error() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let err = credentials(CommandCredentialsProvider::new(dir.path().join("missing"))).unwrap_err();
+
+    assert!(matches!(err, CredentialsError::Spawn { .. }), "{err:?}");
+}
+
+#[test]
+fn an_empty_output_is_invalid() {
+    let dir = tempfile::tempdir().unwrap();
+    let stub = stub(dir.path(), "true");
+
+    let err = credentials(CommandCredentialsProvider::new(stub)).unwrap_err();
+
+    assert!(matches!(err, CredentialsError::InvalidOutput(_)), "{err:?}");
+}
+
+#[test]
+fn a_custom_parser_reads_the_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let stub = stub(
+        dir.path(),
+        r#"echo '{"login":"me@example.com","secret":"hunter2"}'"#,
+    );
+
+    let credentials = credentials(CommandCredentialsProvider::new(stub).parser(|output| {
+        let json: serde_json::Value = serde_json::from_str(output)
+            .map_err(|e| CredentialsError::InvalidOutput(e.to_string()))?;
+        Ok(
+            Credentials::new(json["secret"].as_str().unwrap_or_default())
+                .with_email(json["login"].as_str().unwrap_or_default()),
+        )
+    }))
+    .unwrap();
+
+    assert_eq!(credentials.password, "hunter2");
+    assert_eq!(credentials.email.as_deref(), Some("me@example.com"));
+}
+
+#[test]
+fn the_password_is_not_debug_printed() {
+    let credentials = Credentials::new("hunter2").with_email("me@example.com");
+
+    let debug = format!("{credentials:?}");
+
+    assert!(!debug.contains("hunter2"), "{debug}");
+    assert!(debug.contains("me@example.com"), "{debug}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_command_doesnt_block_the_executor() {
+    let dir = tempfile::tempdir().unwrap();
+    let stub = stub(dir.path(), "sleep 1; echo hunter2");
+    let provider = CommandCredentialsProvider::new(stub);
+    let ticks = AtomicUsize::new(0);
+    let ticker = async {
+        loop {
+            common::sleep(Duration::from_millis(10)).await;
+            ticks.fetch_add(1, Ordering::SeqCst);
+        }
+    };
+
+    // The ticker runs on the same task, it only ticks while the command is waited for if it doesn't block
+    let credentials = match future::select(provider.credentials(), Box::pin(ticker)).await {
+        Either::Left((credentials, _)) => credentials.unwrap(),
+        Either::Right(_) => unreachable!(),
+    };
+
+    assert_eq!(credentials.password, "hunter2");
+    assert!(ticks.load(Ordering::SeqCst) >= 10, "{ticks:?}");
+}
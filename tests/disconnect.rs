@@ -0,0 +1,98 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chromiumoxide::Page;
+use common::{FixtureServer, Response};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{BrowserLoginBot, DefaultBrowserLoginBot};
+use pinterest_login::login_with_options;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::{LoginPhase, PinterestLoginError};
+
+/// Kills the browser a second after the default bot starts waiting for the email field
+struct KillingBot {
+    inner: DefaultBrowserLoginBot,
+    before: Vec<u32>,
+    killed_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl BrowserLoginBot for KillingBot {
+    async fn fill_login_form(&self, page: &Page) -> pinterest_login::Result<()> {
+        let before = self.before.clone();
+        let killed_at = Arc::clone(&self.killed_at);
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(1));
+            let pids: Vec<_> = common::chromiumoxide_processes()
+                .into_iter()
+                .filter(|pid| !before.contains(pid))
+                .map(|pid| pid.to_string())
+                .collect();
+            *killed_at.lock().unwrap() = Some(Instant::now());
+            std::process::Command::new("kill")
+                .arg("-KILL")
+                .args(pids)
+                .status()
+                .expect("kill the browser");
+        });
+        self.inner.fill_login_form(page).await
+    }
+
+    async fn submit_login_form(&self, page: &Page) -> pinterest_login::Result<()> {
+        self.inner.submit_login_form(page).await
+    }
+
+    async fn check_login(&self, page: &Page) -> pinterest_login::Result<()> {
+        self.inner.check_login(page).await
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn killing_the_browser_fails_the_login_fast() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    // No login form, so the bot waits for the email field forever
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html("<html><body><p>Loading...</p></body></html>"),
+    )]);
+
+    let bot = KillingBot {
+        inner: DefaultBrowserLoginBot::new("email@example.com", "password"),
+        before: common::chromiumoxide_processes(),
+        killed_at: Arc::default(),
+    };
+    // A long request timeout, to make sure that it's not what ends the login
+    let config_builder =
+        DefaultBrowserConfigBuilder::new(true, Some(Duration::from_secs(60)), None);
+    let options = LoginOptions::new().login_url(server.url("/login"));
+
+    let err = login_with_options(&bot, &config_builder, &options)
+        .await
+        .expect_err("the login can't succeed without a browser");
+    let failed_at = Instant::now();
+
+    assert!(
+        matches!(
+            err,
+            PinterestLoginError::BrowserDisconnected {
+                during: LoginPhase::FillForm
+            }
+        ),
+        "unexpected error: {err}"
+    );
+    let killed_at = bot
+        .killed_at
+        .lock()
+        .unwrap()
+        .expect("the browser was killed");
+    assert!(
+        failed_at.saturating_duration_since(killed_at) < Duration::from_secs(2),
+        "the disconnection took too long to be detected"
+    );
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::dry_run::{dry_run, DRY_RUN_COOKIES};
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::options::LoginOptions;
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_dry_run_returns_the_fixture_cookies() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let bot = DefaultBrowserLoginBot::new("email@example.com", "password");
+    let config_builder = DefaultBrowserConfigBuilder::default();
+
+    let outcome = dry_run(&bot, &config_builder, &LoginOptions::default())
+        .await
+        .expect("the dry run should succeed");
+
+    for (name, value) in DRY_RUN_COOKIES {
+        assert_eq!(
+            outcome.cookies.get(*name).map(String::as_str),
+            Some(*value),
+            "{name}"
+        );
+    }
+}
@@ -0,0 +1,301 @@
+#![cfg(feature = "encryption")]
+
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+use futures::executor::block_on;
+use pinterest_login::encryption::{
+    load_encrypted, save_encrypted, EncryptedFileSessionStore, SessionKey,
+};
+use pinterest_login::session::{Session, SessionError, SessionStore};
+
+fn session(account: &str) -> Session {
+    let cookies = [("_pinterest_sess", "session"), ("csrftoken", "token")]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    Session::new(account, cookies)
+        .with_created_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        .with_user_agent("Mozilla/5.0")
+}
+
+#[test]
+fn the_sessions_round_trip_with_both_kinds_of_keys() {
+    for key in [
+        SessionKey::raw([7; 32]),
+        SessionKey::passphrase("correct horse"),
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedFileSessionStore::new(dir.path(), key.clone());
+        let sessions = [session("me@example.com"), session("other@example.com")];
+
+        for session in &sessions {
+            block_on(store.put(session)).unwrap();
+        }
+
+        assert_eq!(
+            block_on(store.list()).unwrap(),
+            ["me@example.com", "other@example.com"]
+        );
+        // Another store with the same key reads them, without the cached derived key
+        let reopened = EncryptedFileSessionStore::new(dir.path(), key);
+        for session in &sessions {
+            assert_eq!(
+                block_on(reopened.get(&session.account)).unwrap().as_ref(),
+                Some(session)
+            );
+        }
+        assert_eq!(block_on(reopened.get("missing@example.com")).unwrap(), None);
+        block_on(reopened.delete("me@example.com")).unwrap();
+        assert_eq!(block_on(reopened.list()).unwrap(), ["other@example.com"]);
+    }
+}
+
+#[test]
+fn the_files_are_encrypted() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = EncryptedFileSessionStore::new(dir.path(), SessionKey::passphrase("passphrase"));
+
+    block_on(store.put(&session("me@example.com"))).unwrap();
+
+    let path = store.path("me@example.com");
+    assert_eq!(path, dir.path().join("me@example.com.enc"));
+    let contents = fs::read(path).unwrap();
+    assert_eq!(&contents[..6], b"PLSE\x01\x01");
+    let text = String::from_utf8_lossy(&contents);
+    assert!(!text.contains("_pinterest_sess") && !text.contains("me@example.com"));
+}
+
+#[test]
+fn every_file_has_its_own_salt() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = EncryptedFileSessionStore::new(dir.path(), SessionKey::passphrase("passphrase"));
+
+    block_on(store.put(&session("me@example.com"))).unwrap();
+    block_on(store.put(&session("other@example.com"))).unwrap();
+    let first = fs::read(store.path("me@example.com")).unwrap();
+    block_on(store.put(&session("me@example.com"))).unwrap();
+
+    let salts = [
+        &first,
+        &fs::read(store.path("other@example.com")).unwrap(),
+        &fs::read(store.path("me@example.com")).unwrap(),
+    ]
+    .map(|contents| contents[6..22].to_vec());
+    assert_ne!(salts[0], salts[1]);
+    assert_ne!(salts[0], salts[2]);
+    assert_eq!(
+        block_on(store.get("me@example.com")).unwrap(),
+        Some(session("me@example.com"))
+    );
+}
+
+#[test]
+fn the_files_of_the_documented_format_are_read() {
+    use argon2::{Algorithm, Argon2, Params, Version};
+    use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+    // Written by hand with the parameters of the module docs
+    let mut header = b"PLSE\x01\x01".to_vec();
+    header.extend_from_slice(&[3; 16]);
+    header.extend_from_slice(&[5; 12]);
+    let mut key = [0; 32];
+    Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(19 * 1024, 2, 1, Some(32)).unwrap(),
+    )
+    .hash_password_into(b"correct horse", &[3; 16], &mut key)
+    .unwrap();
+    let json = session("me@example.com").to_json().to_string();
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(
+            Nonce::from_slice(&[5; 12]),
+            Payload {
+                msg: json.as_bytes(),
+                aad: &header,
+            },
+        )
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.enc");
+    fs::write(&path, [header, ciphertext].concat()).unwrap();
+
+    assert_eq!(
+        load_encrypted(&path, "correct horse").unwrap(),
+        session("me@example.com")
+    );
+}
+
+#[test]
+fn the_wrong_keys_fail_to_decrypt() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = EncryptedFileSessionStore::new(dir.path(), SessionKey::passphrase("right"));
+    block_on(store.put(&session("me@example.com"))).unwrap();
+
+    for key in [
+        SessionKey::passphrase("wrong"),
+        SessionKey::passphrase(""),
+        SessionKey::raw([0; 32]),
+    ] {
+        let store = EncryptedFileSessionStore::new(dir.path(), key);
+        assert!(matches!(
+            block_on(store.get("me@example.com")),
+            Err(SessionError::DecryptionFailed)
+        ));
+    }
+}
+
+#[test]
+fn the_tampered_files_fail_to_decrypt() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = EncryptedFileSessionStore::new(dir.path(), SessionKey::raw([7; 32]));
+    block_on(store.put(&session("me@example.com"))).unwrap();
+    let path = store.path("me@example.com");
+    let original = fs::read(&path).unwrap();
+
+    // Every byte after the version and the key kind: the salt, the nonce, the ciphertext and the tag
+    for i in 6..original.len() {
+        let mut tampered = original.clone();
+        tampered[i] ^= 1;
+        fs::write(&path, tampered).unwrap();
+        assert!(
+            matches!(
+                block_on(store.get("me@example.com")),
+                Err(SessionError::DecryptionFailed)
+            ),
+            "byte {i}"
+        );
+    }
+
+    let mut tampered = original.clone();
+    tampered[4] = 2;
+    fs::write(&path, tampered).unwrap();
+    assert!(matches!(
+        block_on(store.get("me@example.com")),
+        Err(SessionError::UnsupportedVersion { found: Some(2), .. })
+    ));
+
+    for tampered in [&original[..20], b"{\"version\": 1}".as_slice()] {
+        fs::write(&path, tampered).unwrap();
+        assert!(matches!(
+            block_on(store.get("me@example.com")),
+            Err(SessionError::InvalidField {
+                field: "header",
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn the_sessions_can_be_re_encrypted_with_a_new_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = SessionKey::passphrase("old");
+    let new = SessionKey::raw([9; 32]);
+    let store = EncryptedFileSessionStore::new(dir.path(), old.clone());
+    let sessions = [session("a@example.com"), session("b@example.com")];
+    for session in &sessions {
+        block_on(store.put(session)).unwrap();
+    }
+
+    let wrong = block_on(EncryptedFileSessionStore::re_encrypt(
+        dir.path(),
+        SessionKey::passphrase("wrong"),
+        new.clone(),
+    ));
+    assert!(matches!(wrong, Err(SessionError::DecryptionFailed)));
+    // Nothing was changed
+    assert!(block_on(store.get("a@example.com")).unwrap().is_some());
+
+    let rotated = block_on(EncryptedFileSessionStore::re_encrypt(
+        dir.path(),
+        old.clone(),
+        new.clone(),
+    ))
+    .unwrap();
+    for session in &sessions {
+        assert_eq!(
+            block_on(rotated.get(&session.account)).unwrap().as_ref(),
+            Some(session)
+        );
+        assert!(matches!(
+            block_on(store.get(&session.account)),
+            Err(SessionError::DecryptionFailed)
+        ));
+    }
+
+    // Running it again keeps the files that already use the new key
+    block_on(EncryptedFileSessionStore::re_encrypt(dir.path(), old, new)).unwrap();
+    assert_eq!(
+        block_on(rotated.get("a@example.com")).unwrap().as_ref(),
+        Some(&sessions[0])
+    );
+}
+
+#[test]
+fn the_keys_are_not_printed() {
+    assert_eq!(
+        format!("{:?}", SessionKey::passphrase("secret")),
+        "SessionKey::Passphrase(..)"
+    );
+    assert_eq!(
+        format!("{:?}", SessionKey::raw([1; 32])),
+        "SessionKey::Raw(..)"
+    );
+}
+
+#[test]
+fn a_single_session_round_trips_with_a_passphrase() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("session.enc");
+    let session = session("me@example.com");
+
+    save_encrypted(&path, "correct horse", &session).unwrap();
+
+    assert_eq!(load_encrypted(&path, "correct horse").unwrap(), session);
+    let contents = fs::read(&path).unwrap();
+    assert_eq!(&contents[..6], b"PLSE\x01\x01");
+    // The same format as the files of the store
+    let store = EncryptedFileSessionStore::new(dir.path(), SessionKey::passphrase("correct horse"));
+    fs::write(store.path("me@example.com"), &contents).unwrap();
+    assert_eq!(
+        block_on(store.get("me@example.com")).unwrap(),
+        Some(session)
+    );
+}
+
+#[test]
+fn a_single_session_is_refused_with_a_typed_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.enc");
+    save_encrypted(&path, "right", &session("me@example.com")).unwrap();
+    let original = fs::read(&path).unwrap();
+
+    assert!(matches!(
+        load_encrypted(&path, "wrong"),
+        Err(SessionError::DecryptionFailed)
+    ));
+
+    let mut tampered = original.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    fs::write(&path, tampered).unwrap();
+    assert!(matches!(
+        load_encrypted(&path, "right"),
+        Err(SessionError::DecryptionFailed)
+    ));
+
+    let mut newer = original.clone();
+    newer[4] = 2;
+    fs::write(&path, newer).unwrap();
+    assert!(matches!(
+        load_encrypted(&path, "right"),
+        Err(SessionError::UnsupportedVersion { found: Some(2), .. })
+    ));
+
+    assert!(matches!(
+        load_encrypted(dir.path().join("missing.enc"), "right"),
+        Err(SessionError::Io(_))
+    ));
+}
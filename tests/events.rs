@@ -0,0 +1,47 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chromiumoxide::cdp::CdpEvent;
+use common::{FixtureServer, Response};
+use futures::FutureExt;
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::login_with_options;
+use pinterest_login::options::LoginOptions;
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_event_sink_receives_navigation_and_network_events() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html("<html><body><p>Nothing to see here</p></body></html>"),
+    )]);
+
+    let (navigated, requested) = (Arc::new(Mutex::new(false)), Arc::new(Mutex::new(false)));
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .event_sink({
+            let (navigated, requested) = (Arc::clone(&navigated), Arc::clone(&requested));
+            move |event| match event {
+                CdpEvent::PageFrameNavigated(_) => *navigated.lock().unwrap() = true,
+                CdpEvent::NetworkRequestWillBeSent(_) => *requested.lock().unwrap() = true,
+                _ => {}
+            }
+        });
+
+    let bot = DefaultBrowserLoginBot::new("email", "password");
+    let config_builder = DefaultBrowserConfigBuilder::default();
+    // There's no login form in the page, so the bot keeps waiting for it
+    futures::select! {
+        _ = login_with_options(&bot, &config_builder, &options).fuse() => {},
+        _ = common::sleep(Duration::from_secs(5)).fuse() => {},
+    }
+
+    assert!(*navigated.lock().unwrap(), "no navigation event");
+    assert!(*requested.lock().unwrap(), "no network event");
+}
@@ -0,0 +1,148 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use pinterest_login::clock::ManualClock;
+use pinterest_login::fallback::with_headful_fallback;
+use pinterest_login::login_bot::PageLoginBot;
+use pinterest_login::mock::MockPage;
+use pinterest_login::scripted::{ScriptedLoginBot, Step};
+use pinterest_login::{LoginPhase, PinterestLoginError};
+
+const DEADLINE: Duration = Duration::from_secs(5 * 60);
+
+/// The bot phases of a login, on a fake page
+async fn attempt(bot: &ScriptedLoginBot) -> pinterest_login::Result<()> {
+    let page = MockPage::new("https://www.pinterest.com/login/");
+    bot.fill_login_form(&page).await?;
+    bot.submit_login_form(&page).await?;
+    bot.check_login(&page).await
+}
+
+fn challenge() -> Step {
+    Step::fail(|| PinterestLoginError::ChallengeRequired)
+}
+
+/// Runs the headless bot, then the headful one if it falls back, returns the result with the time given to the
+/// headful one
+async fn login(
+    clock: &ManualClock,
+    headless: &ScriptedLoginBot,
+    headful: &ScriptedLoginBot,
+) -> (pinterest_login::Result<()>, Option<Duration>) {
+    let time_left = Arc::new(Mutex::new(None));
+    let res = clock
+        .drive(with_headful_fallback(
+            DEADLINE,
+            clock,
+            attempt(headless),
+            |left| {
+                *time_left.lock().unwrap() = Some(left);
+                attempt(headful)
+            },
+        ))
+        .await;
+    let time_left = *time_left.lock().unwrap();
+    (res, time_left)
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_challenged_login_falls_back_to_headful() {
+    let clock = ManualClock::new();
+    let headless = ScriptedLoginBot::new()
+        .with_clock(clock.clone())
+        .script(LoginPhase::FillForm, [Step::Delay(Duration::from_secs(60))])
+        .script(LoginPhase::CheckLogin, [challenge()]);
+    let headful = ScriptedLoginBot::new();
+
+    let (res, time_left) = login(&clock, &headless, &headful).await;
+
+    res.unwrap();
+    assert_eq!(time_left, Some(DEADLINE - Duration::from_secs(60)));
+    assert_eq!(
+        headful.calls(),
+        [
+            LoginPhase::FillForm,
+            LoginPhase::SubmitForm,
+            LoginPhase::CheckLogin
+        ]
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_wrong_password_never_falls_back() {
+    let clock = ManualClock::new();
+    let headless = ScriptedLoginBot::new().script(
+        LoginPhase::CheckLogin,
+        [Step::fail(|| PinterestLoginError::AuthenticationError)],
+    );
+    let headful = ScriptedLoginBot::new();
+
+    let (res, time_left) = login(&clock, &headless, &headful).await;
+
+    assert!(
+        matches!(res, Err(PinterestLoginError::AuthenticationError)),
+        "{res:?}"
+    );
+    assert_eq!(time_left, None);
+    assert!(headful.calls().is_empty());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_successful_headless_login_doesnt_fall_back() {
+    let clock = ManualClock::new();
+    let headful = ScriptedLoginBot::new();
+
+    let (res, time_left) = login(&clock, &ScriptedLoginBot::new(), &headful).await;
+
+    res.unwrap();
+    assert_eq!(time_left, None);
+    assert!(headful.calls().is_empty());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_headful_login_gets_what_is_left_of_the_deadline() {
+    let clock = ManualClock::new();
+    let headless = ScriptedLoginBot::new()
+        .with_clock(clock.clone())
+        .script(
+            LoginPhase::FillForm,
+            [Step::Delay(Duration::from_secs(4 * 60))],
+        )
+        .script(LoginPhase::CheckLogin, [challenge()]);
+    // The human takes longer than what's left
+    let headful = ScriptedLoginBot::new().with_clock(clock.clone()).script(
+        LoginPhase::CheckLogin,
+        [Step::Delay(Duration::from_secs(2 * 60))],
+    );
+
+    let (res, time_left) = login(&clock, &headless, &headful).await;
+
+    assert!(
+        matches!(res, Err(PinterestLoginError::DeadlineExceeded(DEADLINE))),
+        "{res:?}"
+    );
+    assert_eq!(time_left, Some(Duration::from_secs(60)));
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_headless_login_past_the_deadline_doesnt_fall_back() {
+    let clock = ManualClock::new();
+    let headless = ScriptedLoginBot::new()
+        .with_clock(clock.clone())
+        .script(LoginPhase::FillForm, [Step::Delay(DEADLINE * 2)]);
+    let headful = ScriptedLoginBot::new();
+
+    let (res, time_left) = login(&clock, &headless, &headful).await;
+
+    assert!(
+        matches!(res, Err(PinterestLoginError::DeadlineExceeded(_))),
+        "{res:?}"
+    );
+    assert_eq!(time_left, None);
+    assert!(headful.calls().is_empty());
+}
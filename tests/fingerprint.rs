@@ -0,0 +1,172 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use pinterest_login::clock::ManualClock;
+use pinterest_login::fingerprint::{Fingerprint, Fingerprints, TypingTimings};
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, PageLoginBot};
+use pinterest_login::mock::{MockAction, MockElement, MockPage};
+use pinterest_login::retry::{retry_login, LoginRetry};
+use pinterest_login::PinterestLoginError;
+
+/// Runs the login with the policy, every attempt challenged, returns the fingerprints of the attempts
+async fn challenged_attempts(policy: LoginRetry) -> Vec<Option<Fingerprint>> {
+    let clock = ManualClock::new();
+    let attempts = Mutex::new(Vec::new());
+    let res: pinterest_login::Result<()> = clock
+        .drive(retry_login(&policy, &clock, |attempt| {
+            attempts.lock().unwrap().push(attempt.fingerprint);
+            async { Err(PinterestLoginError::ChallengeRequired) }
+        }))
+        .await;
+    assert!(matches!(res, Err(PinterestLoginError::ChallengeRequired)));
+    attempts.into_inner().unwrap()
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn every_retry_gets_a_different_fingerprint() {
+    let policy = LoginRetry::new(4, Duration::from_secs(5))
+        .vary_fingerprint(true)
+        .seed(7);
+
+    let attempts = challenged_attempts(policy).await;
+
+    assert_eq!(attempts.len(), 5);
+    assert_eq!(attempts[0], None);
+    let fingerprints: Vec<_> = attempts[1..].iter().flatten().collect();
+    assert_eq!(fingerprints.len(), 4);
+    for pair in fingerprints.windows(2) {
+        assert_ne!(pair[0], pair[1]);
+    }
+    for fingerprint in fingerprints {
+        assert!(
+            fingerprint.user_agent.contains(" Chrome/"),
+            "{}",
+            fingerprint.user_agent
+        );
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_same_seed_gives_the_same_fingerprints() {
+    let policy = LoginRetry::new(3, Duration::from_secs(5))
+        .vary_fingerprint(true)
+        .seed(42);
+
+    assert_eq!(
+        challenged_attempts(policy).await,
+        challenged_attempts(policy).await
+    );
+    assert_ne!(
+        challenged_attempts(policy).await,
+        challenged_attempts(policy.seed(43)).await
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_fingerprint_is_kept_without_the_variation() {
+    let attempts = challenged_attempts(LoginRetry::new(2, Duration::from_secs(5))).await;
+
+    assert_eq!(attempts, [None, None, None]);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn only_the_challenges_are_retried() {
+    let clock = ManualClock::new();
+    let policy = LoginRetry::new(3, Duration::from_secs(5)).vary_fingerprint(true);
+    let attempts = Mutex::new(0);
+
+    let res: pinterest_login::Result<()> = clock
+        .drive(retry_login(&policy, &clock, |_| {
+            *attempts.lock().unwrap() += 1;
+            async { Err(PinterestLoginError::AuthenticationError) }
+        }))
+        .await;
+
+    assert!(matches!(res, Err(PinterestLoginError::AuthenticationError)));
+    assert_eq!(*attempts.lock().unwrap(), 1);
+    assert!(clock.sleeps().is_empty());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_retry_waits_for_the_delay() {
+    let clock = ManualClock::new();
+    let policy = LoginRetry::new(2, Duration::from_secs(30));
+    let attempts = Mutex::new(0);
+
+    let res = clock
+        .drive(retry_login(&policy, &clock, |attempt| {
+            *attempts.lock().unwrap() += 1;
+            async move {
+                match attempt.attempt {
+                    1 => Err(PinterestLoginError::ChallengeRequired),
+                    _ => Ok(attempt.max_attempts),
+                }
+            }
+        }))
+        .await;
+
+    assert_eq!(res.unwrap(), 3);
+    assert_eq!(*attempts.lock().unwrap(), 2);
+    assert_eq!(clock.sleeps(), [Duration::from_secs(30)]);
+}
+
+#[test]
+fn the_typing_delays_stay_around_the_key_delay() {
+    let typing = TypingTimings::new(Duration::from_millis(100), Duration::from_millis(20), 1);
+
+    let delays: Vec<_> = (0..50).map(|i| typing.delay(i)).collect();
+
+    for delay in &delays {
+        assert!(
+            (Duration::from_millis(80)..=Duration::from_millis(120)).contains(delay),
+            "{delay:?}"
+        );
+    }
+    assert!(delays.windows(2).any(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn the_fingerprints_are_logged_without_the_whole_user_agent() {
+    let fingerprint = Fingerprints::new(3).next_fingerprint();
+
+    let logged = fingerprint.to_string();
+
+    assert!(logged.starts_with("Chrome/1"), "{logged}");
+    assert!(!logged.contains("Mozilla"), "{logged}");
+    assert!(
+        logged.contains(&fingerprint.viewport.to_string()),
+        "{logged}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_bot_types_at_the_pace_of_the_timings() {
+    let clock = ManualClock::new();
+    let typing = TypingTimings::new(Duration::from_millis(100), Duration::from_millis(30), 9);
+    let bot = DefaultBrowserLoginBot::new("me@x.io", "pw")
+        .with_clock(clock.clone())
+        .with_typing(typing);
+    let page = MockPage::new("https://www.pinterest.com/login/")
+        .element("input#email", MockElement::new("email"))
+        .element("input#password", MockElement::new("password"));
+
+    clock.drive(bot.fill_login_form(&page)).await.unwrap();
+
+    let typed: String = page
+        .actions()
+        .into_iter()
+        .filter_map(|action| match action {
+            MockAction::Typed { text, .. } => Some(text),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(typed, "me@x.iopw");
+    let expected: Vec<_> = (0..typed.len()).map(|i| typing.delay(i)).collect();
+    assert_eq!(clock.sleeps(), expected);
+}
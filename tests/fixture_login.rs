@@ -0,0 +1,345 @@
+//! The end to end regression suite of the default bot, against a local imitation of the pinterest login page
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use common::{FixtureServer, Response};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host, PageLoginBot, TwoFactor};
+use pinterest_login::options::LoginOptions;
+use pinterest_login::page::LoginPage;
+use pinterest_login::{login_with_options, PinterestLoginError};
+
+async fn login_against(
+    server: &FixtureServer,
+) -> pinterest_login::Result<pinterest_login::PinterestCookies> {
+    login_with_bot(server, bot(server)).await
+}
+
+fn bot(server: &FixtureServer) -> DefaultBrowserLoginBot {
+    DefaultBrowserLoginBot::new("email@example.com", "password")
+        .with_login_host(Host::new(server.host()))
+}
+
+async fn login_with_bot(
+    server: &FixtureServer,
+    bot: DefaultBrowserLoginBot,
+) -> pinterest_login::Result<pinterest_login::PinterestCookies> {
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let config_builder = DefaultBrowserConfigBuilder::default();
+    login_with_options(&bot, &config_builder, &options)
+        .await
+        .map(|outcome| outcome.cookies)
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_successful_login_returns_the_cookies() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        ("/login", Response::html(common::login_page("/session"))),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/")
+                .header("Set-Cookie", "csrftoken=token; Path=/"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ]);
+
+    let cookies = login_against(&server)
+        .await
+        .expect("the login against the fixture server should succeed");
+
+    assert_eq!(
+        cookies.get("_pinterest_sess").map(String::as_str),
+        Some("fixture")
+    );
+    assert_eq!(cookies.get("csrftoken").map(String::as_str), Some("token"));
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_wrong_password_is_an_authentication_error() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+
+    let err = login_against(&server)
+        .await
+        .expect_err("the login should fail");
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_failed_login_saves_a_screenshot() {
+    use std::sync::{Arc, Mutex};
+
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::wrong_password_page()),
+    )]);
+    let dir = tempfile::tempdir().unwrap();
+    let screenshots = Arc::new(Mutex::new(Vec::new()));
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .screenshot_on_failure(dir.path().join("screenshots"))
+        .on_failure_screenshot({
+            let screenshots = Arc::clone(&screenshots);
+            move |screenshot| {
+                screenshots.lock().unwrap().push(
+                    screenshot
+                        .map(|path| path.to_path_buf())
+                        .map_err(|e| e.to_string()),
+                )
+            }
+        });
+
+    let err = login_with_options(
+        &bot(&server),
+        &DefaultBrowserConfigBuilder::default(),
+        &options,
+    )
+    .await
+    .expect_err("the login should fail");
+
+    // The login error isn't replaced
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+    let screenshots = screenshots.lock().unwrap();
+    let [Ok(path)] = screenshots.as_slice() else {
+        panic!("expected one saved screenshot: {screenshots:?}");
+    };
+    assert!(path.starts_with(dir.path().join("screenshots")), "{path:?}");
+    let png = std::fs::read(path).unwrap();
+    assert!(png.starts_with(b"\x89PNG"), "not a PNG");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_missing_field_fails_the_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![(
+        "/login",
+        Response::html(common::missing_password_page()),
+    )]);
+
+    let err = login_against(&server)
+        .await
+        .expect_err("the login should fail");
+
+    assert!(matches!(err, PinterestLoginError::CdpError(_)), "{err}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_totp_code_completes_the_two_factor_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::two_factor_server();
+    let bot = bot(&server).with_two_factor(TwoFactor::Totp(common::TOTP_SECRET.parse().unwrap()));
+
+    let cookies = login_with_bot(&server, bot)
+        .await
+        .expect("the two-factor login against the fixture server should succeed");
+
+    assert_eq!(
+        cookies.get("_pinterest_sess").map(String::as_str),
+        Some("fixture")
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_two_factor_login_without_a_code_is_a_challenge_error() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::two_factor_server();
+
+    let err = login_against(&server)
+        .await
+        .expect_err("the login should fail");
+
+    assert!(
+        matches!(err, PinterestLoginError::ChallengeRequired),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_wrong_two_factor_code_is_an_authentication_error() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::two_factor_server();
+    let bot = bot(&server).with_two_factor(TwoFactor::callback(|| Some("000000".to_string())));
+
+    let err = login_with_bot(&server, bot)
+        .await
+        .expect_err("the login should fail");
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+/// The default bot, that remembers if it looked for the login form
+struct WatchedBot {
+    bot: DefaultBrowserLoginBot,
+    looked_for_the_form: AtomicBool,
+}
+
+impl PageLoginBot for WatchedBot {
+    async fn fill_login_form<P: LoginPage>(&self, page: &P) -> pinterest_login::Result<()> {
+        self.looked_for_the_form.store(true, Ordering::SeqCst);
+        self.bot.fill_login_form(page).await
+    }
+
+    async fn submit_login_form<P: LoginPage>(&self, page: &P) -> pinterest_login::Result<()> {
+        self.looked_for_the_form.store(true, Ordering::SeqCst);
+        self.bot.submit_login_form(page).await
+    }
+
+    async fn check_login<P: LoginPage>(&self, page: &P) -> pinterest_login::Result<()> {
+        self.bot.check_login(page).await
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn an_already_logged_in_browser_skips_the_login_form() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    // Like a persistent profile, pinterest sends the logged in browser to the home feed
+    let server = FixtureServer::start(vec![
+        ("/login", Response::redirect("/homefeed/")),
+        (
+            "/homefeed/",
+            Response::html("<p>Welcome back</p>")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/")
+                .header("Set-Cookie", "_auth=1; Path=/"),
+        ),
+    ]);
+    let bot = WatchedBot {
+        bot: bot(&server),
+        looked_for_the_form: AtomicBool::new(false),
+    };
+    let landed = Arc::new(Mutex::new(None));
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .on_already_authenticated({
+            let landed = Arc::clone(&landed);
+            move |url| *landed.lock().unwrap() = Some(url.to_string())
+        });
+
+    let outcome = login_with_options(&bot, &DefaultBrowserConfigBuilder::default(), &options)
+        .await
+        .expect("the logged in browser should skip the form");
+
+    assert!(!bot.looked_for_the_form.load(Ordering::SeqCst));
+    assert!(outcome.report.already_authenticated);
+    assert_eq!(
+        landed.lock().unwrap().as_deref(),
+        Some(server.url("/homefeed/").as_str())
+    );
+    assert_eq!(outcome.cookies["_pinterest_sess"], "fixture");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_cookies_of_the_cookie_urls_are_returned() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        ("/login", Response::html(common::login_page("/session"))),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/")
+                // Not sent to the page that the browser ends up on
+                .header("Set-Cookie", "_routing_id=api; Path=/v3/"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ]);
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .cookie_urls([server.url("/v3/")]);
+
+    let outcome = login_with_options(
+        &bot(&server),
+        &DefaultBrowserConfigBuilder::default(),
+        &options,
+    )
+    .await
+    .expect("the login against the fixture server should succeed");
+
+    assert_eq!(outcome.cookies["_pinterest_sess"], "fixture");
+    assert_eq!(outcome.cookies["_routing_id"], "api");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_storages_are_captured_when_asked_for() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        ("/login", Response::html(common::login_page("/session"))),
+        (
+            "/session",
+            Response::redirect("/home").header("Set-Cookie", "_pinterest_sess=fixture; Path=/"),
+        ),
+        (
+            "/home",
+            Response::html(
+                r#"<script>
+                  localStorage.setItem("experiments", '{"new_feed": true}');
+                  sessionStorage.setItem("_auth", "1");
+                </script>"#,
+            ),
+        ),
+    ]);
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .capture_storage(true);
+
+    let outcome = login_with_options(
+        &bot(&server),
+        &DefaultBrowserConfigBuilder::default(),
+        &options,
+    )
+    .await
+    .expect("the login against the fixture server should succeed");
+
+    let storage = outcome.storage.expect("the storages should be captured");
+    assert_eq!(storage.origin, server.url("").trim_end_matches('/'));
+    // The values are kept as they were stored, even the JSON ones
+    assert_eq!(storage.local["experiments"], r#"{"new_feed": true}"#);
+    assert_eq!(storage.session["_auth"], "1");
+}
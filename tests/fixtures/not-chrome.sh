@@ -0,0 +1,3 @@
+#!/bin/sh
+# A fake browser that never speaks CDP
+exec sleep 600
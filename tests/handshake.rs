@@ -0,0 +1,35 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::{login, PinterestLoginError};
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn executable_that_never_speaks_cdp_times_out_quickly() {
+    let executable = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/not-chrome.sh");
+    let bot = DefaultBrowserLoginBot::new("email", "password");
+    let config_builder = DefaultBrowserConfigBuilder::new(true, None, Some(Duration::from_secs(1)))
+        .with_executable(&executable);
+
+    let start = Instant::now();
+    let err = login(&bot, &config_builder).await.unwrap_err();
+
+    assert!(start.elapsed() < Duration::from_secs(10));
+    match err {
+        PinterestLoginError::HandshakeTimeout {
+            executable: ref path,
+            timeout,
+        } => {
+            assert_eq!(timeout, Duration::from_secs(1));
+            assert!(path.ends_with("not-chrome.sh"), "{path}");
+        }
+        e => panic!("expected a handshake timeout, got: {e}"),
+    }
+    assert!(err.to_string().contains("not-chrome.sh"));
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "http")]
+
+use std::time::Duration;
+
+use chromiumoxide::error::CdpError;
+use http::{header, StatusCode};
+use pinterest_login::http::{code, status, ErrorResponse, STATUS_CODES};
+use pinterest_login::{ErrorKind, LoginPhase, PinterestLoginError};
+
+// The services rely on the table, changing a row is a breaking change
+#[test]
+fn the_status_codes_are_stable() {
+    assert_eq!(
+        STATUS_CODES,
+        [
+            (
+                ErrorKind::Authentication,
+                StatusCode::UNAUTHORIZED,
+                "authentication"
+            ),
+            (
+                ErrorKind::Challenge,
+                StatusCode::PRECONDITION_REQUIRED,
+                "challenge"
+            ),
+            (
+                ErrorKind::RateLimited,
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited"
+            ),
+            (
+                ErrorKind::Browser,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "browser"
+            ),
+            (ErrorKind::Timeout, StatusCode::GATEWAY_TIMEOUT, "timeout"),
+            (ErrorKind::Network, StatusCode::BAD_GATEWAY, "network"),
+            (
+                ErrorKind::ServiceUnavailable,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "service_unavailable"
+            ),
+            (ErrorKind::Store, StatusCode::INTERNAL_SERVER_ERROR, "store"),
+        ]
+    );
+    for &(kind, expected_status, expected_code) in STATUS_CODES {
+        assert_eq!(status(kind), expected_status, "{kind:?}");
+        assert_eq!(code(kind), expected_code, "{kind:?}");
+    }
+}
+
+#[test]
+fn the_errors_have_the_status_of_their_kind() {
+    let table = [
+        (PinterestLoginError::AuthenticationError, 401),
+        (PinterestLoginError::ChallengeRequired, 428),
+        (PinterestLoginError::RateLimited, 429),
+        (
+            PinterestLoginError::BrowserDisconnected {
+                during: LoginPhase::FillForm,
+            },
+            500,
+        ),
+        (PinterestLoginError::CdpError(CdpError::Timeout), 504),
+        (
+            PinterestLoginError::CdpError(CdpError::ChromeMessage(
+                "net::ERR_NAME_NOT_RESOLVED".into(),
+            )),
+            502,
+        ),
+        (
+            PinterestLoginError::ServiceUnavailable { retry_hint: None },
+            503,
+        ),
+    ];
+    for (error, expected) in table {
+        let response = ErrorResponse::from(&error);
+
+        assert_eq!(response.status.as_u16(), expected, "{error}");
+        assert_eq!(response.code, code(error.kind()));
+        assert_eq!(response.message, error.to_string());
+    }
+}
+
+#[test]
+fn the_response_has_a_json_body_and_the_retry_after() {
+    let response = ErrorResponse::from(PinterestLoginError::RateLimited)
+        .with_retry_after(Duration::from_millis(90_500))
+        .to_response();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    assert_eq!(response.headers()[header::RETRY_AFTER], "91");
+    let body: serde_json::Value = serde_json::from_str(response.body()).unwrap();
+    assert_eq!(body["code"], "rate_limited");
+    assert_eq!(
+        body["message"],
+        PinterestLoginError::RateLimited.to_string()
+    );
+
+    // Pinterest doesn't tell when to try again
+    let response = ErrorResponse::from(PinterestLoginError::RateLimited).to_response();
+    assert!(!response.headers().contains_key(header::RETRY_AFTER));
+}
+
+#[test]
+fn the_retry_hint_of_an_outage_is_the_retry_after() {
+    let error = PinterestLoginError::ServiceUnavailable {
+        retry_hint: Some(Duration::from_secs(120)),
+    };
+
+    let response = ErrorResponse::from(error).to_response();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+}
+
+#[cfg(feature = "axum")]
+#[test]
+fn the_errors_are_axum_responses() {
+    use axum::response::IntoResponse;
+
+    let response = PinterestLoginError::ChallengeRequired.into_response();
+
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+}
@@ -0,0 +1,165 @@
+#![cfg(feature = "http-login")]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{FixtureServer, Response};
+use pinterest_login::http_login::HttpLogin;
+use pinterest_login::{ErrorKind, PinterestLoginError};
+
+const SESSION_PATH: &str = "/resource/UserSessionResource/create/";
+
+/// The login page, that sets the `csrftoken` cookie
+fn login_page() -> Response {
+    Response::html("<html><body><div id=\"root\"></div></body></html>")
+        .header("Set-Cookie", "csrftoken=token; Path=/; Secure")
+        .header("Set-Cookie", "_pinterest_referrer=direct; Path=/")
+}
+
+/// An answer of the login endpoint
+fn resource(status: u16, json: &str) -> Response {
+    Response::html(json)
+        .status(status)
+        .header("Content-Type", "application/json")
+}
+
+/// An error of the login endpoint
+fn resource_error(status: u16, code: &str, message: &str) -> Response {
+    resource(
+        status,
+        &format!(
+            r#"{{"resource_response":{{"status":"failure","error":{{"status":"failure","code":{code:?},"message":{message:?},"http_status":{status}}}}}}}"#
+        ),
+    )
+}
+
+/// Logs in against a stub with the answer of the login endpoint, it's only given with the `csrftoken` cookie
+async fn login(session: Response) -> pinterest_login::Result<pinterest_login::PinterestCookies> {
+    let server = FixtureServer::start(vec![
+        ("/login/", login_page()),
+        (
+            SESSION_PATH,
+            resource_error(403, "csrf", "CSRF verification failed")
+                .when_cookie("csrftoken", session),
+        ),
+    ]);
+    HttpLogin::new()
+        .with_base_url(server.url(""))
+        .with_timeout(Duration::from_secs(10))
+        .login("me@example.com", "hunter2")
+        .await
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_successful_login_returns_the_cookies() {
+    let success = resource(
+        200,
+        r#"{"resource_response":{"status":"success","data":{}}}"#,
+    )
+    .header(
+        "Set-Cookie",
+        "_pinterest_sess=session; Path=/; Secure; HttpOnly",
+    )
+    .header("Set-Cookie", "csrftoken=rotated; Path=/; Secure")
+    .header("Set-Cookie", "_pinterest_referrer=; Max-Age=0; Path=/");
+
+    let cookies = login(success).await.unwrap();
+
+    assert_eq!(cookies["_pinterest_sess"], "session");
+    assert_eq!(cookies["csrftoken"], "rotated");
+    assert!(!cookies.contains_key("_pinterest_referrer"), "{cookies:?}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_wrong_password_is_an_authentication_error() {
+    let err = login(resource_error(
+        401,
+        "unauthorized",
+        "The password you entered is incorrect.",
+    ))
+    .await
+    .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_captcha_is_a_challenge() {
+    let err = login(resource_error(403, "recaptcha", "Recaptcha required"))
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::ChallengeRequired),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn too_many_attempts_are_rate_limited() {
+    let err = login(resource_error(429, "rate_limited", "Too many requests"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, PinterestLoginError::RateLimited), "{err}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_server_error_is_an_outage_with_its_retry_hint() {
+    let err = login(
+        Response::html("<h1>Service unavailable</h1>")
+            .status(503)
+            .header("Retry-After", "120"),
+    )
+    .await
+    .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::ServiceUnavailable { retry_hint: Some(hint) } if hint == Duration::from_secs(120)),
+        "{err:?}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_login_page_without_a_csrf_token_fails() {
+    let server = FixtureServer::start(vec![("/login/", Response::html("<html></html>"))]);
+
+    let err = HttpLogin::new()
+        .with_base_url(server.url(""))
+        .login("me@example.com", "hunter2")
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::HttpLoginError(ref message) if message.contains("csrftoken")),
+        "{err:?}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn an_unreachable_endpoint_is_a_network_error() {
+    // Nothing listens on the port of a closed listener
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let err = HttpLogin::new()
+        .with_base_url(format!("http://{addr}"))
+        .login("me@example.com", "hunter2")
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Network, "{err:?}");
+}
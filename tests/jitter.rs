@@ -0,0 +1,21 @@
+use pinterest_login::jitter::{random_fraction, Jitter};
+
+#[test]
+fn the_same_seed_gives_the_same_numbers() {
+    let (mut first, mut second) = (Jitter::new(7), Jitter::new(7));
+    for _ in 0..10 {
+        assert_eq!(first.next_u64(), second.next_u64());
+    }
+    assert_ne!(Jitter::new(7).next_u64(), Jitter::new(8).next_u64());
+}
+
+#[test]
+fn the_numbers_stay_in_their_ranges() {
+    let mut jitter = Jitter::new(3);
+    for _ in 0..1000 {
+        assert!(jitter.below(5) < 5);
+        assert!((10..=50).contains(&jitter.between((10, 50))));
+        assert!((0.0..1.0).contains(&jitter.fraction()));
+        assert!((0.0..1.0).contains(&random_fraction()));
+    }
+}
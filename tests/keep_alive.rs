@@ -0,0 +1,56 @@
+mod common;
+
+use std::time::Duration;
+
+use chromiumoxide::Page;
+use common::{FixtureServer, Response};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{BrowserLoginBot, DefaultBrowserLoginBot};
+use pinterest_login::login_with_options;
+use pinterest_login::options::LoginOptions;
+
+/// Waits a while before filling the form, like a human solving a captcha
+struct SlowBot(DefaultBrowserLoginBot);
+
+impl BrowserLoginBot for SlowBot {
+    async fn fill_login_form(&self, page: &Page) -> pinterest_login::Result<()> {
+        common::sleep(Duration::from_secs(1)).await;
+        self.0.fill_login_form(page).await
+    }
+
+    async fn submit_login_form(&self, page: &Page) -> pinterest_login::Result<()> {
+        self.0.submit_login_form(page).await
+    }
+
+    async fn check_login(&self, page: &Page) -> pinterest_login::Result<()> {
+        self.0.check_login(page).await
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_browser_is_pinged_during_long_waits() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        ("/login", Response::html(common::login_page("/home"))),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ]);
+
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .keep_alive(Duration::from_millis(200));
+    let bot = SlowBot(DefaultBrowserLoginBot::new("email@example.com", "password"));
+    let config_builder = DefaultBrowserConfigBuilder::default();
+
+    let outcome = login_with_options(&bot, &config_builder, &options)
+        .await
+        .expect("the login against the fixture server should succeed");
+
+    assert!(
+        outcome.report.keep_alive_pings >= 3,
+        "only {} pings were sent during the wait",
+        outcome.report.keep_alive_pings
+    );
+}
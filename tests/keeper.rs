@@ -0,0 +1,478 @@
+mod common;
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::error::CdpError;
+use chromiumoxide::{Browser, BrowserConfig, Handler};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use pinterest_login::clock::ManualClock;
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::keeper::{KeeperPolicy, SessionKeeper};
+use pinterest_login::launcher::BrowserLauncher;
+use pinterest_login::lifecycle::{ChannelEvents, SessionEvent, SessionEvents};
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::options::LoginOptions;
+use pinterest_login::replay::{ReplayLauncher, SCRUBBED};
+use pinterest_login::retry::StepRetry;
+use pinterest_login::session::{
+    InMemorySessionStore, Session, SessionError, SessionStore, DEFAULT_EXPIRY_MARGIN,
+};
+use pinterest_login::{PinterestCookies, PinterestLoginError};
+
+// The fixture was recorded from a login against a local fixture server
+const LOGIN_URL: &str = "http://127.0.0.1:8080/login";
+const SUCCESS: &str = "tests/fixtures/replay/login-success.json";
+const INTERVAL: Duration = Duration::from_secs(10 * 60);
+const ACCOUNT: &str = "me@example.com";
+
+/// Replays a successful login at every launch
+#[derive(Clone, Default)]
+struct Replays(Arc<AtomicUsize>);
+
+impl BrowserLauncher for Replays {
+    fn launch(
+        &self,
+        config: BrowserConfig,
+    ) -> BoxFuture<'_, pinterest_login::Result<(Browser, Handler)>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            let launcher = ReplayLauncher::load(SUCCESS).unwrap();
+            launcher.launch(config).await
+        })
+    }
+}
+
+/// Fails every launch
+#[derive(Clone, Default)]
+struct BusyPortLauncher(Arc<AtomicUsize>);
+
+impl BrowserLauncher for BusyPortLauncher {
+    fn launch(
+        &self,
+        _config: BrowserConfig,
+    ) -> BoxFuture<'_, pinterest_login::Result<(Browser, Handler)>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {
+            let err = io::Error::new(io::ErrorKind::AddrInUse, "Address already in use");
+            Err(CdpError::Io(err).into())
+        })
+    }
+}
+
+fn session(value: &str, expires_in: Duration) -> Session {
+    let cookies: PinterestCookies = [("_pinterest_sess".to_string(), value.to_string())].into();
+    Session::new(ACCOUNT, cookies).with_expires_at(SystemTime::now() + expires_in)
+}
+
+fn fresh(value: &str) -> Session {
+    session(value, DEFAULT_EXPIRY_MARGIN * 10)
+}
+
+fn expiring(value: &str) -> Session {
+    session(value, DEFAULT_EXPIRY_MARGIN / 2)
+}
+
+fn expired(value: &str) -> Session {
+    let cookies: PinterestCookies = [("_pinterest_sess".to_string(), value.to_string())].into();
+    Session::new(ACCOUNT, cookies).with_expires_at(SystemTime::now() - Duration::from_secs(60))
+}
+
+/// Records its calls, and panics in some of them
+#[derive(Clone, Default)]
+struct PanickingEvents(Arc<Mutex<Vec<String>>>);
+
+impl SessionEvents for PanickingEvents {
+    fn on_expired<'a>(&'a self, account: &'a str) -> BoxFuture<'a, ()> {
+        self.0.lock().unwrap().push(format!("expired {account}"));
+        panic!("before the future");
+    }
+
+    fn on_error<'a>(&'a self, account: &'a str, _: &'a PinterestLoginError) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            self.0.lock().unwrap().push(format!("error {account}"));
+            panic!("in the future");
+        })
+    }
+}
+
+fn policy(clock: &ManualClock, launcher: impl BrowserLauncher + 'static) -> KeeperPolicy {
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher)
+        .launch_retry(StepRetry {
+            retries: 0,
+            delay: Duration::ZERO,
+        })
+        .clock(clock.clone());
+    KeeperPolicy::new()
+        .interval(INTERVAL)
+        .jitter(0.0)
+        .login_options(options)
+}
+
+async fn spawn(
+    store: &Arc<InMemorySessionStore>,
+    policy: KeeperPolicy,
+) -> Result<SessionKeeper, SessionError> {
+    SessionKeeper::spawn(
+        Arc::clone(store) as Arc<dyn SessionStore>,
+        ACCOUNT,
+        || {
+            DefaultBrowserLoginBot::new(ACCOUNT, "hunter2")
+                .with_login_host(Host::new("127.0.0.1:8080"))
+        },
+        // Nothing is launched, the executable just has to be there to build the config
+        DefaultBrowserConfigBuilder::default().with_executable("chrome"),
+        policy,
+    )
+    .await
+}
+
+/// Waits for the keeper task to get there
+async fn eventually(what: &str, condition: impl Fn() -> bool) {
+    for _ in 0..500 {
+        if condition() {
+            return;
+        }
+        common::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the keeper didn't get there: {what}");
+}
+
+/// Waits for the keeper to sleep until its next cycle, and moves the clock to it
+async fn next_cycle(clock: &ManualClock, sleeps: usize) {
+    eventually("sleeping", || {
+        clock.pending() == 1 && clock.sleeps().len() == sleeps
+    })
+    .await;
+    clock.advance(*clock.sleeps().last().unwrap());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_fresh_session_is_kept_and_reloaded_from_the_store() {
+    let clock = ManualClock::new();
+    let launcher = BusyPortLauncher::default();
+    let store = Arc::new(InMemorySessionStore::new());
+    let stored = fresh("stored");
+    store.put(&stored).await.unwrap();
+
+    let keeper = spawn(&store, policy(&clock, launcher.clone()))
+        .await
+        .unwrap();
+    assert_eq!(*keeper.current(), stored);
+
+    // Another process renewed it
+    let renewed = fresh("renewed");
+    store.put(&renewed).await.unwrap();
+    next_cycle(&clock, 1).await;
+    eventually("the renewed session", || *keeper.current() == renewed).await;
+    next_cycle(&clock, 2).await;
+
+    eventually("the third cycle", || clock.sleeps().len() == 3).await;
+    assert_eq!(clock.sleeps(), [INTERVAL; 3]);
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 0);
+    keeper.stop().await;
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn an_expiring_session_is_renewed_and_stored() {
+    let clock = ManualClock::new();
+    let launcher = Replays::default();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.put(&expiring("old")).await.unwrap();
+
+    // It's renewed right away, since it expires soon
+    let keeper = spawn(&store, policy(&clock, launcher.clone()))
+        .await
+        .unwrap();
+    assert_eq!(keeper.current().cookies["_pinterest_sess"], SCRUBBED);
+    assert_eq!(keeper.current().login_url, LOGIN_URL);
+    assert_eq!(
+        store.get(ACCOUNT).await.unwrap().as_ref(),
+        Some(&*keeper.current())
+    );
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 1);
+
+    // And again by the task
+    store.put(&expiring("old again")).await.unwrap();
+    // The handshake timeout of the first login was requested too
+    next_cycle(&clock, 2).await;
+    eventually("the second login", || {
+        keeper.current().cookies["_pinterest_sess"] == SCRUBBED
+            && launcher.0.load(Ordering::SeqCst) == 2
+    })
+    .await;
+    eventually("the stored session", || {
+        futures::executor::block_on(store.get(ACCOUNT))
+            .unwrap()
+            .as_ref()
+            == Some(&*keeper.current())
+    })
+    .await;
+    keeper.stop().await;
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_expiry_is_checked_against_the_clock() {
+    let clock = ManualClock::new();
+    let launcher = Replays::default();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.put(&fresh("stored")).await.unwrap();
+
+    let keeper = spawn(&store, policy(&clock, launcher.clone()))
+        .await
+        .unwrap();
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 0);
+
+    // Only the clock of the options says that the session expires soon
+    eventually("sleeping", || clock.pending() == 1).await;
+    clock.advance(DEFAULT_EXPIRY_MARGIN * 10);
+    eventually("the renewal", || {
+        keeper.current().cookies["_pinterest_sess"] == SCRUBBED
+    })
+    .await;
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 1);
+    keeper.stop().await;
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_failures_back_off_and_are_reported() {
+    let clock = ManualClock::new();
+    let launcher = BusyPortLauncher::default();
+    let store = Arc::new(InMemorySessionStore::new());
+    let current = fresh("current");
+    store.put(&current).await.unwrap();
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let policy = policy(&clock, launcher.clone()).on_failure({
+        let failures = Arc::clone(&failures);
+        move |failure| {
+            assert!(
+                matches!(failure.error, SessionError::Login(_)),
+                "{}",
+                failure.error
+            );
+            failures
+                .lock()
+                .unwrap()
+                .push((failure.failures, failure.retry_in));
+        }
+    });
+    let keeper = spawn(&store, policy).await.unwrap();
+    store.put(&expiring("expiring")).await.unwrap();
+
+    next_cycle(&clock, 1).await;
+    for cycle in 1..6 {
+        eventually("the failure", || failures.lock().unwrap().len() == cycle).await;
+        // Every cycle also requested a handshake timeout
+        next_cycle(&clock, 1 + 2 * cycle).await;
+    }
+    eventually("the last failure", || failures.lock().unwrap().len() == 6).await;
+
+    let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+    assert_eq!(
+        *failures.lock().unwrap(),
+        [
+            (1, minutes(1)),
+            (2, minutes(2)),
+            (3, minutes(4)),
+            (4, minutes(8)),
+            (5, INTERVAL),
+            (6, INTERVAL)
+        ]
+    );
+    // The task is still there, with the previous session
+    assert_eq!(*keeper.current(), current);
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 6);
+    keeper.stop().await;
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_jitter_shortens_the_backoff() {
+    let clock = ManualClock::new();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.put(&fresh("current")).await.unwrap();
+    let retries = Arc::new(Mutex::new(Vec::new()));
+    let policy = policy(&clock, BusyPortLauncher::default())
+        .jitter(0.5)
+        .on_failure({
+            let retries = Arc::clone(&retries);
+            move |failure| retries.lock().unwrap().push(failure.retry_in)
+        });
+    let keeper = spawn(&store, policy).await.unwrap();
+    store.put(&expiring("expiring")).await.unwrap();
+
+    next_cycle(&clock, 1).await;
+    eventually("the failure", || retries.lock().unwrap().len() == 1).await;
+
+    let retry = retries.lock().unwrap()[0];
+    assert!(
+        (Duration::from_secs(30)..=Duration::from_secs(60)).contains(&retry),
+        "{retry:?}"
+    );
+    keeper.stop().await;
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_task_stops_when_the_keeper_is_dropped() {
+    let clock = ManualClock::new();
+    let store = Arc::new(InMemorySessionStore::new());
+    store.put(&fresh("current")).await.unwrap();
+
+    let keeper = spawn(&store, policy(&clock, BusyPortLauncher::default()))
+        .await
+        .unwrap();
+    eventually("sleeping", || clock.pending() == 1).await;
+    drop(keeper);
+
+    eventually("stopped", || clock.pending() == 0).await;
+}
+
+#[cfg(unix)]
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_logins_take_the_credentials_of_the_provider() {
+    use pinterest_login::credentials::{CommandCredentialsProvider, Credentials, CredentialsError};
+
+    let clock = ManualClock::new();
+    let launcher = Replays::default();
+    let store = Arc::new(InMemorySessionStore::new());
+    let spawn = |provider: CommandCredentialsProvider| {
+        SessionKeeper::spawn_with_credentials(
+            Arc::clone(&store) as Arc<dyn SessionStore>,
+            ACCOUNT,
+            provider,
+            |credentials: Credentials| {
+                DefaultBrowserLoginBot::new(ACCOUNT, credentials.password)
+                    .with_login_host(Host::new("127.0.0.1:8080"))
+            },
+            DefaultBrowserConfigBuilder::default().with_executable("chrome"),
+            policy(&clock, launcher.clone()),
+        )
+    };
+
+    let err = spawn(CommandCredentialsProvider::new("/nowhere/pass"))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            SessionError::Credentials(CredentialsError::Spawn { .. })
+        ),
+        "{err:?}"
+    );
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 0);
+
+    let keeper = spawn(CommandCredentialsProvider::new("echo").arg("hunter2"))
+        .await
+        .unwrap();
+    assert_eq!(keeper.current().cookies["_pinterest_sess"], SCRUBBED);
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 1);
+    keeper.stop().await;
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_first_login_has_to_succeed() {
+    let clock = ManualClock::new();
+    let launcher = BusyPortLauncher::default();
+    let store = Arc::new(InMemorySessionStore::new());
+
+    let err = spawn(&store, policy(&clock, launcher.clone()))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SessionError::Login(_)), "{err}");
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 1);
+    assert_eq!(store.list().await.unwrap(), Vec::<String>::new());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_events_are_told_in_order() {
+    let clock = ManualClock::new();
+    let store = Arc::new(InMemorySessionStore::new());
+    let (events, mut receiver) = ChannelEvents::new();
+
+    let keeper = spawn(&store, policy(&clock, Replays::default()).events(events))
+        .await
+        .unwrap();
+    store.put(&expired("expired")).await.unwrap();
+    // The handshake timeout of the first login was requested too
+    next_cycle(&clock, 2).await;
+
+    let mut told = Vec::new();
+    while told.len() < 4 {
+        told.push(match receiver.next().await.unwrap() {
+            SessionEvent::Login { session } => format!("login {}", session.account),
+            SessionEvent::Refreshed { session } => {
+                assert_eq!(session, *keeper.current());
+                format!("refreshed {}", session.account)
+            }
+            SessionEvent::Expired { account } => format!("expired {account}"),
+            SessionEvent::Error { account, message } => format!("error {account}: {message}"),
+            _ => unreachable!(),
+        });
+    }
+    assert_eq!(
+        told,
+        [
+            "login me@example.com",
+            "expired me@example.com",
+            "login me@example.com",
+            "refreshed me@example.com"
+        ]
+    );
+    keeper.stop().await;
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_panicking_hook_doesnt_stop_the_keeper() {
+    let clock = ManualClock::new();
+    let store = Arc::new(InMemorySessionStore::new());
+    let current = fresh("current");
+    store.put(&current).await.unwrap();
+    let events = PanickingEvents::default();
+    let failures = Arc::new(AtomicUsize::new(0));
+    let policy = policy(&clock, BusyPortLauncher::default())
+        .events(events.clone())
+        .on_failure({
+            let failures = Arc::clone(&failures);
+            move |_| {
+                failures.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+    let keeper = spawn(&store, policy).await.unwrap();
+    store.put(&expired("expired")).await.unwrap();
+    next_cycle(&clock, 1).await;
+    eventually("the failure", || failures.load(Ordering::SeqCst) == 1).await;
+    // Every cycle also requested a handshake timeout
+    next_cycle(&clock, 3).await;
+    eventually("the second failure", || {
+        failures.load(Ordering::SeqCst) == 2
+    })
+    .await;
+
+    assert_eq!(
+        *events.0.lock().unwrap(),
+        [
+            "expired me@example.com",
+            "error me@example.com",
+            "expired me@example.com",
+            "error me@example.com"
+        ]
+    );
+    assert_eq!(*keeper.current(), current);
+    keeper.stop().await;
+}
@@ -0,0 +1,292 @@
+#![cfg(feature = "keyring")]
+
+mod common;
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use futures::executor::block_on;
+use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+use keyring::Error;
+use pinterest_login::keyring_store::{self, KeyringSessionStore, KeyringStoreError};
+use pinterest_login::session::{Session, SessionError, SessionStore};
+
+/// The secrets by service and user
+type Secrets = BTreeMap<(String, String), Vec<u8>>;
+
+/// A keyring in memory, shared by all its entries unlike the mock of the keyring crate
+#[derive(Clone, Default)]
+struct MemoryKeyring {
+    secrets: Arc<Mutex<Secrets>>,
+    /// The limit of the size of the secrets, like the one of the Windows credential manager
+    max_size: Option<usize>,
+    /// Like a headless linux system without a secret service
+    unavailable: Arc<AtomicBool>,
+    /// Like a keychain that the user didn't unlock
+    locked: Arc<AtomicBool>,
+}
+
+impl MemoryKeyring {
+    fn store(&self) -> KeyringSessionStore {
+        KeyringSessionStore::new().with_credential_builder(Box::new(self.clone()))
+    }
+
+    fn users(&self) -> Vec<String> {
+        let secrets = self.secrets.lock().unwrap();
+        secrets.keys().map(|(_, user)| user.clone()).collect()
+    }
+}
+
+impl CredentialBuilderApi for MemoryKeyring {
+    fn build(
+        &self,
+        _target: Option<&str>,
+        service: &str,
+        user: &str,
+    ) -> keyring::Result<Box<Credential>> {
+        Ok(Box::new(MemoryCredential {
+            keyring: self.clone(),
+            key: (service.to_string(), user.to_string()),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct MemoryCredential {
+    keyring: MemoryKeyring,
+    key: (String, String),
+}
+
+impl MemoryCredential {
+    fn available(&self) -> keyring::Result<()> {
+        if self.keyring.unavailable.load(Ordering::SeqCst) {
+            return Err(Error::PlatformFailure("no secret service".into()));
+        }
+        if self.keyring.locked.load(Ordering::SeqCst) {
+            return Err(Error::NoStorageAccess("the collection is locked".into()));
+        }
+        Ok(())
+    }
+}
+
+impl CredentialApi for MemoryCredential {
+    fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+        self.available()?;
+        if let Some(max_size) = self.keyring.max_size {
+            if secret.len() > max_size {
+                return Err(Error::TooLong("secret".to_string(), max_size as u32));
+            }
+        }
+        let mut secrets = self.keyring.secrets.lock().unwrap();
+        secrets.insert(self.key.clone(), secret.to_vec());
+        Ok(())
+    }
+
+    fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+        self.available()?;
+        let secrets = self.keyring.secrets.lock().unwrap();
+        secrets.get(&self.key).cloned().ok_or(Error::NoEntry)
+    }
+
+    fn delete_credential(&self) -> keyring::Result<()> {
+        self.available()?;
+        let mut secrets = self.keyring.secrets.lock().unwrap();
+        secrets.remove(&self.key).map(|_| ()).ok_or(Error::NoEntry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn session(account: &str, size: usize) -> Session {
+    let cookies = [("_pinterest_sess".to_string(), "s".repeat(size))].into();
+    Session::new(account, cookies)
+        .with_created_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+}
+
+#[test]
+fn the_keyring_store_conforms() {
+    block_on(common::session_store_conformance(
+        &MemoryKeyring::default().store(),
+    ));
+}
+
+#[test]
+fn the_big_sessions_are_chunked_under_the_size_limit() {
+    let keyring = MemoryKeyring {
+        max_size: Some(100),
+        ..Default::default()
+    };
+    let store = keyring.store().with_chunk_size(90);
+    let big = session("me@example.com", 300);
+
+    block_on(store.put(&big)).unwrap();
+
+    let chunks = big.to_json().to_string().len().div_ceil(90);
+    assert!(chunks > 3, "{chunks}");
+    assert_eq!(block_on(store.get("me@example.com")).unwrap(), Some(big));
+    let mut users = vec!["#accounts".to_string(), "me@example.com".to_string()];
+    users.extend((1..chunks).map(|n| format!("me@example.com#{n}")));
+    assert_eq!(keyring.users(), users);
+
+    // A smaller session doesn't leave the chunks that it doesn't need
+    let small = session("me@example.com", 10);
+    let fewer = small.to_json().to_string().len().div_ceil(90);
+    assert!(fewer < chunks, "{fewer}");
+    block_on(store.put(&small)).unwrap();
+    assert_eq!(block_on(store.get("me@example.com")).unwrap(), Some(small));
+    users.truncate(fewer + 1);
+    assert_eq!(keyring.users(), users);
+
+    block_on(store.delete("me@example.com")).unwrap();
+    assert_eq!(keyring.users(), ["#accounts"]);
+    assert_eq!(block_on(store.list()).unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn without_chunks_the_size_limit_is_an_error() {
+    let keyring = MemoryKeyring {
+        max_size: Some(100),
+        ..Default::default()
+    };
+    let store = keyring.store().with_chunk_size(1000);
+
+    let err = block_on(store.put(&session("me@example.com", 300))).unwrap_err();
+
+    let SessionError::Store(source) = &err else {
+        panic!("{err:?}");
+    };
+    assert!(
+        matches!(
+            source.downcast_ref(),
+            Some(KeyringStoreError::Entry { user, error: Error::TooLong(..) }) if user == "me@example.com"
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn a_missing_secret_service_is_a_clear_error() {
+    let keyring = MemoryKeyring::default();
+    keyring.unavailable.store(true, Ordering::SeqCst);
+    let store = keyring.store();
+
+    for err in [
+        block_on(store.get("me@example.com")).unwrap_err(),
+        block_on(store.put(&session("me@example.com", 10))).unwrap_err(),
+        block_on(store.list()).unwrap_err(),
+    ] {
+        let SessionError::Store(source) = &err else {
+            panic!("{err:?}");
+        };
+        assert!(
+            matches!(
+                source.downcast_ref(),
+                Some(KeyringStoreError::Unavailable(_))
+            ),
+            "{err}"
+        );
+        assert!(err.to_string().contains("secret service"), "{err}");
+    }
+}
+
+#[test]
+fn a_locked_keyring_is_its_own_error() {
+    let keyring = MemoryKeyring::default();
+    keyring.locked.store(true, Ordering::SeqCst);
+
+    let err = block_on(keyring.store().get("me@example.com")).unwrap_err();
+
+    let SessionError::Store(source) = &err else {
+        panic!("{err:?}");
+    };
+    assert!(
+        matches!(source.downcast_ref(), Some(KeyringStoreError::Locked(_))),
+        "{err}"
+    );
+    assert!(err.to_string().contains("locked"), "{err}");
+}
+
+#[test]
+fn a_missing_chunk_is_a_corrupted_entry() {
+    let keyring = MemoryKeyring::default();
+    let store = keyring.store().with_chunk_size(50);
+    block_on(store.put(&session("me@example.com", 300))).unwrap();
+    keyring
+        .secrets
+        .lock()
+        .unwrap()
+        .retain(|(_, user), _| user != "me@example.com#2");
+
+    let err = block_on(store.get("me@example.com")).unwrap_err();
+
+    assert!(err.to_string().contains("incomplete"), "{err}");
+}
+
+#[test]
+fn the_services_dont_share_their_sessions() {
+    let keyring = MemoryKeyring::default();
+    let store = keyring.store();
+    let other = keyring.store().with_service("other");
+
+    block_on(store.put(&session("me@example.com", 10))).unwrap();
+
+    assert_eq!(block_on(other.get("me@example.com")).unwrap(), None);
+    assert_eq!(block_on(other.list()).unwrap(), Vec::<String>::new());
+}
+
+/// The only test that uses the default keyring, so the other tests don't share it
+#[test]
+fn the_functions_use_the_default_keyring() {
+    let keyring = MemoryKeyring::default();
+    keyring::set_default_credential_builder(Box::new(keyring.clone()));
+    let session = session("me@example.com", 10);
+
+    keyring_store::save("functions", &session).unwrap();
+    let loaded = keyring_store::load("functions", "me@example.com").unwrap();
+    assert_eq!(loaded.cookies, session.cookies);
+    // The store of the service sees it too
+    let store = KeyringSessionStore::new().with_service("functions");
+    assert_eq!(block_on(store.list()).unwrap(), ["me@example.com"]);
+
+    keyring_store::delete("functions", "me@example.com").unwrap();
+    assert!(matches!(
+        keyring_store::load("functions", "me@example.com"),
+        Err(KeyringStoreError::NotFound(account)) if account == "me@example.com"
+    ));
+    assert!(matches!(
+        keyring_store::delete("functions", "me@example.com"),
+        Err(KeyringStoreError::NotFound(_))
+    ));
+
+    // A session of a newer version of the crate
+    let mut json = session.to_json();
+    json["format_version"] = 99.into();
+    block_on(store.put(&session)).unwrap();
+    keyring.secrets.lock().unwrap().insert(
+        ("functions".to_string(), "me@example.com".to_string()),
+        format!("1\n{json}").into_bytes(),
+    );
+    assert!(matches!(
+        keyring_store::load("functions", "me@example.com"),
+        Err(KeyringStoreError::UnsupportedVersion { account, found: Some(99), .. }) if account == "me@example.com"
+    ));
+
+    keyring.locked.store(true, Ordering::SeqCst);
+    assert!(matches!(
+        keyring_store::load("functions", "me@example.com"),
+        Err(KeyringStoreError::Locked(_))
+    ));
+    keyring.unavailable.store(true, Ordering::SeqCst);
+    assert!(matches!(
+        keyring_store::load("functions", "me@example.com"),
+        Err(KeyringStoreError::Unavailable(_))
+    ));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use chromiumoxide::{Browser, BrowserConfig, Handler};
+use common::{FixtureServer, Response};
+use futures::future::BoxFuture;
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::launcher::{BrowserLauncher, ChromiumLauncher};
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::login_with_options;
+use pinterest_login::options::LoginOptions;
+
+/// Counts the launches, and delegates them to the default launcher
+#[derive(Clone, Default)]
+struct CountingLauncher(Arc<AtomicUsize>);
+
+impl BrowserLauncher for CountingLauncher {
+    fn launch(
+        &self,
+        config: BrowserConfig,
+    ) -> BoxFuture<'_, pinterest_login::Result<(Browser, Handler)>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        ChromiumLauncher.launch(config)
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn login_uses_the_supplied_launcher() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = FixtureServer::start(vec![
+        ("/login", Response::html(common::login_page("/home"))),
+        (
+            "/home",
+            Response::html("<p>Welcome</p>")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/"),
+        ),
+    ]);
+
+    let launcher = CountingLauncher::default();
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .launcher(launcher.clone());
+    let bot = DefaultBrowserLoginBot::new("email@example.com", "password");
+    let config_builder = DefaultBrowserConfigBuilder::default();
+
+    let outcome = login_with_options(&bot, &config_builder, &options)
+        .await
+        .expect("the login against the fixture server should succeed");
+
+    assert_eq!(launcher.0.load(Ordering::SeqCst), 1);
+    assert_eq!(outcome.report.launch_attempts, 1);
+    assert_eq!(
+        outcome.cookies.get("_pinterest_sess").map(String::as_str),
+        Some("fixture")
+    );
+}
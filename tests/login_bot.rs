@@ -0,0 +1,275 @@
+use pinterest_login::clock::ManualClock;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, ManualLoginBot, PageLoginBot, TwoFactor};
+use pinterest_login::mock::{MockAction, MockElement, MockPage};
+use pinterest_login::totp::TotpSecret;
+use pinterest_login::PinterestLoginError;
+use std::time::Duration;
+
+const LOGIN_URL: &str = "https://www.pinterest.com/login/";
+const EMAIL: &str = "input#email";
+const PASSWORD: &str = "input#password";
+const LOGIN_BUTTON: &str = "//*[contains(text(), 'Log in')]";
+
+fn bot() -> DefaultBrowserLoginBot {
+    DefaultBrowserLoginBot::new("me@example.com", "hunter2")
+}
+
+fn login_page(button: MockElement) -> MockPage {
+    MockPage::new(LOGIN_URL)
+        .element(EMAIL, MockElement::new("email"))
+        .element(PASSWORD, MockElement::new("password"))
+        .element(LOGIN_BUTTON, button)
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn fill_waits_for_the_email_field() {
+    let page = MockPage::new(LOGIN_URL)
+        .element(EMAIL, MockElement::new("email").appears_after(3))
+        .element(PASSWORD, MockElement::new("password"));
+
+    bot().fill_login_form(&page).await.unwrap();
+
+    assert_eq!(page.lookups(EMAIL), 4);
+    assert_eq!(
+        page.actions(),
+        [
+            MockAction::Typed {
+                element: "email".into(),
+                text: "me@example.com".into()
+            },
+            MockAction::Focused("password".into()),
+            MockAction::Typed {
+                element: "password".into(),
+                text: "hunter2".into()
+            },
+        ]
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn fill_fails_without_the_password_field() {
+    let page = MockPage::new(LOGIN_URL).element(EMAIL, MockElement::new("email"));
+
+    let err = bot().fill_login_form(&page).await.unwrap_err();
+
+    assert!(matches!(err, PinterestLoginError::CdpError(_)), "{err}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_successful_login_leaves_the_login_page() {
+    let page = login_page(MockElement::new("login").navigates_to("https://www.pinterest.com/"));
+    let bot = bot();
+
+    bot.fill_login_form(&page).await.unwrap();
+    bot.submit_login_form(&page).await.unwrap();
+    bot.check_login(&page).await.unwrap();
+
+    assert_eq!(
+        page.actions().last(),
+        Some(&MockAction::Clicked("login".into()))
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_wrong_password_is_an_authentication_error() {
+    // Pinterest stays on the login page and shows a tooltip, which moves the button
+    let page = login_page(MockElement::new("login").moves_to(0.0, 40.0));
+    let bot = bot();
+
+    bot.fill_login_form(&page).await.unwrap();
+    bot.submit_login_form(&page).await.unwrap();
+    let err = bot.check_login(&page).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn submit_clicks_every_login_button() {
+    let page = login_page(MockElement::new("login").navigates_to("https://www.pinterest.com/"))
+        .element(LOGIN_BUTTON, MockElement::new("login again"));
+
+    bot().submit_login_form(&page).await.unwrap();
+
+    assert_eq!(
+        page.actions(),
+        [
+            MockAction::Clicked("login".into()),
+            MockAction::Clicked("login again".into()),
+        ]
+    );
+}
+
+const TWO_FACTOR_CODE: &str = "input[autocomplete='one-time-code']";
+const TWO_FACTOR_SUBMIT: &str = "//button[@type='submit']";
+
+/// The page that pinterest shows after the login form when the account has two-factor authentication
+fn two_factor_page(submit: MockElement) -> MockPage {
+    MockPage::new("https://www.pinterest.com/two_factor/")
+        .element(TWO_FACTOR_CODE, MockElement::new("code"))
+        .element(TWO_FACTOR_SUBMIT, submit)
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_two_factor_challenge_without_a_code_source_is_a_challenge_error() {
+    let page = two_factor_page(MockElement::new("verify"));
+
+    let err = bot().check_login(&page).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::ChallengeRequired),
+        "{err}"
+    );
+    assert_eq!(page.actions(), []);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_two_factor_callback_code_is_entered() {
+    let page =
+        two_factor_page(MockElement::new("verify").navigates_to("https://www.pinterest.com/"));
+    let bot = bot().with_two_factor(TwoFactor::callback(|| Some("123456\n".to_string())));
+
+    bot.check_login(&page).await.unwrap();
+
+    assert_eq!(
+        page.actions(),
+        [
+            MockAction::Focused("code".into()),
+            MockAction::Typed {
+                element: "code".into(),
+                text: "123456".into()
+            },
+            MockAction::Clicked("verify".into()),
+        ]
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_two_factor_callback_can_give_up() {
+    let page = two_factor_page(MockElement::new("verify"));
+    let bot = bot().with_two_factor(TwoFactor::callback(|| None));
+
+    let err = bot.check_login(&page).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::ChallengeRequired),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_totp_code_is_entered() {
+    let secret: TotpSecret = "JBSWY3DPEHPK3PXP".parse().unwrap();
+    let page =
+        two_factor_page(MockElement::new("verify").navigates_to("https://www.pinterest.com/"));
+    let bot = bot().with_two_factor(TwoFactor::Totp(secret.clone()));
+
+    let before = secret.code();
+    bot.check_login(&page).await.unwrap();
+    let after = secret.code();
+
+    let Some(MockAction::Typed { text, .. }) = page.actions().get(1).cloned() else {
+        panic!("{:?}", page.actions());
+    };
+    // The code may change in the middle of the login
+    assert!(text == before || text == after, "{text}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_rejected_two_factor_code_is_an_authentication_error() {
+    // The code field stays on the page
+    let page = two_factor_page(MockElement::new("verify"));
+    let clock = ManualClock::new();
+    let bot = bot()
+        .with_clock(clock.clone())
+        .with_two_factor(TwoFactor::callback(|| Some("000000".to_string())));
+
+    let err = clock.drive(bot.check_login(&page)).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_manual_assist_waits_for_the_human() {
+    let clock = ManualClock::new();
+    let bot = bot()
+        .with_clock(clock.clone())
+        .with_manual_assist(Duration::from_secs(60));
+    // Even with a code source, the human answers the challenges
+    let page = two_factor_page(MockElement::new("verify"));
+    let bot = bot.with_two_factor(TwoFactor::callback(|| Some("123456".to_string())));
+
+    let err = clock.drive(bot.check_login(&page)).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::ChallengeRequired),
+        "{err}"
+    );
+    assert_eq!(page.actions(), []);
+    assert!(clock.sleeps().iter().sum::<Duration>() >= Duration::from_secs(60));
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_manual_assist_stops_once_the_login_is_done() {
+    let clock = ManualClock::new();
+    let bot = bot()
+        .with_clock(clock.clone())
+        .with_manual_assist(Duration::from_secs(60));
+
+    clock
+        .drive(bot.check_login(&MockPage::new("https://www.pinterest.com/")))
+        .await
+        .unwrap();
+
+    assert!(clock.sleeps().is_empty());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_manual_bot_leaves_the_form_to_the_human() {
+    let clock = ManualClock::new();
+    let bot = ManualLoginBot::new(Duration::from_secs(60)).with_clock(clock.clone());
+    let page = login_page(MockElement::new("log in").navigates_to("https://www.pinterest.com/"));
+
+    bot.fill_login_form(&page).await.unwrap();
+    bot.submit_login_form(&page).await.unwrap();
+    let err = clock.drive(bot.check_login(&page)).await.unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::DeadlineExceeded(timeout) if timeout == Duration::from_secs(60)),
+        "{err}"
+    );
+    assert_eq!(page.actions(), []);
+    assert!(clock.sleeps().iter().sum::<Duration>() >= Duration::from_secs(60));
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_manual_bot_stops_once_the_human_logged_in() {
+    let clock = ManualClock::new();
+    let bot = ManualLoginBot::new(Duration::from_secs(60)).with_clock(clock.clone());
+
+    clock
+        .drive(bot.check_login(&MockPage::new("https://www.pinterest.com/")))
+        .await
+        .unwrap();
+
+    assert!(clock.sleeps().is_empty());
+}
@@ -0,0 +1,317 @@
+mod common;
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::error::CdpError;
+use chromiumoxide::{Browser, BrowserConfig, Handler};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::future::BoxFuture;
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::credentials::{CommandCredentialsProvider, Credentials, CredentialsError};
+use pinterest_login::launcher::BrowserLauncher;
+use pinterest_login::lifecycle::{ChannelEvents, SessionEvent};
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::manager::SessionManager;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::replay::{ReplayLauncher, SCRUBBED};
+use pinterest_login::retry::StepRetry;
+use pinterest_login::session::{
+    InMemorySessionStore, Session, SessionError, SessionStore, DEFAULT_EXPIRY_MARGIN,
+};
+
+// The fixture was recorded from a login against a local fixture server
+const LOGIN_URL: &str = "http://127.0.0.1:8080/login";
+const SUCCESS: &str = "tests/fixtures/replay/login-success.json";
+const CALLERS: usize = 8;
+
+/// Replays a successful login at every launch, or fails it, after a while so the callers overlap.
+/// It keeps track of how many launches are in flight.
+#[derive(Clone, Default)]
+struct SlowLauncher {
+    fail: bool,
+    launches: Arc<AtomicUsize>,
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl BrowserLauncher for SlowLauncher {
+    fn launch(
+        &self,
+        config: BrowserConfig,
+    ) -> BoxFuture<'_, pinterest_login::Result<(Browser, Handler)>> {
+        self.launches.fetch_add(1, Ordering::SeqCst);
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        Box::pin(async move {
+            common::sleep(Duration::from_millis(100)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if self.fail {
+                let err = io::Error::new(io::ErrorKind::AddrInUse, "Address already in use");
+                return Err(CdpError::Io(err).into());
+            }
+            ReplayLauncher::load(SUCCESS).unwrap().launch(config).await
+        })
+    }
+}
+
+type Manager = SessionManager<DefaultBrowserLoginBot, DefaultBrowserConfigBuilder>;
+
+fn manager(
+    store: &Arc<InMemorySessionStore>,
+    launcher: &SlowLauncher,
+    accounts: &[&str],
+) -> Manager {
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher.clone())
+        .launch_retry(StepRetry {
+            retries: 0,
+            delay: Duration::ZERO,
+        });
+    // Nothing is launched, the executable just has to be there to build the config
+    let config_builder = DefaultBrowserConfigBuilder::default().with_executable("chrome");
+    let mut manager =
+        SessionManager::new(Arc::clone(store) as Arc<dyn SessionStore>, config_builder)
+            .login_options(options);
+    for account in accounts {
+        // The fixture was recorded with these credentials, whatever the account
+        manager = manager.account(*account, || {
+            DefaultBrowserLoginBot::new("me@example.com", "hunter2")
+                .with_login_host(Host::new("127.0.0.1:8080"))
+        });
+    }
+    manager
+}
+
+/// The [`SessionEvent`]s that were told to the receiver, e.g. `login a@example.com`
+fn told(receiver: &mut UnboundedReceiver<SessionEvent>) -> Vec<String> {
+    std::iter::from_fn(|| receiver.try_next().ok().flatten())
+        .map(|event| match event {
+            SessionEvent::Login { session } => format!("login {}", session.account),
+            SessionEvent::Refreshed { session } => format!("refreshed {}", session.account),
+            SessionEvent::Expired { account } => format!("expired {account}"),
+            SessionEvent::Error { account, .. } => format!("error {account}"),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn concurrent_callers_of_an_account_share_a_single_login() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let launcher = SlowLauncher::default();
+    let (events, mut receiver) = ChannelEvents::new();
+    let manager = manager(&store, &launcher, &["a@example.com"]).events(events);
+
+    let sessions =
+        futures::future::join_all((0..CALLERS).map(|_| manager.get_or_login("a@example.com")))
+            .await;
+
+    assert_eq!(launcher.launches.load(Ordering::SeqCst), 1);
+    let first = sessions[0].as_ref().unwrap();
+    assert_eq!(first.cookies["_pinterest_sess"], SCRUBBED);
+    for session in &sessions {
+        assert_eq!(session.as_ref().unwrap(), first);
+    }
+    assert_eq!(
+        store.get("a@example.com").await.unwrap().as_ref(),
+        Some(first)
+    );
+    assert_eq!(told(&mut receiver), ["login a@example.com"]);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn the_accounts_dont_wait_for_each_other() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let launcher = SlowLauncher::default();
+    let manager = manager(&store, &launcher, &["a@example.com", "b@example.com"]);
+
+    let (a, b) = futures::join!(
+        manager.get_or_login("a@example.com"),
+        manager.get_or_login("b@example.com")
+    );
+
+    assert_eq!(a.unwrap().account, "a@example.com");
+    assert_eq!(b.unwrap().account, "b@example.com");
+    assert_eq!(launcher.launches.load(Ordering::SeqCst), 2);
+    assert_eq!(launcher.max_in_flight.load(Ordering::SeqCst), 2);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_fresh_stored_session_is_loaded_and_an_expiring_one_renewed() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let launcher = SlowLauncher::default();
+    let manager = manager(&store, &launcher, &["a@example.com", "b@example.com"]);
+    let fresh = Session::new("a@example.com", Default::default())
+        .with_expires_at(SystemTime::now() + DEFAULT_EXPIRY_MARGIN * 2);
+    let expiring = Session::new("b@example.com", Default::default())
+        .with_expires_at(SystemTime::now() + DEFAULT_EXPIRY_MARGIN / 2);
+    store.put(&fresh).await.unwrap();
+    store.put(&expiring).await.unwrap();
+
+    assert_eq!(manager.get_or_login("a@example.com").await.unwrap(), fresh);
+    let renewed = manager.get_or_login("b@example.com").await.unwrap();
+
+    assert_eq!(renewed.cookies["_pinterest_sess"], SCRUBBED);
+    assert_eq!(launcher.launches.load(Ordering::SeqCst), 1);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn an_invalidated_account_logs_in_again() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let launcher = SlowLauncher::default();
+    let (events, mut receiver) = ChannelEvents::new();
+    let manager = manager(&store, &launcher, &["a@example.com"]).events(events);
+
+    manager.get_or_login("a@example.com").await.unwrap();
+    manager.invalidate("a@example.com").await.unwrap();
+    assert_eq!(store.get("a@example.com").await.unwrap(), None);
+    manager.get_or_login("a@example.com").await.unwrap();
+
+    assert_eq!(launcher.launches.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        told(&mut receiver),
+        ["login a@example.com", "login a@example.com"]
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn refresh_all_logs_every_account_in_with_the_concurrency() {
+    let accounts = [
+        "a@example.com",
+        "b@example.com",
+        "c@example.com",
+        "d@example.com",
+    ];
+    let store = Arc::new(InMemorySessionStore::new());
+    let launcher = SlowLauncher {
+        fail: true,
+        ..Default::default()
+    };
+    let (events, mut receiver) = ChannelEvents::new();
+    let manager = manager(&store, &launcher, &accounts).events(events);
+
+    let results = manager.refresh_all(2).await;
+
+    let names: Vec<_> = results
+        .iter()
+        .map(|(account, _)| account.as_str())
+        .collect();
+    assert_eq!(names, accounts);
+    assert!(results
+        .iter()
+        .all(|(_, result)| matches!(result, Err(SessionError::Login(_)))));
+    assert_eq!(launcher.launches.load(Ordering::SeqCst), 4);
+    assert_eq!(launcher.max_in_flight.load(Ordering::SeqCst), 2);
+    let failed = told(&mut receiver)
+        .iter()
+        .filter(|event| event.starts_with("error"))
+        .count();
+    assert_eq!(failed, 4);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn an_unknown_account_is_an_error() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let launcher = SlowLauncher::default();
+    let manager = manager(&store, &launcher, &["a@example.com"]);
+
+    let err = manager.get_or_login("b@example.com").await.unwrap_err();
+
+    assert!(matches!(&err, SessionError::UnknownAccount(account) if account == "b@example.com"));
+    assert!(matches!(
+        manager.invalidate("b@example.com").await,
+        Err(SessionError::UnknownAccount(_))
+    ));
+    assert_eq!(manager.accounts().collect::<Vec<_>>(), ["a@example.com"]);
+    assert_eq!(launcher.launches.load(Ordering::SeqCst), 0);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_credentials_are_asked_to_the_provider_before_every_login() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let launcher = SlowLauncher::default();
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let manager = manager(&store, &launcher, &[])
+        .account_with_credentials(
+            "a@example.com",
+            Credentials::new("hunter2").with_email("me@example.com"),
+            {
+                let asked = Arc::clone(&asked);
+                move |credentials| {
+                    asked.lock().unwrap().push(credentials.clone());
+                    DefaultBrowserLoginBot::new(credentials.email.unwrap(), credentials.password)
+                        .with_login_host(Host::new("127.0.0.1:8080"))
+                }
+            },
+        )
+        .account_with_credentials(
+            "b@example.com",
+            CommandCredentialsProvider::new("/nowhere/pass"),
+            |_| unreachable!(),
+        );
+
+    manager.refresh("a@example.com").await.unwrap();
+    manager.refresh("a@example.com").await.unwrap();
+    let err = manager.get_or_login("b@example.com").await.unwrap_err();
+
+    let credentials = Credentials::new("hunter2").with_email("me@example.com");
+    assert_eq!(*asked.lock().unwrap(), [credentials.clone(), credentials]);
+    assert!(
+        matches!(
+            err,
+            SessionError::Credentials(CredentialsError::Spawn { .. })
+        ),
+        "{err:?}"
+    );
+    assert_eq!(launcher.launches.load(Ordering::SeqCst), 2);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_session_events_are_told_in_order() {
+    let store = Arc::new(InMemorySessionStore::new());
+    let (events, mut receiver) = ChannelEvents::new();
+    let failing = SlowLauncher {
+        fail: true,
+        ..Default::default()
+    };
+    let failing = manager(&store, &failing, &["c@example.com"]).events(events.clone());
+    let manager = manager(
+        &store,
+        &SlowLauncher::default(),
+        &["a@example.com", "b@example.com"],
+    )
+    .events(events);
+    let expired = Session::new("a@example.com", Default::default())
+        .with_expires_at(SystemTime::now() - Duration::from_secs(60));
+    store.put(&expired).await.unwrap();
+
+    manager.get_or_login("a@example.com").await.unwrap();
+    manager.get_or_login("b@example.com").await.unwrap();
+    manager.refresh("b@example.com").await.unwrap();
+    failing.get_or_login("c@example.com").await.unwrap_err();
+
+    assert_eq!(
+        told(&mut receiver),
+        [
+            "expired a@example.com",
+            "login a@example.com",
+            "refreshed a@example.com",
+            "login b@example.com",
+            "login b@example.com",
+            "refreshed b@example.com",
+            "error c@example.com"
+        ]
+    );
+}
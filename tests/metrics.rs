@@ -0,0 +1,110 @@
+#![cfg(feature = "otel-metrics")]
+
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData, ResourceMetrics};
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::login_with_options;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::replay::ReplayLauncher;
+
+// The fixtures were recorded from a login against a local fixture server
+const LOGIN_URL: &str = "http://127.0.0.1:8080/login";
+const SUCCESS: &str = "tests/fixtures/replay/login-success.json";
+const WRONG_PASSWORD: &str = "tests/fixtures/replay/wrong-password.json";
+
+async fn replay(recording: &str) {
+    let launcher = ReplayLauncher::load(recording).unwrap();
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher.clone());
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2")
+        .with_login_host(Host::new("127.0.0.1:8080"));
+    let config_builder = DefaultBrowserConfigBuilder::default().with_executable("chrome");
+
+    let _ = login_with_options(&bot, &config_builder, &options).await;
+    launcher.verify();
+}
+
+/// The sums of the counter by its attributes
+fn counter(metrics: &[ResourceMetrics], name: &str) -> Vec<(Vec<KeyValue>, u64)> {
+    metrics
+        .iter()
+        .flat_map(|resource| resource.scope_metrics())
+        .flat_map(|scope| scope.metrics())
+        .filter(|metric| metric.name() == name)
+        .flat_map(|metric| match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .map(|point| (point.attributes().cloned().collect(), point.value()))
+                .collect::<Vec<_>>(),
+            data => panic!("{name} isn't a counter: {data:?}"),
+        })
+        .collect()
+}
+
+/// The phases of the histogram, with their counts
+fn phases(metrics: &[ResourceMetrics]) -> Vec<(String, u64)> {
+    let mut phases: Vec<_> = metrics
+        .iter()
+        .flat_map(|resource| resource.scope_metrics())
+        .flat_map(|scope| scope.metrics())
+        .filter(|metric| metric.name() == "pinterest_login.duration")
+        .flat_map(|metric| match metric.data() {
+            AggregatedMetrics::F64(MetricData::Histogram(histogram)) => histogram
+                .data_points()
+                .map(|point| {
+                    let phase = point
+                        .attributes()
+                        .find(|kv| kv.key.as_str() == "phase")
+                        .map(|kv| kv.value.to_string())
+                        .unwrap();
+                    (phase, point.count())
+                })
+                .collect::<Vec<_>>(),
+            data => panic!("the duration isn't a histogram: {data:?}"),
+        })
+        .collect();
+    phases.sort();
+    phases
+}
+
+// A single test, since the meter provider is global
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_logins_are_recorded_with_the_global_meter_provider() {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    opentelemetry::global::set_meter_provider(provider.clone());
+
+    replay(SUCCESS).await;
+    replay(WRONG_PASSWORD).await;
+    provider.force_flush().unwrap();
+    let metrics = exporter.get_finished_metrics().unwrap();
+
+    assert_eq!(counter(&metrics, "pinterest_login.attempts"), [(vec![], 2)]);
+    assert_eq!(
+        counter(&metrics, "pinterest_login.successes"),
+        [(vec![], 1)]
+    );
+    assert_eq!(
+        counter(&metrics, "pinterest_login.failures"),
+        [(vec![KeyValue::new("code", "authentication")], 1)]
+    );
+    // The wrong password fails in the check
+    assert_eq!(
+        phases(&metrics),
+        [
+            ("check login".to_string(), 2),
+            ("collect cookies".to_string(), 1),
+            ("fill form".to_string(), 2),
+            ("launch".to_string(), 2),
+            ("navigation".to_string(), 2),
+            ("setup".to_string(), 2),
+            ("submit form".to_string(), 2),
+        ]
+    );
+}
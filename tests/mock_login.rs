@@ -0,0 +1,92 @@
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::mock::MockLogin;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::provider::{BrowserLogin, LoginProvider};
+use pinterest_login::{PinterestCookies, PinterestLoginError};
+
+const LOGIN_URL: &str = "http://127.0.0.1:8080/login";
+
+fn cookies(session: &str) -> PinterestCookies {
+    [("_pinterest_sess".to_string(), session.to_string())].into()
+}
+
+/// The code under test, that only knows about the trait
+async fn session(provider: &dyn LoginProvider) -> Result<String, PinterestLoginError> {
+    let options = LoginOptions::new().login_url(LOGIN_URL);
+    let outcome = provider.login(&options).await?;
+    Ok(outcome.cookies["_pinterest_sess"].clone())
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_mock_returns_the_cookies_with_their_details() {
+    let login = MockLogin::returning(cookies("session"));
+
+    let outcome = login.login(&LoginOptions::new()).await.unwrap();
+
+    assert_eq!(outcome.cookies, cookies("session"));
+    assert_eq!(outcome.cookie_details.len(), 1);
+    assert_eq!(outcome.cookie_details[0].name, "_pinterest_sess");
+    assert_eq!(outcome.cookie_details[0].domain, ".pinterest.com");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_mock_fails_with_a_fresh_error_every_time() {
+    let login = MockLogin::failing(|| PinterestLoginError::ChallengeRequired);
+
+    for _ in 0..2 {
+        assert!(matches!(
+            session(&login).await,
+            Err(PinterestLoginError::ChallengeRequired)
+        ));
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_script_is_played_in_order_and_its_last_step_repeats() {
+    let login = MockLogin::returning(cookies("first"))
+        .then_failing(|| PinterestLoginError::RateLimited)
+        .then_returning(cookies("second"));
+
+    assert_eq!(session(&login).await.unwrap(), "first");
+    assert!(matches!(
+        session(&login).await,
+        Err(PinterestLoginError::RateLimited)
+    ));
+    assert_eq!(session(&login).await.unwrap(), "second");
+    assert_eq!(session(&login).await.unwrap(), "second");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_logins_are_recorded_and_shared_by_the_clones() {
+    let login = MockLogin::returning(cookies("session"));
+    let clone = login.clone();
+
+    session(&clone).await.unwrap();
+    login
+        .login(&LoginOptions::new().login_url("http://127.0.0.1:8080/other"))
+        .await
+        .unwrap();
+
+    let calls = login.calls();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].login_url, LOGIN_URL);
+    assert_eq!(calls[1].login_url, "http://127.0.0.1:8080/other");
+    assert_eq!(clone.calls().len(), 2);
+}
+
+#[test]
+fn the_browser_login_is_a_shareable_provider() {
+    fn assert_provider<P: LoginProvider + Send + Sync + 'static>(_: &P) {}
+
+    let provider = std::sync::Arc::new(BrowserLogin::new(
+        DefaultBrowserLoginBot::new("me@example.com", "hunter2"),
+        DefaultBrowserConfigBuilder::default(),
+    ));
+    assert_provider(&provider);
+    let _: &dyn LoginProvider = &provider;
+}
@@ -0,0 +1,133 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host, PageLoginBot};
+use pinterest_login::mock::{MockElement, MockPage};
+use pinterest_login::options::LoginOptions;
+use pinterest_login::outage::is_outage_page;
+use pinterest_login::replay::ReplayLauncher;
+use pinterest_login::retry::StepRetry;
+use pinterest_login::{login_with_options, LoginPhase, PinterestLoginError};
+
+const LOGIN_URL: &str = "https://www.pinterest.com/login/";
+const EMAIL: &str = "input#email";
+const PASSWORD: &str = "input#password";
+// Recorded from a login against a local fixture server that answered the login page with a 503, twice
+const SERVICE_UNAVAILABLE: &str = "tests/fixtures/replay/service-unavailable.json";
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(format!("tests/fixtures/outage/{name}.html")).unwrap()
+}
+
+fn bot() -> DefaultBrowserLoginBot {
+    DefaultBrowserLoginBot::new("me@example.com", "hunter2")
+}
+
+#[test]
+fn the_saved_pages_are_classified() {
+    // (fixture, is an outage)
+    let table = [
+        ("maintenance", true),
+        ("service-unavailable", true),
+        ("bad-gateway", true),
+        // The app carries the error messages in its scripts
+        ("loading", false),
+        ("login", false),
+        ("wrong-password", false),
+    ];
+    for (name, outage) in table {
+        assert_eq!(is_outage_page(&fixture(name)), outage, "{name}");
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn fill_fails_on_a_maintenance_page() {
+    let page = MockPage::new(LOGIN_URL).content(fixture("maintenance"));
+
+    let err = bot().fill_login_form(&page).await.unwrap_err();
+
+    assert!(
+        matches!(
+            err,
+            PinterestLoginError::ServiceUnavailable { retry_hint: None }
+        ),
+        "{err}"
+    );
+    assert_eq!(page.lookups(EMAIL), 1);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn fill_waits_for_a_slow_login_page() {
+    let page = MockPage::new(LOGIN_URL)
+        .content(fixture("loading"))
+        .element(EMAIL, MockElement::new("email").appears_after(3))
+        .element(PASSWORD, MockElement::new("password"));
+
+    bot().fill_login_form(&page).await.unwrap();
+
+    assert_eq!(page.lookups(EMAIL), 4);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn check_tells_a_maintenance_page_from_a_wrong_password() {
+    let page = MockPage::new(LOGIN_URL).content(fixture("service-unavailable"));
+    let err = bot().check_login(&page).await.unwrap_err();
+    assert!(
+        matches!(err, PinterestLoginError::ServiceUnavailable { .. }),
+        "{err}"
+    );
+
+    let page = MockPage::new(LOGIN_URL).content(fixture("wrong-password"));
+    let err = bot().check_login(&page).await.unwrap_err();
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_503_login_page_is_retried_and_reported_with_its_retry_after() {
+    let launcher = ReplayLauncher::load(SERVICE_UNAVAILABLE).unwrap();
+    let retries = Arc::new(Mutex::new(Vec::new()));
+    let options = LoginOptions::new()
+        .login_url("http://127.0.0.1:8080/login")
+        .launcher(launcher.clone())
+        .navigation_retry(StepRetry {
+            retries: 1,
+            delay: Duration::ZERO,
+        })
+        .on_retry({
+            let retries = Arc::clone(&retries);
+            move |notice| {
+                retries
+                    .lock()
+                    .unwrap()
+                    .push((notice.phase, notice.error.to_string()))
+            }
+        });
+    let bot = bot().with_login_host(Host::new("127.0.0.1:8080"));
+    let config_builder = DefaultBrowserConfigBuilder::default().with_executable("chrome");
+
+    let err = login_with_options(&bot, &config_builder, &options)
+        .await
+        .unwrap_err();
+    launcher.verify();
+
+    assert!(
+        matches!(
+            err,
+            PinterestLoginError::ServiceUnavailable { retry_hint: Some(hint) }
+                if hint == Duration::from_secs(120)
+        ),
+        "{err}"
+    );
+    assert_eq!(
+        *retries.lock().unwrap(),
+        [(LoginPhase::Navigation, err.to_string())]
+    );
+}
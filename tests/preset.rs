@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use pinterest_login::config_builder::{BrowserConfigBuilder, DefaultBrowserConfigBuilder};
+use pinterest_login::fingerprint::{TypingTimings, Viewport};
+use pinterest_login::options::{LoginOptions, DEFAULT_KEEP_ALIVE_INTERVAL};
+use pinterest_login::preset::{debug_screenshot_dir, stealthy_fingerprint, Preset};
+use pinterest_login::retry::StepRetry;
+
+const NO_RETRY: StepRetry = StepRetry {
+    retries: 0,
+    delay: Duration::ZERO,
+};
+
+fn secs(secs: u64) -> Option<Duration> {
+    Some(Duration::from_secs(secs))
+}
+
+#[test]
+fn the_config_builder_presets_are_locked_down() {
+    // (preset, the same config with the setters)
+    let table = [
+        (
+            Preset::Fast,
+            DefaultBrowserConfigBuilder::new(true, secs(3), secs(10)).with_images(false),
+        ),
+        (
+            Preset::Stealthy,
+            DefaultBrowserConfigBuilder::new(true, secs(10), secs(20))
+                .with_webrtc_protection(true)
+                .with_window_size(1920, 1080),
+        ),
+        (
+            Preset::Debug,
+            DefaultBrowserConfigBuilder::new(false, secs(30), secs(60)),
+        ),
+    ];
+    for (preset, expected) in table {
+        assert_eq!(
+            DefaultBrowserConfigBuilder::default().preset(preset),
+            expected,
+            "{preset:?}"
+        );
+    }
+}
+
+#[test]
+fn the_options_presets_are_locked_down() {
+    // (preset, the same options with the setters)
+    let table = [
+        (
+            Preset::Fast,
+            LoginOptions::new()
+                .launch_retry(NO_RETRY)
+                .navigation_retry(NO_RETRY),
+        ),
+        (
+            Preset::Stealthy,
+            LoginOptions::new().fingerprint(stealthy_fingerprint()),
+        ),
+        (
+            Preset::Debug,
+            LoginOptions::new()
+                .screenshot_on_failure(debug_screenshot_dir())
+                .keep_alive(DEFAULT_KEEP_ALIVE_INTERVAL),
+        ),
+    ];
+    for (preset, expected) in table {
+        assert_eq!(
+            format!("{:?}", LoginOptions::new().preset(preset)),
+            format!("{expected:?}"),
+            "{preset:?}"
+        );
+    }
+}
+
+#[test]
+fn the_stealthy_fingerprint_is_locked_down() {
+    let fingerprint = stealthy_fingerprint();
+
+    assert_eq!(
+        fingerprint.user_agent,
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36"
+    );
+    assert_eq!(
+        fingerprint.viewport,
+        Viewport {
+            width: 1920,
+            height: 1080
+        }
+    );
+    assert_eq!(
+        fingerprint.typing,
+        TypingTimings::new(Duration::from_millis(100), Duration::from_millis(40), 0)
+    );
+}
+
+#[test]
+fn the_setters_override_a_preset() {
+    let config_builder = DefaultBrowserConfigBuilder::default()
+        .preset(Preset::Debug)
+        .with_headless(true)
+        .with_request_timeout(None);
+
+    assert_eq!(
+        config_builder,
+        DefaultBrowserConfigBuilder::new(true, None, secs(60))
+    );
+
+    let options = LoginOptions::new()
+        .preset(Preset::Fast)
+        .launch_retry(StepRetry::default());
+    assert_eq!(
+        format!("{options:?}"),
+        format!("{:?}", LoginOptions::new().navigation_retry(NO_RETRY))
+    );
+}
+
+#[test]
+fn a_preset_overrides_the_setters_called_before_it() {
+    let config_builder = DefaultBrowserConfigBuilder::default()
+        .with_images(false)
+        .with_proxy("socks5://127.0.0.1:1080")
+        .preset(Preset::Stealthy);
+
+    // The proxy isn't part of the posture
+    assert_eq!(
+        config_builder,
+        DefaultBrowserConfigBuilder::default()
+            .preset(Preset::Stealthy)
+            .with_proxy("socks5://127.0.0.1:1080")
+    );
+}
+
+#[test]
+fn the_knobs_become_chrome_flags() {
+    let flags = |preset| {
+        let config = DefaultBrowserConfigBuilder::default()
+            .preset(preset)
+            .with_executable("/usr/bin/chromium")
+            .build_browser_config()
+            .unwrap();
+        format!("{config:?}")
+    };
+
+    let fast = flags(Preset::Fast);
+    assert!(
+        fast.contains("--blink-settings=imagesEnabled=false"),
+        "{fast}"
+    );
+    assert!(!fast.contains("webrtc"), "{fast}");
+
+    let stealthy = flags(Preset::Stealthy);
+    assert!(
+        stealthy.contains("--force-webrtc-ip-handling-policy=disable_non_proxied_udp"),
+        "{stealthy}"
+    );
+    assert!(stealthy.contains("(1920, 1080)"), "{stealthy}");
+    assert!(!stealthy.contains("imagesEnabled"), "{stealthy}");
+}
@@ -0,0 +1,137 @@
+//! The readiness strategies against a login page whose form is rendered by a script, after a slow request
+
+mod common;
+
+use std::time::Duration;
+
+use common::{FixtureServer, Response};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::options::{LoginOptions, ReadyWhen};
+use pinterest_login::report::ReadyReport;
+use pinterest_login::{login_with_options, PinterestCookies};
+
+/// How long the page waits for its config before rendering the form
+const CONFIG_DELAY: Duration = Duration::from_millis(1000);
+
+/// The login page of [`common::login_page`], rendered once `/config` answers
+fn scripted_login_page() -> String {
+    let form = common::login_page("/session").replace('`', "\\`");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+  <body>
+    <script>
+      fetch("/config").then(() => {{
+        document.open();
+        document.write(`{form}`);
+        document.close();
+      }});
+    </script>
+  </body>
+</html>"#
+    )
+}
+
+fn server(config_delay: Duration) -> FixtureServer {
+    FixtureServer::start(vec![
+        ("/login", Response::html(scripted_login_page())),
+        ("/config", Response::html("{}").delay(config_delay)),
+        (
+            "/session",
+            Response::redirect("/home").header("Set-Cookie", "_pinterest_sess=fixture; Path=/"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ])
+}
+
+async fn login(server: &FixtureServer, ready_when: ReadyWhen) -> (PinterestCookies, ReadyReport) {
+    let bot = DefaultBrowserLoginBot::new("email@example.com", "password")
+        .with_login_host(Host::new(server.host()));
+    let options = LoginOptions::new()
+        .login_url(server.url("/login"))
+        .ready_when(ready_when);
+    let outcome = login_with_options(&bot, &DefaultBrowserConfigBuilder::default(), &options)
+        .await
+        .expect("the login against the fixture server should succeed");
+    (outcome.cookies, outcome.report.ready)
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_default_strategy_does_not_wait_for_the_script() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = server(CONFIG_DELAY);
+
+    let (cookies, ready) = login(&server, ReadyWhen::default()).await;
+
+    // The bot still waits for the form itself
+    assert!(cookies.contains_key("_pinterest_sess"));
+    assert_eq!(ready.strategy, ReadyWhen::DomContentLoaded);
+    assert!(ready.waited < CONFIG_DELAY, "{ready:?}");
+    assert!(!ready.timed_out);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_network_idle_strategy_waits_for_the_slow_request() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = server(CONFIG_DELAY);
+    let strategy = ReadyWhen::NetworkIdle {
+        idle_for: Duration::from_millis(300),
+        max_wait: Duration::from_secs(10),
+    };
+
+    let (cookies, ready) = login(&server, strategy.clone()).await;
+
+    assert!(cookies.contains_key("_pinterest_sess"));
+    assert_eq!(ready.strategy, strategy);
+    assert!(ready.waited >= CONFIG_DELAY, "{ready:?}");
+    assert!(!ready.timed_out);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_network_idle_strategy_gives_up_after_its_maximum_wait() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = server(Duration::from_secs(3));
+    let max_wait = Duration::from_millis(500);
+
+    let (cookies, ready) = login(
+        &server,
+        ReadyWhen::NetworkIdle {
+            idle_for: Duration::from_millis(300),
+            max_wait,
+        },
+    )
+    .await;
+
+    // A timeout isn't a failure, the bot waits for the form
+    assert!(cookies.contains_key("_pinterest_sess"));
+    assert!(ready.timed_out);
+    assert!(ready.waited >= max_wait, "{ready:?}");
+    assert!(ready.waited < Duration::from_secs(3), "{ready:?}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_selector_strategy_waits_for_the_form() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = server(CONFIG_DELAY);
+    let strategy = ReadyWhen::Selector("input#email".to_string());
+
+    let (cookies, ready) = login(&server, strategy.clone()).await;
+
+    assert!(cookies.contains_key("_pinterest_sess"));
+    assert_eq!(ready.strategy, strategy);
+    assert!(ready.waited >= CONFIG_DELAY, "{ready:?}");
+    assert!(!ready.timed_out);
+}
@@ -0,0 +1,146 @@
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::options::{CookieAllowlist, LoginOptions, ReadyWhen};
+use pinterest_login::replay::{ReplayLauncher, SCRUBBED};
+use pinterest_login::selectors::FormField;
+use pinterest_login::{login_with_options, LoginPhase, PinterestLoginError};
+
+// The fixtures were recorded from a login against a local fixture server
+const LOGIN_URL: &str = "http://127.0.0.1:8080/login";
+const SUCCESS: &str = "tests/fixtures/replay/login-success.json";
+const WRONG_PASSWORD: &str = "tests/fixtures/replay/wrong-password.json";
+
+fn bot() -> DefaultBrowserLoginBot {
+    DefaultBrowserLoginBot::new("me@example.com", "hunter2")
+        .with_login_host(Host::new("127.0.0.1:8080"))
+}
+
+fn config_builder() -> DefaultBrowserConfigBuilder {
+    // Nothing is launched, the executable just has to be there to build the config
+    DefaultBrowserConfigBuilder::default().with_executable("chrome")
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_replayed_login_returns_the_recorded_cookies() {
+    let launcher = ReplayLauncher::load(SUCCESS).unwrap();
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher.clone());
+
+    let outcome = login_with_options(&bot(), &config_builder(), &options)
+        .await
+        .expect("the replayed login should succeed");
+    launcher.verify();
+
+    let mut names: Vec<_> = outcome.cookies.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["_pinterest_sess", "csrftoken"]);
+    assert!(outcome.cookies.values().all(|value| value == SCRUBBED));
+    assert_eq!(outcome.cookie_details.len(), outcome.cookies.len());
+    // The storages aren't read by default
+    assert_eq!(outcome.storage, None);
+    // The fixture server has the form of pinterest
+    let matched: Vec<_> = outcome
+        .report
+        .selectors
+        .iter()
+        .map(|matched| (matched.field, matched.position))
+        .collect();
+    assert_eq!(
+        matched,
+        [
+            (FormField::Email, 0),
+            (FormField::Password, 0),
+            (FormField::Submit, 0)
+        ]
+    );
+    // The default strategy sends nothing more than the recording
+    assert_eq!(outcome.report.ready.strategy, ReadyWhen::DomContentLoaded);
+    assert!(!outcome.report.ready.timed_out);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_replayed_wrong_password_is_an_authentication_error() {
+    let launcher = ReplayLauncher::load(WRONG_PASSWORD).unwrap();
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher.clone());
+
+    let err = login_with_options(&bot(), &config_builder(), &options)
+        .await
+        .unwrap_err();
+    launcher.verify();
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn an_unexpected_command_fails_the_replay() {
+    let launcher = ReplayLauncher::load(SUCCESS).unwrap();
+    let options = LoginOptions::new()
+        .login_url("http://127.0.0.1:8080/not-the-recorded-url")
+        .launcher(launcher.clone());
+
+    let err = login_with_options(&bot(), &config_builder(), &options)
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(
+            err,
+            PinterestLoginError::BrowserDisconnected {
+                during: LoginPhase::Navigation
+            }
+        ),
+        "{err}"
+    );
+    let failure = std::panic::catch_unwind(|| launcher.verify()).unwrap_err();
+    let failure = failure.downcast_ref::<String>().unwrap();
+    assert!(failure.contains("not-the-recorded-url"), "{failure}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_allowlist_filters_the_replayed_cookies() {
+    let launcher = ReplayLauncher::load(SUCCESS).unwrap();
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher.clone())
+        .cookie_allowlist(
+            CookieAllowlist::new()
+                .require("_pinterest_sess")
+                .allow("_routing_id"),
+        );
+
+    let outcome = login_with_options(&bot(), &config_builder(), &options)
+        .await
+        .expect("the replayed login should succeed");
+
+    assert_eq!(
+        outcome.cookies.keys().collect::<Vec<_>>(),
+        ["_pinterest_sess"]
+    );
+    assert_eq!(outcome.cookie_details.len(), 1);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_missing_required_cookie_fails_the_replayed_login() {
+    let launcher = ReplayLauncher::load(SUCCESS).unwrap();
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher.clone())
+        .cookie_allowlist(CookieAllowlist::pinterest().require("_auth"));
+
+    let err = login_with_options(&bot(), &config_builder(), &options)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, PinterestLoginError::MissingCookie(name) if name == "_auth"));
+}
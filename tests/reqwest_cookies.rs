@@ -0,0 +1,103 @@
+#![cfg(feature = "reqwest-cookies")]
+
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use pinterest_login::cookies::PinterestCookie;
+use pinterest_login::reqwest_cookies::{into_reqwest_jar, reqwest_jar, PINTEREST_URLS};
+use reqwest::cookie::{CookieStore, Jar};
+
+/// The sorted cookies that the jar sends to the url, `None` if there's none
+fn sent(jar: &Jar, url: &str) -> Option<Vec<String>> {
+    let header = jar.cookies(&url.parse().unwrap())?;
+    let mut cookies: Vec<_> = header
+        .to_str()
+        .unwrap()
+        .split("; ")
+        .map(str::to_string)
+        .collect();
+    cookies.sort();
+    Some(cookies)
+}
+
+#[test]
+fn the_map_is_sent_to_the_website_and_the_api() {
+    let cookies = [("_pinterest_sess", "sess"), ("csrftoken", "token")]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+    let jar = into_reqwest_jar(&cookies);
+
+    for url in PINTEREST_URLS {
+        assert_eq!(
+            sent(&jar, &format!("{url}resource/")).unwrap(),
+            ["_pinterest_sess=sess", "csrftoken=token"],
+            "{url}"
+        );
+    }
+    assert_eq!(sent(&jar, "http://www.pinterest.com/"), None);
+    assert_eq!(sent(&jar, "https://example.com/"), None);
+}
+
+#[test]
+fn the_full_cookies_keep_their_scope() {
+    let hour = Duration::from_secs(60 * 60);
+    let cookies = [
+        PinterestCookie {
+            secure: true,
+            http_only: true,
+            expires: Some(SystemTime::now() + hour),
+            ..PinterestCookie::new("_pinterest_sess", "sess", ".pinterest.com")
+        },
+        PinterestCookie::new("_routing_id", "id", "www.pinterest.com"),
+        PinterestCookie {
+            path: "/resource/".to_string(),
+            ..PinterestCookie::new("resource", "1", ".pinterest.com")
+        },
+        PinterestCookie {
+            expires: Some(SystemTime::now() - hour),
+            ..PinterestCookie::new("expired", "1", ".pinterest.com")
+        },
+    ];
+
+    let jar = reqwest_jar(&cookies);
+
+    assert_eq!(
+        sent(&jar, "https://www.pinterest.com/resource/UserResource/get/").unwrap(),
+        ["_pinterest_sess=sess", "_routing_id=id", "resource=1"]
+    );
+    assert_eq!(
+        sent(&jar, "https://api.pinterest.com/v3/").unwrap(),
+        ["_pinterest_sess=sess"]
+    );
+    assert_eq!(sent(&jar, "http://api.pinterest.com/v3/"), None);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn a_client_sends_the_cookies_of_the_jar() {
+    use std::sync::Arc;
+
+    use common::{FixtureServer, Response};
+
+    let server = FixtureServer::start(vec![(
+        "/me",
+        Response::html("anonymous")
+            .status(401)
+            .when_cookie("_pinterest_sess", Response::html("logged in")),
+    )]);
+    let host = server.host();
+    let ip = host.split(':').next().unwrap();
+    let jar = reqwest_jar(&[PinterestCookie::new("_pinterest_sess", "sess", ip)]);
+    let client = reqwest::Client::builder()
+        .cookie_provider(Arc::new(jar))
+        .build()
+        .unwrap();
+
+    let res = client.get(server.url("/me")).send().await.unwrap();
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.text().await.unwrap(), "logged in");
+}
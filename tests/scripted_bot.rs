@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use pinterest_login::clock::ManualClock;
+use pinterest_login::login_bot::PageLoginBot;
+use pinterest_login::mock::MockPage;
+use pinterest_login::scripted::{ScriptedLoginBot, Step};
+use pinterest_login::{LoginPhase, PinterestLoginError};
+
+fn page() -> MockPage {
+    MockPage::new("https://www.pinterest.com/login/")
+}
+
+fn authentication_error() -> Step {
+    Step::fail(|| PinterestLoginError::AuthenticationError)
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_phases_without_a_script_succeed() {
+    let bot = ScriptedLoginBot::new();
+    let page = page();
+
+    bot.fill_login_form(&page).await.unwrap();
+    bot.submit_login_form(&page).await.unwrap();
+    bot.check_login(&page).await.unwrap();
+
+    assert_eq!(
+        bot.calls(),
+        [
+            LoginPhase::FillForm,
+            LoginPhase::SubmitForm,
+            LoginPhase::CheckLogin
+        ]
+    );
+    assert!(page.actions().is_empty());
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn fail_twice_then_succeed() {
+    let bot = ScriptedLoginBot::new().script(
+        LoginPhase::SubmitForm,
+        [
+            authentication_error(),
+            authentication_error(),
+            Step::Succeed,
+        ],
+    );
+    let page = page();
+
+    let mut outcomes = Vec::new();
+    for _ in 0..4 {
+        outcomes.push(bot.submit_login_form(&page).await.is_ok());
+    }
+
+    assert_eq!(outcomes, [false, false, true, true]);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_last_step_repeats() {
+    let bot = ScriptedLoginBot::new().script(
+        LoginPhase::CheckLogin,
+        [
+            Step::Succeed,
+            Step::fail(|| PinterestLoginError::HandshakeTimeout {
+                executable: "chrome".into(),
+                timeout: Duration::from_secs(1),
+            }),
+        ],
+    );
+    let page = page();
+
+    bot.check_login(&page).await.unwrap();
+    for _ in 0..3 {
+        let err = bot.check_login(&page).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                PinterestLoginError::HandshakeTimeout { ref executable, timeout }
+                    if executable == "chrome" && timeout == Duration::from_secs(1)
+            ),
+            "{err}"
+        );
+    }
+    // The other phases aren't affected
+    bot.fill_login_form(&page).await.unwrap();
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_delay_waits_on_the_clock_then_succeeds() {
+    let clock = ManualClock::new();
+    let bot = ScriptedLoginBot::new()
+        .script(
+            LoginPhase::FillForm,
+            [Step::Delay(Duration::from_secs(90)), authentication_error()],
+        )
+        .with_clock(clock.clone());
+    let page = page();
+
+    clock.drive(bot.fill_login_form(&page)).await.unwrap();
+    assert!(clock.drive(bot.fill_login_form(&page)).await.is_err());
+
+    assert_eq!(clock.sleeps(), [Duration::from_secs(90)]);
+}
+
+#[test]
+#[should_panic(expected = "the navigation phase isn't run by the login bot")]
+fn only_the_bot_phases_can_be_scripted() {
+    let _ = ScriptedLoginBot::new().script(LoginPhase::Navigation, [Step::Succeed]);
+}
+
+#[test]
+fn it_plugs_into_the_login() {
+    fn assert_login_bot<B: pinterest_login::login_bot::BrowserLoginBot>(_bot: &B) {}
+    assert_login_bot(&ScriptedLoginBot::default());
+}
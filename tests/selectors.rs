@@ -0,0 +1,124 @@
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, PageLoginBot};
+use pinterest_login::mock::{MockAction, MockElement, MockPage};
+use pinterest_login::selectors::{FormField, LoginSelectors, Selector};
+use pinterest_login::PinterestLoginError;
+
+const LOGIN_URL: &str = "https://www.pinterest.com/login/";
+
+fn bot() -> DefaultBrowserLoginBot {
+    DefaultBrowserLoginBot::new("me@example.com", "hunter2")
+}
+
+/// The (field, selector, position) of the matches of the bot
+fn matches(bot: &DefaultBrowserLoginBot) -> Vec<(FormField, String, usize)> {
+    bot.matched_selectors()
+        .into_iter()
+        .map(|matched| {
+            (
+                matched.field,
+                matched.selector.to_string(),
+                matched.position,
+            )
+        })
+        .collect()
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_primary_selectors_are_reported() {
+    let page = MockPage::new(LOGIN_URL)
+        .element("input#email", MockElement::new("email"))
+        .element("input#password", MockElement::new("password"))
+        .element(
+            "//*[contains(text(), 'Log in')]",
+            MockElement::new("login").navigates_to("https://www.pinterest.com/"),
+        );
+    let bot = bot();
+
+    bot.fill_login_form(&page).await.unwrap();
+    bot.submit_login_form(&page).await.unwrap();
+
+    assert_eq!(
+        matches(&bot),
+        [
+            (FormField::Email, "input#email".into(), 0),
+            (FormField::Password, "input#password".into(), 0),
+            (
+                FormField::Submit,
+                "//*[contains(text(), 'Log in')]".into(),
+                0
+            ),
+        ]
+    );
+    assert!(bot.matched_selectors().iter().all(|m| !m.is_fallback()));
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_fallbacks_are_tried_in_order_when_the_primary_selectors_are_gone() {
+    // Only the later selectors of the default chains exist
+    let page = MockPage::new(LOGIN_URL)
+        .element("input[name='id']", MockElement::new("email"))
+        .element("input[type='password']", MockElement::new("password"))
+        .element(
+            "form button[type='submit']",
+            MockElement::new("login").navigates_to("https://www.pinterest.com/"),
+        );
+    let bot = bot();
+
+    bot.fill_login_form(&page).await.unwrap();
+    bot.submit_login_form(&page).await.unwrap();
+
+    assert_eq!(
+        matches(&bot),
+        [
+            (FormField::Email, "input[name='id']".into(), 2),
+            (FormField::Password, "input[type='password']".into(), 3),
+            (FormField::Submit, "form button[type='submit']".into(), 2),
+        ]
+    );
+    assert!(page.actions().contains(&MockAction::Typed {
+        element: "password".into(),
+        text: "hunter2".into()
+    }));
+    assert_eq!(
+        page.actions().last(),
+        Some(&MockAction::Clicked("login".into()))
+    );
+    // Every selector before the match was tried once
+    assert_eq!(page.lookups("input#email"), 1);
+    assert_eq!(page.lookups("[data-test-id='emailInputField'] input"), 1);
+    assert_eq!(page.lookups("input[type='email']"), 0);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_chains_can_be_replaced() {
+    let page = MockPage::new(LOGIN_URL)
+        .element("input#email", MockElement::new("email"))
+        .element("#login-email", MockElement::new("custom email"))
+        .element("input#password", MockElement::new("password"));
+    let bot =
+        bot().with_selectors(LoginSelectors::default().with_email([Selector::css("#login-email")]));
+
+    bot.fill_login_form(&page).await.unwrap();
+
+    assert_eq!(page.lookups("input#email"), 0);
+    assert_eq!(
+        matches(&bot)[0],
+        (FormField::Email, "#login-email".into(), 0)
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_field_that_no_selector_finds_fails_the_fill() {
+    let page = MockPage::new(LOGIN_URL).element("input#email", MockElement::new("email"));
+
+    let err = bot().fill_login_form(&page).await.unwrap_err();
+
+    assert!(matches!(err, PinterestLoginError::CdpError(_)), "{err}");
+    for selector in LoginSelectors::default().chain(FormField::Password) {
+        assert_eq!(page.lookups(&selector.to_string()), 1, "{selector}");
+    }
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "serde")]
+
+use std::time::{Duration, SystemTime};
+
+use pinterest_login::cookies::{PinterestCookie, SameSite};
+use pinterest_login::session::Session;
+use pinterest_login::PinterestCookies;
+use serde_json::json;
+
+fn at(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn round_trip<T>(value: &T) -> T
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+}
+
+#[test]
+fn the_cookies_round_trip() {
+    let values = [
+        "3f2a%3D%3D9c",
+        "%E2%9C%93 and %",
+        "a;b,c d",
+        "\"quoted\"",
+        "\\back\\slash",
+        "ünïcödé ✓",
+        "",
+    ];
+    for value in values {
+        let cookie = PinterestCookie {
+            expires: Some(SystemTime::UNIX_EPOCH + Duration::from_secs_f64(1798761600.25)),
+            secure: true,
+            same_site: Some(SameSite::None),
+            ..PinterestCookie::new("csrftoken", value, ".pinterest.com")
+        };
+
+        assert_eq!(round_trip(&cookie), cookie, "{value:?}");
+    }
+}
+
+#[test]
+fn the_cookie_map_is_a_sorted_object() {
+    let cookies = PinterestCookies::from([
+        ("csrftoken".to_string(), "token".to_string()),
+        ("_pinterest_sess".to_string(), "sess".to_string()),
+    ]);
+
+    assert_eq!(
+        serde_json::to_string(&cookies).unwrap(),
+        r#"{"_pinterest_sess":"sess","csrftoken":"token"}"#
+    );
+    assert_eq!(round_trip(&cookies), cookies);
+    assert!(serde_json::from_value::<PinterestCookies>(json!({"csrftoken": 1})).is_err());
+}
+
+#[test]
+fn the_expiry_is_in_seconds_since_the_unix_epoch() {
+    let cookie = PinterestCookie {
+        expires: Some(at(1767225600)),
+        http_only: true,
+        same_site: Some(SameSite::Lax),
+        ..PinterestCookie::new("_pinterest_sess", "sess", ".pinterest.com")
+    };
+
+    assert_eq!(
+        serde_json::to_value(&cookie).unwrap(),
+        json!({
+            "name": "_pinterest_sess", "value": "sess", "domain": ".pinterest.com", "path": "/",
+            "expires": 1767225600.0, "secure": false, "http_only": true, "same_site": "Lax"
+        })
+    );
+}
+
+#[test]
+fn a_session_cookie_has_a_null_expiry() {
+    let cookie = PinterestCookie::new("_routing_id", "id", "www.pinterest.com");
+
+    let json = serde_json::to_value(&cookie).unwrap();
+
+    assert_eq!(json["expires"], json!(null));
+    assert_eq!(json["same_site"], json!(null));
+    assert_eq!(round_trip(&cookie), cookie);
+}
+
+#[test]
+fn an_invalid_expiry_is_an_error() {
+    let mut json = serde_json::to_value(PinterestCookie::new("a", "b", "c")).unwrap();
+    json["expires"] = json!(-1);
+
+    let err = serde_json::from_value::<PinterestCookie>(json).unwrap_err();
+
+    assert!(err.to_string().contains("invalid expiry -1"), "{err}");
+}
+
+#[test]
+fn the_sessions_round_trip_as_their_file() {
+    let cookies = [
+        ("csrftoken".to_string(), "3f2a%3D%3D9c%".to_string()),
+        (
+            "_pinterest_sess".to_string(),
+            "TWc9PSZs;1XZ3 Y2dm==".to_string(),
+        ),
+    ]
+    .into();
+    let session = Session::new("me@example.com", cookies)
+        .with_created_at(at(1700000000))
+        .with_expires_at(at(1767225600))
+        .with_user_agent("Mozilla/5.0");
+
+    assert_eq!(serde_json::to_value(&session).unwrap(), session.to_json());
+    assert_eq!(round_trip(&session), session);
+}
+
+#[test]
+fn an_old_session_is_migrated_when_it_is_deserialized() {
+    let json = json!({
+        "version": 1, "account": "me@example.com", "created_at": 1700000000, "user_agent": null,
+        "login_url": "https://www.pinterest.com/login/", "cookies": {"csrftoken": "token"}
+    });
+
+    let session: Session = serde_json::from_value(json).unwrap();
+
+    assert_eq!(session.cookies["csrftoken"], "token");
+    assert_eq!(session.expires_at, None);
+}
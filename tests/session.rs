@@ -0,0 +1,638 @@
+mod common;
+
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+use futures::executor::block_on;
+use futures::future::{self, Either};
+use futures::{Stream, StreamExt};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::cookies::PinterestCookie;
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::options::LoginOptions;
+use pinterest_login::session::{
+    login_or_load, Expiry, FileSessionStore, InMemorySessionStore, Session, SessionError,
+    SessionStore, WatchMode, CSRF_HEADER, DEFAULT_EXPIRY_MARGIN, MIN_SESSION_VERSION,
+    SESSION_VERSION,
+};
+use pinterest_login::PinterestCookies;
+use serde_json::json;
+
+fn cookies() -> PinterestCookies {
+    [
+        ("_pinterest_sess", "session"),
+        ("csrftoken", "it's \"quoted\""),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+fn at(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[test]
+fn a_saved_session_loads_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sessions").join("me.json");
+    let sessions = [
+        Session::new("me@example.com", cookies()).with_created_at(at(1_700_000_000)),
+        Session::new("ünïcödé@example.com", PinterestCookies::new())
+            .with_created_at(at(0))
+            .with_user_agent("Mozilla/5.0 (X11; Linux x86_64)")
+            .with_expires_at(at(1_800_000_000))
+            .with_login_url("http://127.0.0.1:8080/login"),
+    ];
+
+    for session in sessions {
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path).unwrap(), session);
+    }
+}
+
+#[test]
+fn the_sub_seconds_are_not_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.json");
+    let session = Session::new("me@example.com", cookies())
+        .with_created_at(at(10) + Duration::from_millis(999));
+
+    session.save(&path).unwrap();
+
+    assert_eq!(Session::load(&path).unwrap().created_at, at(10));
+}
+
+#[test]
+fn the_file_is_versioned_json() {
+    let session = Session::new("me@example.com", cookies()).with_created_at(at(1_700_000_000));
+
+    assert_eq!(
+        session.to_json(),
+        json!({
+            "format_version": SESSION_VERSION,
+            "account": "me@example.com",
+            "created_at": 1_700_000_000,
+            "expires_at": null,
+            "user_agent": null,
+            "login_url": "https://pinterest.com/login",
+            "cookies": {"_pinterest_sess": "session", "csrftoken": "it's \"quoted\""},
+        })
+    );
+}
+
+/// The files written by the previous versions, they must never be regenerated: they're what the users have on disk
+const V1: &str = "tests/fixtures/session/v1.json";
+const V1_EXPIRES_AT: &str = "tests/fixtures/session/v1-expires-at.json";
+const V2: &str = "tests/fixtures/session/v2.json";
+
+fn fixture_cookies() -> PinterestCookies {
+    [("_pinterest_sess", "session"), ("csrftoken", "token")]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn the_files_of_the_previous_versions_are_migrated() {
+    let v1 = Session::new("me@example.com", fixture_cookies()).with_created_at(at(1_700_000_000));
+    let v1_expires_at = Session::new("me@example.com", fixture_cookies())
+        .with_created_at(at(1_700_000_000))
+        .with_expires_at(at(1_731_536_000))
+        .with_user_agent("Mozilla/5.0 (X11; Linux x86_64)")
+        .with_login_url("http://127.0.0.1:8080/login");
+
+    assert_eq!(Session::load(V1).unwrap(), v1);
+    assert_eq!(Session::load(V1_EXPIRES_AT).unwrap(), v1_expires_at);
+    assert_eq!(Session::load(V2).unwrap(), v1_expires_at);
+}
+
+#[test]
+fn the_files_of_the_previous_versions_can_be_upgraded() {
+    let dir = tempfile::tempdir().unwrap();
+    for fixture in [V1, V1_EXPIRES_AT] {
+        let path = dir.path().join("session.json");
+        fs::copy(fixture, &path).unwrap();
+
+        let session = Session::load_and_upgrade(&path).unwrap();
+
+        assert_eq!(session, Session::load(fixture).unwrap());
+        let upgraded: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(upgraded, session.to_json());
+        assert_eq!(upgraded["format_version"], SESSION_VERSION);
+        assert_eq!(upgraded.get("version"), None);
+    }
+}
+
+#[test]
+fn the_current_files_are_not_rewritten() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.json");
+    // Not the way it would be saved, so a rewrite would be noticed
+    let contents = Session::new("me@example.com", cookies())
+        .to_json()
+        .to_string();
+    fs::write(&path, &contents).unwrap();
+
+    Session::load_and_upgrade(&path).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+}
+
+#[test]
+fn expiry() {
+    const HOUR: u64 = 60 * 60;
+    const DAY: u64 = 24 * HOUR;
+    let created = 1_000 * DAY;
+    let max_age = Duration::from_secs(7 * DAY);
+    let margin = Duration::from_secs(DAY);
+
+    // (the cookies expiry, now, expected)
+    for (expires_at, now, expected) in [
+        // By the cookies expiry
+        (Some(created + 10 * DAY), created, Expiry::Fresh),
+        (
+            Some(created + 10 * DAY),
+            created + 9 * DAY - 1,
+            Expiry::Fresh,
+        ),
+        (
+            Some(created + 10 * DAY),
+            created + 9 * DAY,
+            Expiry::ExpiringSoon,
+        ),
+        (
+            Some(created + 10 * DAY),
+            created + 10 * DAY - 1,
+            Expiry::ExpiringSoon,
+        ),
+        (
+            Some(created + 10 * DAY),
+            created + 10 * DAY,
+            Expiry::Expired,
+        ),
+        (
+            Some(created + 10 * DAY),
+            created + 20 * DAY,
+            Expiry::Expired,
+        ),
+        // The cookies expiry wins over the max age
+        (Some(created + HOUR), created, Expiry::ExpiringSoon),
+        (Some(created + 100 * DAY), created + 50 * DAY, Expiry::Fresh),
+        (Some(created - DAY), created, Expiry::Expired),
+        // By the age
+        (None, created, Expiry::Fresh),
+        (None, created + 6 * DAY - 1, Expiry::Fresh),
+        (None, created + 6 * DAY, Expiry::ExpiringSoon),
+        (None, created + 7 * DAY, Expiry::Expired),
+        // A clock that went back
+        (None, created - DAY, Expiry::Fresh),
+    ] {
+        let mut session = Session::new("me@example.com", cookies()).with_created_at(at(created));
+        session.expires_at = expires_at.map(at);
+
+        assert_eq!(
+            session.is_expired_with_max_age(at(now), margin, max_age),
+            expected,
+            "{expires_at:?} at {now}"
+        );
+    }
+}
+
+/// The cookies of a login, the auth ones expire at the given times
+fn full_cookies(session: Option<SystemTime>, csrf: Option<SystemTime>) -> Vec<PinterestCookie> {
+    let cookie = |name, expires| PinterestCookie {
+        expires,
+        ..PinterestCookie::new(name, "value", ".pinterest.com")
+    };
+    vec![
+        cookie("_pinterest_sess", session),
+        cookie("csrftoken", csrf),
+        // Not an auth cookie
+        cookie("_routing_id", Some(at(1))),
+    ]
+}
+
+#[test]
+fn the_expiry_is_the_earliest_of_the_auth_cookies() {
+    let now = SystemTime::now();
+    let hour = Duration::from_secs(60 * 60);
+    let margin = Duration::from_secs(24 * 60 * 60);
+    // (session cookie expiry, csrf token expiry, the session expiry, needs a refresh)
+    let table = [
+        // Expired
+        (
+            Some(now - hour),
+            Some(now + 365 * margin),
+            Some(now - hour),
+            true,
+        ),
+        // Expiring soon
+        (
+            Some(now + 365 * margin),
+            Some(now + hour),
+            Some(now + hour),
+            true,
+        ),
+        (
+            Some(now + 365 * margin),
+            Some(now + 2 * margin),
+            Some(now + 2 * margin),
+            false,
+        ),
+        // No expiry, so it's not known
+        (None, Some(now + 365 * margin), None, false),
+    ];
+    for (session_expiry, csrf_expiry, expires_at, needs_refresh) in table {
+        let session = Session::from_full_cookies(
+            "me@example.com",
+            &full_cookies(session_expiry, csrf_expiry),
+        );
+
+        assert_eq!(
+            session.expires_at(),
+            expires_at,
+            "{session_expiry:?} {csrf_expiry:?}"
+        );
+        assert_eq!(
+            session.needs_refresh(margin),
+            needs_refresh,
+            "{session_expiry:?} {csrf_expiry:?}"
+        );
+        assert_eq!(session.cookies().len(), 3);
+    }
+}
+
+#[test]
+fn an_unknown_expiry_is_not_forever() {
+    let session = Session::from_full_cookies("me@example.com", &full_cookies(None, None))
+        .with_created_at(SystemTime::now() - Duration::from_secs(31 * 24 * 60 * 60));
+
+    assert_eq!(session.expires_at(), None);
+    assert!(session.needs_refresh(Duration::ZERO));
+    assert_eq!(
+        Session::from_full_cookies("me@example.com", &[]).expires_at(),
+        None
+    );
+}
+
+#[test]
+fn the_default_max_age_is_30_days() {
+    let session = Session::new("me@example.com", cookies()).with_created_at(at(0));
+    const DAY: u64 = 24 * 60 * 60;
+
+    assert_eq!(
+        session.is_expired(at(29 * DAY), Duration::ZERO),
+        Expiry::Fresh
+    );
+    assert_eq!(
+        session.is_expired(at(30 * DAY), Duration::ZERO),
+        Expiry::Expired
+    );
+    assert_eq!(
+        session.is_expired(at(29 * DAY), DEFAULT_EXPIRY_MARGIN),
+        Expiry::ExpiringSoon
+    );
+    // A margin that overflows the time
+    assert_eq!(
+        session.is_expired(at(0), Duration::MAX),
+        Expiry::ExpiringSoon
+    );
+}
+
+#[test]
+fn the_session_exposes_its_auth_cookies() {
+    let session = Session::new("me@example.com", cookies());
+
+    assert_eq!(session.session_cookie().unwrap(), "session");
+    assert_eq!(session.csrf_token().unwrap(), "it's \"quoted\"");
+    assert_eq!(
+        session.csrf_header().unwrap(),
+        (CSRF_HEADER, "it's \"quoted\"")
+    );
+    assert_eq!(session.cookies(), &cookies());
+}
+
+#[test]
+fn a_missing_auth_cookie_is_a_typed_error() {
+    let session = Session::new("me@example.com", PinterestCookies::new());
+
+    for (result, name) in [
+        (session.session_cookie(), "_pinterest_sess"),
+        (session.csrf_token(), "csrftoken"),
+        (session.csrf_header().map(|(_, token)| token), "csrftoken"),
+    ] {
+        match result {
+            Err(err @ SessionError::MissingCookie(missing)) => {
+                assert_eq!(missing, name);
+                assert!(err.to_string().contains(name), "{err}");
+            }
+            other => panic!("{name}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn a_corrupted_file_is_a_typed_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.json");
+    let valid = Session::new("me@example.com", cookies()).to_json();
+    let with = |field: &str, value: serde_json::Value| {
+        let mut json = valid.clone();
+        json[field] = value;
+        json.to_string()
+    };
+
+    assert!(matches!(
+        Session::load(dir.path().join("missing.json")),
+        Err(SessionError::Io(_))
+    ));
+    fs::write(&path, "{not json").unwrap();
+    assert!(matches!(Session::load(&path), Err(SessionError::Json(_))));
+
+    let newer = SESSION_VERSION + 1;
+    for (contents, version) in [
+        (with("format_version", json!(newer)), Some(newer)),
+        (with("format_version", json!(0)), Some(0)),
+        (with("format_version", json!("2")), None),
+        (r#"{"version": 0, "cookies": {}}"#.to_string(), Some(0)),
+        (r#"{"cookies": {}}"#.to_string(), None),
+    ] {
+        fs::write(&path, contents).unwrap();
+        match Session::load(&path) {
+            Err(SessionError::UnsupportedVersion { found, supported }) => {
+                assert_eq!(found, version);
+                assert_eq!(supported, MIN_SESSION_VERSION..=SESSION_VERSION);
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+
+    for (contents, invalid) in [
+        (with("account", json!(null)), "account"),
+        (with("created_at", json!(-1)), "created_at"),
+        (with("created_at", json!("yesterday")), "created_at"),
+        (with("expires_at", json!(1.5)), "expires_at"),
+        (
+            {
+                let mut json = valid.clone();
+                json.as_object_mut().unwrap().remove("expires_at");
+                json.to_string()
+            },
+            "expires_at",
+        ),
+        (with("user_agent", json!(42)), "user_agent"),
+        (with("login_url", json!([])), "login_url"),
+        (with("cookies", json!([])), "cookies"),
+        (with("cookies", json!({"csrftoken": 1})), "cookies"),
+    ] {
+        fs::write(&path, contents).unwrap();
+        match Session::load(&path) {
+            Err(SessionError::InvalidField { field, .. }) => assert_eq!(field, invalid),
+            other => panic!("{invalid}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn the_in_memory_store_conforms() {
+    block_on(common::session_store_conformance(
+        &InMemorySessionStore::new(),
+    ));
+}
+
+#[test]
+fn the_file_store_conforms() {
+    let dir = tempfile::tempdir().unwrap();
+    block_on(common::session_store_conformance(&FileSessionStore::new(
+        dir.path().join("sessions"),
+    )));
+}
+
+#[test]
+fn the_file_store_keeps_a_file_per_account_in_its_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FileSessionStore::new(dir.path());
+    let session = Session::new("../me@example.com", cookies()).with_created_at(at(10));
+
+    block_on(store.put(&session)).unwrap();
+    fs::write(dir.path().join("notes.txt"), "not a session").unwrap();
+    fs::create_dir(dir.path().join("directory.json")).unwrap();
+
+    let path = store.path("../me@example.com");
+    assert_eq!(path, dir.path().join("%2E.%2Fme@example.com.json"));
+    assert_eq!(Session::load(&path).unwrap(), session);
+    assert_eq!(block_on(store.list()).unwrap(), ["../me@example.com"]);
+
+    fs::write(&path, "{not json").unwrap();
+    assert!(matches!(
+        block_on(store.get("../me@example.com")),
+        Err(SessionError::Json(_))
+    ));
+}
+
+#[test]
+fn login_or_load_returns_the_stored_session() {
+    let store = InMemorySessionStore::new();
+    let session = Session::new("me@example.com", cookies())
+        .with_expires_at(SystemTime::now() + DEFAULT_EXPIRY_MARGIN * 2);
+    block_on(store.put(&session)).unwrap();
+    // It would fail to launch the browser if it tried to log in
+    let config_builder =
+        DefaultBrowserConfigBuilder::default().with_executable("/nonexistent/chrome");
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "password");
+
+    let loaded = block_on(login_or_load(
+        &store,
+        "me@example.com",
+        &bot,
+        &config_builder,
+        &LoginOptions::new(),
+    ));
+
+    assert_eq!(loaded.unwrap(), session);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn login_or_load_logs_in_again_when_the_session_expires_soon() {
+    let store = InMemorySessionStore::new();
+    let session = Session::new("me@example.com", cookies())
+        .with_expires_at(SystemTime::now() + DEFAULT_EXPIRY_MARGIN / 2);
+    store.put(&session).await.unwrap();
+    let config_builder =
+        DefaultBrowserConfigBuilder::default().with_executable("/nonexistent/chrome");
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "password");
+
+    let loaded = login_or_load(
+        &store,
+        "me@example.com",
+        &bot,
+        &config_builder,
+        &LoginOptions::new(),
+    )
+    .await;
+
+    assert!(matches!(loaded, Err(SessionError::Login(_))), "{loaded:?}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn login_or_load_stores_a_new_login() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::FixtureServer::start(vec![
+        (
+            "/login",
+            common::Response::html(common::login_page("/home")),
+        ),
+        (
+            "/home",
+            common::Response::html("<p>Welcome</p>")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/"),
+        ),
+    ]);
+    let store = InMemorySessionStore::new();
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "password");
+    let config_builder = DefaultBrowserConfigBuilder::default();
+
+    let session = login_or_load(&store, "me@example.com", &bot, &config_builder, &options)
+        .await
+        .unwrap();
+
+    assert_eq!(session.cookies["_pinterest_sess"], "fixture");
+    assert_eq!(session.login_url, server.url("/login"));
+    assert_eq!(store.get("me@example.com").await.unwrap(), Some(session));
+}
+
+/// The next session of the watch, it fails after a few seconds
+async fn next_session(sessions: &mut (impl Stream<Item = Session> + Unpin)) -> Session {
+    let timeout = Box::pin(common::sleep(Duration::from_secs(5)));
+    match future::select(sessions.next(), timeout).await {
+        Either::Left((Some(session), _)) => session,
+        _ => panic!("the watch didn't see the change"),
+    }
+}
+
+async fn assert_no_session(sessions: &mut (impl Stream<Item = Session> + Unpin)) {
+    let timeout = Box::pin(common::sleep(Duration::from_millis(200)));
+    if let Either::Left((session, _)) = future::select(sessions.next(), timeout).await {
+        panic!("unexpected session {session:?}");
+    }
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_watch_follows_the_rewrites_of_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FileSessionStore::new(dir.path())
+        .with_watch_mode(WatchMode::Poll(Duration::from_millis(10)));
+    let first = Session::new("me@example.com", cookies()).with_created_at(at(1_700_000_000));
+    store.put(&first).await.unwrap();
+    let mut sessions = store.watch("me@example.com");
+
+    assert_eq!(next_session(&mut sessions).await, first);
+
+    // Another process logs in again
+    let second = first.clone().with_created_at(at(1_700_000_100));
+    FileSessionStore::new(dir.path())
+        .put(&second)
+        .await
+        .unwrap();
+    assert_eq!(next_session(&mut sessions).await, second);
+    assert_eq!(
+        store.get("me@example.com").await.unwrap(),
+        Some(second.clone())
+    );
+
+    // The same session again, a removed file and a partial write aren't sessions
+    store.put(&second).await.unwrap();
+    assert_no_session(&mut sessions).await;
+    fs::remove_file(store.path("me@example.com")).unwrap();
+    assert_no_session(&mut sessions).await;
+    fs::write(
+        store.path("me@example.com"),
+        r#"{"format_version": 2, "account": "me@exa"#,
+    )
+    .unwrap();
+    assert_no_session(&mut sessions).await;
+
+    let third = first.clone().with_created_at(at(1_700_000_200));
+    store.put(&third).await.unwrap();
+    assert_eq!(next_session(&mut sessions).await, third);
+}
+
+#[cfg(feature = "notify")]
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_watch_sees_the_file_system_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FileSessionStore::new(dir.path()).with_watch_mode(WatchMode::Notify);
+    let mut sessions = store.watch("me@example.com");
+
+    // There's no session before the first login
+    assert_no_session(&mut sessions).await;
+
+    let first = Session::new("me@example.com", cookies()).with_created_at(at(1_700_000_000));
+    store.put(&first).await.unwrap();
+    assert_eq!(next_session(&mut sessions).await, first);
+
+    let second = first.clone().with_created_at(at(1_700_000_100));
+    store.put(&second).await.unwrap();
+    assert_eq!(next_session(&mut sessions).await, second);
+}
+
+#[test]
+fn the_curl_command_quotes_the_values() {
+    let cookies = [
+        ("_pinterest_sess".to_string(), "it's;sess".to_string()),
+        ("csrftoken".to_string(), "token".to_string()),
+    ]
+    .into();
+    let session =
+        Session::new("me@example.com", cookies).with_user_agent("Mozilla/5.0 (X11; Linux)");
+
+    assert_eq!(
+        session.to_curl("https://www.pinterest.com/?a=1&b=2"),
+        r"curl 'https://www.pinterest.com/?a=1&b=2' -H 'Cookie: _pinterest_sess=it'\''s;sess; csrftoken=token' -H 'X-CSRFToken: token' --user-agent 'Mozilla/5.0 (X11; Linux)'"
+    );
+    // Without a CSRF token nor a user agent
+    let session = Session::new(
+        "me@example.com",
+        [("_auth".to_string(), "1".to_string())].into(),
+    );
+    assert_eq!(
+        session.to_curl("https://www.pinterest.com/"),
+        "curl 'https://www.pinterest.com/' -H 'Cookie: _auth=1'"
+    );
+}
+
+/// The arguments of the command come back as they were from a real shell, without running curl
+#[cfg(unix)]
+#[test]
+fn the_curl_arguments_survive_the_shell() {
+    let cookies = [(
+        "csrftoken".to_string(),
+        r#"'; touch pwned; "$(id)""#.to_string(),
+    )]
+    .into();
+    let session = Session::new("me@example.com", cookies).with_user_agent("it's `me`");
+
+    let command = session.to_curl("https://www.pinterest.com/");
+    let args = command.strip_prefix("curl ").unwrap();
+    let output = std::process::Command::new("sh")
+        .args(["-c", &format!("printf '%s\\n' {args}")])
+        .current_dir(std::env::temp_dir())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "https://www.pinterest.com/\n-H\nCookie: csrftoken='; touch pwned; \"$(id)\"\n\
+         -H\nX-CSRFToken: '; touch pwned; \"$(id)\"\n--user-agent\nit's `me`\n"
+    );
+}
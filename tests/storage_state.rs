@@ -0,0 +1,136 @@
+use std::time::{Duration, SystemTime};
+
+use pinterest_login::cookies::{PinterestCookie, SameSite};
+use pinterest_login::session::Session;
+use pinterest_login::storage_state::{
+    export, export_cookies, import, import_cookies, StorageStateError,
+};
+use serde_json::Value;
+
+fn fixture() -> String {
+    std::fs::read_to_string("tests/fixtures/storage_state/storage_state.json").unwrap()
+}
+
+fn at(secs: f64) -> Option<SystemTime> {
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs_f64(secs))
+}
+
+#[test]
+fn the_cookies_of_playwright_round_trip() {
+    let cookies = import_cookies(&fixture()).unwrap();
+
+    assert_eq!(cookies.len(), 6);
+    assert_eq!(
+        cookies[5],
+        PinterestCookie {
+            expires: at(1798761600.25),
+            secure: true,
+            same_site: Some(SameSite::Lax),
+            ..PinterestCookie::new("csrftoken", "3f2a9c1d7e8b4a6f", ".pinterest.com")
+        }
+    );
+    assert!(cookies[3].is_session());
+
+    let exported: Value = serde_json::from_str(&export_cookies(&cookies, None)).unwrap();
+    let captured: Value = serde_json::from_str(&fixture()).unwrap();
+    assert_eq!(exported["cookies"], captured["cookies"]);
+    assert_eq!(exported["origins"], serde_json::json!([]));
+}
+
+#[test]
+fn the_session_keeps_the_pinterest_cookies() {
+    let session = import("me@example.com", &fixture()).unwrap();
+
+    assert_eq!(session.account, "me@example.com");
+    assert_eq!(session.cookies.len(), 5);
+    assert!(!session.cookies.contains("NID"));
+    assert_eq!(session.cookies.get_csrf(), Some("3f2a9c1d7e8b4a6f"));
+    // The earliest of `_auth`, `_pinterest_sess` and `csrftoken`
+    assert_eq!(session.expires_at, at(1798761600.25));
+}
+
+#[test]
+fn an_exported_session_imports_back() {
+    let cookies = [
+        ("_pinterest_sess", "TWc9PSZs"),
+        ("csrftoken", "token"),
+        ("_routing_id", "\"b6c4\""),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect();
+    let expires_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_798_761_600);
+    let session = Session::new("me@example.com", cookies).with_expires_at(expires_at);
+
+    let exported = export(&session);
+    let state: Value = serde_json::from_str(&exported).unwrap();
+    let by_name = |name: &str| {
+        state["cookies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|cookie| cookie["name"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(by_name("csrftoken")["expires"], 1_798_761_600);
+    assert_eq!(by_name("_routing_id")["expires"], -1);
+    assert_eq!(by_name("_pinterest_sess")["sameSite"], "Lax");
+    assert!(exported.ends_with('\n'));
+
+    let imported = import("me@example.com", &exported).unwrap();
+    assert_eq!(imported.cookies, session.cookies);
+    assert_eq!(imported.expires_at, Some(expires_at));
+}
+
+#[test]
+fn the_same_site_spellings_are_understood() {
+    let cookies = import_cookies(
+        r#"{"cookies": [
+            {"name": "a", "value": "1", "domain": "pinterest.com", "sameSite": "no_restriction"},
+            {"name": "b", "value": "2", "domain": "pinterest.com", "sameSite": "unspecified"},
+            {"name": "c", "value": "3", "domain": "pinterest.com", "sameSite": "strict", "expires": 0},
+            {"name": "d", "value": "4", "domain": "pinterest.com"}
+        ]}"#,
+    )
+    .unwrap();
+
+    let same_sites: Vec<_> = cookies.iter().map(|cookie| cookie.same_site).collect();
+    assert_eq!(
+        same_sites,
+        [Some(SameSite::None), None, Some(SameSite::Strict), None]
+    );
+    assert!(cookies.iter().all(PinterestCookie::is_session));
+    assert!(cookies.iter().all(|cookie| cookie.path == "/"));
+}
+
+#[test]
+fn an_invalid_storage_state_is_a_typed_error() {
+    for (contents, expected) in [
+        ("{", "isn't valid JSON"),
+        (r#"{"origins": []}"#, "no `cookies` array"),
+        (
+            r#"{"cookies": [{"name": "a", "value": "1"}]}"#,
+            "cookie 0 of the storage state has an invalid `domain`",
+        ),
+        (
+            r#"{"cookies": [{"name": "a", "value": "1", "domain": "d", "expires": "never"}]}"#,
+            "invalid `expires`",
+        ),
+        (
+            r#"{"cookies": [{"name": "a", "value": "1", "domain": "d", "sameSite": "Loose"}]}"#,
+            "invalid `sameSite`",
+        ),
+        (
+            r#"{"cookies": [{"name": "a", "value": "1", "domain": "d", "secure": "yes"}]}"#,
+            "invalid `secure`",
+        ),
+    ] {
+        let err = import_cookies(contents).unwrap_err();
+        assert!(err.to_string().contains(expected), "{contents}: {err}");
+    }
+    assert!(matches!(
+        import("me@example.com", "[]"),
+        Err(StorageStateError::MissingCookies)
+    ));
+}
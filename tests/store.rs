@@ -0,0 +1,285 @@
+mod common;
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures::executor::block_on;
+use futures::{future::BoxFuture, FutureExt};
+use pinterest_login::store::{
+    load_json, save_json, write_atomic, CookieStore, JsonFileStore, MemoryStore, StoreError,
+};
+use pinterest_login::PinterestCookies;
+
+/// The names of the files in the directory, sorted
+fn files(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+#[cfg(unix)]
+#[test]
+fn the_file_is_only_readable_by_its_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cookies.json");
+    // An existing file with loose permissions gets replaced by a private one
+    fs::write(&path, "old").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+    write_atomic(&path, b"new", false).unwrap();
+
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600, "{mode:o}");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+}
+
+#[test]
+fn no_temporary_file_is_left_behind() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cookies.txt");
+
+    write_atomic(&path, b"first", false).unwrap();
+    write_atomic(&path, b"second", false).unwrap();
+
+    assert_eq!(files(dir.path()), ["cookies.txt"]);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+}
+
+#[test]
+fn the_parent_directories_are_only_created_on_demand() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a").join("b").join("cookies.json");
+
+    let err = write_atomic(&path, b"{}", false).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(files(dir.path()).is_empty());
+
+    write_atomic(&path, b"{}", true).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+}
+
+#[test]
+fn a_failed_write_leaves_the_target_untouched() {
+    let dir = tempfile::tempdir().unwrap();
+    // A directory can't be replaced by a file
+    let target = dir.path().join("cookies");
+    fs::create_dir(&target).unwrap();
+    fs::write(target.join("keep"), "kept").unwrap();
+
+    assert!(write_atomic(&target, b"new", false).is_err());
+
+    assert_eq!(files(dir.path()), ["cookies"]);
+    assert_eq!(fs::read_to_string(target.join("keep")).unwrap(), "kept");
+}
+
+#[test]
+fn a_reader_never_sees_a_partial_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cookies.txt");
+    let contents: Vec<Vec<u8>> = (b'a'..=b'e').map(|c| vec![c; 256 * 1024]).collect();
+    write_atomic(&path, &contents[0], false).unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let (path, done, contents) = (path.clone(), Arc::clone(&done), contents.clone());
+        std::thread::spawn(move || {
+            let mut reads = 0;
+            while !done.load(Ordering::SeqCst) {
+                let read = fs::read(&path).unwrap();
+                assert!(contents.contains(&read), "read a partial file");
+                reads += 1;
+            }
+            reads
+        })
+    };
+    for round in 0..20 {
+        write_atomic(&path, &contents[round % contents.len()], false).unwrap();
+    }
+    done.store(true, Ordering::SeqCst);
+
+    assert!(reader.join().unwrap() > 0);
+}
+
+fn cookies() -> PinterestCookies {
+    [
+        ("_pinterest_sess", "TWc9PSZs%3D"),
+        ("csrftoken", "it's \"quoted\""),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+#[test]
+fn the_json_cookies_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a").join("b").join("cookies.json");
+
+    save_json(&path, &cookies()).unwrap();
+
+    assert_eq!(load_json(&path).unwrap(), cookies());
+    // Sorted, so saving the same cookies again doesn't change the file
+    let first = fs::read_to_string(&path).unwrap();
+    assert!(first.find("_pinterest_sess") < first.find("csrftoken"));
+    save_json(&path, &cookies()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), first);
+    assert_eq!(files(path.parent().unwrap()), ["cookies.json"]);
+}
+
+#[test]
+fn a_corrupted_json_file_is_a_typed_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cookies.json");
+    save_json(&path, &cookies()).unwrap();
+    let full = fs::read(&path).unwrap();
+
+    for contents in [
+        &full[..full.len() / 2],
+        b"".as_slice(),
+        b"[\"csrftoken\"]",
+        b"{\"csrftoken\": 1}",
+        b"\xff\xfe",
+    ] {
+        fs::write(&path, contents).unwrap();
+        let err = load_json(&path).unwrap_err();
+        assert!(
+            matches!(err, StoreError::Corrupted(_)),
+            "{contents:?}: {err}"
+        );
+        assert!(err.to_string().contains("corrupted"), "{err}");
+    }
+
+    assert!(matches!(
+        load_json(dir.path().join("missing.json")),
+        Err(StoreError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound
+    ));
+}
+
+#[test]
+fn the_cookie_stores_conform() {
+    block_on(common::cookie_store_conformance(&MemoryStore::new()));
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = JsonFileStore::new(dir.path().join("cookies"));
+    block_on(common::cookie_store_conformance(&store));
+    // Every account stays in the directory
+    assert_eq!(
+        files(store.dir()),
+        [
+            "%2E.%2F..%2Fetc%2Fpasswd.json",
+            "%2Ehidden.json",
+            "%C3%BCn%C3%AF%20c%C3%B6d%C3%A9%2F50%25.json"
+        ]
+    );
+}
+
+#[test]
+fn the_memory_store_only_changes_when_its_futures_run() {
+    let store = MemoryStore::new();
+    let cookies = cookies();
+
+    let save = store.save("me@example.com", &cookies);
+    assert_eq!(block_on(store.load("me@example.com")).unwrap(), None);
+    block_on(save).unwrap();
+    assert_eq!(
+        block_on(store.load("me@example.com")).unwrap(),
+        Some(cookies)
+    );
+
+    drop(store.clear("me@example.com"));
+    assert!(block_on(store.load("me@example.com")).unwrap().is_some());
+}
+
+#[test]
+fn a_corrupted_cookie_file_is_an_error_of_the_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = JsonFileStore::new(dir.path());
+    fs::write(store.path("me@example.com"), "{").unwrap();
+
+    assert!(matches!(
+        block_on(store.load("me@example.com")),
+        Err(StoreError::Corrupted(_))
+    ));
+}
+
+/// A store that keeps the cookies in a list, and fails for the accounts it doesn't know
+struct Allowlisted {
+    accounts: &'static [&'static str],
+    cookies: Mutex<Vec<(String, PinterestCookies)>>,
+}
+
+impl Allowlisted {
+    fn check(&self, account: &str) -> Result<(), StoreError> {
+        match self.accounts.contains(&account) {
+            true => Ok(()),
+            false => Err(StoreError::Backend(
+                format!("unknown account {account}").into(),
+            )),
+        }
+    }
+}
+
+impl CookieStore for Allowlisted {
+    fn load<'a>(
+        &'a self,
+        account: &'a str,
+    ) -> BoxFuture<'a, Result<Option<PinterestCookies>, StoreError>> {
+        async move {
+            self.check(account)?;
+            let cookies = self.cookies.lock().unwrap();
+            Ok(cookies
+                .iter()
+                .find(|(a, _)| a == account)
+                .map(|(_, c)| c.clone()))
+        }
+        .boxed()
+    }
+
+    fn save<'a>(
+        &'a self,
+        account: &'a str,
+        cookies: &'a PinterestCookies,
+    ) -> BoxFuture<'a, Result<(), StoreError>> {
+        async move {
+            self.check(account)?;
+            let mut stored = self.cookies.lock().unwrap();
+            stored.retain(|(a, _)| a != account);
+            stored.push((account.to_string(), cookies.clone()));
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn clear<'a>(&'a self, account: &'a str) -> BoxFuture<'a, Result<(), StoreError>> {
+        async move {
+            self.check(account)?;
+            self.cookies.lock().unwrap().retain(|(a, _)| a != account);
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[test]
+fn a_custom_cookie_store_can_be_used_as_a_trait_object() {
+    let store: Box<dyn CookieStore> = Box::new(Allowlisted {
+        accounts: &[
+            "me@example.com",
+            "../../etc/passwd",
+            ".hidden",
+            "ünï cödé/50%",
+        ],
+        cookies: Mutex::default(),
+    });
+    block_on(common::cookie_store_conformance(store.as_ref()));
+
+    let err = block_on(store.save("someone@example.com", &cookies())).unwrap_err();
+    assert!(matches!(err, StoreError::Backend(_)));
+    assert_eq!(err.to_string(), "unknown account someone@example.com");
+}
@@ -0,0 +1,82 @@
+use std::future::Future;
+
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::options::{LoginOptions, TaskMode};
+use pinterest_login::replay::ReplayLauncher;
+use pinterest_login::report::LoginOutcome;
+use pinterest_login::{login_with_options, PinterestLoginError};
+
+const LOGIN_URL: &str = "http://127.0.0.1:8080/login";
+const SUCCESS: &str = "tests/fixtures/replay/login-success.json";
+const WRONG_PASSWORD: &str = "tests/fixtures/replay/wrong-password.json";
+
+/// Replays the recorded login with the task mode
+async fn replay(fixture: &str, mode: TaskMode) -> pinterest_login::Result<LoginOutcome> {
+    let launcher = ReplayLauncher::load(fixture).unwrap();
+    let options = LoginOptions::new()
+        .login_url(LOGIN_URL)
+        .launcher(launcher.clone())
+        .task_mode(mode);
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2")
+        .with_login_host(Host::new("127.0.0.1:8080"));
+    let config_builder = DefaultBrowserConfigBuilder::default().with_executable("chrome");
+
+    let res = login_with_options(&bot, &config_builder, &options).await;
+    launcher.verify();
+    res
+}
+
+/// Runs the future on a current-thread runtime, inside a `LocalSet` with tokio
+fn current_thread<F: Future>(future: F) -> F::Output {
+    #[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+    return async_std::task::block_on(future);
+    #[cfg(feature = "tokio")]
+    return tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(tokio::task::LocalSet::new().run_until(future));
+}
+
+#[test]
+fn a_local_login_runs_on_a_current_thread_runtime() {
+    let outcome = current_thread(replay(SUCCESS, TaskMode::Local)).unwrap();
+
+    assert!(outcome.cookies.contains_key("_pinterest_sess"));
+}
+
+#[test]
+fn spawn_local_is_the_local_mode() {
+    let options = LoginOptions::new().spawn_local(true);
+    assert!(format!("{options:?}").contains("task_mode: Local"));
+
+    let options = options.spawn_local(false);
+    assert!(format!("{options:?}").contains("task_mode: Spawn"));
+}
+
+#[test]
+fn an_inline_login_runs_on_a_current_thread_runtime() {
+    let outcome = current_thread(replay(SUCCESS, TaskMode::Inline)).unwrap();
+
+    assert!(outcome.cookies.contains_key("_pinterest_sess"));
+}
+
+#[test]
+fn an_inline_login_fails_like_a_spawned_one() {
+    let err = current_thread(replay(WRONG_PASSWORD, TaskMode::Inline)).unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+}
+
+/// Nothing is spawned, so an executor without any runtime can drive the login
+#[cfg(all(feature = "__async-std", not(feature = "tokio")))]
+#[test]
+fn an_inline_login_needs_no_runtime() {
+    let outcome = futures::executor::block_on(replay(SUCCESS, TaskMode::Inline)).unwrap();
+
+    assert!(outcome.cookies.contains_key("_pinterest_sess"));
+}
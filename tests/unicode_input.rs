@@ -0,0 +1,80 @@
+//! The default bot must type any password exactly, not just the keys of the US keyboard layout
+
+mod common;
+
+use std::sync::Mutex;
+
+use common::{FixtureServer, Response};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, PageLoginBot};
+use pinterest_login::options::LoginOptions;
+use pinterest_login::page::LoginPage;
+
+/// Fills the form with the default bot and keeps the echoed password, without submitting anything
+struct EchoBot {
+    bot: DefaultBrowserLoginBot,
+    echoed: Mutex<Option<String>>,
+}
+
+impl PageLoginBot for EchoBot {
+    async fn fill_login_form<P: LoginPage>(&self, page: &P) -> pinterest_login::Result<()> {
+        self.bot.fill_login_form(page).await?;
+        let content = page.content().await?;
+        let echoed = content
+            .split_once(r#"<pre id="echo-password">"#)
+            .and_then(|(_, rest)| rest.split_once("</pre>"))
+            .map(|(echoed, _)| echoed.to_string());
+        *self.echoed.lock().unwrap() = echoed;
+        Ok(())
+    }
+
+    async fn submit_login_form<P: LoginPage>(&self, _page: &P) -> pinterest_login::Result<()> {
+        Ok(())
+    }
+
+    async fn check_login<P: LoginPage>(&self, _page: &P) -> pinterest_login::Result<()> {
+        Ok(())
+    }
+}
+
+/// Types the password into the echo page, and returns what the page got
+async fn typed_password(password: &str) -> String {
+    let server = FixtureServer::start(vec![("/login", Response::html(common::echo_page()))]);
+    let bot = EchoBot {
+        bot: DefaultBrowserLoginBot::new("email@example.com", password),
+        echoed: Mutex::new(None),
+    };
+    let options = LoginOptions::new().login_url(server.url("/login"));
+
+    pinterest_login::login_with_options(&bot, &DefaultBrowserConfigBuilder::default(), &options)
+        .await
+        .expect("the login against the echo page should succeed");
+    let echoed = bot
+        .echoed
+        .lock()
+        .unwrap()
+        .take()
+        .expect("nothing was typed");
+    serde_json::from_str(&echoed).expect("the page echoes a JSON string")
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn passwords_are_typed_exactly() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let passwords = [
+        "P@ss w0rd!~",
+        "hunter2🔑",
+        "pa§sword",
+        // A combining acute accent after the `e`, and a family emoji made of several code points
+        "cafe\u{301} 👨‍👩‍👧",
+        // A tab would move the focus to the next field if it was typed as a key
+        "tab\there",
+        "\u{301}§🔑\t!",
+    ];
+    for password in passwords {
+        assert_eq!(typed_password(password).await, password, "{password:?}");
+    }
+}
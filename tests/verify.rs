@@ -0,0 +1,230 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use chromiumoxide::cdp::browser_protocol::network::{CookieParam, TimeSinceEpoch};
+use common::{FixtureServer, Response};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::options::LoginOptions;
+use pinterest_login::store::{CookieStore, JsonFileStore, MemoryStore, StoreError};
+use pinterest_login::verify::{
+    login_with_cookies_and_options, refresh_session_with_options, refresh_stored_session,
+    verify_cookies, RefreshOutcome, RefreshPath,
+};
+use pinterest_login::{ErrorKind, PinterestLoginError};
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn logged_in_cookies_are_valid() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::verify_server();
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let mut session = CookieParam::new("_pinterest_sess", "fixture");
+    session.expires = Some(TimeSinceEpoch::new(4_102_444_800.0));
+    let cookies = vec![session, CookieParam::new("csrftoken", "token")];
+
+    let report = verify_cookies(cookies, &DefaultBrowserConfigBuilder::default(), &options)
+        .await
+        .unwrap();
+
+    assert!(report.valid);
+    assert_eq!(
+        report.earliest_expiry,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(4_102_444_800))
+    );
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_expiries_that_arent_times_dont_panic() {
+    let mut cookies = Vec::new();
+    for expires in [f64::INFINITY, f64::NAN, 1e300, f64::MAX] {
+        let mut cookie = CookieParam::new("_pinterest_sess", "fixture");
+        cookie.expires = Some(TimeSinceEpoch::new(expires));
+        cookies.push(cookie);
+    }
+    // No browser is launched, the expiries are read before
+    let config_builder = DefaultBrowserConfigBuilder::default().with_executable("/nowhere/chrome");
+
+    let err = verify_cookies(cookies, &config_builder, &LoginOptions::new())
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::Browser, "{err}");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn cookies_that_stay_on_the_login_page_are_invalid() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::verify_server();
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let cookies = vec![CookieParam::new("csrftoken", "token")];
+
+    let report = verify_cookies(cookies, &DefaultBrowserConfigBuilder::default(), &options)
+        .await
+        .unwrap();
+
+    assert!(!report.valid);
+    assert_eq!(report.earliest_expiry, None);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn login_with_cookies_returns_the_browser_cookies() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::verify_server();
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let cookies = vec![
+        CookieParam::new("_pinterest_sess", "fixture"),
+        CookieParam::new("csrftoken", "token"),
+    ];
+
+    let cookies =
+        login_with_cookies_and_options(cookies, &DefaultBrowserConfigBuilder::default(), &options)
+            .await
+            .unwrap();
+
+    assert_eq!(cookies["_pinterest_sess"], "fixture");
+    assert_eq!(cookies["csrftoken"], "token");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn login_with_logged_out_cookies_is_an_authentication_error() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = common::verify_server();
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let cookies = vec![CookieParam::new("csrftoken", "token")];
+
+    let err =
+        login_with_cookies_and_options(cookies, &DefaultBrowserConfigBuilder::default(), &options)
+            .await
+            .unwrap_err();
+
+    assert!(matches!(err, PinterestLoginError::AuthenticationError));
+}
+
+fn refresh_server() -> FixtureServer {
+    FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(common::login_page("/session"))
+                .when_cookie("_pinterest_sess", Response::redirect("/home")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home").header("Set-Cookie", "_pinterest_sess=fresh; Path=/"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ])
+}
+
+async fn refresh_against(
+    server: &FixtureServer,
+    cookies: Vec<CookieParam>,
+) -> pinterest_login::Result<RefreshOutcome> {
+    let bot = DefaultBrowserLoginBot::new("email@example.com", "password")
+        .with_login_host(Host::new(server.host()));
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    refresh_session_with_options(
+        cookies,
+        &bot,
+        &DefaultBrowserConfigBuilder::default(),
+        &options,
+    )
+    .await
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn refreshing_logged_in_cookies_skips_the_credentials() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = refresh_server();
+    let cookies = vec![CookieParam::new("_pinterest_sess", "stored")];
+
+    let refreshed = refresh_against(&server, cookies).await.unwrap();
+
+    assert_eq!(refreshed.path, RefreshPath::Cookies);
+    assert_eq!(refreshed.outcome.cookies["_pinterest_sess"], "stored");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn refreshing_logged_out_cookies_falls_back_to_the_credentials() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = refresh_server();
+    let cookies = vec![CookieParam::new("csrftoken", "stale")];
+
+    let refreshed = refresh_against(&server, cookies).await.unwrap();
+
+    assert_eq!(refreshed.path, RefreshPath::Credentials);
+    assert_eq!(refreshed.outcome.cookies["_pinterest_sess"], "fresh");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_stored_cookies_are_replaced_by_the_refreshed_ones() {
+    if !common::chrome_available() {
+        return common::skip("no chrome executable found");
+    }
+    let server = refresh_server();
+    let bot = DefaultBrowserLoginBot::new("email@example.com", "password")
+        .with_login_host(Host::new(server.host()));
+    let options = LoginOptions::new().login_url(server.url("/login"));
+    let store = MemoryStore::new();
+    let stale = [("csrftoken".to_string(), "stale".to_string())].into();
+    store.save("me@example.com", &stale).await.unwrap();
+
+    let refreshed = refresh_stored_session(
+        &store,
+        "me@example.com",
+        &bot,
+        &DefaultBrowserConfigBuilder::default(),
+        &options,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(refreshed.path, RefreshPath::Credentials);
+    let stored = store.load("me@example.com").await.unwrap().unwrap();
+    assert_eq!(stored["_pinterest_sess"], "fresh");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_store_errors_are_login_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = JsonFileStore::new(dir.path());
+    std::fs::write(store.path("me@example.com"), "{").unwrap();
+    let bot = DefaultBrowserLoginBot::new("email@example.com", "password");
+
+    let err = refresh_stored_session(
+        &store,
+        "me@example.com",
+        &bot,
+        &DefaultBrowserConfigBuilder::default(),
+        &LoginOptions::new(),
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(
+        err,
+        PinterestLoginError::StoreError(StoreError::Corrupted(_))
+    ));
+    assert_eq!(err.kind(), ErrorKind::Store);
+}
@@ -0,0 +1,383 @@
+#![cfg(feature = "webdriver")]
+
+mod common;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{password_page, skip, FixtureServer, Response};
+use futures::future::Either;
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::{DefaultBrowserLoginBot, Host};
+use pinterest_login::options::{Engine, LoginOptions};
+use pinterest_login::webdriver::WebDriverConfig;
+use pinterest_login::{login_with_engine, login_with_options, ErrorKind, PinterestLoginError};
+use serde_json::{json, Value};
+
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f926f4c3e6e";
+const LOGIN_URL: &str = "https://fixture.test/login/";
+
+/// What the fake WebDriver server went through
+#[derive(Debug, Default)]
+struct FakeState {
+    url: String,
+    typed: Vec<(String, String)>,
+    logged_in: bool,
+    rejected: bool,
+    /// The positions of the button that were read after the rejection
+    rejected_rects: u32,
+    /// The clicks are never answered
+    stall_clicks: bool,
+    /// A click is waiting for its answer
+    stalled: bool,
+    deleted: bool,
+}
+
+/// A fake WebDriver server with a login form that only accepts the password
+struct FakeWebDriver {
+    url: String,
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeWebDriver {
+    fn start(password: &'static str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(FakeState::default()));
+        let shared = Arc::clone(&state);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = Arc::clone(&shared);
+                std::thread::spawn(move || serve(stream, &state, password));
+            }
+        });
+        Self { url, state }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap()
+    }
+}
+
+fn serve(stream: TcpStream, state: &Mutex<FakeState>, password: &str) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut content_length = 0;
+    let mut line = String::new();
+    while reader.read_line(&mut line).map(|n| n > 2).unwrap_or(false) {
+        if let Some((name, value)) = line.trim_end().split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        line.clear();
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap_or_default();
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap(), parts.next().unwrap());
+    if path.ends_with("/click") && state.lock().unwrap().stall_clicks {
+        state.lock().unwrap().stalled = true;
+        // The connection is left open until the login is dropped
+        std::thread::sleep(Duration::from_secs(60));
+        return;
+    }
+    let (status, value) = answer(method, path, &body, &mut state.lock().unwrap(), password);
+
+    let body = json!({ "value": value }).to_string();
+    let response = format!(
+        "HTTP/1.1 {status} Fake\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = (&stream).write_all(response.as_bytes());
+}
+
+fn element(id: &str) -> Value {
+    json!({ ELEMENT_KEY: id })
+}
+
+fn no_such_element() -> (u16, Value) {
+    (
+        404,
+        json!({ "error": "no such element", "message": "Unable to locate element" }),
+    )
+}
+
+fn answer(
+    method: &str,
+    path: &str,
+    body: &Value,
+    state: &mut FakeState,
+    password: &str,
+) -> (u16, Value) {
+    let command = path.strip_prefix("/session/s1").unwrap_or(path);
+    match (method, command) {
+        ("POST", "/session") => (200, json!({ "sessionId": "s1", "capabilities": {} })),
+        ("DELETE", "") => {
+            state.deleted = true;
+            (200, Value::Null)
+        }
+        ("POST", "/url") => {
+            state.url = body["url"].as_str().unwrap().to_string();
+            (200, Value::Null)
+        }
+        ("GET", "/url") => (200, json!(state.url)),
+        ("GET", "/source") => (
+            200,
+            json!(r#"<form><input id="email"><input id="password" type="password"></form>"#),
+        ),
+        ("POST", "/execute/sync") => (200, json!("complete")),
+        ("POST", "/element") if !state.logged_in => match body["value"].as_str() {
+            Some("input#email") => (200, element("email")),
+            Some("input#password") => (200, element("password")),
+            _ => no_such_element(),
+        },
+        ("POST", "/element") => no_such_element(),
+        ("POST", "/elements") => (200, json!([element("submit")])),
+        ("POST", "/element/submit/click") => {
+            let typed = |field: &str| {
+                state
+                    .typed
+                    .iter()
+                    .filter(|(id, _)| id == field)
+                    .map(|(_, text)| text.as_str())
+                    .collect::<String>()
+            };
+            if typed("password") == password {
+                state.logged_in = true;
+                state.url = "https://fixture.test/".to_string();
+            } else {
+                state.rejected = true;
+            }
+            (200, Value::Null)
+        }
+        ("GET", "/element/submit/rect") => {
+            // The tooltip of a wrong password shows up a bit after the click, and moves the button
+            let y = if state.rejected_rects > 0 {
+                140.0
+            } else {
+                100.0
+            };
+            if state.rejected {
+                state.rejected_rects += 1;
+            }
+            (
+                200,
+                json!({ "x": 10.0, "y": y, "width": 80.0, "height": 20.0 }),
+            )
+        }
+        ("GET", "/cookie") if state.logged_in => (
+            200,
+            json!([
+                { "name": "_pinterest_sess", "value": "session", "path": "/", "domain": ".fixture.test", "secure": true, "httpOnly": true, "expiry": 1_900_000_000 },
+                { "name": "csrftoken", "value": "token", "path": "/", "domain": ".fixture.test", "secure": true, "httpOnly": false },
+            ]),
+        ),
+        ("GET", "/cookie") => (200, json!([])),
+        (method, command) if command.starts_with("/element/") => {
+            let id = command.split('/').nth(2).unwrap().to_string();
+            if method == "POST" && command.ends_with("/value") {
+                let text = body["text"].as_str().unwrap().to_string();
+                state.typed.push((id, text));
+            }
+            (200, Value::Null)
+        }
+        _ => (
+            404,
+            json!({ "error": "unknown command", "message": format!("{method} {path}") }),
+        ),
+    }
+}
+
+/// Logs in with the engine of the WebDriver server
+async fn login(
+    webdriver_url: &str,
+    login_url: &str,
+    host: Host,
+    password: &str,
+) -> pinterest_login::Result<pinterest_login::report::LoginOutcome> {
+    let bot = DefaultBrowserLoginBot::new("me@example.com", password).with_login_host(host);
+    let options = LoginOptions::new()
+        .login_url(login_url)
+        .engine(Engine::WebDriver(
+            WebDriverConfig::new(webdriver_url).timeout(Duration::from_secs(30)),
+        ));
+    login_with_engine(&bot, &DefaultBrowserConfigBuilder::default(), &options).await
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_default_bot_logs_in_over_webdriver() {
+    let server = FakeWebDriver::start("hunter2");
+
+    let outcome = login(&server.url, LOGIN_URL, Host::new("fixture.test"), "hunter2")
+        .await
+        .unwrap();
+
+    assert_eq!(outcome.cookies["_pinterest_sess"], "session");
+    assert_eq!(outcome.cookies["csrftoken"], "token");
+    let session = outcome
+        .cookie_details
+        .iter()
+        .find(|cookie| cookie.name == "_pinterest_sess")
+        .unwrap();
+    assert!(session.http_only && !session.session);
+    assert_eq!(outcome.report.selectors.len(), 3);
+    let state = server.state();
+    assert_eq!(
+        state.typed,
+        [
+            ("email".to_string(), "me@example.com".to_string()),
+            ("password".to_string(), "hunter2".to_string()),
+        ]
+    );
+    assert!(state.deleted);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_wrong_password_fails_and_deletes_the_session() {
+    let server = FakeWebDriver::start("hunter2");
+
+    let err = login(&server.url, LOGIN_URL, Host::new("fixture.test"), "wrong")
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::AuthenticationError),
+        "{err}"
+    );
+    assert!(server.state().deleted);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_dropped_login_deletes_the_session() {
+    let server = FakeWebDriver::start("hunter2");
+    server.state().stall_clicks = true;
+    let login = Box::pin(login(
+        &server.url,
+        LOGIN_URL,
+        Host::new("fixture.test"),
+        "hunter2",
+    ));
+    let stalled = Box::pin(async {
+        while !server.state().stalled {
+            common::sleep(Duration::from_millis(10)).await;
+        }
+    });
+
+    let Either::Right(((), login)) = futures::future::select(login, stalled).await else {
+        panic!("the login completed");
+    };
+    assert!(!server.state().deleted);
+    drop(login);
+
+    for _ in 0..500 {
+        if server.state().deleted {
+            return;
+        }
+        common::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the session wasn't deleted");
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn a_missing_server_is_a_browser_error() {
+    // Nothing listens on the port of a closed listener
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let err = login(
+        &format!("http://{addr}"),
+        LOGIN_URL,
+        Host::new("fixture.test"),
+        "hunter2",
+    )
+    .await
+    .unwrap_err();
+
+    assert!(
+        matches!(err, PinterestLoginError::WebDriverError(_)),
+        "{err}"
+    );
+    assert_eq!(err.kind(), ErrorKind::Browser);
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn the_cdp_login_refuses_the_webdriver_engine() {
+    let bot = DefaultBrowserLoginBot::new("me@example.com", "hunter2");
+    let options = LoginOptions::new().engine(Engine::WebDriver(WebDriverConfig::default()));
+
+    let err = login_with_options(&bot, &DefaultBrowserConfigBuilder::default(), &options)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("login_with_engine"), "{err}");
+}
+
+/// Starts geckodriver on a free port if it's installed, and waits for it to listen
+fn geckodriver() -> Option<(Child, String)> {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .ok()?
+        .local_addr()
+        .ok()?
+        .port();
+    let child = Command::new("geckodriver")
+        .args(["--port", &port.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    for _ in 0..50 {
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            return Some((child, format!("http://127.0.0.1:{port}")));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
+
+#[cfg_attr(all(feature = "__async-std", not(feature = "tokio")), async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test)]
+async fn firefox_logs_in_to_the_fixture() {
+    let Some((mut geckodriver, url)) = geckodriver() else {
+        return skip("geckodriver isn't installed");
+    };
+    let server = FixtureServer::start(vec![
+        (
+            "/login",
+            Response::html(password_page("hunter2", "/session")),
+        ),
+        (
+            "/session",
+            Response::redirect("/home")
+                .header("Set-Cookie", "_pinterest_sess=fixture; Path=/; HttpOnly")
+                .header("Set-Cookie", "csrftoken=token; Path=/"),
+        ),
+        ("/home", Response::html("<p>Welcome</p>")),
+    ]);
+
+    let res = login(
+        &url,
+        &server.url("/login"),
+        Host::new(server.host()),
+        "hunter2",
+    )
+    .await;
+    let _ = geckodriver.kill();
+
+    let outcome = res.unwrap();
+    assert_eq!(outcome.cookies["_pinterest_sess"], "fixture");
+    assert_eq!(outcome.cookies["csrftoken"], "token");
+}